@@ -0,0 +1,539 @@
+//! TOML and YAML configuration, which have no tree-sitter grammar here, chunked
+//! by section: every TOML table (`[server.tls]`, `[[bin]]`) and every YAML key
+//! holding a nested block becomes a module chunk named by its key path below
+//! the enclosing section. A TOML table's chunk runs on over the sub-tables
+//! right after it, so `[server.tls]` nests inside `[server]` as a method nests
+//! in its class. Comments directly above a section are its leading trivia;
+//! keys outside any section are left to the gap text chunks.
+
+use crate::sfc::span_of;
+use crate::{BreadcrumbSegment, Chunk, ChunkMetadata, ChunkType, fill_gaps};
+
+/// A table or block of keys, by 0-based line
+struct Section {
+    /// Key path from the document root
+    path: Vec<String>,
+    /// The table header or key line
+    header: usize,
+    /// One past its last non-blank line, nested sections included
+    end: usize,
+    parent: Option<usize>,
+}
+
+/// Chunks for the tables in a TOML document, with the text between them
+/// filled in; empty when there are none
+pub(crate) fn chunk_toml(text: &str) -> Vec<Chunk> {
+    let lines = split_lines(text);
+    let mut headers: Vec<(usize, Vec<String>)> = Vec::new();
+    let mut value = ValueState::default();
+    for (idx, &(_, line)) in lines.iter().enumerate() {
+        if value.is_open() {
+            value.scan(line);
+            continue;
+        }
+        match table_header(line.trim()) {
+            Some(path) => headers.push((idx, path)),
+            None => value.scan(line),
+        }
+    }
+
+    let starts: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, &(header, _))| {
+            let floor = i.checked_sub(1).map_or(0, |prev| headers[prev].0 + 1);
+            attached_comments_start(&lines, header, floor)
+        })
+        .collect();
+    let own_ends: Vec<usize> = (0..headers.len())
+        .map(|i| {
+            let next = starts.get(i + 1).copied().unwrap_or(lines.len());
+            trim_blank_lines(&lines, headers[i].0, next)
+        })
+        .collect();
+
+    let mut sections: Vec<Section> = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    for (i, (header, path)) in headers.iter().enumerate() {
+        while open
+            .last()
+            .is_some_and(|&top| !is_strict_prefix(&sections[top].path, path))
+        {
+            open.pop();
+        }
+        let last_descendant = (i + 1..headers.len())
+            .take_while(|&j| is_strict_prefix(path, &headers[j].1))
+            .last()
+            .unwrap_or(i);
+        sections.push(Section {
+            path: path.clone(),
+            header: *header,
+            end: own_ends[last_descendant],
+            parent: open.last().copied(),
+        });
+        open.push(i);
+    }
+
+    section_chunks(text, &lines, &sections)
+}
+
+/// Chunks for the keys holding nested blocks in a YAML document, with the
+/// text between them filled in; empty when there are none
+pub(crate) fn chunk_yaml(text: &str) -> Vec<Chunk> {
+    let lines = split_lines(text);
+    let mut sections: Vec<Section> = Vec::new();
+    // Indentation and index of the sections enclosing the current line
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut block_scalar_indent: Option<usize> = None;
+
+    for (idx, &(_, line)) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = indentation(line);
+        if let Some(owner) = block_scalar_indent {
+            if indent > owner {
+                continue;
+            }
+            block_scalar_indent = None;
+        }
+        if trimmed == "---" || trimmed == "..." || trimmed.starts_with("--- ") {
+            open.clear();
+            continue;
+        }
+        // A key's list items may sit at its own indentation
+        let is_item = is_list_item(trimmed);
+        while open.last().is_some_and(|&(open_indent, _)| {
+            open_indent > indent || (open_indent == indent && !is_item)
+        }) {
+            open.pop();
+        }
+
+        let Some((key, value)) = mapping_key(trimmed) else {
+            continue;
+        };
+        let Some(block_scalar) = opens_block(value) else {
+            continue;
+        };
+        if block_scalar {
+            block_scalar_indent = Some(indent);
+        }
+        let end = yaml_block_end(&lines, idx, indent);
+        if end == idx + 1 {
+            continue;
+        }
+
+        let parent = open.last().map(|&(_, section)| section);
+        let mut path = parent.map_or_else(Vec::new, |parent| sections[parent].path.clone());
+        path.push(key);
+        sections.push(Section {
+            path,
+            header: idx,
+            end,
+            parent,
+        });
+        open.push((indent, sections.len() - 1));
+    }
+
+    section_chunks(text, &lines, &sections)
+}
+
+/// A chunk per section, each named by the keys below its parent's path and
+/// nested in its ancestors' breadcrumb segments
+fn section_chunks(text: &str, lines: &[(usize, &str)], sections: &[Section]) -> Vec<Chunk> {
+    let starts: Vec<usize> = sections
+        .iter()
+        .enumerate()
+        .map(|(i, section)| {
+            let floor = i.checked_sub(1).map_or(0, |prev| sections[prev].header + 1);
+            attached_comments_start(lines, section.header, floor)
+        })
+        .collect();
+    let byte_range = |i: usize| {
+        let (last_start, last_line) = lines[sections[i].end - 1];
+        (lines[starts[i]].0, last_start + last_line.len())
+    };
+    let name = |i: usize| {
+        let skip = sections[i]
+            .parent
+            .map_or(0, |parent| sections[parent].path.len());
+        sections[i].path[skip..].join(".")
+    };
+
+    let mut top_level = Vec::new();
+    let mut nested = Vec::new();
+    for (i, section) in sections.iter().enumerate() {
+        let mut ancestors = Vec::new();
+        let mut parent = section.parent;
+        while let Some(ancestor) = parent {
+            ancestors.push(ancestor);
+            parent = sections[ancestor].parent;
+        }
+        let segments = ancestors
+            .iter()
+            .rev()
+            .map(|&ancestor| {
+                let (start, end) = byte_range(ancestor);
+                BreadcrumbSegment {
+                    name: name(ancestor),
+                    span: span_of(text, start, end),
+                }
+            })
+            .collect();
+        let leading_trivia = lines[starts[i]..section.header]
+            .iter()
+            .map(|(_, line)| line.trim().to_string())
+            .collect();
+
+        let (start, end) = byte_range(i);
+        let chunk_text = &text[start..end];
+        let chunk = Chunk {
+            span: span_of(text, start, end),
+            text: chunk_text.to_string(),
+            chunk_type: ChunkType::Module,
+            stride_info: None,
+            metadata: ChunkMetadata::from_context(
+                chunk_text,
+                segments,
+                Some(name(i)),
+                leading_trivia,
+                Vec::new(),
+            ),
+        };
+        if section.parent.is_some() {
+            nested.push(chunk);
+        } else {
+            top_level.push(chunk);
+        }
+    }
+    if top_level.is_empty() {
+        return Vec::new();
+    }
+
+    // Gaps are what no outermost section covers, so nested ones join afterwards
+    let mut chunks = fill_gaps(top_level, text);
+    chunks.extend(nested);
+    chunks.sort_by_key(|chunk| chunk.span.byte_start);
+    chunks
+}
+
+/// Lines of `text` with the byte each starts at, without their newlines
+fn split_lines(text: &str) -> Vec<(usize, &str)> {
+    let mut offset = 0;
+    text.split('\n')
+        .map(|line| {
+            let start = offset;
+            offset += line.len() + 1;
+            (start, line)
+        })
+        .collect()
+}
+
+/// The first of the comment lines directly above `header`, no higher than `floor`
+fn attached_comments_start(lines: &[(usize, &str)], header: usize, floor: usize) -> usize {
+    let mut start = header;
+    while start > floor && lines[start - 1].1.trim_start().starts_with('#') {
+        start -= 1;
+    }
+    start
+}
+
+/// `end` moved back over blank lines, but not onto `header`
+fn trim_blank_lines(lines: &[(usize, &str)], header: usize, mut end: usize) -> usize {
+    while end > header + 1 && lines[end - 1].1.trim().is_empty() {
+        end -= 1;
+    }
+    end
+}
+
+fn is_strict_prefix(prefix: &[String], path: &[String]) -> bool {
+    prefix.len() < path.len() && path.starts_with(prefix)
+}
+
+/// The key path of a `[table]` or `[[array.of.tables]]` header line
+fn table_header(line: &str) -> Option<Vec<String>> {
+    let (inner, close) = match line.strip_prefix("[[") {
+        Some(inner) => (inner, "]]"),
+        None => (line.strip_prefix('[')?, "]"),
+    };
+    let (path, rest) = key_path(inner)?;
+    let rest = rest.trim_start().strip_prefix(close)?.trim_start();
+    (rest.is_empty() || rest.starts_with('#')).then_some(path)
+}
+
+/// A dotted TOML key at the start of `text` and what follows it
+fn key_path(text: &str) -> Option<(Vec<String>, &str)> {
+    let mut path = Vec::new();
+    let mut rest = text;
+    loop {
+        rest = rest.trim_start();
+        let (segment, after) = match rest.chars().next()? {
+            quote @ ('"' | '\'') => {
+                let end = rest[1..].find(quote)? + 1;
+                (rest[1..end].to_string(), &rest[end + 1..])
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                    .unwrap_or(rest.len());
+                if end == 0 {
+                    return None;
+                }
+                (rest[..end].to_string(), &rest[end..])
+            }
+        };
+        path.push(segment);
+        match after.trim_start().strip_prefix('.') {
+            Some(next) => rest = next,
+            None => return Some((path, after)),
+        }
+    }
+}
+
+/// Whether a TOML value continues past the end of its line, so lines starting
+/// with `[` inside it aren't table headers
+#[derive(Default)]
+struct ValueState {
+    /// The delimiter closing an open multi-line string
+    string: Option<&'static str>,
+    /// Unclosed `[` and `{` of a multi-line array
+    depth: usize,
+}
+
+impl ValueState {
+    fn is_open(&self) -> bool {
+        self.string.is_some() || self.depth > 0
+    }
+
+    fn scan(&mut self, line: &str) {
+        let mut rest = line;
+        while !rest.is_empty() {
+            if let Some(delimiter) = self.string {
+                match rest.find(delimiter) {
+                    Some(end) => {
+                        self.string = None;
+                        rest = &rest[end + delimiter.len()..];
+                        continue;
+                    }
+                    None => return,
+                }
+            }
+            let Some(c) = rest.chars().next() else {
+                return;
+            };
+            if let Some(delimiter) = ["\"\"\"", "'''"].into_iter().find(|d| rest.starts_with(d)) {
+                self.string = Some(delimiter);
+                rest = &rest[3..];
+                continue;
+            }
+            match c {
+                '#' => return,
+                '"' | '\'' => {
+                    // A one-line string; backslashes escape only in basic strings
+                    let mut escaped = false;
+                    let end = rest[1..].char_indices().find(|&(_, next)| {
+                        let closes = next == c && !escaped;
+                        escaped = c == '"' && next == '\\' && !escaped;
+                        closes
+                    });
+                    match end {
+                        Some((end, _)) => rest = &rest[end + 2..],
+                        None => return,
+                    }
+                    continue;
+                }
+                '[' | '{' => self.depth += 1,
+                ']' | '}' => self.depth = self.depth.saturating_sub(1),
+                _ => {}
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// The key and the rest of a YAML `key: value` line; `None` for list items,
+/// flow collections and anything else that isn't a mapping key
+fn mapping_key(trimmed: &str) -> Option<(String, &str)> {
+    if trimmed.starts_with(['-', '{', '[', '?', '#']) {
+        return None;
+    }
+    let (key, value) = match trimmed.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let end = trimmed[1..].find(quote)? + 1;
+            let value = trimmed[end + 1..].trim_start().strip_prefix(':')?;
+            (trimmed[1..end].to_string(), value)
+        }
+        _ => {
+            let colon = trimmed
+                .match_indices(':')
+                .map(|(idx, _)| idx)
+                .find(|&idx| {
+                    trimmed[idx + 1..].is_empty() || trimmed[idx + 1..].starts_with([' ', '\t'])
+                })?;
+            (
+                trimmed[..colon].trim_end().to_string(),
+                &trimmed[colon + 1..],
+            )
+        }
+    };
+    (!key.is_empty() && (value.is_empty() || value.starts_with([' ', '\t'])))
+        .then(|| (key, value.trim()))
+}
+
+/// Whether a key's value is a block on the lines below: `Some(true)` for a
+/// `|` or `>` block scalar, `Some(false)` for a nested mapping or sequence,
+/// `None` for an inline value. Anchors, tags and comments are skipped.
+fn opens_block(value: &str) -> Option<bool> {
+    for token in value.split_whitespace() {
+        if token.starts_with('#') {
+            break;
+        }
+        if token.starts_with(['&', '!']) {
+            continue;
+        }
+        return token.starts_with(['|', '>']).then_some(true);
+    }
+    Some(false)
+}
+
+fn is_list_item(trimmed: &str) -> bool {
+    trimmed == "-" || trimmed.starts_with("- ")
+}
+
+/// One past the last line of the block under the key at `header`: lines
+/// indented deeper, or list items at the key's own indentation
+fn yaml_block_end(lines: &[(usize, &str)], header: usize, indent: usize) -> usize {
+    let mut last = header;
+    for (idx, &(_, line)) in lines.iter().enumerate().skip(header + 1) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let line_indent = indentation(line);
+        if line_indent > indent || (line_indent == indent && is_list_item(trimmed)) {
+            if !trimmed.starts_with('#') {
+                last = idx;
+            }
+        } else if !trimmed.starts_with('#') {
+            break;
+        }
+    }
+    last + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(chunks: &[Chunk]) -> Vec<(String, usize, usize)> {
+        chunks
+            .iter()
+            .filter(|chunk| chunk.chunk_type == ChunkType::Module)
+            .map(|chunk| {
+                let name = chunk.metadata.name.clone().unwrap_or_default();
+                let path = match &chunk.metadata.breadcrumb {
+                    Some(crumb) => format!("{}::{}", crumb, name),
+                    None => name,
+                };
+                (path, chunk.span.line_start, chunk.span.line_end)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn toml_tables_nest_under_their_parents() {
+        let toml = r#"title = "demo"
+
+# The HTTP listener
+[server]
+port = 8080
+hosts = [
+  "a",
+  "b",
+]
+
+[server.tls]
+cert = "/etc/cert.pem" # [not.a.table]
+
+[database."primary.db"]
+url = """
+[not a header]
+"""
+
+[[bin]]
+name = "ck"
+
+[[bin]]
+name = "ck-tui"
+"#;
+        let chunks = chunk_toml(toml);
+        assert_eq!(
+            outline(&chunks),
+            vec![
+                ("server".to_string(), 3, 12),
+                ("server::tls".to_string(), 11, 12),
+                ("database.primary.db".to_string(), 14, 17),
+                ("bin".to_string(), 19, 20),
+                ("bin".to_string(), 22, 23),
+            ]
+        );
+
+        let server = chunks
+            .iter()
+            .find(|chunk| chunk.metadata.name.as_deref() == Some("server"))
+            .unwrap();
+        assert_eq!(server.metadata.leading_trivia, vec!["# The HTTP listener"]);
+        let tls = chunks
+            .iter()
+            .find(|chunk| chunk.metadata.name.as_deref() == Some("tls"))
+            .unwrap();
+        assert_eq!(tls.metadata.breadcrumb_segments[0].span.line_start, 3);
+
+        // Keys before the first table are kept as text
+        assert_eq!(chunks[0].chunk_type, ChunkType::Text);
+        assert!(chunks[0].text.starts_with("title"));
+        assert!(chunk_toml("a = 1\nb = [\n  [1, 2],\n]\n").is_empty());
+    }
+
+    #[test]
+    fn yaml_keys_with_blocks_become_sections() {
+        let yaml = "\
+version: 2
+# Web tier
+server:
+  port: 8080
+  tls:
+    cert: /etc/cert.pem
+    key: &key /etc/key.pem
+  script: |
+    run: not-a-key
+jobs:
+- name: build
+  steps:
+    - run: make
+empty:
+";
+        let chunks = chunk_yaml(yaml);
+        assert_eq!(
+            outline(&chunks),
+            vec![
+                ("server".to_string(), 2, 9),
+                ("server::tls".to_string(), 5, 7),
+                ("server::script".to_string(), 8, 9),
+                ("jobs".to_string(), 10, 13),
+                ("jobs::steps".to_string(), 12, 13),
+            ]
+        );
+        assert_eq!(chunks[0].chunk_type, ChunkType::Text);
+        assert_eq!(
+            chunks[1].metadata.leading_trivia,
+            vec!["# Web tier".to_string()]
+        );
+        assert!(chunk_yaml("a: 1\nb: two\n").is_empty());
+    }
+}
@@ -0,0 +1,335 @@
+//! GraphQL SDL, which has no tree-sitter grammar here: each top-level type
+//! definition (`type`, `input`, `enum`, `interface`, `union`, `scalar`, and
+//! their `extend` forms) becomes a class chunk named after the type, with the
+//! comments and description strings right above it as leading trivia. Schema,
+//! directive and operation definitions are left to the gap text chunks.
+
+use crate::sfc::span_of;
+use crate::{Chunk, ChunkMetadata, ChunkType, fill_gaps};
+
+/// Keywords opening a definition that gets a chunk of its own
+const TYPE_KEYWORDS: &[&str] = &["type", "input", "enum", "interface", "union", "scalar"];
+
+/// Keywords that can open any top-level definition, ending the one before
+const TOP_LEVEL_KEYWORDS: &[&str] = &[
+    "type",
+    "input",
+    "enum",
+    "interface",
+    "union",
+    "scalar",
+    "extend",
+    "schema",
+    "directive",
+    "query",
+    "mutation",
+    "subscription",
+    "fragment",
+];
+
+/// Chunks for the type definitions in `text`, with the text between them
+/// filled in; empty when there are none
+pub(crate) fn chunk_schema(text: &str) -> Vec<Chunk> {
+    let bytes = text.as_bytes();
+    let mut chunks = Vec::new();
+    // Comments and descriptions since the last definition, as byte ranges
+    let mut trivia: Vec<(usize, usize)> = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let byte = bytes[pos];
+        if byte.is_ascii_whitespace() || byte == b',' {
+            pos += 1;
+        } else if byte == b'#' {
+            let end = line_end(text, pos);
+            trivia.push((pos, end));
+            pos = end;
+        } else if byte == b'"' {
+            let end = string_end(bytes, pos);
+            trivia.push((pos, end));
+            pos = end;
+        } else if is_name_start(byte) {
+            let keyword_start = pos;
+            let (mut keyword, mut after) = read_name(text, pos);
+            if keyword == "extend" {
+                let next = skip_whitespace(bytes, after);
+                (keyword, after) = read_name(text, next);
+            }
+            let name_start = skip_whitespace(bytes, after);
+            let (name, after_name) = read_name(text, name_start);
+            let end = definition_end(text, after_name);
+
+            if TYPE_KEYWORDS.contains(&keyword) && !name.is_empty() {
+                let leading = attached_trivia(text, &trivia, keyword_start);
+                let start = leading.first().map_or(keyword_start, |&(start, _)| start);
+                let chunk_text = &text[start..end];
+                let leading_trivia = leading
+                    .iter()
+                    .map(|&(start, end)| text[start..end].to_string())
+                    .collect();
+                chunks.push(Chunk {
+                    span: span_of(text, start, end),
+                    text: chunk_text.to_string(),
+                    chunk_type: ChunkType::Class,
+                    stride_info: None,
+                    metadata: ChunkMetadata::from_context(
+                        chunk_text,
+                        Vec::new(),
+                        Some(name.to_string()),
+                        leading_trivia,
+                        Vec::new(),
+                    ),
+                });
+            }
+            trivia.clear();
+            pos = end.max(after_name).max(keyword_start + 1);
+        } else {
+            // Anything else at the top level (an anonymous `{ ... }` query)
+            pos = if byte == b'{' {
+                block_end(text, pos)
+            } else {
+                pos + 1
+            };
+            trivia.clear();
+        }
+    }
+
+    fill_gaps(chunks, text)
+}
+
+/// The trailing run of `trivia` that sits directly above `start`, with no
+/// blank line between it and the definition
+fn attached_trivia(text: &str, trivia: &[(usize, usize)], start: usize) -> Vec<(usize, usize)> {
+    let mut attached = Vec::new();
+    let mut next_start = start;
+    for &(trivia_start, trivia_end) in trivia.iter().rev() {
+        let between = &text[trivia_end..next_start];
+        if between.matches('\n').count() > 1 {
+            break;
+        }
+        attached.push((trivia_start, trivia_end));
+        next_start = trivia_start;
+    }
+    attached.reverse();
+    attached
+}
+
+/// Where the definition whose header continues at `from` ends: after its
+/// `{ ... }` body, or for one without a body (`scalar`, `union`), before the
+/// next top-level definition, comment or description
+fn definition_end(text: &str, from: usize) -> usize {
+    let bytes = text.as_bytes();
+    let mut pos = from;
+    let mut depth = 0usize;
+    let mut last_content = from;
+
+    while pos < bytes.len() {
+        let byte = bytes[pos];
+        // Only asked at ASCII bytes, which are always character boundaries
+        let at_line_start = || {
+            text[..pos]
+                .rsplit('\n')
+                .next()
+                .is_some_and(|line| line.trim().is_empty())
+        };
+        match byte {
+            b'#' | b'"' if depth == 0 && at_line_start() => return last_content,
+            b'#' => pos = line_end(text, pos),
+            b'"' => {
+                pos = string_end(bytes, pos);
+                last_content = pos;
+            }
+            b'{' if depth == 0 => return block_end(text, pos),
+            b'(' | b'[' | b'{' => {
+                depth += 1;
+                pos += 1;
+                last_content = pos;
+            }
+            b')' | b']' | b'}' => {
+                depth = depth.saturating_sub(1);
+                pos += 1;
+                last_content = pos;
+            }
+            _ if is_name_start(byte) => {
+                let (name, after) = read_name(text, pos);
+                if depth == 0 && at_line_start() && TOP_LEVEL_KEYWORDS.contains(&name) {
+                    return last_content;
+                }
+                pos = after;
+                last_content = pos;
+            }
+            _ if byte.is_ascii_whitespace() || byte == b',' => pos += 1,
+            _ => {
+                pos += 1;
+                last_content = pos;
+            }
+        }
+    }
+    last_content
+}
+
+/// The byte after the `}` closing the block opened at `open`
+fn block_end(text: &str, open: usize) -> usize {
+    let bytes = text.as_bytes();
+    let mut depth = 0usize;
+    let mut pos = open;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'#' => pos = line_end(text, pos),
+            b'"' => pos = string_end(bytes, pos),
+            b'{' => {
+                depth += 1;
+                pos += 1;
+            }
+            b'}' => {
+                depth -= 1;
+                pos += 1;
+                if depth == 0 {
+                    return pos;
+                }
+            }
+            _ => pos += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// The byte after the string starting at `start`: a `"""` block string or a
+/// one-line `"..."` string, which an unterminated quote ends at the newline
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    if bytes[start..].starts_with(b"\"\"\"") {
+        let mut pos = start + 3;
+        while pos < bytes.len() {
+            if bytes[pos..].starts_with(b"\\\"\"\"") {
+                pos += 4;
+            } else if bytes[pos..].starts_with(b"\"\"\"") {
+                return pos + 3;
+            } else {
+                pos += 1;
+            }
+        }
+        return bytes.len();
+    }
+
+    let mut pos = start + 1;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b'"' => return pos + 1,
+            b'\n' => return pos,
+            _ => pos += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn line_end(text: &str, from: usize) -> usize {
+    text[from..].find('\n').map_or(text.len(), |end| from + end)
+}
+
+fn skip_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+    while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+        pos += 1;
+    }
+    pos
+}
+
+fn is_name_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_'
+}
+
+/// The GraphQL name at `start` (empty when there is none) and the byte after it
+fn read_name(text: &str, start: usize) -> (&str, usize) {
+    let bytes = text.as_bytes();
+    let mut end = start;
+    while end < bytes.len() && (bytes[end].is_ascii_alphanumeric() || bytes[end] == b'_') {
+        end += 1;
+    }
+    (&text[start..end], end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"schema {
+  query: Query
+}
+
+# Someone with an account
+"""
+A registered user
+"""
+type User implements Node @key(fields: "id") {
+  id: ID!
+  # Shown on their profile
+  name: String
+}
+
+input NewUser {
+  name: String!
+}
+
+enum Role {
+  ADMIN
+  MEMBER
+}
+
+scalar DateTime
+union SearchHit = User | Post
+
+extend type Query {
+  users(first: Int = 10): [User!]!
+}
+"#;
+
+    #[test]
+    fn type_definitions_become_named_class_chunks() {
+        let chunks = chunk_schema(SCHEMA);
+        let named: Vec<(Option<&str>, usize, usize)> = chunks
+            .iter()
+            .filter(|chunk| chunk.chunk_type == ChunkType::Class)
+            .map(|chunk| {
+                (
+                    chunk.metadata.name.as_deref(),
+                    chunk.span.line_start,
+                    chunk.span.line_end,
+                )
+            })
+            .collect();
+        assert_eq!(
+            named,
+            vec![
+                (Some("User"), 5, 13),
+                (Some("NewUser"), 15, 17),
+                (Some("Role"), 19, 22),
+                (Some("DateTime"), 24, 24),
+                (Some("SearchHit"), 25, 25),
+                (Some("Query"), 27, 29),
+            ]
+        );
+
+        let user = chunks
+            .iter()
+            .find(|chunk| chunk.metadata.name.as_deref() == Some("User"))
+            .unwrap();
+        assert!(user.text.starts_with("# Someone with an account"));
+        assert!(user.text.ends_with('}'));
+        assert_eq!(
+            user.metadata.leading_trivia,
+            vec![
+                "# Someone with an account".to_string(),
+                "\"\"\"\nA registered user\n\"\"\"".to_string(),
+            ]
+        );
+
+        // The schema definition is kept as text
+        assert_eq!(chunks[0].chunk_type, ChunkType::Text);
+        assert!(chunks[0].text.starts_with("schema {"));
+    }
+
+    #[test]
+    fn text_without_type_definitions_has_no_chunks() {
+        assert!(chunk_schema("# just a comment\n").is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{ParseableLanguage, tree_sitter_language};
+use serde::{Deserialize, Serialize};
+
+/// What a highlighted token is, for the renderer to pick a color by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightClass {
+    Keyword,
+    Type,
+    Function,
+    String,
+    Number,
+    Comment,
+}
+
+/// A highlighted byte range `[start, end)` of one line, excluding its newline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighlightSpan {
+    pub start: usize,
+    pub end: usize,
+    pub class: HighlightClass,
+}
+
+/// Syntax highlighting for `text`, one list of spans per line of
+/// `text.lines()`, each sorted and non-overlapping. The file is parsed with the
+/// same tree-sitter grammar as chunking and tokens are classed by node kind, so
+/// nothing beyond the grammars is needed. `None` for languages without one.
+pub fn highlight_lines(text: &str, language: ck_core::Language) -> Option<Vec<Vec<HighlightSpan>>> {
+    let language = ParseableLanguage::try_from(language).ok()?;
+    let mut parser = tree_sitter::Parser::new();
+    parser
+        .set_language(&tree_sitter_language(language).ok()?)
+        .ok()?;
+    let tree = parser.parse(text, None)?;
+
+    let line_lengths: Vec<usize> = text.lines().map(str::len).collect();
+    let mut lines = vec![Vec::new(); line_lengths.len()];
+    let mut cursor = tree.walk();
+    loop {
+        let node = cursor.node();
+        let class = classify(node);
+        if let Some(class) = class {
+            push_node_spans(&mut lines, &line_lengths, node, class);
+        }
+        // Strings, comments and numbers are highlighted whole, with their parts
+        let descend = class.is_none() && cursor.goto_first_child();
+        if !descend && !cursor.goto_next_sibling() {
+            loop {
+                if !cursor.goto_parent() {
+                    return Some(lines);
+                }
+                if cursor.goto_next_sibling() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn classify(node: tree_sitter::Node<'_>) -> Option<HighlightClass> {
+    let kind = node.kind();
+    if kind.contains("comment") {
+        return Some(HighlightClass::Comment);
+    }
+    if kind.contains("string")
+        || matches!(kind, "char_literal" | "character_literal" | "rune_literal")
+    {
+        return Some(HighlightClass::String);
+    }
+    if kind.contains("integer") || kind.contains("float") || kind.starts_with("number") {
+        return Some(HighlightClass::Number);
+    }
+    if node.child_count() > 0 {
+        return None;
+    }
+    if !node.is_named() {
+        // Anonymous leaves are punctuation, operators and keywords
+        return kind
+            .chars()
+            .all(|c| c.is_ascii_alphabetic() || c == '_')
+            .then_some(HighlightClass::Keyword);
+    }
+    if matches!(
+        kind,
+        "type_identifier" | "primitive_type" | "predefined_type" | "builtin_type"
+    ) {
+        return Some(HighlightClass::Type);
+    }
+    (kind.ends_with("identifier") && names_function(node)).then_some(HighlightClass::Function)
+}
+
+/// Whether an identifier is the name of a function or method being defined,
+/// or the function a call expression calls (directly or as `recv.method`)
+fn names_function(node: tree_sitter::Node<'_>) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    let is_field = |parent: tree_sitter::Node<'_>, field: &str| {
+        parent.child_by_field_name(field) == Some(node)
+    };
+    let parent_kind = parent.kind();
+    if (parent_kind.contains("function") || parent_kind.contains("method"))
+        && is_field(parent, "name")
+    {
+        return true;
+    }
+    if is_field(parent, "function") || (parent_kind == "call" && is_field(parent, "method")) {
+        return true;
+    }
+    // `recv.method(...)`: the member is the callee's last part
+    ["field", "property", "attribute"]
+        .iter()
+        .any(|field| is_field(parent, field))
+        && parent
+            .parent()
+            .is_some_and(|call| call.child_by_field_name("function") == Some(parent))
+}
+
+fn push_node_spans(
+    lines: &mut [Vec<HighlightSpan>],
+    line_lengths: &[usize],
+    node: tree_sitter::Node<'_>,
+    class: HighlightClass,
+) {
+    let start = node.start_position();
+    let end = node.end_position();
+    for row in start.row..=end.row.min(line_lengths.len().saturating_sub(1)) {
+        let from = if row == start.row { start.column } else { 0 };
+        let to = if row == end.row {
+            end.column
+        } else {
+            line_lengths[row]
+        };
+        let to = to.min(line_lengths[row]);
+        if from < to {
+            lines[row].push(HighlightSpan {
+                start: from,
+                end: to,
+                class,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classes_tokens_per_line() {
+        let source = "/* a\n   b */\nfn add(x: u32) -> u32 {\n    x.max(1) + \"s\".len()\n}\n";
+        let lines = highlight_lines(source, ck_core::Language::Rust).unwrap();
+        assert_eq!(lines.len(), 5);
+        let classes = |row: usize| -> Vec<(&str, HighlightClass)> {
+            let line = source.lines().nth(row).unwrap();
+            lines[row]
+                .iter()
+                .map(|span| (&line[span.start..span.end], span.class))
+                .collect()
+        };
+
+        // A block comment is split at its line breaks
+        assert_eq!(classes(0), vec![("/* a", HighlightClass::Comment)]);
+        assert_eq!(classes(1), vec![("   b */", HighlightClass::Comment)]);
+        assert_eq!(
+            classes(2),
+            vec![
+                ("fn", HighlightClass::Keyword),
+                ("add", HighlightClass::Function),
+                ("u32", HighlightClass::Type),
+                ("u32", HighlightClass::Type),
+            ]
+        );
+        assert_eq!(
+            classes(3),
+            vec![
+                ("max", HighlightClass::Function),
+                ("1", HighlightClass::Number),
+                ("\"s\"", HighlightClass::String),
+                ("len", HighlightClass::Function),
+            ]
+        );
+        assert!(highlight_lines("plain", ck_core::Language::Toml).is_none());
+    }
+}
@@ -0,0 +1,141 @@
+//! `ck:ignore` comments, which keep chunks out of the index while the chunk
+//! view still shows them. `// ck:ignore` above a definition skips its chunk,
+//! `// ck:ignore-all` also skips the chunks nested in it, and
+//! `// ck:ignore-file` in the comments opening a file skips the whole file.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Chunk, ChunkType};
+
+/// What a `ck:ignore` comment heading a chunk leaves out of the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IgnoreScope {
+    /// `ck:ignore`: only the marked chunk
+    Chunk,
+    /// `ck:ignore-all`: the marked chunk and every chunk nested in it
+    WithChildren,
+}
+
+/// The marker in the comments heading `chunk`: its leading trivia, or the
+/// comment lines right above it in `source` for grammars whose comments don't
+/// join the definition
+pub(crate) fn ignore_marker(source: &str, chunk: &Chunk) -> Option<IgnoreScope> {
+    if chunk.chunk_type == ChunkType::Text {
+        return None;
+    }
+    // Whole lines only; a chunk can start after `pub ` on its line
+    let before = source.get(..chunk.span.byte_start).unwrap_or_default();
+    let before = &before[..before.rfind('\n').map_or(0, |at| at + 1)];
+    let above = before
+        .lines()
+        .rev()
+        .take_while(|line| is_comment_line(line));
+
+    chunk
+        .metadata
+        .leading_trivia
+        .iter()
+        .flat_map(|trivia| trivia.lines())
+        .chain(above)
+        .find_map(|line| match marker_word(line) {
+            Some("ck:ignore") => Some(IgnoreScope::Chunk),
+            Some("ck:ignore-all") => Some(IgnoreScope::WithChildren),
+            _ => None,
+        })
+}
+
+/// Whether one of the comments opening `text` (before any code) is `ck:ignore-file`
+pub fn has_ignore_file_marker(text: &str) -> bool {
+    text.lines()
+        .take_while(|line| line.trim().is_empty() || is_comment_line(line))
+        .any(|line| marker_word(line) == Some("ck:ignore-file"))
+}
+
+/// `chunks` without those a `ck:ignore` comment keeps out of the index
+pub fn without_ignored(chunks: Vec<Chunk>) -> Vec<Chunk> {
+    let ignored_trees: Vec<(usize, usize)> = chunks
+        .iter()
+        .filter(|chunk| chunk.metadata.ignore == Some(IgnoreScope::WithChildren))
+        .map(|chunk| (chunk.span.byte_start, chunk.span.byte_end))
+        .collect();
+
+    chunks
+        .into_iter()
+        .filter(|chunk| {
+            chunk.metadata.ignore.is_none()
+                && !ignored_trees.iter().any(|&(start, end)| {
+                    start <= chunk.span.byte_start && chunk.span.byte_end <= end
+                })
+        })
+        .collect()
+}
+
+fn is_comment_line(line: &str) -> bool {
+    let line = line.trim_start();
+    ["//", "#", "--", "/*", "*", ";", "<!--"]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+}
+
+/// The first word of a comment line, without its comment markers
+fn marker_word(line: &str) -> Option<&str> {
+    if !is_comment_line(line) {
+        return None;
+    }
+    line.trim()
+        .trim_start_matches(['/', '#', '-', '*', ';', '<', '!'])
+        .split_whitespace()
+        .next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_text;
+
+    fn indexed_names(source: &str, language: ck_core::Language) -> Vec<String> {
+        without_ignored(chunk_text(source, Some(language)).unwrap())
+            .into_iter()
+            .filter_map(|chunk| chunk.metadata.name)
+            .collect()
+    }
+
+    #[test]
+    fn ignore_comments_skip_chunks_and_children() {
+        let source = "// ck:ignore generated\nfn fixture() {\n    1\n}\n\nfn kept() {\n    2\n}\n";
+        let chunks = chunk_text(source, Some(ck_core::Language::Rust)).unwrap();
+        let fixture = chunks
+            .iter()
+            .find(|chunk| chunk.metadata.name.as_deref() == Some("fixture"))
+            .expect("the marked chunk is still chunked");
+        assert_eq!(fixture.metadata.ignore, Some(IgnoreScope::Chunk));
+        assert_eq!(
+            indexed_names(source, ck_core::Language::Rust),
+            vec!["kept".to_string()]
+        );
+
+        let class = "class Fixtures:\n    def one(self):\n        return 1\n\n# ck:IGNORE is not a marker\ndef two():\n    return 2\n";
+        let marked_all = format!("# ck:ignore-all\n{class}");
+        let marked_self = format!("# ck:ignore\n{class}");
+        assert_eq!(
+            indexed_names(&marked_all, ck_core::Language::Python),
+            vec!["two".to_string()]
+        );
+        assert_eq!(
+            indexed_names(&marked_self, ck_core::Language::Python),
+            vec!["one".to_string(), "two".to_string()]
+        );
+    }
+
+    #[test]
+    fn ignore_file_marker_must_open_the_file() {
+        assert!(has_ignore_file_marker(
+            "#!/usr/bin/env python\n# Generated by protoc\n# ck:ignore-file\nimport os\n"
+        ));
+        assert!(!has_ignore_file_marker("fn main() {}\n// ck:ignore-file\n"));
+        assert!(!has_ignore_file_marker(
+            "let marker = \"ck:ignore-file\";\n"
+        ));
+    }
+}
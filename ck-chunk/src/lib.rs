@@ -2,10 +2,18 @@ use anyhow::Result;
 use ck_core::Span;
 use serde::{Deserialize, Serialize};
 
+mod config_files;
+mod graphql;
+mod highlight;
+mod ignore;
 mod query_chunker;
+mod sfc;
+mod strings;
 
 /// Import token estimation from ck-embed
 pub use ck_embed::TokenEstimator;
+pub use highlight::{HighlightClass, HighlightSpan, highlight_lines};
+pub use ignore::{IgnoreScope, has_ignore_file_marker, without_ignored};
 
 /// Fallback to estimation if precise tokenization fails
 fn estimate_tokens(text: &str) -> usize {
@@ -54,23 +62,49 @@ pub struct StrideInfo {
     pub overlap_end: usize,
 }
 
+/// One enclosing chunk in a breadcrumb trail: its name and the span of the
+/// chunk it names, so a UI can jump straight to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreadcrumbSegment {
+    pub name: String,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ChunkMetadata {
+    /// Names of the enclosing chunks, outermost first; the chunk's own name is not included
     pub ancestry: Vec<String>,
+    /// `ancestry` with each enclosing chunk's span
+    #[serde(default)]
+    pub breadcrumb_segments: Vec<BreadcrumbSegment>,
     pub breadcrumb: Option<String>,
+    /// The chunk's own name (the function, class or module it defines), when it has one
+    #[serde(default)]
+    pub name: Option<String>,
     pub leading_trivia: Vec<String>,
     pub trailing_trivia: Vec<String>,
     pub byte_length: usize,
+    /// Tokens in the chunk's `text`, which is exactly what gets embedded: leading
+    /// trivia such as doc comments is part of it (comments are not stripped),
+    /// trailing trivia is not
     pub estimated_tokens: usize,
+    /// Set when a `ck:ignore` comment heads the chunk; see [`without_ignored`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignore: Option<IgnoreScope>,
 }
 
 impl ChunkMetadata {
     fn from_context(
         text: &str,
-        ancestry: Vec<String>,
+        breadcrumb_segments: Vec<BreadcrumbSegment>,
+        name: Option<String>,
         leading_trivia: Vec<String>,
         trailing_trivia: Vec<String>,
     ) -> Self {
+        let ancestry: Vec<String> = breadcrumb_segments
+            .iter()
+            .map(|segment| segment.name.clone())
+            .collect();
         let breadcrumb = if ancestry.is_empty() {
             None
         } else {
@@ -79,22 +113,28 @@ impl ChunkMetadata {
 
         Self {
             ancestry,
+            breadcrumb_segments,
             breadcrumb,
+            name,
             leading_trivia,
             trailing_trivia,
             byte_length: text.len(),
             estimated_tokens: estimate_tokens(text),
+            ignore: None,
         }
     }
 
     fn from_text(text: &str) -> Self {
         Self {
             ancestry: Vec::new(),
+            breadcrumb_segments: Vec::new(),
             breadcrumb: None,
+            name: None,
             leading_trivia: Vec::new(),
             trailing_trivia: Vec::new(),
             byte_length: text.len(),
             estimated_tokens: estimate_tokens(text),
+            ignore: None,
         }
     }
 
@@ -125,6 +165,31 @@ pub enum ChunkType {
     Module,
 }
 
+impl From<&ChunkType> for ck_core::SymbolKind {
+    fn from(chunk_type: &ChunkType) -> Self {
+        match chunk_type {
+            ChunkType::Function => ck_core::SymbolKind::Function,
+            ChunkType::Method => ck_core::SymbolKind::Method,
+            ChunkType::Class => ck_core::SymbolKind::Class,
+            ChunkType::Module => ck_core::SymbolKind::Module,
+            ChunkType::Text => ck_core::SymbolKind::Text,
+        }
+    }
+}
+
+impl From<ChunkType> for ck_core::SymbolKind {
+    fn from(chunk_type: ChunkType) -> Self {
+        Self::from(&chunk_type)
+    }
+}
+
+impl ChunkType {
+    /// The chunk type stored in the index; text chunks have none
+    pub fn index_name(&self) -> Option<&'static str> {
+        ck_core::SymbolKind::from(self).index_chunk_type()
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ParseableLanguage {
     Python,
@@ -181,6 +246,9 @@ pub fn chunk_text(text: &str, language: Option<ck_core::Language>) -> Result<Vec
     chunk_text_with_config(text, language, &ChunkConfig::default())
 }
 
+/// Counts the tokens in a chunk's text, given the chunk's language
+pub type TokenEstimateFn = fn(&str, Option<ck_core::Language>) -> usize;
+
 /// Configuration for chunking behavior
 #[derive(Debug, Clone)]
 pub struct ChunkConfig {
@@ -190,6 +258,71 @@ pub struct ChunkConfig {
     pub stride_overlap: usize,
     /// Enable striding for chunks that exceed max_tokens
     pub enable_striding: bool,
+    /// Decides which chunks get strided and fills `estimated_tokens`. Defaults to
+    /// [`TokenEstimator::estimate_tokens_for_language`]; tests can swap in
+    /// [`deterministic_token_estimate`] so boundaries don't move when the
+    /// estimator's heuristics are retuned.
+    pub token_estimator: TokenEstimateFn,
+    /// Also chunk string literals and heredocs of five or more lines on their
+    /// own, under their host's breadcrumb, so embedded SQL or HTML is searchable
+    pub string_chunks: bool,
+    /// The windows text without structural chunking is cut into
+    pub text_window: TextWindow,
+}
+
+/// Overlapping windows of lines for files without structural chunking, such as
+/// config files and languages without a grammar. A window is cut short where
+/// its text would exceed [`ChunkConfig::max_tokens`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextWindow {
+    /// Lines per window
+    pub lines: usize,
+    /// Lines each window shares with the one before it, so a block isn't only
+    /// ever seen split across two windows
+    pub overlap: usize,
+}
+
+impl Default for TextWindow {
+    fn default() -> Self {
+        Self {
+            lines: 40,
+            overlap: 8,
+        }
+    }
+}
+
+impl std::str::FromStr for TextWindow {
+    type Err = anyhow::Error;
+
+    /// `LINES` or `LINES:OVERLAP`; the overlap defaults to a fifth of the window
+    fn from_str(spec: &str) -> Result<Self> {
+        let (lines, overlap) = match spec.split_once(':') {
+            Some((lines, overlap)) => (lines, Some(overlap)),
+            None => (spec, None),
+        };
+        let lines: usize = lines.trim().parse().map_err(|_| {
+            anyhow::anyhow!("Invalid text window '{}': expected LINES[:OVERLAP]", spec)
+        })?;
+        let overlap = match overlap {
+            Some(overlap) => overlap.trim().parse().map_err(|_| {
+                anyhow::anyhow!("Invalid text window '{}': expected LINES[:OVERLAP]", spec)
+            })?,
+            None => lines / 5,
+        };
+        if lines == 0 || overlap >= lines {
+            anyhow::bail!(
+                "Invalid text window '{}': windows need at least one line and an overlap smaller than the window",
+                spec
+            );
+        }
+        Ok(Self { lines, overlap })
+    }
+}
+
+impl std::fmt::Display for TextWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.lines, self.overlap)
+    }
 }
 
 impl Default for ChunkConfig {
@@ -198,28 +331,159 @@ impl Default for ChunkConfig {
             max_tokens: 8192,     // Default to Nomic model limit
             stride_overlap: 1024, // 12.5% overlap
             enable_striding: true,
+            token_estimator: TokenEstimator::estimate_tokens_for_language,
+            string_chunks: false,
+            text_window: TextWindow::default(),
         }
     }
 }
 
+/// One token per four characters, whatever the text or language. Meant for
+/// tests that assert on chunk counts and spans.
+pub fn deterministic_token_estimate(text: &str, _language: Option<ck_core::Language>) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
 /// New function that accepts model name for model-specific chunking
 pub fn chunk_text_with_model(
     text: &str,
     language: Option<ck_core::Language>,
     model_name: Option<&str>,
 ) -> Result<Vec<Chunk>> {
-    let (target_tokens, overlap_tokens) = get_model_chunk_config(model_name);
+    chunk_text_with_model_and_strings(text, language, model_name, false)
+}
 
-    // Create a config based on model-specific parameters
+/// [`chunk_text_with_model`], with big string literals as chunks of their own
+/// when `string_chunks` is set (see [`ChunkConfig::string_chunks`])
+pub fn chunk_text_with_model_and_strings(
+    text: &str,
+    language: Option<ck_core::Language>,
+    model_name: Option<&str>,
+    string_chunks: bool,
+) -> Result<Vec<Chunk>> {
     let config = ChunkConfig {
-        max_tokens: target_tokens,
-        stride_overlap: overlap_tokens,
-        enable_striding: true,
+        string_chunks,
+        ..ChunkConfig::for_model(model_name)
     };
-
     chunk_text_with_config_and_model(text, language, &config, model_name)
 }
 
+impl ChunkConfig {
+    /// The defaults with `model_name`'s chunk size and overlap (see
+    /// [`get_model_chunk_config`])
+    pub fn for_model(model_name: Option<&str>) -> Self {
+        let (target_tokens, overlap_tokens) = get_model_chunk_config(model_name);
+        Self {
+            max_tokens: target_tokens,
+            stride_overlap: overlap_tokens,
+            enable_striding: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// [`chunk_text_with_model`], handing each chunk to `each` instead of
+/// collecting them, so a caller can embed and drop chunks as they come. An
+/// error from `each` stops chunking and is returned.
+pub fn chunk_text_with_model_each(
+    text: &str,
+    language: Option<ck_core::Language>,
+    model_name: Option<&str>,
+    mut each: impl FnMut(Chunk) -> Result<()>,
+) -> Result<()> {
+    let config = ChunkConfig::for_model(model_name);
+    chunk_text_each(text, language, &config, model_name, &mut each)
+}
+
+/// The smallest chunk whose line span covers `line` (1-based), if any
+pub fn innermost_chunk_at(chunks: &[Chunk], line: usize) -> Option<&Chunk> {
+    chunks
+        .iter()
+        .filter(|chunk| chunk.span.line_start <= line && line <= chunk.span.line_end)
+        .min_by_key(|chunk| chunk.span.line_end - chunk.span.line_start)
+}
+
+/// Whether `language` has a tree-sitter grammar, i.e. gets function/class chunks
+/// rather than the plain-text fallback
+pub fn has_structural_support(language: ck_core::Language) -> bool {
+    ParseableLanguage::try_from(language).is_ok()
+        || sfc::ComponentKind::from_language(language).is_some()
+        || matches!(
+            language,
+            ck_core::Language::GraphQl | ck_core::Language::Toml | ck_core::Language::Yaml
+        )
+}
+
+/// Syntax problems tree-sitter found while parsing a file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseDiagnostics {
+    /// `ERROR` nodes: text the grammar couldn't place
+    pub error_nodes: usize,
+    /// Tokens the parser had to invent to recover, e.g. a missing `}`
+    pub missing_nodes: usize,
+    /// 1-based line of the first error or missing node
+    pub first_error_line: Option<usize>,
+}
+
+impl ParseDiagnostics {
+    pub fn error_count(&self) -> usize {
+        self.error_nodes + self.missing_nodes
+    }
+}
+
+/// Parse `text` with the grammar for `language` and count syntax errors. `None`
+/// when the language has no structural support, so nothing would be parsed.
+/// Vue and Svelte components count the errors in their script blocks.
+pub fn parse_diagnostics(
+    text: &str,
+    language: ck_core::Language,
+) -> Result<Option<ParseDiagnostics>> {
+    if sfc::ComponentKind::from_language(language).is_some() {
+        return sfc::component_diagnostics(text).map(Some);
+    }
+    let Ok(parseable) = ParseableLanguage::try_from(language) else {
+        return Ok(None);
+    };
+    diagnostics_for(text, parseable).map(Some)
+}
+
+fn diagnostics_for(text: &str, parseable: ParseableLanguage) -> Result<ParseDiagnostics> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&tree_sitter_language(parseable)?)?;
+    let tree = parser
+        .parse(text, None)
+        .ok_or_else(|| anyhow::anyhow!("Failed to parse {} code", parseable))?;
+
+    let mut diagnostics = ParseDiagnostics::default();
+    let mut cursor = tree.walk();
+    loop {
+        let node = cursor.node();
+        if node.is_error() || node.is_missing() {
+            if node.is_error() {
+                diagnostics.error_nodes += 1;
+            } else {
+                diagnostics.missing_nodes += 1;
+            }
+            let line = node.start_position().row + 1;
+            diagnostics.first_error_line = Some(
+                diagnostics
+                    .first_error_line
+                    .map_or(line, |first| first.min(line)),
+            );
+        }
+
+        // Error subtrees are counted once, not once per node inside them
+        if !node.is_error() && cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return Ok(diagnostics);
+            }
+        }
+    }
+}
+
 pub fn chunk_text_with_config(
     text: &str,
     language: Option<ck_core::Language>,
@@ -228,12 +492,35 @@ pub fn chunk_text_with_config(
     chunk_text_with_config_and_model(text, language, config, None)
 }
 
-fn chunk_text_with_config_and_model(
+/// [`chunk_text_with_config`] for `model_name`, which decides how parsed
+/// chunks are sized; start from [`ChunkConfig::for_model`] to match it
+pub fn chunk_text_with_config_and_model(
     text: &str,
     language: Option<ck_core::Language>,
     config: &ChunkConfig,
     model_name: Option<&str>,
 ) -> Result<Vec<Chunk>> {
+    let mut chunks = Vec::new();
+    chunk_text_each(text, language, config, model_name, &mut |chunk| {
+        chunks.push(chunk);
+        Ok(())
+    })?;
+
+    tracing::debug!("Successfully created {} final chunks", chunks.len());
+    Ok(chunks)
+}
+
+/// Hand each final chunk to `each` as soon as it is ready. Generic chunking
+/// streams line windows straight through; parsed files still build their
+/// structural chunks first (gap filling and nesting need them all), but each
+/// is strided and recounted only on its way out.
+fn chunk_text_each(
+    text: &str,
+    language: Option<ck_core::Language>,
+    config: &ChunkConfig,
+    model_name: Option<&str>,
+    each: &mut dyn FnMut(Chunk) -> Result<()>,
+) -> Result<()> {
     tracing::debug!(
         "Chunking text with language: {:?}, length: {} chars, config: {:?}",
         language,
@@ -241,51 +528,111 @@ fn chunk_text_with_config_and_model(
         config
     );
 
-    let result = match language.map(ParseableLanguage::try_from) {
-        Some(Ok(lang)) => {
+    let mut finish = |mut chunk: Chunk| -> Result<()> {
+        chunk.metadata.ignore = ignore::ignore_marker(text, &chunk);
+        let estimated_tokens = (config.token_estimator)(&chunk.text, language);
+        let pieces = if config.enable_striding && estimated_tokens > config.max_tokens {
+            tracing::debug!(
+                "Chunk with {} tokens exceeds limit of {}, applying striding",
+                estimated_tokens,
+                config.max_tokens
+            );
+            stride_large_chunk(chunk, config, language)?
+        } else {
+            vec![chunk]
+        };
+
+        // Chunk builders estimate without knowing the language or the configured
+        // estimator; recount now that the chunk's final text is known
+        for mut piece in pieces {
+            piece.metadata.estimated_tokens = (config.token_estimator)(&piece.text, language);
+            each(piece)?;
+        }
+        Ok(())
+    };
+
+    let component = language.and_then(sfc::ComponentKind::from_language);
+    let chunks = match (component, language.map(ParseableLanguage::try_from)) {
+        (Some(kind), _) => {
+            tracing::debug!("Splitting single-file component into its blocks");
+            sfc::chunk_component(text, kind, model_name)?
+        }
+        _ if language == Some(ck_core::Language::GraphQl) => {
+            tracing::debug!("Splitting GraphQL schema into its type definitions");
+            let chunks = graphql::chunk_schema(text);
+            if chunks.is_empty() {
+                return chunk_generic_each(text, language, config, &mut finish);
+            }
+            chunks
+        }
+        _ if matches!(
+            language,
+            Some(ck_core::Language::Toml | ck_core::Language::Yaml)
+        ) =>
+        {
+            tracing::debug!("Splitting config file into its sections");
+            let chunks = if language == Some(ck_core::Language::Toml) {
+                config_files::chunk_toml(text)
+            } else {
+                config_files::chunk_yaml(text)
+            };
+            if chunks.is_empty() {
+                return chunk_generic_each(text, language, config, &mut finish);
+            }
+            chunks
+        }
+        (_, Some(Ok(lang))) => {
             tracing::debug!("Using {} tree-sitter parser", lang);
-            chunk_language_with_model(text, lang, model_name)
+            let mut chunks = chunk_language_with_model(text, lang, model_name)?;
+            if config.string_chunks {
+                let literals = strings::string_chunks(text, lang, &chunks)?;
+                if !literals.is_empty() {
+                    chunks.extend(literals);
+                    chunks.sort_by_key(|chunk| chunk.span.byte_start);
+                }
+            }
+            chunks
         }
-        Some(Err(_)) => {
+        (_, Some(Err(_))) => {
             tracing::debug!("Language not supported for parsing, using generic chunking strategy");
-            chunk_generic_with_token_config(text, model_name)
+            return chunk_generic_each(text, language, config, &mut finish);
         }
-        None => {
+        (_, None) => {
             tracing::debug!("Using generic chunking strategy");
-            chunk_generic_with_token_config(text, model_name)
+            return chunk_generic_each(text, language, config, &mut finish);
         }
     };
 
-    let mut chunks = result?;
-
-    // Apply striding if enabled and necessary
-    if config.enable_striding {
-        chunks = apply_striding(chunks, config)?;
-    }
-
-    tracing::debug!("Successfully created {} final chunks", chunks.len());
-    Ok(chunks)
+    chunks.into_iter().try_for_each(finish)
 }
 
 fn chunk_generic(text: &str) -> Result<Vec<Chunk>> {
-    chunk_generic_with_token_config(text, None)
+    chunk_generic_with_config(text, &ChunkConfig::default())
 }
 
 fn chunk_generic_with_token_config(text: &str, model_name: Option<&str>) -> Result<Vec<Chunk>> {
-    let mut chunks = Vec::new();
-    let lines: Vec<&str> = text.lines().collect();
-
-    // Get model-specific optimal chunk size in tokens
-    let (target_tokens, overlap_tokens) = get_model_chunk_config(model_name);
+    chunk_generic_with_config(text, &ChunkConfig::for_model(model_name))
+}
 
-    // Convert token targets to approximate line counts
-    // This is a rough heuristic - we'll validate with actual token counting
-    let avg_tokens_per_line = 10.0; // Rough estimate for code
-    let target_lines = ((target_tokens as f32) / avg_tokens_per_line) as usize;
-    let overlap_lines = ((overlap_tokens as f32) / avg_tokens_per_line) as usize;
+fn chunk_generic_with_config(text: &str, config: &ChunkConfig) -> Result<Vec<Chunk>> {
+    let mut chunks = Vec::new();
+    chunk_generic_each(text, None, config, &mut |chunk| {
+        chunks.push(chunk);
+        Ok(())
+    })?;
+    Ok(chunks)
+}
 
-    let chunk_size = target_lines.max(5); // Minimum 5 lines
-    let overlap = overlap_lines.max(1); // Minimum 1 line overlap
+/// Overlapping windows of lines (see [`TextWindow`]), each handed to `each` as it is cut
+fn chunk_generic_each(
+    text: &str,
+    language: Option<ck_core::Language>,
+    config: &ChunkConfig,
+    each: &mut dyn FnMut(Chunk) -> Result<()>,
+) -> Result<()> {
+    let lines: Vec<&str> = text.lines().collect();
+    let window = config.text_window.lines.max(1);
+    let overlap = config.text_window.overlap.min(window - 1);
 
     // Pre-compute cumulative byte offsets for O(1) lookup, accounting for different line endings
     let mut line_byte_offsets = Vec::with_capacity(lines.len() + 1);
@@ -317,14 +664,18 @@ fn chunk_generic_with_token_config(text: &str, model_name: Option<&str>) -> Resu
 
     let mut i = 0;
     while i < lines.len() {
-        let end = (i + chunk_size).min(lines.len());
-        let chunk_lines = &lines[i..end];
-        let chunk_text = chunk_lines.join("\n");
+        let mut end = (i + window).min(lines.len());
+        let mut chunk_text = lines[i..end].join("\n");
+        // Stay within the token budget, down to a single line
+        while end > i + 1 && (config.token_estimator)(&chunk_text, language) > config.max_tokens {
+            end -= 1;
+            chunk_text = lines[i..end].join("\n");
+        }
         let byte_start = line_byte_offsets[i];
         let byte_end = line_byte_offsets[end];
         let metadata = ChunkMetadata::from_text(&chunk_text);
 
-        chunks.push(Chunk {
+        each(Chunk {
             span: Span {
                 byte_start,
                 byte_end,
@@ -335,15 +686,17 @@ fn chunk_generic_with_token_config(text: &str, model_name: Option<&str>) -> Resu
             chunk_type: ChunkType::Text,
             stride_info: None,
             metadata,
-        });
+        })?;
 
-        i += chunk_size - overlap;
-        if i >= lines.len() {
+        if end == lines.len() {
             break;
         }
+        // A window cut short shares a proportional part of itself with the next
+        let shared = overlap * (end - i) / window;
+        i = (end - shared).max(i + 1);
     }
 
-    Ok(chunks)
+    Ok(())
 }
 
 pub(crate) fn tree_sitter_language(language: ParseableLanguage) -> Result<tree_sitter::Language> {
@@ -537,7 +890,11 @@ fn fill_gaps(mut chunks: Vec<Chunk>, text: &str) -> Vec<Chunk> {
     result
 }
 
-/// Merge Haskell function equations that belong to the same function definition
+/// Merge Haskell function equations that belong to the same function definition.
+///
+/// This is the only pass that merges chunks, and it groups equations by
+/// function name rather than by adjacency, so neighbouring one-liners that
+/// define different functions always stay separate chunks.
 fn merge_haskell_functions(chunks: Vec<Chunk>, source: &str) -> Vec<Chunk> {
     if chunks.is_empty() {
         return chunks;
@@ -806,10 +1163,10 @@ fn chunk_type_for_node(
         {
             return None;
         }
-        ParseableLanguage::CSharp if node.kind() == "variable_declaration" => {
-            if !is_csharp_field_like(*node) {
-                return None;
-            }
+        ParseableLanguage::CSharp
+            if node.kind() == "variable_declaration" && !is_csharp_field_like(*node) =>
+        {
+            return None;
         }
         _ => {}
     }
@@ -894,10 +1251,12 @@ pub(crate) fn build_chunk(
     }
 
     let chunk_type = adjust_chunk_type_for_context(target_node, initial_type, language);
-    let ancestry = collect_ancestry(target_node, language, source);
+    let segments = collect_ancestry(target_node, language, source);
+    let name = display_name_for_node(target_node, language, source, chunk_type.clone());
     let leading_trivia = segments_to_strings(&leading_segments, source);
     let trailing_trivia = segments_to_strings(&trailing_segments, source);
-    let metadata = ChunkMetadata::from_context(&text, ancestry, leading_trivia, trailing_trivia);
+    let metadata =
+        ChunkMetadata::from_context(&text, segments, name, leading_trivia, trailing_trivia);
 
     Some(Chunk {
         span: Span {
@@ -968,20 +1327,33 @@ fn extend_with_leading_trivia(
     let mut current = node;
     let mut segments = Vec::new();
 
-    while let Some(prev) = current.prev_sibling() {
-        if should_attach_leading_trivia(language, &prev)
-            && only_whitespace_between(source, prev.end_byte(), start_byte)
-        {
-            start_byte = prev.start_byte();
-            start_row = prev.start_position().row;
-            segments.push(TriviaSegment {
-                start_byte: prev.start_byte(),
-                end_byte: prev.end_byte(),
-            });
-            current = prev;
-            continue;
+    loop {
+        while let Some(prev) = current.prev_sibling() {
+            if should_attach_leading_trivia(language, &prev)
+                && only_whitespace_between(source, prev.end_byte(), start_byte)
+            {
+                start_byte = prev.start_byte();
+                start_row = prev.start_position().row;
+                segments.push(TriviaSegment {
+                    start_byte: prev.start_byte(),
+                    end_byte: prev.end_byte(),
+                });
+                current = prev;
+                continue;
+            }
+            break;
+        }
+
+        // Decorators can live in a wrapper node around the definition; comments
+        // above the wrapper belong to the definition too
+        match current.parent() {
+            Some(parent)
+                if is_decorator_wrapper(language, &parent) && parent.start_byte() == start_byte =>
+            {
+                current = parent;
+            }
+            _ => break,
         }
-        break;
     }
 
     segments.reverse();
@@ -1003,6 +1375,10 @@ fn should_attach_leading_trivia(language: ParseableLanguage, node: &tree_sitter:
     }
 }
 
+fn is_decorator_wrapper(language: ParseableLanguage, node: &tree_sitter::Node<'_>) -> bool {
+    matches!(language, ParseableLanguage::Python) && node.kind() == "decorated_definition"
+}
+
 fn collect_trailing_trivia(
     node: tree_sitter::Node<'_>,
     language: ParseableLanguage,
@@ -1052,18 +1428,30 @@ fn segments_to_strings(segments: &[TriviaSegment], source: &str) -> Vec<String>
     result
 }
 
+/// The named chunks enclosing `node`, outermost first, each spanning what
+/// [`build_chunk`] would give its chunk
 fn collect_ancestry(
     mut node: tree_sitter::Node<'_>,
     language: ParseableLanguage,
     source: &str,
-) -> Vec<String> {
+) -> Vec<BreadcrumbSegment> {
     let mut parts = Vec::new();
 
     while let Some(parent) = node.parent() {
         if let Some(parent_chunk_type) = chunk_type_for_node(language, &parent)
             && let Some(name) = display_name_for_node(parent, language, source, parent_chunk_type)
         {
-            parts.push(name);
+            let target = adjust_node_for_language(parent, language);
+            let (byte_start, start_row, _) = extend_with_leading_trivia(target, language, source);
+            parts.push(BreadcrumbSegment {
+                name,
+                span: Span {
+                    byte_start,
+                    byte_end: target.end_byte(),
+                    line_start: start_row + 1,
+                    line_end: target.end_position().row + 1,
+                },
+            });
         }
         node = parent;
     }
@@ -1231,34 +1619,12 @@ fn is_csharp_field_like(node: tree_sitter::Node<'_>) -> bool {
     false
 }
 
-/// Apply striding to chunks that exceed the token limit
-fn apply_striding(chunks: Vec<Chunk>, config: &ChunkConfig) -> Result<Vec<Chunk>> {
-    let mut result = Vec::new();
-
-    for chunk in chunks {
-        let estimated_tokens = estimate_tokens(&chunk.text);
-
-        if estimated_tokens <= config.max_tokens {
-            // Chunk fits within limit, no striding needed
-            result.push(chunk);
-        } else {
-            // Chunk exceeds limit, apply striding
-            tracing::debug!(
-                "Chunk with {} tokens exceeds limit of {}, applying striding",
-                estimated_tokens,
-                config.max_tokens
-            );
-
-            let strided_chunks = stride_large_chunk(chunk, config)?;
-            result.extend(strided_chunks);
-        }
-    }
-
-    Ok(result)
-}
-
 /// Create strided chunks from a large chunk that exceeds token limits
-fn stride_large_chunk(chunk: Chunk, config: &ChunkConfig) -> Result<Vec<Chunk>> {
+fn stride_large_chunk(
+    chunk: Chunk,
+    config: &ChunkConfig,
+    language: Option<ck_core::Language>,
+) -> Result<Vec<Chunk>> {
     let text = &chunk.text;
 
     // Early return for empty chunks to avoid divide-by-zero
@@ -1269,7 +1635,7 @@ fn stride_large_chunk(chunk: Chunk, config: &ChunkConfig) -> Result<Vec<Chunk>>
     // Calculate stride parameters in characters (not bytes!)
     // Use a conservative estimate to ensure we stay under token limits
     let char_count = text.chars().count();
-    let estimated_tokens = estimate_tokens(text);
+    let estimated_tokens = (config.token_estimator)(text, language);
     // Guard against zero token estimate to prevent divide-by-zero panic
     let chars_per_token = if estimated_tokens == 0 {
         4.5 // Use default average if estimation fails
@@ -1325,9 +1691,11 @@ fn stride_large_chunk(chunk: Chunk, config: &ChunkConfig) -> Result<Vec<Chunk>>
         let byte_offset_start = chunk.span.byte_start + start_byte_pos;
         let byte_offset_end = chunk.span.byte_start + end_byte_pos;
 
-        // Estimate line numbers (approximate)
+        // The stride starts on the line containing start_byte_pos, i.e. one line
+        // further for every newline before it (lines().count() under-counts when
+        // the prefix ends exactly on a newline)
         let text_before_start = &text[..start_byte_pos];
-        let line_offset_start = text_before_start.lines().count().saturating_sub(1);
+        let line_offset_start = text_before_start.matches('\n').count();
         let stride_lines = stride_text.lines().count();
         let metadata = chunk.metadata.with_updated_text(stride_text);
 
@@ -1367,7 +1735,7 @@ fn stride_large_chunk(chunk: Chunk, config: &ChunkConfig) -> Result<Vec<Chunk>>
     tracing::debug!(
         "Created {} strides from chunk of {} tokens",
         strided_chunks.len(),
-        estimate_tokens(text)
+        estimated_tokens
     );
 
     Ok(strided_chunks)
@@ -1379,6 +1747,45 @@ fn stride_large_chunk(chunk: Chunk, config: &ChunkConfig) -> Result<Vec<Chunk>>
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_innermost_chunk_at_prefers_smallest_cover() {
+        let code = "struct Counter {\n    n: u32,\n}\n\nimpl Counter {\n    fn bump(&mut self) {\n        self.n += 1;\n    }\n}\n";
+        let chunks = chunk_text(code, Some(ck_core::Language::Rust)).unwrap();
+
+        let method = innermost_chunk_at(&chunks, 7).unwrap();
+        assert_eq!(method.chunk_type, ChunkType::Method);
+        assert!(method.text.contains("fn bump"));
+
+        let impl_block = innermost_chunk_at(&chunks, 5).unwrap();
+        assert!(impl_block.text.starts_with("impl Counter"));
+        assert!(innermost_chunk_at(&chunks, 100).is_none());
+    }
+
+    #[test]
+    fn test_parse_diagnostics_counts_syntax_errors() {
+        assert!(has_structural_support(ck_core::Language::Rust));
+        assert!(!has_structural_support(ck_core::Language::Java));
+        assert_eq!(
+            parse_diagnostics("class A {}", ck_core::Language::Java).unwrap(),
+            None
+        );
+
+        let clean = parse_diagnostics("fn ok() {}\n", ck_core::Language::Rust)
+            .unwrap()
+            .unwrap();
+        assert_eq!(clean.error_count(), 0);
+        assert_eq!(clean.first_error_line, None);
+
+        let broken = parse_diagnostics(
+            "fn ok() {}\n\nfn broken( {\n    let x = ;\n",
+            ck_core::Language::Rust,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(broken.error_count() > 0);
+        assert_eq!(broken.first_error_line, Some(3));
+    }
+
     fn canonicalize_spans(
         mut spans: Vec<(usize, usize, ChunkType)>,
     ) -> Vec<(usize, usize, ChunkType)> {
@@ -1479,6 +1886,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_text_windows_overlap_and_respect_the_token_budget() {
+        let text: String = (1..=100).map(|i| format!("key{i} = {i}\n")).collect();
+        let config = ChunkConfig {
+            token_estimator: deterministic_token_estimate,
+            ..ChunkConfig::default()
+        };
+        let spans = |config: &ChunkConfig| -> Vec<(usize, usize)> {
+            chunk_text_with_config(&text, None, config)
+                .unwrap()
+                .iter()
+                .map(|chunk| (chunk.span.line_start, chunk.span.line_end))
+                .collect()
+        };
+        assert_eq!(spans(&config), vec![(1, 40), (33, 72), (65, 100)]);
+
+        let window: TextWindow = "30:10".parse().unwrap();
+        assert_eq!(
+            spans(&ChunkConfig {
+                text_window: window,
+                ..config.clone()
+            }),
+            vec![(1, 30), (21, 50), (41, 70), (61, 90), (81, 100)]
+        );
+
+        // Lines of 8 to 12 characters: a 40-token budget fits the first 16
+        let budget = ChunkConfig {
+            max_tokens: 40,
+            ..config.clone()
+        };
+        let budget_spans = spans(&budget);
+        assert_eq!(budget_spans[0], (1, 16));
+        assert_eq!(budget_spans.last().unwrap().1, 100);
+        assert!(budget_spans.windows(2).all(|pair| pair[1].0 > pair[0].0));
+
+        assert_eq!("50".parse::<TextWindow>().unwrap().overlap, 10);
+        assert!("10:10".parse::<TextWindow>().is_err());
+        assert!("0".parse::<TextWindow>().is_err());
+        assert!("many".parse::<TextWindow>().is_err());
+    }
+
     #[test]
     fn test_chunk_generic_large_file_performance() {
         // Create a large text to ensure O(n) performance
@@ -1506,6 +1954,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_chunk_text_with_model_each_matches_collected_chunks() {
+        let rust_code = "fn one() {\n    1\n}\n\nfn two() {\n    2\n}\n";
+        let plain: String = (0..200).map(|i| format!("line {i}\n")).collect();
+
+        for (text, language) in [
+            (rust_code, Some(ck_core::Language::Rust)),
+            (plain.as_str(), None),
+        ] {
+            let collected = chunk_text_with_model(text, language, None).unwrap();
+            let mut streamed = Vec::new();
+            chunk_text_with_model_each(text, language, None, |chunk| {
+                streamed.push((chunk.span.byte_start, chunk.span.line_end, chunk.text));
+                Ok(())
+            })
+            .unwrap();
+            let expected: Vec<_> = collected
+                .into_iter()
+                .map(|chunk| (chunk.span.byte_start, chunk.span.line_end, chunk.text))
+                .collect();
+            assert_eq!(streamed, expected);
+        }
+
+        // An error from the callback stops chunking
+        let mut seen = 0;
+        let result = chunk_text_with_model_each(&plain, None, None, |_| {
+            seen += 1;
+            anyhow::bail!("stop")
+        });
+        assert!(result.is_err());
+        assert_eq!(seen, 1);
+    }
+
     #[test]
     fn test_chunk_rust() {
         let rust_code = r#"
@@ -1580,6 +2061,57 @@ async def async_helper():
         assert_query_parity(ParseableLanguage::Python, source);
     }
 
+    #[test]
+    fn test_python_decorators_join_their_definition() {
+        let code = std::fs::read_to_string("../examples/code/decorated_handlers.py")
+            .expect("Failed to read decorated_handlers.py");
+        let chunks = chunk_language(&code, ParseableLanguage::Python).unwrap();
+
+        let list_users = chunks
+            .iter()
+            .find(|chunk| chunk.text.contains("def list_users"))
+            .expect("list_users chunk");
+        assert_eq!(list_users.chunk_type, ChunkType::Function);
+        assert!(
+            list_users
+                .text
+                .starts_with("# List every registered user\n@app.route(\"/users\")")
+        );
+        assert_eq!(
+            list_users.metadata.leading_trivia,
+            vec![
+                "# List every registered user".to_string(),
+                "@app.route(\"/users\")".to_string(),
+                "@login_required".to_string(),
+            ]
+        );
+        let comment_line = code
+            .lines()
+            .position(|line| line.starts_with("# List every"))
+            .unwrap();
+        assert_eq!(list_users.span.line_start, comment_line + 1);
+
+        let delete_user = chunks
+            .iter()
+            .find(|chunk| {
+                chunk.chunk_type != ChunkType::Class && chunk.text.contains("def delete_user")
+            })
+            .expect("delete_user chunk");
+        assert_eq!(delete_user.chunk_type, ChunkType::Method);
+        assert!(
+            delete_user
+                .text
+                .starts_with("@staticmethod\n    @app.route(")
+        );
+        assert_eq!(delete_user.metadata.leading_trivia.len(), 2);
+
+        // No decorator or its comment is left behind in a separate chunk
+        assert!(chunks.iter().all(|chunk| {
+            chunk.chunk_type != ChunkType::Text
+                || !(chunk.text.contains('@') || chunk.text.contains("# List every"))
+        }));
+    }
+
     #[test]
     fn test_chunk_ruby() {
         let ruby_code = r#"
@@ -2083,7 +2615,7 @@ public class Calculator
         };
 
         let config = ChunkConfig::default();
-        let result = stride_large_chunk(empty_chunk.clone(), &config);
+        let result = stride_large_chunk(empty_chunk.clone(), &config, None);
 
         // Should not panic and return the original chunk
         assert!(result.is_ok());
@@ -2109,12 +2641,79 @@ public class Calculator
         };
 
         let config = ChunkConfig::default();
-        let result = stride_large_chunk(chunk, &config);
+        let result = stride_large_chunk(chunk, &config, None);
 
         // Should not panic and handle gracefully
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_estimated_tokens_match_embedded_text() {
+        let body = (1..=40)
+            .map(|i| format!("    value_{i} = compute({i})  # step {i} of the pipeline"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let code = format!(
+            "# Runs the pipeline.\n# Every step is logged.\n@traced\ndef pipeline():\n{body}\n\n# trailing note\n"
+        );
+
+        let chunks = chunk_language(&code, ParseableLanguage::Python).unwrap();
+        let pipeline = chunks
+            .iter()
+            .find(|chunk| chunk.chunk_type == ChunkType::Function)
+            .unwrap();
+        // Leading comments are embedded with the function, so they count too
+        assert!(pipeline.text.starts_with("# Runs the pipeline."));
+
+        let config = ChunkConfig {
+            max_tokens: 120,
+            stride_overlap: 10,
+            ..Default::default()
+        };
+        let strided = stride_large_chunk(pipeline.clone(), &config, None).unwrap();
+        assert!(strided.len() > 1);
+
+        for chunk in chunks.iter().chain(&strided) {
+            assert_eq!(
+                chunk.metadata.estimated_tokens,
+                estimate_tokens(&chunk.text),
+                "token estimate drifted from the embedded text of {:?}",
+                chunk.span
+            );
+            assert_eq!(chunk.metadata.byte_length, chunk.text.len());
+        }
+    }
+
+    #[test]
+    fn test_deterministic_token_estimate_pins_stride_boundaries() {
+        // 30 lines of 40 characters joined by newlines: 1229 chars, 308 tokens
+        let text = (1..=30)
+            .map(|i| format!("{:<40}", format!("let value_{} = compute({});", i, i)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let config = ChunkConfig {
+            max_tokens: 100,
+            stride_overlap: 10,
+            token_estimator: deterministic_token_estimate,
+            ..Default::default()
+        };
+
+        let chunks = chunk_text_with_config(&text, None, &config).unwrap();
+        let spans: Vec<(usize, usize)> = chunks
+            .iter()
+            .map(|chunk| (chunk.span.line_start, chunk.span.line_end))
+            .collect();
+        // Text windows are cut to the 9 lines (368 chars) that fit 100 tokens,
+        // sharing 8/40 of that with the next
+        assert_eq!(spans, vec![(1, 9), (9, 17), (17, 25), (25, 30)]);
+        for chunk in &chunks {
+            assert_eq!(
+                chunk.metadata.estimated_tokens,
+                chunk.text.chars().count().div_ceil(4)
+            );
+        }
+    }
+
     #[test]
     fn test_strided_chunk_line_calculation() {
         // Regression test for line_end calculation in strided chunks
@@ -2141,7 +2740,7 @@ public class Calculator
             ..Default::default()
         };
 
-        let result = stride_large_chunk(chunk, &config);
+        let result = stride_large_chunk(chunk, &config, None);
         if let Err(e) = &result {
             eprintln!("Stride error: {}", e);
         }
@@ -2174,6 +2773,80 @@ public class Calculator
         }
     }
 
+    /// Every chunk's line_start must be the 1-based line containing its first byte
+    fn assert_one_based_lines(source: &str, chunks: &[Chunk]) {
+        for chunk in chunks {
+            let expected = source[..chunk.span.byte_start].matches('\n').count() + 1;
+            assert_eq!(
+                chunk.span.line_start, expected,
+                "chunk at byte {} reports line {}, expected {}",
+                chunk.span.byte_start, chunk.span.line_start, expected
+            );
+            assert!(chunk.span.line_end >= chunk.span.line_start);
+        }
+    }
+
+    #[test]
+    fn test_spans_are_one_based_across_chunkers() {
+        let sources = [
+            (
+                Some(ck_core::Language::Rust),
+                "fn first() {}\n\n/// doc\nfn second() {\n    1\n}\n",
+            ),
+            (
+                Some(ck_core::Language::Python),
+                "def first():\n    pass\n\nclass C:\n    def m(self):\n        pass\n",
+            ),
+            (
+                Some(ck_core::Language::Go),
+                "package main\n\nfunc main() {\n}\n",
+            ),
+            (
+                Some(ck_core::Language::Haskell),
+                "main :: IO ()\nmain = pure ()\n",
+            ),
+            (None, "plain text file\nsecond line\n"),
+        ];
+
+        for (lang, source) in sources {
+            let chunks = chunk_text(source, lang).unwrap();
+            assert_eq!(
+                chunks.first().map(|chunk| chunk.span.line_start),
+                Some(1),
+                "first chunk of {:?} should start on line 1",
+                lang
+            );
+            assert_one_based_lines(source, &chunks);
+        }
+    }
+
+    #[test]
+    fn test_strided_spans_are_one_based() {
+        // One-character lines make stride boundaries land right after newlines
+        let text = vec!["x"; 600].join("\n");
+        let chunk = Chunk {
+            span: Span {
+                byte_start: 0,
+                byte_end: text.len(),
+                line_start: 1,
+                line_end: 600,
+            },
+            metadata: ChunkMetadata::from_text(&text),
+            text: text.clone(),
+            chunk_type: ChunkType::Text,
+            stride_info: None,
+        };
+        let config = ChunkConfig {
+            max_tokens: 100,
+            stride_overlap: 10,
+            ..Default::default()
+        };
+
+        let strides = stride_large_chunk(chunk, &config, None).unwrap();
+        assert!(strides.len() > 1);
+        assert_one_based_lines(&text, &strides);
+    }
+
     #[test]
     fn test_gap_filling_coverage() {
         // Test that all non-whitespace content gets chunked
@@ -2363,6 +3036,61 @@ function main() {
         );
     }
 
+    #[test]
+    fn test_chunk_name_is_kept_apart_from_ancestry() {
+        let rust = "mod net {\n    impl Client {\n        fn get(&self) {}\n    }\n}\n";
+        let chunks = chunk_text(rust, Some(ck_core::Language::Rust)).unwrap();
+        let method = chunks
+            .iter()
+            .find(|c| c.chunk_type == ChunkType::Method)
+            .unwrap();
+        assert_eq!(method.metadata.name.as_deref(), Some("get"));
+        assert_eq!(method.metadata.ancestry, vec!["net", "Client"]);
+        assert_eq!(method.metadata.breadcrumb.as_deref(), Some("net::Client"));
+
+        // Each segment spans the chunk it names
+        let segments: Vec<(&str, usize, usize)> = method
+            .metadata
+            .breadcrumb_segments
+            .iter()
+            .map(|s| (s.name.as_str(), s.span.line_start, s.span.line_end))
+            .collect();
+        assert_eq!(segments, vec![("net", 1, 5), ("Client", 2, 4)]);
+        for segment in &method.metadata.breadcrumb_segments {
+            assert!(
+                chunks
+                    .iter()
+                    .any(|c| c.span.byte_start == segment.span.byte_start
+                        && c.span.byte_end == segment.span.byte_end)
+            );
+        }
+    }
+
+    #[test]
+    fn test_adjacent_one_liners_are_not_merged() {
+        let haskell = "inc :: Int -> Int\ninc x = x + 1\ndec x = x - 1\ndouble x = x * 2\n";
+        let chunks = chunk_language(haskell, ParseableLanguage::Haskell).unwrap();
+        let functions: Vec<(usize, usize)> = chunks
+            .iter()
+            .filter(|c| c.chunk_type == ChunkType::Function)
+            .map(|c| (c.span.line_start, c.span.line_end))
+            .collect();
+        // The signature joins its own equation; the other functions stay apart
+        assert_eq!(functions, vec![(1, 2), (3, 3), (4, 4)]);
+
+        let rust = "fn a() {}\nfn b() {}\nconst C: u8 = 1;\n";
+        let chunks = chunk_text(rust, Some(ck_core::Language::Rust)).unwrap();
+        let spans: Vec<(usize, usize)> = chunks
+            .iter()
+            .map(|c| (c.span.line_start, c.span.line_end))
+            .collect();
+        assert!(
+            spans.contains(&(1, 1)) && spans.contains(&(2, 2)),
+            "{:?}",
+            spans
+        );
+    }
+
     #[test]
     fn test_haskell_function_chunking() {
         let haskell_code = r#"
@@ -0,0 +1,380 @@
+//! Vue and Svelte single-file components: `<script>` blocks are chunked with
+//! the JS/TS grammar, `<template>` and `<style>` blocks (and Svelte's markup
+//! outside any block) become text chunks, each under a breadcrumb naming its block.
+
+use anyhow::Result;
+use ck_core::Span;
+
+use crate::{
+    BreadcrumbSegment, Chunk, ParseDiagnostics, ParseableLanguage, chunk_generic_with_token_config,
+    chunk_language, diagnostics_for,
+};
+
+/// Which framework a component file belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ComponentKind {
+    Vue,
+    Svelte,
+}
+
+impl ComponentKind {
+    pub(crate) fn from_language(language: ck_core::Language) -> Option<Self> {
+        match language {
+            ck_core::Language::Vue => Some(ComponentKind::Vue),
+            ck_core::Language::Svelte => Some(ComponentKind::Svelte),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Template,
+    Script,
+    Style,
+}
+
+impl BlockKind {
+    const ALL: [BlockKind; 3] = [BlockKind::Template, BlockKind::Script, BlockKind::Style];
+
+    fn tag(self) -> &'static str {
+        match self {
+            BlockKind::Template => "template",
+            BlockKind::Script => "script",
+            BlockKind::Style => "style",
+        }
+    }
+}
+
+/// A top-level block, as byte offsets into the component source
+#[derive(Debug)]
+struct Block {
+    kind: BlockKind,
+    /// Where the opening tag starts and the closing tag ends
+    start: usize,
+    end: usize,
+    /// The text between the tags
+    content_start: usize,
+    content_end: usize,
+    /// The script's grammar, from its `lang` attribute
+    language: ParseableLanguage,
+}
+
+pub(crate) fn chunk_component(
+    text: &str,
+    kind: ComponentKind,
+    model_name: Option<&str>,
+) -> Result<Vec<Chunk>> {
+    let blocks = find_blocks(text);
+    if blocks.is_empty() {
+        return chunk_generic_with_token_config(text, model_name);
+    }
+
+    let mut chunks = Vec::new();
+    let mut gap_start = 0;
+    for block in &blocks {
+        chunk_gap(text, gap_start, block.start, kind, model_name, &mut chunks)?;
+        gap_start = block.end;
+
+        let segment = BreadcrumbSegment {
+            name: block.kind.tag().to_string(),
+            span: span_of(text, block.start, block.end),
+        };
+        let content = &text[block.content_start..block.content_end];
+        if content.trim().is_empty() {
+            continue;
+        }
+        let (offset, block_chunks) = match block.kind {
+            BlockKind::Script => (
+                block.content_start,
+                chunk_language(content, block.language)?,
+            ),
+            BlockKind::Template | BlockKind::Style => (
+                block.start,
+                chunk_generic_with_token_config(&text[block.start..block.end], model_name)?,
+            ),
+        };
+        chunks.extend(
+            block_chunks
+                .into_iter()
+                .map(|chunk| relocate(chunk, text, offset, Some(&segment))),
+        );
+    }
+    chunk_gap(text, gap_start, text.len(), kind, model_name, &mut chunks)?;
+
+    Ok(chunks)
+}
+
+/// Syntax errors across the component's script blocks, at their lines in the component
+pub(crate) fn component_diagnostics(text: &str) -> Result<ParseDiagnostics> {
+    let mut diagnostics = ParseDiagnostics::default();
+    for block in find_blocks(text) {
+        if block.kind != BlockKind::Script {
+            continue;
+        }
+        let script = diagnostics_for(
+            &text[block.content_start..block.content_end],
+            block.language,
+        )?;
+        let line_offset = line_at(text, block.content_start) - 1;
+        diagnostics.error_nodes += script.error_nodes;
+        diagnostics.missing_nodes += script.missing_nodes;
+        if diagnostics.first_error_line.is_none() {
+            diagnostics.first_error_line = script.first_error_line.map(|line| line + line_offset);
+        }
+    }
+    Ok(diagnostics)
+}
+
+/// Text between blocks: Svelte's markup, or Vue comments and custom blocks
+fn chunk_gap(
+    text: &str,
+    start: usize,
+    end: usize,
+    kind: ComponentKind,
+    model_name: Option<&str>,
+    chunks: &mut Vec<Chunk>,
+) -> Result<()> {
+    let gap = &text[start..end];
+    let Some(first) = gap.find(|c: char| !c.is_whitespace()) else {
+        return Ok(());
+    };
+    // Start on the line holding the first non-blank character
+    let start = start + gap[..first].rfind('\n').map_or(0, |newline| newline + 1);
+    let end = start + text[start..end].trim_end().len();
+
+    let segment = BreadcrumbSegment {
+        name: BlockKind::Template.tag().to_string(),
+        span: span_of(text, start, end),
+    };
+    let segment = (kind == ComponentKind::Svelte).then_some(&segment);
+    chunks.extend(
+        chunk_generic_with_token_config(&text[start..end], model_name)?
+            .into_iter()
+            .map(|chunk| relocate(chunk, text, start, segment)),
+    );
+    Ok(())
+}
+
+/// Move a chunk of `text[offset..]` to its place in `text`, nested under `segment`
+pub(crate) fn relocate(
+    mut chunk: Chunk,
+    text: &str,
+    offset: usize,
+    segment: Option<&BreadcrumbSegment>,
+) -> Chunk {
+    let line_offset = line_at(text, offset) - 1;
+    let shift = |span: &mut Span| {
+        span.byte_start += offset;
+        span.byte_end += offset;
+        span.line_start += line_offset;
+        span.line_end += line_offset;
+    };
+
+    shift(&mut chunk.span);
+    let metadata = &mut chunk.metadata;
+    for enclosing in &mut metadata.breadcrumb_segments {
+        shift(&mut enclosing.span);
+    }
+    if let Some(segment) = segment {
+        metadata.breadcrumb_segments.insert(0, segment.clone());
+        metadata.ancestry.insert(0, segment.name.clone());
+        metadata.breadcrumb = Some(metadata.ancestry.join("::"));
+    }
+    chunk
+}
+
+/// The component's top-level `<template>`, `<script>` and `<style>` blocks in order
+fn find_blocks(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut pos = 0;
+
+    while let Some(found) = text[pos..].find('<') {
+        let start = pos + found;
+        let rest = &text[start..];
+        if rest.starts_with("<!--") {
+            pos = rest.find("-->").map_or(text.len(), |end| start + end + 3);
+            continue;
+        }
+
+        let Some(kind) = BlockKind::ALL
+            .into_iter()
+            .find(|kind| opens_tag(rest, kind.tag()))
+        else {
+            pos = start + 1;
+            continue;
+        };
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let open_tag = &rest[..=tag_end];
+        let content_start = start + tag_end + 1;
+        if open_tag.ends_with("/>") {
+            pos = content_start;
+            continue;
+        }
+
+        let (content_end, end) = find_close(text, content_start, kind.tag());
+        let language = if kind == BlockKind::Script && is_typescript(open_tag) {
+            ParseableLanguage::TypeScript
+        } else {
+            ParseableLanguage::JavaScript
+        };
+        blocks.push(Block {
+            kind,
+            start,
+            end,
+            content_start,
+            content_end,
+            language,
+        });
+        pos = end;
+    }
+
+    blocks
+}
+
+/// Whether `rest` starts with an opening `<tag` rather than a longer tag name
+fn opens_tag(rest: &str, tag: &str) -> bool {
+    rest.strip_prefix('<')
+        .and_then(|rest| rest.strip_prefix(tag))
+        .and_then(|rest| rest.chars().next())
+        .is_some_and(|next| next == '>' || next == '/' || next.is_whitespace())
+}
+
+/// Where the content opened at `from` ends and where its closing tag ends.
+/// Templates nest (`<template v-if>` inside a template), so those count depth;
+/// an unclosed block runs to the end of the file.
+fn find_close(text: &str, from: usize, tag: &str) -> (usize, usize) {
+    let close = format!("</{}", tag);
+    let mut depth = 0;
+    let mut pos = from;
+
+    while let Some(found) = text[pos..].find('<') {
+        let at = pos + found;
+        let rest = &text[at..];
+        if rest.starts_with(&close) {
+            if depth == 0 {
+                let end = rest.find('>').map_or(text.len(), |end| at + end + 1);
+                return (at, end);
+            }
+            depth -= 1;
+        } else if tag == "template" && opens_tag(rest, tag) {
+            let self_closing = rest.find('>').is_some_and(|end| rest[..end].ends_with('/'));
+            if !self_closing {
+                depth += 1;
+            }
+        }
+        pos = at + 1;
+    }
+
+    (text.len(), text.len())
+}
+
+fn is_typescript(open_tag: &str) -> bool {
+    ["ts", "tsx", "typescript"].iter().any(|lang| {
+        open_tag.contains(&format!("lang=\"{}\"", lang))
+            || open_tag.contains(&format!("lang='{}'", lang))
+    })
+}
+
+/// 1-based line of the byte at `offset`
+pub(crate) fn line_at(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+pub(crate) fn span_of(text: &str, start: usize, end: usize) -> Span {
+    let last = text[start..end].trim_end_matches('\n').len() + start;
+    Span {
+        byte_start: start,
+        byte_end: end,
+        line_start: line_at(text, start),
+        line_end: line_at(text, last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChunkType;
+
+    const VUE: &str = r#"<template>
+  <div class="counter">
+    <template v-if="count > 0">
+      <span>{{ count }}</span>
+    </template>
+    <button @click="increment">Add</button>
+  </div>
+</template>
+
+<script setup lang="ts">
+import { ref } from 'vue'
+
+const count = ref(0)
+
+function increment(): void {
+  count.value++
+}
+</script>
+
+<style scoped>
+.counter {
+  color: red;
+}
+</style>
+"#;
+
+    fn summary(chunks: &[Chunk]) -> Vec<(ChunkType, Option<&str>, usize, usize)> {
+        chunks
+            .iter()
+            .map(|chunk| {
+                (
+                    chunk.chunk_type.clone(),
+                    chunk.metadata.breadcrumb.as_deref(),
+                    chunk.span.line_start,
+                    chunk.span.line_end,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_vue_component_splits_into_blocks() {
+        let chunks = chunk_component(VUE, ComponentKind::Vue, None).unwrap();
+        let summary = summary(&chunks);
+
+        // The nested <template v-if> stays inside the outer template
+        assert_eq!(summary[0], (ChunkType::Text, Some("template"), 1, 8));
+        let function = chunks
+            .iter()
+            .find(|chunk| chunk.chunk_type == ChunkType::Function)
+            .expect("script function chunk");
+        assert_eq!(function.metadata.breadcrumb.as_deref(), Some("script"));
+        assert_eq!(function.metadata.name.as_deref(), Some("increment"));
+        assert_eq!((function.span.line_start, function.span.line_end), (15, 17));
+        assert_eq!(
+            &VUE[function.span.byte_start..function.span.byte_end],
+            function.text
+        );
+        assert_eq!(function.metadata.breadcrumb_segments[0].span.line_start, 10);
+        assert_eq!(function.metadata.breadcrumb_segments[0].span.line_end, 18);
+
+        let style = summary.last().unwrap();
+        assert_eq!(*style, (ChunkType::Text, Some("style"), 20, 24));
+    }
+
+    #[test]
+    fn test_svelte_markup_outside_blocks_is_template() {
+        let svelte = "<script>\n  export let name;\n  function greet() {\n    alert(name);\n  }\n</script>\n\n<h1 on:click={greet}>Hello {name}!</h1>\n\n<style>\n  h1 { color: blue; }\n</style>\n";
+        let chunks = chunk_component(svelte, ComponentKind::Svelte, None).unwrap();
+        let summary = summary(&chunks);
+
+        assert!(summary.contains(&(ChunkType::Function, Some("script"), 3, 5)));
+        assert!(summary.contains(&(ChunkType::Text, Some("template"), 8, 8)));
+        assert!(summary.contains(&(ChunkType::Text, Some("style"), 10, 12)));
+
+        let diagnostics = component_diagnostics("<p/>\n<script>\nfunction (\n</script>\n").unwrap();
+        assert!(diagnostics.error_count() > 0);
+        assert_eq!(diagnostics.first_error_line, Some(3));
+    }
+}
@@ -0,0 +1,329 @@
+//! Big string literals and heredocs as text chunks of their own, so SQL, HTML
+//! or another language embedded in code can be found apart from the function
+//! holding it. Each chunk sits under its host's breadcrumb. A hint naming the
+//! embedded language (a heredoc's terminator, a template literal's tag) names
+//! the chunk, and SQL or a language ck parses is split further.
+
+use anyhow::Result;
+use ck_core::Span;
+use tree_sitter::Node;
+
+use crate::sfc::{relocate, span_of};
+use crate::{
+    BreadcrumbSegment, Chunk, ChunkMetadata, ChunkType, ParseableLanguage, chunk_language,
+    tree_sitter_language,
+};
+
+/// Shorter literals stay part of their host only
+const MIN_STRING_LINES: usize = 5;
+
+/// String literal node kinds across the supported grammars
+const STRING_KINDS: &[&str] = &[
+    "string",
+    "string_literal",
+    "raw_string_literal",
+    "interpreted_string_literal",
+    "verbatim_string_literal",
+    "template_string",
+    "multiline_string",
+    "heredoc_body",
+];
+
+/// Chunks for the literals in `text` of at least [`MIN_STRING_LINES`] lines,
+/// nested under whichever of `chunks` encloses each
+pub(crate) fn string_chunks(
+    text: &str,
+    language: ParseableLanguage,
+    chunks: &[Chunk],
+) -> Result<Vec<Chunk>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&tree_sitter_language(language)?)?;
+    let Some(tree) = parser.parse(text, None) else {
+        return Ok(Vec::new());
+    };
+
+    let mut literals = Vec::new();
+    collect_literals(tree.root_node(), &mut literals);
+
+    let mut found = Vec::new();
+    for node in literals {
+        let (start, end) = content_range(node);
+        if start >= end || text[start..end].trim().is_empty() {
+            continue;
+        }
+        let span = span_of(text, start, end);
+        if span.line_end - span.line_start + 1 < MIN_STRING_LINES {
+            continue;
+        }
+        let hint = language_hint(node, text);
+        let host = host_segments(chunks, &span);
+        found.extend(literal_chunks(text, span, hint.as_deref(), &host)?);
+    }
+    Ok(found)
+}
+
+fn collect_literals<'a>(node: Node<'a>, literals: &mut Vec<Node<'a>>) {
+    if STRING_KINDS.contains(&node.kind()) && !is_docstring(node) {
+        literals.push(node);
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_literals(child, literals);
+    }
+}
+
+/// A bare string statement, i.e. a Python docstring, which already reads as
+/// documentation of its host
+fn is_docstring(node: Node<'_>) -> bool {
+    node.parent().is_some_and(|parent| {
+        parent.kind() == "expression_statement" && parent.named_child_count() == 1
+    })
+}
+
+/// What the chunk covers: a heredoc's body without its terminator line, or
+/// the whole literal with its quotes
+fn content_range(node: Node<'_>) -> (usize, usize) {
+    let mut cursor = node.walk();
+    let content = (node.kind() == "heredoc_body")
+        .then(|| {
+            node.children(&mut cursor)
+                .find(|child| child.kind() == "heredoc_content")
+        })
+        .flatten();
+    match content {
+        // The body starts with the newline ending the `<<~SQL` line
+        Some(content) => (content.start_byte() + 1, content.end_byte()),
+        None => (node.start_byte(), node.end_byte()),
+    }
+}
+
+/// The embedded language a literal names: `SQL` in `<<~SQL ... SQL`, `html`
+/// in ``html`<p>...</p>` ``
+fn language_hint(node: Node<'_>, text: &str) -> Option<String> {
+    let tag = match node.kind() {
+        "heredoc_body" => {
+            let mut cursor = node.walk();
+            node.children(&mut cursor)
+                .find(|child| child.kind() == "heredoc_end")
+        }
+        "template_string" => node
+            .parent()
+            .filter(|parent| parent.kind() == "call_expression")
+            .and_then(|call| call.child_by_field_name("function"))
+            .filter(|function| function.kind() == "identifier"),
+        _ => None,
+    }?;
+    let hint = text[tag.byte_range()].trim().to_lowercase();
+    let is_word = !hint.is_empty() && hint.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    is_word.then_some(hint)
+}
+
+fn hint_language(hint: &str) -> Option<ParseableLanguage> {
+    let language = match hint {
+        "python" => ck_core::Language::Python,
+        "ruby" => ck_core::Language::Ruby,
+        "rust" => ck_core::Language::Rust,
+        "javascript" => ck_core::Language::JavaScript,
+        "typescript" => ck_core::Language::TypeScript,
+        "golang" => ck_core::Language::Go,
+        _ => ck_core::Language::from_extension(hint)?,
+    };
+    ParseableLanguage::try_from(language).ok()
+}
+
+/// The breadcrumb down to and including the innermost structural chunk around `span`
+fn host_segments(chunks: &[Chunk], span: &Span) -> Vec<BreadcrumbSegment> {
+    let Some(host) = chunks
+        .iter()
+        .filter(|chunk| {
+            chunk.chunk_type != ChunkType::Text
+                && chunk.span.byte_start <= span.byte_start
+                && span.byte_end <= chunk.span.byte_end
+        })
+        .min_by_key(|chunk| chunk.span.byte_end - chunk.span.byte_start)
+    else {
+        return Vec::new();
+    };
+    let mut segments = host.metadata.breadcrumb_segments.clone();
+    if let Some(name) = &host.metadata.name {
+        segments.push(BreadcrumbSegment {
+            name: name.clone(),
+            span: host.span.clone(),
+        });
+    }
+    segments
+}
+
+/// One text chunk for the literal, or its statements or structure when the
+/// hint says how to split it, each under the literal's own breadcrumb segment
+fn literal_chunks(
+    text: &str,
+    span: Span,
+    hint: Option<&str>,
+    host: &[BreadcrumbSegment],
+) -> Result<Vec<Chunk>> {
+    let literal = &text[span.byte_start..span.byte_end];
+    let name = hint.unwrap_or("string").to_string();
+
+    let parts = match hint {
+        Some("sql") => sql_statements(literal),
+        Some(hint) => match hint_language(hint) {
+            Some(language) => chunk_language(literal, language)?,
+            None => Vec::new(),
+        },
+        None => Vec::new(),
+    };
+    if parts.len() > 1 {
+        let mut outer = host.to_vec();
+        outer.push(BreadcrumbSegment {
+            name,
+            span: span.clone(),
+        });
+        return Ok(parts
+            .into_iter()
+            .map(|part| nest(relocate(part, text, span.byte_start, None), &outer))
+            .collect());
+    }
+
+    Ok(vec![Chunk {
+        text: literal.to_string(),
+        metadata: ChunkMetadata::from_context(
+            literal,
+            host.to_vec(),
+            Some(name),
+            Vec::new(),
+            Vec::new(),
+        ),
+        span,
+        chunk_type: ChunkType::Text,
+        stride_info: None,
+    }])
+}
+
+/// Put `chunk` inside the `outer` breadcrumb segments
+fn nest(mut chunk: Chunk, outer: &[BreadcrumbSegment]) -> Chunk {
+    let metadata = &mut chunk.metadata;
+    let inner = std::mem::take(&mut metadata.breadcrumb_segments);
+    metadata.breadcrumb_segments = outer.iter().cloned().chain(inner).collect();
+    metadata.ancestry = metadata
+        .breadcrumb_segments
+        .iter()
+        .map(|segment| segment.name.clone())
+        .collect();
+    metadata.breadcrumb = (!metadata.ancestry.is_empty()).then(|| metadata.ancestry.join("::"));
+    chunk
+}
+
+/// One text chunk per `;`-terminated statement, as offsets into `sql`
+fn sql_statements(sql: &str) -> Vec<Chunk> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    for line in sql.split_inclusive('\n') {
+        offset += line.len();
+        let ends_statement = line.trim_end().ends_with(';');
+        if (ends_statement || offset == sql.len()) && !sql[start..offset].trim().is_empty() {
+            // Skip the blank lines between statements
+            let leading = sql[start..offset].len() - sql[start..offset].trim_start().len();
+            let statement_start = start
+                + sql[start..start + leading]
+                    .rfind('\n')
+                    .map_or(0, |at| at + 1);
+            let statement = &sql[statement_start..offset];
+            statements.push(Chunk {
+                text: statement.to_string(),
+                span: span_of(sql, statement_start, offset),
+                chunk_type: ChunkType::Text,
+                metadata: ChunkMetadata::from_text(statement),
+                stride_info: None,
+            });
+        }
+        if ends_statement {
+            start = offset;
+        }
+    }
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChunkConfig, chunk_text_with_config};
+
+    fn chunk_strings(text: &str, language: ck_core::Language) -> Vec<Chunk> {
+        let config = ChunkConfig {
+            string_chunks: true,
+            ..ChunkConfig::default()
+        };
+        chunk_text_with_config(text, Some(language), &config)
+            .unwrap()
+            .into_iter()
+            .filter(|chunk| {
+                chunk.chunk_type == ChunkType::Text && chunk.metadata.breadcrumb.is_some()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn big_literals_become_chunks_under_their_host() {
+        let source = r#####"impl Repo {
+    fn find_users(&self) -> String {
+        let short = "SELECT 1";
+        let query = r#"
+            SELECT id, name
+            FROM users
+            WHERE active = true
+            ORDER BY name
+        "#;
+        query.to_string()
+    }
+}
+"#####;
+        let strings = chunk_strings(source, ck_core::Language::Rust);
+        assert_eq!(strings.len(), 1);
+        let query = &strings[0];
+        assert_eq!(
+            query.metadata.breadcrumb.as_deref(),
+            Some("Repo::find_users")
+        );
+        assert_eq!(query.metadata.name.as_deref(), Some("string"));
+        assert_eq!((query.span.line_start, query.span.line_end), (4, 9));
+        assert!(query.text.contains("FROM users"));
+
+        // Off by default
+        let plain = crate::chunk_text(source, Some(ck_core::Language::Rust)).unwrap();
+        assert!(
+            plain
+                .iter()
+                .all(|chunk| chunk.metadata.name.as_deref() != Some("string"))
+        );
+    }
+
+    #[test]
+    fn sql_heredocs_split_into_statements() {
+        let source = "class Report\n  def build\n    db.execute(<<~SQL)\n      DELETE FROM staging;\n\n      INSERT INTO staging\n      SELECT * FROM events\n      WHERE day = today();\n    SQL\n  end\nend\n";
+        let statements = chunk_strings(source, ck_core::Language::Ruby);
+        assert_eq!(statements.len(), 2);
+        assert!(statements.iter().all(|statement| {
+            statement.metadata.breadcrumb.as_deref() == Some("Report::build::sql")
+        }));
+        assert_eq!(statements[0].text.trim(), "DELETE FROM staging;");
+        assert_eq!(
+            (statements[1].span.line_start, statements[1].span.line_end),
+            (6, 8)
+        );
+        assert!(
+            statements[1]
+                .text
+                .trim_start()
+                .starts_with("INSERT INTO staging")
+        );
+    }
+
+    #[test]
+    fn docstrings_stay_with_their_function() {
+        let source = "def parse():\n    \"\"\"\n    Parse the input.\n\n    Returns the tree.\n    \"\"\"\n    return None\n";
+        assert!(chunk_strings(source, ck_core::Language::Python).is_empty());
+    }
+}
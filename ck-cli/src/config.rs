@@ -0,0 +1,249 @@
+use anyhow::Result;
+use ck_core::SearchMode;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use toml::Spanned;
+
+/// Name of the defaults file looked up in the home and current directories
+pub const CONFIG_FILE_NAME: &str = "ck.toml";
+
+/// Search defaults read from `ck.toml`. Every field is optional; a flag given on
+/// the command line always takes precedence.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    pub mode: Option<SearchMode>,
+    pub types: Option<Vec<String>>,
+    pub min_score: Option<f32>,
+    pub limit: Option<usize>,
+    pub model: Option<String>,
+    pub test_patterns: Option<Vec<String>>,
+}
+
+impl Config {
+    /// Read `~/ck.toml` and then `./ck.toml`, with keys in the current
+    /// directory's file overriding the home one. Missing files are fine.
+    pub fn load() -> Result<Self> {
+        let mut config = Config::default();
+        for path in config_paths() {
+            if !path.is_file() {
+                continue;
+            }
+            let text = std::fs::read_to_string(&path)
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+            config = config.overridden_by(parse_config(&text, &path)?);
+        }
+        Ok(config)
+    }
+
+    fn overridden_by(self, other: Config) -> Config {
+        Config {
+            mode: other.mode.or(self.mode),
+            types: other.types.or(self.types),
+            min_score: other.min_score.or(self.min_score),
+            limit: other.limit.or(self.limit),
+            model: other.model.or(self.model),
+            test_patterns: other.test_patterns.or(self.test_patterns),
+        }
+    }
+}
+
+fn config_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(home) = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+        paths.push(PathBuf::from(home).join(CONFIG_FILE_NAME));
+    }
+    let local = PathBuf::from(CONFIG_FILE_NAME);
+    // Running from the home directory must not read the same file twice
+    let same_file = match (
+        paths.first().and_then(|home| home.canonicalize().ok()),
+        local.canonicalize().ok(),
+    ) {
+        (Some(home), Some(local)) => home == local,
+        _ => false,
+    };
+    if !same_file {
+        paths.push(local);
+    }
+    paths
+}
+
+/// `ck.toml` as written, before the values are checked against ck's vocabulary
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    mode: Option<Spanned<String>>,
+    #[serde(alias = "type")]
+    types: Option<Spanned<OneOrMany>>,
+    #[serde(alias = "threshold")]
+    min_score: Option<f32>,
+    #[serde(alias = "topk")]
+    limit: Option<usize>,
+    model: Option<String>,
+    test_patterns: Option<Vec<String>>,
+}
+
+/// `types` may be a single chunk type or an array of them
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Parse `ck.toml`. Unknown keys and values outside the known vocabulary are
+/// errors, so typos don't silently fall back to defaults.
+pub fn parse_config(text: &str, origin: &Path) -> Result<Config> {
+    let raw: RawConfig = toml::from_str(text)
+        .map_err(|e| anyhow::anyhow!("{}: {}", origin.display(), e.to_string().trim_end()))?;
+    let at = |span: std::ops::Range<usize>, message: String| {
+        let line_number = text[..span.start].matches('\n').count() + 1;
+        anyhow::anyhow!("{}:{}: {}", origin.display(), line_number, message)
+    };
+
+    let mode = match raw.mode {
+        Some(mode) => {
+            let span = mode.span();
+            Some(parse_mode(mode.get_ref()).map_err(|message| at(span, message))?)
+        }
+        None => None,
+    };
+    let types = match raw.types {
+        Some(types) => {
+            let span = types.span();
+            let types = match types.into_inner() {
+                OneOrMany::One(single) => vec![single],
+                OneOrMany::Many(items) => items,
+            };
+            if let Some(kind) = types
+                .iter()
+                .find(|kind| !ck_core::CHUNK_TYPE_NAMES.contains(&kind.as_str()))
+            {
+                return Err(at(
+                    span,
+                    format!(
+                        "unknown chunk type '{}' (expected one of: {})",
+                        kind,
+                        ck_core::CHUNK_TYPE_NAMES.join(", ")
+                    ),
+                ));
+            }
+            Some(types)
+        }
+        None => None,
+    };
+
+    Ok(Config {
+        mode,
+        types,
+        min_score: raw.min_score,
+        limit: raw.limit,
+        model: raw.model,
+        test_patterns: raw.test_patterns,
+    })
+}
+
+fn parse_mode(mode: &str) -> std::result::Result<SearchMode, String> {
+    match mode {
+        "regex" => Ok(SearchMode::Regex),
+        "lex" | "lexical" => Ok(SearchMode::Lexical),
+        "sem" | "semantic" => Ok(SearchMode::Semantic),
+        "hybrid" => Ok(SearchMode::Hybrid),
+        "structural" => Ok(SearchMode::Structural),
+        other => Err(format!(
+            "unknown mode '{}' (expected one of: regex, lex, sem, hybrid, structural)",
+            other
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Config> {
+        parse_config(text, Path::new("ck.toml"))
+    }
+
+    #[test]
+    fn test_parse_config_reads_all_keys() {
+        let config = parse(
+            r#"
+# team defaults
+mode = "sem"
+types = ["function", "method",]  # trailing comma is fine
+min_score = 0.3
+limit = 50
+model = "nomic-v1.5"
+test_patterns = ["*_spec.rb", "spec/**"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                mode: Some(SearchMode::Semantic),
+                types: Some(vec!["function".to_string(), "method".to_string()]),
+                min_score: Some(0.3),
+                limit: Some(50),
+                model: Some("nomic-v1.5".to_string()),
+                test_patterns: Some(vec!["*_spec.rb".to_string(), "spec/**".to_string()]),
+            }
+        );
+        assert_eq!(parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_parse_config_rejects_typos() {
+        let err = parse("mode = \"semantc\"").unwrap_err().to_string();
+        assert!(err.contains("ck.toml:1"), "{}", err);
+        assert!(err.contains("unknown mode 'semantc'"), "{}", err);
+
+        let err = parse("\ntypes = [\"function\", \"fucntion\"]")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("ck.toml:2"), "{}", err);
+        assert!(err.contains("unknown chunk type 'fucntion'"), "{}", err);
+
+        let err = parse("min_scroe = 0.5").unwrap_err().to_string();
+        assert!(err.contains("ck.toml"), "{}", err);
+        assert!(err.contains("unknown field `min_scroe`"), "{}", err);
+
+        assert!(parse("limit = \"50\"").is_err());
+        assert!(parse("limit = -1").is_err());
+        assert!(parse("model = nomic").is_err());
+        assert!(parse("[search]").is_err());
+    }
+
+    #[test]
+    fn test_parse_config_accepts_full_toml_syntax() {
+        let config = parse(
+            r#"
+model = 'nomic-v1.5'
+types = "function"
+test_patterns = [
+    "**/*.{test,spec}.ts",  # commas inside a brace glob
+    'tests/**',
+]
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.model.as_deref(), Some("nomic-v1.5"));
+        assert_eq!(config.types, Some(vec!["function".to_string()]));
+        assert_eq!(
+            config.test_patterns,
+            Some(vec![
+                "**/*.{test,spec}.ts".to_string(),
+                "tests/**".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_later_config_overrides_earlier() {
+        let home = parse("mode = \"lex\"\nlimit = 5").unwrap();
+        let local = parse("limit = 20").unwrap();
+        let merged = home.overridden_by(local);
+        assert_eq!(merged.mode, Some(SearchMode::Lexical));
+        assert_eq!(merged.limit, Some(20));
+    }
+}
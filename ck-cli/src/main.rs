@@ -9,8 +9,10 @@ use owo_colors::{OwoColorize, Rgb};
 use regex::RegexBuilder;
 use std::path::{Path, PathBuf};
 
+mod config;
 mod mcp;
 mod mcp_server;
+mod pager;
 mod path_utils;
 mod progress;
 // TUI is now in its own crate: ck-tui
@@ -37,34 +39,83 @@ QUICK START EXAMPLES:
     ck --sem "database connection"     # Find DB-related code  
     ck --sem --limit 5 "authentication"    # Limit to top 5 results
     ck --sem --threshold 0.8 "auth"   # Higher precision filtering
+    echo "parse the config file" | ck --sem - src/  # Read the query from stdin
 
   Lexical search (BM25 full-text search):
     ck --lex "user authentication"    # Full-text search with ranking
     ck --lex "http client request"    # Better than regex for phrases
+    ck --lex --exact-tokens "getUserName"  # Don't split identifiers into words
 
   Hybrid search (combines regex + semantic):  
     ck --hybrid "async function"      # Best of both worlds
     ck --hybrid "error" --limit 10    # Top 10 most relevant results (--limit is alias for --topk)
     ck --hybrid "bug" --threshold 0.02 # Only results with RRF score >= 0.02
     ck --sem "auth" --scores           # Show similarity scores in output
+    ck --sem "parse config" --highlight-terms  # Underline words shared with the query
+
+  Find similar code (search by example):
+    ck --like-file src/parser.rs       # Chunks similar to any chunk of parser.rs
+    ck --like-snippet "fn retry(n: u32)" src/  # Chunks similar to a pasted snippet
+    pbpaste | ck --like-snippet -      # Read the snippet from stdin
+    ck --sem "retry" --scope net::http  # Only chunks nested under net::http
+    ck --sem "token" --scope auth --scope billing  # Chunks under either module
+    ck --sem "retry" -g 'src/**/*.rs' -g '!**/tests/**'  # Only these paths, no reindex
+    ck --sem "why we cache" --docs-only  # Only comments and docs (--code-only skips them)
+    ck --sem "parse args" --type function,method  # Only functions and methods
+    ck --no-semantic parse_cfg src/    # Fuzzy symbol lookup by chunk name, no model or index
+    ck --references parse_config src/  # Chunks using the identifier, most uses first
+    ck --sem "retry logic" --no-tests src/  # Leave test files and test functions out
+    ck --duplicates .                  # Top 20 near-duplicate chunk pairs (similarity ≥0.95)
+    ck --duplicates --clusters --threshold 0.9 src/  # Group copy-paste into clusters
+    ck --diff main...HEAD "error handling"  # Only chunks changed on this branch (no indexing)
+    ck --at v1.2.0 --sem "retry logic" src/  # Search the code as it was at a tag or commit
 
   Index management:
     ck --status .                     # Check index status
     ck --status-verbose .              # Detailed index statistics
+    ck --warmup .                      # Load the index's model once and time it
+    ck --doctor .                      # Find corrupt sidecars, missing embeddings, stray temp files
+    ck --doctor --fix .                # Repair them; affected files re-index on the next search
+    ck --explain src/weird.ext         # Why a file did or didn't get structural chunks
+    ck --dump-chunks --stable src/lib.rs  # Chunk boundaries as sorted lines, for diffing
+    ck --list --type function src/     # Every function in file order, no query
+    ck --export-chunk src/lib.rs:42    # Chunk at line 42 between ck:begin/ck:end anchors
+    ck --split src/lib.rs --out parts/   # One file per function/class, e.g. parts/Parser.parse.rs
+    ck --vector src/lib.rs:42          # Stored embedding of that chunk as JSON (--vector-format txt for numpy)
+    ck --embed "retry with backoff"    # The query's embedding as JSON, without searching
+    pbpaste | ck --apply               # Write the edited chunk back if the original is unchanged
+    ck --top-chunks --limit 20 .       # Biggest chunks by estimated tokens (--by lines|bytes)
     ck --clean-orphans .               # Clean up orphaned files
+    ck --prune --dry-run .             # Index entries of deleted files that --prune would remove
     ck --clean .                       # Remove entire index
     ck --switch-model nomic-v1.5       # Clean + rebuild with a different embedding model
+    ck --index --context-prefix .      # Embed each chunk with its file path and breadcrumb
+    ck --index --text-window 60:12 .   # 60-line windows for files without a grammar
     ck --add file.rs                   # Add single file to index
     ck --index .                       # Optional: pre-build before CI runs
+    ck --index packages/api            # One index per package; searching . then merges them
+    ck --index --push-index s3://team-ck/myrepo .  # Build once in CI and publish it
+    ck --remote s3://team-ck/myrepo --sem "auth" .  # Fetch the published index, then search
 
   JSON output for tools/scripts:
     ck --json --sem "bug fix" src/    # Traditional JSON (single array)
     ck --json --limit 5 "TODO"       # Limit results (--limit alias for --topk)
     
+  Editor integration:
+    ck --vimgrep --sem "auth" src/    # path:line:1: breadcrumb (score), for :cexpr / quickfix
+    ck --compact --sem "auth" src/    # score  path:line  breadcrumb  — first line, one per result
+    ck --sem "retry" --merge-adjacent src/  # One result for neighbouring matched chunks
+    ck --sem "retry" --blame src/     # Author and date of the last commit on each result
+    ck -n "unwrap()" --show-enclosing src/  # Prefix previews with their enclosing signatures
+    ck --sem "retry" --recent 2d .     # Only files modified in the last two days
+    ck --sem "retry" --recent 1w --recent-mode boost .  # Rank this week's files first
+    ck --markdown --markdown-rows 5 --sem "auth" src/  # Markdown table for PR comments
+
   JSONL output for AI agents (recommended):
     ck --jsonl "auth" --no-snippet    # Streaming, memory-efficient format
     ck --jsonl --sem "error" src/     # Perfect for LLM/agent consumption
     ck --jsonl --topk 5 --threshold 0.8 "func"  # High-confidence agent results
+    ck --sem --queries-file eval.txt src/  # One NDJSON line of hits per query, model loaded once
     # Why JSONL? Streaming, error-resilient, standard in AI pipelines
 
   Advanced grep features:
@@ -76,6 +127,8 @@ QUICK START EXAMPLES:
   Model and embedding options:
     ck --index --model nomic-v1.5      # Index with higher-quality model (8k context)
     ck --index --model jina-code       # Index with code-specialized model
+    ck --index --model nomic-v1.5 --dims 256  # Truncate embeddings to 256 dims (matryoshka)
+    ck --index --model openai:text-embedding-3-small  # Remote endpoint (CK_EMBEDDINGS_BASE_URL, CK_EMBEDDINGS_API_KEY)
     ck --sem "auth" --rerank           # Enable reranking for better relevance
     ck --sem "login" --rerank-model bge # Use specific reranking model
 
@@ -96,6 +149,21 @@ RESULT FILTERING:
                       (0.0-1.0 semantic/lexical, 0.01-0.05 hybrid RRF)
   --scores          : Show scores in output [0.950] file:line:match
 
+CONFIG FILE:
+  Defaults for mode, type, min_score, limit and model are read from ck.toml in
+  the home directory, then ck.toml in the current directory (which wins).
+  Flags on the command line always override both:
+    mode = "sem"                 # regex, lex, sem or hybrid
+    types = ["function", "method"]
+    min_score = 0.3
+    limit = 50
+    model = "nomic-v1.5"
+
+EXIT STATUS (same as grep):
+  0 : At least one match was found
+  1 : No matches, including when --threshold/--min-score filtered out every result
+  2 : An error occurred (bad arguments, unreadable path, invalid regex, index failure)
+
 The semantic search understands meaning - searching for "error handling" 
 will find try/catch blocks, error returns, exception handling, etc.
 "#)]
@@ -129,9 +197,29 @@ struct Cli {
     )]
     files_without_matches: bool,
 
-    #[arg(short = 'i', long = "ignore-case", help = "Case insensitive search")]
+    #[arg(
+        short = 'i',
+        long = "ignore-case",
+        help = "Case insensitive search",
+        overrides_with = "case_sensitive"
+    )]
     ignore_case: bool,
 
+    #[arg(
+        short = 's',
+        long = "case-sensitive",
+        help = "Case sensitive search, even for an all-lowercase pattern (default: smart case, ignoring case unless the pattern has an uppercase letter)",
+        overrides_with = "ignore_case"
+    )]
+    case_sensitive: bool,
+
+    #[arg(
+        short = 'U',
+        long = "multiline",
+        help = "Match the regex against the whole file so it can span lines (e.g. 'fn\\s+foo[\\s\\S]*?\\{'); ^ and $ still match at line boundaries"
+    )]
+    multiline: bool,
+
     #[arg(short = 'w', long = "word-regexp", help = "Match whole words only")]
     word_regexp: bool,
 
@@ -154,7 +242,7 @@ struct Cli {
         short = 'C',
         long = "context",
         value_name = "NUM",
-        help = "Show NUM lines of context before and after"
+        help = "Show NUM lines of context before and after. With --tui, sets the preview context for this run"
     )]
     context: Option<usize>,
 
@@ -162,7 +250,7 @@ struct Cli {
         short = 'A',
         long = "after-context",
         value_name = "NUM",
-        help = "Show NUM lines after match"
+        help = "Show NUM lines after match (also the --tui preview)"
     )]
     after_context: Option<usize>,
 
@@ -170,7 +258,7 @@ struct Cli {
         short = 'B',
         long = "before-context",
         value_name = "NUM",
-        help = "Show NUM lines before match"
+        help = "Show NUM lines before match (also the --tui preview)"
     )]
     before_context: Option<usize>,
 
@@ -195,6 +283,27 @@ struct Cli {
     #[arg(long = "regex", help = "Regex search mode (default, grep-compatible)")]
     regex: bool,
 
+    #[arg(
+        long = "no-semantic",
+        help = "Structural search - fuzzy-match the query against function, class and method names and their breadcrumbs; chunks files live, no model or index needed (default: top 10)",
+        conflicts_with_all = ["semantic", "lexical", "hybrid", "regex"]
+    )]
+    no_semantic: bool,
+
+    #[arg(
+        long = "list",
+        help = "List every named chunk (function, class, method...) under the given paths without a query, in path and line order unless --sort says otherwise; narrow it with --type, --scope, --docs-only/--code-only and --glob. Chunks files live like --no-semantic, with no result limit unless --topk sets one",
+        conflicts_with_all = ["semantic", "lexical", "hybrid", "regex", "no_semantic", "like_file", "like_snippet", "diff", "query_stdin"]
+    )]
+    list: bool,
+
+    #[arg(
+        long = "references",
+        help = "Find the chunks using the pattern as an identifier - a whole word, case-sensitive - ranked by how often they use it, leaving out the chunks that define it. An approximation of callers and references without a language server; chunks files live like --no-semantic (default: top 10)",
+        conflicts_with_all = ["semantic", "lexical", "hybrid", "regex", "no_semantic", "list", "like_file", "like_snippet", "diff"]
+    )]
+    references: bool,
+
     #[arg(
         long = "topk",
         alias = "limit",
@@ -205,11 +314,114 @@ struct Cli {
 
     #[arg(
         long = "threshold",
+        visible_alias = "min-score",
         value_name = "SCORE",
         help = "Minimum score threshold (0.0-1.0 for semantic/lexical, 0.01-0.05 for hybrid RRF) [default: 0.6 for semantic search]"
     )]
     threshold: Option<f32>,
 
+    #[arg(
+        long = "sort",
+        value_name = "ORDER",
+        value_parser = clap::builder::PossibleValuesParser::new(ck_core::RESULT_SORT_NAMES),
+        help = "Order results by: matches (most matching lines in the chunk first), score, path or lines (longest chunk first); ties fall back to score. Also applies to --tui"
+    )]
+    sort: Option<String>,
+
+    #[arg(
+        long = "metric",
+        value_name = "METRIC",
+        value_parser = clap::builder::PossibleValuesParser::new(ck_core::SIMILARITY_METRIC_NAMES),
+        help = "Similarity metric for semantic and hybrid search: cosine, dot (for models trained on unnormalized dot products) or euclidean. With --index or --import-embeddings, records the model's intended metric, which searches then use by default; a different --metric at search time warns"
+    )]
+    metric: Option<String>,
+
+    #[arg(
+        long = "highlight-terms",
+        help = "In semantic and hybrid results, underline the words the chunk shares with the query (compared lowercased and stemmed, within identifiers too), showing why a hit was returned. JSON and JSONL output list them as match_ranges"
+    )]
+    highlight_terms: bool,
+
+    #[arg(
+        long = "merge-adjacent",
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "2",
+        help = "Merge results in the same file whose spans are at most N lines apart (--merge-adjacent=N, default 2) into one, scored by its best member, and show the combined range with its chunk outline. JSON output reports the combined span"
+    )]
+    merge_adjacent: Option<usize>,
+
+    #[arg(
+        long = "blame",
+        help = "Annotate each result with the author and date of the most recent commit touching its lines, from git blame (each file is blamed once). Results outside a git repository or in untracked files are shown without it. JSON and JSONL output include it as blame"
+    )]
+    blame: bool,
+
+    #[arg(
+        long = "show-enclosing",
+        help = "Start each preview with the first line of every chunk enclosing the result (the class, impl or module a method sits in), outermost first and marked ▸ with its line number, so buried matches show where they are. Lines the preview already shows aren't repeated"
+    )]
+    show_enclosing: bool,
+
+    #[arg(
+        long = "recent",
+        value_name = "DURATION",
+        value_parser = ck_core::parse_duration,
+        help = "Only search files modified (by mtime) within DURATION of now, e.g. 30m, 2h, 3d or 1w; with --recent-mode boost, rank them first instead"
+    )]
+    recent: Option<std::time::Duration>,
+
+    #[arg(
+        long = "recent-mode",
+        value_name = "MODE",
+        value_parser = clap::builder::PossibleValuesParser::new(ck_core::RECENT_MODE_NAMES),
+        default_value = "filter",
+        requires = "recent",
+        help = "What --recent does with older files: filter leaves them out, boost keeps them but multiplies the scores of recent files by 1.5 and re-ranks"
+    )]
+    recent_mode: String,
+
+    #[arg(
+        long = "no-tests",
+        conflicts_with = "tests_only",
+        help = "Leave out test code: files named like tests (foo_test.go, test_foo.py, foo.spec.ts, anything under tests/ or __tests__/) and, in chunk-based searches, chunks marked as tests by an attribute or decorator such as #[test], or named test_*, Test* or tests or sitting inside one"
+    )]
+    no_tests: bool,
+
+    #[arg(
+        long = "tests-only",
+        help = "Search only test code, the files and chunks --no-tests leaves out; regex and lexical searches keep whole test files"
+    )]
+    tests_only: bool,
+
+    #[arg(
+        long = "test-pattern",
+        value_name = "GLOB",
+        help = "Glob naming test files for --no-tests and --tests-only, matched against the file name and the path relative to the search path; replaces the built-in patterns (repeatable; default from test_patterns in ck.toml)"
+    )]
+    test_patterns: Vec<String>,
+
+    #[arg(
+        long = "exact-tokens",
+        help = "Keep identifiers whole when matching words: lexical search (--lex) and --highlight-terms otherwise split getUserName, user_name and user-name into their words, so a query for \"user name\" finds them"
+    )]
+    exact_tokens: bool,
+
+    #[arg(
+        long = "pager",
+        help = "Page the output through $PAGER (default less, run with LESS=FRX unless $LESS is set) with colors kept. On by default for searches when stdout is a terminal; PAGER=cat or an empty PAGER turns it off. Only searches and listings are paged, never commands like --index or --status",
+        overrides_with = "no_pager"
+    )]
+    pager: bool,
+
+    #[arg(
+        long = "no-pager",
+        help = "Print straight to stdout, even on a terminal",
+        overrides_with = "pager"
+    )]
+    no_pager: bool,
+
     #[arg(long = "scores", help = "Show similarity scores in output")]
     show_scores: bool,
 
@@ -222,6 +434,36 @@ struct Cli {
     #[arg(long = "jsonl", help = "Output results as JSONL for agent workflows")]
     jsonl: bool,
 
+    #[arg(
+        long = "vimgrep",
+        help = "Output one 'path:line:1: breadcrumb (score)' line per match for editor quickfix lists",
+        conflicts_with_all = ["json", "json_v1", "jsonl", "files_with_matches", "files_without_matches"]
+    )]
+    vimgrep: bool,
+
+    #[arg(
+        long = "markdown",
+        help = "Output a Markdown table (File, Symbol, Lines, Score, Preview) for PR comments",
+        conflicts_with_all = ["json", "json_v1", "jsonl", "vimgrep", "files_with_matches", "files_without_matches"]
+    )]
+    markdown: bool,
+
+    #[arg(
+        long = "compact",
+        help = "Output one 'score  path:line  breadcrumb  — first line' line per result for scanning many results; the line is the chunk's first that isn't blank or only braces",
+        conflicts_with_all = ["json", "json_v1", "jsonl", "vimgrep", "markdown", "files_with_matches", "files_without_matches"]
+    )]
+    compact: bool,
+
+    #[arg(
+        long = "markdown-rows",
+        value_name = "N",
+        default_value_t = 10,
+        help = "Rows shown by --markdown before the remaining results are summarized",
+        requires = "markdown"
+    )]
+    markdown_rows: usize,
+
     #[arg(long = "no-snippet", help = "Exclude code snippets from JSONL output")]
     no_snippet: bool,
 
@@ -235,6 +477,14 @@ struct Cli {
     )]
     exclude: Vec<String>,
 
+    #[arg(
+        short = 'g',
+        long = "glob",
+        value_name = "GLOB",
+        help = "Only search paths matching this gitignore-style glob, relative to the search path; prefix with ! to exclude (repeatable, later globs win)"
+    )]
+    globs: Vec<String>,
+
     #[arg(
         long = "no-default-excludes",
         help = "Disable default directory exclusions (like .git, node_modules, etc.)"
@@ -247,6 +497,18 @@ struct Cli {
     #[arg(long = "no-ckignore", help = "Don't respect .ckignore file")]
     no_ckignore: bool,
 
+    #[arg(
+        long = "follow-symlinks",
+        help = "Follow symlinked directories when indexing and searching (each real directory is visited once)"
+    )]
+    follow_symlinks: bool,
+
+    #[arg(
+        long = "archives",
+        help = "Also index and search the text files inside .zip/.jar archives (shown as lib.jar!/path/in/archive)"
+    )]
+    archives: bool,
+
     #[arg(
         long = "print-default-ckignore",
         help = "Print the default .ckignore content that ck generates and exit"
@@ -273,12 +535,78 @@ struct Cli {
     )]
     index: bool,
 
+    #[arg(
+        long = "context-prefix",
+        value_name = "BOOL",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        help = "With --index or --switch-model, start each chunk's embedding input with a comment naming its file and breadcrumb (e.g. // net/http/client.rs — Client::new) so look-alike functions in different modules embed apart. Remembered by the index; changing it re-embeds everything. Displayed text is unchanged"
+    )]
+    context_prefix: Option<bool>,
+
+    #[arg(
+        long = "string-chunks",
+        value_name = "BOOL",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        help = "With --index or --switch-model, also chunk string literals and heredocs of five or more lines on their own, under their function's breadcrumb, so embedded SQL or HTML is searchable. SQL heredocs and tagged templates are split per statement. Remembered by the index; changing it re-indexes everything"
+    )]
+    string_chunks: Option<bool>,
+
+    #[arg(
+        long = "text-window",
+        value_name = "LINES[:OVERLAP]",
+        help = "With --index or --switch-model, cut files without structural chunking (config files, languages without a grammar) into windows of LINES lines, each sharing OVERLAP lines with the one before (default 40:8; OVERLAP defaults to a fifth of LINES). Windows are cut short to fit the model's token budget. Remembered by the index; changing it re-indexes everything"
+    )]
+    text_window: Option<ck_chunk::TextWindow>,
+
+    #[arg(
+        long = "dims",
+        value_name = "N",
+        help = "With --index or --switch-model, keep only the first N dimensions of every embedding (matryoshka truncation), for models trained for it such as nomic-v1.5 (512, 256, 128 or 64). Shrinks the index and speeds up scoring for a little quality; queries are truncated to match. Remembered by the index; changing it re-indexes everything, and the model's full size turns it off"
+    )]
+    dims: Option<usize>,
+
+    #[arg(
+        long = "import-embeddings",
+        value_name = "FILE",
+        help = "Build the index from pre-computed embeddings instead of embedding files: JSON Lines of {path, line_start, line_end, chunk_type, breadcrumb, vector}, '-' for stdin. --model names the model that made them, which queries are embedded with; searches leave an imported index as it is"
+    )]
+    import_embeddings: Option<PathBuf>,
+
+    #[arg(
+        long = "push-index",
+        value_name = "LOCATION",
+        help = "Publish the index at PATH (default .) for others to search: s3://bucket/prefix for S3-compatible storage (credentials, region and AWS_ENDPOINT_URL from the AWS_* variables) or a shared directory. Only changed files are uploaded, and the new index replaces the old one in a single step. With --index, indexes first"
+    )]
+    push_index: Option<String>,
+
+    #[arg(
+        long = "remote",
+        value_name = "LOCATION",
+        help = "Bring the index last published to LOCATION with --push-index into PATH's .ck (default .) before searching, fetching only the files that changed since the last pull. Without a query, just updates the index"
+    )]
+    remote: Option<String>,
+
     #[arg(long = "clean", help = "Clean up search index")]
     clean: bool,
 
     #[arg(long = "clean-orphans", help = "Clean only orphaned index files")]
     clean_orphans: bool,
 
+    #[arg(
+        long = "prune",
+        help = "Remove the index entries of files that no longer exist and report how many went. Only the paths the index references are checked, so it's quicker than --clean-orphans on a large tree; the lexical index is dropped to be rebuilt without them"
+    )]
+    prune: bool,
+
+    #[arg(
+        long = "dry-run",
+        requires = "prune",
+        help = "With --prune, list the deleted files whose entries would go without changing the index"
+    )]
+    dry_run: bool,
+
     #[arg(
         long = "switch-model",
         value_name = "NAME",
@@ -287,10 +615,13 @@ struct Cli {
             "index",
             "clean",
             "clean_orphans",
+            "prune",
             "status",
             "status_verbose",
+            "doctor",
             "add",
-            "inspect"
+            "inspect",
+            "explain"
         ],
         conflicts_with = "model"
     )]
@@ -312,23 +643,163 @@ struct Cli {
     #[arg(long = "status-verbose", help = "Show detailed index statistics")]
     status_verbose: bool,
 
+    #[arg(
+        long = "warmup",
+        help = "Load the embedding model (the index's, or --model) and run one inference, reporting how long each took"
+    )]
+    warmup: bool,
+
+    #[arg(
+        long = "doctor",
+        help = "Check the index for corrupt sidecars, missing embeddings, dimension mismatches and leftover temporary files; exits 2 while problems remain"
+    )]
+    doctor: bool,
+
+    #[arg(
+        long = "fix",
+        requires = "doctor",
+        help = "With --doctor, repair what it finds; affected files are re-indexed on the next search"
+    )]
+    fix: bool,
+
     #[arg(
         long = "inspect",
         help = "Show detailed metadata for a specific file (chunks, embeddings, tree-sitter parsing info)"
     )]
     inspect: bool,
 
+    #[arg(
+        long = "explain",
+        help = "Explain how a file is chunked: detected language, whether tree-sitter structure is supported, and parse errors"
+    )]
+    explain: bool,
+
     #[arg(
         long = "dump-chunks",
         help = "Visualize chunk boundaries for a file using the same rendering as TUI chunk mode"
     )]
     dump_chunks: bool,
 
+    #[arg(
+        long = "gutter",
+        value_name = "MODE",
+        value_parser = ["lines", "bytes", "both"],
+        default_value = "lines",
+        help = "With --dump-chunks, show line numbers, each line's byte range [start..end) in the file, or both",
+        requires = "dump_chunks"
+    )]
+    gutter: String,
+
+    #[arg(
+        long = "stable",
+        requires = "dump_chunks",
+        conflicts_with = "gutter",
+        help = "With --dump-chunks, print one 'START-END TYPE PATH' line per chunk, sorted by position, with no token counts or byte lengths, so the output diffs cleanly across commits"
+    )]
+    stable: bool,
+
+    #[arg(
+        long = "legend",
+        help = "Explain the chunk view's bracket glyphs (as drawn by --dump-chunks and the TUI) and its match highlighting"
+    )]
+    legend: bool,
+
+    #[arg(
+        long = "export-chunk",
+        value_name = "FILE:LINE",
+        help = "Print the innermost chunk containing LINE wrapped in ck:begin/ck:end anchor comments (chunk id and span), for pasting to an LLM and applying the edit back"
+    )]
+    export_chunk: Option<String>,
+
+    #[arg(
+        long = "split",
+        value_name = "FILE",
+        help = "Write each function, class and method of FILE to its own file in --out, named by its breadcrumb with FILE's extension; text between them goes to _misc",
+        requires = "out"
+    )]
+    split: Option<PathBuf>,
+
+    #[arg(
+        long = "out",
+        value_name = "DIR",
+        help = "Directory --split writes into (created if missing)",
+        requires = "split"
+    )]
+    out: Option<PathBuf>,
+
+    #[arg(
+        long = "vector",
+        value_name = "FILE:LINE",
+        help = "Print the stored embedding of the innermost indexed chunk containing LINE, with its model name and dimensions (reads the existing index)"
+    )]
+    vector: Option<String>,
+
+    #[arg(
+        long = "vector-format",
+        value_name = "FORMAT",
+        value_parser = ["json", "txt"],
+        default_value = "json",
+        help = "With --vector, print JSON or plain text loadable with numpy.loadtxt (one row of floats after '#' header lines)",
+        requires = "vector"
+    )]
+    vector_format: String,
+
+    #[arg(
+        long = "embed",
+        value_name = "TEXT",
+        help = "Print TEXT's embedding as a search would embed the query, as JSON with the model name and dimensions, without searching (uses the index's model, or --model)"
+    )]
+    embed: Option<String>,
+
+    #[arg(
+        long = "verbatim",
+        help = "With --export-chunk, keep tabs and trailing whitespace as in the source (the default expands tabs to spaces and trims trailing whitespace)",
+        requires = "export_chunk"
+    )]
+    verbatim: bool,
+
+    #[arg(
+        long = "apply",
+        value_name = "SNIPPET",
+        num_args = 0..=1,
+        default_missing_value = "-",
+        help = "Write chunks edited between ck:begin/ck:end anchors back into their files, refusing any whose original content has changed since the export (reads the snippet from stdin by default)"
+    )]
+    apply: Option<String>,
+
+    #[arg(
+        long = "duplicates",
+        help = "Report near-duplicate chunk pairs across the index (uses --threshold [default: 0.95] and --topk [default: 20])"
+    )]
+    duplicates: bool,
+
+    #[arg(
+        long = "clusters",
+        help = "With --duplicates, group transitively similar chunks into clusters",
+        requires = "duplicates"
+    )]
+    clusters: bool,
+
+    #[arg(
+        long = "top-chunks",
+        help = "Report the biggest indexed chunks, the main embedding and context cost (uses --by [default: tokens] and --topk [default: 20]; indexes without embedding)"
+    )]
+    top_chunks: bool,
+
+    #[arg(
+        long = "by",
+        value_name = "MEASURE",
+        value_parser = clap::builder::PossibleValuesParser::new(ck_engine::CHUNK_SIZE_MEASURE_NAMES),
+        help = "With --top-chunks, size chunks by: tokens (estimated), lines or bytes",
+        requires = "top_chunks"
+    )]
+    by: Option<String>,
+
     // Model selection (index-time only)
     #[arg(
         long = "model",
         value_name = "MODEL",
-        help = "Embedding model to use for indexing (bge-small, nomic-v1.5, jina-code) [default: bge-small]. Only used with --index."
+        help = "Embedding model to use for indexing (bge-small, nomic-v1.5, jina-code, or openai:<model> for an OpenAI-compatible endpoint) [default: bge-small]. Only used with --index."
     )]
     model: Option<String>,
 
@@ -346,18 +817,105 @@ struct Cli {
     )]
     rerank_model: Option<String>,
 
+    #[arg(
+        long = "scope",
+        value_name = "PREFIX",
+        help = "Only search chunks nested under this breadcrumb path, e.g. 'net::http' (case-sensitive; repeat to search several; semantic/hybrid only)",
+        conflicts_with_all = ["lexical", "regex"]
+    )]
+    scope: Vec<String>,
+
+    #[arg(
+        long = "docs-only",
+        help = "Only search comment, doc and other text chunks (semantic/hybrid only)",
+        conflicts_with_all = ["code_only", "lexical", "regex"]
+    )]
+    docs_only: bool,
+
+    #[arg(
+        long = "code-only",
+        help = "Only search code chunks (functions, classes, methods, modules), skipping comments and docs (semantic/hybrid only)",
+        conflicts_with_all = ["lexical", "regex"]
+    )]
+    code_only: bool,
+
+    #[arg(
+        long = "type",
+        value_name = "TYPE",
+        value_delimiter = ',',
+        value_parser = clap::builder::PossibleValuesParser::new(ck_core::CHUNK_TYPE_NAMES),
+        help = "Only search chunks of these kinds: function, class, method, module, text (repeatable or comma-separated; semantic/hybrid only)",
+        conflicts_with_all = ["lexical", "regex"]
+    )]
+    chunk_types: Vec<String>,
+
+    #[arg(
+        long = "diff",
+        value_name = "RANGE",
+        help = "Semantic search only within chunks changed by a git ref range, e.g. 'main...HEAD' (embeds just those chunks; no index needed)",
+        conflicts_with_all = ["lexical", "hybrid", "regex"]
+    )]
+    diff: Option<String>,
+
+    #[arg(
+        long = "at",
+        value_name = "REF",
+        help = "Search (or with --index, pre-build) the tree of a past commit; the extracted snapshot and its index are cached under ~/.cache/ck/snapshots/<sha>",
+        conflicts_with_all = ["diff", "add", "clean", "clean_orphans", "prune", "switch_model"]
+    )]
+    at: Option<String>,
+
+    // Query-by-example search
+    #[arg(
+        long = "like-file",
+        value_name = "PATH",
+        help = "Find code similar to the contents of PATH (semantic search by example); PATH's own chunks are left out",
+        conflicts_with_all = ["like_snippet", "lexical", "hybrid", "regex"]
+    )]
+    like_file: Option<PathBuf>,
+
+    #[arg(
+        long = "like-snippet",
+        value_name = "CODE",
+        help = "Find code similar to CODE (use '-' to read the snippet from stdin)",
+        conflicts_with_all = ["lexical", "hybrid", "regex"]
+    )]
+    like_snippet: Option<String>,
+
+    #[arg(
+        long = "query-stdin",
+        help = "Read the query from stdin, all of it, like a PATTERN of '-' does; for queries generated by scripts. Every positional argument is a path to search",
+        conflicts_with_all = ["like_file", "like_snippet"]
+    )]
+    query_stdin: bool,
+
+    #[arg(
+        long = "queries-file",
+        value_name = "FILE",
+        help = "Run every non-blank line of FILE as a query against the same paths and print NDJSON, one line per query: {\"query\": ..., \"hits\": [...]} with the hits in --jsonl form. The model loads once for all of them; for building retrieval evaluation sets",
+        conflicts_with_all = ["query_stdin", "like_file", "like_snippet", "list", "json", "json_v1", "vimgrep", "markdown", "markdown_rows", "compact", "files_with_matches", "files_without_matches"]
+    )]
+    queries_file: Option<PathBuf>,
+
+    #[arg(
+        long = "no-restore",
+        help = "With --tui, don't restore the cursor and scroll position saved for the same query",
+        requires = "tui"
+    )]
+    no_restore: bool,
+
     // MCP Server mode
     #[arg(
         long = "serve",
         help = "Start MCP server mode for AI agent integration",
         conflicts_with_all = [
             "pattern", "files", "line_numbers", "no_filenames", "with_filenames",
-            "files_with_matches", "files_without_matches", "ignore_case", "word_regexp",
+            "files_with_matches", "files_without_matches", "ignore_case", "case_sensitive", "multiline", "word_regexp",
             "fixed_strings", "recursive", "context", "after_context", "before_context",
-            "semantic", "lexical", "hybrid", "regex", "top_k", "threshold", "show_scores",
-            "json", "json_v1", "jsonl", "no_snippet", "reindex", "exclude", "no_default_excludes",
-            "no_ignore", "full_section", "index", "clean", "clean_orphans", "switch_model",
-            "force", "add", "status", "status_verbose", "inspect", "dump_chunks", "model", "rerank", "rerank_model", "tui"
+            "semantic", "lexical", "hybrid", "regex", "no_semantic", "list", "references", "top_k", "threshold", "show_scores",
+            "json", "json_v1", "jsonl", "vimgrep", "markdown", "markdown_rows", "compact", "no_snippet", "reindex", "exclude", "no_default_excludes",
+            "no_ignore", "full_section", "index", "clean", "clean_orphans", "prune", "dry_run", "switch_model",
+            "force", "add", "status", "status_verbose", "doctor", "fix", "warmup", "inspect", "explain", "dump_chunks", "legend", "export_chunk", "split", "out", "vector", "embed", "context_prefix", "string_chunks", "text_window", "dims", "import_embeddings", "push_index", "remote", "metric", "highlight_terms", "exact_tokens", "merge_adjacent", "blame", "show_enclosing", "recent", "recent_mode", "no_tests", "tests_only", "test_patterns", "pager", "no_pager", "apply", "duplicates", "clusters", "top_chunks", "by", "model", "rerank", "rerank_model", "like_file", "like_snippet", "query_stdin", "queries_file", "scope", "globs", "docs_only", "code_only", "chunk_types", "diff", "at", "sort", "no_restore", "tui"
         ]
     )]
     serve: bool,
@@ -368,12 +926,12 @@ struct Cli {
         help = "Interactive TUI mode - like fzf but semantic. Live search with arrow keys, Tab to switch modes, Enter to open in $EDITOR",
         conflicts_with_all = [
             "line_numbers", "no_filenames", "with_filenames",
-            "files_with_matches", "files_without_matches", "ignore_case", "word_regexp",
-            "fixed_strings", "recursive", "context", "after_context", "before_context",
-            "semantic", "lexical", "hybrid", "regex", "top_k", "threshold", "show_scores",
-            "json", "json_v1", "jsonl", "no_snippet", "reindex", "exclude", "no_default_excludes",
-            "no_ignore", "full_section", "index", "clean", "clean_orphans", "switch_model",
-            "force", "add", "status", "status_verbose", "inspect", "dump_chunks", "model", "rerank", "rerank_model", "serve"
+            "files_with_matches", "files_without_matches", "ignore_case", "case_sensitive", "multiline", "word_regexp",
+            "fixed_strings", "recursive",
+            "semantic", "lexical", "hybrid", "regex", "no_semantic", "list", "references", "top_k", "threshold", "show_scores",
+            "json", "json_v1", "jsonl", "vimgrep", "markdown", "markdown_rows", "compact", "no_snippet", "reindex", "exclude", "no_default_excludes",
+            "no_ignore", "full_section", "index", "clean", "clean_orphans", "prune", "dry_run", "switch_model",
+            "force", "add", "status", "status_verbose", "doctor", "fix", "warmup", "inspect", "explain", "dump_chunks", "legend", "export_chunk", "split", "out", "vector", "embed", "context_prefix", "string_chunks", "text_window", "dims", "import_embeddings", "push_index", "remote", "metric", "highlight_terms", "exact_tokens", "merge_adjacent", "blame", "show_enclosing", "recent", "recent_mode", "no_tests", "tests_only", "test_patterns", "pager", "no_pager", "apply", "duplicates", "clusters", "top_chunks", "by", "model", "rerank", "rerank_model", "like_file", "like_snippet", "query_stdin", "queries_file", "scope", "globs", "docs_only", "code_only", "chunk_types", "diff", "at", "serve"
         ]
     )]
     tui: bool,
@@ -457,20 +1015,12 @@ fn resolve_model_selection(
 ) -> Result<(String, ck_models::ModelConfig)> {
     match requested {
         Some(name) => {
-            if let Some(config) = registry.get_model(name) {
-                return Ok((name.to_string(), config.clone()));
-            }
-
-            if let Some((alias, config)) = registry
-                .models
-                .iter()
-                .find(|(_, config)| config.name == name)
-            {
-                return Ok((alias.clone(), config.clone()));
+            if let Some(resolved) = registry.resolve(name) {
+                return Ok(resolved);
             }
 
             anyhow::bail!(
-                "Unknown model '{}'. Available models: {}",
+                "Unknown model '{}'. Available models: {} (or openai:<model> for a remote endpoint)",
                 name,
                 registry
                     .models
@@ -503,15 +1053,18 @@ async fn run_index_workflow(
     status.section_header(heading);
     status.info(&format!("Scanning files in {}", path.display()));
 
+    // Remote models not known to ck only learn their size from the endpoint
+    let dims = if model_config.dimensions == 0 {
+        "dims from endpoint".to_string()
+    } else {
+        format!("{} dims", model_config.dimensions)
+    };
     if model_alias == model_config.name {
-        status.info(&format!(
-            "🤖 Model: {} ({} dims)",
-            model_config.name, model_config.dimensions
-        ));
+        status.info(&format!("🤖 Model: {} ({})", model_config.name, dims));
     } else {
         status.info(&format!(
-            "🤖 Model: {} (alias '{}', {} dims)",
-            model_config.name, model_alias, model_config.dimensions
+            "🤖 Model: {} (alias '{}', {})",
+            model_config.name, model_alias, dims
         ));
     }
 
@@ -535,6 +1088,47 @@ async fn run_index_workflow(
 
     let exclude_patterns = build_exclude_patterns(cli);
 
+    // A rebuild keeps the index's context prefix, string chunk and text window settings unless they're being changed
+    let previous_manifest = clean_first
+        .then(|| std::fs::read(path.join(".ck").join("manifest.json")).ok())
+        .flatten()
+        .and_then(|data| serde_json::from_slice::<ck_index::IndexManifest>(&data).ok());
+    let context_prefix = cli
+        .context_prefix
+        .or_else(|| previous_manifest.as_ref()?.context_prefix.then_some(true));
+    let string_chunks = cli
+        .string_chunks
+        .or_else(|| previous_manifest.as_ref()?.string_chunks.then_some(true));
+    let text_window = cli.text_window.or_else(|| {
+        let window = previous_manifest.as_ref()?.text_window;
+        (window != ck_chunk::TextWindow::default()).then_some(window)
+    });
+
+    // Full size means no truncation; anything else has to be a size the model was trained for
+    let truncated_dims = cli
+        .dims
+        .map(|dims| (dims != model_config.dimensions).then_some(dims));
+    if let Some(Some(dims)) = truncated_dims
+        && !model_config.matryoshka_dims.contains(&dims)
+    {
+        let supported = if model_config.matryoshka_dims.is_empty() {
+            "it wasn't trained for truncated embeddings".to_string()
+        } else {
+            let sizes: Vec<String> = model_config
+                .matryoshka_dims
+                .iter()
+                .map(usize::to_string)
+                .collect();
+            format!("it supports {}", sizes.join(", "))
+        };
+        anyhow::bail!(
+            "--dims {} isn't available for {}: {}",
+            dims,
+            model_config.name,
+            supported
+        );
+    }
+
     if clean_first {
         let index_dir = path.join(".ck");
         if index_dir.exists() {
@@ -546,6 +1140,65 @@ async fn run_index_workflow(
         }
     }
 
+    if let Some(enabled) = context_prefix {
+        let state = if enabled { "on" } else { "off" };
+        if ck_index::set_context_prefix(path, enabled)? {
+            status.warn(&format!(
+                "Context prefix turned {}; removed the existing index to re-embed every file",
+                state
+            ));
+        } else {
+            status.info(&format!("🏷  Context prefix: {}", state));
+        }
+    }
+
+    if let Some(enabled) = string_chunks {
+        let state = if enabled { "on" } else { "off" };
+        if ck_index::set_string_chunks(path, enabled)? {
+            status.warn(&format!(
+                "String chunks turned {}; removed the existing index to re-chunk every file",
+                state
+            ));
+        } else {
+            status.info(&format!("🧵 String chunks: {}", state));
+        }
+    }
+
+    if let Some(window) = text_window {
+        if ck_index::set_text_window(path, window)? {
+            status.warn(&format!(
+                "Text windows set to {} lines with {} overlapping; removed the existing index to re-chunk every file",
+                window.lines, window.overlap
+            ));
+        } else {
+            status.info(&format!(
+                "📄 Text windows: {} lines, {} overlapping",
+                window.lines, window.overlap
+            ));
+        }
+    }
+
+    if let Some(dims) = truncated_dims {
+        let size = dims.unwrap_or(model_config.dimensions);
+        if ck_index::set_truncated_dims(path, dims)? {
+            status.warn(&format!(
+                "Embeddings set to {} dims; removed the existing index to re-embed every file",
+                size
+            ));
+        } else {
+            status.info(&format!("✂️  Embedding dims: {}", size));
+        }
+    }
+
+    if let Some(metric) = cli
+        .metric
+        .as_deref()
+        .and_then(ck_core::SimilarityMetric::from_name)
+    {
+        ck_index::set_similarity_metric(path, metric)?;
+        status.info(&format!("📐 Similarity metric: {}", metric.name()));
+    }
+
     let start_time = std::time::Instant::now();
 
     let (
@@ -630,6 +1283,8 @@ async fn run_index_workflow(
     let file_options = ck_core::FileCollectionOptions {
         respect_gitignore: !cli.no_ignore,
         use_ckignore: !cli.no_ckignore,
+        follow_symlinks: cli.follow_symlinks,
+        archives: cli.archives,
         exclude_patterns: exclude_patterns.clone(),
     };
     let index_future = ck_index::smart_update_index_with_detailed_progress(
@@ -714,6 +1369,12 @@ async fn run_index_workflow(
             stats.orphaned_files_removed
         ));
     }
+    if stats.symlinked_dirs_skipped > 0 {
+        status.info(&format!(
+            "  🔗 {} symlinked directories skipped (use --follow-symlinks to index them)",
+            stats.symlinked_dirs_skipped
+        ));
+    }
 
     if clean_first {
         status.info(&format!(
@@ -725,16 +1386,47 @@ async fn run_index_workflow(
     Ok(())
 }
 
-async fn dump_file_chunks(file_path: &PathBuf) -> Result<()> {
-    use std::path::Path;
-
-    let path = Path::new(file_path);
+/// `--dump-chunks --stable`: a `START-END TYPE PATH` line per chunk, outer
+/// chunks before the ones they contain. PATH is the breadcrumb and name
+/// joined with `::`, or `-` for an unnamed chunk.
+fn format_stable_chunk_dump(chunks: &[ck_tui::IndexedChunkMeta]) -> String {
+    let mut rows: Vec<(usize, usize, &str, String)> = chunks
+        .iter()
+        .map(|chunk| {
+            let path = match (&chunk.breadcrumb, &chunk.name) {
+                (Some(breadcrumb), Some(name)) => format!("{}::{}", breadcrumb, name),
+                (Some(path), None) | (None, Some(path)) => path.clone(),
+                (None, None) => "-".to_string(),
+            };
+            let chunk_type = chunk.chunk_type.as_deref().unwrap_or("text");
+            (chunk.span.line_start, chunk.span.line_end, chunk_type, path)
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        (a.0, std::cmp::Reverse(a.1), a.2, &a.3).cmp(&(b.0, std::cmp::Reverse(b.1), b.2, &b.3))
+    });
+    rows.iter()
+        .map(|(start, end, chunk_type, path)| {
+            format!("{}-{} {} {}\n", start, end, chunk_type, path)
+        })
+        .collect()
+}
+
+/// Print what each chunk view glyph means
+fn print_chunk_legend() {
+    println!("Legend:");
+    for (glyph, meaning) in ck_tui::chunk_glyph_legend() {
+        println!("  {:<6} {}", glyph, meaning);
+    }
+}
+
+async fn dump_file_chunks(file_path: &PathBuf, gutter: &str) -> Result<()> {
+    use std::path::Path;
+
+    let path = Path::new(file_path);
 
     // Use the shared live chunking function
-    let (lines, chunk_metas) = ck_tui::chunk_file_live(path).map_err(|err| {
-        eprintln!("Error: {}", err);
-        std::process::exit(1);
-    })?;
+    let (lines, chunk_metas) = ck_tui::chunk_file_live(path).map_err(anyhow::Error::msg)?;
 
     // Display chunks for entire file
     let display_lines = ck_tui::chunks::collect_chunk_display_lines(
@@ -766,11 +1458,384 @@ async fn dump_file_chunks(file_path: &PathBuf) -> Result<()> {
     );
     println!();
 
+    let gutter = match gutter {
+        "bytes" => ck_tui::GutterMode::Bytes,
+        "both" => ck_tui::GutterMode::Both,
+        _ => ck_tui::GutterMode::Lines,
+    };
+    let byte_ranges = ck_tui::line_byte_ranges(&lines);
+
     // Convert display lines to strings and print
     for line in display_lines {
-        println!("{}", ck_tui::chunk_display_line_to_string(&line));
+        println!(
+            "{}",
+            ck_tui::chunk_display_line_to_string_with_gutter(&line, gutter, &byte_ranges)
+        );
+    }
+
+    Ok(())
+}
+
+/// Write each structural chunk of `file` to its own file in `out`, named by its
+/// breadcrumb (`Parser.parse.rs`) with a `-2`, `-3`... suffix for repeated
+/// names. The lines of the text chunks, without overlaps, go to `_misc`.
+/// Returns the files written, in source order with `_misc` last.
+fn split_file_chunks(file: &Path, out: &Path) -> Result<Vec<PathBuf>> {
+    let (lines, chunks) = ck_tui::chunk_file_live(file).map_err(|err| anyhow::anyhow!(err))?;
+    std::fs::create_dir_all(out)
+        .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", out.display(), e))?;
+
+    let extension = file
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let body = |start: usize, end: usize| {
+        let start = start.saturating_sub(1).min(lines.len());
+        let end = end.min(lines.len()).max(start);
+        lines[start..end].join("\n")
+    };
+
+    let mut written = Vec::new();
+    let mut used: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    let mut misc: Vec<(usize, usize)> = Vec::new();
+    for chunk in &chunks {
+        if chunk
+            .chunk_type
+            .as_deref()
+            .is_none_or(|kind| kind == "text")
+        {
+            misc.push((chunk.span.line_start, chunk.span.line_end));
+            continue;
+        }
+
+        let path: Vec<&str> = chunk
+            .ancestry
+            .iter()
+            .map(String::as_str)
+            .chain(chunk.name.as_deref())
+            .collect();
+        let stem = if chunk.name.is_some() {
+            sanitize_file_stem(&path.join("."))
+        } else {
+            format!(
+                "{}-L{}",
+                chunk.chunk_type.as_deref().unwrap_or("chunk"),
+                chunk.span.line_start
+            )
+        };
+        let count = used.entry(stem.clone()).or_default();
+        *count += 1;
+        let name = if *count == 1 {
+            format!("{}{}", stem, extension)
+        } else {
+            format!("{}-{}{}", stem, count, extension)
+        };
+
+        let target = out.join(name);
+        let mut text = body(chunk.span.line_start, chunk.span.line_end);
+        text.push('\n');
+        std::fs::write(&target, text)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", target.display(), e))?;
+        written.push(target);
+    }
+
+    // Text chunks overlap one another, so write each line once
+    misc.sort();
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in misc {
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => ranges.push((start, end)),
+        }
+    }
+    if !ranges.is_empty() {
+        let sections: Vec<String> = ranges
+            .iter()
+            .map(|&(start, end)| body(start, end))
+            .collect();
+        let target = out.join(format!("_misc{}", extension));
+        std::fs::write(&target, format!("{}\n", sections.join("\n\n")))
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", target.display(), e))?;
+        written.push(target);
+    }
+
+    Ok(written)
+}
+
+/// `name` with anything but letters, digits, `.`, `_` and `-` replaced by `_`,
+/// so breadcrumbs like `impl Display for Span` make portable file names
+fn sanitize_file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Split a `FILE:LINE` target given to `flag` into its path and 1-based line
+fn parse_file_line<'a>(target: &'a str, flag: &str) -> Result<(&'a str, usize)> {
+    target
+        .rsplit_once(':')
+        .and_then(|(file, line)| Some((file, line.parse::<usize>().ok()?)))
+        .filter(|(file, line)| !file.is_empty() && *line > 0)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "{} expects FILE:LINE (e.g. src/lib.rs:42), found '{}'",
+                flag,
+                target
+            )
+        })
+}
+
+/// A chunk's stored embedding as JSON, or as `txt`: `#` header lines naming the
+/// chunk, model and dimensions followed by one row of floats, which
+/// `numpy.loadtxt` reads as-is
+fn format_chunk_vector(vector: &ck_engine::ChunkVector, format: &str) -> Result<String> {
+    if format == "json" {
+        return Ok(format!("{}\n", serde_json::to_string_pretty(vector)?));
+    }
+
+    let mut out = format!(
+        "# chunk: {} {}:{}-{}\n",
+        vector.id,
+        vector.file.display(),
+        vector.span.line_start,
+        vector.span.line_end
+    );
+    if let Some(breadcrumb) = &vector.breadcrumb {
+        out.push_str(&format!("# breadcrumb: {}\n", breadcrumb));
+    }
+    out.push_str(&format!("# model: {}\n", vector.model));
+    out.push_str(&format!("# dimensions: {}\n", vector.dimensions));
+    let values: Vec<String> = vector.embedding.iter().map(f32::to_string).collect();
+    out.push_str(&values.join(" "));
+    out.push('\n');
+    Ok(out)
+}
+
+/// The innermost chunk containing the line in a `FILE:LINE` target, wrapped in
+/// anchor comments carrying its chunk id and span
+fn export_anchored_chunk(target: &str, whitespace: ck_core::WhitespaceMode) -> Result<String> {
+    let (file, line) = parse_file_line(target, "--export-chunk")?;
+    let path = Path::new(file);
+
+    let (lines, chunk_metas) = ck_tui::chunk_file_live(path).map_err(|err| anyhow::anyhow!(err))?;
+    let chunk = chunk_metas
+        .iter()
+        .filter(|meta| meta.span.line_start <= line && line <= meta.span.line_end)
+        .min_by_key(|meta| meta.span.line_end - meta.span.line_start)
+        .ok_or_else(|| anyhow::anyhow!("No chunk covers {}:{}", file, line))?;
+
+    let start = chunk.span.line_start.saturating_sub(1).min(lines.len());
+    let end = chunk.span.line_end.min(lines.len()).max(start);
+    let mut body = whitespace.apply_lines(&lines[start..end]).join("\n");
+    body.push('\n');
+
+    let anchor = ck_core::anchor::ChunkAnchor {
+        chunk_id: chunk.chunk_id.clone().unwrap_or_default(),
+        path: file.to_string(),
+        line_start: chunk.span.line_start,
+        line_end: chunk.span.line_end,
+    };
+    Ok(ck_core::anchor::wrap_with_anchors(
+        &anchor,
+        &body,
+        ck_core::Language::from_path(path),
+    ))
+}
+
+/// Replace each anchored region in `snippet` with its edited body. A region is
+/// only written if a chunk with its id (and so its original content) is still
+/// in the file; if any region has drifted nothing is written.
+fn apply_anchored_edits(snippet: &str) -> Result<Vec<String>> {
+    let regions = ck_core::anchor::parse_anchored_regions(snippet)?;
+    if regions.is_empty() {
+        anyhow::bail!(
+            "No ck:begin/ck:end anchors found; export a chunk with --export-chunk FILE:LINE first"
+        );
+    }
+
+    let mut by_file: Vec<(String, Vec<ck_core::anchor::AnchoredRegion>)> = Vec::new();
+    for region in regions {
+        match by_file
+            .iter_mut()
+            .find(|(path, _)| *path == region.anchor.path)
+        {
+            Some((_, file_regions)) => file_regions.push(region),
+            None => by_file.push((region.anchor.path.clone(), vec![region])),
+        }
+    }
+
+    let mut writes = Vec::new();
+    let mut applied = Vec::new();
+    for (path, file_regions) in by_file {
+        let file = Path::new(&path);
+        let (_, chunk_metas) = ck_tui::chunk_file_live(file).map_err(|err| anyhow::anyhow!(err))?;
+
+        let mut targets = Vec::new();
+        for region in file_regions {
+            let anchor = &region.anchor;
+            // Identical chunks share an id; take the one nearest where it was exported
+            let current = chunk_metas
+                .iter()
+                .filter(|meta| meta.chunk_id.as_deref() == Some(anchor.chunk_id.as_str()))
+                .min_by_key(|meta| meta.span.line_start.abs_diff(anchor.line_start))
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{}:{}-{} has changed since chunk {} was exported, so applying the edit could clobber those changes. Nothing was written; re-export with --export-chunk {}:{} and redo the edit.",
+                        path,
+                        anchor.line_start,
+                        anchor.line_end,
+                        anchor.chunk_id,
+                        path,
+                        anchor.line_start
+                    )
+                })?;
+            targets.push((current.span.clone(), region));
+        }
+
+        // Replace bottom-up so earlier spans keep their line numbers
+        targets.sort_by_key(|(span, _)| std::cmp::Reverse(span.line_start));
+        for pair in targets.windows(2) {
+            if pair[1].0.line_end >= pair[0].0.line_start {
+                anyhow::bail!(
+                    "Chunks {} and {} overlap in {}; apply them separately",
+                    pair[1].1.anchor.chunk_id,
+                    pair[0].1.anchor.chunk_id,
+                    path
+                );
+            }
+        }
+
+        let mut content = std::fs::read_to_string(file)?;
+        for (span, region) in &targets {
+            content = ck_core::anchor::replace_lines(
+                &content,
+                span.line_start,
+                span.line_end,
+                &region.body,
+            );
+            let moved = if span.line_start != region.anchor.line_start {
+                format!(
+                    " (moved from {}-{})",
+                    region.anchor.line_start, region.anchor.line_end
+                )
+            } else {
+                String::new()
+            };
+            applied.push(format!(
+                "Applied chunk {} to {}:{}-{}{}",
+                region.anchor.chunk_id, path, span.line_start, span.line_end, moved
+            ));
+        }
+        writes.push((file.to_path_buf(), content));
+    }
+
+    for (file, content) in writes {
+        std::fs::write(&file, content)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", file.display(), e))?;
+    }
+    applied.reverse();
+    Ok(applied)
+}
+
+/// Say why a file was (or wasn't) split into structural chunks: which language
+/// its extension maps to, whether that language has a grammar, and how cleanly
+/// the grammar parsed it
+fn explain_file_chunking(file_path: &Path, status: &StatusReporter) -> Result<()> {
+    if !file_path.is_file() {
+        anyhow::bail!("File does not exist: {}", file_path.display());
+    }
+    let content = std::fs::read_to_string(file_path)?;
+    let extension = file_path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()));
+
+    println!("File: {}", style(file_path.display()).cyan().bold());
+
+    let Some(language) = ck_core::Language::from_path(file_path) else {
+        match &extension {
+            Some(ext) => println!(
+                "Language: {} (extension {} is not mapped to a language)",
+                style("none").red(),
+                ext
+            ),
+            None => println!(
+                "Language: {} (no file extension to detect it from)",
+                style("none").red()
+            ),
+        }
+        println!("\nVerdict: chunked as plain text because the language is unknown.");
+        return Ok(());
+    };
+    println!(
+        "Language: {}{}",
+        style(language.to_string()).green(),
+        extension
+            .map(|ext| format!(" (from {})", ext))
+            .unwrap_or_default()
+    );
+
+    if !ck_chunk::has_structural_support(language) {
+        println!("Structural support: {}", style("no").red());
+        println!(
+            "\nVerdict: chunked as plain text because {} has no tree-sitter grammar in ck yet.",
+            language
+        );
+        return Ok(());
+    }
+    println!("Structural support: {}", style("yes").green());
+
+    let diagnostics = ck_chunk::parse_diagnostics(&content, language)?.unwrap_or_default();
+    let chunks = ck_chunk::chunk_text(&content, Some(language))?;
+    let structural = chunks
+        .iter()
+        .filter(|chunk| chunk.chunk_type != ck_chunk::ChunkType::Text)
+        .count();
+
+    match diagnostics.first_error_line {
+        Some(line) => println!(
+            "Parse: {} ({} unparsed regions, {} missing tokens; first at line {})",
+            style(format!(
+                "{} syntax error{}",
+                diagnostics.error_count(),
+                if diagnostics.error_count() == 1 {
+                    ""
+                } else {
+                    "s"
+                }
+            ))
+            .red(),
+            diagnostics.error_nodes,
+            diagnostics.missing_nodes,
+            line
+        ),
+        None => println!("Parse: {}", style("no syntax errors").green()),
     }
+    println!(
+        "Chunks: {} ({} structural, {} text)",
+        chunks.len(),
+        structural,
+        chunks.len() - structural
+    );
 
+    let verdict = if structural > 0 && diagnostics.error_count() == 0 {
+        "structural chunking worked.".to_string()
+    } else if structural > 0 {
+        "structural chunking worked, but code around the syntax errors may be missing from or merged into its chunks.".to_string()
+    } else if diagnostics.error_count() > 0 {
+        "chunked as plain text because parsing failed; fix the syntax errors above or check the file is really this language.".to_string()
+    } else {
+        format!(
+            "chunked as plain text because the file parsed but has no top-level definitions ck chunks for {}.",
+            language
+        )
+    };
+    println!("\nVerdict: {}", verdict);
     Ok(())
 }
 
@@ -783,8 +1848,7 @@ async fn inspect_file_metadata(file_path: &PathBuf, status: &StatusReporter) ->
     let path = Path::new(file_path);
 
     if !path.exists() {
-        status.error("File does not exist");
-        return Ok(());
+        anyhow::bail!("File does not exist: {}", path.display());
     }
 
     let metadata = fs::metadata(path)?;
@@ -903,6 +1967,11 @@ async fn inspect_file_metadata(file_path: &PathBuf, status: &StatusReporter) ->
     Ok(())
 }
 
+/// grep's exit status when nothing matched
+const EXIT_NO_MATCHES: i32 = 1;
+/// grep's exit status for errors, kept distinct from "no matches" for scripts
+const EXIT_ERROR: i32 = 2;
+
 #[tokio::main]
 async fn main() {
     if let Err(e) = run_main().await {
@@ -916,18 +1985,26 @@ async fn main() {
             source = err.source();
         }
 
-        std::process::exit(1);
+        std::process::exit(EXIT_ERROR);
     }
 }
 
 async fn run_main() -> Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
 
     if cli.print_default_ckignore {
         print!("{}", get_default_ckignore_content());
         return Ok(());
     }
 
+    apply_list_mode(&mut cli);
+    apply_queries_file_mode(&mut cli);
+    apply_query_stdin_mode(&mut cli);
+    if cli.references {
+        cli.no_semantic = true;
+    }
+    apply_config_defaults(&mut cli, &config::Config::load()?);
+
     // Handle MCP server mode first
     if cli.serve {
         return run_mcp_server().await;
@@ -941,7 +2018,12 @@ async fn run_main() -> Result<()> {
             .cloned()
             .unwrap_or_else(|| PathBuf::from("."));
         let initial_query = cli.pattern.clone();
-        return ck_tui::run_tui(search_path, initial_query).await;
+        let sort = cli.sort.as_deref().and_then(ck_core::ResultSort::from_name);
+        let context = ck_tui::ContextOverride {
+            before: cli.before_context.or(cli.context),
+            after: cli.after_context.or(cli.context),
+        };
+        return ck_tui::run_tui(search_path, initial_query, sort, context, !cli.no_restore).await;
     }
 
     // Regular CLI mode
@@ -959,11 +2041,25 @@ async fn run_mcp_server() -> Result<()> {
         .init();
 
     let cwd = std::env::current_dir()?;
+    // Load the model in the background so the first search doesn't wait for it
+    if ck_engine::index_has_embeddings(&cwd) {
+        let warmup_path = cwd.clone();
+        tokio::task::spawn_blocking(move || {
+            match ck_engine::warm_up_embedder(&warmup_path, None) {
+                Ok(warmup) => tracing::info!(
+                    "Embedding model {} warmed up in {:.2}s",
+                    warmup.model_name,
+                    (warmup.load_time + warmup.inference_time).as_secs_f64()
+                ),
+                Err(err) => tracing::warn!("Embedding model warm-up failed: {}", err),
+            }
+        });
+    }
     let server = mcp_server::CkMcpServer::new(cwd)?;
     server.run().await
 }
 
-async fn run_cli_mode(cli: Cli) -> Result<()> {
+async fn run_cli_mode(mut cli: Cli) -> Result<()> {
     // Regular CLI mode logging
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -985,7 +2081,7 @@ async fn run_cli_mode(cli: Cli) -> Result<()> {
         let registry = ck_models::ModelRegistry::default();
         let (model_alias, model_config) = resolve_model_selection(&registry, Some(model_name))?;
 
-        if !cli.force {
+        if !cli.force && cli.dims.is_none() {
             let manifest_path = path.join(".ck").join("manifest.json");
             if manifest_path.exists()
                 && let Ok(data) = std::fs::read(&manifest_path)
@@ -1034,12 +2130,50 @@ async fn run_cli_mode(cli: Cli) -> Result<()> {
         return Ok(());
     }
 
-    if cli.index {
+    if let Some(source) = &cli.import_embeddings {
         let path = cli
             .files
             .first()
             .cloned()
             .unwrap_or_else(|| PathBuf::from("."));
+        let registry = ck_models::ModelRegistry::default();
+        let (model_alias, model_config) = resolve_model_selection(&registry, cli.model.as_deref())?;
+
+        status.section_header("Importing Embeddings");
+        let import_spinner = status.create_spinner("Writing index...");
+        let imported = if source.as_os_str() == "-" {
+            ck_index::import_embeddings(&path, std::io::stdin().lock(), &model_config.name)
+        } else {
+            let file = std::fs::File::open(source)
+                .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", source.display(), e))?;
+            ck_index::import_embeddings(&path, std::io::BufReader::new(file), &model_config.name)
+        };
+        let stats = imported?;
+        if let Some(metric) = cli
+            .metric
+            .as_deref()
+            .and_then(ck_core::SimilarityMetric::from_name)
+        {
+            ck_index::set_similarity_metric(&path, metric)?;
+        }
+        status.finish_progress(import_spinner, "Index written");
+        status.success(&format!(
+            "Imported {} chunks across {} files ({} dims, model {})",
+            stats.chunks, stats.files, stats.dimensions, model_alias
+        ));
+        return Ok(());
+    }
+
+    if cli.index {
+        let mut path = cli
+            .files
+            .first()
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from("."));
+        if let Some(rev) = cli.at.as_deref() {
+            let snapshot = take_snapshot(&path, rev, &status)?;
+            path = snapshot.path_for(&path)?;
+        }
 
         let registry = ck_models::ModelRegistry::default();
         let (model_alias, model_config) = resolve_model_selection(&registry, cli.model.as_deref())?;
@@ -1054,6 +2188,19 @@ async fn run_cli_mode(cli: Cli) -> Result<()> {
             false,
         )
         .await?;
+        if let Some(location) = &cli.push_index {
+            push_index_to(&status, &path, location)?;
+        }
+        return Ok(());
+    }
+
+    if let Some(location) = &cli.push_index {
+        let path = cli
+            .files
+            .first()
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from("."));
+        push_index_to(&status, &path, location)?;
         return Ok(());
     }
 
@@ -1077,6 +2224,8 @@ async fn run_cli_mode(cli: Cli) -> Result<()> {
             let file_options = ck_core::FileCollectionOptions {
                 respect_gitignore: !cli.no_ignore,
                 use_ckignore: !cli.no_ckignore,
+                follow_symlinks: cli.follow_symlinks,
+                archives: cli.archives,
                 exclude_patterns: exclude_patterns.clone(),
             };
             let cleanup_stats = ck_index::cleanup_index(&clean_path, &file_options)?;
@@ -1108,6 +2257,42 @@ async fn run_cli_mode(cli: Cli) -> Result<()> {
         return Ok(());
     }
 
+    if cli.prune {
+        let prune_path = cli
+            .files
+            .first()
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from("."));
+        status.section_header(if cli.dry_run {
+            "Pruning Deleted Files (dry run)"
+        } else {
+            "Pruning Deleted Files"
+        });
+        let stats = ck_index::prune_index(&prune_path, cli.dry_run)?;
+        if stats.pruned_files.is_empty() {
+            status.info("No index entries for deleted files");
+            return Ok(());
+        }
+        for file in &stats.pruned_files {
+            println!("{}", file.display());
+        }
+        let summary = format!(
+            "{} deleted files ({} chunks, {} sidecars)",
+            stats.pruned_files.len(),
+            stats.chunks_removed,
+            stats.sidecars_removed
+        );
+        if cli.dry_run {
+            status.info(&format!("Would prune {}", summary));
+        } else {
+            status.success(&format!("Pruned {}", summary));
+            if stats.lexical_index_removed {
+                status.info("Removed the lexical index; the next --lex search rebuilds it");
+            }
+        }
+        return Ok(());
+    }
+
     if cli.add {
         // Handle --add flag
         // When using --add, the file path might be in pattern or files
@@ -1239,6 +2424,81 @@ async fn run_cli_mode(cli: Cli) -> Result<()> {
         return Ok(());
     }
 
+    if cli.warmup {
+        let warmup_path = cli
+            .files
+            .first()
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        status.section_header("Model Warm-up");
+        let load_spinner = status.create_spinner("Loading embedding model...");
+        let warmup = ck_engine::warm_up_embedder(&warmup_path, cli.model.as_deref());
+        match warmup {
+            Ok(warmup) => {
+                status.finish_progress(load_spinner, "Model ready");
+                status.success(&format!(
+                    "{} ({} dims): loaded in {:.2}s, first inference in {}ms",
+                    warmup.model_name,
+                    warmup.dim,
+                    warmup.load_time.as_secs_f64(),
+                    warmup.inference_time.as_millis()
+                ));
+            }
+            Err(err) => {
+                status.finish_progress(load_spinner, "Warm-up failed");
+                return Err(err);
+            }
+        }
+        return Ok(());
+    }
+
+    if cli.doctor {
+        let doctor_path = cli
+            .files
+            .first()
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        status.section_header("Index Doctor");
+        let check_spinner = status.create_spinner("Checking index...");
+        let check = ck_index::check_index(&doctor_path, cli.fix)?;
+        status.finish_progress(check_spinner, "Check complete");
+
+        status.info(&format!(
+            "Checked {} files, {} chunks",
+            check.files_checked, check.chunks_checked
+        ));
+        for problem in &check.problems {
+            let place = problem
+                .file
+                .as_ref()
+                .map(|file| format!("{}: ", file.display()))
+                .unwrap_or_default();
+            if problem.fixed {
+                status.success(&format!("{place}{} (fixed)", problem.message));
+            } else {
+                status.warn(&format!("{place}{}", problem.message));
+            }
+        }
+
+        let unfixed = check.problems.iter().filter(|p| !p.fixed).count();
+        if check.problems.is_empty() {
+            status.success("No problems found");
+        } else if unfixed == 0 {
+            status.success(&format!(
+                "Repaired {} problems; re-run your search to re-index the affected files",
+                check.problems.len()
+            ));
+        } else {
+            if !cli.fix {
+                status.info("Run 'ck --doctor --fix' to repair them");
+            }
+            std::process::exit(EXIT_ERROR);
+        }
+        return Ok(());
+    }
+
     if cli.inspect {
         // Handle --inspect flag
         // For inspect, the file path could be in pattern or files
@@ -1248,7 +2508,7 @@ async fn run_cli_mode(cli: Cli) -> Result<()> {
             cli.files[0].clone()
         } else {
             eprintln!("Error: --inspect requires a file path");
-            std::process::exit(1);
+            std::process::exit(EXIT_ERROR);
         };
 
         status.section_header("File Inspection");
@@ -1258,77 +2518,266 @@ async fn run_cli_mode(cli: Cli) -> Result<()> {
         return Ok(());
     }
 
-    if cli.dump_chunks {
-        // Handle --dump-chunks flag
+    if cli.explain {
         let file_path = if let Some(pattern) = &cli.pattern {
             PathBuf::from(pattern)
         } else if !cli.files.is_empty() {
             cli.files[0].clone()
         } else {
-            eprintln!("Error: --dump-chunks requires a file path");
-            std::process::exit(1);
+            eprintln!("Error: --explain requires a file path");
+            std::process::exit(EXIT_ERROR);
         };
 
-        dump_file_chunks(&file_path).await?;
+        status.section_header("Chunking Explanation");
+        explain_file_chunking(&file_path, &status)?;
         return Ok(());
     }
 
-    // Validate conflicting flags
-    if cli.files_with_matches && cli.files_without_matches {
-        eprintln!("Error: Cannot use -l and -L together");
-        std::process::exit(1);
+    if let Some(target) = &cli.export_chunk {
+        let whitespace = if cli.verbatim {
+            ck_core::WhitespaceMode::Verbatim
+        } else {
+            ck_core::WhitespaceMode::Normalized
+        };
+        print!("{}", export_anchored_chunk(target, whitespace)?);
+        return Ok(());
     }
 
-    // Default behavior: search with pattern
-    if let Some(ref pattern) = cli.pattern {
-        let reindex = cli.reindex;
+    if let (Some(file), Some(out)) = (&cli.split, &cli.out) {
+        for written in split_file_chunks(file, out)? {
+            println!("{}", written.display());
+        }
+        return Ok(());
+    }
 
-        // Determine repo root for .ckignore loading
-        let repo_root_path = cli
+    if let Some(target) = &cli.vector {
+        let (file, line) = parse_file_line(target, "--vector")?;
+        let vector = ck_engine::chunk_vector(Path::new(file), line)?;
+        print!("{}", format_chunk_vector(&vector, &cli.vector_format)?);
+        return Ok(());
+    }
+
+    if let Some(text) = &cli.embed {
+        let path = cli
             .files
             .first()
-            .map(|p| {
-                if p.is_dir() {
-                    p.clone()
-                } else {
-                    p.parent().unwrap_or(p).to_path_buf()
-                }
-            })
+            .cloned()
             .unwrap_or_else(|| PathBuf::from("."));
+        let vector = ck_engine::query_vector(&path, cli.model.as_deref(), text)?;
+        println!("{}", serde_json::to_string_pretty(&vector)?);
+        return Ok(());
+    }
 
-        let repo_root = Some(repo_root_path.as_path());
-
-        // Build options to get exclusion patterns
-        let temp_options = build_options(&cli, reindex, repo_root);
-
-        let expanded_targets = if cli.files.is_empty() {
-            vec![PathBuf::from(".")]
-        } else {
-            expand_glob_patterns(&cli.files, &temp_options.exclude_patterns)?
-        };
-
-        let include_patterns = if cli.files.is_empty() {
-            Vec::new()
+    if let Some(source) = &cli.apply {
+        let snippet = if source == "-" {
+            let mut buffer = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut buffer)?;
+            buffer
         } else {
-            build_include_patterns(&expanded_targets)
+            std::fs::read_to_string(source)
+                .map_err(|e| anyhow::anyhow!("Failed to read --apply {}: {}", source, e))?
         };
+        match apply_anchored_edits(&snippet) {
+            Ok(applied) => {
+                for message in applied {
+                    status.success(&message);
+                }
+            }
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                std::process::exit(EXIT_ERROR);
+            }
+        }
+        return Ok(());
+    }
 
-        let mut search_root = if include_patterns.is_empty() {
-            PathBuf::from(".")
+    if cli.dump_chunks {
+        // Handle --dump-chunks flag
+        let file_path = if let Some(pattern) = &cli.pattern {
+            PathBuf::from(pattern)
+        } else if !cli.files.is_empty() {
+            cli.files[0].clone()
         } else {
-            find_search_root(&include_patterns)
+            eprintln!("Error: --dump-chunks requires a file path");
+            std::process::exit(EXIT_ERROR);
         };
 
-        if expanded_targets.len() == 1 && !expanded_targets[0].exists() {
-            search_root = expanded_targets[0].clone();
+        if cli.stable {
+            let (_, chunk_metas) =
+                ck_tui::chunk_file_live(&file_path).map_err(anyhow::Error::msg)?;
+            print!("{}", format_stable_chunk_dump(&chunk_metas));
+            return Ok(());
+        }
+        dump_file_chunks(&file_path, &cli.gutter).await?;
+        if cli.legend {
+            println!();
+            print_chunk_legend();
         }
+        return Ok(());
+    }
 
-        let include_patterns = if include_patterns.len() > 1 {
-            include_patterns
-                .into_iter()
-                .filter(|pattern| !(pattern.is_dir && pattern.path == search_root))
-                .collect()
-        } else {
+    if cli.legend {
+        print_chunk_legend();
+        return Ok(());
+    }
+
+    // Every command above has returned; only search results and listings,
+    // which can run long, go through the pager
+    if pager::wants_pager(pager_choice(&cli))
+        && let Some(code) = pager::run_through_pager()?
+    {
+        std::process::exit(code);
+    }
+    pager::exit_quietly_on_closed_pager();
+
+    if cli.duplicates {
+        let path = cli
+            .pattern
+            .as_ref()
+            .map(PathBuf::from)
+            .or_else(|| cli.files.first().cloned())
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        let mut options = build_options(&cli, cli.reindex, Some(path.as_path()));
+        options.path = path;
+
+        let spinner = status.create_spinner("Comparing chunk embeddings...");
+        let pairs = ck_engine::find_duplicates(&options).await?;
+        status.finish_progress(
+            spinner,
+            &format!("Found {} near-duplicate pairs", pairs.len()),
+        );
+
+        print_duplicates(&pairs, cli.clusters, &options)?;
+        return Ok(());
+    }
+
+    if cli.top_chunks {
+        let path = cli
+            .pattern
+            .as_ref()
+            .map(PathBuf::from)
+            .or_else(|| cli.files.first().cloned())
+            .unwrap_or_else(|| PathBuf::from("."));
+        let measure = cli
+            .by
+            .as_deref()
+            .and_then(ck_engine::ChunkSizeMeasure::from_name)
+            .unwrap_or(ck_engine::ChunkSizeMeasure::Tokens);
+
+        let mut options = build_options(&cli, cli.reindex, Some(path.as_path()));
+        options.path = path;
+
+        let spinner = status.create_spinner("Measuring indexed chunks...");
+        let chunks = ck_engine::largest_chunks(&options, measure).await?;
+        status.finish_progress(spinner, &format!("Found {} chunks", chunks.len()));
+
+        print_top_chunks(&chunks, measure, &options)?;
+        return Ok(());
+    }
+
+    // Validate conflicting flags
+    if cli.files_with_matches && cli.files_without_matches {
+        eprintln!("Error: Cannot use -l and -L together");
+        std::process::exit(EXIT_ERROR);
+    }
+
+    if cli.query_stdin || cli.pattern.as_deref() == Some("-") {
+        cli.pattern = Some(read_stdin_query()?);
+    }
+
+    if let Some(location) = &cli.remote {
+        let path = cli
+            .files
+            .first()
+            .filter(|path| path.is_dir())
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from("."));
+        pull_index_from(&status, &path, location)?;
+        if cli.pattern.is_none() && cli.like_file.is_none() && cli.like_snippet.is_none() {
+            return Ok(());
+        }
+    }
+
+    // Search by example: the query is the example code, so a positional pattern
+    // is just another search target
+    let mut targets = cli.files.clone();
+    let query = match load_example_query(&cli)? {
+        Some(example) => {
+            if let Some(pattern) = &cli.pattern {
+                targets.insert(0, PathBuf::from(pattern));
+            }
+            Some(example)
+        }
+        None => cli.pattern.clone(),
+    };
+
+    // --at: search the same targets inside the commit's snapshot
+    if let Some(rev) = cli.at.as_deref() {
+        let base = targets
+            .first()
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from("."));
+        let snapshot = take_snapshot(&base, rev, &status)?;
+        targets = if targets.is_empty() {
+            vec![snapshot.path_for(Path::new("."))?]
+        } else {
+            targets
+                .iter()
+                .map(|target| snapshot.path_for(target))
+                .collect::<Result<_>>()?
+        };
+    }
+
+    // Default behavior: search with pattern
+    if let Some(ref pattern) = query {
+        let reindex = cli.reindex;
+
+        // Determine repo root for .ckignore loading
+        let repo_root_path = targets
+            .first()
+            .map(|p| {
+                if p.is_dir() {
+                    p.clone()
+                } else {
+                    p.parent().unwrap_or(p).to_path_buf()
+                }
+            })
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        let repo_root = Some(repo_root_path.as_path());
+
+        // Build options to get exclusion patterns
+        let temp_options = build_options(&cli, reindex, repo_root);
+
+        let expanded_targets = if targets.is_empty() {
+            vec![PathBuf::from(".")]
+        } else {
+            expand_glob_patterns(&targets, &temp_options.exclude_patterns)?
+        };
+
+        let include_patterns = if targets.is_empty() {
+            Vec::new()
+        } else {
+            build_include_patterns(&expanded_targets)
+        };
+
+        let mut search_root = if include_patterns.is_empty() {
+            PathBuf::from(".")
+        } else {
+            find_search_root(&include_patterns)
+        };
+
+        if expanded_targets.len() == 1 && !expanded_targets[0].exists() {
+            search_root = expanded_targets[0].clone();
+        }
+
+        let include_patterns = if include_patterns.len() > 1 {
+            include_patterns
+                .into_iter()
+                .filter(|pattern| !(pattern.is_dir && pattern.path == search_root))
+                .collect()
+        } else {
             include_patterns
         };
 
@@ -1349,6 +2798,10 @@ async fn run_cli_mode(cli: Cli) -> Result<()> {
         options.include_patterns = include_patterns.clone();
         options.path = search_root.clone();
 
+        if let Some(queries_file) = &cli.queries_file {
+            return run_query_batch(queries_file, options, &status).await;
+        }
+
         let summary = run_search(pattern.clone(), search_root, options, &status).await?;
 
         if cli.files_without_matches {
@@ -1375,7 +2828,7 @@ async fn run_cli_mode(cli: Cli) -> Result<()> {
             }
         }
 
-        // grep-like exit codes: 0 if matches found, 1 if none
+        // grep-like exit codes: 0 if matches found, 1 if none (2 on error, see main)
         if !summary.had_matches {
             eprintln!("No matches found");
 
@@ -1387,7 +2840,8 @@ async fn run_cli_mode(cli: Cli) -> Result<()> {
 
                 // Get the pattern as a string
                 let options = build_options(&cli, false, repo_root);
-                let highlighted_preview = highlight_matches(&closest.preview, pattern, &options);
+                let highlighted_preview =
+                    highlight_matches(&closest.preview, &closest.match_ranges, pattern, &options);
 
                 // Print in red with same format as regular results, with header
                 eprintln!();
@@ -1401,23 +2855,308 @@ async fn run_cli_mode(cli: Cli) -> Result<()> {
                 );
             }
 
-            std::process::exit(1);
+            std::process::exit(EXIT_NO_MATCHES);
         }
     } else {
         eprintln!("Error: No pattern specified");
-        std::process::exit(1);
+        std::process::exit(EXIT_ERROR);
+    }
+
+    Ok(())
+}
+
+/// Extract (or reuse) the snapshot of `rev` for the repository containing `path`
+fn take_snapshot(path: &Path, rev: &str, status: &StatusReporter) -> Result<ck_engine::Snapshot> {
+    let snapshot = ck_engine::materialize_snapshot(path, rev, &ck_engine::default_snapshot_dir())?;
+    status.info(&format!(
+        "🕰 {} at {} ({}): {}",
+        if snapshot.reused {
+            "Using snapshot"
+        } else {
+            "Extracted snapshot"
+        },
+        rev,
+        &snapshot.commit[..12],
+        snapshot.root.display()
+    ));
+    Ok(snapshot)
+}
+
+fn format_duplicate_location(chunk: &ck_engine::DuplicateChunk) -> String {
+    let location = format!(
+        "{}:{}-{}",
+        chunk.file.display(),
+        chunk.span.line_start,
+        chunk.span.line_end
+    );
+    match &chunk.breadcrumb {
+        Some(crumb) => format!("{} ({})", location, crumb),
+        None => location,
+    }
+}
+
+fn print_duplicates(
+    pairs: &[ck_engine::DuplicatePair],
+    clusters: bool,
+    options: &SearchOptions,
+) -> Result<()> {
+    let json = options.json_output || options.jsonl_output;
+
+    if clusters {
+        for (index, cluster) in ck_engine::cluster_duplicates(pairs).iter().enumerate() {
+            if json {
+                println!("{}", serde_json::to_string(cluster)?);
+                continue;
+            }
+            println!(
+                "{} {} chunks, best {}",
+                style(format!("Cluster {}:", index + 1)).bold(),
+                cluster.chunks.len(),
+                style(format!("[{:.3}]", cluster.score)).cyan()
+            );
+            for chunk in &cluster.chunks {
+                println!("  {}", format_duplicate_location(chunk));
+            }
+        }
+        return Ok(());
     }
 
+    for pair in pairs {
+        if json {
+            println!("{}", serde_json::to_string(pair)?);
+        } else {
+            println!(
+                "{} {}  ↔  {}",
+                style(format!("[{:.3}]", pair.score)).cyan(),
+                format_duplicate_location(&pair.first),
+                format_duplicate_location(&pair.second)
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_top_chunks(
+    chunks: &[ck_engine::ChunkSize],
+    measure: ck_engine::ChunkSizeMeasure,
+    options: &SearchOptions,
+) -> Result<()> {
+    let json = options.json_output || options.jsonl_output;
+    let unit = match measure {
+        ck_engine::ChunkSizeMeasure::Tokens => "tokens",
+        ck_engine::ChunkSizeMeasure::Lines => "lines",
+        ck_engine::ChunkSizeMeasure::Bytes => "bytes",
+    };
+
+    for chunk in chunks {
+        if json {
+            println!("{}", serde_json::to_string(chunk)?);
+            continue;
+        }
+        let size = chunk
+            .size(measure)
+            .map_or_else(|| "?".to_string(), |size| size.to_string());
+        let location = format!(
+            "{}:{}-{}",
+            chunk.file.display(),
+            chunk.span.line_start,
+            chunk.span.line_end
+        );
+        let location = match &chunk.breadcrumb {
+            Some(crumb) => format!("{} ({})", location, crumb),
+            None => location,
+        };
+        println!(
+            "{}  {}",
+            style(format!("{:>7} {}", size, unit)).cyan(),
+            location
+        );
+    }
+    Ok(())
+}
+
+/// `--push-index`: publish the index at `path` to `location`
+fn push_index_to(status: &StatusReporter, path: &Path, location: &str) -> Result<()> {
+    status.section_header("Publishing Index");
+    let storage = ck_index::open_storage(location)?;
+    let spinner = status.create_spinner(&format!("Uploading to {}...", location));
+    let stats = ck_index::push_index(path, storage.as_ref())?;
+    status.finish_progress(spinner, "Index published");
+    status.success(&format!(
+        "Published generation {}: {} of {} files uploaded, the rest were already there",
+        stats.generation, stats.uploaded, stats.files
+    ));
     Ok(())
 }
 
+/// `--remote`: bring the index published at `location` into `path`
+fn pull_index_from(status: &StatusReporter, path: &Path, location: &str) -> Result<()> {
+    let storage = ck_index::open_storage(location)?;
+    let spinner = status.create_spinner(&format!("Checking {} for a newer index...", location));
+    let stats = ck_index::pull_index(path, storage.as_ref())
+        .map_err(|e| anyhow::anyhow!("Failed to pull the index from {}: {}", location, e))?;
+    if stats.up_to_date {
+        status.finish_progress(spinner, "Index is up to date");
+    } else {
+        status.finish_progress(spinner, "Index updated");
+        status.info(&format!(
+            "Pulled generation {}: {} of {} files fetched, the rest reused",
+            stats.generation, stats.downloaded, stats.files
+        ));
+    }
+    Ok(())
+}
+
+/// The query for `ck -` / `--query-stdin`: all of stdin, trimmed
+fn read_stdin_query() -> Result<String> {
+    let mut buffer = String::new();
+    std::io::Read::read_to_string(&mut std::io::stdin(), &mut buffer)
+        .map_err(|e| anyhow::anyhow!("Failed to read the query from stdin: {}", e))?;
+    let query = buffer.trim();
+    if query.is_empty() {
+        anyhow::bail!("The query read from stdin is empty");
+    }
+    Ok(query.to_string())
+}
+
+/// Read the example code for `--like-file` / `--like-snippet`, if either was given
+fn load_example_query(cli: &Cli) -> Result<Option<String>> {
+    let example = if let Some(path) = &cli.like_file {
+        std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read --like-file {}: {}", path.display(), e))?
+    } else if let Some(snippet) = &cli.like_snippet {
+        if snippet == "-" {
+            let mut buffer = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut buffer)?;
+            buffer
+        } else {
+            snippet.clone()
+        }
+    } else {
+        return Ok(None);
+    };
+
+    if example.trim().is_empty() {
+        anyhow::bail!("The example code to search for is empty");
+    }
+
+    Ok(Some(example))
+}
+
+/// `--pager` or `--no-pager`, or `None` to page when stdout is a terminal.
+/// Only asked once the run is known to print search results or a listing.
+fn pager_choice(cli: &Cli) -> Option<bool> {
+    (cli.pager || cli.no_pager).then_some(cli.pager)
+}
+
+/// `--list` is a structural search with an empty query, which matches every
+/// named chunk; a positional pattern is one more path to list
+fn apply_list_mode(cli: &mut Cli) {
+    if !cli.list {
+        return;
+    }
+    if let Some(pattern) = cli.pattern.take() {
+        cli.files.insert(0, PathBuf::from(pattern));
+    }
+    cli.pattern = Some(String::new());
+    cli.no_semantic = true;
+    if cli.sort.is_none() {
+        cli.sort = Some("path".to_string());
+    }
+}
+
+/// With `--queries-file` the queries come from the file, so a positional
+/// pattern is one more path to search
+fn apply_queries_file_mode(cli: &mut Cli) {
+    if cli.queries_file.is_none() {
+        return;
+    }
+    if let Some(pattern) = cli.pattern.take() {
+        cli.files.insert(0, PathBuf::from(pattern));
+    }
+    cli.pattern = Some(String::new());
+}
+
+/// With `--query-stdin` the query comes from stdin, so a positional pattern
+/// is one more path to search
+fn apply_query_stdin_mode(cli: &mut Cli) {
+    if !cli.query_stdin {
+        return;
+    }
+    if let Some(pattern) = cli.pattern.take() {
+        cli.files.insert(0, PathBuf::from(pattern));
+    }
+}
+
+/// Fill in anything `ck.toml` sets that wasn't given as a flag
+fn apply_config_defaults(cli: &mut Cli, config: &config::Config) {
+    let mode_chosen = cli.semantic
+        || cli.lexical
+        || cli.hybrid
+        || cli.regex
+        || cli.no_semantic
+        || cli.like_file.is_some()
+        || cli.like_snippet.is_some()
+        || cli.diff.is_some();
+    if !mode_chosen && let Some(mode) = &config.mode {
+        match mode {
+            SearchMode::Regex => cli.regex = true,
+            SearchMode::Lexical => cli.lexical = true,
+            SearchMode::Semantic => cli.semantic = true,
+            SearchMode::Hybrid => cli.hybrid = true,
+            SearchMode::Structural => cli.no_semantic = true,
+        }
+    }
+
+    // Type filters only apply to chunk-based searches, so a configured default
+    // doesn't turn every plain grep into an error
+    let semantic =
+        cli.semantic || cli.like_file.is_some() || cli.like_snippet.is_some() || cli.diff.is_some();
+    let chunk_based = semantic || cli.no_semantic || (cli.hybrid && !cli.lexical);
+    if cli.chunk_types.is_empty()
+        && chunk_based
+        && let Some(types) = &config.types
+    {
+        cli.chunk_types = types.clone();
+    }
+
+    if cli.threshold.is_none() {
+        cli.threshold = config.min_score;
+    }
+    if cli.top_k.is_none() {
+        cli.top_k = config.limit;
+    }
+    if cli.test_patterns.is_empty()
+        && let Some(patterns) = &config.test_patterns
+    {
+        cli.test_patterns = patterns.clone();
+    }
+    if cli.model.is_none() {
+        cli.model = config.model.clone();
+    }
+}
+
+/// Smart case unless `-i` or `-s` says otherwise: ignore case when the pattern
+/// has no uppercase letter.
+fn ignores_case(cli: &Cli) -> bool {
+    if cli.ignore_case || cli.case_sensitive {
+        return cli.ignore_case;
+    }
+    let pattern = cli.pattern.as_deref().unwrap_or_default();
+    // -w escapes the pattern like -F does
+    !ck_core::pattern_has_uppercase(pattern, cli.fixed_strings || cli.word_regexp)
+}
+
 fn build_options(cli: &Cli, reindex: bool, _repo_root: Option<&Path>) -> SearchOptions {
-    let mode = if cli.semantic {
+    let search_by_example = cli.like_file.is_some() || cli.like_snippet.is_some();
+    let mode = if cli.semantic || search_by_example || cli.diff.is_some() {
         SearchMode::Semantic
     } else if cli.lexical {
         SearchMode::Lexical
     } else if cli.hybrid {
         SearchMode::Hybrid
+    } else if cli.no_semantic {
+        SearchMode::Structural
     } else {
         SearchMode::Regex
     };
@@ -1431,7 +3170,8 @@ fn build_options(cli: &Cli, reindex: bool, _repo_root: Option<&Path>) -> SearchO
 
     // Set intelligent defaults for semantic search
     let default_topk = match mode {
-        SearchMode::Semantic => Some(10),
+        SearchMode::Structural if cli.list => None,
+        SearchMode::Semantic | SearchMode::Structural => Some(10),
         _ => None,
     };
     let default_threshold = match mode {
@@ -1445,9 +3185,10 @@ fn build_options(cli: &Cli, reindex: bool, _repo_root: Option<&Path>) -> SearchO
         path: PathBuf::from("."),
         top_k: cli.top_k.or(default_topk),
         threshold: cli.threshold.or(default_threshold),
-        case_insensitive: cli.ignore_case,
+        case_insensitive: ignores_case(cli),
         whole_word: cli.word_regexp,
         fixed_string: cli.fixed_strings,
+        multiline: cli.multiline,
         line_numbers: cli.line_numbers,
         context_lines: context,
         before_context_lines: before_context,
@@ -1455,6 +3196,9 @@ fn build_options(cli: &Cli, reindex: bool, _repo_root: Option<&Path>) -> SearchO
         recursive: cli.recursive,
         json_output: cli.json || cli.json_v1,
         jsonl_output: cli.jsonl,
+        vimgrep_output: cli.vimgrep,
+        compact_output: cli.compact,
+        markdown_rows: cli.markdown.then_some(cli.markdown_rows),
         no_snippet: cli.no_snippet,
         reindex,
         show_scores: cli.show_scores,
@@ -1463,17 +3207,67 @@ fn build_options(cli: &Cli, reindex: bool, _repo_root: Option<&Path>) -> SearchO
         files_without_matches: cli.files_without_matches,
         exclude_patterns,
         include_patterns: Vec::new(),
+        globs: cli.globs.clone(),
         respect_gitignore: !cli.no_ignore,
         use_ckignore: !cli.no_ckignore,
+        follow_symlinks: cli.follow_symlinks,
+        archives: cli.archives,
         full_section: cli.full_section,
         // Enhanced embedding options (search-time only)
         rerank: cli.rerank,
         rerank_model: cli.rerank_model.clone(),
         embedding_model: cli.model.clone(),
+        search_by_example,
+        example_language: cli
+            .like_file
+            .as_deref()
+            .and_then(ck_core::Language::from_path),
+        example_file: cli.like_file.clone(),
+        scopes: cli.scope.clone(),
+        chunk_filter: if cli.docs_only {
+            ck_core::ChunkFilter::DocsOnly
+        } else if cli.code_only {
+            ck_core::ChunkFilter::CodeOnly
+        } else {
+            ck_core::ChunkFilter::All
+        },
+        chunk_types: cli.chunk_types.clone(),
+        diff_range: cli.diff.clone(),
+        sort: cli.sort.as_deref().and_then(ck_core::ResultSort::from_name),
+        metric: cli
+            .metric
+            .as_deref()
+            .and_then(ck_core::SimilarityMetric::from_name),
+        highlight_terms: cli.highlight_terms,
+        exact_tokens: cli.exact_tokens,
+        merge_adjacent: cli.merge_adjacent,
+        blame: cli.blame,
+        show_enclosing: cli.show_enclosing,
+        recent: cli.recent.map(|window| ck_core::RecentFiles {
+            window,
+            mode: ck_core::RecentMode::from_name(&cli.recent_mode)
+                .unwrap_or(ck_core::RecentMode::Filter),
+        }),
+        references: cli.references,
+        tests: if cli.no_tests {
+            ck_core::TestFilter::Exclude
+        } else if cli.tests_only {
+            ck_core::TestFilter::Only
+        } else {
+            ck_core::TestFilter::All
+        },
+        test_patterns: cli.test_patterns.clone(),
     }
 }
 
-fn highlight_matches(text: &str, pattern: &str, options: &SearchOptions) -> String {
+/// `term_ranges` are the byte ranges of `text` sharing words with the query
+/// (see `--highlight-terms`), underlined in semantic and hybrid results
+fn highlight_matches(
+    text: &str,
+    term_ranges: &[(usize, usize)],
+    pattern: &str,
+    options: &SearchOptions,
+) -> String {
     // Don't highlight if this is JSON/JSONL output
     if options.json_output || options.jsonl_output {
         return text.to_string();
@@ -1481,6 +3275,9 @@ fn highlight_matches(text: &str, pattern: &str, options: &SearchOptions) -> Stri
 
     match options.mode {
         SearchMode::Regex => highlight_regex_matches(text, pattern, options),
+        SearchMode::Semantic | SearchMode::Hybrid if !term_ranges.is_empty() => {
+            highlight_query_terms(text, term_ranges, pattern, options)
+        }
         SearchMode::Semantic | SearchMode::Hybrid => {
             // For semantic/hybrid search, use subchunk similarity highlighting
             highlight_semantic_chunks(text, pattern, options)
@@ -1502,13 +3299,20 @@ fn highlight_regex_matches(text: &str, pattern: &str, options: &SearchOptions) -
 
     let regex_result = RegexBuilder::new(&regex_pattern)
         .case_insensitive(options.case_insensitive)
+        .multi_line(options.multiline)
+        .crlf(options.multiline)
         .build();
 
     match regex_result {
         Ok(re) => {
-            // Replace matches with highlighted versions
+            // Replace matches with highlighted versions, styling each line of a
+            // multiline match on its own so no escape code spans a newline
             re.replace_all(text, |caps: &regex::Captures| {
-                style(&caps[0]).red().bold().to_string()
+                caps[0]
+                    .split('\n')
+                    .map(|piece| style(piece).red().bold().to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
             })
             .to_string()
         }
@@ -1535,6 +3339,27 @@ fn highlight_semantic_chunks(text: &str, pattern: &str, _options: &SearchOptions
     highlighted_tokens.join("")
 }
 
+/// The similarity heatmap, with the words shared with the query underlined
+fn highlight_query_terms(
+    text: &str,
+    term_ranges: &[(usize, usize)],
+    pattern: &str,
+    options: &SearchOptions,
+) -> String {
+    let mut highlighted = String::new();
+    let mut at = 0;
+    for &(start, end) in term_ranges {
+        let (Some(before), Some(term)) = (text.get(at..start), text.get(start..end)) else {
+            continue;
+        };
+        highlighted.push_str(&highlight_semantic_chunks(before, pattern, options));
+        highlighted.push_str(&style(term).underlined().bold().to_string());
+        at = end;
+    }
+    highlighted.push_str(&highlight_semantic_chunks(&text[at..], pattern, options));
+    highlighted
+}
+
 fn apply_heatmap_color(token: &str, score: f32) -> String {
     if token.trim().is_empty() || token.chars().all(|c| !c.is_alphanumeric()) {
         return token.to_string();
@@ -1555,12 +3380,244 @@ fn apply_heatmap_color(token: &str, score: f32) -> String {
     }
 }
 
+/// Best score first; ties (e.g. every regex match) fall back to path and line so
+/// repeated runs list matches in the same order
+fn sort_by_score_then_location(results: &mut [&ck_core::SearchResult]) {
+    results.sort_by(|a, b| ck_core::compare_by_score(a, b));
+}
+
+/// Render results as a GitHub/GitLab-flavoured Markdown table with at most `rows`
+/// rows, summarizing the rest in a footer
+fn format_markdown_table(
+    results: &[&ck_core::SearchResult],
+    rows: usize,
+    mode: &SearchMode,
+) -> String {
+    let mut table =
+        String::from("| File | Symbol | Lines | Score | Preview |\n|---|---|---|---:|---|\n");
+    for result in results.iter().take(rows) {
+        let span = &result.span;
+        let lines = if span.line_start == span.line_end {
+            span.line_start.to_string()
+        } else {
+            format!("{}-{}", span.line_start, span.line_end)
+        };
+        let score = if matches!(mode, SearchMode::Regex) {
+            "-".to_string()
+        } else {
+            format!("{:.3}", result.score)
+        };
+        let symbol = result
+            .breadcrumb
+            .as_deref()
+            .filter(|crumb| !crumb.is_empty())
+            .map(markdown_code)
+            .unwrap_or_default();
+        let preview = result
+            .preview
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(|line| markdown_code(&truncate_chars(line, MARKDOWN_PREVIEW_CHARS)))
+            .unwrap_or_default();
+
+        table.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            markdown_code(&result.file.display().to_string()),
+            symbol,
+            lines,
+            score,
+            preview
+        ));
+    }
+
+    if results.len() > rows {
+        table.push_str(&format!("\n…and {} more\n", results.len() - rows));
+    }
+    table.trim_end().to_string()
+}
+
+/// Characters of the first preview line kept in a Markdown table cell
+const MARKDOWN_PREVIEW_CHARS: usize = 80;
+
+/// Wrap text in a code span that survives inside a table cell
+fn markdown_code(text: &str) -> String {
+    let text = text.replace('|', "\\|");
+    if text.contains('`') {
+        format!("`` {} ``", text)
+    } else {
+        format!("`{}`", text)
+    }
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    if text.chars().count() > max {
+        format!("{}…", text.chars().take(max).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+/// Format a result as `path:line:col: message` for Vim/Neovim quickfix lists.
+/// The message is the chunk breadcrumb when known, otherwise the first matched line;
+/// ranked modes append the score.
+fn format_vimgrep_line(result: &ck_core::SearchResult, mode: &SearchMode) -> String {
+    let message = result
+        .breadcrumb
+        .clone()
+        .filter(|crumb| !crumb.is_empty())
+        .unwrap_or_else(|| {
+            result
+                .preview
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .unwrap_or_default()
+                .to_string()
+        });
+
+    let score = if matches!(mode, SearchMode::Regex) {
+        String::new()
+    } else {
+        format!(" ({:.3})", result.score)
+    };
+
+    format!(
+        "{}:{}:1: {}{}",
+        result.file.display(),
+        result.span.line_start,
+        message,
+        score
+    )
+}
+
+/// Format a result as `score  path:line  breadcrumb  — preview` for `--compact`,
+/// colored on a terminal. The preview is the first line of the result that
+/// isn't blank or only braces; regex results have no score and results without
+/// a breadcrumb skip that column.
+fn format_compact_line(result: &ck_core::SearchResult, mode: &SearchMode) -> String {
+    let mut line = String::new();
+    if !matches!(mode, SearchMode::Regex) {
+        line.push_str(&format!(
+            "{}  ",
+            style(format!("{:.3}", result.score)).yellow()
+        ));
+    }
+    line.push_str(&format!(
+        "{}:{}",
+        style(result.file.display()).cyan().bold(),
+        style(result.span.line_start).yellow()
+    ));
+    if let Some(crumb) = result
+        .breadcrumb
+        .as_deref()
+        .filter(|crumb| !crumb.is_empty())
+    {
+        line.push_str(&format!("  {}", style(crumb).magenta()));
+    }
+    let preview = result
+        .preview
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.chars().all(|c| "{}()[];,".contains(c)));
+    if let Some(preview) = preview {
+        line.push_str(&format!(
+            "  {} {}",
+            style("—").dim(),
+            truncate_chars(preview, COMPACT_PREVIEW_CHARS)
+        ));
+    }
+    line
+}
+
+/// Characters of the preview line kept by `--compact`
+const COMPACT_PREVIEW_CHARS: usize = 120;
+
+/// The lines a `--merge-adjacent` result covers, with the chunk outline
+/// `--dump-chunks` draws; `None` when the file can't be read and chunked
+fn merged_range_rows(result: &ck_core::SearchResult) -> Option<Vec<String>> {
+    let (lines, chunks) = ck_tui::chunk_file_live(&result.file).ok()?;
+    let rows = ck_tui::chunks::collect_chunk_display_lines(
+        &lines,
+        result.span.line_start.saturating_sub(1),
+        result.span.line_end,
+        result.span.line_start,
+        None,
+        &chunks,
+        true,
+    );
+    Some(
+        rows.iter()
+            .map(ck_tui::chunks::chunk_display_line_to_string)
+            .collect(),
+    )
+}
+
 struct SearchSummary {
     had_matches: bool,
     closest_below_threshold: Option<ck_core::SearchResult>,
     matched_paths: Vec<PathBuf>,
 }
 
+/// One `--queries-file` query and its results, a line of NDJSON
+#[derive(serde::Serialize)]
+struct QueryBatchLine<'a> {
+    query: &'a str,
+    hits: Vec<ck_core::JsonlSearchResult>,
+}
+
+/// Run every non-blank line of `queries_file` as a query with the same
+/// options, printing each query's results as it finishes. Semantic queries
+/// share the model the first one loads, and `--reindex` updates the index once.
+async fn run_query_batch(
+    queries_file: &Path,
+    mut options: SearchOptions,
+    status: &StatusReporter,
+) -> Result<()> {
+    let content = std::fs::read_to_string(queries_file).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to read --queries-file {}: {}",
+            queries_file.display(),
+            e
+        )
+    })?;
+    let queries: Vec<&str> = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    if options.reindex {
+        let reindex_spinner = status.create_spinner("Updating index...");
+        let file_options = ck_core::FileCollectionOptions::from(&options);
+        ck_index::update_index(&options.path, true, &file_options).await?;
+        status.finish_progress(reindex_spinner, "Index updated");
+        options.reindex = false;
+    }
+
+    let spinner = status.create_spinner(&format!("Running {} queries...", queries.len()));
+    for (done, query) in queries.iter().enumerate() {
+        if let Some(spinner) = &spinner {
+            spinner.set_message(format!("Query {}/{}: {}", done + 1, queries.len(), query));
+        }
+        options.query = query.to_string();
+        let results = ck_engine::search_enhanced(&options).await?;
+        let line = QueryBatchLine {
+            query,
+            hits: results
+                .matches
+                .iter()
+                .map(|result| {
+                    ck_core::JsonlSearchResult::from_search_result(result, !options.no_snippet)
+                })
+                .collect(),
+        };
+        println!("{}", serde_json::to_string(&line)?);
+    }
+    status.finish_progress(spinner, &format!("Ran {} queries", queries.len()));
+    Ok(())
+}
+
 async fn run_search(
     pattern: String,
     path: PathBuf,
@@ -1577,6 +3634,16 @@ async fn run_search(
         status.finish_progress(reindex_spinner, "Index updated");
     }
 
+    if let Some(range) = options.diff_range.as_deref() {
+        let touched = ck_engine::diff_chunks(&options)?;
+        eprintln!(
+            "🔀 Diff {}: {} chunks touched in {} files",
+            range,
+            touched.chunks.len(),
+            touched.files_changed
+        );
+    }
+
     // Show search parameters for semantic mode
     if matches!(
         options.mode,
@@ -1747,9 +3814,38 @@ async fn run_search(
                 },
                 preview: result.preview.clone(),
                 model: "none".to_string(),
+                match_ranges: result.match_ranges.clone(),
+                blame: result.blame.clone(),
             };
             println!("{}", serde_json::to_string(&json_result)?);
         }
+    } else if let Some(rows) = options.markdown_rows {
+        let mut ordered: Vec<&ck_core::SearchResult> = results.iter().collect();
+        if options.sort.is_none() {
+            sort_by_score_then_location(&mut ordered);
+        }
+        has_matches = !ordered.is_empty();
+        if has_matches {
+            println!("{}", format_markdown_table(&ordered, rows, &options.mode));
+        }
+    } else if options.compact_output {
+        let mut ordered: Vec<&ck_core::SearchResult> = results.iter().collect();
+        if options.sort.is_none() {
+            sort_by_score_then_location(&mut ordered);
+        }
+        for result in ordered {
+            has_matches = true;
+            println!("{}", format_compact_line(result, &options.mode));
+        }
+    } else if options.vimgrep_output {
+        let mut ordered: Vec<&ck_core::SearchResult> = results.iter().collect();
+        if options.sort.is_none() {
+            sort_by_score_then_location(&mut ordered);
+        }
+        for result in ordered {
+            has_matches = true;
+            println!("{}", format_vimgrep_line(result, &options.mode));
+        }
     } else if options.files_with_matches {
         // For -l flag: print only unique filenames that have matches
         let mut printed_files = std::collections::HashSet::new();
@@ -1767,13 +3863,44 @@ async fn run_search(
         // Normal output
         for result in results {
             has_matches = true;
-            let score_text = if options.show_scores {
+            let mut score_text = if options.show_scores {
                 format!("[{:.3}] ", result.score)
             } else {
                 String::new()
             };
+            if let Some(blame) = result.blame.as_ref() {
+                score_text.push_str(&format!(
+                    "{} ",
+                    style(format!("[{} {}]", blame.author, blame.date)).magenta()
+                ));
+            }
 
-            let highlighted_preview = highlight_matches(&result.preview, &options.query, &options);
+            if options.merge_adjacent.is_some()
+                && let Some(rows) = merged_range_rows(result)
+            {
+                let lines = if result.span.line_start == result.span.line_end {
+                    result.span.line_start.to_string()
+                } else {
+                    format!("{}-{}", result.span.line_start, result.span.line_end)
+                };
+                println!(
+                    "{}{}:{}",
+                    score_text,
+                    style(result.file.display()).cyan().bold(),
+                    style(lines).yellow()
+                );
+                for row in rows {
+                    println!("{}", row);
+                }
+                continue;
+            }
+
+            let highlighted_preview = highlight_matches(
+                &result.preview,
+                &result.match_ranges,
+                &options.query,
+                &options,
+            );
 
             // Format output based on options
             if options.line_numbers && options.show_filenames {
@@ -1824,6 +3951,20 @@ mod tests {
     use crate::path_utils::{self, expand_glob_patterns_with_base};
     use tempfile::tempdir;
 
+    #[test]
+    fn test_pager_choice_follows_the_last_pager_flag() {
+        let choice = |args: &[&str]| {
+            let cli =
+                Cli::try_parse_from(std::iter::once("ck").chain(args.iter().copied())).unwrap();
+            pager_choice(&cli)
+        };
+        assert_eq!(choice(&["hello", "."]), None);
+        assert_eq!(choice(&["--pager", "hello", "."]), Some(true));
+        assert_eq!(choice(&["--no-pager", "hello", "."]), Some(false));
+        assert_eq!(choice(&["--no-pager", "--pager", "hello"]), Some(true));
+        assert_eq!(choice(&["--pager", "--no-pager", "hello"]), Some(false));
+    }
+
     #[test]
     fn test_expand_glob_patterns_supports_semicolon_lists() {
         let temp_dir = tempdir().unwrap();
@@ -1868,6 +4009,253 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_compact_lines_skip_blank_and_brace_lines() {
+        let result = |preview: &str, crumb: Option<&str>| ck_core::SearchResult {
+            file: PathBuf::from("src/net.rs"),
+            span: ck_core::Span::new_unchecked(0, 0, 12, 20),
+            score: 0.8421,
+            preview: preview.to_string(),
+            breadcrumb: crumb.map(str::to_string),
+            ..Default::default()
+        };
+        let plain = |result: &ck_core::SearchResult, mode: &SearchMode| {
+            console::strip_ansi_codes(&format_compact_line(result, mode)).into_owned()
+        };
+
+        let chunk = result("\n  {\n\n    fn connect() {\n", Some("net::Client"));
+        assert_eq!(
+            plain(&chunk, &SearchMode::Semantic),
+            "0.842  src/net.rs:12  net::Client  — fn connect() {"
+        );
+        let line = result("todo: retry", None);
+        assert_eq!(
+            plain(&line, &SearchMode::Regex),
+            "src/net.rs:12  — todo: retry"
+        );
+    }
+
+    #[test]
+    fn test_vimgrep_lines_are_quickfix_formatted_and_stable() {
+        let result =
+            |file: &str, line: usize, score: f32, crumb: Option<&str>| ck_core::SearchResult {
+                file: PathBuf::from(file),
+                span: ck_core::Span::new_unchecked(0, 0, line, line + 2),
+                score,
+                preview: "\n    fn connect() {\n".to_string(),
+                breadcrumb: crumb.map(str::to_string),
+                ..Default::default()
+            };
+
+        let with_crumb = result("src/net.rs", 12, 0.8421, Some("net::Client"));
+        assert_eq!(
+            format_vimgrep_line(&with_crumb, &SearchMode::Semantic),
+            "src/net.rs:12:1: net::Client (0.842)"
+        );
+        let without_crumb = result("src/net.rs", 3, 1.0, None);
+        assert_eq!(
+            format_vimgrep_line(&without_crumb, &SearchMode::Regex),
+            "src/net.rs:3:1: fn connect() {"
+        );
+
+        let (a, b, c) = (
+            result("b.rs", 9, 1.0, None),
+            result("a.rs", 20, 1.0, None),
+            result("a.rs", 4, 1.0, None),
+        );
+        let mut ordered = vec![&a, &b, &c];
+        sort_by_score_then_location(&mut ordered);
+        let keys: Vec<_> = ordered
+            .iter()
+            .map(|r| (r.file.clone(), r.span.line_start))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                (PathBuf::from("a.rs"), 4),
+                (PathBuf::from("a.rs"), 20),
+                (PathBuf::from("b.rs"), 9)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markdown_table_escapes_cells_and_summarizes_overflow() {
+        let result = |file: &str, line_end: usize, preview: &str| ck_core::SearchResult {
+            file: PathBuf::from(file),
+            span: ck_core::Span::new_unchecked(0, 0, 7, line_end),
+            score: 0.91234,
+            preview: preview.to_string(),
+            breadcrumb: Some("parse|args".to_string()),
+            ..Default::default()
+        };
+
+        let first = result("src/cli.rs", 12, "\n  let a = b || c;\n");
+        let second = result("src/lib.rs", 7, "let s = `x`;");
+        let third = result("src/main.rs", 9, "");
+        let table = format_markdown_table(&[&first, &second, &third], 2, &SearchMode::Semantic);
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines[0], "| File | Symbol | Lines | Score | Preview |");
+        assert_eq!(
+            lines[2],
+            "| `src/cli.rs` | `parse\\|args` | 7-12 | 0.912 | `let a = b \\|\\| c;` |"
+        );
+        assert_eq!(
+            lines[3],
+            "| `src/lib.rs` | `parse\\|args` | 7 | 0.912 | `` let s = `x`; `` |"
+        );
+        assert_eq!(lines.last(), Some(&"…and 1 more"));
+
+        let regex = format_markdown_table(&[&first], 10, &SearchMode::Regex);
+        assert!(regex.contains("| 7-12 | - |"));
+        assert!(!regex.contains("more"));
+    }
+
+    #[test]
+    fn test_stable_chunk_dump_is_sorted_and_size_free() {
+        let src = "pub struct Point;\n\nimpl Point {\n    fn x(&self) {}\n}\n";
+        let chunks = ck_chunk::chunk_text(src, Some(ck_core::Language::Rust)).unwrap();
+        let mut metas =
+            ck_tui::chunks::convert_chunks_to_meta(chunks, Path::new("p.rs"), src.lines().count());
+        metas.reverse();
+
+        assert_eq!(
+            format_stable_chunk_dump(&metas),
+            "1-1 class Point\n3-5 module Point\n4-5 text -\n4-4 method Point::x\n"
+        );
+    }
+
+    #[test]
+    fn test_split_file_chunks_writes_one_file_per_symbol() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("calc.py");
+        fs::write(
+            &file,
+            "import math\n\nclass Calc:\n    def add(self, a, b):\n        return a + b\n\n    def add(self, a):\n        return a\n\nprint(Calc().add(1, 2))\n",
+        )
+        .unwrap();
+        let out = dir.path().join("parts");
+
+        let written = split_file_chunks(&file, &out).unwrap();
+        let names: Vec<String> = written
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["Calc.py", "Calc.add.py", "Calc.add-2.py", "_misc.py"]
+        );
+        assert_eq!(
+            fs::read_to_string(out.join("Calc.add.py")).unwrap(),
+            "    def add(self, a, b):\n        return a + b\n"
+        );
+        let misc = fs::read_to_string(out.join("_misc.py")).unwrap();
+        assert!(misc.contains("import math"));
+        assert!(misc.contains("print(Calc().add(1, 2))"));
+        assert!(!misc.contains("return a + b"));
+
+        assert_eq!(
+            sanitize_file_stem("impl Display for Span"),
+            "impl_Display_for_Span"
+        );
+    }
+
+    #[test]
+    fn test_format_chunk_vector_as_loadtxt_rows() {
+        let vector = ck_engine::ChunkVector {
+            id: "abc123".to_string(),
+            file: PathBuf::from("src/lib.rs"),
+            span: ck_core::Span::new_unchecked(0, 0, 4, 9),
+            breadcrumb: Some("Parser".to_string()),
+            model: "BAAI/bge-small-en-v1.5".to_string(),
+            dimensions: 3,
+            embedding: vec![0.5, -1.0, 0.25],
+        };
+
+        let text = format_chunk_vector(&vector, "txt").unwrap();
+        let (header, rows): (Vec<&str>, Vec<&str>) =
+            text.lines().partition(|line| line.starts_with('#'));
+        assert_eq!(rows, vec!["0.5 -1 0.25"]);
+        assert!(header.contains(&"# model: BAAI/bge-small-en-v1.5"));
+        assert!(header.contains(&"# dimensions: 3"));
+        assert!(header.contains(&"# chunk: abc123 src/lib.rs:4-9"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format_chunk_vector(&vector, "json").unwrap()).unwrap();
+        assert_eq!(json["dimensions"], 3);
+        assert_eq!(json["embedding"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_export_anchored_chunk_wraps_innermost_chunk() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("calc.py");
+        fs::write(
+            &file,
+            "class Calc:\n    def add(self, a, b):\n        return a + b\n\n    def sub(self, a, b):\n        return a - b\n",
+        )
+        .unwrap();
+
+        let target = format!("{}:3", file.display());
+        let exported = export_anchored_chunk(&target, ck_core::WhitespaceMode::Verbatim).unwrap();
+        let regions = ck_core::anchor::parse_anchored_regions(&exported).unwrap();
+        assert_eq!(regions.len(), 1);
+        assert!(exported.starts_with("# ck:begin "), "{}", exported);
+        assert_eq!(regions[0].anchor.line_start, 2);
+        assert!(regions[0].body.contains("return a + b"));
+        assert!(!regions[0].body.contains("return a - b"));
+
+        let verbatim = ck_core::WhitespaceMode::Verbatim;
+        assert!(export_anchored_chunk(&file.display().to_string(), verbatim).is_err());
+        assert!(export_anchored_chunk(&format!("{}:0", file.display()), verbatim).is_err());
+
+        let go_file = dir.path().join("main.go");
+        fs::write(&go_file, "package main\n\nfunc f() {\n\treturn  \n}\n").unwrap();
+        let go_target = format!("{}:4", go_file.display());
+        let normalized =
+            export_anchored_chunk(&go_target, ck_core::WhitespaceMode::Normalized).unwrap();
+        assert!(normalized.contains("\n    return\n"), "{}", normalized);
+        let kept = export_anchored_chunk(&go_target, verbatim).unwrap();
+        assert!(kept.contains("\n\treturn  \n"), "{}", kept);
+    }
+
+    #[test]
+    fn test_apply_anchored_edits_replaces_unchanged_chunk_only() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("calc.py");
+        let original = "def add(a, b):\n    return a + b\n\n\ndef sub(a, b):\n    return a - b\n";
+        fs::write(&file, original).unwrap();
+
+        let exported = export_anchored_chunk(
+            &format!("{}:2", file.display()),
+            ck_core::WhitespaceMode::Normalized,
+        )
+        .unwrap();
+        let edited = format!(
+            "Sure, here it is:\n```python\n{}```\n",
+            exported.replace("a + b", "int(a) + int(b)")
+        );
+
+        let applied = apply_anchored_edits(&edited).unwrap();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            original.replace("a + b", "int(a) + int(b)")
+        );
+
+        // The chunk has changed now, so the same export no longer applies
+        let err = apply_anchored_edits(&edited).unwrap_err().to_string();
+        assert!(err.contains("has changed since"), "{}", err);
+        assert!(
+            fs::read_to_string(&file)
+                .unwrap()
+                .contains("int(a) + int(b)")
+        );
+
+        assert!(apply_anchored_edits("no anchors here").is_err());
+    }
+
     #[test]
     fn test_highlight_regex_matches_with_valid_pattern() {
         let options = SearchOptions {
@@ -1887,6 +4275,23 @@ mod tests {
         assert!(result.contains("world"));
     }
 
+    #[test]
+    fn test_highlight_query_terms_keeps_text_and_skips_bad_ranges() {
+        let options = SearchOptions {
+            mode: SearchMode::Semantic,
+            ..Default::default()
+        };
+        let text = "fn parse_config() {}";
+        let ranges = ck_core::heatmap::query_term_ranges(text, "config parsing", false);
+        assert_eq!(ranges, vec![(3, 8), (9, 15)]);
+
+        let highlighted = highlight_matches(text, &ranges, "config parsing", &options);
+        assert_eq!(console::strip_ansi_codes(&highlighted), text);
+        // Ranges that don't fit the text are ignored rather than panicking
+        let stale = highlight_matches(text, &[(9, 15), (3, 8), (18, 99)], "x", &options);
+        assert_eq!(console::strip_ansi_codes(&stale), text);
+    }
+
     #[test]
     fn test_highlight_regex_matches_with_invalid_pattern() {
         let options = SearchOptions {
@@ -4,9 +4,8 @@ use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use tokio::sync::RwLock;
 
-// EmbedderCache removed - embedder caching would require deeper integration with ck-engine.
-// Currently, search functions create embedders internally via ck_embed::create_embedder().
-// Future optimization: expose search APIs that accept pre-created embedders for true caching.
+// Loaded embedding models are cached process-wide by ck_embed::cached_embedder(),
+// which the search and indexing paths use, so there's no embedder cache here.
 
 /// Cache for index statistics with TTL
 #[derive(Debug, Clone)]
@@ -37,6 +37,7 @@ impl McpContext {
             case_insensitive: false,
             whole_word: false,
             fixed_string: false,
+            multiline: false,
             line_numbers: false,
             context_lines: 0,
             before_context_lines: 0,
@@ -44,6 +45,9 @@ impl McpContext {
             recursive: true,
             json_output: false,
             jsonl_output: true, // Default to JSONL for agent consumption
+            vimgrep_output: false,
+            compact_output: false,
+            markdown_rows: None,
             no_snippet: false,
             reindex: false,
             show_scores: true,
@@ -52,12 +56,33 @@ impl McpContext {
             files_without_matches: false,
             exclude_patterns: get_default_exclude_patterns(),
             include_patterns: Vec::new(),
+            globs: Vec::new(),
             respect_gitignore: true,
             use_ckignore: true,
+            follow_symlinks: false,
+            archives: false,
             full_section: false,
             rerank: false,
             rerank_model: None,
             embedding_model: None,
+            search_by_example: false,
+            example_language: None,
+            example_file: None,
+            scopes: Vec::new(),
+            chunk_filter: ck_core::ChunkFilter::All,
+            chunk_types: Vec::new(),
+            diff_range: None,
+            sort: None,
+            metric: None,
+            highlight_terms: false,
+            exact_tokens: false,
+            merge_adjacent: None,
+            blame: false,
+            show_enclosing: false,
+            recent: None,
+            references: false,
+            tests: ck_core::TestFilter::All,
+            test_patterns: Vec::new(),
         };
 
         Ok(Self {
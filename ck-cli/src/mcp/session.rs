@@ -447,6 +447,7 @@ mod tests {
             case_insensitive: false,
             whole_word: false,
             fixed_string: false,
+            multiline: false,
             line_numbers: false,
             context_lines: 0,
             before_context_lines: 0,
@@ -454,6 +455,9 @@ mod tests {
             recursive: true,
             json_output: false,
             jsonl_output: false,
+            vimgrep_output: false,
+            compact_output: false,
+            markdown_rows: None,
             no_snippet: false,
             reindex: false,
             show_scores: true,
@@ -462,12 +466,33 @@ mod tests {
             files_without_matches: false,
             exclude_patterns: vec![],
             include_patterns: Vec::new(),
+            globs: Vec::new(),
             respect_gitignore: true,
             use_ckignore: true,
+            follow_symlinks: false,
+            archives: false,
             full_section: false,
             rerank: false,
             rerank_model: None,
             embedding_model: None,
+            search_by_example: false,
+            example_language: None,
+            example_file: None,
+            scopes: Vec::new(),
+            chunk_filter: ck_core::ChunkFilter::All,
+            chunk_types: Vec::new(),
+            diff_range: None,
+            sort: None,
+            metric: None,
+            highlight_terms: false,
+            exact_tokens: false,
+            merge_adjacent: None,
+            blame: false,
+            show_enclosing: false,
+            recent: None,
+            references: false,
+            tests: ck_core::TestFilter::All,
+            test_patterns: Vec::new(),
         }
     }
 
@@ -484,9 +509,7 @@ mod tests {
                 },
                 score: 0.8 - (i as f32 * 0.01),
                 lang: Some(Language::Rust),
-                symbol: None,
-                chunk_hash: None,
-                index_epoch: None,
+                ..Default::default()
             })
             .collect()
     }
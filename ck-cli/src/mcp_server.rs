@@ -34,7 +34,8 @@ const DEFAULT_MCP_TOP_K: usize = 10;
 
 /// Filter out search results from missing files to prevent errors during result processing
 fn filter_valid_results(mut results: Vec<ck_core::SearchResult>) -> Vec<ck_core::SearchResult> {
-    results.retain(|result| result.file.exists());
+    // Archive entries exist as long as their archive does
+    results.retain(|result| ck_core::archive::source_metadata(&result.file).is_ok());
     results
 }
 
@@ -1070,6 +1071,7 @@ impl CkMcpServer {
             case_insensitive: request.case_insensitive.unwrap_or(false),
             whole_word: request.whole_word.unwrap_or(false),
             fixed_string: request.fixed_string.unwrap_or(false),
+            multiline: false,
             line_numbers: false,
             context_lines,
             before_context_lines,
@@ -1077,6 +1079,9 @@ impl CkMcpServer {
             recursive: true,
             json_output: false,
             jsonl_output: true,
+            vimgrep_output: false,
+            compact_output: false,
+            markdown_rows: None,
             no_snippet: !include_snippet,
             reindex: false,
             show_scores: true,
@@ -1085,12 +1090,33 @@ impl CkMcpServer {
             files_without_matches: false,
             exclude_patterns,
             include_patterns,
+            globs: Vec::new(),
             respect_gitignore,
             use_ckignore: true,
+            follow_symlinks: false,
+            archives: false,
             full_section: false,
             rerank: request.rerank.unwrap_or(false),
             rerank_model: request.rerank_model.clone(),
             embedding_model: None,
+            search_by_example: false,
+            example_language: None,
+            example_file: None,
+            scopes: Vec::new(),
+            chunk_filter: ck_core::ChunkFilter::All,
+            chunk_types: Vec::new(),
+            diff_range: None,
+            sort: None,
+            metric: None,
+            highlight_terms: false,
+            exact_tokens: false,
+            merge_adjacent: None,
+            blame: false,
+            show_enclosing: false,
+            recent: None,
+            references: false,
+            tests: ck_core::TestFilter::All,
+            test_patterns: Vec::new(),
         };
 
         // Note: Embedders are created fresh for each request by ck-engine
@@ -1277,6 +1303,7 @@ impl CkMcpServer {
             case_insensitive: request.case_insensitive.unwrap_or(false),
             whole_word: request.whole_word.unwrap_or(false),
             fixed_string: request.fixed_string.unwrap_or(false),
+            multiline: false,
             line_numbers: false,
             context_lines,
             before_context_lines,
@@ -1284,6 +1311,9 @@ impl CkMcpServer {
             recursive: true,
             json_output: false,
             jsonl_output: true,
+            vimgrep_output: false,
+            compact_output: false,
+            markdown_rows: None,
             no_snippet: !include_snippet,
             reindex: false,
             show_scores: true,
@@ -1292,12 +1322,33 @@ impl CkMcpServer {
             files_without_matches: false,
             exclude_patterns,
             include_patterns,
+            globs: Vec::new(),
             respect_gitignore,
             use_ckignore: true,
+            follow_symlinks: false,
+            archives: false,
             full_section: false,
             rerank: false,
             rerank_model: None,
             embedding_model: None,
+            search_by_example: false,
+            example_language: None,
+            example_file: None,
+            scopes: Vec::new(),
+            chunk_filter: ck_core::ChunkFilter::All,
+            chunk_types: Vec::new(),
+            diff_range: None,
+            sort: None,
+            metric: None,
+            highlight_terms: false,
+            exact_tokens: false,
+            merge_adjacent: None,
+            blame: false,
+            show_enclosing: false,
+            recent: None,
+            references: false,
+            tests: ck_core::TestFilter::All,
+            test_patterns: Vec::new(),
         };
 
         let started = Instant::now();
@@ -1412,6 +1463,7 @@ impl CkMcpServer {
             case_insensitive: ignore_case.unwrap_or(false),
             whole_word: request.whole_word.unwrap_or(false),
             fixed_string: request.fixed_string.unwrap_or(false),
+            multiline: false,
             line_numbers: true,
             context_lines,
             before_context_lines: context_lines,
@@ -1419,6 +1471,9 @@ impl CkMcpServer {
             recursive: true,
             json_output: false,
             jsonl_output: true,
+            vimgrep_output: false,
+            compact_output: false,
+            markdown_rows: None,
             no_snippet: !include_snippet,
             reindex: false,
             show_scores: false, // No scores for regex search
@@ -1427,12 +1482,33 @@ impl CkMcpServer {
             files_without_matches: false,
             exclude_patterns,
             include_patterns,
+            globs: Vec::new(),
             respect_gitignore,
             use_ckignore: true,
+            follow_symlinks: false,
+            archives: false,
             full_section: false,
             rerank: false,
             rerank_model: None,
             embedding_model: None,
+            search_by_example: false,
+            example_language: None,
+            example_file: None,
+            scopes: Vec::new(),
+            chunk_filter: ck_core::ChunkFilter::All,
+            chunk_types: Vec::new(),
+            diff_range: None,
+            sort: None,
+            metric: None,
+            highlight_terms: false,
+            exact_tokens: false,
+            merge_adjacent: None,
+            blame: false,
+            show_enclosing: false,
+            recent: None,
+            references: false,
+            tests: ck_core::TestFilter::All,
+            test_patterns: Vec::new(),
         };
 
         // Perform the search (no indexing needed for regex)
@@ -1547,6 +1623,7 @@ impl CkMcpServer {
             case_insensitive: request.case_insensitive.unwrap_or(false),
             whole_word: request.whole_word.unwrap_or(false),
             fixed_string: request.fixed_string.unwrap_or(false),
+            multiline: false,
             line_numbers: false,
             context_lines,
             before_context_lines,
@@ -1554,6 +1631,9 @@ impl CkMcpServer {
             recursive: true,
             json_output: false,
             jsonl_output: true,
+            vimgrep_output: false,
+            compact_output: false,
+            markdown_rows: None,
             no_snippet: !include_snippet,
             reindex: false,
             show_scores: true,
@@ -1562,12 +1642,33 @@ impl CkMcpServer {
             files_without_matches: false,
             exclude_patterns,
             include_patterns,
+            globs: Vec::new(),
             respect_gitignore,
             use_ckignore: true,
+            follow_symlinks: false,
+            archives: false,
             full_section: false,
             rerank: request.rerank.unwrap_or(false),
             rerank_model: request.rerank_model.clone(),
             embedding_model: None,
+            search_by_example: false,
+            example_language: None,
+            example_file: None,
+            scopes: Vec::new(),
+            chunk_filter: ck_core::ChunkFilter::All,
+            chunk_types: Vec::new(),
+            diff_range: None,
+            sort: None,
+            metric: None,
+            highlight_terms: false,
+            exact_tokens: false,
+            merge_adjacent: None,
+            blame: false,
+            show_enclosing: false,
+            recent: None,
+            references: false,
+            tests: ck_core::TestFilter::All,
+            test_patterns: Vec::new(),
         };
 
         // Perform the search (suppress progress callbacks for MCP)
@@ -1802,6 +1903,7 @@ impl CkMcpServer {
             case_insensitive: false,
             whole_word: false,
             fixed_string: false,
+            multiline: false,
             line_numbers: false,
             context_lines: 0,
             before_context_lines: 0,
@@ -1809,6 +1911,9 @@ impl CkMcpServer {
             recursive: true,
             json_output: false,
             jsonl_output: true,
+            vimgrep_output: false,
+            compact_output: false,
+            markdown_rows: None,
             no_snippet: false,
             reindex: force, // Use the force parameter directly
             show_scores: false,
@@ -1817,12 +1922,33 @@ impl CkMcpServer {
             files_without_matches: false,
             exclude_patterns: get_default_exclude_patterns(),
             include_patterns: Vec::new(),
+            globs: Vec::new(),
             respect_gitignore: true,
             use_ckignore: true,
+            follow_symlinks: false,
+            archives: false,
             full_section: false,
             rerank: false,
             rerank_model: None,
             embedding_model: None,
+            search_by_example: false,
+            example_language: None,
+            example_file: None,
+            scopes: Vec::new(),
+            chunk_filter: ck_core::ChunkFilter::All,
+            chunk_types: Vec::new(),
+            diff_range: None,
+            sort: None,
+            metric: None,
+            highlight_terms: false,
+            exact_tokens: false,
+            merge_adjacent: None,
+            blame: false,
+            show_enclosing: false,
+            recent: None,
+            references: false,
+            tests: ck_core::TestFilter::All,
+            test_patterns: Vec::new(),
         };
 
         // Perform reindexing
@@ -0,0 +1,144 @@
+//! Paging long output: the command runs again as a child process whose stdout
+//! feeds `$PAGER`, with colors forced on since the child no longer writes to a
+//! terminal.
+
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::process::{Command, ExitStatus, Stdio};
+
+/// Set in the child so it prints instead of paging again
+const PAGER_CHILD_ENV: &str = "CK_PAGER_CHILD";
+
+/// Whether the output should go through a pager: `--pager` or `--no-pager`
+/// when given, otherwise whenever stdout is a terminal
+pub fn wants_pager(choice: Option<bool>) -> bool {
+    if std::env::var_os(PAGER_CHILD_ENV).is_some() {
+        return false;
+    }
+    choice.unwrap_or_else(|| std::io::stdout().is_terminal())
+}
+
+/// The pager command and its arguments from `$PAGER`, defaulting to `less`.
+/// An empty `$PAGER` or `cat` turns paging off.
+fn pager_command(pager: Option<OsString>) -> Option<Vec<String>> {
+    let pager = pager
+        .map(|pager| pager.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "less".to_string());
+    let words = shlex::split(&pager)?;
+    match words.first().map(String::as_str) {
+        None | Some("cat") => None,
+        Some(_) => Some(words),
+    }
+}
+
+/// Run this same command with its output piped through the pager and return
+/// the exit code it finished with, or `None` when there's no pager to run
+pub fn run_through_pager() -> std::io::Result<Option<i32>> {
+    let Some(words) = pager_command(std::env::var_os("PAGER")) else {
+        return Ok(None);
+    };
+
+    let mut pager = Command::new(&words[0]);
+    pager.args(&words[1..]).stdin(Stdio::piped());
+    // Like git: keep colors, quit when it fits on one screen, leave it on screen
+    if std::env::var_os("LESS").is_none() {
+        pager.env("LESS", "FRX");
+    }
+    let Ok(mut pager) = pager.spawn() else {
+        return Ok(None);
+    };
+    let Some(pager_input) = pager.stdin.take() else {
+        return Ok(None);
+    };
+
+    let mut child = Command::new(std::env::current_exe()?)
+        .args(std::env::args_os().skip(1))
+        .env(PAGER_CHILD_ENV, "1")
+        .env("CLICOLOR_FORCE", "1")
+        .stdout(Stdio::from(pager_input))
+        .spawn()?;
+    // Like git, leave Ctrl-C to the child and the pager: if it stopped this
+    // process, the pager would be left running on a terminal nobody restores
+    let status = {
+        let _interrupts = IgnoreInterrupts::start();
+        let status = child.wait();
+        pager.wait()?;
+        status?
+    };
+    Ok(Some(exit_code(status)))
+}
+
+/// The child's exit code; quitting the pager before the output ends is a
+/// success, not the broken pipe that stopped the child
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if status.signal() == Some(libc::SIGPIPE) {
+            return 0;
+        }
+    }
+    status.code().unwrap_or(super::EXIT_ERROR)
+}
+
+/// In the child, quitting the pager early closes its stdout. Rust ignores
+/// SIGPIPE, turning the next write into a panic; with the default action the
+/// child stops quietly instead, which the parent reports as success.
+pub fn exit_quietly_on_closed_pager() {
+    if std::env::var_os(PAGER_CHILD_ENV).is_none() {
+        return;
+    }
+    #[cfg(unix)]
+    // SAFETY: resetting a signal to its default action before other threads
+    // or signal handlers exist
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+    }
+}
+
+/// SIGINT ignored until dropped, then restored to what it was
+struct IgnoreInterrupts {
+    #[cfg(unix)]
+    previous: libc::sighandler_t,
+}
+
+impl IgnoreInterrupts {
+    fn start() -> Self {
+        Self {
+            #[cfg(unix)]
+            // SAFETY: swaps the disposition of SIGINT for this process only;
+            // children already spawned keep their own
+            previous: unsafe { libc::signal(libc::SIGINT, libc::SIG_IGN) },
+        }
+    }
+}
+
+impl Drop for IgnoreInterrupts {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: restores the disposition returned by `start`
+        unsafe {
+            libc::signal(libc::SIGINT, self.previous);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pager_command_reads_pager_words() {
+        assert_eq!(pager_command(None), Some(vec!["less".to_string()]));
+        assert_eq!(
+            pager_command(Some("less -R --mouse".into())),
+            Some(vec![
+                "less".to_string(),
+                "-R".to_string(),
+                "--mouse".to_string()
+            ])
+        );
+        assert_eq!(pager_command(Some("".into())), None);
+        assert_eq!(pager_command(Some("cat".into())), None);
+    }
+}
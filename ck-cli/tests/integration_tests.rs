@@ -62,6 +62,79 @@ fn test_case_insensitive_search() {
     assert_eq!(line_count, 6); // Should match all three lines (filename + content for each)
 }
 
+#[test]
+fn test_smart_case_search() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("test.txt");
+    fs::write(&file, "Hello World\nHELLO WORLD\nhello world\n").unwrap();
+
+    let matches = |args: &[&str]| {
+        let output = Command::new(ck_binary())
+            .args(args)
+            .arg(&file)
+            .output()
+            .expect("Failed to run ck");
+        String::from_utf8(output.stdout).unwrap().lines().count()
+    };
+
+    // All-lowercase ignores case; an uppercase letter makes it exact
+    assert_eq!(matches(&["hello"]), 3);
+    assert_eq!(matches(&["Hello"]), 1);
+    assert_eq!(matches(&[r"\Sello"]), 3);
+    // -s and -i override smart case, the last one winning
+    assert_eq!(matches(&["-s", "hello"]), 1);
+    assert_eq!(matches(&["-i", "Hello"]), 3);
+    assert_eq!(matches(&["-i", "-s", "hello"]), 1);
+    assert_eq!(matches(&["-s", "-i", "Hello"]), 3);
+}
+
+#[test]
+fn test_multiline_search() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("lib.rs");
+    fs::write(&file, "fn foo(\n    a: u32,\n) {\n}\nfn bar() {}\n").unwrap();
+
+    let output = Command::new(ck_binary())
+        .args(["-U", "-n", r"fn\s+foo[\s\S]*?\{"])
+        .arg(&file)
+        .output()
+        .expect("Failed to run ck");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "1:fn foo(\n    a: u32,\n) {\n");
+}
+
+#[test]
+fn test_glob_filters_search_paths() {
+    let temp_dir = TempDir::new().unwrap();
+    for file in ["src/lib.rs", "src/tests/it.rs", "notes.md"] {
+        let path = temp_dir.path().join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "needle\n").unwrap();
+    }
+
+    let output = Command::new(ck_binary())
+        .args([
+            "-l",
+            "-g",
+            "src/**/*.rs",
+            "-g",
+            "!**/tests/**",
+            "needle",
+            ".",
+        ])
+        .current_dir(temp_dir.path())
+        .output()
+        .expect("Failed to run ck");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let files: Vec<&str> = stdout.lines().collect();
+    assert_eq!(files.len(), 1, "unexpected files: {:?}", files);
+    assert!(files[0].ends_with("lib.rs"));
+}
+
 #[test]
 fn test_recursive_search() {
     let temp_dir = TempDir::new().unwrap();
@@ -468,6 +541,124 @@ fn test_no_matches_stderr_message() {
     assert!(stderr.contains("No matches found"));
 }
 
+#[test]
+fn test_exit_codes_follow_grep() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("test.txt"), "hello world").unwrap();
+    let dir = temp_dir.path().to_str().unwrap();
+
+    let code = |args: &[&str]| {
+        Command::new(ck_binary())
+            .args(args)
+            .output()
+            .expect("Failed to run ck")
+            .status
+            .code()
+    };
+
+    assert_eq!(code(&["hello", dir]), Some(0));
+    assert_eq!(code(&["goodbye", dir]), Some(1));
+    // Errors are distinct from "no matches"
+    assert_eq!(code(&["(unclosed", dir]), Some(2));
+    assert_eq!(code(&["-l", "-L", "hello", dir]), Some(2));
+    let missing = temp_dir.path().join("missing.rs");
+    assert_eq!(code(&["--explain", missing.to_str().unwrap()]), Some(2));
+    assert_eq!(code(&["--inspect", missing.to_str().unwrap()]), Some(2));
+}
+
+#[test]
+fn test_query_from_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("test.txt"),
+        "hello world\nrust programming\n",
+    )
+    .unwrap();
+
+    let run = |args: &[&str], stdin: &str| {
+        let mut child = Command::new(ck_binary())
+            .args(args)
+            .current_dir(temp_dir.path())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Failed to run ck");
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(stdin.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    // The trailing newline from echo is not part of the query
+    let output = run(&["-", "test.txt"], "rust programming\n");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("rust programming"));
+
+    let output = run(&["--query-stdin"], "hello");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("hello world"));
+
+    // With --query-stdin every positional argument is a path to search
+    fs::create_dir(temp_dir.path().join("src")).unwrap();
+    fs::write(temp_dir.path().join("src/lib.txt"), "parse config\n").unwrap();
+    let output = run(&["--query-stdin", "src"], "parse config\n");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("parse config"));
+    assert!(!stdout.contains("hello world"));
+
+    let output = run(&["-"], "  \n");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("empty"));
+}
+
+#[test]
+fn test_queries_file_prints_one_line_per_query() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("test.txt"),
+        "hello world\nrust programming\nhello rust\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("queries.txt"),
+        "hello\n\nmissing\nrust\n",
+    )
+    .unwrap();
+
+    let output = Command::new(ck_binary())
+        .args(["--queries-file", "queries.txt", "test.txt"])
+        .current_dir(temp_dir.path())
+        .output()
+        .expect("Failed to run ck");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Invalid JSON line"))
+        .collect();
+    let counts: Vec<(&str, usize)> = lines
+        .iter()
+        .map(|line| {
+            (
+                line["query"].as_str().unwrap(),
+                line["hits"].as_array().unwrap().len(),
+            )
+        })
+        .collect();
+    // Blank lines are skipped; a query without hits still gets its line
+    assert_eq!(counts, vec![("hello", 2), ("missing", 0), ("rust", 2)]);
+    assert_eq!(lines[0]["hits"][0]["span"]["line_start"], 1);
+}
+
 #[test]
 fn test_nonexistent_directory_error() {
     let output = Command::new(ck_binary())
@@ -821,3 +1012,119 @@ fn test_add_file_with_relative_path() {
     let stdout = String::from_utf8(output.stdout).unwrap();
     assert!(stdout.contains("Relative path content"));
 }
+
+#[test]
+fn test_sort_by_matches_puts_densest_chunk_first() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("code.rs"),
+        "fn a() {\n    retry();\n}\n\nfn b() {\n    retry();\n    retry();\n}\n",
+    )
+    .unwrap();
+
+    let output = Command::new(ck_binary())
+        .args(["--vimgrep", "--sort", "matches", "retry", "."])
+        .current_dir(temp_dir.path())
+        .output()
+        .expect("Failed to run ck");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<usize> = stdout
+        .lines()
+        .map(|line| line.split(':').nth(1).unwrap().parse().unwrap())
+        .collect();
+    assert_eq!(lines, vec![6, 7, 2], "{stdout}");
+}
+
+#[test]
+fn test_legend_explains_chunk_glyphs() {
+    let output = Command::new(ck_binary())
+        .arg("--legend")
+        .output()
+        .expect("Failed to run ck");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    for glyph in ["┌", "│", "└", "·", "┣━"] {
+        assert!(
+            stdout
+                .lines()
+                .any(|line| line.trim_start().starts_with(glyph)),
+            "{glyph} missing from legend:\n{stdout}"
+        );
+    }
+}
+
+#[test]
+fn test_vimgrep_output_format() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("b.txt"), "todo: later\n").unwrap();
+    fs::write(
+        temp_dir.path().join("a.txt"),
+        "first\n  todo: now\ntodo: again\n",
+    )
+    .unwrap();
+
+    let output = Command::new(ck_binary())
+        .args(["--vimgrep", "todo", "."])
+        .current_dir(temp_dir.path())
+        .output()
+        .expect("Failed to run ck");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    let expected = [
+        "a.txt:2:1: todo: now",
+        "a.txt:3:1: todo: again",
+        "b.txt:1:1: todo: later",
+    ];
+    assert_eq!(lines.len(), expected.len(), "{stdout}");
+    for (line, expected) in lines.iter().zip(expected) {
+        assert!(
+            line.ends_with(expected),
+            "{line} should end with {expected}"
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pager_only_pages_search_output() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("test.txt"), "hello world\n").unwrap();
+    // A pager that notes it ran and passes the output through
+    let pager = temp_dir.path().join("pager.sh");
+    let marker = temp_dir.path().join("paged");
+    fs::write(
+        &pager,
+        format!("#!/bin/sh\ntouch '{}'\ncat\n", marker.display()),
+    )
+    .unwrap();
+    // Run through sh: exec'ing a script just written races other tests' forks
+    let pager = format!("sh '{}'", pager.display());
+
+    let run = |args: &[&str]| {
+        Command::new(ck_binary())
+            .args(args)
+            .current_dir(temp_dir.path())
+            .env("PAGER", &pager)
+            .output()
+            .expect("Failed to run ck")
+    };
+
+    let output = run(&["--pager", "--status", "."]);
+    assert!(output.status.success());
+    assert!(!marker.exists(), "--status went through the pager");
+
+    let output = run(&["--pager", "hello", "test.txt"]);
+    assert!(output.status.success());
+    assert!(
+        marker.exists(),
+        "the search wasn't paged: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // Colors are forced on for the pager, so the match is highlighted
+    assert!(String::from_utf8_lossy(&output.stdout).contains("world"));
+}
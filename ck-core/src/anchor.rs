@@ -0,0 +1,255 @@
+use crate::{ChunkId, CkError, Language, Result};
+
+/// Opens an anchored region: `<comment> ck:begin <chunk_id> <path>:<start>-<end>`
+pub const BEGIN_MARKER: &str = "ck:begin";
+/// Closes an anchored region: `<comment> ck:end <chunk_id>`
+pub const END_MARKER: &str = "ck:end";
+
+/// Line comment syntax for `lang`, `//` when unknown
+pub fn line_comment_marker(lang: Option<Language>) -> &'static str {
+    match lang {
+        Some(Language::Python)
+        | Some(Language::Ruby)
+        | Some(Language::GraphQl)
+        | Some(Language::Toml)
+        | Some(Language::Yaml) => "#",
+        Some(Language::Haskell) => "--",
+        _ => "//",
+    }
+}
+
+/// Where an anchored chunk came from: its id and 1-based inclusive line span
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkAnchor {
+    pub chunk_id: ChunkId,
+    pub path: String,
+    pub line_start: usize,
+    pub line_end: usize,
+}
+
+/// A region found between a matching begin/end pair, with the text in between
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnchoredRegion {
+    pub anchor: ChunkAnchor,
+    pub body: String,
+}
+
+/// Wrap `body` in begin/end anchor comments using `lang`'s line comment syntax
+pub fn wrap_with_anchors(anchor: &ChunkAnchor, body: &str, lang: Option<Language>) -> String {
+    let comment = line_comment_marker(lang);
+    let mut output = format!(
+        "{} {} {} {}:{}-{}\n",
+        comment, BEGIN_MARKER, anchor.chunk_id, anchor.path, anchor.line_start, anchor.line_end
+    );
+    output.push_str(body);
+    if !body.is_empty() && !body.ends_with('\n') {
+        output.push('\n');
+    }
+    output.push_str(&format!("{} {} {}\n", comment, END_MARKER, anchor.chunk_id));
+    output
+}
+
+/// Find every anchored region in `text`, e.g. a chunk pasted back from an LLM.
+///
+/// Anchors are recognised whatever comment syntax surrounds them, and text
+/// outside anchors (prose, code fences) is ignored. Bodies keep their line
+/// endings. Nested, unterminated or mismatched anchors are errors.
+pub fn parse_anchored_regions(text: &str) -> Result<Vec<AnchoredRegion>> {
+    let mut regions = Vec::new();
+    let mut open: Option<(usize, ChunkAnchor, String)> = None;
+
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        let line_number = index + 1;
+        let at = |message: String| CkError::Other(format!("line {}: {}", line_number, message));
+
+        match anchor_directive(line) {
+            Some((BEGIN_MARKER, args)) => {
+                if let Some((begin_line, anchor, _)) = &open {
+                    return Err(at(format!(
+                        "{} inside the region for {} opened on line {}",
+                        BEGIN_MARKER, anchor.chunk_id, begin_line
+                    )));
+                }
+                open = Some((line_number, parse_begin(args).map_err(at)?, String::new()));
+            }
+            Some((_, args)) => {
+                let Some((_, anchor, body)) = open.take() else {
+                    return Err(at(format!(
+                        "{} without a matching {}",
+                        END_MARKER, BEGIN_MARKER
+                    )));
+                };
+                if args != anchor.chunk_id {
+                    return Err(at(format!(
+                        "{} {} closes the region for {}",
+                        END_MARKER, args, anchor.chunk_id
+                    )));
+                }
+                regions.push(AnchoredRegion { anchor, body });
+            }
+            None => {
+                if let Some((_, _, body)) = &mut open {
+                    body.push_str(line);
+                }
+            }
+        }
+    }
+
+    if let Some((begin_line, anchor, _)) = open {
+        return Err(CkError::Other(format!(
+            "line {}: region for {} has no {}",
+            begin_line, anchor.chunk_id, END_MARKER
+        )));
+    }
+    Ok(regions)
+}
+
+/// Replace lines `line_start..=line_end` (1-based) of `content` with `body`.
+///
+/// The body takes on the file's line endings (CRLF or LF), and a file that
+/// didn't end in a newline still doesn't when the region reaches its end.
+pub fn replace_lines(content: &str, line_start: usize, line_end: usize, body: &str) -> String {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let start = line_start.saturating_sub(1).min(lines.len());
+    let end = line_end.min(lines.len()).max(start);
+    let crlf = lines.first().is_some_and(|line| line.ends_with("\r\n"));
+
+    let mut replacement: String = body
+        .split_inclusive('\n')
+        .map(|line| {
+            let text = line.trim_end_matches(['\r', '\n']);
+            match (line.ends_with('\n'), crlf) {
+                (false, _) => text.to_string(),
+                (true, true) => format!("{}\r\n", text),
+                (true, false) => format!("{}\n", text),
+            }
+        })
+        .collect();
+    if end == lines.len() && !content.ends_with('\n') {
+        replacement.truncate(replacement.trim_end_matches(['\r', '\n']).len());
+    } else if !replacement.is_empty() && !replacement.ends_with('\n') {
+        replacement.push_str(if crlf { "\r\n" } else { "\n" });
+    }
+
+    let mut output = lines[..start].concat();
+    output.push_str(&replacement);
+    output.push_str(&lines[end..].concat());
+    output
+}
+
+/// The marker and its arguments when `line` is an anchor comment
+fn anchor_directive(line: &str) -> Option<(&'static str, &str)> {
+    let content = line
+        .trim()
+        .trim_start_matches(['/', '#', '-', ';', '*', '%'])
+        .trim_end_matches("*/")
+        .trim();
+    [BEGIN_MARKER, END_MARKER].into_iter().find_map(|marker| {
+        let args = content.strip_prefix(marker)?;
+        // `ck:beginning` is not an anchor
+        (args.is_empty() || args.starts_with(' ')).then(|| (marker, args.trim()))
+    })
+}
+
+/// Parse `<chunk_id> <path>:<start>-<end>`; a single line number is also accepted
+fn parse_begin(args: &str) -> std::result::Result<ChunkAnchor, String> {
+    let invalid = || {
+        format!(
+            "expected '{} <chunk_id> <path>:<start>-<end>', found '{} {}'",
+            BEGIN_MARKER, BEGIN_MARKER, args
+        )
+    };
+    let (chunk_id, location) = args.split_once(' ').ok_or_else(invalid)?;
+    let (path, lines) = location.trim().rsplit_once(':').ok_or_else(invalid)?;
+    let (start, end) = lines.split_once('-').unwrap_or((lines, lines));
+    let line_start: usize = start.parse().map_err(|_| invalid())?;
+    let line_end: usize = end.parse().map_err(|_| invalid())?;
+    if path.is_empty() || line_start == 0 || line_end < line_start {
+        return Err(invalid());
+    }
+    Ok(ChunkAnchor {
+        chunk_id: chunk_id.to_string(),
+        path: path.to_string(),
+        line_start,
+        line_end,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anchor() -> ChunkAnchor {
+        ChunkAnchor {
+            chunk_id: "0123456789abcdef".to_string(),
+            path: "src/my lib.rs".to_string(),
+            line_start: 10,
+            line_end: 12,
+        }
+    }
+
+    #[test]
+    fn test_wrap_and_parse_round_trip() {
+        let body = "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n";
+        let wrapped = wrap_with_anchors(&anchor(), body, Some(Language::Rust));
+        assert!(wrapped.starts_with("// ck:begin 0123456789abcdef src/my lib.rs:10-12\n"));
+        assert!(wrapped.ends_with("// ck:end 0123456789abcdef\n"));
+
+        // As an LLM might send it back: fenced, with commentary and an edited body
+        let reply = format!(
+            "Here you go:\n```rust\n{}```\n",
+            wrapped.replace("a + b", "a.wrapping_add(b)")
+        );
+        let regions = parse_anchored_regions(&reply).unwrap();
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].anchor, anchor());
+        assert_eq!(
+            regions[0].body,
+            "fn add(a: i32, b: i32) -> i32 {\n    a.wrapping_add(b)\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_anchor_comment_follows_language() {
+        let python = wrap_with_anchors(&anchor(), "pass\n", Some(Language::Python));
+        assert!(python.starts_with("# ck:begin "));
+        let haskell = wrap_with_anchors(&anchor(), "x = 1", Some(Language::Haskell));
+        assert!(haskell.starts_with("-- ck:begin "));
+        assert!(haskell.contains("x = 1\n-- ck:end "));
+        assert_eq!(parse_anchored_regions(&haskell).unwrap()[0].body, "x = 1\n");
+    }
+
+    #[test]
+    fn test_replace_lines_keeps_surroundings_and_line_endings() {
+        let content = "a\nb\nc\nd\n";
+        assert_eq!(replace_lines(content, 2, 3, "x\ny\nz\n"), "a\nx\ny\nz\nd\n");
+        assert_eq!(replace_lines(content, 4, 4, "last"), "a\nb\nc\nlast\n");
+
+        let crlf = "a\r\nb\r\nc\r\n";
+        assert_eq!(replace_lines(crlf, 2, 2, "x\ny\n"), "a\r\nx\r\ny\r\nc\r\n");
+
+        let no_trailing_newline = "a\nb";
+        assert_eq!(replace_lines(no_trailing_newline, 2, 2, "x\n"), "a\nx");
+    }
+
+    #[test]
+    fn test_parse_rejects_broken_anchors() {
+        let unterminated = "// ck:begin abc a.rs:1-2\nfn f() {}\n";
+        assert!(parse_anchored_regions(unterminated).is_err());
+
+        let mismatched = "// ck:begin abc a.rs:1-2\n// ck:end xyz\n";
+        let err = parse_anchored_regions(mismatched).unwrap_err().to_string();
+        assert!(err.contains("line 2"), "{}", err);
+
+        let nested = "// ck:begin a a.rs:1\n// ck:begin b a.rs:2\n";
+        assert!(parse_anchored_regions(nested).is_err());
+
+        assert!(parse_anchored_regions("// ck:begin abc a.rs:0\n// ck:end abc\n").is_err());
+        assert!(parse_anchored_regions("// ck:end abc\n").is_err());
+        assert!(
+            parse_anchored_regions("// ck:beginning of the story\n")
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
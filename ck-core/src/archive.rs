@@ -0,0 +1,474 @@
+use crate::{CkError, Result};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::SystemTime;
+
+/// Joins an archive and an entry inside it: `lib.jar!/com/foo/Bar.java`
+pub const ARCHIVE_SEPARATOR: &str = "!/";
+
+/// Extensions of the archives ck can walk into
+pub const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "jar"];
+
+/// Bytes sniffed for NUL when deciding whether an entry is text, as for files
+const SNIFF_BYTES: u64 = 8192;
+
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const CENTRAL_SIGNATURE: u32 = 0x0201_4b50;
+const LOCAL_SIGNATURE: u32 = 0x0403_4b50;
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+/// Archives kept open between reads. Indexing reads an archive's entries one
+/// after another, so a few are enough to read each central directory once.
+const OPEN_ARCHIVES_KEPT: usize = 16;
+
+/// The most recently used archives last, each with the length and
+/// modification time it was opened at
+static OPEN_ARCHIVES: Mutex<Vec<(PathBuf, ArchiveStamp, SharedArchive)>> = Mutex::new(Vec::new());
+
+/// Length and modification time, to notice an archive replaced since opening
+type ArchiveStamp = (u64, Option<SystemTime>);
+
+/// An open archive, locked while one of its entries is read
+type SharedArchive = Arc<Mutex<ZipArchive>>;
+
+/// Check if a file is a `.zip` or `.jar` archive by extension
+pub fn is_archive_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            ARCHIVE_EXTENSIONS
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        })
+}
+
+/// The virtual path of `entry` inside `archive`
+pub fn virtual_path(archive: &Path, entry: &str) -> PathBuf {
+    let mut path = OsString::from(archive.as_os_str());
+    path.push(ARCHIVE_SEPARATOR);
+    path.push(entry);
+    PathBuf::from(path)
+}
+
+/// Split a virtual path into its archive and the entry name inside it
+pub fn split_virtual_path(path: &Path) -> Option<(PathBuf, String)> {
+    let text = path.to_str()?;
+    text.match_indices(ARCHIVE_SEPARATOR).find_map(|(at, _)| {
+        let archive = Path::new(&text[..at]);
+        let entry = &text[at + ARCHIVE_SEPARATOR.len()..];
+        (is_archive_file(archive) && !entry.is_empty())
+            .then(|| (archive.to_path_buf(), entry.to_string()))
+    })
+}
+
+/// Whether `path` names an entry inside an archive rather than a real file
+pub fn is_virtual_path(path: &Path) -> bool {
+    split_virtual_path(path).is_some()
+}
+
+/// Names of the text entries in `archive`, in archive order. Directories,
+/// encrypted entries, unsupported compression methods, names that would
+/// escape the archive when extracted (`..`, absolute paths) and entries with
+/// a NUL byte in their first 8KB are left out.
+pub fn list_text_entries(archive: &Path) -> Result<Vec<String>> {
+    let zip = open_archive(archive)?;
+    let mut zip = zip.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut names = Vec::new();
+    for index in 0..zip.entries.len() {
+        let entry = &zip.entries[index];
+        if entry.name.ends_with('/') || entry.encrypted || !is_safe_name(&entry.name) {
+            continue;
+        }
+        let mut prefix = Vec::new();
+        let sniffed = zip
+            .reader(index)
+            .and_then(|reader| Ok(reader.take(SNIFF_BYTES).read_to_end(&mut prefix)?));
+        if sniffed.is_ok() && !prefix.contains(&0) {
+            names.push(zip.entries[index].name.clone());
+        }
+    }
+    Ok(names)
+}
+
+/// The uncompressed bytes of `name` inside `archive`
+pub fn read_entry(archive: &Path, name: &str) -> Result<Vec<u8>> {
+    let zip = open_archive(archive)?;
+    let mut zip = zip.lock().unwrap_or_else(PoisonError::into_inner);
+    let index = *zip
+        .by_name
+        .get(name)
+        .ok_or_else(|| in_archive(archive, format!("no entry named {}", name)))?;
+
+    let mut bytes = Vec::new();
+    zip.reader(index)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Read a file as text, looking inside the archive for virtual paths
+pub fn read_to_string(path: &Path) -> Result<String> {
+    let Some((archive, entry)) = split_virtual_path(path) else {
+        return Ok(fs::read_to_string(path)?);
+    };
+    String::from_utf8(read_entry(&archive, &entry)?).map_err(|_| {
+        CkError::Other(format!(
+            "{} is not valid UTF-8 ({})",
+            entry,
+            archive.display()
+        ))
+    })
+}
+
+/// Filesystem metadata for `path`; an archive entry reports its archive's, so
+/// a changed archive marks all of its entries as possibly changed
+pub fn source_metadata(path: &Path) -> std::io::Result<fs::Metadata> {
+    match split_virtual_path(path) {
+        Some((archive, _)) => fs::metadata(archive),
+        None => fs::metadata(path),
+    }
+}
+
+/// Get path for cached archive entry content
+pub fn get_content_cache_path(repo_root: &Path, virtual_path: &Path) -> PathBuf {
+    let relative = virtual_path.strip_prefix(repo_root).unwrap_or(virtual_path);
+    repo_root.join(".ck").join("content").join(relative)
+}
+
+/// Extract an archive entry into the index's content cache (unless the cached
+/// copy is newer than the archive) and return the cached file's path
+pub fn cache_entry(repo_root: &Path, virtual_path: &Path) -> Result<PathBuf> {
+    let cache_path = get_content_cache_path(repo_root, virtual_path);
+    let fresh = match (source_metadata(virtual_path), fs::metadata(&cache_path)) {
+        (Ok(source), Ok(cache)) => cache.modified()? >= source.modified()?,
+        _ => false,
+    };
+    if !fresh {
+        write_entry(virtual_path, &cache_path)?;
+    }
+    Ok(cache_path)
+}
+
+/// Extract an archive entry to a temporary file (e.g. to open it in an
+/// editor) and return its path. The file keeps the entry's name, so editors
+/// still pick the right syntax.
+pub fn extract_to_temp(virtual_path: &Path) -> Result<PathBuf> {
+    let (archive, entry) = split_virtual_path(virtual_path).ok_or_else(|| {
+        CkError::Other(format!("{} is not in an archive", virtual_path.display()))
+    })?;
+    let archive_id = blake3::hash(archive.to_string_lossy().as_bytes()).to_hex();
+    let temp_path = std::env::temp_dir()
+        .join("ck-archives")
+        .join(&archive_id.as_str()[..16])
+        .join(&entry);
+    write_entry(virtual_path, &temp_path)?;
+    Ok(temp_path)
+}
+
+fn write_entry(virtual_path: &Path, destination: &Path) -> Result<()> {
+    let (archive, entry) = split_virtual_path(virtual_path).ok_or_else(|| {
+        CkError::Other(format!("{} is not in an archive", virtual_path.display()))
+    })?;
+    if !is_safe_name(&entry) {
+        return Err(in_archive(
+            &archive,
+            format!("refusing to extract {}", entry),
+        ));
+    }
+    let bytes = read_entry(&archive, &entry)?;
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(destination, bytes)?;
+    Ok(())
+}
+
+/// `path` opened, reusing the handle from an earlier read unless the archive
+/// has changed since
+fn open_archive(path: &Path) -> Result<SharedArchive> {
+    let metadata = fs::metadata(path)?;
+    let stamp = (metadata.len(), metadata.modified().ok());
+    let mut open = OPEN_ARCHIVES.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(at) = open.iter().position(|(open_path, _, _)| open_path == path) {
+        let (open_path, open_stamp, zip) = open.remove(at);
+        if open_stamp == stamp {
+            open.push((open_path, open_stamp, Arc::clone(&zip)));
+            return Ok(zip);
+        }
+    }
+
+    let zip = Arc::new(Mutex::new(ZipArchive::open(path)?));
+    if open.len() == OPEN_ARCHIVES_KEPT {
+        open.remove(0);
+    }
+    open.push((path.to_path_buf(), stamp, Arc::clone(&zip)));
+    Ok(zip)
+}
+
+fn in_archive(archive: &Path, message: impl std::fmt::Display) -> CkError {
+    CkError::Other(format!("{}: {}", archive.display(), message))
+}
+
+/// An entry name that stays inside the directory it is extracted to
+fn is_safe_name(name: &str) -> bool {
+    Path::new(name)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// One file listed in a ZIP central directory
+struct ZipEntry {
+    name: String,
+    method: u16,
+    encrypted: bool,
+    compressed_size: u64,
+    local_header_offset: u64,
+}
+
+/// A ZIP (and so JAR) archive whose central directory has been read. Entries
+/// are read by seeking, so listing or reading one doesn't load the archive.
+struct ZipArchive {
+    path: PathBuf,
+    file: fs::File,
+    entries: Vec<ZipEntry>,
+    /// Index into `entries` by name; a repeated name maps to its first entry
+    by_name: HashMap<String, usize>,
+}
+
+impl ZipArchive {
+    fn open(path: &Path) -> Result<Self> {
+        let mut file = fs::File::open(path)?;
+        let len = file.metadata()?.len();
+
+        // The end record is 22 bytes plus a comment of up to 64KB
+        let tail_len = len.min(22 + u16::MAX as u64);
+        let tail = read_at(&mut file, len - tail_len, tail_len)?;
+        let last = tail
+            .len()
+            .checked_sub(22)
+            .ok_or_else(|| in_archive(path, "not a zip archive"))?;
+        let end = (0..=last)
+            .rev()
+            .find(|&at| read_u32(&tail, at) == Some(EOCD_SIGNATURE))
+            .ok_or_else(|| in_archive(path, "not a zip archive"))?;
+
+        let truncated = || in_archive(path, "truncated archive");
+        let count = read_u16(&tail, end + 10).ok_or_else(truncated)?;
+        let size = read_u32(&tail, end + 12).ok_or_else(truncated)?;
+        let offset = read_u32(&tail, end + 16).ok_or_else(truncated)?;
+        if count == u16::MAX || offset == u32::MAX {
+            return Err(in_archive(path, "ZIP64 archives are not supported"));
+        }
+
+        let directory = read_at(&mut file, offset as u64, size as u64)?;
+        let mut entries = Vec::with_capacity(count as usize);
+        let mut at = 0;
+        for _ in 0..count {
+            if read_u32(&directory, at) != Some(CENTRAL_SIGNATURE) {
+                return Err(in_archive(path, "corrupt central directory"));
+            }
+            let field = |offset: usize| read_u16(&directory, at + offset).ok_or_else(truncated);
+            let name_len = field(28)? as usize;
+            let skip = name_len + field(30)? as usize + field(32)? as usize;
+            let name = directory
+                .get(at + 46..at + 46 + name_len)
+                .ok_or_else(truncated)?;
+            entries.push(ZipEntry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                method: field(10)?,
+                encrypted: field(8)? & 1 != 0,
+                compressed_size: read_u32(&directory, at + 20).ok_or_else(truncated)? as u64,
+                local_header_offset: read_u32(&directory, at + 42).ok_or_else(truncated)? as u64,
+            });
+            at += 46 + skip;
+        }
+
+        let mut by_name = HashMap::with_capacity(entries.len());
+        for (index, entry) in entries.iter().enumerate() {
+            by_name.entry(entry.name.clone()).or_insert(index);
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            entries,
+            by_name,
+        })
+    }
+
+    /// A reader over the uncompressed bytes of the entry at `index`
+    fn reader(&mut self, index: usize) -> Result<Box<dyn Read + '_>> {
+        let entry = &self.entries[index];
+        let corrupt = || {
+            in_archive(
+                &self.path,
+                format!("corrupt local header for {}", entry.name),
+            )
+        };
+        let header = read_at(&mut self.file, entry.local_header_offset, 30)?;
+        if read_u32(&header, 0) != Some(LOCAL_SIGNATURE) {
+            return Err(corrupt());
+        }
+        let name_len = read_u16(&header, 26).ok_or_else(corrupt)? as u64;
+        let extra_len = read_u16(&header, 28).ok_or_else(corrupt)? as u64;
+        self.file.seek(SeekFrom::Start(
+            entry.local_header_offset + 30 + name_len + extra_len,
+        ))?;
+
+        let compressed = (&self.file).take(entry.compressed_size);
+        match entry.method {
+            METHOD_STORED => Ok(Box::new(compressed)),
+            METHOD_DEFLATED => Ok(Box::new(flate2::read::DeflateDecoder::new(compressed))),
+            method => Err(in_archive(
+                &self.path,
+                format!(
+                    "{} uses unsupported compression method {}",
+                    entry.name, method
+                ),
+            )),
+        }
+    }
+}
+
+/// Read `len` bytes of `file` starting at `offset`
+fn read_at(file: &mut fs::File, offset: u64, len: u64) -> Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.take(len).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    let bytes = data.get(at..at + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    let bytes = data.get(at..at + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Write a ZIP holding `entries`, deflating those marked so
+    fn write_zip(path: &Path, entries: &[(&str, &[u8], bool)]) {
+        let mut data = Vec::new();
+        let mut central = Vec::new();
+        for (name, content, deflate) in entries {
+            let (method, stored) = if *deflate {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(content).unwrap();
+                (METHOD_DEFLATED, encoder.finish().unwrap())
+            } else {
+                (METHOD_STORED, content.to_vec())
+            };
+            let offset = data.len() as u32;
+            for (signature, out) in [
+                (LOCAL_SIGNATURE, &mut data),
+                (CENTRAL_SIGNATURE, &mut central),
+            ] {
+                out.extend(signature.to_le_bytes());
+                if signature == CENTRAL_SIGNATURE {
+                    out.extend(20u16.to_le_bytes()); // version made by
+                }
+                out.extend(20u16.to_le_bytes()); // version needed
+                out.extend(0u16.to_le_bytes()); // flags
+                out.extend(method.to_le_bytes());
+                out.extend([0u8; 8]); // time, date, crc
+                out.extend((stored.len() as u32).to_le_bytes());
+                out.extend((content.len() as u32).to_le_bytes());
+                out.extend((name.len() as u16).to_le_bytes());
+                out.extend(0u16.to_le_bytes()); // extra
+                if signature == CENTRAL_SIGNATURE {
+                    out.extend([0u8; 10]); // comment, disk, attributes
+                    out.extend(offset.to_le_bytes());
+                }
+                out.extend(name.as_bytes());
+            }
+            data.extend(&stored);
+        }
+
+        let central_offset = data.len() as u32;
+        data.extend(&central);
+        data.extend(EOCD_SIGNATURE.to_le_bytes());
+        data.extend([0u8; 4]); // disk numbers
+        data.extend((entries.len() as u16).to_le_bytes());
+        data.extend((entries.len() as u16).to_le_bytes());
+        data.extend((central.len() as u32).to_le_bytes());
+        data.extend(central_offset.to_le_bytes());
+        data.extend(0u16.to_le_bytes());
+        fs::write(path, data).unwrap();
+    }
+
+    #[test]
+    fn test_virtual_paths() {
+        let path = virtual_path(Path::new("vendor/lib.jar"), "com/foo/Bar.java");
+        assert_eq!(path, PathBuf::from("vendor/lib.jar!/com/foo/Bar.java"));
+        assert_eq!(
+            split_virtual_path(&path),
+            Some((
+                PathBuf::from("vendor/lib.jar"),
+                "com/foo/Bar.java".to_string()
+            ))
+        );
+        assert!(is_archive_file(Path::new("deps.ZIP")));
+        assert!(!is_virtual_path(Path::new("src/wow!/main.rs")));
+        assert!(!is_virtual_path(Path::new("lib.jar")));
+    }
+
+    #[test]
+    fn test_lists_and_reads_text_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let jar = dir.path().join("lib.jar");
+        let source = b"package com.foo;\n\nclass Bar {}\n";
+        write_zip(
+            &jar,
+            &[
+                ("com/", b"", false),
+                ("com/foo/Bar.java", source, true),
+                ("com/foo/Bar.class", b"\xca\xfe\xba\xbe\0\0\0\x34", false),
+                ("README.md", b"# lib\n", false),
+                ("../escape.txt", b"nope\n", false),
+            ],
+        );
+
+        assert_eq!(
+            list_text_entries(&jar).unwrap(),
+            vec!["com/foo/Bar.java", "README.md"]
+        );
+        let entry = virtual_path(&jar, "com/foo/Bar.java");
+        assert_eq!(read_to_string(&entry).unwrap().as_bytes(), source);
+        assert!(read_to_string(&virtual_path(&jar, "missing.txt")).is_err());
+
+        let cached = cache_entry(dir.path(), &entry).unwrap();
+        assert_eq!(
+            cached,
+            dir.path().join(".ck/content/lib.jar!/com/foo/Bar.java")
+        );
+        assert_eq!(fs::read(&cached).unwrap(), source);
+
+        let temp = extract_to_temp(&entry).unwrap();
+        assert!(temp.ends_with("com/foo/Bar.java"));
+        assert_eq!(fs::read(&temp).unwrap(), source);
+
+        // Reads reuse the archive opened for listing until it changes
+        let first = open_archive(&jar).unwrap();
+        assert!(Arc::ptr_eq(&first, &open_archive(&jar).unwrap()));
+        write_zip(
+            &jar,
+            &[("com/foo/Bar.java", b"class Bar { int x; }\n", true)],
+        );
+        assert_eq!(read_to_string(&entry).unwrap(), "class Bar { int x; }\n");
+        assert!(!Arc::ptr_eq(&first, &open_archive(&jar).unwrap()));
+
+        let not_zip = dir.path().join("broken.zip");
+        fs::write(&not_zip, "plain text").unwrap();
+        assert!(list_text_entries(&not_zip).is_err());
+    }
+}
@@ -147,6 +147,116 @@ fn calculate_fuzzy_similarity(s1: &str, s2: &str) -> f32 {
     common_chars as f32 / max_len as f32
 }
 
+/// Query words too common to say anything about why a chunk matched
+const STOP_WORDS: &[&str] = &[
+    "and", "are", "for", "from", "how", "the", "that", "this", "what", "when", "where", "which",
+    "with",
+];
+
+/// Byte ranges of the words in `text` that share a stem with a word of `query`,
+/// e.g. `parseConfig` and `config_parser` for "parsing configs". Unless
+/// `exact_tokens` is set, identifiers are split into their camelCase and
+/// snake_case words (see [`token_ranges`]); adjacent matches merge.
+pub fn query_term_ranges(text: &str, query: &str, exact_tokens: bool) -> Vec<(usize, usize)> {
+    let terms: HashSet<String> = token_ranges(query, exact_tokens)
+        .into_iter()
+        .map(|(start, end)| stem(&query[start..end]))
+        .filter(|term| term.len() >= 3 && !STOP_WORDS.contains(&term.as_str()))
+        .collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in token_ranges(text, exact_tokens) {
+        if !terms.contains(&stem(&text[start..end])) {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if last.1 == start => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges
+}
+
+/// Byte ranges of the tokens in `text`: its identifiers (runs of alphanumerics
+/// and `_`) when `exact_tokens` is set, otherwise their words, splitting
+/// `parseHTTPHeader` into `parse`, `HTTP` and `Header` and `user_name` or
+/// `user-name` into `user` and `name`
+pub fn token_ranges(text: &str, exact_tokens: bool) -> Vec<(usize, usize)> {
+    if exact_tokens {
+        return identifiers(text);
+    }
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut prev: Option<char> = None;
+
+    for (i, &(at, c)) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if let Some(start) = start.take() {
+                words.push((start, at));
+            }
+            prev = None;
+            continue;
+        }
+        let next = chars.get(i + 1).map(|&(_, next)| next);
+        let boundary = prev.is_some_and(|prev| {
+            (prev.is_lowercase() && c.is_uppercase())
+                || (prev.is_uppercase() && c.is_uppercase() && next.is_some_and(char::is_lowercase))
+        });
+        if boundary && let Some(start) = start.replace(at) {
+            words.push((start, at));
+        }
+        start.get_or_insert(at);
+        prev = Some(c);
+    }
+    if let Some(start) = start {
+        words.push((start, text.len()));
+    }
+    words
+}
+
+fn identifiers(text: &str) -> Vec<(usize, usize)> {
+    let mut identifiers = Vec::new();
+    let mut start: Option<usize> = None;
+    for (at, c) in text.char_indices() {
+        if c.is_alphanumeric() || c == '_' {
+            start.get_or_insert(at);
+        } else if let Some(start) = start.take() {
+            identifiers.push((start, at));
+        }
+    }
+    if let Some(start) = start {
+        identifiers.push((start, text.len()));
+    }
+    identifiers
+        .into_iter()
+        .filter(|&(start, end)| text[start..end].chars().any(|c| c != '_'))
+        .collect()
+}
+
+/// A crude English stem, enough that `parse`, `parser`, `parsing` and `parsed`
+/// all come out as `pars`
+fn stem(word: &str) -> String {
+    let word = word.to_lowercase();
+    let mut stem = word.as_str();
+    for suffix in ["ing", "ers", "er", "ed", "es", "ly", "s"] {
+        if let Some(base) = stem.strip_suffix(suffix)
+            && base.len() >= 3
+            && !(suffix == "s" && base.ends_with('s'))
+        {
+            stem = base;
+            break;
+        }
+    }
+    stem.strip_suffix('e')
+        .filter(|base| base.len() >= 3)
+        .unwrap_or(stem)
+        .to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,6 +299,48 @@ mod tests {
         assert_eq!(calculate_token_similarity("{", "hello"), 0.0);
     }
 
+    #[test]
+    fn query_terms_match_stems_inside_identifiers() {
+        let text = "fn parseConfig(path: &str) -> ConfigParser { load_configs(path) }";
+        let ranges = query_term_ranges(text, "parsing the configs", false);
+        let matched: Vec<&str> = ranges
+            .iter()
+            .map(|&(start, end)| &text[start..end])
+            .collect();
+        assert_eq!(matched, vec!["parseConfig", "ConfigParser", "configs"]);
+
+        // Stop words and short words never match on their own
+        assert!(query_term_ranges("for the id", "the id for", false).is_empty());
+    }
+
+    #[test]
+    fn identifiers_split_unless_tokens_are_exact() {
+        let text = "getUserName(user_name, HTTPServer, user-id)";
+        let tokens = |exact| -> Vec<&str> {
+            token_ranges(text, exact)
+                .into_iter()
+                .map(|(start, end)| &text[start..end])
+                .collect()
+        };
+        assert_eq!(
+            tokens(false),
+            vec![
+                "get", "User", "Name", "user", "name", "HTTP", "Server", "user", "id"
+            ]
+        );
+        assert_eq!(
+            tokens(true),
+            vec!["getUserName", "user_name", "HTTPServer", "user", "id"]
+        );
+
+        let call = "getUserName(user_name)";
+        assert_eq!(
+            query_term_ranges(call, "user name", false),
+            vec![(3, 11), (12, 16), (17, 21)]
+        );
+        assert!(query_term_ranges(call, "user name", true).is_empty());
+    }
+
     #[test]
     fn heatmap_bucket_mapping_matches_thresholds() {
         assert_eq!(HeatmapBucket::from_score(0.0), HeatmapBucket::None);
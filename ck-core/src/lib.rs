@@ -1,3 +1,5 @@
+pub mod anchor;
+pub mod archive;
 pub mod heatmap;
 
 use serde::{Deserialize, Serialize};
@@ -53,6 +55,11 @@ pub enum Language {
     Swift,
     Kotlin,
     Zig,
+    Vue,
+    Svelte,
+    GraphQl,
+    Toml,
+    Yaml,
     Pdf,
 }
 
@@ -76,6 +83,11 @@ impl Language {
             "swift" => Some(Language::Swift),
             "kt" | "kts" => Some(Language::Kotlin),
             "zig" => Some(Language::Zig),
+            "vue" => Some(Language::Vue),
+            "svelte" => Some(Language::Svelte),
+            "graphql" | "gql" => Some(Language::GraphQl),
+            "toml" => Some(Language::Toml),
+            "yaml" | "yml" => Some(Language::Yaml),
             "pdf" => Some(Language::Pdf),
             _ => None,
         }
@@ -106,13 +118,25 @@ impl std::fmt::Display for Language {
             Language::Swift => "swift",
             Language::Kotlin => "kotlin",
             Language::Zig => "zig",
+            Language::Vue => "vue",
+            Language::Svelte => "svelte",
+            Language::GraphQl => "graphql",
+            Language::Toml => "toml",
+            Language::Yaml => "yaml",
             Language::Pdf => "pdf",
         };
         write!(f, "{}", name)
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A region of a source file.
+///
+/// Conventions, relied on by every consumer:
+/// - `byte_start..byte_end` is a half-open byte range into the file content.
+/// - `line_start..=line_end` is an inclusive range of **1-based** line numbers,
+///   so the first line of a file is `line_start == 1`. Producers that work
+///   with 0-based rows (e.g. tree-sitter positions) must add one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Span {
     pub byte_start: usize,
     pub byte_end: usize,
@@ -215,7 +239,7 @@ pub struct FileMetadata {
     pub size: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SearchResult {
     pub file: PathBuf,
     pub span: Span,
@@ -225,10 +249,46 @@ pub struct SearchResult {
     pub lang: Option<Language>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub symbol: Option<String>,
+    /// `::`-joined ancestry of the matched chunk, for chunk-based results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breadcrumb: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub chunk_hash: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub index_epoch: Option<u64>,
+    /// Who last touched the result's lines, filled in when `blame` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blame: Option<Blame>,
+    /// Byte ranges of `preview` sharing words with the query, filled in by
+    /// semantic and hybrid searches with `highlight_terms` set
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub match_ranges: Vec<(usize, usize)>,
+}
+
+/// The most recent commit among the lines of a result, from `git blame`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Blame {
+    pub author: String,
+    /// Author date as `YYYY-MM-DD`, in the author's time zone
+    pub date: String,
+    /// Abbreviated commit hash; all zeros for uncommitted changes
+    pub commit: String,
+}
+
+/// The ranking order: best score first, ties broken by path, then first and
+/// last line. A total order, so equal scores list the same way on every run.
+pub fn compare_by_score(a: &SearchResult, b: &SearchResult) -> std::cmp::Ordering {
+    b.score
+        .total_cmp(&a.score)
+        .then_with(|| compare_by_location(a, b))
+}
+
+/// Path, then first and last line
+pub fn compare_by_location(a: &SearchResult, b: &SearchResult) -> std::cmp::Ordering {
+    a.file
+        .cmp(&b.file)
+        .then_with(|| a.span.line_start.cmp(&b.span.line_start))
+        .then_with(|| a.span.line_end.cmp(&b.span.line_end))
 }
 
 /// Enhanced search results that include near-miss information for threshold queries
@@ -249,6 +309,11 @@ pub struct JsonSearchResult {
     pub signals: SearchSignals,
     pub preview: String,
     pub model: String,
+    /// Byte ranges of `preview` to underline, see [`SearchResult::match_ranges`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub match_ranges: Vec<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blame: Option<Blame>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -264,6 +329,11 @@ pub struct JsonlSearchResult {
     pub chunk_hash: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub index_epoch: Option<u64>,
+    /// Byte ranges of `snippet` to underline, see [`SearchResult::match_ranges`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub match_ranges: Vec<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blame: Option<Blame>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -279,6 +349,383 @@ pub enum SearchMode {
     Lexical,
     Semantic,
     Hybrid,
+    /// Fuzzy matching of chunk names and breadcrumbs, without embeddings
+    Structural,
+}
+
+/// Chunk kinds `--type` accepts, in the index vocabulary
+pub const CHUNK_TYPE_NAMES: &[&str] = &["function", "class", "method", "module", "text"];
+
+/// The kind of symbol a chunk defines, for outlines and other frontends that
+/// pick an icon or label per kind. Structs, enums, traits and interfaces are
+/// all class chunks; comments, docs and loose code are text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Class,
+    Module,
+    Text,
+}
+
+impl SymbolKind {
+    /// From a chunk type in the index vocabulary; `None` and unknown names are text
+    pub fn from_chunk_type(chunk_type: Option<&str>) -> Self {
+        match chunk_type {
+            Some("function") => SymbolKind::Function,
+            Some("method") => SymbolKind::Method,
+            Some("class") => SymbolKind::Class,
+            Some("module") => SymbolKind::Module,
+            _ => SymbolKind::Text,
+        }
+    }
+
+    /// The name in the index vocabulary, as in [`CHUNK_TYPE_NAMES`]
+    pub fn name(self) -> &'static str {
+        match self {
+            SymbolKind::Function => "function",
+            SymbolKind::Method => "method",
+            SymbolKind::Class => "class",
+            SymbolKind::Module => "module",
+            SymbolKind::Text => "text",
+        }
+    }
+
+    /// The chunk type stored in the index, where text chunks have none
+    pub fn index_chunk_type(self) -> Option<&'static str> {
+        (self != SymbolKind::Text).then(|| self.name())
+    }
+
+    /// The matching LSP `SymbolKind` number. LSP has no kind for plain text,
+    /// so text maps to `String`.
+    pub fn lsp_kind(self) -> u8 {
+        match self {
+            SymbolKind::Module => 2,
+            SymbolKind::Class => 5,
+            SymbolKind::Method => 6,
+            SymbolKind::Function => 12,
+            SymbolKind::Text => 15,
+        }
+    }
+}
+
+/// Whether a chunk of `chunk_type` is one of the `--type` kinds in `types` (an
+/// empty list accepts everything). Text chunks are `None` or `"text"`.
+pub fn chunk_type_selected(types: &[String], chunk_type: Option<&str>) -> bool {
+    let kind = chunk_type.unwrap_or("text");
+    types.is_empty() || types.iter().any(|wanted| wanted == kind)
+}
+
+/// How chunk text is emitted when extracted for copying or export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WhitespaceMode {
+    /// Exactly as in the source
+    #[default]
+    Verbatim,
+    /// Tabs expanded to spaces at every [`WhitespaceMode::TAB_WIDTH`] columns
+    /// and trailing whitespace trimmed from each line
+    Normalized,
+}
+
+impl WhitespaceMode {
+    pub const TAB_WIDTH: usize = 4;
+
+    /// Apply the mode to one line (without its line terminator)
+    pub fn apply<'a>(self, line: &'a str) -> std::borrow::Cow<'a, str> {
+        if self == WhitespaceMode::Verbatim {
+            return std::borrow::Cow::Borrowed(line);
+        }
+        let trimmed = line.trim_end();
+        if !trimmed.contains('\t') {
+            return std::borrow::Cow::Borrowed(trimmed);
+        }
+        let mut expanded = String::with_capacity(trimmed.len() + Self::TAB_WIDTH);
+        let mut column = 0;
+        for ch in trimmed.chars() {
+            if ch == '\t' {
+                let spaces = Self::TAB_WIDTH - column % Self::TAB_WIDTH;
+                expanded.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            } else {
+                expanded.push(ch);
+                column += 1;
+            }
+        }
+        std::borrow::Cow::Owned(expanded)
+    }
+
+    /// Apply the mode to every line
+    pub fn apply_lines(self, lines: &[String]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| self.apply(line).into_owned())
+            .collect()
+    }
+}
+
+/// Restricts chunk-based searches to prose or to code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChunkFilter {
+    #[default]
+    All,
+    /// Only text chunks: comments, doc comments and other non-structural text
+    DocsOnly,
+    /// Only structural chunks (functions, classes, methods, modules)
+    CodeOnly,
+}
+
+impl ChunkFilter {
+    /// `chunk_type` uses the index vocabulary, where text chunks are `None` or `"text"`
+    pub fn accepts(self, chunk_type: Option<&str>) -> bool {
+        let is_text = chunk_type.is_none_or(|kind| kind == "text");
+        match self {
+            ChunkFilter::All => true,
+            ChunkFilter::DocsOnly => is_text,
+            ChunkFilter::CodeOnly => !is_text,
+        }
+    }
+}
+
+/// Order to list search results in, instead of the search's own order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultSort {
+    /// Most query matches inside the result's span first, ties by score
+    Matches,
+    /// Best score first, ties by path and line
+    Score,
+    /// By path, then line
+    Path,
+    /// Longest span first, ties by score
+    Lines,
+}
+
+/// `--sort` names, in the same order as [`ResultSort`]
+pub const RESULT_SORT_NAMES: &[&str] = &["matches", "score", "path", "lines"];
+
+impl ResultSort {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "matches" => Some(ResultSort::Matches),
+            "score" => Some(ResultSort::Score),
+            "path" => Some(ResultSort::Path),
+            "lines" => Some(ResultSort::Lines),
+            _ => None,
+        }
+    }
+}
+
+/// What `--recent` does with files modified outside its window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecentMode {
+    /// Leave out results from older files
+    Filter,
+    /// Keep every result, but rank results from recent files higher
+    Boost,
+}
+
+/// `--recent-mode` names, in the same order as [`RecentMode`]
+pub const RECENT_MODE_NAMES: &[&str] = &["filter", "boost"];
+
+impl RecentMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "filter" => Some(RecentMode::Filter),
+            "boost" => Some(RecentMode::Boost),
+            _ => None,
+        }
+    }
+}
+
+/// Files modified (by mtime) within `window` of now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecentFiles {
+    pub window: std::time::Duration,
+    pub mode: RecentMode,
+}
+
+impl RecentFiles {
+    /// Whether `path` was modified within the window; unreadable files aren't
+    pub fn includes(&self, path: &Path) -> bool {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            // A modification time in the future counts as just now
+            .map(|modified| modified.elapsed().unwrap_or_default())
+            .is_ok_and(|age| age <= self.window)
+    }
+}
+
+/// A duration such as `90s`, `30m`, `2h`, `3d` or `1w`; a bare number is seconds
+pub fn parse_duration(text: &str) -> std::result::Result<std::time::Duration, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (amount, unit) = text.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("'{}' is not a duration like 30m, 2h, 3d or 1w", text))?;
+    let seconds = match unit.trim() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        other => {
+            return Err(format!(
+                "Unknown duration unit '{}' in '{}'; use s, m, h, d or w",
+                other, text
+            ));
+        }
+    };
+    Ok(std::time::Duration::from_secs(
+        amount.saturating_mul(seconds),
+    ))
+}
+
+/// What `--no-tests` / `--tests-only` do with test code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TestFilter {
+    /// Search test code like any other
+    #[default]
+    All,
+    /// Leave out test files and test chunks
+    Exclude,
+    /// Keep only test files and test chunks
+    Only,
+}
+
+impl TestFilter {
+    /// Whether a chunk, test code or not, survives the filter
+    pub fn keeps(self, is_test: bool) -> bool {
+        match self {
+            TestFilter::All => true,
+            TestFilter::Exclude => !is_test,
+            TestFilter::Only => is_test,
+        }
+    }
+}
+
+/// Globs for test files, matched against a file's name and its path relative
+/// to the search root; `test_patterns` in `ck.toml` replaces them
+pub const DEFAULT_TEST_PATTERNS: &[&str] = &[
+    "*_test.*",
+    "test_*.*",
+    "*.test.*",
+    "*.spec.*",
+    "**/tests/**",
+    "**/__tests__/**",
+];
+
+/// Whether a chunk looks like test code from its metadata: a test attribute
+/// or decorator such as `#[test]` or `@pytest.mark` heads it, or it or an
+/// enclosing chunk is named like a test (`test_parse`, `TestParser`, `mod tests`)
+pub fn is_test_chunk(name: Option<&str>, ancestry: &[String], leading_trivia: &[String]) -> bool {
+    leading_trivia
+        .iter()
+        .any(|line| is_test_attribute(line.trim()))
+        || name.is_some_and(is_test_name)
+        || ancestry.iter().any(|segment| is_test_name(segment))
+}
+
+/// `#[test]`, `#[tokio::test]`, `#[cfg(test)]`, `@Test`, `@test` and
+/// `@pytest...`, but not other attributes that mention tests, like
+/// `#[cfg(not(test))]` or `#[cfg_attr(test, derive(Debug))]`
+fn is_test_attribute(line: &str) -> bool {
+    if let Some(inner) = line
+        .strip_prefix("#[")
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        let inner: String = inner.chars().filter(|c| !c.is_whitespace()).collect();
+        let path = inner.split('(').next().unwrap_or_default();
+        return path == "test" || path.ends_with("::test") || inner == "cfg(test)";
+    }
+    let Some(decorator) = line.strip_prefix('@') else {
+        return false;
+    };
+    let name = decorator
+        .split(|c: char| c == '(' || c.is_whitespace())
+        .next()
+        .unwrap_or_default();
+    matches!(name, "Test" | "test" | "pytest") || name.starts_with("pytest.")
+}
+
+/// `test`, `tests`, `test_x`, `testX`, `TestX`, but not `testament` or `contest`
+fn is_test_name(name: &str) -> bool {
+    let Some(rest) = name
+        .strip_prefix("test")
+        .or_else(|| name.strip_prefix("Test"))
+    else {
+        return false;
+    };
+    rest.is_empty()
+        || rest == "s"
+        || rest.starts_with('_')
+        || rest
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+/// How semantic search scores a chunk's embedding against the query's; every
+/// metric gives higher scores to closer vectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SimilarityMetric {
+    /// Dot product of the normalized vectors, in [-1, 1]
+    #[default]
+    Cosine,
+    /// Raw dot product, for models trained without normalizing
+    Dot,
+    /// `1 / (1 + distance)`, in (0, 1]
+    Euclidean,
+}
+
+/// `--metric` names, in the same order as [`SimilarityMetric`]
+pub const SIMILARITY_METRIC_NAMES: &[&str] = &["cosine", "dot", "euclidean"];
+
+impl SimilarityMetric {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cosine" => Some(SimilarityMetric::Cosine),
+            "dot" => Some(SimilarityMetric::Dot),
+            "euclidean" => Some(SimilarityMetric::Euclidean),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SimilarityMetric::Cosine => "cosine",
+            SimilarityMetric::Dot => "dot",
+            SimilarityMetric::Euclidean => "euclidean",
+        }
+    }
+
+    /// Score `b` against `a`; vectors of different lengths score 0
+    pub fn score(self, a: &[f32], b: &[f32]) -> f32 {
+        if a.len() != b.len() {
+            return 0.0;
+        }
+        let dot = || a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+        match self {
+            SimilarityMetric::Cosine => {
+                let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+                let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+                if norm_a == 0.0 || norm_b == 0.0 {
+                    0.0
+                } else {
+                    dot() / (norm_a * norm_b)
+                }
+            }
+            SimilarityMetric::Dot => dot(),
+            SimilarityMetric::Euclidean => {
+                let distance = a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum::<f32>();
+                1.0 / (1.0 + distance.sqrt())
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -298,6 +745,10 @@ pub struct FileCollectionOptions {
     pub use_ckignore: bool,
     /// Patterns to exclude files/directories
     pub exclude_patterns: Vec<String>,
+    /// Walk into symlinked directories (each real directory is visited once)
+    pub follow_symlinks: bool,
+    /// Collect the text entries of `.zip`/`.jar` archives as `archive!/entry` paths
+    pub archives: bool,
 }
 
 impl From<&SearchOptions> for FileCollectionOptions {
@@ -306,6 +757,8 @@ impl From<&SearchOptions> for FileCollectionOptions {
             respect_gitignore: opts.respect_gitignore,
             use_ckignore: true, // Always use .ckignore for hierarchical ignore support
             exclude_patterns: opts.exclude_patterns.clone(),
+            follow_symlinks: opts.follow_symlinks,
+            archives: opts.archives,
         }
     }
 }
@@ -320,6 +773,8 @@ pub struct SearchOptions {
     pub case_insensitive: bool,
     pub whole_word: bool,
     pub fixed_string: bool,
+    /// Regex search matches the whole file, so a match can span lines
+    pub multiline: bool,
     pub line_numbers: bool,
     pub context_lines: usize,
     pub before_context_lines: usize,
@@ -327,6 +782,11 @@ pub struct SearchOptions {
     pub recursive: bool,
     pub json_output: bool,
     pub jsonl_output: bool,
+    pub vimgrep_output: bool,
+    // One `score  path:line  breadcrumb  — preview` line per result
+    pub compact_output: bool,
+    // Some(n): print a Markdown table of at most n results
+    pub markdown_rows: Option<usize>,
     pub no_snippet: bool,
     pub reindex: bool,
     pub show_scores: bool,
@@ -335,13 +795,55 @@ pub struct SearchOptions {
     pub files_without_matches: bool,
     pub exclude_patterns: Vec<String>,
     pub include_patterns: Vec<IncludePattern>,
+    // Gitignore-style `--glob` patterns (`!` negates) matched against paths
+    // relative to `path`; empty means any
+    pub globs: Vec<String>,
     pub respect_gitignore: bool,
     pub use_ckignore: bool,
+    pub follow_symlinks: bool,
+    pub archives: bool,
     pub full_section: bool,
     // Enhanced embedding options (search-time only)
     pub rerank: bool,
     pub rerank_model: Option<String>,
     pub embedding_model: Option<String>,
+    // Query-by-example: `query` holds source code to find similar chunks for
+    pub search_by_example: bool,
+    pub example_language: Option<Language>,
+    // The file `--like-file` read the example from; its own chunks are left out
+    pub example_file: Option<PathBuf>,
+    // Only consider chunks whose breadcrumb lies within one of these `::` paths;
+    // empty means any
+    pub scopes: Vec<String>,
+    pub chunk_filter: ChunkFilter,
+    // Only consider chunks of these kinds (see `CHUNK_TYPE_NAMES`); empty means any
+    pub chunk_types: Vec<String>,
+    // Only search chunks touched by this git ref range (e.g. `main...HEAD`)
+    pub diff_range: Option<String>,
+    // Reorder the results; `None` keeps the search's own ranking
+    pub sort: Option<ResultSort>,
+    // Overrides the similarity metric recorded with the index
+    pub metric: Option<SimilarityMetric>,
+    // Semantic and hybrid results mark the preview words shared with the query
+    pub highlight_terms: bool,
+    // Keep identifiers whole in lexical matching instead of splitting them on
+    // camelCase, snake_case and kebab-case boundaries
+    pub exact_tokens: bool,
+    // Some(n): merge results in the same file whose spans are at most n lines apart
+    pub merge_adjacent: Option<usize>,
+    // Annotate each result with the most recent author and date from git blame
+    pub blame: bool,
+    // Start each preview with the first line of every chunk enclosing the result
+    pub show_enclosing: bool,
+    // Keep only, or rank first, files modified within a window of now
+    pub recent: Option<RecentFiles>,
+    // Structural search finds the chunks using the query as an identifier
+    // rather than the chunks named like it
+    pub references: bool,
+    // Leave out, or keep only, test files and test chunks
+    pub tests: TestFilter,
+    // Globs naming test files; empty means DEFAULT_TEST_PATTERNS
+    pub test_patterns: Vec<String>,
 }
 
 impl JsonlSearchResult {
@@ -362,6 +864,12 @@ impl JsonlSearchResult {
             },
             chunk_hash: result.chunk_hash.clone(),
             index_epoch: result.index_epoch,
+            blame: result.blame.clone(),
+            match_ranges: if include_snippet {
+                result.match_ranges.clone()
+            } else {
+                Vec::new()
+            },
         }
     }
 }
@@ -377,6 +885,7 @@ impl Default for SearchOptions {
             case_insensitive: false,
             whole_word: false,
             fixed_string: false,
+            multiline: false,
             line_numbers: false,
             context_lines: 0,
             before_context_lines: 0,
@@ -384,6 +893,9 @@ impl Default for SearchOptions {
             recursive: true,
             json_output: false,
             jsonl_output: false,
+            vimgrep_output: false,
+            compact_output: false,
+            markdown_rows: None,
             no_snippet: false,
             reindex: false,
             show_scores: false,
@@ -392,17 +904,74 @@ impl Default for SearchOptions {
             files_without_matches: false,
             exclude_patterns: get_default_exclude_patterns(),
             include_patterns: Vec::new(),
+            globs: Vec::new(),
             respect_gitignore: true,
             use_ckignore: true,
+            follow_symlinks: false,
+            archives: false,
             full_section: false,
             // Enhanced embedding options (search-time only)
             rerank: false,
             rerank_model: None,
             embedding_model: None,
+            search_by_example: false,
+            example_language: None,
+            example_file: None,
+            scopes: Vec::new(),
+            chunk_filter: ChunkFilter::All,
+            chunk_types: Vec::new(),
+            diff_range: None,
+            sort: None,
+            metric: None,
+            highlight_terms: false,
+            exact_tokens: false,
+            merge_adjacent: None,
+            blame: false,
+            show_enclosing: false,
+            recent: None,
+            references: false,
+            tests: TestFilter::All,
+            test_patterns: Vec::new(),
         }
     }
 }
 
+/// Whether a search pattern has an uppercase letter, which turns off smart-case
+/// matching. Letters in regex escapes (`\S`, `\p{Lu}`, `\x4A`) don't count;
+/// a `literal` pattern (`-F`) is taken as plain text.
+pub fn pattern_has_uppercase(pattern: &str, literal: bool) -> bool {
+    if literal {
+        return pattern.chars().any(char::is_uppercase);
+    }
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            if c.is_uppercase() {
+                return true;
+            }
+            continue;
+        }
+        let Some(class @ ('p' | 'P' | 'x' | 'u' | 'U')) = chars.next() else {
+            continue;
+        };
+        if chars.next_if_eq(&'{').is_some() {
+            chars.by_ref().find(|&c| c == '}');
+        } else if matches!(class, 'p' | 'P') {
+            chars.next();
+        } else {
+            let digits = match class {
+                'x' => 2,
+                'u' => 4,
+                _ => 8,
+            };
+            for _ in 0..digits {
+                chars.next_if(char::is_ascii_hexdigit);
+            }
+        }
+    }
+    false
+}
+
 /// Get default exclusion patterns for directories that should be skipped during search.
 /// These are common cache, build, and system directories that rarely contain user code.
 pub fn get_default_exclude_patterns() -> Vec<String> {
@@ -597,6 +1166,12 @@ pub fn get_sidecar_path(repo_root: &Path, file_path: &Path) -> PathBuf {
 pub fn compute_file_hash(path: &Path) -> Result<String> {
     use std::io::Read;
 
+    // Archive entries hash their uncompressed bytes
+    if let Some((archive, entry)) = archive::split_virtual_path(path) {
+        let bytes = archive::read_entry(&archive, &entry)?;
+        return Ok(blake3::hash(&bytes).to_hex().to_string());
+    }
+
     let mut file = std::fs::File::open(path)?;
     let mut hasher = blake3::Hasher::new();
 
@@ -644,6 +1219,57 @@ pub fn compute_chunk_hash(
     hasher.finalize().to_hex().to_string()
 }
 
+/// A chunk identifier as produced by [`compute_chunk_id`]
+pub type ChunkId = String;
+
+/// Compute a stable identifier for a chunk.
+///
+/// Derived from the file path, breadcrumb and content hash (see
+/// [`compute_chunk_hash`]) so it survives reindexing and line shifts,
+/// but changes whenever the chunk's content does. Path separators are
+/// normalized so ids match across platforms.
+pub fn compute_chunk_id(file_path: &Path, breadcrumb: Option<&str>, content_hash: &str) -> ChunkId {
+    let normalized_path = file_path.to_string_lossy().replace('\\', "/");
+    let normalized_path = normalized_path.trim_start_matches("./");
+
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(normalized_path.as_bytes());
+    hasher.update(&[0]);
+    hasher.update(breadcrumb.unwrap_or("").as_bytes());
+    hasher.update(&[0]);
+    hasher.update(content_hash.as_bytes());
+
+    let hex = hasher.finalize().to_hex();
+    hex[..16].to_string()
+}
+
+/// Check whether a chunk's breadcrumb (its `::`-joined ancestry) lies within `scope`.
+///
+/// Matching is case-sensitive and by whole path segments: `net::http` matches
+/// `net::http` and `net::http::Client` but not `net::https`. Top-level chunks
+/// have no breadcrumb and are only in an empty scope.
+pub fn breadcrumb_in_scope(breadcrumb: Option<&str>, scope: &str) -> bool {
+    let scope = scope.trim_end_matches("::");
+    if scope.is_empty() {
+        return true;
+    }
+
+    breadcrumb.is_some_and(|crumb| {
+        crumb
+            .strip_prefix(scope)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+    })
+}
+
+/// Whether a breadcrumb lies within any of `scopes` (an empty list accepts
+/// everything), each matched as by [`breadcrumb_in_scope`]
+pub fn breadcrumb_in_any_scope(breadcrumb: Option<&str>, scopes: &[String]) -> bool {
+    scopes.is_empty()
+        || scopes
+            .iter()
+            .any(|scope| breadcrumb_in_scope(breadcrumb, scope))
+}
+
 /// PDF-specific utilities
 pub mod pdf {
     use std::path::{Path, PathBuf};
@@ -877,6 +1503,36 @@ mod tests {
         assert!(span.is_valid());
     }
 
+    #[test]
+    fn test_pattern_has_uppercase_skips_escapes() {
+        assert!(!pattern_has_uppercase("hello", false));
+        assert!(pattern_has_uppercase("Hello", false));
+        assert!(!pattern_has_uppercase(r"\S+\W\bfoo\B", false));
+        assert!(!pattern_has_uppercase(r"\p{Lu}\pL\x4A\x{1F600}", false));
+        assert!(pattern_has_uppercase(r"\p{Lu}Foo", false));
+        assert!(pattern_has_uppercase(r"\x41Bad", false));
+        assert!(pattern_has_uppercase(r"\W", true));
+        assert!(!pattern_has_uppercase("a.b(", true));
+    }
+
+    #[test]
+    fn test_similarity_metrics_score_closer_vectors_higher() {
+        let query = [1.0, 0.0];
+        let (near, far) = ([2.0, 0.5], [0.0, 1.0]);
+        for name in SIMILARITY_METRIC_NAMES {
+            let metric = SimilarityMetric::from_name(name).unwrap();
+            assert_eq!(metric.name(), *name);
+            assert!(metric.score(&query, &near) > metric.score(&query, &far));
+            assert_eq!(metric.score(&query, &[1.0]), 0.0);
+        }
+
+        // Dot keeps magnitude, which cosine normalizes away
+        assert!((SimilarityMetric::Dot.score(&query, &[3.0, 4.0]) - 3.0).abs() < 1e-6);
+        assert!((SimilarityMetric::Cosine.score(&query, &[3.0, 4.0]) - 0.6).abs() < 1e-6);
+        assert!((SimilarityMetric::Euclidean.score(&query, &[1.0, 3.0]) - 0.25).abs() < 1e-6);
+        assert_eq!(SimilarityMetric::Euclidean.score(&query, &query), 1.0);
+    }
+
     #[test]
     fn test_search_options_default() {
         let options = SearchOptions::default();
@@ -929,8 +1585,10 @@ mod tests {
             preview: "hello world".to_string(),
             lang: Some(Language::Rust),
             symbol: Some("main".to_string()),
+            breadcrumb: None,
             chunk_hash: Some("abc123".to_string()),
             index_epoch: Some(1699123456),
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -959,8 +1617,10 @@ mod tests {
             preview: "function authenticate(user) {...}".to_string(),
             lang: Some(Language::Rust),
             symbol: Some("authenticate".to_string()),
+            breadcrumb: None,
             chunk_hash: Some("abc123def456".to_string()),
             index_epoch: Some(1699123456),
+            ..Default::default()
         };
 
         // Test with snippet
@@ -1055,6 +1715,135 @@ mod tests {
         assert_ne!(hash1, hash3);
     }
 
+    #[test]
+    fn test_chunk_filter_accepts() {
+        assert!(ChunkFilter::All.accepts(Some("function")));
+        assert!(ChunkFilter::All.accepts(None));
+        assert!(ChunkFilter::DocsOnly.accepts(None));
+        assert!(ChunkFilter::DocsOnly.accepts(Some("text")));
+        assert!(!ChunkFilter::DocsOnly.accepts(Some("method")));
+        assert!(ChunkFilter::CodeOnly.accepts(Some("class")));
+        assert!(!ChunkFilter::CodeOnly.accepts(None));
+    }
+
+    #[test]
+    fn test_compare_by_score_breaks_ties_by_location() {
+        let result = |file: &str, lines: (usize, usize), score: f32| SearchResult {
+            file: PathBuf::from(file),
+            span: Span::new_unchecked(0, 0, lines.0, lines.1),
+            score,
+            ..Default::default()
+        };
+        let mut results = [
+            result("b.rs", (1, 4), 0.8),
+            result("a.rs", (10, 12), 0.8),
+            result("a.rs", (3, 9), 0.8),
+            result("a.rs", (3, 5), 0.8),
+            result("z.rs", (1, 1), 0.9),
+        ];
+        results.sort_by(compare_by_score);
+        let order: Vec<(&str, usize, usize)> = results
+            .iter()
+            .map(|r| (r.file.to_str().unwrap(), r.span.line_start, r.span.line_end))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("z.rs", 1, 1),
+                ("a.rs", 3, 5),
+                ("a.rs", 3, 9),
+                ("a.rs", 10, 12),
+                ("b.rs", 1, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_symbol_kind_round_trips_chunk_types() {
+        for name in CHUNK_TYPE_NAMES {
+            assert_eq!(SymbolKind::from_chunk_type(Some(name)).name(), *name);
+        }
+        assert_eq!(SymbolKind::from_chunk_type(None), SymbolKind::Text);
+        assert_eq!(SymbolKind::from_chunk_type(Some("macro")), SymbolKind::Text);
+        assert_eq!(SymbolKind::Text.index_chunk_type(), None);
+        assert_eq!(SymbolKind::Method.index_chunk_type(), Some("method"));
+        assert_eq!(SymbolKind::Function.lsp_kind(), 12);
+    }
+
+    #[test]
+    fn test_chunk_type_selected() {
+        let types = vec!["function".to_string(), "text".to_string()];
+        assert!(chunk_type_selected(&[], Some("class")));
+        assert!(chunk_type_selected(&types, Some("function")));
+        assert!(chunk_type_selected(&types, None));
+        assert!(!chunk_type_selected(&types, Some("method")));
+    }
+
+    #[test]
+    fn test_breadcrumb_in_scope() {
+        assert!(breadcrumb_in_scope(Some("net::http"), "net::http"));
+        assert!(breadcrumb_in_scope(Some("net::http::Client"), "net::http"));
+        assert!(breadcrumb_in_scope(
+            Some("net::http::Client"),
+            "net::http::"
+        ));
+        assert!(breadcrumb_in_scope(Some("net::http"), "net"));
+        assert!(!breadcrumb_in_scope(Some("net::https"), "net::http"));
+        assert!(!breadcrumb_in_scope(Some("Net::http"), "net::http"));
+        assert!(!breadcrumb_in_scope(Some("net"), "net::http"));
+        assert!(!breadcrumb_in_scope(None, "net"));
+        assert!(breadcrumb_in_scope(None, ""));
+
+        let scopes = ["auth".to_string(), "billing::".to_string()];
+        assert!(breadcrumb_in_any_scope(Some("auth::Session"), &scopes));
+        assert!(breadcrumb_in_any_scope(Some("billing::Invoice"), &scopes));
+        assert!(!breadcrumb_in_any_scope(Some("authz::Policy"), &scopes));
+        assert!(!breadcrumb_in_any_scope(None, &scopes));
+        assert!(breadcrumb_in_any_scope(None, &[]));
+    }
+
+    #[test]
+    fn test_whitespace_mode_normalizes_tabs_and_trailing_space() {
+        let line = "\tif x {\t// note  \t";
+        assert_eq!(WhitespaceMode::Verbatim.apply(line), line);
+        assert_eq!(
+            WhitespaceMode::Normalized.apply(line),
+            "    if x {  // note"
+        );
+        assert_eq!(WhitespaceMode::Normalized.apply("ab\tc"), "ab  c");
+        assert_eq!(
+            WhitespaceMode::Normalized.apply_lines(&["x  ".to_string(), "\t".to_string()]),
+            vec!["x".to_string(), String::new()]
+        );
+    }
+
+    #[test]
+    fn test_compute_chunk_id_stability() {
+        let path = Path::new("src/lib.rs");
+        let hash = compute_chunk_hash("fn a() {}", &[], &[]);
+
+        let id = compute_chunk_id(path, Some("Foo"), &hash);
+        assert_eq!(id.len(), 16);
+        assert_eq!(
+            id,
+            compute_chunk_id(Path::new("./src/lib.rs"), Some("Foo"), &hash)
+        );
+        assert_eq!(
+            id,
+            compute_chunk_id(Path::new("src\\lib.rs"), Some("Foo"), &hash)
+        );
+
+        // Any change to path, breadcrumb or content yields a new id
+        let edited = compute_chunk_hash("fn a() { 1 }", &[], &[]);
+        assert_ne!(id, compute_chunk_id(path, Some("Foo"), &edited));
+        assert_ne!(id, compute_chunk_id(path, Some("Bar"), &hash));
+        assert_ne!(id, compute_chunk_id(path, None, &hash));
+        assert_ne!(
+            id,
+            compute_chunk_id(Path::new("src/main.rs"), Some("Foo"), &hash)
+        );
+    }
+
     #[test]
     fn test_json_search_result_serialization() {
         let signals = SearchSignals {
@@ -1077,6 +1866,8 @@ mod tests {
             signals,
             preview: "hello".to_string(),
             model: "bge-small".to_string(),
+            match_ranges: Vec::new(),
+            blame: None,
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -1107,9 +1898,77 @@ mod tests {
         assert_eq!(Language::from_extension("swift"), Some(Language::Swift));
         assert_eq!(Language::from_extension("kt"), Some(Language::Kotlin));
         assert_eq!(Language::from_extension("kts"), Some(Language::Kotlin));
+        assert_eq!(Language::from_extension("graphql"), Some(Language::GraphQl));
+        assert_eq!(Language::from_extension("gql"), Some(Language::GraphQl));
+        assert_eq!(Language::from_extension("toml"), Some(Language::Toml));
+        assert_eq!(Language::from_extension("yml"), Some(Language::Yaml));
         assert_eq!(Language::from_extension("unknown"), None);
     }
 
+    #[test]
+    fn test_parse_duration() {
+        use std::time::Duration;
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(2 * 3600)));
+        assert_eq!(parse_duration("3d"), Ok(Duration::from_secs(3 * 86400)));
+        assert_eq!(parse_duration("1w"), Ok(Duration::from_secs(7 * 86400)));
+        assert!(parse_duration("2 days").is_err());
+        assert!(parse_duration("h").is_err());
+    }
+
+    #[test]
+    fn test_is_test_chunk() {
+        let none: &[String] = &[];
+        assert!(is_test_chunk(Some("test_parse"), none, none));
+        assert!(is_test_chunk(Some("TestParser"), none, none));
+        assert!(is_test_chunk(Some("testParse"), none, none));
+        assert!(!is_test_chunk(Some("testament"), none, none));
+        assert!(!is_test_chunk(Some("contest"), none, none));
+        assert!(!is_test_chunk(None, none, none));
+
+        // Inside `mod tests`, or headed by a test attribute or decorator
+        assert!(is_test_chunk(Some("helper"), &["tests".to_string()], none));
+        assert!(is_test_chunk(
+            Some("parses"),
+            none,
+            &["#[tokio::test]".to_string()]
+        ));
+        assert!(is_test_chunk(Some("parses"), none, &["@Test".to_string()]));
+        assert!(is_test_chunk(
+            Some("parses"),
+            none,
+            &["@pytest.mark.parametrize(\"x\", [1])".to_string()]
+        ));
+        assert!(is_test_chunk(
+            Some("unit"),
+            none,
+            &["#[cfg(test)]".to_string()]
+        ));
+        // Attributes that only mention tests leave production code alone
+        for attribute in [
+            "#[cfg(not(test))]",
+            "#[cfg_attr(test, derive(Debug))]",
+            "@attestation",
+            "#[testing_only]",
+        ] {
+            assert!(
+                !is_test_chunk(Some("parses"), none, &[attribute.to_string()]),
+                "{}",
+                attribute
+            );
+        }
+        assert!(!is_test_chunk(
+            Some("parses"),
+            none,
+            &["/// test the parser".to_string()]
+        ));
+
+        assert!(TestFilter::All.keeps(true) && TestFilter::All.keeps(false));
+        assert!(!TestFilter::Exclude.keeps(true) && TestFilter::Exclude.keeps(false));
+        assert!(TestFilter::Only.keeps(true) && !TestFilter::Only.keeps(false));
+    }
+
     #[test]
     fn test_language_from_extension_case_insensitive() {
         // Test uppercase extensions - only for actually supported languages
@@ -0,0 +1,282 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// fastembed downloads into the Hugging Face cache layout: each file's bytes are
+// `<cache>/models--<org>--<name>/blobs/<etag>`, linked from
+// `snapshots/<revision>/<file>`, and a download in progress is
+// `blobs/<etag>.sync.part`, which the next attempt appends to. Large files are
+// stored with Git LFS, whose etag is the SHA-256 of the content.
+
+/// Times a model is fetched before ck gives up on a download
+const MAX_DOWNLOAD_ATTEMPTS: u32 = 3;
+
+/// Left next to a blob once its content checked out, holding its length, so
+/// later loads don't hash the model again
+const VERIFIED_SUFFIX: &str = ".ck-verified";
+
+/// Suffix of a partial download in the cache
+const PARTIAL_SUFFIX: &str = ".sync.part";
+
+/// A cached model file that failed verification and was deleted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CorruptFile {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Run `load`, which downloads whatever model files are missing from
+/// `cache_dir` and loads the model, with every file verified before it is
+/// trusted. Files whose SHA-256 doesn't match their etag, or JSON files that
+/// don't parse, are deleted and the load is retried so they are fetched again;
+/// a load that fails with a partial download on disk is retried too, resuming
+/// where the download stopped. Progress goes to `progress`.
+pub(crate) fn load_verified<T, F>(
+    cache_dir: &Path,
+    progress: Option<&F>,
+    mut load: impl FnMut() -> Result<T>,
+) -> Result<T>
+where
+    F: Fn(&str) + ?Sized,
+{
+    let report = |message: &str| {
+        if let Some(callback) = progress {
+            callback(message);
+        }
+    };
+    let report_corrupt = |corrupt: &[CorruptFile]| {
+        for file in corrupt {
+            report(&format!(
+                "Deleted corrupt model file {} ({}); downloading it again",
+                file.path.display(),
+                file.reason
+            ));
+        }
+    };
+
+    let mut attempt = 1;
+    loop {
+        report_corrupt(&remove_corrupt_files(cache_dir)?);
+        let loaded = load();
+        report("Verifying model files...");
+        let corrupt = remove_corrupt_files(cache_dir)?;
+        report_corrupt(&corrupt);
+
+        let retry_reason = match loaded {
+            Ok(model) if corrupt.is_empty() => return Ok(model),
+            Ok(_) => format!("{} file(s) failed verification", corrupt.len()),
+            Err(err) if !corrupt.is_empty() || has_partial_download(cache_dir) => {
+                format!("download interrupted: {}", err)
+            }
+            Err(err) => return Err(err),
+        };
+        if attempt == MAX_DOWNLOAD_ATTEMPTS {
+            anyhow::bail!(
+                "Model download into {} failed after {} attempts ({}). Check the connection and run again; finished files are kept and partial ones resume",
+                cache_dir.display(),
+                attempt,
+                retry_reason
+            );
+        }
+        report(&format!(
+            "Model download attempt {} of {} failed ({}); retrying",
+            attempt, MAX_DOWNLOAD_ATTEMPTS, retry_reason
+        ));
+        std::thread::sleep(Duration::from_secs(u64::from(attempt)));
+        attempt += 1;
+    }
+}
+
+/// Verify every file downloaded into `cache_dir` and delete those that fail,
+/// with the snapshot links to them, so the next load downloads them again.
+/// Blobs already verified at their current length are skipped.
+pub(crate) fn remove_corrupt_files(cache_dir: &Path) -> Result<Vec<CorruptFile>> {
+    let mut corrupt = Vec::new();
+    let Ok(repos) = fs::read_dir(cache_dir) else {
+        return Ok(corrupt);
+    };
+    for repo in repos.flatten() {
+        let repo = repo.path();
+        if repo.join("blobs").is_dir() {
+            corrupt.extend(remove_corrupt_repo_files(&repo)?);
+        }
+    }
+    Ok(corrupt)
+}
+
+fn remove_corrupt_repo_files(repo: &Path) -> Result<Vec<CorruptFile>> {
+    // Which snapshot files point at each blob, by blob name
+    let mut links: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    let mut copies = Vec::new();
+    for file in files_under(&repo.join("snapshots")) {
+        match fs::read_link(&file) {
+            Ok(target) => {
+                if let Some(blob) = target.file_name() {
+                    links
+                        .entry(blob.to_string_lossy().into_owned())
+                        .or_default()
+                        .push(file);
+                }
+            }
+            // Where symlinks aren't allowed the blob is moved into the snapshot
+            Err(_) => copies.push(file),
+        }
+    }
+
+    let mut corrupt = Vec::new();
+    for entry in fs::read_dir(repo.join("blobs"))?.flatten() {
+        let blob = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // Locks, partial downloads and our markers all have an extension
+        if name.contains('.') || !blob.is_file() {
+            continue;
+        }
+        let marker = blob.with_file_name(format!("{}{}", name, VERIFIED_SUFFIX));
+        let length = entry.metadata()?.len().to_string();
+        if fs::read_to_string(&marker).is_ok_and(|verified| verified == length) {
+            continue;
+        }
+
+        let linked = links.get(&name).map(Vec::as_slice).unwrap_or_default();
+        let problem = if is_sha256(&name) {
+            let actual = sha256_file(&blob)?;
+            (actual != name).then(|| format!("SHA-256 {} instead of {}", actual, name))
+        } else if linked.iter().any(|link| is_json(link)) {
+            invalid_json(&blob)
+        } else {
+            None
+        };
+        match problem {
+            Some(reason) => {
+                for path in linked.iter().chain([&blob, &marker]) {
+                    let _ = fs::remove_file(path);
+                }
+                corrupt.push(CorruptFile { path: blob, reason });
+            }
+            None => fs::write(&marker, length)?,
+        }
+    }
+
+    for copy in copies.into_iter().filter(|copy| is_json(copy)) {
+        if let Some(reason) = invalid_json(&copy) {
+            fs::remove_file(&copy)?;
+            corrupt.push(CorruptFile { path: copy, reason });
+        }
+    }
+    Ok(corrupt)
+}
+
+fn has_partial_download(cache_dir: &Path) -> bool {
+    files_under(cache_dir)
+        .iter()
+        .any(|file| file.to_string_lossy().ends_with(PARTIAL_SUFFIX))
+}
+
+/// Every file below `dir`, not following symlinks to directories
+fn files_under(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            match entry.file_type() {
+                Ok(kind) if kind.is_dir() => pending.push(entry.path()),
+                Ok(_) => files.push(entry.path()),
+                Err(_) => {}
+            }
+        }
+    }
+    files
+}
+
+fn is_sha256(name: &str) -> bool {
+    name.len() == 64 && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Why a config or tokenizer file isn't valid JSON, as a truncated one isn't
+fn invalid_json(path: &Path) -> Option<String> {
+    let reader = std::io::BufReader::new(fs::File::open(path).ok()?);
+    serde_json::from_reader::<_, serde::de::IgnoredAny>(reader)
+        .err()
+        .map(|err| format!("invalid JSON: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrupt_blobs_and_their_links_are_deleted() {
+        let cache = tempfile::TempDir::new().unwrap();
+        let repo = cache.path().join("models--org--model");
+        let snapshot = repo.join("snapshots").join("abc123");
+        fs::create_dir_all(repo.join("blobs")).unwrap();
+        fs::create_dir_all(snapshot.join("onnx")).unwrap();
+        let blob = |name: &str, content: &str, link: &str| {
+            fs::write(repo.join("blobs").join(name), content).unwrap();
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(repo.join("blobs").join(name), snapshot.join(link)).unwrap();
+        };
+
+        let weights = "model weights";
+        let good_hash = format!("{:x}", Sha256::digest(weights));
+        blob(&good_hash, weights, "onnx/model.onnx");
+        let truncated = "a4d8a8a8dc6f7d6e1b0c23fbe5d7f0d7e8d9f1c4b0e5a2f3d6c7b8a9e0f1d2c3";
+        blob(truncated, "model wei", "onnx/model_quantized.onnx");
+        blob("11f7ac5c", "{\"vocab\": {}}", "tokenizer.json");
+        blob("2b1e0d3a", "{\"vocab\": {", "config.json");
+        fs::write(repo.join("blobs").join("feed.sync.part"), "partial").unwrap();
+
+        let mut corrupt: Vec<String> = remove_corrupt_files(cache.path())
+            .unwrap()
+            .into_iter()
+            .map(|file| {
+                file.path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        corrupt.sort();
+        let mut expected = vec![truncated.to_string()];
+        if cfg!(unix) {
+            // Without links the JSON blobs can't be told apart from other files
+            expected.insert(0, "2b1e0d3a".to_string());
+        }
+        assert_eq!(corrupt, expected);
+        assert!(!repo.join("blobs").join(truncated).exists());
+        assert!(repo.join("blobs").join(&good_hash).exists());
+        assert!(has_partial_download(cache.path()));
+        #[cfg(unix)]
+        {
+            assert!(fs::symlink_metadata(snapshot.join("onnx/model_quantized.onnx")).is_err());
+            assert!(snapshot.join("onnx/model.onnx").exists());
+        }
+
+        // Verified blobs are marked and not hashed again
+        let marker = repo
+            .join("blobs")
+            .join(format!("{}{}", good_hash, VERIFIED_SUFFIX));
+        assert_eq!(
+            fs::read_to_string(&marker).unwrap(),
+            weights.len().to_string()
+        );
+        assert!(remove_corrupt_files(cache.path()).unwrap().is_empty());
+    }
+}
@@ -1,11 +1,18 @@
 use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
 
 #[cfg(feature = "fastembed")]
 use std::path::{Path, PathBuf};
 
+#[cfg(feature = "fastembed")]
+mod download;
+pub mod remote;
 pub mod reranker;
 pub mod tokenizer;
 
+pub use remote::{RemoteConfig, RemoteEmbedder};
 pub use reranker::{RerankResult, Reranker, create_reranker, create_reranker_with_progress};
 pub use tokenizer::TokenEstimator;
 
@@ -18,6 +25,8 @@ pub trait Embedder: Send + Sync {
 
 pub type ModelDownloadCallback = Box<dyn Fn(&str) + Send + Sync>;
 
+const DEFAULT_MODEL: &str = "BAAI/bge-small-en-v1.5";
+
 pub fn create_embedder(model_name: Option<&str>) -> Result<Box<dyn Embedder>> {
     create_embedder_with_progress(model_name, None)
 }
@@ -26,7 +35,18 @@ pub fn create_embedder_with_progress(
     model_name: Option<&str>,
     progress_callback: Option<ModelDownloadCallback>,
 ) -> Result<Box<dyn Embedder>> {
-    let model = model_name.unwrap_or("BAAI/bge-small-en-v1.5");
+    let model = model_name.unwrap_or(DEFAULT_MODEL);
+
+    if ck_models::remote_model_id(model).is_some() {
+        let config = RemoteConfig::from_env();
+        if let Some(callback) = progress_callback {
+            callback(&format!(
+                "Using remote embeddings endpoint {}",
+                config.base_url
+            ));
+        }
+        return Ok(Box::new(RemoteEmbedder::new(model, config)?));
+    }
 
     #[cfg(feature = "fastembed")]
     {
@@ -45,6 +65,190 @@ pub fn create_embedder_with_progress(
     }
 }
 
+type LoadedEmbedder = Arc<Mutex<Box<dyn Embedder>>>;
+
+/// Models loaded so far in this process, by name. Loading an ONNX session
+/// costs far more than embedding a query, so each model loads once and every
+/// later search in a long-running mode (the TUI, `--serve`) reuses it.
+static LOADED_EMBEDDERS: OnceLock<Mutex<HashMap<String, LoadedEmbedder>>> = OnceLock::new();
+
+/// Like [`create_embedder`], but shares one loaded model per name across the
+/// process instead of loading it again
+pub fn cached_embedder(model_name: Option<&str>) -> Result<Box<dyn Embedder>> {
+    let model = model_name.unwrap_or(DEFAULT_MODEL);
+    Ok(Box::new(cached_with(model, || {
+        create_embedder(Some(model))
+    })?))
+}
+
+fn cached_with(
+    model: &str,
+    load: impl FnOnce() -> Result<Box<dyn Embedder>>,
+) -> Result<SharedEmbedder> {
+    let mut loaded = LOADED_EMBEDDERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    let inner = match loaded.get(model) {
+        Some(inner) => inner.clone(),
+        // Failures aren't cached, so a later search retries the load
+        None => {
+            let inner = Arc::new(Mutex::new(load()?));
+            loaded.insert(model.to_string(), inner.clone());
+            inner
+        }
+    };
+    Ok(SharedEmbedder::new(inner))
+}
+
+fn is_loaded(model: &str) -> bool {
+    LOADED_EMBEDDERS.get().is_some_and(|loaded| {
+        loaded
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(model)
+    })
+}
+
+/// A handle on a model in the process-wide cache; embedding locks it, so
+/// concurrent searches take turns
+struct SharedEmbedder {
+    inner: LoadedEmbedder,
+    id: &'static str,
+    dim: usize,
+    model_name: String,
+}
+
+impl SharedEmbedder {
+    fn new(inner: LoadedEmbedder) -> Self {
+        let (id, dim, model_name) = {
+            let embedder = inner.lock().unwrap_or_else(PoisonError::into_inner);
+            (
+                embedder.id(),
+                embedder.dim(),
+                embedder.model_name().to_string(),
+            )
+        };
+        Self {
+            inner,
+            id,
+            dim,
+            model_name,
+        }
+    }
+}
+
+impl Embedder for SharedEmbedder {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    fn embed(&mut self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .embed(texts)
+    }
+}
+
+/// An embedder keeping only the leading `dims` dimensions of each embedding,
+/// rescaled to unit length. Matryoshka-trained models (see
+/// `ck_models::ModelConfig::matryoshka_dims`) lose little by it.
+pub struct TruncatedEmbedder {
+    inner: Box<dyn Embedder>,
+    dims: usize,
+}
+
+impl TruncatedEmbedder {
+    pub fn new(inner: Box<dyn Embedder>, dims: usize) -> Self {
+        Self { inner, dims }
+    }
+}
+
+impl Embedder for TruncatedEmbedder {
+    fn id(&self) -> &'static str {
+        self.inner.id()
+    }
+
+    fn dim(&self) -> usize {
+        self.dims.min(self.inner.dim())
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+
+    fn embed(&mut self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = self.inner.embed(texts)?;
+        for embedding in &mut embeddings {
+            truncate_embedding(embedding, self.dims);
+        }
+        Ok(embeddings)
+    }
+}
+
+/// `embedder`, truncated to `dims` dimensions when that's set
+pub fn with_dims(embedder: Box<dyn Embedder>, dims: Option<usize>) -> Box<dyn Embedder> {
+    match dims {
+        Some(dims) => Box::new(TruncatedEmbedder::new(embedder, dims)),
+        None => embedder,
+    }
+}
+
+/// Keep the first `dims` values of `embedding` and rescale them to unit
+/// length; an all-zero prefix stays zero
+pub fn truncate_embedding(embedding: &mut Vec<f32>, dims: usize) {
+    embedding.truncate(dims);
+    let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in embedding.iter_mut() {
+            *x /= norm;
+        }
+    }
+}
+
+/// What warming up a model took
+#[derive(Debug, Clone)]
+pub struct Warmup {
+    pub model_name: String,
+    pub dim: usize,
+    /// Whether an earlier search or warm-up in this process already loaded it
+    pub already_loaded: bool,
+    pub load_time: Duration,
+    pub inference_time: Duration,
+}
+
+/// Load a model into the process-wide cache and run one throwaway inference,
+/// so the first real query doesn't pay for either
+pub fn warm_up(model_name: Option<&str>) -> Result<Warmup> {
+    let model = model_name.unwrap_or(DEFAULT_MODEL);
+    let already_loaded = is_loaded(model);
+
+    let started = Instant::now();
+    let mut embedder = cached_embedder(Some(model))?;
+    let load_time = started.elapsed();
+
+    let started = Instant::now();
+    embedder.embed(&["warm up".to_string()])?;
+    let inference_time = started.elapsed();
+
+    Ok(Warmup {
+        model_name: embedder.model_name().to_string(),
+        dim: embedder.dim(),
+        already_loaded,
+        load_time,
+        inference_time,
+    })
+}
+
 pub struct DummyEmbedder {
     dim: usize,
     model_name: String,
@@ -165,10 +369,13 @@ impl FastEmbedder {
 
         let init_options = InitOptions::new(model.clone())
             .with_show_download_progress(progress_callback.is_some())
-            .with_cache_dir(model_cache_dir)
+            .with_cache_dir(model_cache_dir.clone())
             .with_max_length(max_length);
 
-        let embedding = TextEmbedding::try_new(init_options)?;
+        let embedding =
+            download::load_verified(&model_cache_dir, progress_callback.as_deref(), || {
+                TextEmbedding::try_new(init_options.clone())
+            })?;
 
         if let Some(ref callback) = progress_callback {
             callback("Model loaded successfully");
@@ -246,6 +453,38 @@ impl Embedder for FastEmbedder {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_truncated_embedder_keeps_a_unit_prefix() {
+        struct Fixed;
+        impl Embedder for Fixed {
+            fn id(&self) -> &'static str {
+                "fixed"
+            }
+            fn dim(&self) -> usize {
+                4
+            }
+            fn model_name(&self) -> &str {
+                "fixed"
+            }
+            fn embed(&mut self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+                Ok(texts.iter().map(|_| vec![3.0, 4.0, 12.0, 0.0]).collect())
+            }
+        }
+
+        let mut embedder = with_dims(Box::new(Fixed), Some(2));
+        assert_eq!(embedder.dim(), 2);
+        assert_eq!(embedder.model_name(), "fixed");
+        assert_eq!(
+            embedder.embed(&["a".to_string()]).unwrap(),
+            vec![vec![0.6, 0.8]]
+        );
+        assert_eq!(with_dims(Box::new(Fixed), None).dim(), 4);
+
+        let mut zeros = vec![0.0; 4];
+        truncate_embedding(&mut zeros, 3);
+        assert_eq!(zeros, vec![0.0; 3]);
+    }
+
     #[test]
     fn test_dummy_embedder() {
         let mut embedder = DummyEmbedder::new();
@@ -343,6 +582,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cached_embedder_loads_each_model_once() {
+        let first = cached_with("test/shared-model", || {
+            Ok(Box::new(DummyEmbedder::new_with_model("test/shared-model")))
+        })
+        .unwrap();
+        assert!(is_loaded("test/shared-model"));
+
+        let mut second = cached_with("test/shared-model", || {
+            panic!("the model should come from the cache")
+        })
+        .unwrap();
+        assert!(Arc::ptr_eq(&first.inner, &second.inner));
+        assert_eq!(second.model_name(), "test/shared-model");
+        assert_eq!(second.dim(), 384);
+        assert_eq!(second.embed(&["query".to_string()]).unwrap()[0].len(), 384);
+
+        // A failed load leaves nothing behind to reuse
+        assert!(cached_with("test/broken-model", || anyhow::bail!("no such model")).is_err());
+        assert!(!is_loaded("test/broken-model"));
+    }
+
     #[test]
     fn test_embedder_empty_input() {
         let mut embedder = DummyEmbedder::new();
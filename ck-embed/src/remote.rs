@@ -0,0 +1,346 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::Embedder;
+
+/// Base URL used when `CK_EMBEDDINGS_BASE_URL` is unset
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+const DEFAULT_BATCH_SIZE: usize = 64;
+const MAX_RETRIES: u32 = 6;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Where and how to reach an OpenAI-compatible embeddings endpoint
+#[derive(Debug, Clone)]
+pub struct RemoteConfig {
+    /// Everything before `/embeddings`, e.g. `http://localhost:8080/v1`
+    pub base_url: String,
+    /// Sent as a bearer token when set; self-hosted endpoints often need none
+    pub api_key: Option<String>,
+    /// Texts per request
+    pub batch_size: usize,
+}
+
+impl RemoteConfig {
+    /// Read `CK_EMBEDDINGS_BASE_URL`, `CK_EMBEDDINGS_API_KEY` (falling back to
+    /// `OPENAI_API_KEY`) and `CK_EMBEDDINGS_BATCH_SIZE`
+    pub fn from_env() -> Self {
+        let non_empty = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        Self {
+            base_url: non_empty("CK_EMBEDDINGS_BASE_URL")
+                .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+            api_key: non_empty("CK_EMBEDDINGS_API_KEY").or_else(|| non_empty("OPENAI_API_KEY")),
+            batch_size: non_empty("CK_EMBEDDINGS_BATCH_SIZE")
+                .and_then(|size| size.parse().ok())
+                .filter(|&size| size > 0)
+                .unwrap_or(DEFAULT_BATCH_SIZE),
+        }
+    }
+}
+
+/// Embeds by POSTing to `<base_url>/embeddings`, in batches, retrying rate
+/// limits and server errors with exponential backoff
+pub struct RemoteEmbedder {
+    agent: ureq::Agent,
+    endpoint: String,
+    api_key: Option<String>,
+    batch_size: usize,
+    /// Name recorded in the index, e.g. `openai:text-embedding-3-small`
+    model_name: String,
+    /// Name the endpoint knows the model by
+    remote_model: String,
+    dim: usize,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: Option<usize>,
+}
+
+impl RemoteEmbedder {
+    /// `model_name` is a remote name (`openai:<model>`). Models whose size ck
+    /// doesn't know are probed with one request so `dim()` is right up front.
+    pub fn new(model_name: &str, config: RemoteConfig) -> Result<Self> {
+        let model_config = ck_models::remote_model_config(model_name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "'{}' is not a remote model name (expected {}<model>)",
+                model_name,
+                ck_models::REMOTE_MODEL_PREFIX
+            )
+        })?;
+        let remote_model = ck_models::remote_model_id(model_name)
+            .unwrap_or_default()
+            .to_string();
+
+        let mut embedder = Self {
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+            endpoint: format!("{}/embeddings", config.base_url.trim_end_matches('/')),
+            api_key: config.api_key,
+            batch_size: config.batch_size.max(1),
+            model_name: model_name.to_string(),
+            remote_model,
+            dim: model_config.dimensions,
+        };
+        if embedder.dim == 0 {
+            embedder.embed(&["dimension probe".to_string()])?;
+        }
+        Ok(embedder)
+    }
+
+    fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let body = serde_json::json!({
+            "model": self.remote_model,
+            "input": texts,
+        });
+
+        let mut attempt = 0;
+        let response = loop {
+            let mut request = self.agent.post(&self.endpoint);
+            if let Some(key) = &self.api_key {
+                request = request.set("Authorization", &format!("Bearer {}", key));
+            }
+
+            let retry_after = match request.send_json(&body) {
+                Ok(response) => break response,
+                Err(ureq::Error::Status(code, response)) if code == 429 || code >= 500 => {
+                    if attempt >= MAX_RETRIES {
+                        anyhow::bail!(
+                            "Embeddings endpoint {} still returned {} after {} retries",
+                            self.endpoint,
+                            code,
+                            MAX_RETRIES
+                        );
+                    }
+                    response.header("retry-after").and_then(parse_retry_after)
+                }
+                Err(ureq::Error::Status(code, response)) => {
+                    let detail = response.into_string().unwrap_or_default();
+                    anyhow::bail!(
+                        "Embeddings endpoint {} returned {}: {}",
+                        self.endpoint,
+                        code,
+                        detail.trim()
+                    );
+                }
+                Err(ureq::Error::Transport(err)) => {
+                    if attempt >= MAX_RETRIES {
+                        anyhow::bail!(
+                            "Failed to reach embeddings endpoint {}: {}",
+                            self.endpoint,
+                            err
+                        );
+                    }
+                    None
+                }
+            };
+
+            let delay = retry_after
+                .unwrap_or_else(|| INITIAL_BACKOFF * 2u32.pow(attempt))
+                .min(MAX_BACKOFF);
+            tracing::warn!(
+                "Embeddings request to {} failed, retrying in {:?} (attempt {}/{})",
+                self.endpoint,
+                delay,
+                attempt + 1,
+                MAX_RETRIES
+            );
+            std::thread::sleep(delay);
+            attempt += 1;
+        };
+
+        let mut parsed: EmbeddingResponse = response.into_json().map_err(|e| {
+            anyhow::anyhow!(
+                "Embeddings endpoint {} sent an unexpected response: {}",
+                self.endpoint,
+                e
+            )
+        })?;
+        if parsed.data.len() != texts.len() {
+            anyhow::bail!(
+                "Embeddings endpoint {} returned {} embeddings for {} texts",
+                self.endpoint,
+                parsed.data.len(),
+                texts.len()
+            );
+        }
+        // The spec lets results come back in any order; `index` says which input each is for
+        parsed
+            .data
+            .sort_by_key(|item| item.index.unwrap_or(usize::MAX));
+        Ok(parsed.data.into_iter().map(|item| item.embedding).collect())
+    }
+}
+
+impl Embedder for RemoteEmbedder {
+    fn id(&self) -> &'static str {
+        "openai"
+    }
+
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    fn embed(&mut self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.batch_size) {
+            for embedding in self.embed_batch(batch)? {
+                if self.dim == 0 {
+                    self.dim = embedding.len();
+                } else if embedding.len() != self.dim {
+                    anyhow::bail!(
+                        "Embeddings endpoint {} returned a {}-dimensional vector for {}, expected {}",
+                        self.endpoint,
+                        embedding.len(),
+                        self.model_name,
+                        self.dim
+                    );
+                }
+                embeddings.push(embedding);
+            }
+        }
+        Ok(embeddings)
+    }
+}
+
+/// A `Retry-After` given in seconds; `None` for anything that isn't a finite,
+/// non-negative number, which leaves the wait to the backoff
+fn parse_retry_after(seconds: &str) -> Option<Duration> {
+    let seconds = seconds.trim().parse::<f64>().ok()?;
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// Serve one canned status per connection, answering 200s with 3-d vectors
+    /// whose first value is the input's position in the request
+    fn spawn_server(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<serde_json::Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&requests);
+
+        std::thread::spawn(move || {
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                let mut authorization = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    let lower = line.to_ascii_lowercase();
+                    if let Some(length) = lower.strip_prefix("content-length:") {
+                        content_length = length.trim().parse().unwrap();
+                    }
+                    if lower.starts_with("authorization:") {
+                        authorization = line["authorization:".len()..].trim().to_string();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let mut request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                request["authorization"] = serde_json::json!(authorization);
+
+                let inputs = request["input"].as_array().unwrap().len();
+                seen.lock().unwrap().push(request);
+
+                let (head, body) = if status == 200 {
+                    // Reversed, to check results are put back in input order
+                    let data: Vec<_> = (0..inputs)
+                        .rev()
+                        .map(
+                            |i| serde_json::json!({"index": i, "embedding": [i as f32, 0.5, 0.25]}),
+                        )
+                        .collect();
+                    ("200 OK", serde_json::json!({ "data": data }).to_string())
+                } else {
+                    ("429 Too Many Requests\r\nRetry-After: 0", String::new())
+                };
+                let mut stream = stream;
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    head,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+
+        (base_url, requests)
+    }
+
+    #[test]
+    fn test_remote_embedder_batches_and_retries_rate_limits() {
+        // One probe for the unknown model's size, then a rate limit and two batches
+        let (base_url, requests) = spawn_server(vec![200, 429, 200, 200]);
+        let mut embedder = RemoteEmbedder::new(
+            "openai:nomic-embed-text",
+            RemoteConfig {
+                base_url,
+                api_key: Some("secret".to_string()),
+                batch_size: 2,
+            },
+        )
+        .unwrap();
+        assert_eq!(embedder.dim(), 3);
+        assert_eq!(embedder.model_name(), "openai:nomic-embed-text");
+
+        let texts: Vec<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        let embeddings = embedder.embed(&texts).unwrap();
+        let firsts: Vec<f32> = embeddings.iter().map(|e| e[0]).collect();
+        assert_eq!(firsts, vec![0.0, 1.0, 0.0]);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0]["model"], "nomic-embed-text");
+        assert_eq!(requests[0]["authorization"], "Bearer secret");
+        // The rate-limited batch is resent as is
+        assert_eq!(requests[1]["input"], requests[2]["input"]);
+        assert_eq!(requests[2]["input"], serde_json::json!(["a", "b"]));
+        assert_eq!(requests[3]["input"], serde_json::json!(["c"]));
+    }
+
+    #[test]
+    fn test_retry_after_ignores_values_it_cannot_wait_for() {
+        assert_eq!(parse_retry_after(" 2 "), Some(Duration::from_secs(2)));
+        assert_eq!(parse_retry_after("0.5"), Some(Duration::from_millis(500)));
+        for bad in ["-1", "NaN", "inf", "-inf", "soon", ""] {
+            assert_eq!(parse_retry_after(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_remote_embedder_rejects_local_model_names() {
+        let config = RemoteConfig {
+            base_url: "http://127.0.0.1:9/v1".to_string(),
+            api_key: None,
+            batch_size: 8,
+        };
+        assert!(RemoteEmbedder::new("BAAI/bge-small-en-v1.5", config).is_err());
+    }
+}
@@ -129,9 +129,12 @@ impl FastReranker {
 
         let init_options = RerankInitOptions::new(model.clone())
             .with_show_download_progress(progress_callback.is_some())
-            .with_cache_dir(model_cache_dir);
+            .with_cache_dir(model_cache_dir.clone());
 
-        let reranker = TextRerank::try_new(init_options)?;
+        let reranker =
+            crate::download::load_verified(&model_cache_dir, progress_callback.as_deref(), || {
+                TextRerank::try_new(init_options.clone())
+            })?;
 
         if let Some(ref callback) = progress_callback {
             callback("Reranker model loaded successfully");
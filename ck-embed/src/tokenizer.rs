@@ -1,4 +1,30 @@
 //use anyhow::Result;
+use ck_core::Language;
+
+/// Per-language correction applied on top of the character-based estimate.
+/// Symbol-heavy syntax (sigils, `::`, generics) splits into more tokens per
+/// character than verbose, identifier-heavy code; PDF text is mostly prose.
+/// These are starting points from eyeballing BERT-style tokenizer output on
+/// typical files, not measurements; languages not listed use 1.0.
+pub const LANGUAGE_TOKEN_MULTIPLIERS: &[(Language, f32)] = &[
+    (Language::Rust, 1.10),
+    (Language::Cpp, 1.10),
+    (Language::Php, 1.10),
+    (Language::C, 1.05),
+    (Language::Haskell, 1.05),
+    (Language::JavaScript, 1.05),
+    (Language::TypeScript, 1.05),
+    (Language::Zig, 1.05),
+    (Language::Python, 0.95),
+    (Language::Java, 0.95),
+    (Language::CSharp, 0.95),
+    (Language::Pdf, 0.90),
+];
+
+/// Tokens per CJK character. BERT-style vocabularies split every CJK ideograph
+/// into its own token, so character-per-token ratios tuned on Latin text
+/// undercount CJK by roughly 4x.
+const CJK_TOKENS_PER_CHAR: f32 = 1.0;
 
 /// Simple token estimation for code and text
 /// This is a rough approximation since we don't have access to the actual model tokenizer
@@ -17,6 +43,7 @@ impl TokenEstimator {
 
         // More sophisticated estimation based on content type
         let char_count = text.chars().count();
+        let cjk_count = text.chars().filter(|&ch| is_cjk(ch)).count();
 
         // Detect if text is primarily code vs natural language
         let code_indicators = Self::count_code_indicators(text);
@@ -35,7 +62,31 @@ impl TokenEstimator {
             4.8
         };
 
-        (char_count as f32 / chars_per_token).ceil() as usize
+        ((char_count - cjk_count) as f32 / chars_per_token + cjk_count as f32 * CJK_TOKENS_PER_CHAR)
+            .ceil() as usize
+    }
+
+    /// Like [`Self::estimate_tokens`], scaled by the language's entry in
+    /// [`LANGUAGE_TOKEN_MULTIPLIERS`]
+    pub fn estimate_tokens_for_language(text: &str, language: Option<Language>) -> usize {
+        let estimate = Self::estimate_tokens(text);
+        let multiplier = Self::language_multiplier(language);
+        if multiplier == 1.0 {
+            return estimate;
+        }
+        (estimate as f32 * multiplier).ceil() as usize
+    }
+
+    /// Calibration multiplier for `language`, 1.0 when it has none
+    pub fn language_multiplier(language: Option<Language>) -> f32 {
+        language
+            .and_then(|language| {
+                LANGUAGE_TOKEN_MULTIPLIERS
+                    .iter()
+                    .find(|(known, _)| *known == language)
+                    .map(|(_, multiplier)| *multiplier)
+            })
+            .unwrap_or(1.0)
     }
 
     /// Check if text exceeds token limit for a given model
@@ -98,6 +149,20 @@ impl TokenEstimator {
     }
 }
 
+/// Han ideographs, kana, Hangul and full-width forms
+fn is_cjk(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x3000..=0x30FF // CJK punctuation, hiragana, katakana
+            | 0x3400..=0x4DBF // CJK extension A
+            | 0x4E00..=0x9FFF // CJK unified ideographs
+            | 0xAC00..=0xD7AF // Hangul syllables
+            | 0xF900..=0xFAFF // CJK compatibility ideographs
+            | 0xFF00..=0xFFEF // Half-width and full-width forms
+            | 0x20000..=0x2FA1F // CJK extensions B onwards
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -129,6 +194,30 @@ fn main() {
         assert!((15..=25).contains(&tokens), "Got {} tokens", tokens);
     }
 
+    #[test]
+    fn test_estimate_tokens_counts_cjk_per_character() {
+        // Eleven ideographs are eleven tokens, not the ~3 a Latin ratio would give
+        let text = "这是一个用于测试的句子";
+        let tokens = TokenEstimator::estimate_tokens(text);
+        assert!((11..=12).contains(&tokens), "Got {} tokens", tokens);
+
+        let mixed = "// 计算总和\nfn sum() {}";
+        assert!(TokenEstimator::estimate_tokens(mixed) > mixed.chars().count() / 4);
+    }
+
+    #[test]
+    fn test_language_multiplier_scales_estimate() {
+        let code = "fn main() { let x: Vec<u8> = Vec::new(); }\n".repeat(10);
+        let base = TokenEstimator::estimate_tokens(&code);
+        assert_eq!(
+            TokenEstimator::estimate_tokens_for_language(&code, None),
+            base
+        );
+        assert!(TokenEstimator::estimate_tokens_for_language(&code, Some(Language::Rust)) > base);
+        assert!(TokenEstimator::estimate_tokens_for_language(&code, Some(Language::Pdf)) < base);
+        assert_eq!(TokenEstimator::language_multiplier(Some(Language::Go)), 1.0);
+    }
+
     #[test]
     fn test_exceeds_limit() {
         assert!(!TokenEstimator::exceeds_limit("short text", 100));
@@ -0,0 +1,226 @@
+use chrono::{DateTime, FixedOffset};
+use ck_core::{Blame, SearchOptions, SearchResult};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::diff::run_git;
+
+/// One line's commit from `git blame --line-porcelain`
+#[derive(Debug, Clone, PartialEq)]
+struct BlameLine {
+    commit: String,
+    author: String,
+    author_time: i64,
+    author_tz: String,
+}
+
+/// Set each result's `blame` to the most recent commit among its lines when
+/// `options.blame` asks for it. Every file is blamed once, however many results
+/// it has; files git can't blame (outside a repo, untracked, or without git
+/// installed) keep `blame` empty.
+pub(crate) fn annotate_blame(search_results: &mut ck_core::SearchResults, options: &SearchOptions) {
+    if !options.blame {
+        return;
+    }
+    let mut cache: HashMap<PathBuf, Option<Vec<BlameLine>>> = HashMap::new();
+    let results = search_results
+        .matches
+        .iter_mut()
+        .chain(search_results.closest_below_threshold.as_mut());
+    for result in results {
+        let lines = cache
+            .entry(result.file.clone())
+            .or_insert_with(|| blame_file(&result.file));
+        result.blame = lines
+            .as_deref()
+            .and_then(|lines| most_recent(lines, result));
+    }
+}
+
+fn blame_file(file: &Path) -> Option<Vec<BlameLine>> {
+    let dir = match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = file.file_name()?.to_str()?;
+    match run_git(dir, &["blame", "--line-porcelain", "--", name]) {
+        Ok(output) => Some(parse_line_porcelain(&output)),
+        Err(e) => {
+            tracing::debug!("No blame for {:?}: {}", file, e);
+            None
+        }
+    }
+}
+
+/// The newest of the commits behind `result`'s lines
+fn most_recent(lines: &[BlameLine], result: &SearchResult) -> Option<Blame> {
+    let first = result.span.line_start.max(1) - 1;
+    let last = result.span.line_end.min(lines.len());
+    let newest = lines
+        .get(first..last)?
+        .iter()
+        .max_by_key(|line| line.author_time)?;
+    Some(Blame {
+        author: newest.author.clone(),
+        date: author_date(newest.author_time, &newest.author_tz),
+        commit: newest.commit.chars().take(8).collect(),
+    })
+}
+
+/// `YYYY-MM-DD` of a commit in its author's `+hhmm` time zone
+fn author_date(time: i64, tz: &str) -> String {
+    let offset = tz
+        .split_at_checked(3)
+        .and_then(|(hours, minutes)| {
+            Some((hours.parse::<i32>().ok()?, minutes.parse::<i32>().ok()?))
+        })
+        .and_then(|(hours, minutes)| {
+            let minutes = if hours < 0 || tz.starts_with('-') {
+                -minutes
+            } else {
+                minutes
+            };
+            FixedOffset::east_opt(hours * 3600 + minutes * 60)
+        })
+        .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+    DateTime::from_timestamp(time, 0)
+        .map(|date| date.with_timezone(&offset).format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// The commit of every line, in line order. Each line's entry opens with the
+/// commit hash, repeats its headers and ends with the tab-prefixed source line.
+fn parse_line_porcelain(output: &str) -> Vec<BlameLine> {
+    let mut lines = Vec::new();
+    let mut current: Option<BlameLine> = None;
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            lines.extend(current.take());
+        } else if let Some(entry) = current.as_mut() {
+            if let Some(author) = line.strip_prefix("author ") {
+                entry.author = author.to_string();
+            } else if let Some(time) = line.strip_prefix("author-time ") {
+                entry.author_time = time.parse().unwrap_or_default();
+            } else if let Some(tz) = line.strip_prefix("author-tz ") {
+                entry.author_tz = tz.to_string();
+            }
+        } else if let Some(commit) = line.split_whitespace().next() {
+            current = Some(BlameLine {
+                commit: commit.to_string(),
+                author: String::new(),
+                author_time: 0,
+                author_tz: String::new(),
+            });
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ck_core::Span;
+    use std::process::Command;
+
+    fn result(file: &Path, lines: (usize, usize)) -> SearchResult {
+        SearchResult {
+            file: file.to_path_buf(),
+            span: Span::new_unchecked(0, 0, lines.0, lines.1),
+            score: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_line_porcelain() {
+        let output = "\
+1f2e3d4c5b6a79881f2e3d4c5b6a79881f2e3d4c 1 1 2
+author Alice
+author-mail <alice@example.com>
+author-time 1700000000
+author-tz -0500
+summary first
+filename lib.rs
+\tfn a() {}
+1f2e3d4c5b6a79881f2e3d4c5b6a79881f2e3d4c 2 2
+author Alice
+author-time 1700000000
+author-tz -0500
+\t}
+";
+        let lines = parse_line_porcelain(output);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].author, "Alice");
+        assert_eq!(lines[1].author_time, 1_700_000_000);
+        // 2023-11-14 22:13 UTC is still the 14th five hours west
+        assert_eq!(author_date(1_700_000_000, "-0500"), "2023-11-14");
+        assert_eq!(author_date(1_700_000_000, "+0230"), "2023-11-15");
+    }
+
+    #[test]
+    fn annotates_results_with_their_newest_commit() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let git = |author: &str, date: &str, args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(root)
+                .args(["-c", &format!("user.name={}", author)])
+                .args(["-c", "user.email=ck@example.com"])
+                .args(args)
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+
+        let file = root.join("lib.rs");
+        std::fs::write(&file, "fn a() {}\nfn b() {}\nfn c() {}\n").unwrap();
+        git("Alice", "2024-01-02T10:00:00+00:00", &["init", "-q"]);
+        git("Alice", "2024-01-02T10:00:00+00:00", &["add", "."]);
+        git(
+            "Alice",
+            "2024-01-02T10:00:00+00:00",
+            &["commit", "-q", "-m", "a"],
+        );
+        std::fs::write(&file, "fn a() {}\nfn b() { 2 }\nfn c() {}\n").unwrap();
+        git(
+            "Bob",
+            "2024-03-04T10:00:00+00:00",
+            &["commit", "-q", "-am", "b"],
+        );
+
+        let untracked = root.join("new.rs");
+        std::fs::write(&untracked, "fn d() {}\n").unwrap();
+
+        let mut results = ck_core::SearchResults {
+            matches: vec![
+                result(&file, (1, 1)),
+                result(&file, (1, 3)),
+                result(&untracked, (1, 1)),
+            ],
+            closest_below_threshold: None,
+        };
+        let options = SearchOptions {
+            blame: true,
+            ..Default::default()
+        };
+        annotate_blame(&mut results, &options);
+
+        let blame: Vec<Option<(String, String)>> = results
+            .matches
+            .iter()
+            .map(|r| r.blame.as_ref().map(|b| (b.author.clone(), b.date.clone())))
+            .collect();
+        assert_eq!(
+            blame,
+            vec![
+                Some(("Alice".to_string(), "2024-01-02".to_string())),
+                Some(("Bob".to_string(), "2024-03-04".to_string())),
+                None,
+            ]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use ck_core::Span;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use super::semantic_v3::load_indexed_chunks;
+
+/// An indexed structural chunk named after a looked-up symbol
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolDefinition {
+    pub file: PathBuf,
+    pub span: Span,
+    /// The definition's full path: its enclosing chunks, then its own name
+    pub breadcrumb: String,
+    pub chunk_type: Option<String>,
+    pub chunk_hash: Option<String>,
+    /// The name matched case-sensitively rather than only ignoring case
+    pub exact: bool,
+}
+
+/// Structural chunks under `index_root` that define `symbol`, i.e. whose own
+/// name is `symbol`. Exact matches come first, then matches ignoring case;
+/// within each, functions, methods and classes come before modules. Reads the
+/// existing index without updating it; sidecars written before chunk names
+/// were kept find nothing until reindexed.
+pub fn find_definitions(index_root: &Path, symbol: &str) -> Result<Vec<SymbolDefinition>> {
+    if symbol.is_empty() {
+        return Ok(Vec::new());
+    }
+    let chunks = load_indexed_chunks(&index_root.join(".ck"), index_root, &[], |chunk| {
+        chunk
+            .chunk_type
+            .as_deref()
+            .is_some_and(|kind| kind != "text")
+    })?;
+    Ok(match_definitions(chunks, symbol))
+}
+
+/// The chunks among `chunks` that define `symbol`, ranked as in [`find_definitions`]
+fn match_definitions(
+    chunks: Vec<(PathBuf, ck_index::ChunkEntry)>,
+    symbol: &str,
+) -> Vec<SymbolDefinition> {
+    let mut definitions: Vec<SymbolDefinition> = chunks
+        .into_iter()
+        .filter_map(|(file, chunk)| {
+            let name = chunk.name.filter(|name| !name.is_empty())?;
+            let exact = name == symbol;
+            if !exact && !name.eq_ignore_ascii_case(symbol) {
+                return None;
+            }
+            let breadcrumb = match chunk.breadcrumb.filter(|crumb| !crumb.is_empty()) {
+                Some(enclosing) => format!("{}::{}", enclosing, name),
+                None => name,
+            };
+            Some(SymbolDefinition {
+                file,
+                span: chunk.span,
+                breadcrumb,
+                chunk_type: chunk.chunk_type,
+                chunk_hash: chunk.chunk_hash,
+                exact,
+            })
+        })
+        .collect();
+
+    definitions.sort_by(|a, b| {
+        (!a.exact, a.chunk_type.as_deref() == Some("module"))
+            .cmp(&(!b.exact, b.chunk_type.as_deref() == Some("module")))
+            .then_with(|| a.file.cmp(&b.file))
+            .then_with(|| a.span.line_start.cmp(&b.span.line_start))
+    });
+    definitions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chunk defining the last segment of `path`, inside the rest of it
+    fn chunk(file: &str, line: usize, kind: &str, path: &str) -> (PathBuf, ck_index::ChunkEntry) {
+        let (enclosing, name) = match path.rsplit_once("::") {
+            Some((enclosing, name)) => (Some(enclosing.to_string()), name),
+            None => (None, path),
+        };
+        let entry = ck_index::ChunkEntry {
+            span: Span::new_unchecked(0, 0, line, line + 2),
+            embedding: None,
+            chunk_type: Some(kind.to_string()),
+            breadcrumb: enclosing,
+            ancestry: None,
+            breadcrumb_segments: None,
+            name: Some(name.to_string()),
+            byte_length: None,
+            estimated_tokens: None,
+            leading_trivia: None,
+            trailing_trivia: None,
+            chunk_hash: None,
+        };
+        (PathBuf::from(file), entry)
+    }
+
+    #[test]
+    fn test_match_definitions_ranks_exact_names_first() {
+        let chunks = vec![
+            chunk("b.rs", 1, "module", "parse"),
+            chunk("a.rs", 5, "function", "Parser::Parse"),
+            chunk("b.rs", 9, "method", "Parser::parse"),
+            chunk("a.rs", 30, "function", "parse_all"),
+            chunk("a.rs", 40, "function", "parse"),
+            // Lives inside `parse` but is not called that
+            chunk("a.rs", 42, "function", "parse::helper"),
+        ];
+
+        let found = match_definitions(chunks, "parse");
+        let ranked: Vec<(&str, usize, bool)> = found
+            .iter()
+            .map(|def| (def.breadcrumb.as_str(), def.span.line_start, def.exact))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                ("parse", 40, true),
+                ("Parser::parse", 9, true),
+                ("parse", 1, true),
+                ("Parser::Parse", 5, false),
+            ]
+        );
+        assert!(match_definitions(Vec::new(), "parse").is_empty());
+    }
+}
@@ -0,0 +1,390 @@
+use anyhow::Result;
+use ck_core::{CkError, SearchOptions, SearchResult};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::semantic_v3::{best_similarity, example_query_chunks, retain_outside_example_file};
+use super::{
+    SearchProgressCallback, filter_files_by_include, find_nearest_index_root, resolve_metric,
+    resolve_model_from_root,
+};
+
+/// A chunk covering at least one line changed by a diff
+#[derive(Debug, Clone)]
+pub struct DiffChunk {
+    pub file: PathBuf,
+    pub chunk: ck_chunk::Chunk,
+}
+
+/// The chunks a git ref range touches within the search path
+#[derive(Debug, Clone, Default)]
+pub struct DiffChunks {
+    pub files_changed: usize,
+    pub chunks: Vec<DiffChunk>,
+}
+
+/// Chunk the files changed by `options.diff_range` and keep the innermost chunk
+/// around every changed line. Files are chunked as they are on disk, so the new
+/// side of the range should be the checked-out tree.
+pub fn diff_chunks(options: &SearchOptions) -> Result<DiffChunks> {
+    let Some(range) = options.diff_range.as_deref() else {
+        return Ok(DiffChunks::default());
+    };
+
+    let search_dir = if options.path.is_file() {
+        options.path.parent().unwrap_or(&options.path).to_path_buf()
+    } else {
+        options.path.clone()
+    };
+    let toplevel = git_toplevel(&search_dir)?;
+    let changed = parse_unified_diff(&git_diff(&toplevel, range)?);
+
+    let target = options
+        .path
+        .canonicalize()
+        .unwrap_or_else(|_| options.path.clone());
+    let files: Vec<PathBuf> = changed
+        .keys()
+        .map(|relative| toplevel.join(relative))
+        .filter(|file| {
+            file.canonicalize()
+                .is_ok_and(|real| real.starts_with(&target))
+        })
+        .collect();
+    let globs = super::PathGlobs::new(options)?;
+    let files: Vec<PathBuf> = filter_files_by_include(files, &options.include_patterns)
+        .into_iter()
+        .filter(|file| globs.matches(file))
+        .collect();
+
+    let index_root = find_nearest_index_root(&options.path).unwrap_or_else(|| search_dir.clone());
+    let model = resolve_model_from_root(&index_root, options.embedding_model.as_deref())?;
+
+    let mut touched = DiffChunks::default();
+    for file in files {
+        let Ok(content) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let relative = file.strip_prefix(&toplevel).unwrap_or(&file);
+        let Some(ranges) = changed.get(relative) else {
+            continue;
+        };
+        touched.files_changed += 1;
+
+        // Chunks kept out of the index by `ck:ignore` stay out of diff searches too
+        if ck_chunk::has_ignore_file_marker(&content) {
+            continue;
+        }
+        let chunks = ck_chunk::without_ignored(ck_chunk::chunk_text_with_model(
+            &content,
+            ck_core::Language::from_path(&file),
+            Some(&model.canonical_name),
+        )?);
+
+        let mut seen = HashSet::new();
+        for &(start, end) in ranges {
+            for line in start..=end {
+                if let Some(chunk) = ck_chunk::innermost_chunk_at(&chunks, line)
+                    && seen.insert((chunk.span.byte_start, chunk.span.byte_end))
+                    && chunk_passes_filters(chunk, options)
+                    && globs.keeps_chunk(&file, is_test_chunk(chunk))
+                {
+                    touched.chunks.push(DiffChunk {
+                        file: file.clone(),
+                        chunk: chunk.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(touched)
+}
+
+/// Semantic search over only the chunks touched by `options.diff_range`,
+/// embedding them on the fly instead of reading (or building) the index
+pub fn diff_search(
+    options: &SearchOptions,
+    progress_callback: Option<SearchProgressCallback>,
+) -> Result<ck_core::SearchResults> {
+    if let Some(ref callback) = progress_callback {
+        callback("Collecting chunks changed by the diff...");
+    }
+    let mut touched = diff_chunks(options)?;
+    retain_outside_example_file(&mut touched.chunks, options, |diff_chunk| &diff_chunk.file);
+    if touched.chunks.is_empty() {
+        return Ok(ck_core::SearchResults {
+            matches: Vec::new(),
+            closest_below_threshold: None,
+        });
+    }
+
+    let index_root = find_nearest_index_root(&options.path).unwrap_or_else(|| options.path.clone());
+    let model = resolve_model_from_root(&index_root, options.embedding_model.as_deref())?;
+    let metric = resolve_metric(&index_root, options.metric);
+    let mut embedder = model.embedder()?;
+
+    if let Some(ref callback) = progress_callback {
+        callback(&format!(
+            "Embedding {} changed chunks...",
+            touched.chunks.len()
+        ));
+    }
+    let texts: Vec<String> = touched
+        .chunks
+        .iter()
+        .map(|diff_chunk| diff_chunk.chunk.text.clone())
+        .collect();
+    let embeddings = embedder.embed(&texts)?;
+
+    let query_texts = if options.search_by_example {
+        example_query_chunks(
+            &options.query,
+            options.example_language,
+            &model.canonical_name,
+        )?
+    } else {
+        vec![options.query.clone()]
+    };
+    let query_embeddings = embedder.embed(&query_texts)?;
+
+    let mut scored: Vec<(f32, &DiffChunk)> = touched
+        .chunks
+        .iter()
+        .zip(&embeddings)
+        .map(|(diff_chunk, embedding)| {
+            (
+                best_similarity(metric, &query_embeddings, embedding),
+                diff_chunk,
+            )
+        })
+        .collect();
+    scored.sort_by(|(score_a, a), (score_b, b)| {
+        score_b
+            .total_cmp(score_a)
+            .then_with(|| a.file.cmp(&b.file))
+            .then_with(|| a.chunk.span.line_start.cmp(&b.chunk.span.line_start))
+            .then_with(|| a.chunk.span.line_end.cmp(&b.chunk.span.line_end))
+    });
+
+    let mut matches = Vec::new();
+    let mut closest_below_threshold = None;
+    for (score, diff_chunk) in scored.into_iter().take(options.top_k.unwrap_or(usize::MAX)) {
+        let chunk = &diff_chunk.chunk;
+        let preview = if options.full_section {
+            chunk.text.clone()
+        } else {
+            chunk.text.lines().take(3).collect::<Vec<_>>().join("\n")
+        };
+        let result = SearchResult {
+            file: diff_chunk.file.clone(),
+            span: chunk.span.clone(),
+            score,
+            preview,
+            lang: ck_core::Language::from_path(&diff_chunk.file),
+            symbol: None,
+            breadcrumb: chunk_breadcrumb(chunk),
+            chunk_hash: None,
+            index_epoch: None,
+            blame: None,
+            match_ranges: Vec::new(),
+        };
+
+        if options.threshold.is_some_and(|threshold| score < threshold) {
+            if closest_below_threshold.is_none() {
+                closest_below_threshold = Some(result);
+            }
+        } else {
+            matches.push(result);
+        }
+    }
+
+    Ok(ck_core::SearchResults {
+        matches,
+        closest_below_threshold,
+    })
+}
+
+pub(crate) fn chunk_breadcrumb(chunk: &ck_chunk::Chunk) -> Option<String> {
+    chunk
+        .metadata
+        .breadcrumb
+        .clone()
+        .filter(|crumb| !crumb.is_empty())
+        .or_else(|| {
+            (!chunk.metadata.ancestry.is_empty()).then(|| chunk.metadata.ancestry.join("::"))
+        })
+}
+
+/// The same `--scope` / `--type` / `--docs-only` / `--code-only` filtering
+/// indexed chunks get
+pub(crate) fn chunk_passes_filters(chunk: &ck_chunk::Chunk, options: &SearchOptions) -> bool {
+    let chunk_type = chunk.chunk_type.index_name();
+    options.chunk_filter.accepts(chunk_type)
+        && ck_core::chunk_type_selected(&options.chunk_types, chunk_type)
+        && ck_core::breadcrumb_in_any_scope(chunk_breadcrumb(chunk).as_deref(), &options.scopes)
+}
+
+/// Whether a live chunk's metadata marks it as test code
+pub(crate) fn is_test_chunk(chunk: &ck_chunk::Chunk) -> bool {
+    ck_core::is_test_chunk(
+        chunk.metadata.name.as_deref(),
+        &chunk.metadata.ancestry,
+        &chunk.metadata.leading_trivia,
+    )
+}
+
+pub(crate) fn git_toplevel(dir: &Path) -> Result<PathBuf> {
+    let output = run_git(dir, &["rev-parse", "--show-toplevel"])?;
+    Ok(PathBuf::from(output.trim_end()))
+}
+
+fn git_diff(toplevel: &Path, range: &str) -> Result<String> {
+    // A range is a revision expression, never an option
+    if range.is_empty() || range.starts_with('-') {
+        return Err(CkError::Search(format!("Invalid git ref range: '{}'", range)).into());
+    }
+    run_git(
+        toplevel,
+        &[
+            "diff",
+            "--unified=0",
+            "--no-color",
+            "--no-ext-diff",
+            range,
+            "--",
+        ],
+    )
+}
+
+pub(crate) fn run_git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| CkError::Search(format!("Failed to run git: {}", e)))?;
+    if !output.status.success() {
+        return Err(CkError::Search(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Changed new-side line ranges (1-based, inclusive) per file from `git diff
+/// --unified=0` output. A pure deletion counts as touching the line just before it.
+fn parse_unified_diff(diff: &str) -> BTreeMap<PathBuf, Vec<(usize, usize)>> {
+    let mut changed: BTreeMap<PathBuf, Vec<(usize, usize)>> = BTreeMap::new();
+    let mut current: Option<PathBuf> = None;
+
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            current = path.strip_prefix("b/").map(PathBuf::from);
+        } else if let Some(hunk) = line.strip_prefix("@@ ")
+            && let Some(file) = current.as_ref()
+            && let Some(new_side) = hunk
+                .split_whitespace()
+                .find_map(|part| part.strip_prefix('+'))
+        {
+            let (start, count) = match new_side.split_once(',') {
+                Some((start, count)) => (start.parse::<usize>().ok(), count.parse::<usize>().ok()),
+                None => (new_side.parse::<usize>().ok(), Some(1)),
+            };
+            if let (Some(start), Some(count)) = (start, count) {
+                let range = if count == 0 {
+                    (start.max(1), start.max(1))
+                } else {
+                    (start, start + count - 1)
+                };
+                changed.entry(file.clone()).or_default().push(range);
+            }
+        }
+    }
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_unified_diff_collects_new_side_ranges() {
+        let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -3 +3 @@ fn alpha() {
+-    1
++    2
+@@ -10,0 +11,3 @@ fn beta() {
++    let a = 1;
++    let b = 2;
++    a + b
+@@ -20,2 +22,0 @@ fn gamma() {
+-    old();
+-    older();
+diff --git a/gone.rs b/gone.rs
+deleted file mode 100644
+--- a/gone.rs
++++ /dev/null
+@@ -1,2 +0,0 @@
+-fn gone() {}
+-
+";
+        let changed = parse_unified_diff(diff);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(
+            changed[Path::new("src/lib.rs")],
+            vec![(3, 3), (11, 13), (22, 22)]
+        );
+    }
+
+    #[test]
+    fn test_diff_chunks_maps_changes_to_innermost_chunks() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(root)
+                .args(["-c", "user.name=ck", "-c", "user.email=ck@example.com"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+
+        let original = "fn alpha() -> u32 {\n    1\n}\n\nfn beta() -> u32 {\n    2\n}\n\nfn gamma() -> u32 {\n    3\n}\n";
+        std::fs::write(root.join("lib.rs"), original).unwrap();
+        std::fs::write(root.join("notes.txt"), "unchanged\n").unwrap();
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "initial"]);
+
+        std::fs::write(root.join("lib.rs"), original.replace("    2", "    20")).unwrap();
+
+        let options = SearchOptions {
+            path: root.to_path_buf(),
+            diff_range: Some("HEAD".to_string()),
+            ..Default::default()
+        };
+        let touched = diff_chunks(&options).unwrap();
+        assert_eq!(touched.files_changed, 1);
+        assert_eq!(touched.chunks.len(), 1);
+        assert!(touched.chunks[0].chunk.text.contains("fn beta"));
+
+        let invalid = SearchOptions {
+            diff_range: Some("--output=/tmp/x".to_string()),
+            ..options
+        };
+        assert!(diff_chunks(&invalid).is_err());
+    }
+}
@@ -0,0 +1,296 @@
+use anyhow::Result;
+use ck_core::{CkError, SearchOptions, Span};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::semantic_v3::{cosine_similarity, load_embedded_chunks};
+use super::{ensure_index_updated_with_progress, find_nearest_index_root};
+
+/// Similarity a pair needs to be reported when no threshold is given
+pub const DEFAULT_DUPLICATE_THRESHOLD: f32 = 0.95;
+/// Number of pairs reported when no limit is given
+pub const DEFAULT_DUPLICATE_LIMIT: usize = 20;
+/// Chunks shorter than this (imports, closing braces, one-liners) are too
+/// generic to be interesting duplicates
+const MIN_DUPLICATE_LINES: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateChunk {
+    pub file: PathBuf,
+    pub span: Span,
+    pub breadcrumb: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicatePair {
+    pub score: f32,
+    pub first: DuplicateChunk,
+    pub second: DuplicateChunk,
+}
+
+/// Chunks connected (transitively) through reported pairs
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateCluster {
+    /// Highest pair score inside the cluster
+    pub score: f32,
+    pub chunks: Vec<DuplicateChunk>,
+}
+
+/// Find pairs of indexed chunks whose embeddings are at least `options.threshold`
+/// similar, best first and capped at `options.top_k` pairs. Chunks are never paired
+/// with themselves or with chunks overlapping them in the same file.
+pub async fn find_duplicates(options: &SearchOptions) -> Result<Vec<DuplicatePair>> {
+    if !options.path.exists() {
+        return Err(
+            CkError::Search(format!("Path does not exist: {}", options.path.display())).into(),
+        );
+    }
+
+    let file_options = ck_core::FileCollectionOptions::from(options);
+    ensure_index_updated_with_progress(
+        &options.path,
+        options.reindex,
+        true,
+        None,
+        None,
+        &file_options,
+        options.embedding_model.as_deref(),
+    )
+    .await?;
+
+    let index_root = find_nearest_index_root(&options.path).unwrap_or_else(|| {
+        if options.path.is_file() {
+            options.path.parent().unwrap_or(&options.path).to_path_buf()
+        } else {
+            options.path.clone()
+        }
+    });
+    let index_dir = index_root.join(".ck");
+
+    let target = options
+        .path
+        .canonicalize()
+        .unwrap_or_else(|_| options.path.clone());
+    let globs = super::PathGlobs::new(options)?;
+    let chunks: Vec<(PathBuf, ck_index::ChunkEntry)> =
+        load_embedded_chunks(&index_dir, &index_root, &options.include_patterns)?
+            .into_iter()
+            .filter(|(file, chunk)| {
+                (chunk.span.line_end + 1).saturating_sub(chunk.span.line_start)
+                    >= MIN_DUPLICATE_LINES
+                    && globs.matches(file)
+                    && globs.keeps_chunk(file, super::semantic_v3::is_test_chunk(chunk))
+                    && file
+                        .canonicalize()
+                        .unwrap_or_else(|_| file.clone())
+                        .starts_with(&target)
+            })
+            .collect();
+
+    let entries: Vec<(&Path, &Span, &[f32])> = chunks
+        .iter()
+        .filter_map(|(file, chunk)| {
+            chunk
+                .embedding
+                .as_deref()
+                .map(|embedding| (file.as_path(), &chunk.span, embedding))
+        })
+        .collect();
+
+    let threshold = options.threshold.unwrap_or(DEFAULT_DUPLICATE_THRESHOLD);
+    let limit = options.top_k.unwrap_or(DEFAULT_DUPLICATE_LIMIT);
+
+    let to_chunk = |index: usize| {
+        let (file, chunk) = &chunks[index];
+        DuplicateChunk {
+            file: file.clone(),
+            span: chunk.span.clone(),
+            breadcrumb: chunk.breadcrumb.clone(),
+        }
+    };
+
+    Ok(rank_duplicate_pairs(&entries, threshold, limit)
+        .into_iter()
+        .map(|(score, first, second)| DuplicatePair {
+            score,
+            first: to_chunk(first),
+            second: to_chunk(second),
+        })
+        .collect())
+}
+
+/// Score every unordered pair of entries and keep the `limit` best at or above `threshold`
+fn rank_duplicate_pairs(
+    entries: &[(&Path, &Span, &[f32])],
+    threshold: f32,
+    limit: usize,
+) -> Vec<(f32, usize, usize)> {
+    let mut pairs: Vec<(f32, usize, usize)> = (0..entries.len())
+        .into_par_iter()
+        .flat_map_iter(|i| {
+            let (file_a, span_a, embedding_a) = entries[i];
+            (i + 1..entries.len()).filter_map(move |j| {
+                let (file_b, span_b, embedding_b) = entries[j];
+                if file_a == file_b && spans_overlap(span_a, span_b) {
+                    return None;
+                }
+                let score = cosine_similarity(embedding_a, embedding_b);
+                (score >= threshold).then_some((score, i, j))
+            })
+        })
+        .collect();
+
+    pairs.sort_by(|a, b| {
+        b.0.partial_cmp(&a.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| (a.1, a.2).cmp(&(b.1, b.2)))
+    });
+    pairs.truncate(limit);
+    pairs
+}
+
+pub(crate) fn spans_overlap(a: &Span, b: &Span) -> bool {
+    a.line_start <= b.line_end && b.line_start <= a.line_end
+}
+
+/// Group pairs that share a chunk into clusters, largest first
+pub fn cluster_duplicates(pairs: &[DuplicatePair]) -> Vec<DuplicateCluster> {
+    fn find(parent: &mut [usize], mut node: usize) -> usize {
+        while parent[node] != node {
+            parent[node] = parent[parent[node]];
+            node = parent[node];
+        }
+        node
+    }
+
+    let mut members: Vec<&DuplicateChunk> = Vec::new();
+    let mut index_of: HashMap<(&Path, usize, usize), usize> = HashMap::new();
+    let mut parent: Vec<usize> = Vec::new();
+    let mut edges = Vec::with_capacity(pairs.len());
+
+    for pair in pairs {
+        let mut ids = [0; 2];
+        for (slot, chunk) in [&pair.first, &pair.second].into_iter().enumerate() {
+            let key = (
+                chunk.file.as_path(),
+                chunk.span.line_start,
+                chunk.span.line_end,
+            );
+            ids[slot] = *index_of.entry(key).or_insert_with(|| {
+                members.push(chunk);
+                parent.push(parent.len());
+                parent.len() - 1
+            });
+        }
+        let (a, b) = (find(&mut parent, ids[0]), find(&mut parent, ids[1]));
+        if a != b {
+            parent[b] = a;
+        }
+        edges.push((ids[0], pair.score));
+    }
+
+    let mut clusters: HashMap<usize, DuplicateCluster> = HashMap::new();
+    let mut order = Vec::new();
+    for (id, chunk) in members.iter().enumerate() {
+        let root = find(&mut parent, id);
+        clusters
+            .entry(root)
+            .or_insert_with(|| {
+                order.push(root);
+                DuplicateCluster {
+                    score: f32::MIN,
+                    chunks: Vec::new(),
+                }
+            })
+            .chunks
+            .push((*chunk).clone());
+    }
+    for (id, score) in edges {
+        let root = find(&mut parent, id);
+        if let Some(cluster) = clusters.get_mut(&root) {
+            cluster.score = cluster.score.max(score);
+        }
+    }
+
+    let mut result: Vec<DuplicateCluster> = order
+        .into_iter()
+        .filter_map(|root| clusters.remove(&root))
+        .collect();
+    result.sort_by(|a, b| {
+        b.chunks.len().cmp(&a.chunks.len()).then_with(|| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(line_start: usize, line_end: usize) -> Span {
+        Span {
+            byte_start: 0,
+            byte_end: 0,
+            line_start,
+            line_end,
+        }
+    }
+
+    fn chunk(file: &str, line_start: usize, line_end: usize) -> DuplicateChunk {
+        DuplicateChunk {
+            file: PathBuf::from(file),
+            span: span(line_start, line_end),
+            breadcrumb: None,
+        }
+    }
+
+    #[test]
+    fn test_rank_duplicate_pairs_skips_self_and_overlaps() {
+        let (a, b) = (Path::new("a.rs"), Path::new("b.rs"));
+        let (outer, inner, other, far) = (span(1, 20), span(5, 10), span(1, 20), span(30, 40));
+        let same = [1.0, 0.0];
+        let close = [0.9, 0.1];
+        let entries: Vec<(&Path, &Span, &[f32])> = vec![
+            (a, &outer, &same),
+            (a, &inner, &same),
+            (b, &other, &same),
+            (a, &far, &close),
+        ];
+
+        let pairs = rank_duplicate_pairs(&entries, 0.9, 10);
+        assert!(pairs.iter().all(|&(_, i, j)| i != j));
+        assert!(!pairs.iter().any(|&(_, i, j)| (i, j) == (0, 1)));
+        assert_eq!((pairs[0].1, pairs[0].2), (0, 2));
+        assert!(pairs.windows(2).all(|w| w[0].0 >= w[1].0));
+
+        let capped = rank_duplicate_pairs(&entries, 0.9, 2);
+        assert_eq!(capped.len(), 2);
+        assert!(rank_duplicate_pairs(&entries, 1.1, 10).is_empty());
+    }
+
+    #[test]
+    fn test_cluster_duplicates_merges_transitive_pairs() {
+        let pair = |score, first, second| DuplicatePair {
+            score,
+            first,
+            second,
+        };
+        let pairs = vec![
+            pair(0.99, chunk("a.rs", 1, 9), chunk("b.rs", 1, 9)),
+            pair(0.97, chunk("x.rs", 1, 5), chunk("y.rs", 1, 5)),
+            pair(0.96, chunk("b.rs", 1, 9), chunk("c.rs", 3, 11)),
+        ];
+
+        let clusters = cluster_duplicates(&pairs);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].chunks.len(), 3);
+        assert!((clusters[0].score - 0.99).abs() < f32::EPSILON);
+        let files: Vec<_> = clusters[1].chunks.iter().map(|c| c.file.clone()).collect();
+        assert_eq!(files, vec![PathBuf::from("x.rs"), PathBuf::from("y.rs")]);
+    }
+}
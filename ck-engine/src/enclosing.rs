@@ -0,0 +1,200 @@
+use ck_core::{SearchMode, SearchOptions, SearchResult};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Marks a collapsed signature line, as the TUI's sibling outline does
+const ENCLOSING_MARKER: &str = "▸";
+
+/// The signature line of a chunk enclosing a result: 1-based line and text
+type Signature = (usize, String);
+
+/// A file's structural chunks as line spans with their signature lines
+struct FileOutline {
+    chunks: Vec<((usize, usize), Signature)>,
+}
+
+/// When `options.show_enclosing` is set, put the first line of every chunk
+/// enclosing each result at the top of its preview, outermost first, as
+/// `▸ impl Client { · L12`. Signatures the preview already shows are left out.
+/// Files are chunked as they are now, once however many results they have.
+pub(crate) fn prepend_enclosing_signatures(
+    search_results: &mut ck_core::SearchResults,
+    options: &SearchOptions,
+) {
+    if !options.show_enclosing {
+        return;
+    }
+    // Only regex previews start above the match, by the context lines
+    let before = if options.mode == SearchMode::Regex {
+        options.before_context_lines.max(options.context_lines)
+    } else {
+        0
+    };
+    let mut outlines: HashMap<PathBuf, Option<FileOutline>> = HashMap::new();
+    let results = search_results
+        .matches
+        .iter_mut()
+        .chain(search_results.closest_below_threshold.as_mut());
+    for result in results {
+        let Some(outline) = outlines
+            .entry(result.file.clone())
+            .or_insert_with(|| FileOutline::load(&result.file))
+        else {
+            continue;
+        };
+        let header: Vec<String> = outline
+            .enclosing(result)
+            .into_iter()
+            .filter(|(line, text)| {
+                *line < result.span.line_start.saturating_sub(before)
+                    && !result.preview.lines().any(|shown| shown == text)
+            })
+            .map(|(line, text)| format!("{} {} · L{}", ENCLOSING_MARKER, text.trim_end(), line))
+            .collect();
+        if !header.is_empty() {
+            result.preview = format!("{}\n{}", header.join("\n"), result.preview);
+        }
+    }
+}
+
+impl FileOutline {
+    fn load(file: &Path) -> Option<Self> {
+        let lang = ck_core::Language::from_path(file)
+            .filter(|lang| ck_chunk::has_structural_support(*lang))?;
+        let content = std::fs::read_to_string(file).ok()?;
+        let chunks = ck_chunk::chunk_text(&content, Some(lang)).ok()?;
+        let lines: Vec<&str> = content.lines().collect();
+        let chunks = chunks
+            .into_iter()
+            .filter(|chunk| chunk.chunk_type != ck_chunk::ChunkType::Text)
+            .filter_map(|chunk| {
+                let span = (chunk.span.line_start, chunk.span.line_end);
+                signature_line(&lines, &chunk).map(|signature| (span, signature))
+            })
+            .collect();
+        Some(Self { chunks })
+    }
+
+    /// Signatures of the chunks strictly containing `result`, outermost first
+    fn enclosing(&self, result: &SearchResult) -> Vec<Signature> {
+        let target = (result.span.line_start, result.span.line_end);
+        let mut enclosing: Vec<&((usize, usize), Signature)> = self
+            .chunks
+            .iter()
+            .filter(|((start, end), _)| {
+                *start <= target.0 && *end >= target.1 && (*start, *end) != target
+            })
+            .collect();
+        enclosing.sort_by_key(|((start, end), _)| (*start, std::cmp::Reverse(*end)));
+        enclosing.dedup_by_key(|(_, (line, _))| *line);
+        enclosing
+            .into_iter()
+            .map(|(_, signature)| signature.clone())
+            .collect()
+    }
+}
+
+/// First line of a chunk after its leading trivia (comments, attributes, decorators)
+fn signature_line(lines: &[&str], chunk: &ck_chunk::Chunk) -> Option<Signature> {
+    let trivia_lines: usize = chunk
+        .metadata
+        .leading_trivia
+        .iter()
+        .map(|trivia| trivia.lines().count())
+        .sum();
+    let start = chunk.span.line_start.saturating_sub(1) + trivia_lines;
+    let end = chunk.span.line_end.min(lines.len());
+    lines
+        .get(start..end)?
+        .iter()
+        .enumerate()
+        .find(|(_, line)| !line.trim().is_empty())
+        .map(|(offset, line)| (start + offset + 1, line.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ck_core::Span;
+
+    const SOURCE: &str = "\
+/// A client
+pub struct Client;
+
+impl Client {
+    /// Sends it
+    pub fn send(&self) -> u32 {
+        let a = 1;
+        let b = 2;
+        a + b
+    }
+}
+";
+
+    fn result(file: &Path, lines: (usize, usize), preview: &str) -> SearchResult {
+        SearchResult {
+            file: file.to_path_buf(),
+            span: Span::new_unchecked(0, 0, lines.0, lines.1),
+            score: 1.0,
+            preview: preview.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn previews_start_with_enclosing_signatures() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("client.rs");
+        std::fs::write(&file, SOURCE).unwrap();
+
+        let mut results = ck_core::SearchResults {
+            matches: vec![
+                result(&file, (9, 9), "        a + b"),
+                result(
+                    &file,
+                    (5, 10),
+                    "    /// Sends it\n    pub fn send(&self) -> u32 {",
+                ),
+                result(&file, (2, 2), "pub struct Client;"),
+            ],
+            closest_below_threshold: None,
+        };
+        let options = SearchOptions {
+            mode: SearchMode::Regex,
+            show_enclosing: true,
+            ..Default::default()
+        };
+        prepend_enclosing_signatures(&mut results, &options);
+
+        assert_eq!(
+            results.matches[0].preview,
+            "▸ impl Client { · L4\n▸     pub fn send(&self) -> u32 { · L6\n        a + b"
+        );
+        // A chunk result shows its own signature already
+        assert!(
+            results.matches[1]
+                .preview
+                .starts_with("▸ impl Client { · L4\n    /// Sends")
+        );
+        assert_eq!(results.matches[2].preview, "pub struct Client;");
+
+        // Signatures among the context lines above a regex match aren't repeated
+        let mut with_context = ck_core::SearchResults {
+            matches: vec![result(
+                &file,
+                (7, 7),
+                "    pub fn send(&self) -> u32 {\n        let a = 1;",
+            )],
+            closest_below_threshold: None,
+        };
+        let context = SearchOptions {
+            before_context_lines: 1,
+            ..options
+        };
+        prepend_enclosing_signatures(&mut with_context, &context);
+        assert_eq!(
+            with_context.matches[0].preview,
+            "▸ impl Client { · L4\n    pub fn send(&self) -> u32 {\n        let a = 1;"
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use anyhow::Result;
+use ck_core::SearchResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::find_nearest_index_root;
+use super::semantic_v3::cosine_similarity;
+use super::vector::{innermost_embedded_chunk, query_vector};
+
+/// A result marked as what the user was or wasn't looking for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feedback {
+    Relevant,
+    Irrelevant,
+}
+
+/// Rocchio weights: how much of the original query is kept, how far it moves
+/// toward the mean of the relevant chunks and how far away from the mean of
+/// the irrelevant ones
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeedbackWeights {
+    pub query: f32,
+    pub relevant: f32,
+    pub irrelevant: f32,
+}
+
+impl Default for FeedbackWeights {
+    fn default() -> Self {
+        Self {
+            query: 1.0,
+            relevant: 0.75,
+            irrelevant: 0.15,
+        }
+    }
+}
+
+/// The query embedding moved by feedback:
+/// `query·q + relevant·mean(relevant) − irrelevant·mean(irrelevant)`.
+/// An empty side contributes nothing.
+pub fn rocchio_query(
+    query: &[f32],
+    relevant: &[Vec<f32>],
+    irrelevant: &[Vec<f32>],
+    weights: FeedbackWeights,
+) -> Vec<f32> {
+    let mut moved: Vec<f32> = query.iter().map(|value| value * weights.query).collect();
+    for (vectors, weight) in [
+        (relevant, weights.relevant),
+        (irrelevant, -weights.irrelevant),
+    ] {
+        if vectors.is_empty() {
+            continue;
+        }
+        let scale = weight / vectors.len() as f32;
+        for vector in vectors {
+            for (value, component) in moved.iter_mut().zip(vector) {
+                *value += scale * component;
+            }
+        }
+    }
+    moved
+}
+
+/// Re-rank `results` against the query `query` moved toward the results
+/// `feedback` marks relevant and away from those it marks irrelevant. Each
+/// result is scored by cosine similarity between its chunk's stored embedding
+/// and the moved query, then sorted best first; results without a stored
+/// embedding keep their score and go last. The query is embedded with the
+/// index's model, the one loaded for the search. Returns how many results were
+/// re-scored.
+pub fn rerank_with_feedback(
+    path: &Path,
+    query: &str,
+    results: &mut [SearchResult],
+    feedback: impl Fn(&SearchResult) -> Option<Feedback>,
+    weights: FeedbackWeights,
+) -> Result<usize> {
+    let mut entries: HashMap<PathBuf, Option<ck_index::IndexEntry>> = HashMap::new();
+    let embeddings: Vec<Option<Vec<f32>>> = results
+        .iter()
+        .map(|result| stored_embedding(&mut entries, result))
+        .collect();
+
+    let mut relevant = Vec::new();
+    let mut irrelevant = Vec::new();
+    for (result, embedding) in results.iter().zip(&embeddings) {
+        match (feedback(result), embedding) {
+            (Some(Feedback::Relevant), Some(embedding)) => relevant.push(embedding.clone()),
+            (Some(Feedback::Irrelevant), Some(embedding)) => irrelevant.push(embedding.clone()),
+            _ => {}
+        }
+    }
+    let query = query_vector(path, None, query)?.embedding;
+    let moved = rocchio_query(&query, &relevant, &irrelevant, weights);
+
+    let mut rescored = 0;
+    let mut order: Vec<(Option<f32>, usize)> = Vec::with_capacity(results.len());
+    for (index, (result, embedding)) in results.iter_mut().zip(&embeddings).enumerate() {
+        let score = embedding
+            .as_ref()
+            .filter(|embedding| embedding.len() == moved.len())
+            .map(|embedding| cosine_similarity(&moved, embedding));
+        if let Some(score) = score {
+            result.score = score;
+            rescored += 1;
+        }
+        order.push((score, index));
+    }
+    // Scored results first, best first; the rest stay in their current order
+    order.sort_by(|a, b| match (a.0, b.0) {
+        (Some(a_score), Some(b_score)) => b_score.total_cmp(&a_score).then(a.1.cmp(&b.1)),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.1.cmp(&b.1),
+    });
+    let reordered: Vec<SearchResult> = order
+        .into_iter()
+        .map(|(_, index)| results[index].clone())
+        .collect();
+    results.clone_from_slice(&reordered);
+    Ok(rescored)
+}
+
+/// The embedding the index stores for a result's chunk: the chunk with its
+/// exact span, else the innermost embedded chunk around its first line
+fn stored_embedding(
+    entries: &mut HashMap<PathBuf, Option<ck_index::IndexEntry>>,
+    result: &SearchResult,
+) -> Option<Vec<f32>> {
+    let entry = entries
+        .entry(result.file.clone())
+        .or_insert_with(|| {
+            let file = result.file.canonicalize().ok()?;
+            let index_root = find_nearest_index_root(&file)?;
+            ck_index::load_index_entry(&ck_core::get_sidecar_path(&index_root, &file)).ok()
+        })
+        .as_ref()?;
+    let span = (result.span.line_start, result.span.line_end);
+    entry
+        .chunks
+        .iter()
+        .find(|chunk| {
+            (chunk.span.line_start, chunk.span.line_end) == span
+                && chunk.embedding.as_ref().is_some_and(|e| !e.is_empty())
+        })
+        .or_else(|| innermost_embedded_chunk(&entry.chunks, result.span.line_start))
+        .and_then(|chunk| chunk.embedding.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rocchio_moves_toward_relevant_and_away_from_irrelevant() {
+        let weights = FeedbackWeights::default();
+        let query = [1.0, 0.0, 0.0];
+        let moved = rocchio_query(
+            &query,
+            &[vec![0.0, 1.0, 0.0], vec![0.0, 0.0, 1.0]],
+            &[vec![1.0, 0.0, 0.0]],
+            weights,
+        );
+        assert_eq!(moved, vec![0.85, 0.375, 0.375]);
+
+        // Without feedback the query is only scaled, which cosine ignores
+        let unchanged = rocchio_query(
+            &query,
+            &[],
+            &[],
+            FeedbackWeights {
+                query: 2.0,
+                ..weights
+            },
+        );
+        assert_eq!(unchanged, vec![2.0, 0.0, 0.0]);
+    }
+}
@@ -0,0 +1,164 @@
+use tantivy::Index;
+use tantivy::schema::{Field, IndexRecordOption, STORED, Schema, TextFieldIndexing, TextOptions};
+use tantivy::tokenizer::{
+    LowerCaser, RemoveLongFilter, TextAnalyzer, Token, TokenStream, Tokenizer,
+};
+
+const EXACT_TOKENIZER: &str = "ck_exact";
+const SPLIT_TOKENIZER: &str = "ck_split";
+
+/// The fields of the lexical index. `content` holds whole identifiers and
+/// `content_split` adds the words inside them, so `getUserName` is found by a
+/// query for "user name" unless the search asks for exact tokens.
+pub(crate) struct LexicalFields {
+    pub content: Field,
+    pub content_split: Field,
+    pub path: Field,
+}
+
+impl LexicalFields {
+    /// The field a query should be parsed against
+    pub fn query_field(&self, exact_tokens: bool) -> Field {
+        if exact_tokens {
+            self.content
+        } else {
+            self.content_split
+        }
+    }
+}
+
+pub(crate) fn schema() -> Schema {
+    let text = |tokenizer: &str| {
+        TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(tokenizer)
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        )
+    };
+    let mut schema_builder = Schema::builder();
+    schema_builder.add_text_field("content", text(EXACT_TOKENIZER) | STORED);
+    schema_builder.add_text_field("content_split", text(SPLIT_TOKENIZER));
+    schema_builder.add_text_field("path", text("default") | STORED);
+    schema_builder.build()
+}
+
+/// The fields of an opened index, with its tokenizers registered; `None` for
+/// an index built before identifier splitting, which has to be rebuilt
+pub(crate) fn fields(index: &Index) -> Option<LexicalFields> {
+    let schema = index.schema();
+    let fields = LexicalFields {
+        content: schema.get_field("content").ok()?,
+        content_split: schema.get_field("content_split").ok()?,
+        path: schema.get_field("path").ok()?,
+    };
+    for (name, exact_tokens) in [(EXACT_TOKENIZER, true), (SPLIT_TOKENIZER, false)] {
+        index.tokenizers().register(
+            name,
+            TextAnalyzer::builder(CodeTokenizer { exact_tokens })
+                .filter(RemoveLongFilter::limit(40))
+                .filter(LowerCaser)
+                .build(),
+        );
+    }
+    Some(fields)
+}
+
+/// Splits text into identifiers, and those into their camelCase, snake_case
+/// and kebab-case words unless `exact_tokens` is set. A split identifier is
+/// also indexed whole, at the position of its first word.
+#[derive(Clone)]
+struct CodeTokenizer {
+    exact_tokens: bool,
+}
+
+impl Tokenizer for CodeTokenizer {
+    type TokenStream<'a> = CodeTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> CodeTokenStream {
+        let token = |(offset_from, offset_to): (usize, usize), position: usize| Token {
+            offset_from,
+            offset_to,
+            position,
+            text: text[offset_from..offset_to].to_string(),
+            position_length: 1,
+        };
+
+        let mut tokens = Vec::new();
+        let mut position = 0;
+        for (start, end) in ck_core::heatmap::token_ranges(text, true) {
+            let words = if self.exact_tokens {
+                Vec::new()
+            } else {
+                ck_core::heatmap::token_ranges(&text[start..end], false)
+            };
+            tokens.push(token((start, end), position));
+            if words.len() <= 1 {
+                position += 1;
+                continue;
+            }
+            for (word_start, word_end) in words {
+                tokens.push(token((start + word_start, start + word_end), position));
+                position += 1;
+            }
+        }
+        CodeTokenStream {
+            tokens,
+            current: None,
+        }
+    }
+}
+
+struct CodeTokenStream {
+    tokens: Vec<Token>,
+    current: Option<usize>,
+}
+
+impl TokenStream for CodeTokenStream {
+    fn advance(&mut self) -> bool {
+        let next = self.current.map_or(0, |current| current + 1);
+        self.current = Some(next);
+        next < self.tokens.len()
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.current.unwrap_or(0)]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.current.unwrap_or(0)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str, exact_tokens: bool) -> Vec<(String, usize)> {
+        let mut tokenizer = CodeTokenizer { exact_tokens };
+        let mut stream = tokenizer.token_stream(text);
+        let mut tokens = Vec::new();
+        while let Some(token) = stream.next() {
+            tokens.push((token.text.clone(), token.position));
+        }
+        tokens
+    }
+
+    #[test]
+    fn identifiers_are_indexed_whole_and_by_word() {
+        assert_eq!(
+            tokens("let getUserName = x;", false),
+            vec![
+                ("let".to_string(), 0),
+                ("getUserName".to_string(), 1),
+                ("get".to_string(), 1),
+                ("User".to_string(), 2),
+                ("Name".to_string(), 3),
+                ("x".to_string(), 4),
+            ]
+        );
+        assert_eq!(
+            tokens("user_name", true),
+            vec![("user_name".to_string(), 0)]
+        );
+    }
+}
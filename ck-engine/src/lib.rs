@@ -1,5 +1,7 @@
 use anyhow::Result;
-use ck_core::{CkError, IncludePattern, SearchMode, SearchOptions, SearchResult, Span};
+use ck_core::{
+    CkError, IncludePattern, SearchMode, SearchOptions, SearchResult, SimilarityMetric, Span,
+};
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use rayon::prelude::*;
 use regex::{Regex, RegexBuilder};
@@ -9,12 +11,45 @@ use std::path::PathBuf as StdPathBuf;
 use std::path::{Path, PathBuf};
 use tantivy::collector::TopDocs;
 use tantivy::query::QueryParser;
-use tantivy::schema::{STORED, Schema, TEXT, Value};
+use tantivy::schema::Value;
 use tantivy::{Index, ReloadPolicy, TantivyDocument, doc};
 use walkdir::WalkDir;
 
+mod blame;
+mod definitions;
+mod diff;
+mod duplicates;
+mod enclosing;
+mod feedback;
+mod lexical;
+mod merge;
+mod neighbors;
+mod recent;
+mod references;
+mod roots;
 mod semantic_v3;
+mod snapshot;
+mod sort;
+mod streaming;
+mod structural;
+mod top_chunks;
+mod vector;
+pub use definitions::{SymbolDefinition, find_definitions};
+pub use diff::{DiffChunk, DiffChunks, diff_chunks, diff_search};
+pub use duplicates::{
+    DEFAULT_DUPLICATE_LIMIT, DEFAULT_DUPLICATE_THRESHOLD, DuplicateChunk, DuplicateCluster,
+    DuplicatePair, cluster_duplicates, find_duplicates,
+};
+pub use feedback::{Feedback, FeedbackWeights, rerank_with_feedback, rocchio_query};
+pub use neighbors::{ChunkNeighbor, nearest_chunk_neighbors, nearest_chunks};
 pub use semantic_v3::{semantic_search_v3, semantic_search_v3_with_progress};
+pub use snapshot::{Snapshot, default_snapshot_dir, materialize_snapshot};
+pub use sort::sort_results;
+pub use streaming::{PartialResultsCallback, SearchStream, SearchUpdate, TopK, search_stream};
+pub use top_chunks::{
+    CHUNK_SIZE_MEASURE_NAMES, ChunkSize, ChunkSizeMeasure, DEFAULT_TOP_CHUNKS_LIMIT, largest_chunks,
+};
+pub use vector::{ChunkVector, QueryVector, chunk_vector, query_vector};
 
 pub type SearchProgressCallback = Box<dyn Fn(&str) + Send + Sync>;
 pub type IndexingProgressCallback = Box<dyn Fn(&str) + Send + Sync>;
@@ -22,9 +57,17 @@ pub type DetailedIndexingProgressCallback = Box<dyn Fn(ck_index::EmbeddingProgre
 
 /// Resolve the actual file path to read content from
 /// For PDFs: returns cache path and validates it exists
+/// For archive entries: extracts to the index's content cache (or a temp
+/// file when there is no index) and returns that path
 /// For regular files: returns original path
 fn resolve_content_path(file_path: &Path, repo_root: &Path) -> Result<PathBuf> {
-    if ck_core::pdf::is_pdf_file(file_path) {
+    if ck_core::archive::is_virtual_path(file_path) {
+        if repo_root.join(".ck").is_dir() {
+            Ok(ck_core::archive::cache_entry(repo_root, file_path)?)
+        } else {
+            Ok(ck_core::archive::extract_to_temp(file_path)?)
+        }
+    } else if ck_core::pdf::is_pdf_file(file_path) {
         // PDFs: Read from cached extracted text
         let cache_path = ck_core::pdf::get_content_cache_path(repo_root, file_path);
         if !cache_path.exists() {
@@ -182,6 +225,123 @@ fn filter_files_by_include(
         .collect()
 }
 
+/// `--glob` filters compiled against the search root. Like ripgrep, a path is
+/// dropped when its last matching glob is negated, or when there are positive
+/// globs and none match it. `--recent` in filter mode also drops files
+/// modified outside its window, and `--no-tests` drops test files.
+pub(crate) struct PathGlobs {
+    root: PathBuf,
+    overrides: Option<ignore::overrides::Override>,
+    recent: Option<ck_core::RecentFiles>,
+    tests: ck_core::TestFilter,
+    test_files: GlobSet,
+    /// Line-based searches know no chunks, so `--tests-only` keeps whole files
+    filter_only_test_files: bool,
+}
+
+impl PathGlobs {
+    pub(crate) fn new(options: &SearchOptions) -> Result<Self> {
+        let root = canonicalize_for_matching(&options.path);
+        let root = if root.is_file() {
+            root.parent().map(Path::to_path_buf).unwrap_or(root)
+        } else {
+            root
+        };
+        let recent = options
+            .recent
+            .filter(|recent| recent.mode == ck_core::RecentMode::Filter);
+        let test_files = if options.tests == ck_core::TestFilter::All {
+            GlobSet::empty()
+        } else {
+            build_test_globset(&options.test_patterns)?
+        };
+        let filter_only_test_files = options.tests == ck_core::TestFilter::Only
+            && matches!(options.mode, SearchMode::Regex | SearchMode::Lexical);
+        let mut globs = Self {
+            root,
+            overrides: None,
+            recent,
+            tests: options.tests,
+            test_files,
+            filter_only_test_files,
+        };
+        if options.globs.is_empty() {
+            return Ok(globs);
+        }
+
+        let invalid = |glob: &str, err: ignore::Error| {
+            CkError::Search(format!("Invalid --glob '{}': {}", glob, err))
+        };
+        let mut builder = ignore::overrides::OverrideBuilder::new(&globs.root);
+        for glob in &options.globs {
+            builder.add(glob).map_err(|err| invalid(glob, err))?;
+        }
+        globs.overrides = Some(
+            builder
+                .build()
+                .map_err(|err| invalid(&options.globs.join(" "), err))?,
+        );
+        Ok(globs)
+    }
+
+    pub(crate) fn matches(&self, path: &Path) -> bool {
+        if self.recent.is_some_and(|recent| !recent.includes(path)) {
+            return false;
+        }
+        if (self.tests == ck_core::TestFilter::Exclude || self.filter_only_test_files)
+            && !self.tests.keeps(self.is_test_file(path))
+        {
+            return false;
+        }
+        let Some(overrides) = &self.overrides else {
+            return true;
+        };
+        let candidate = canonicalize_for_matching(path);
+        let relative = candidate.strip_prefix(&self.root).unwrap_or(&candidate);
+        !overrides.matched(relative, false).is_ignore()
+    }
+
+    /// Whether a chunk of `path` passes `--no-tests` / `--tests-only`, given
+    /// whether its metadata marks it as a test ([`ck_core::is_test_chunk`])
+    pub(crate) fn keeps_chunk(&self, path: &Path, is_test_chunk: bool) -> bool {
+        self.tests == ck_core::TestFilter::All
+            || self.tests.keeps(is_test_chunk || self.is_test_file(path))
+    }
+
+    fn is_test_file(&self, path: &Path) -> bool {
+        let candidate = canonicalize_for_matching(path);
+        let relative = candidate.strip_prefix(&self.root).unwrap_or(&candidate);
+        self.test_files.is_match(relative)
+            || relative
+                .file_name()
+                .is_some_and(|name| self.test_files.is_match(name))
+    }
+}
+
+/// Test file globs, `test_patterns` or else [`ck_core::DEFAULT_TEST_PATTERNS`]
+fn build_test_globset(patterns: &[String]) -> Result<GlobSet> {
+    let defaults: Vec<String>;
+    let patterns = if patterns.is_empty() {
+        defaults = ck_core::DEFAULT_TEST_PATTERNS
+            .iter()
+            .map(|pattern| pattern.to_string())
+            .collect();
+        &defaults
+    } else {
+        patterns
+    };
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|err| {
+            CkError::Search(format!("Invalid test pattern '{}': {}", pattern, err))
+        })?;
+        builder.add(glob);
+    }
+    Ok(builder
+        .build()
+        .map_err(|err| CkError::Search(format!("Invalid test patterns: {}", err)))?)
+}
+
 fn find_nearest_index_root(path: &Path) -> Option<StdPathBuf> {
     let mut current = if path.is_file() {
         path.parent().unwrap_or(path)
@@ -204,21 +364,65 @@ pub struct ResolvedModel {
     pub canonical_name: String,
     pub alias: String,
     pub dimensions: usize,
+    /// The index's matryoshka truncation, which queries get too
+    pub truncated_dims: Option<usize>,
 }
 
-fn find_model_entry<'a>(
-    registry: &'a ck_models::ModelRegistry,
-    key: &str,
-) -> Option<(String, &'a ck_models::ModelConfig)> {
-    if let Some(config) = registry.get_model(key) {
-        return Some((key.to_string(), config));
+impl ResolvedModel {
+    /// The model's shared embedder, truncated as the index's embeddings are
+    pub fn embedder(&self) -> Result<Box<dyn ck_embed::Embedder>> {
+        Ok(ck_embed::with_dims(
+            ck_embed::cached_embedder(Some(self.canonical_name.as_str()))?,
+            self.truncated_dims,
+        ))
     }
+}
 
-    registry
-        .models
-        .iter()
-        .find(|(_, config)| config.name == key)
-        .map(|(alias, config)| (alias.clone(), config))
+fn find_model_entry(
+    registry: &ck_models::ModelRegistry,
+    key: &str,
+) -> Option<(String, ck_models::ModelConfig)> {
+    registry.resolve(key)
+}
+
+/// Whether `path` is inside an index with embeddings, whose semantic search
+/// would have to load a model
+pub fn index_has_embeddings(path: &Path) -> bool {
+    find_nearest_index_root(path)
+        .and_then(|root| std::fs::read(root.join(".ck").join("manifest.json")).ok())
+        .and_then(|data| serde_json::from_slice::<ck_index::IndexManifest>(&data).ok())
+        .is_some_and(|manifest| manifest.embedding_model.is_some())
+}
+
+/// Load the model a semantic search under `path` would embed its query with,
+/// and run one inference, so the first real query doesn't wait for either
+pub fn warm_up_embedder(path: &Path, model: Option<&str>) -> Result<ck_embed::Warmup> {
+    let index_root = find_nearest_index_root(path).unwrap_or_else(|| path.to_path_buf());
+    let resolved = resolve_model_from_root(&index_root, model)?;
+    ck_embed::warm_up(Some(resolved.canonical_name.as_str()))
+}
+
+/// The metric to score embeddings with: `requested`, else the one recorded
+/// with the index, else cosine. Warns when `requested` overrides a different
+/// recorded metric.
+pub(crate) fn resolve_metric(
+    index_root: &Path,
+    requested: Option<SimilarityMetric>,
+) -> SimilarityMetric {
+    let recorded = std::fs::read(index_root.join(".ck").join("manifest.json"))
+        .ok()
+        .and_then(|data| serde_json::from_slice::<ck_index::IndexManifest>(&data).ok())
+        .and_then(|manifest| manifest.similarity_metric);
+    if let (Some(requested), Some(recorded)) = (requested, recorded)
+        && requested != recorded
+    {
+        tracing::warn!(
+            "Scoring with --metric {}, but the index's embeddings are meant for {}",
+            requested.name(),
+            recorded.name()
+        );
+    }
+    requested.or(recorded).unwrap_or_default()
 }
 
 pub(crate) fn resolve_model_from_root(
@@ -249,7 +453,7 @@ pub(crate) fn resolve_model_from_root(
                 let (_, requested_config) =
                     find_model_entry(&registry, requested).ok_or_else(|| {
                         CkError::Embedding(format!(
-                            "Unknown model '{}'. Available models: {}",
+                            "Unknown model '{}'. Available models: {} (or openai:<model> for a remote endpoint)",
                             requested,
                             registry
                                 .models
@@ -278,6 +482,7 @@ pub(crate) fn resolve_model_from_root(
                 canonical_name: existing_model,
                 alias,
                 dimensions: dims,
+                truncated_dims: manifest.truncated_dims,
             });
         }
     }
@@ -285,7 +490,7 @@ pub(crate) fn resolve_model_from_root(
     let (alias, config) = if let Some(requested) = cli_model {
         find_model_entry(&registry, requested).ok_or_else(|| {
             CkError::Embedding(format!(
-                "Unknown model '{}'. Available models: {}",
+                "Unknown model '{}'. Available models: {} (or openai:<model> for a remote endpoint)",
                 requested,
                 registry
                     .models
@@ -297,7 +502,7 @@ pub(crate) fn resolve_model_from_root(
         })?
     } else {
         let alias = registry.default_model.clone();
-        let config = registry.get_default_model().ok_or_else(|| {
+        let config = registry.get_default_model().cloned().ok_or_else(|| {
             CkError::Embedding("No default embedding model configured".to_string())
         })?;
         (alias, config)
@@ -307,6 +512,7 @@ pub(crate) fn resolve_model_from_root(
         canonical_name: config.name.clone(),
         alias,
         dimensions: config.dimensions,
+        truncated_dims: None,
     })
 }
 
@@ -353,6 +559,25 @@ pub async fn search_enhanced_with_indexing_progress(
     progress_callback: Option<SearchProgressCallback>,
     indexing_progress_callback: Option<IndexingProgressCallback>,
     detailed_indexing_progress_callback: Option<DetailedIndexingProgressCallback>,
+) -> Result<ck_core::SearchResults> {
+    search_enhanced_streaming(
+        options,
+        progress_callback,
+        indexing_progress_callback,
+        detailed_indexing_progress_callback,
+        None,
+    )
+    .await
+}
+
+/// Enhanced search that also hands semantic searches' best-so-far results to
+/// `partial_results_callback` while scoring. Other modes only return the final results.
+pub async fn search_enhanced_streaming(
+    options: &SearchOptions,
+    progress_callback: Option<SearchProgressCallback>,
+    indexing_progress_callback: Option<IndexingProgressCallback>,
+    detailed_indexing_progress_callback: Option<DetailedIndexingProgressCallback>,
+    partial_results_callback: Option<PartialResultsCallback>,
 ) -> Result<ck_core::SearchResults> {
     // Validate that the search path exists
     if !options.path.exists() {
@@ -363,8 +588,58 @@ pub async fn search_enhanced_with_indexing_progress(
         .into());
     }
 
-    // Auto-update index if needed (unless it's regex-only mode)
-    if !matches!(options.mode, SearchMode::Regex) {
+    // Diff searches embed the changed chunks directly, so there is no index to update
+    if options.diff_range.is_some() {
+        if options.mode != SearchMode::Semantic {
+            return Err(ck_core::CkError::Search(
+                "--diff searches changed chunks semantically and can't be combined with regex, lexical or hybrid search"
+                    .to_string(),
+            )
+            .into());
+        }
+        let mut search_results = diff_search(options, progress_callback)?;
+        recent::boost_recent_files(&mut search_results.matches, options);
+        merge::merge_adjacent_results(&mut search_results.matches, options);
+        sort_results(&mut search_results.matches, options);
+        mark_query_terms(&mut search_results, options);
+        enclosing::prepend_enclosing_signatures(&mut search_results, options);
+        blame::annotate_blame(&mut search_results, options);
+        return Ok(search_results);
+    }
+
+    // A directory with no index of its own but indexed packages below it
+    // searches each package's index rather than building one over them all
+    if !matches!(options.mode, SearchMode::Regex | SearchMode::Structural)
+        && options.path.is_dir()
+        && find_nearest_index_root(&options.path).is_none()
+    {
+        let roots = roots::nested_index_roots(&options.path, &options.exclude_patterns);
+        if !roots.is_empty() {
+            tracing::info!("Searching {} nested index roots", roots.len());
+            return roots::search_index_roots(
+                options,
+                &roots,
+                progress_callback,
+                indexing_progress_callback,
+                detailed_indexing_progress_callback,
+            )
+            .await;
+        }
+    }
+
+    // Chunk filters work on indexed chunk metadata, which only chunk-based modes know about
+    if semantic_v3::has_chunk_filters(options)
+        && matches!(options.mode, SearchMode::Regex | SearchMode::Lexical)
+    {
+        return Err(ck_core::CkError::Search(
+            "--scope, --type, --docs-only and --code-only filter indexed chunks and need semantic or hybrid search"
+                .to_string(),
+        )
+        .into());
+    }
+
+    // Auto-update index if needed (regex and structural searches read files directly)
+    if !matches!(options.mode, SearchMode::Regex | SearchMode::Structural) {
         let need_embeddings = matches!(options.mode, SearchMode::Semantic | SearchMode::Hybrid);
         let file_options = ck_core::FileCollectionOptions::from(options);
         ensure_index_updated_with_progress(
@@ -379,7 +654,7 @@ pub async fn search_enhanced_with_indexing_progress(
         .await?;
     }
 
-    let search_results = match options.mode {
+    let mut search_results = match options.mode {
         SearchMode::Regex => {
             let matches = regex_search(options)?;
             ck_core::SearchResults {
@@ -394,9 +669,29 @@ pub async fn search_enhanced_with_indexing_progress(
                 closest_below_threshold: None,
             }
         }
+        SearchMode::Structural => ck_core::SearchResults {
+            matches: structural::structural_search(options)?,
+            closest_below_threshold: None,
+        },
         SearchMode::Semantic => {
+            // Partial results follow the requested order too
+            let partial_results_callback = match (partial_results_callback, options.sort) {
+                (Some(callback), Some(_)) => {
+                    let sort_options = options.clone();
+                    Some(Box::new(move |mut results: Vec<SearchResult>| {
+                        sort_results(&mut results, &sort_options);
+                        callback(results)
+                    }) as PartialResultsCallback)
+                }
+                (callback, _) => callback,
+            };
             // Use v3 semantic search (reads pre-computed embeddings from sidecars using spans)
-            semantic_search_v3_with_progress(options, progress_callback).await?
+            semantic_v3::semantic_search_v3_streaming(
+                options,
+                progress_callback,
+                partial_results_callback,
+            )
+            .await?
         }
         SearchMode::Hybrid => {
             let matches = hybrid_search_with_progress(options, progress_callback).await?;
@@ -407,10 +702,38 @@ pub async fn search_enhanced_with_indexing_progress(
         }
     };
 
+    recent::boost_recent_files(&mut search_results.matches, options);
+    merge::merge_adjacent_results(&mut search_results.matches, options);
+    sort_results(&mut search_results.matches, options);
+    mark_query_terms(&mut search_results, options);
+    enclosing::prepend_enclosing_signatures(&mut search_results, options);
+    blame::annotate_blame(&mut search_results, options);
     Ok(search_results)
 }
 
-fn regex_search(options: &SearchOptions) -> Result<Vec<SearchResult>> {
+/// Fill in the preview words semantic and hybrid results share with the query,
+/// when `highlight_terms` asks for them
+fn mark_query_terms(search_results: &mut ck_core::SearchResults, options: &SearchOptions) {
+    if !options.highlight_terms
+        || !matches!(options.mode, SearchMode::Semantic | SearchMode::Hybrid)
+    {
+        return;
+    }
+    let results = search_results
+        .matches
+        .iter_mut()
+        .chain(search_results.closest_below_threshold.as_mut());
+    for result in results {
+        result.match_ranges = ck_core::heatmap::query_term_ranges(
+            &result.preview,
+            &options.query,
+            options.exact_tokens,
+        );
+    }
+}
+
+/// The regex a regex search for `options` matches lines with
+pub(crate) fn query_regex(options: &SearchOptions) -> Result<Regex> {
     let pattern = if options.fixed_string {
         regex::escape(&options.query)
     } else if options.whole_word {
@@ -419,10 +742,17 @@ fn regex_search(options: &SearchOptions) -> Result<Vec<SearchResult>> {
         options.query.clone()
     };
 
-    let regex = RegexBuilder::new(&pattern)
+    Ok(RegexBuilder::new(&pattern)
         .case_insensitive(options.case_insensitive)
+        .multi_line(options.multiline)
+        .crlf(options.multiline)
         .build()
-        .map_err(CkError::Regex)?;
+        .map_err(CkError::Regex)?)
+}
+
+fn regex_search(options: &SearchOptions) -> Result<Vec<SearchResult>> {
+    let regex = query_regex(options)?;
+    let globs = PathGlobs::new(options)?;
 
     // Default to recursive for directories (like grep) to maintain compatibility
     let should_recurse = options.path.is_dir() || options.recursive;
@@ -431,6 +761,8 @@ fn regex_search(options: &SearchOptions) -> Result<Vec<SearchResult>> {
         let file_options = ck_core::FileCollectionOptions {
             respect_gitignore: options.respect_gitignore,
             use_ckignore: true,
+            follow_symlinks: options.follow_symlinks,
+            archives: options.archives,
             exclude_patterns: options.exclude_patterns.clone(),
         };
         let collected = ck_index::collect_files(&options.path, &file_options)?;
@@ -440,6 +772,10 @@ fn regex_search(options: &SearchOptions) -> Result<Vec<SearchResult>> {
         let collected = collect_files(&options.path, should_recurse, &options.exclude_patterns)?;
         filter_files_by_include(collected, &options.include_patterns)
     };
+    let files: Vec<PathBuf> = files
+        .into_iter()
+        .filter(|file| globs.matches(file))
+        .collect();
 
     let results: Vec<Vec<SearchResult>> = files
         .par_iter()
@@ -459,14 +795,8 @@ fn regex_search(options: &SearchOptions) -> Result<Vec<SearchResult>> {
         .collect();
 
     let mut all_results: Vec<SearchResult> = results.into_iter().flatten().collect();
-    // Deterministic ordering: file path, then line number
-    all_results.sort_by(|a, b| {
-        let path_cmp = a.file.cmp(&b.file);
-        if path_cmp != std::cmp::Ordering::Equal {
-            return path_cmp;
-        }
-        a.span.line_start.cmp(&b.span.line_start)
-    });
+    // Deterministic ordering: file path, then lines
+    all_results.sort_by(ck_core::compare_by_location);
 
     if let Some(top_k) = options.top_k {
         all_results.truncate(top_k);
@@ -484,6 +814,11 @@ fn search_file(
     let repo_root = find_nearest_index_root(file_path)
         .unwrap_or_else(|| file_path.parent().unwrap_or(file_path).to_path_buf());
 
+    if options.multiline && !regex.as_str().is_empty() {
+        let content = read_file_content(file_path, &repo_root)?;
+        return Ok(search_file_multiline(regex, file_path, options, &content));
+    }
+
     // For full_section mode, we need the entire content for parsing
     // For context previews, we need all lines for surrounding context
     // So we'll load content when needed, but optimize for the common case
@@ -560,8 +895,11 @@ fn search_file_in_memory(
                 preview,
                 lang: ck_core::Language::from_path(file_path),
                 symbol: None,
+                breadcrumb: None,
                 chunk_hash: None,
                 index_epoch: None,
+                blame: None,
+                match_ranges: Vec::new(),
             });
         } else {
             // Find all matches in the line with their positions
@@ -594,8 +932,11 @@ fn search_file_in_memory(
                     preview,
                     lang: ck_core::Language::from_path(file_path),
                     symbol: None,
+                    breadcrumb: None,
                     chunk_hash: None,
                     index_epoch: None,
+                    blame: None,
+                    match_ranges: Vec::new(),
                 });
             }
         }
@@ -608,6 +949,80 @@ fn search_file_in_memory(
     Ok(results)
 }
 
+/// Multiline search: the regex runs over the whole file, and each match is one
+/// result spanning the lines it touches, previewed by those lines
+fn search_file_multiline(
+    regex: &Regex,
+    file_path: &Path,
+    options: &SearchOptions,
+    content: &str,
+) -> Vec<SearchResult> {
+    let (lines, line_ending_lengths) = split_lines_with_endings(content);
+    let mut line_starts = Vec::with_capacity(lines.len());
+    let mut offset = 0;
+    for (line, ending) in lines.iter().zip(&line_ending_lengths) {
+        line_starts.push(offset);
+        offset += line.len() + ending;
+    }
+    // 1-based line holding the byte at `byte`
+    let line_of = |byte: usize| line_starts.partition_point(|&start| start <= byte).max(1);
+    let code_sections = if options.full_section {
+        extract_code_sections(file_path, content)
+    } else {
+        None
+    };
+    let before = options.before_context_lines.max(options.context_lines);
+    let after = options.after_context_lines.max(options.context_lines);
+
+    let mut results: Vec<SearchResult> = Vec::new();
+    for mat in regex.find_iter(content) {
+        let line_start = line_of(mat.start());
+        // Where the match ends, not the line its trailing newline leads to
+        let line_end = line_of(mat.end().saturating_sub(1).max(mat.start()));
+        // An empty match (`^`, `x*`) counts once per line, like line mode
+        if mat.is_empty()
+            && results
+                .last()
+                .is_some_and(|last| last.span.line_end >= line_start)
+        {
+            continue;
+        }
+
+        let section = code_sections
+            .as_ref()
+            .and_then(|sections| find_containing_section(sections, line_start - 1));
+        let preview = match section {
+            Some(section) => section.clone(),
+            None => {
+                let start_idx = (line_start - 1).saturating_sub(before);
+                let end_idx = (line_end + after).min(lines.len());
+                lines[start_idx..end_idx].join("\n")
+            }
+        };
+
+        results.push(SearchResult {
+            file: file_path.to_path_buf(),
+            span: Span {
+                byte_start: mat.start(),
+                byte_end: mat.end(),
+                line_start,
+                line_end,
+            },
+            score: 1.0,
+            preview,
+            lang: ck_core::Language::from_path(file_path),
+            symbol: None,
+            breadcrumb: None,
+            chunk_hash: None,
+            index_epoch: None,
+            blame: None,
+            match_ranges: Vec::new(),
+        });
+    }
+
+    results
+}
+
 /// Streaming search for simple cases without context or code sections
 fn search_file_streaming(
     regex: &Regex,
@@ -728,8 +1143,11 @@ fn process_streaming_line(
             preview: line.to_string(),
             lang: ck_core::Language::from_path(file_path),
             symbol: None,
+            breadcrumb: None,
             chunk_hash: None,
             index_epoch: None,
+            blame: None,
+            match_ranges: Vec::new(),
         });
     } else {
         for mat in regex.find_iter(line) {
@@ -745,14 +1163,18 @@ fn process_streaming_line(
                 preview: line.to_string(),
                 lang: ck_core::Language::from_path(file_path),
                 symbol: None,
+                breadcrumb: None,
                 chunk_hash: None,
                 index_epoch: None,
+                blame: None,
+                match_ranges: Vec::new(),
             });
         }
     }
 }
 
 async fn lexical_search(options: &SearchOptions) -> Result<Vec<SearchResult>> {
+    let globs = PathGlobs::new(options)?;
     // Handle both files and directories and reuse nearest existing .ck index up the tree
     let index_root = find_nearest_index_root(&options.path).unwrap_or_else(|| {
         if options.path.is_file() {
@@ -770,16 +1192,23 @@ async fn lexical_search(options: &SearchOptions) -> Result<Vec<SearchResult>> {
     let tantivy_index_path = index_dir.join("tantivy_index");
 
     if !tantivy_index_path.exists() {
-        return build_tantivy_index(options).await;
+        let build_root = if options.path.is_file() {
+            options.path.parent().unwrap_or(&options.path)
+        } else {
+            &options.path
+        };
+        return build_tantivy_index(options, build_root).await;
     }
 
-    let mut schema_builder = Schema::builder();
-    let content_field = schema_builder.add_text_field("content", TEXT | STORED);
-    let path_field = schema_builder.add_text_field("path", TEXT | STORED);
-    let _schema = schema_builder.build();
-
     let index = Index::open_in_dir(&tantivy_index_path)
         .map_err(|e| CkError::Index(format!("Failed to open tantivy index: {}", e)))?;
+    let Some(fields) = lexical::fields(&index) else {
+        // Built before identifiers were split
+        drop(index);
+        fs::remove_dir_all(&tantivy_index_path)?;
+        return build_tantivy_index(options, &index_root).await;
+    };
+    let (content_field, path_field) = (fields.content, fields.path);
 
     let reader = index
         .reader_builder()
@@ -788,7 +1217,8 @@ async fn lexical_search(options: &SearchOptions) -> Result<Vec<SearchResult>> {
         .map_err(|e| CkError::Index(format!("Failed to create index reader: {}", e)))?;
 
     let searcher = reader.searcher();
-    let query_parser = QueryParser::for_index(&index, vec![content_field]);
+    let query_parser =
+        QueryParser::for_index(&index, vec![fields.query_field(options.exact_tokens)]);
 
     let query = query_parser
         .parse_query(&options.query)
@@ -814,7 +1244,9 @@ async fn lexical_search(options: &SearchOptions) -> Result<Vec<SearchResult>> {
             .unwrap_or("");
 
         let file_path = PathBuf::from(path_text);
-        if !path_matches_include(&file_path, &options.include_patterns) {
+        if !path_matches_include(&file_path, &options.include_patterns)
+            || !globs.matches(&file_path)
+        {
             continue;
         }
         let preview = if options.full_section {
@@ -837,8 +1269,11 @@ async fn lexical_search(options: &SearchOptions) -> Result<Vec<SearchResult>> {
                 preview,
                 lang: ck_core::Language::from_path(&PathBuf::from(path_text)),
                 symbol: None,
+                breadcrumb: None,
                 chunk_hash: None,
                 index_epoch: None,
+                blame: None,
+                match_ranges: Vec::new(),
             },
         ));
     }
@@ -866,30 +1301,25 @@ async fn lexical_search(options: &SearchOptions) -> Result<Vec<SearchResult>> {
             }
         }
     }
+    // Tantivy orders equal scores by document, i.e. by when each file was indexed
+    results.sort_by(ck_core::compare_by_score);
 
     Ok(results)
 }
 
-async fn build_tantivy_index(options: &SearchOptions) -> Result<Vec<SearchResult>> {
-    // Handle both files and directories by finding the appropriate directory for indexing
-    let index_root = if options.path.is_file() {
-        options.path.parent().unwrap_or(&options.path)
-    } else {
-        &options.path
-    };
-
+async fn build_tantivy_index(
+    options: &SearchOptions,
+    index_root: &Path,
+) -> Result<Vec<SearchResult>> {
     let index_dir = index_root.join(".ck");
     let tantivy_index_path = index_dir.join("tantivy_index");
 
     fs::create_dir_all(&tantivy_index_path)?;
 
-    let mut schema_builder = Schema::builder();
-    let content_field = schema_builder.add_text_field("content", TEXT | STORED);
-    let path_field = schema_builder.add_text_field("path", TEXT | STORED);
-    let schema = schema_builder.build();
-
-    let index = Index::create_in_dir(&tantivy_index_path, schema.clone())
+    let index = Index::create_in_dir(&tantivy_index_path, lexical::schema())
         .map_err(|e| CkError::Index(format!("Failed to create tantivy index: {}", e)))?;
+    let fields = lexical::fields(&index)
+        .ok_or_else(|| CkError::Index("Lexical index schema is missing fields".to_string()))?;
 
     let mut index_writer = index
         .writer(50_000_000)
@@ -903,8 +1333,9 @@ async fn build_tantivy_index(options: &SearchOptions) -> Result<Vec<SearchResult
     for file_path in &files {
         if let Ok(content) = fs::read_to_string(file_path) {
             let doc = doc!(
-                content_field => content,
-                path_field => file_path.display().to_string()
+                fields.content => content.clone(),
+                fields.content_split => content,
+                fields.path => file_path.display().to_string()
             );
             index_writer.add_document(doc)?;
         }
@@ -915,14 +1346,7 @@ async fn build_tantivy_index(options: &SearchOptions) -> Result<Vec<SearchResult
         .map_err(|e| CkError::Index(format!("Failed to commit index: {}", e)))?;
 
     // After building, search again with the same options
-    let tantivy_index_path = index_root.join(".ck").join("tantivy_index");
-    let mut schema_builder = Schema::builder();
-    let content_field = schema_builder.add_text_field("content", TEXT | STORED);
-    let path_field = schema_builder.add_text_field("path", TEXT | STORED);
-    let _schema = schema_builder.build();
-
-    let index = Index::open_in_dir(&tantivy_index_path)
-        .map_err(|e| CkError::Index(format!("Failed to open tantivy index: {}", e)))?;
+    let (content_field, path_field) = (fields.content, fields.path);
 
     let reader = index
         .reader_builder()
@@ -931,7 +1355,8 @@ async fn build_tantivy_index(options: &SearchOptions) -> Result<Vec<SearchResult
         .map_err(|e| CkError::Index(format!("Failed to create index reader: {}", e)))?;
 
     let searcher = reader.searcher();
-    let query_parser = QueryParser::for_index(&index, vec![content_field]);
+    let query_parser =
+        QueryParser::for_index(&index, vec![fields.query_field(options.exact_tokens)]);
 
     let query = query_parser
         .parse_query(&options.query)
@@ -977,8 +1402,11 @@ async fn build_tantivy_index(options: &SearchOptions) -> Result<Vec<SearchResult
                 preview,
                 lang: ck_core::Language::from_path(&PathBuf::from(path_text)),
                 symbol: None,
+                breadcrumb: None,
                 chunk_hash: None,
                 index_epoch: None,
+                blame: None,
+                match_ranges: Vec::new(),
             },
         ));
     }
@@ -1022,7 +1450,10 @@ async fn hybrid_search_with_progress(
     if let Some(ref callback) = progress_callback {
         callback("Running regex search...");
     }
-    let regex_results = regex_search(options)?;
+    let mut regex_results = regex_search(options)?;
+    if semantic_v3::has_chunk_filters(options) || options.tests != ck_core::TestFilter::All {
+        retain_results_in_filtered_chunks(&mut regex_results, options)?;
+    }
 
     if let Some(ref callback) = progress_callback {
         callback("Running semantic search...");
@@ -1069,14 +1500,13 @@ async fn hybrid_search_with_progress(
         })
         .collect();
 
-    rrf_results.retain(|result| path_matches_include(&result.file, &options.include_patterns));
+    let globs = PathGlobs::new(options)?;
+    rrf_results.retain(|result| {
+        path_matches_include(&result.file, &options.include_patterns) && globs.matches(&result.file)
+    });
 
     // Sort by RRF score (highest first)
-    rrf_results.sort_by(|a, b| {
-        b.score
-            .partial_cmp(&a.score)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    rrf_results.sort_by(ck_core::compare_by_score);
 
     if let Some(top_k) = options.top_k {
         rrf_results.truncate(top_k);
@@ -1085,6 +1515,57 @@ async fn hybrid_search_with_progress(
     Ok(rrf_results)
 }
 
+/// Drop line-based results that don't fall inside an indexed chunk passing the
+/// chunk filters, or that `--no-tests` / `--tests-only` leave out
+fn retain_results_in_filtered_chunks(
+    results: &mut Vec<SearchResult>,
+    options: &SearchOptions,
+) -> Result<()> {
+    let index_root = find_nearest_index_root(&options.path).unwrap_or_else(|| {
+        if options.path.is_file() {
+            options.path.parent().unwrap_or(&options.path).to_path_buf()
+        } else {
+            options.path.clone()
+        }
+    });
+    let chunk_filters = semantic_v3::has_chunk_filters(options);
+    let globs = PathGlobs::new(options)?;
+
+    let mut allowed_spans: HashMap<PathBuf, Vec<Span>> = HashMap::new();
+    let mut test_spans: HashMap<PathBuf, Vec<Span>> = HashMap::new();
+    for (file, chunk) in semantic_v3::load_embedded_chunks(
+        &index_root.join(".ck"),
+        &index_root,
+        &options.include_patterns,
+    )? {
+        let file = canonicalize_for_matching(&file);
+        if options.tests != ck_core::TestFilter::All && semantic_v3::is_test_chunk(&chunk) {
+            test_spans
+                .entry(file.clone())
+                .or_default()
+                .push(chunk.span.clone());
+        }
+        if chunk_filters && semantic_v3::chunk_passes_filters(&chunk, options) {
+            allowed_spans.entry(file).or_default().push(chunk.span);
+        }
+    }
+
+    let covers = |spans: Option<&Vec<Span>>, line: usize| {
+        spans.is_some_and(|spans| {
+            spans
+                .iter()
+                .any(|span| span.line_start <= line && line <= span.line_end)
+        })
+    };
+    results.retain(|result| {
+        let file = canonicalize_for_matching(&result.file);
+        let line = result.span.line_start;
+        (!chunk_filters || covers(allowed_spans.get(&file), line))
+            && globs.keeps_chunk(&result.file, covers(test_spans.get(&file), line))
+    });
+    Ok(())
+}
+
 fn build_globset(patterns: &[String]) -> GlobSet {
     let mut builder = GlobSetBuilder::new();
     for pat in patterns {
@@ -1213,6 +1694,10 @@ async fn ensure_index_updated_with_progress(
 
     // For incremental updates with individual files, we need special handling
     // to ensure only the specific file is indexed, not the entire directory
+    if ck_index::is_imported_index(index_root) {
+        // Imported embeddings came from elsewhere; there's nothing to refresh
+        return Ok(());
+    }
     if path.is_file() {
         // Index just this one file
         use ck_index::index_file;
@@ -1405,6 +1890,174 @@ mod tests {
         assert_eq!(files[0], test_files[0]);
     }
 
+    #[tokio::test]
+    async fn test_chunk_filters_rejected_for_line_based_modes() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_files(temp_dir.path());
+
+        for mode in [SearchMode::Regex, SearchMode::Lexical] {
+            let options = SearchOptions {
+                mode: mode.clone(),
+                query: "rust".to_string(),
+                path: temp_dir.path().to_path_buf(),
+                scopes: vec!["net::http".to_string()],
+                ..Default::default()
+            };
+
+            let err = search(&options).await.unwrap_err();
+            assert!(err.to_string().contains("--scope"));
+
+            let options = SearchOptions {
+                mode,
+                query: "rust".to_string(),
+                path: temp_dir.path().to_path_buf(),
+                chunk_filter: ck_core::ChunkFilter::DocsOnly,
+                ..Default::default()
+            };
+
+            let err = search(&options).await.unwrap_err();
+            assert!(err.to_string().contains("--docs-only"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lexical_search_splits_identifiers_unless_exact() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join(".ck")).unwrap();
+        fs::write(
+            root.join("user.js"),
+            "function getUserName(user) { return user.fullName; }\n",
+        )
+        .unwrap();
+        fs::write(root.join("other.js"), "const total = 1;\n").unwrap();
+
+        let options = |query: &str, exact_tokens| SearchOptions {
+            mode: SearchMode::Lexical,
+            query: query.to_string(),
+            path: root.to_path_buf(),
+            exact_tokens,
+            // The default excludes would skip the temp dir itself
+            exclude_patterns: vec![".ck".to_string()],
+            ..Default::default()
+        };
+        let files = |results: Vec<SearchResult>| -> Vec<String> {
+            results
+                .iter()
+                .map(|result| {
+                    result
+                        .file
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            files(lexical_search(&options("name", false)).await.unwrap()),
+            vec!["user.js"]
+        );
+        assert_eq!(
+            files(
+                lexical_search(&options("getUserName", false))
+                    .await
+                    .unwrap()
+            ),
+            vec!["user.js"]
+        );
+        assert!(
+            lexical_search(&options("name", true))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            files(lexical_search(&options("getusername", true)).await.unwrap()),
+            vec!["user.js"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_structural_search_ranks_chunk_names_without_an_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join("config.rs"),
+            "struct Loader;\n\nimpl Loader {\n    fn parse_config(&self) {}\n    fn parse_args(&self) {}\n}\n\nfn render() {}\n",
+        )
+        .unwrap();
+
+        let options = SearchOptions {
+            mode: SearchMode::Structural,
+            query: "parse_cfg".to_string(),
+            path: root.to_path_buf(),
+            exclude_patterns: vec![".ck".to_string()],
+            ..Default::default()
+        };
+        let results = search(&options).await.unwrap();
+        let symbols: Vec<&str> = results
+            .iter()
+            .filter_map(|result| result.symbol.as_deref())
+            .collect();
+        assert_eq!(symbols.first(), Some(&"parse_config"));
+        assert!(!symbols.contains(&"render"));
+        assert_eq!(
+            results[0].breadcrumb.as_deref(),
+            Some("Loader::parse_config")
+        );
+        assert!(!root.join(".ck").exists());
+    }
+
+    #[tokio::test]
+    async fn test_search_merges_nested_index_roots() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for (package, source) in [
+            ("api", "function retryRequest() { return retry(3); }\n"),
+            ("web", "const retry = () => fetchAgain();\n"),
+        ] {
+            let dir = root.join("packages").join(package);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("index.js"), source).unwrap();
+        }
+
+        let options = |path: &Path| SearchOptions {
+            mode: SearchMode::Lexical,
+            query: "retry".to_string(),
+            path: path.to_path_buf(),
+            exclude_patterns: vec![".ck".to_string()],
+            ..Default::default()
+        };
+        // Each package indexes on its own
+        for package in ["api", "web"] {
+            let dir = root.join("packages").join(package);
+            fs::create_dir_all(dir.join(".ck")).unwrap();
+            search(&options(&dir)).await.unwrap();
+        }
+
+        let results = search(&options(root)).await.unwrap();
+        let mut packages: Vec<String> = results
+            .iter()
+            .map(|result| {
+                result
+                    .file
+                    .parent()
+                    .unwrap()
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        packages.sort();
+        assert_eq!(packages, vec!["api", "web"]);
+        assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+        // No index was built over the packages
+        assert!(!root.join(".ck").exists());
+    }
+
     #[test]
     fn test_regex_search() {
         let temp_dir = TempDir::new().unwrap();
@@ -1447,6 +2100,165 @@ mod tests {
         assert!(!results.is_empty());
     }
 
+    #[test]
+    fn test_resolve_metric_prefers_request_then_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        assert_eq!(resolve_metric(root, None), SimilarityMetric::Cosine);
+
+        ck_index::set_similarity_metric(root, SimilarityMetric::Dot).unwrap();
+        assert_eq!(resolve_metric(root, None), SimilarityMetric::Dot);
+        assert_eq!(
+            resolve_metric(root, Some(SimilarityMetric::Euclidean)),
+            SimilarityMetric::Euclidean
+        );
+    }
+
+    #[test]
+    fn test_regex_search_multiline_spans_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("lib.rs");
+        let source = "// header\r\nfn foo(\r\n    a: u32,\r\n) -> u32 {\r\n    a\r\n}\r\n";
+        fs::write(&file, source).unwrap();
+
+        let options = SearchOptions {
+            mode: SearchMode::Regex,
+            query: r"fn\s+foo[\s\S]*?\{$".to_string(),
+            path: file.clone(),
+            multiline: true,
+            ..Default::default()
+        };
+        let results = regex_search(&options).unwrap();
+        assert_eq!(results.len(), 1);
+        let span = &results[0].span;
+        assert_eq!((span.line_start, span.line_end), (2, 4));
+        assert!(source[span.byte_start..span.byte_end].starts_with("fn foo("));
+        assert!(source[span.byte_start..span.byte_end].ends_with("u32 {"));
+        assert_eq!(results[0].preview, "fn foo(\n    a: u32,\n) -> u32 {");
+
+        // Line-oriented matching can't see across the newline
+        let single_line = SearchOptions {
+            multiline: false,
+            ..options.clone()
+        };
+        assert!(regex_search(&single_line).unwrap().is_empty());
+
+        // Empty matches count once per line
+        let empty = SearchOptions {
+            query: "^".to_string(),
+            ..options
+        };
+        assert_eq!(regex_search(&empty).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_globs_filter_paths_like_ripgrep() {
+        let temp_dir = TempDir::new().unwrap();
+        for file in ["src/lib.rs", "src/tests/it.rs", "src/readme.md", "build.rs"] {
+            let path = temp_dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "needle\n").unwrap();
+        }
+        let found = |globs: &[&str]| {
+            let options = SearchOptions {
+                mode: SearchMode::Regex,
+                query: "needle".to_string(),
+                path: temp_dir.path().to_path_buf(),
+                globs: globs.iter().map(|glob| glob.to_string()).collect(),
+                ..Default::default()
+            };
+            let mut files: Vec<String> = regex_search(&options)
+                .unwrap()
+                .iter()
+                .map(|result| {
+                    let relative = result.file.strip_prefix(temp_dir.path()).unwrap();
+                    relative.to_string_lossy().replace('\\', "/")
+                })
+                .collect();
+            files.sort();
+            files
+        };
+
+        assert_eq!(found(&[]).len(), 4);
+        assert_eq!(
+            found(&["src/**/*.rs", "!**/tests/**"]),
+            vec!["src/lib.rs".to_string()]
+        );
+        // Globs without a slash match at any depth; negations alone keep the rest
+        assert_eq!(found(&["*.rs"]).len(), 3);
+        assert_eq!(found(&["!*.md"]).len(), 3);
+
+        let invalid = SearchOptions {
+            mode: SearchMode::Regex,
+            query: "needle".to_string(),
+            path: temp_dir.path().to_path_buf(),
+            globs: vec!["src/{lib".to_string()],
+            ..Default::default()
+        };
+        let err = regex_search(&invalid).unwrap_err();
+        assert!(err.to_string().contains("--glob"));
+    }
+
+    #[test]
+    fn test_test_filter_drops_or_keeps_test_code() {
+        let temp_dir = TempDir::new().unwrap();
+        let files = [
+            (
+                "src/lib.rs",
+                "fn parse() {}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn parses() {}\n}\n",
+            ),
+            ("tests/it.rs", "fn end_to_end() {}\n"),
+        ];
+        for (file, content) in files {
+            let path = temp_dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, content).unwrap();
+        }
+        let symbols = |tests: ck_core::TestFilter, mode: SearchMode| {
+            let regex = mode == SearchMode::Regex;
+            let options = SearchOptions {
+                mode,
+                query: if regex { "fn " } else { "" }.to_string(),
+                path: temp_dir.path().to_path_buf(),
+                tests,
+                ..Default::default()
+            };
+            let results = if regex {
+                regex_search(&options)
+            } else {
+                structural::structural_search(&options)
+            };
+            let mut found: Vec<String> = results
+                .unwrap()
+                .into_iter()
+                .map(|result| match result.symbol {
+                    Some(symbol) => symbol,
+                    None => result.preview.trim().to_string(),
+                })
+                .collect();
+            found.sort();
+            found
+        };
+
+        assert_eq!(
+            symbols(ck_core::TestFilter::All, SearchMode::Structural),
+            vec!["end_to_end", "parse", "parses", "tests"]
+        );
+        assert_eq!(
+            symbols(ck_core::TestFilter::Exclude, SearchMode::Structural),
+            vec!["parse"]
+        );
+        assert_eq!(
+            symbols(ck_core::TestFilter::Only, SearchMode::Structural),
+            vec!["end_to_end", "parses", "tests"]
+        );
+        // Line-based searches filter whole files
+        assert_eq!(
+            symbols(ck_core::TestFilter::Only, SearchMode::Regex),
+            vec!["fn end_to_end() {}"]
+        );
+    }
+
     #[test]
     fn test_regex_search_fixed_string() {
         let temp_dir = TempDir::new().unwrap();
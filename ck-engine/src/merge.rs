@@ -0,0 +1,178 @@
+use ck_core::{SearchOptions, SearchResult, Span};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Coalesce results in the same file whose line spans lie at most
+/// `options.merge_adjacent` lines apart into one result covering them all,
+/// scored by its best member and placed where that member was. The merged
+/// result keeps the best member's symbol and breadcrumb; with `full_section`
+/// its preview is the whole combined range, otherwise its first lines.
+pub(crate) fn merge_adjacent_results(results: &mut Vec<SearchResult>, options: &SearchOptions) {
+    let Some(max_gap) = options.merge_adjacent else {
+        return;
+    };
+
+    let mut by_file: HashMap<PathBuf, Vec<usize>> = HashMap::new();
+    for (position, result) in results.iter().enumerate() {
+        by_file
+            .entry(result.file.clone())
+            .or_default()
+            .push(position);
+    }
+
+    // Runs of positions in line order, each merged into its best member
+    let mut absorbed = vec![false; results.len()];
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for mut positions in by_file.into_values() {
+        positions.sort_by_key(|&position| results[position].span.line_start);
+        let mut group: Vec<usize> = Vec::new();
+        let mut group_end = 0;
+        for position in positions {
+            let span = &results[position].span;
+            if !group.is_empty() && span.line_start > group_end + max_gap + 1 {
+                groups.push(std::mem::take(&mut group));
+            }
+            group_end = if group.is_empty() {
+                span.line_end
+            } else {
+                group_end.max(span.line_end)
+            };
+            group.push(position);
+        }
+        groups.push(group);
+    }
+
+    for group in groups.into_iter().filter(|group| group.len() > 1) {
+        let best = *group
+            .iter()
+            .max_by(|&&a, &&b| {
+                results[a]
+                    .score
+                    .total_cmp(&results[b].score)
+                    // Equal scores keep the earlier result
+                    .then(b.cmp(&a))
+            })
+            .expect("groups are never empty");
+        let members: Vec<&SearchResult> =
+            group.iter().map(|&position| &results[position]).collect();
+        let merged = merge_group(&members, &results[best], options.full_section);
+        results[best] = merged;
+        for position in group.into_iter().filter(|&position| position != best) {
+            absorbed[position] = true;
+        }
+    }
+
+    let mut position = 0;
+    results.retain(|_| {
+        let keep = !absorbed[position];
+        position += 1;
+        keep
+    });
+}
+
+fn merge_group(members: &[&SearchResult], best: &SearchResult, full_section: bool) -> SearchResult {
+    let first = members
+        .iter()
+        .min_by_key(|member| member.span.line_start)
+        .expect("groups are never empty");
+    let span = Span {
+        byte_start: members.iter().map(|m| m.span.byte_start).min().unwrap_or(0),
+        byte_end: members.iter().map(|m| m.span.byte_end).max().unwrap_or(0),
+        line_start: first.span.line_start,
+        line_end: members.iter().map(|m| m.span.line_end).max().unwrap_or(0),
+    };
+
+    let preview = if full_section {
+        fs::read_to_string(&best.file)
+            .ok()
+            .map(|content| {
+                content
+                    .lines()
+                    .skip(span.line_start.saturating_sub(1))
+                    .take(span.line_end + 1 - span.line_start)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_else(|| {
+                members
+                    .iter()
+                    .map(|member| member.preview.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+    } else {
+        first.preview.clone()
+    };
+
+    SearchResult {
+        span,
+        preview,
+        // The merged range is no single chunk
+        chunk_hash: None,
+        match_ranges: Vec::new(),
+        ..best.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(file: &str, lines: (usize, usize), score: f32) -> SearchResult {
+        SearchResult {
+            file: PathBuf::from(file),
+            span: Span::new_unchecked(lines.0 * 10, lines.1 * 10, lines.0, lines.1),
+            score,
+            preview: format!("line {}", lines.0),
+            breadcrumb: Some(format!("chunk_{}", lines.0)),
+            chunk_hash: Some(format!("hash_{}", lines.0)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn adjacent_chunks_merge_into_their_best_member() {
+        let mut results = vec![
+            result("a.rs", (12, 20), 0.9),
+            result("b.rs", (1, 5), 0.8),
+            result("a.rs", (1, 10), 0.7),
+            result("a.rs", (40, 50), 0.6),
+        ];
+        let options = SearchOptions {
+            merge_adjacent: Some(1),
+            ..Default::default()
+        };
+        merge_adjacent_results(&mut results, &options);
+
+        let summary: Vec<(&str, usize, usize, f32)> = results
+            .iter()
+            .map(|r| {
+                (
+                    r.file.to_str().unwrap(),
+                    r.span.line_start,
+                    r.span.line_end,
+                    r.score,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("a.rs", 1, 20, 0.9),
+                ("b.rs", 1, 5, 0.8),
+                ("a.rs", 40, 50, 0.6),
+            ]
+        );
+        let merged = &results[0];
+        assert_eq!(merged.breadcrumb.as_deref(), Some("chunk_12"));
+        assert_eq!(merged.preview, "line 1");
+        assert_eq!(merged.chunk_hash, None);
+        assert_eq!((merged.span.byte_start, merged.span.byte_end), (10, 200));
+
+        // A gap wider than allowed keeps the chunks apart
+        let mut results = vec![result("a.rs", (1, 10), 0.7), result("a.rs", (13, 20), 0.9)];
+        merge_adjacent_results(&mut results, &options);
+        assert_eq!(results.len(), 2);
+    }
+}
@@ -0,0 +1,141 @@
+use anyhow::Result;
+use ck_core::{ChunkId, CkError, Span};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use super::duplicates::spans_overlap;
+use super::semantic_v3::{cosine_similarity, load_embedded_chunks};
+
+/// An indexed chunk ranked by embedding similarity to another chunk
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkNeighbor {
+    pub id: ChunkId,
+    pub score: f32,
+    pub file: PathBuf,
+    pub span: Span,
+    pub breadcrumb: Option<String>,
+    pub chunk_hash: Option<String>,
+}
+
+/// The `k` indexed chunks whose embeddings are closest to the chunk `chunk_id`,
+/// best first. See [`nearest_chunk_neighbors`].
+pub fn nearest_chunks(index_root: &Path, chunk_id: &str, k: usize) -> Result<Vec<(ChunkId, f32)>> {
+    Ok(nearest_chunk_neighbors(index_root, chunk_id, k)?
+        .into_iter()
+        .map(|neighbor| (neighbor.id, neighbor.score))
+        .collect())
+}
+
+/// The `k` indexed chunks under `index_root` most similar to `chunk_id`, with
+/// where they live. Reads the existing index without updating it; the chunk
+/// itself and chunks overlapping it in the same file are never returned.
+pub fn nearest_chunk_neighbors(
+    index_root: &Path,
+    chunk_id: &str,
+    k: usize,
+) -> Result<Vec<ChunkNeighbor>> {
+    let chunks = load_embedded_chunks(&index_root.join(".ck"), index_root, &[])?;
+
+    let entries: Vec<(ChunkId, &Path, &Span, &[f32])> = chunks
+        .iter()
+        .filter_map(|(file, chunk)| {
+            let hash = chunk.chunk_hash.as_deref()?;
+            let embedding = chunk.embedding.as_deref()?;
+            let relative = file.strip_prefix(index_root).unwrap_or(file);
+            let id = ck_core::compute_chunk_id(relative, chunk.breadcrumb.as_deref(), hash);
+            Some((id, file.as_path(), &chunk.span, embedding))
+        })
+        .collect();
+
+    let ranked = rank_neighbors(&entries, chunk_id, k)
+        .ok_or_else(|| CkError::Search(format!("Chunk {} is not in the index", chunk_id)))?;
+
+    Ok(ranked
+        .into_iter()
+        .map(|(index, score)| {
+            let (file, chunk) = &chunks[index];
+            ChunkNeighbor {
+                id: entries[index].0.clone(),
+                score,
+                file: file.clone(),
+                span: chunk.span.clone(),
+                breadcrumb: chunk.breadcrumb.clone(),
+                chunk_hash: chunk.chunk_hash.clone(),
+            }
+        })
+        .collect())
+}
+
+/// Indices of the `k` entries most similar to the one with id `chunk_id`, or
+/// `None` when no entry has that id
+fn rank_neighbors(
+    entries: &[(ChunkId, &Path, &Span, &[f32])],
+    chunk_id: &str,
+    k: usize,
+) -> Option<Vec<(usize, f32)>> {
+    let (_, source_file, source_span, source_embedding) =
+        entries.iter().find(|(id, ..)| id == chunk_id)?;
+
+    let mut ranked: Vec<(usize, f32)> = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, file, span, _))| {
+            !(file == source_file && spans_overlap(span, source_span))
+        })
+        .map(|(index, (_, _, _, embedding))| {
+            (index, cosine_similarity(source_embedding, embedding))
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    ranked.truncate(k);
+    Some(ranked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_neighbors_orders_by_similarity_and_skips_source() {
+        let span = |start, end| Span::new_unchecked(0, 0, start, end);
+        let (a, b) = (Path::new("a.rs"), Path::new("b.rs"));
+        let (outer, inner, far, near, other) = (
+            span(1, 20),
+            span(5, 8),
+            span(30, 40),
+            span(1, 9),
+            span(10, 12),
+        );
+        let embeddings: [&[f32]; 5] = [
+            &[1.0, 0.0],
+            &[1.0, 0.1],
+            &[0.0, 1.0],
+            &[0.9, 0.2],
+            &[0.6, 0.6],
+        ];
+        let entries: Vec<(ChunkId, &Path, &Span, &[f32])> = vec![
+            ("src".to_string(), a, &outer, embeddings[0]),
+            ("nested".to_string(), a, &inner, embeddings[1]),
+            ("far".to_string(), a, &far, embeddings[2]),
+            ("near".to_string(), b, &near, embeddings[3]),
+            ("other".to_string(), b, &other, embeddings[4]),
+        ];
+
+        let ranked = rank_neighbors(&entries, "src", 10).unwrap();
+        let ids: Vec<&str> = ranked
+            .iter()
+            .map(|(index, _)| entries[*index].0.as_str())
+            .collect();
+        // The overlapping chunk in the same file is excluded along with the source
+        assert_eq!(ids, vec!["near", "other", "far"]);
+        assert!(ranked[0].1 > ranked[1].1 && ranked[1].1 > ranked[2].1);
+
+        assert_eq!(rank_neighbors(&entries, "src", 1).unwrap().len(), 1);
+        assert!(rank_neighbors(&entries, "missing", 3).is_none());
+    }
+}
@@ -0,0 +1,90 @@
+use ck_core::{RecentMode, SearchOptions, SearchResult};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Score multiplier for results from recently modified files
+const RECENT_BOOST: f32 = 1.5;
+
+/// With `--recent` in boost mode, scale the score of every result from a
+/// file modified within the window by [`RECENT_BOOST`] and re-rank by score,
+/// so recent files rise without older ones being dropped. Filter mode is
+/// applied while searching, by [`super::PathGlobs`].
+pub(crate) fn boost_recent_files(results: &mut [SearchResult], options: &SearchOptions) {
+    let Some(recent) = options
+        .recent
+        .filter(|recent| recent.mode == RecentMode::Boost)
+    else {
+        return;
+    };
+    let mut is_recent: HashMap<PathBuf, bool> = HashMap::new();
+    for result in results.iter_mut() {
+        let boosted = *is_recent
+            .entry(result.file.clone())
+            .or_insert_with(|| recent.includes(&result.file));
+        if boosted && result.score > 0.0 {
+            result.score *= RECENT_BOOST;
+        }
+    }
+    results.sort_by(ck_core::compare_by_score);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ck_core::{RecentFiles, Span};
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+
+    fn result(file: &Path, score: f32) -> SearchResult {
+        SearchResult {
+            file: file.to_path_buf(),
+            span: Span::new_unchecked(0, 0, 1, 1),
+            score,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn boost_ranks_recent_files_first_and_keeps_the_rest() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let old = temp_dir.path().join("old.rs");
+        let new = temp_dir.path().join("new.rs");
+        std::fs::write(&old, "fn old() {}\n").unwrap();
+        std::fs::write(&new, "fn new() {}\n").unwrap();
+        let last_month = SystemTime::now() - Duration::from_secs(30 * 24 * 60 * 60);
+        std::fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(last_month)
+            .unwrap();
+
+        let mut recent = RecentFiles {
+            window: Duration::from_secs(24 * 60 * 60),
+            mode: RecentMode::Boost,
+        };
+        assert!(recent.includes(&new));
+        assert!(!recent.includes(&old));
+
+        let mut results = vec![result(&old, 0.8), result(&new, 0.6)];
+        let options = SearchOptions {
+            recent: Some(recent),
+            ..Default::default()
+        };
+        boost_recent_files(&mut results, &options);
+        assert_eq!(results[0].file, new);
+        assert!((results[0].score - 0.9).abs() < 1e-6);
+        assert_eq!(results[1].score, 0.8);
+
+        // Filter mode leaves scores alone here
+        recent.mode = RecentMode::Filter;
+        let mut results = vec![result(&old, 0.8), result(&new, 0.6)];
+        let options = SearchOptions {
+            recent: Some(recent),
+            ..Default::default()
+        };
+        boost_recent_files(&mut results, &options);
+        assert_eq!(results[0].file, old);
+        assert_eq!(results[1].score, 0.6);
+    }
+}
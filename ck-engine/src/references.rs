@@ -0,0 +1,192 @@
+use anyhow::Result;
+use ck_core::{SearchOptions, SearchResult};
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+
+use super::PathGlobs;
+use super::diff::{chunk_breadcrumb, chunk_passes_filters, is_test_chunk};
+
+/// Lines of a referencing chunk shown in its preview
+const PREVIEW_LINES: usize = 3;
+
+/// Find the chunks of `files` that use `options.query` as an identifier, an
+/// approximation of callers and references needing no language server. Every
+/// whole-word, case-sensitive occurrence counts once towards the innermost
+/// chunk passing the chunk filters around it, which scores its count. Chunks
+/// named like the symbol are its definitions and are left out, with the
+/// occurrences inside them.
+pub(crate) fn reference_search(
+    files: &[PathBuf],
+    globs: &PathGlobs,
+    options: &SearchOptions,
+) -> Vec<SearchResult> {
+    let symbol = options.query.trim();
+    if symbol.is_empty() {
+        return Vec::new();
+    }
+    let mut results: Vec<SearchResult> = files
+        .par_iter()
+        .flat_map_iter(|file| {
+            search_file_references(file, symbol, globs, options).unwrap_or_else(|e| {
+                tracing::debug!("Skipping {:?}: {}", file, e);
+                Vec::new()
+            })
+        })
+        .collect();
+
+    results.sort_by(ck_core::compare_by_score);
+    if let Some(top_k) = options.top_k {
+        results.truncate(top_k);
+    }
+    results
+}
+
+fn search_file_references(
+    file: &Path,
+    symbol: &str,
+    globs: &PathGlobs,
+    options: &SearchOptions,
+) -> Result<Vec<SearchResult>> {
+    let content = std::fs::read_to_string(file)?;
+    if ck_chunk::has_ignore_file_marker(&content) {
+        return Ok(Vec::new());
+    }
+    let occurrences = identifier_occurrences(&content, symbol);
+    if occurrences.is_empty() {
+        return Ok(Vec::new());
+    }
+    let lang = ck_core::Language::from_path(file);
+    let chunks = ck_chunk::without_ignored(ck_chunk::chunk_text(&content, lang)?);
+    let contains = |chunk: &ck_chunk::Chunk, offset: usize| {
+        chunk.span.byte_start <= offset && offset < chunk.span.byte_end
+    };
+
+    let mut counts = vec![0usize; chunks.len()];
+    for &offset in &occurrences {
+        let in_definition = chunks
+            .iter()
+            .any(|chunk| chunk.metadata.name.as_deref() == Some(symbol) && contains(chunk, offset));
+        if in_definition {
+            continue;
+        }
+        let innermost = chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| {
+                contains(chunk, offset)
+                    && chunk_passes_filters(chunk, options)
+                    && globs.keeps_chunk(file, is_test_chunk(chunk))
+            })
+            .min_by_key(|(_, chunk)| chunk.span.byte_end - chunk.span.byte_start);
+        if let Some((index, _)) = innermost {
+            counts[index] += 1;
+        }
+    }
+
+    Ok(chunks
+        .iter()
+        .zip(counts)
+        .filter(|(_, count)| *count > 0)
+        .map(|(chunk, count)| {
+            let preview = if options.full_section {
+                chunk.text.clone()
+            } else {
+                chunk
+                    .text
+                    .lines()
+                    .filter(|line| !identifier_occurrences(line, symbol).is_empty())
+                    .take(PREVIEW_LINES)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            let match_ranges = identifier_occurrences(&preview, symbol)
+                .into_iter()
+                .map(|start| (start, start + symbol.len()))
+                .collect();
+            let name = chunk.metadata.name.clone().filter(|name| !name.is_empty());
+            let breadcrumb = match (chunk_breadcrumb(chunk), name.as_deref()) {
+                (Some(enclosing), Some(name)) => Some(format!("{}::{}", enclosing, name)),
+                (enclosing, name) => enclosing.or(name.map(str::to_string)),
+            };
+            SearchResult {
+                file: file.to_path_buf(),
+                span: chunk.span.clone(),
+                score: count as f32,
+                preview,
+                lang,
+                symbol: name,
+                breadcrumb,
+                chunk_hash: None,
+                index_epoch: None,
+                blame: None,
+                match_ranges,
+            }
+        })
+        .collect())
+}
+
+/// Byte offsets of `symbol` in `text` where it isn't part of a longer identifier
+fn identifier_occurrences(text: &str, symbol: &str) -> Vec<usize> {
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(symbol)
+        .map(|(start, _)| start)
+        .filter(|&start| {
+            let before = text[..start].chars().next_back();
+            let after = text[start + symbol.len()..].chars().next();
+            !before.is_some_and(is_identifier) && !after.is_some_and(is_identifier)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_chunks_using_a_symbol_ranked_by_count() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("lib.rs");
+        std::fs::write(
+            &file,
+            "\
+fn parse(input: &str) -> u32 {
+    parse(input)
+}
+
+fn once() -> u32 {
+    parse(\"1\")
+}
+
+fn twice() -> u32 {
+    parse(\"1\") + parse(\"2\")
+}
+
+fn unrelated() -> u32 {
+    parse_all(\"3\") + reparse()
+}
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            identifier_occurrences("parse(parse_all) reparse parse", "parse"),
+            vec![0, 25]
+        );
+
+        let options = SearchOptions {
+            query: "parse".to_string(),
+            references: true,
+            ..Default::default()
+        };
+        let globs = PathGlobs::new(&options).unwrap();
+        let results = reference_search(&[file], &globs, &options);
+        let found: Vec<(Option<&str>, f32)> = results
+            .iter()
+            .map(|result| (result.symbol.as_deref(), result.score))
+            .collect();
+        // The definition's recursive call isn't a reference of its own
+        assert_eq!(found, vec![(Some("twice"), 2.0), (Some("once"), 1.0)]);
+        assert_eq!(results[0].preview, "    parse(\"1\") + parse(\"2\")");
+        assert_eq!(results[0].match_ranges, vec![(4, 9), (17, 22)]);
+    }
+}
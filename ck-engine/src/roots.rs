@@ -0,0 +1,184 @@
+use anyhow::Result;
+use ck_core::{CkError, SearchMode, SearchOptions, SearchResult};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use walkdir::WalkDir;
+
+use super::{
+    DetailedIndexingProgressCallback, IndexingProgressCallback, SearchProgressCallback,
+    build_globset, canonicalize_for_matching, resolve_model_from_root, sort_results,
+};
+
+/// Directories below `path` that hold an index of their own, in path order.
+/// A root's subtree belongs to its index, so nothing inside one is searched
+/// for further roots; hidden and excluded directories are skipped.
+pub(crate) fn nested_index_roots(path: &Path, exclude_patterns: &[String]) -> Vec<PathBuf> {
+    let globset = build_globset(exclude_patterns);
+    let mut roots = Vec::new();
+    let mut walker = WalkDir::new(path)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter();
+    while let Some(entry) = walker.next() {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+        let name = entry.file_name();
+        // Matched relative to `path`, so excludes like `tmp` don't hit its ancestors
+        let relative = entry.path().strip_prefix(path).unwrap_or(entry.path());
+        if name.to_string_lossy().starts_with('.')
+            || globset.is_match(name)
+            || globset.is_match(relative)
+        {
+            walker.skip_current_dir();
+            continue;
+        }
+        if entry.path().join(".ck").is_dir() {
+            roots.push(entry.path().to_path_buf());
+            walker.skip_current_dir();
+        }
+    }
+    roots
+}
+
+/// Search each of `roots` as if it had been searched on its own, then combine
+/// the results into one ranking capped at `options.top_k`. Each root keeps
+/// its own index up to date. Semantic and hybrid searches need every root to
+/// use the same embedding model, since scores from different models don't compare.
+pub(crate) fn search_index_roots<'a>(
+    options: &'a SearchOptions,
+    roots: &'a [PathBuf],
+    progress_callback: Option<SearchProgressCallback>,
+    indexing_progress_callback: Option<IndexingProgressCallback>,
+    detailed_indexing_progress_callback: Option<DetailedIndexingProgressCallback>,
+) -> Pin<Box<dyn Future<Output = Result<ck_core::SearchResults>> + Send + 'a>> {
+    Box::pin(async move {
+        if matches!(options.mode, SearchMode::Semantic | SearchMode::Hybrid) {
+            check_consistent_model(roots, options.embedding_model.as_deref())?;
+        }
+
+        let progress_callback = progress_callback.map(Arc::new);
+        let indexing_progress_callback = indexing_progress_callback.map(Arc::new);
+        let detailed_indexing_progress_callback = detailed_indexing_progress_callback.map(Arc::new);
+
+        let mut matches = Vec::new();
+        let mut closest_below_threshold: Option<SearchResult> = None;
+        for root in roots {
+            let root_options = SearchOptions {
+                path: root.clone(),
+                ..options.clone()
+            };
+            let results = super::search_enhanced_streaming(
+                &root_options,
+                progress_callback.clone().map(|callback| {
+                    Box::new(move |message: &str| callback(message)) as SearchProgressCallback
+                }),
+                indexing_progress_callback.clone().map(|callback| {
+                    Box::new(move |message: &str| callback(message)) as IndexingProgressCallback
+                }),
+                detailed_indexing_progress_callback.clone().map(|callback| {
+                    Box::new(move |progress| callback(progress)) as DetailedIndexingProgressCallback
+                }),
+                None,
+            )
+            .await?;
+            matches.extend(results.matches);
+            if let Some(closest) = results.closest_below_threshold
+                && closest_below_threshold
+                    .as_ref()
+                    .is_none_or(|best| closest.score > best.score)
+            {
+                closest_below_threshold = Some(closest);
+            }
+        }
+
+        let mut matches = dedup_by_location(matches);
+        matches.sort_by(ck_core::compare_by_score);
+        if let Some(top_k) = options.top_k {
+            matches.truncate(top_k);
+        }
+        sort_results(&mut matches, options);
+
+        Ok(ck_core::SearchResults {
+            matches,
+            closest_below_threshold,
+        })
+    })
+}
+
+/// Fail when the roots' indexes (or the ones they'd build) use different models
+fn check_consistent_model(roots: &[PathBuf], cli_model: Option<&str>) -> Result<()> {
+    let mut models: Vec<(String, &Path)> = Vec::new();
+    for root in roots {
+        let model = resolve_model_from_root(root, cli_model)?;
+        if !models.iter().any(|(name, _)| *name == model.canonical_name) {
+            models.push((model.canonical_name, root));
+        }
+    }
+    if models.len() > 1 {
+        let listed: Vec<String> = models
+            .iter()
+            .map(|(name, root)| format!("{} ({})", root.display(), name))
+            .collect();
+        return Err(CkError::Search(format!(
+            "Nested indexes use different embedding models and can't be searched together: {}. Rebuild them with the same model using `ck --switch-model <model> <dir>`",
+            listed.join(", ")
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Keep the best-scoring result for each file and span; the same file can be
+/// reached through more than one root by a symlink
+fn dedup_by_location(results: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut best: HashMap<(PathBuf, usize, usize), SearchResult> = HashMap::new();
+    for result in results {
+        let key = (
+            canonicalize_for_matching(&result.file),
+            result.span.line_start,
+            result.span.line_end,
+        );
+        match best.get(&key) {
+            Some(existing) if existing.score >= result.score => {}
+            _ => {
+                best.insert(key, result);
+            }
+        }
+    }
+    best.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn nested_roots_stop_at_the_first_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for dir in [
+            "packages/api/.ck",
+            "packages/api/vendor/inner/.ck",
+            "packages/web/.ck",
+            "node_modules/dep/.ck",
+            ".hidden/.ck",
+            "docs",
+        ] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        let roots = nested_index_roots(root, &["node_modules".to_string()]);
+        assert_eq!(
+            roots,
+            vec![root.join("packages/api"), root.join("packages/web")]
+        );
+    }
+}
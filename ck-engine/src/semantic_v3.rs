@@ -1,10 +1,14 @@
 use anyhow::Result;
-use ck_core::{CkError, SearchOptions, SearchResult};
-use std::path::Path;
+use ck_core::{
+    ChunkFilter, CkError, IncludePattern, SearchOptions, SearchResult, SimilarityMetric,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+use super::streaming::{PartialResultsCallback, SCORE_BATCH, TopK};
 use super::{
-    SearchProgressCallback, extract_content_from_span, find_nearest_index_root,
+    SearchProgressCallback, extract_content_from_span, find_nearest_index_root, resolve_metric,
     resolve_model_from_root,
 };
 
@@ -16,6 +20,15 @@ pub async fn semantic_search_v3(options: &SearchOptions) -> Result<ck_core::Sear
 pub async fn semantic_search_v3_with_progress(
     options: &SearchOptions,
     progress_callback: Option<SearchProgressCallback>,
+) -> Result<ck_core::SearchResults> {
+    semantic_search_v3_streaming(options, progress_callback, None).await
+}
+
+/// Semantic search that reports the best results so far between scoring batches
+pub(crate) async fn semantic_search_v3_streaming(
+    options: &SearchOptions,
+    progress_callback: Option<SearchProgressCallback>,
+    partial_results_callback: Option<PartialResultsCallback>,
 ) -> Result<ck_core::SearchResults> {
     // Find the index root
     let index_root = find_nearest_index_root(&options.path).unwrap_or_else(|| {
@@ -39,37 +52,7 @@ pub async fn semantic_search_v3_with_progress(
     }
 
     // Collect all sidecar files and their embeddings
-    let mut file_chunks: Vec<(std::path::PathBuf, ck_index::ChunkEntry)> = Vec::new();
-
-    for entry in WalkDir::new(&index_dir) {
-        let entry = entry?;
-        if entry.file_type().is_file() {
-            let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("ck") {
-                // Load the sidecar file
-                if let Ok(index_entry) = ck_index::load_index_entry(path) {
-                    let original_file = reconstruct_original_path(path, &index_dir, &index_root);
-                    if let Some(original_file) = original_file {
-                        if !super::path_matches_include(&original_file, &options.include_patterns) {
-                            continue;
-                        }
-                        for chunk in index_entry.chunks {
-                            if chunk.embedding.is_some() {
-                                file_chunks.push((original_file.clone(), chunk));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    if file_chunks.is_empty() {
-        return Err(CkError::Index(
-            "No embeddings found. Run 'ck --index' first with embeddings.".to_string(),
-        )
-        .into());
-    }
+    let file_chunks = load_candidate_chunks(options, &index_dir, &index_root)?;
 
     if let Some(ref callback) = progress_callback {
         callback(&format!(
@@ -91,8 +74,29 @@ pub async fn semantic_search_v3_with_progress(
         ));
     }
 
-    let mut embedder = ck_embed::create_embedder(Some(resolved_model.canonical_name.as_str()))?;
-    let query_embeddings = embedder.embed(std::slice::from_ref(&options.query))?;
+    let metric = resolve_metric(&index_root, options.metric);
+    let mut embedder = resolved_model.embedder()?;
+    let query_texts = if options.search_by_example {
+        example_query_chunks(
+            &options.query,
+            options.example_language,
+            &resolved_model.canonical_name,
+        )?
+    } else {
+        vec![options.query.clone()]
+    };
+    let query_embeddings = embedder.embed(&query_texts)?;
+    if let Some(query) = query_embeddings.first()
+        && query.len() != resolved_model.dimensions
+    {
+        return Err(CkError::Embedding(format!(
+            "The index holds {}-dimensional embeddings from '{}', but it embedded the query with {} dimensions. Rebuild the index with 'ck --clean .' then 'ck --index'.",
+            resolved_model.dimensions,
+            resolved_model.canonical_name,
+            query.len()
+        ))
+        .into());
+    }
 
     if query_embeddings.is_empty() {
         return Ok(ck_core::SearchResults {
@@ -101,31 +105,124 @@ pub async fn semantic_search_v3_with_progress(
         });
     }
 
-    let query_embedding = &query_embeddings[0];
-
     if let Some(ref callback) = progress_callback {
         callback("Computing similarity scores...");
     }
 
-    // Compute similarities
-    let mut similarities: Vec<(f32, &std::path::PathBuf, &ck_index::ChunkEntry)> = Vec::new();
+    // Score into a bounded heap; with several query chunks a candidate scores as its best match
+    let limit = options.top_k.unwrap_or(file_chunks.len());
+    let mut top = TopK::new(limit);
+    let batches = file_chunks.len().div_ceil(SCORE_BATCH);
+
+    for (batch_index, batch) in file_chunks.chunks(SCORE_BATCH).enumerate() {
+        let mut changed = false;
+        for (file_path, chunk) in batch {
+            if let Some(ref embedding) = chunk.embedding {
+                let similarity = best_similarity(metric, &query_embeddings, embedding);
+                changed |= top.push(similarity, (file_path, chunk));
+            }
+        }
 
-    for (file_path, chunk) in &file_chunks {
-        if let Some(ref embedding) = chunk.embedding {
-            let similarity = cosine_similarity(query_embedding, embedding);
-            similarities.push((similarity, file_path, chunk));
+        // The last batch's results go out as the final answer instead
+        if let Some(ref callback) = partial_results_callback
+            && changed
+            && batch_index + 1 < batches
+        {
+            let best = top
+                .sorted()
+                .into_iter()
+                .map(|(similarity, &(file_path, chunk))| (similarity, file_path, chunk))
+                .collect();
+            let (matches, _) = collect_results(best, options).await;
+            callback(matches);
         }
     }
 
-    // Sort by similarity (highest first)
-    similarities.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    let (mut results, closest_below_threshold) = collect_results(
+        top.into_sorted_vec()
+            .into_iter()
+            .map(|(similarity, (file_path, chunk))| (similarity, file_path, chunk))
+            .collect(),
+        options,
+    )
+    .await;
+    results.sort_by(ck_core::compare_by_score);
+
+    // Apply reranking if enabled
+    if options.rerank && !results.is_empty() {
+        if let Some(ref callback) = progress_callback {
+            callback("Reranking results for improved relevance...");
+        }
 
-    // Apply threshold and top_k filtering
+        let rerank_model_name = match options.rerank_model.as_deref() {
+            Some("jina") => Some("jina-reranker-v1-base-en"),
+            Some("bge") => Some("BAAI/bge-reranker-base"),
+            Some(name) => Some(name), // Pass through custom model names
+            None => Some("jina-reranker-v1-base-en"), // Default to jina
+        };
+
+        match ck_embed::create_reranker(rerank_model_name) {
+            Ok(mut reranker) => {
+                let documents: Vec<String> = results.iter().map(|r| r.preview.clone()).collect();
+
+                match reranker.rerank(&options.query, &documents) {
+                    Ok(rerank_results) => {
+                        // Create a map from document text to indices for handling duplicates
+                        let mut doc_to_indices: std::collections::HashMap<String, Vec<usize>> =
+                            std::collections::HashMap::new();
+                        for (i, result) in results.iter().enumerate() {
+                            doc_to_indices
+                                .entry(result.preview.clone())
+                                .or_default()
+                                .push(i);
+                        }
+
+                        // Update results with reranked scores
+                        // The reranker returns results in reranked order, so we match by document text
+                        for rerank_result in rerank_results.iter() {
+                            if let Some(indices) = doc_to_indices.get_mut(&rerank_result.document)
+                                && let Some(idx) = indices.pop()
+                            {
+                                results[idx].score = rerank_result.score;
+                            }
+                        }
+
+                        // Re-sort by reranked scores
+                        results.sort_by(ck_core::compare_by_score);
+
+                        // Apply top_k limit again after reranking
+                        if let Some(limit) = options.top_k {
+                            results.truncate(limit);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Reranking failed, using original scores: {}", e);
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to create reranker, using original scores: {}", e);
+            }
+        }
+    }
+
+    Ok(ck_core::SearchResults {
+        matches: results,
+        closest_below_threshold,
+    })
+}
+
+/// Turn scored candidates (best first) into results, applying the threshold and
+/// path filter and reading previews. Also returns the best candidate below the
+/// threshold, if any.
+async fn collect_results(
+    candidates: Vec<(f32, &std::path::PathBuf, &ck_index::ChunkEntry)>,
+    options: &SearchOptions,
+) -> (Vec<SearchResult>, Option<SearchResult>) {
     let mut results = Vec::new();
     let mut closest_below_threshold: Option<SearchResult> = None;
-    let limit = options.top_k.unwrap_or(similarities.len());
 
-    for (similarity, file_path, chunk) in similarities.into_iter().take(limit) {
+    for (similarity, file_path, chunk) in candidates {
         let is_below_threshold = options
             .threshold
             .is_some_and(|threshold| similarity < threshold);
@@ -187,8 +284,11 @@ pub async fn semantic_search_v3_with_progress(
             preview: content,
             lang: ck_core::Language::from_path(file_path),
             symbol: None,
+            breadcrumb: chunk_breadcrumb(chunk),
             chunk_hash: None,
             index_epoch: None,
+            blame: None,
+            match_ranges: Vec::new(),
         };
 
         if is_below_threshold {
@@ -202,72 +302,157 @@ pub async fn semantic_search_v3_with_progress(
         }
     }
 
-    // Apply reranking if enabled
-    if options.rerank && !results.is_empty() {
-        if let Some(ref callback) = progress_callback {
-            callback("Reranking results for improved relevance...");
-        }
+    (results, closest_below_threshold)
+}
 
-        let rerank_model_name = match options.rerank_model.as_deref() {
-            Some("jina") => Some("jina-reranker-v1-base-en"),
-            Some("bge") => Some("BAAI/bge-reranker-base"),
-            Some(name) => Some(name), // Pass through custom model names
-            None => Some("jina-reranker-v1-base-en"), // Default to jina
-        };
+/// The embedded chunks a semantic search scores: those under `index_dir` that
+/// pass the path, test and chunk filters in `options`, leaving out the
+/// `--like-file` example's own file
+fn load_candidate_chunks(
+    options: &SearchOptions,
+    index_dir: &Path,
+    index_root: &Path,
+) -> Result<Vec<(PathBuf, ck_index::ChunkEntry)>> {
+    let mut file_chunks = load_embedded_chunks(index_dir, index_root, &options.include_patterns)?;
+    if !options.globs.is_empty()
+        || options.recent.is_some()
+        || options.tests != ck_core::TestFilter::All
+    {
+        let globs = super::PathGlobs::new(options)?;
+        file_chunks.retain(|(file, chunk)| {
+            globs.matches(file) && globs.keeps_chunk(file, is_test_chunk(chunk))
+        });
+    }
 
-        match ck_embed::create_reranker(rerank_model_name) {
-            Ok(mut reranker) => {
-                let documents: Vec<String> = results.iter().map(|r| r.preview.clone()).collect();
+    if file_chunks.is_empty() {
+        return Err(CkError::Index(
+            "No embeddings found. Run 'ck --index' first with embeddings.".to_string(),
+        )
+        .into());
+    }
 
-                match reranker.rerank(&options.query, &documents) {
-                    Ok(rerank_results) => {
-                        // Create a map from document text to indices for handling duplicates
-                        let mut doc_to_indices: std::collections::HashMap<String, Vec<usize>> =
-                            std::collections::HashMap::new();
-                        for (i, result) in results.iter().enumerate() {
-                            doc_to_indices
-                                .entry(result.preview.clone())
-                                .or_default()
-                                .push(i);
-                        }
+    if has_chunk_filters(options) {
+        file_chunks.retain(|(_, chunk)| chunk_passes_filters(chunk, options));
+    }
+    retain_outside_example_file(&mut file_chunks, options, |(file, _)| file);
+    Ok(file_chunks)
+}
 
-                        // Update results with reranked scores
-                        // The reranker returns results in reranked order, so we match by document text
-                        for rerank_result in rerank_results.iter() {
-                            if let Some(indices) = doc_to_indices.get_mut(&rerank_result.document)
-                                && let Some(idx) = indices.pop()
-                            {
-                                results[idx].score = rerank_result.score;
-                            }
-                        }
+/// Drop the items `file_of` places in the file `--like-file` read its example
+/// from. Its chunks match the example almost exactly and would fill the top
+/// results ahead of the similar code elsewhere.
+pub(crate) fn retain_outside_example_file<T>(
+    items: &mut Vec<T>,
+    options: &SearchOptions,
+    file_of: impl Fn(&T) -> &Path,
+) {
+    let Some(example) = options
+        .example_file
+        .as_deref()
+        .and_then(|path| path.canonicalize().ok())
+    else {
+        return;
+    };
+    let mut is_example: HashMap<PathBuf, bool> = HashMap::new();
+    items.retain(|item| {
+        let file = file_of(item);
+        let same = match is_example.get(file) {
+            Some(&same) => same,
+            None => {
+                let same = file.canonicalize().is_ok_and(|file| file == example);
+                is_example.insert(file.to_path_buf(), same);
+                same
+            }
+        };
+        !same
+    });
+}
 
-                        // Re-sort by reranked scores
-                        results.sort_by(|a, b| {
-                            b.score
-                                .partial_cmp(&a.score)
-                                .unwrap_or(std::cmp::Ordering::Equal)
-                        });
+/// Load every chunk that has an embedding from the sidecar files under `index_dir`,
+/// paired with the source file it came from
+pub(crate) fn load_embedded_chunks(
+    index_dir: &Path,
+    index_root: &Path,
+    include_patterns: &[IncludePattern],
+) -> Result<Vec<(std::path::PathBuf, ck_index::ChunkEntry)>> {
+    load_indexed_chunks(index_dir, index_root, include_patterns, |chunk| {
+        chunk.embedding.is_some()
+    })
+}
 
-                        // Apply top_k limit again after reranking
-                        if let Some(limit) = options.top_k {
-                            results.truncate(limit);
+/// Load the chunks `keep` accepts from the sidecar files under `index_dir`,
+/// paired with the source file each came from
+pub(crate) fn load_indexed_chunks(
+    index_dir: &Path,
+    index_root: &Path,
+    include_patterns: &[IncludePattern],
+    keep: impl Fn(&ck_index::ChunkEntry) -> bool,
+) -> Result<Vec<(std::path::PathBuf, ck_index::ChunkEntry)>> {
+    let mut file_chunks = Vec::new();
+
+    // Sorted so chunks tying on score are offered, and kept, in path order
+    for entry in WalkDir::new(index_dir).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) == Some("ck") {
+                // Load the sidecar file
+                if let Ok(index_entry) = ck_index::load_index_entry(path) {
+                    let original_file = reconstruct_original_path(path, index_dir, index_root);
+                    if let Some(original_file) = original_file {
+                        if !super::path_matches_include(&original_file, include_patterns) {
+                            continue;
+                        }
+                        for chunk in index_entry.chunks {
+                            if keep(&chunk) {
+                                file_chunks.push((original_file.clone(), chunk));
+                            }
                         }
-                    }
-                    Err(e) => {
-                        tracing::warn!("Reranking failed, using original scores: {}", e);
                     }
                 }
             }
-            Err(e) => {
-                tracing::warn!("Failed to create reranker, using original scores: {}", e);
-            }
         }
     }
 
-    Ok(ck_core::SearchResults {
-        matches: results,
-        closest_below_threshold,
-    })
+    Ok(file_chunks)
+}
+
+/// Whether `options` narrows candidates by chunk metadata (`--scope`, `--docs-only`, `--code-only`)
+pub(crate) fn has_chunk_filters(options: &SearchOptions) -> bool {
+    !options.scopes.is_empty()
+        || options.chunk_filter != ChunkFilter::All
+        || !options.chunk_types.is_empty()
+}
+
+/// Whether an indexed chunk survives the chunk-metadata filters in `options`
+pub(crate) fn chunk_passes_filters(chunk: &ck_index::ChunkEntry, options: &SearchOptions) -> bool {
+    options.chunk_filter.accepts(chunk.chunk_type.as_deref())
+        && ck_core::chunk_type_selected(&options.chunk_types, chunk.chunk_type.as_deref())
+        && ck_core::breadcrumb_in_any_scope(chunk_breadcrumb(chunk).as_deref(), &options.scopes)
+}
+
+/// Whether an indexed chunk's metadata marks it as test code
+pub(crate) fn is_test_chunk(chunk: &ck_index::ChunkEntry) -> bool {
+    ck_core::is_test_chunk(
+        chunk.name.as_deref(),
+        chunk.ancestry.as_deref().unwrap_or_default(),
+        chunk.leading_trivia.as_deref().unwrap_or_default(),
+    )
+}
+
+/// The chunk's breadcrumb, falling back to its joined ancestry for older sidecars
+fn chunk_breadcrumb(chunk: &ck_index::ChunkEntry) -> Option<String> {
+    chunk
+        .breadcrumb
+        .clone()
+        .filter(|crumb| !crumb.is_empty())
+        .or_else(|| {
+            chunk
+                .ancestry
+                .as_ref()
+                .filter(|ancestry| !ancestry.is_empty())
+                .map(|ancestry| ancestry.join("::"))
+        })
 }
 
 fn reconstruct_original_path(
@@ -292,18 +477,101 @@ fn reconstruct_original_path(
     Some(repo_root.join(original_path))
 }
 
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    if a.len() != b.len() {
-        return 0.0;
+/// Split example code into the same kind of chunks the index holds, so each piece
+/// is embedded the way indexed chunks were
+pub(crate) fn example_query_chunks(
+    code: &str,
+    language: Option<ck_core::Language>,
+    model_name: &str,
+) -> Result<Vec<String>> {
+    let chunks: Vec<String> = ck_chunk::chunk_text_with_model(code, language, Some(model_name))?
+        .into_iter()
+        .map(|chunk| chunk.text)
+        .filter(|text| !text.trim().is_empty())
+        .collect();
+
+    if chunks.is_empty() {
+        Ok(vec![code.to_string()])
+    } else {
+        Ok(chunks)
     }
+}
 
-    let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+pub(crate) fn best_similarity(
+    metric: SimilarityMetric,
+    queries: &[Vec<f32>],
+    embedding: &[f32],
+) -> f32 {
+    queries
+        .iter()
+        .map(|query| metric.score(query, embedding))
+        .fold(f32::MIN, f32::max)
+}
 
-    if norm_a == 0.0 || norm_b == 0.0 {
-        0.0
-    } else {
-        dot_product / (norm_a * norm_b)
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    SimilarityMetric::Cosine.score(a, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_best_similarity_uses_closest_query_chunk() {
+        let queries = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        let cosine = SimilarityMetric::Cosine;
+        assert!((best_similarity(cosine, &queries, &[0.0, 2.0]) - 1.0).abs() < 1e-6);
+        assert!((best_similarity(cosine, &queries, &[1.0, 1.0]) - 0.70710677).abs() < 1e-6);
+        assert!((best_similarity(SimilarityMetric::Dot, &queries, &[0.0, 2.0]) - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_like_file_leaves_out_its_own_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let mut records = String::new();
+        for name in ["example.rs", "elsewhere.rs"] {
+            std::fs::write(root.join(name), "fn parse() {}\n").unwrap();
+            records.push_str(&format!(
+                "{{\"path\": \"{}\", \"line_start\": 1, \"line_end\": 1, \"vector\": {}}}\n",
+                name,
+                serde_json::to_string(&vec![0.5; 384]).unwrap()
+            ));
+        }
+        ck_index::import_embeddings(&root, records.as_bytes(), "bge-small").unwrap();
+
+        let files = |options: &SearchOptions| -> Vec<PathBuf> {
+            load_candidate_chunks(options, &root.join(".ck"), &root)
+                .unwrap()
+                .into_iter()
+                .map(|(file, _)| file)
+                .collect()
+        };
+        let mut options = SearchOptions {
+            path: root.clone(),
+            search_by_example: true,
+            ..Default::default()
+        };
+        assert_eq!(files(&options).len(), 2);
+        options.example_file = Some(root.join("./example.rs"));
+        assert_eq!(files(&options), vec![root.join("elsewhere.rs")]);
+    }
+
+    #[test]
+    fn test_example_query_chunks_splits_definitions() {
+        let code = "fn alpha() -> u32 {\n    1\n}\n\nfn beta() -> u32 {\n    2\n}\n";
+        let chunks = example_query_chunks(
+            code,
+            Some(ck_core::Language::Rust),
+            "BAAI/bge-small-en-v1.5",
+        )
+        .unwrap();
+        assert!(chunks.iter().any(|c| c.contains("fn alpha")));
+        assert!(chunks.iter().any(|c| c.contains("fn beta")));
+        assert!(
+            !chunks
+                .iter()
+                .any(|c| c.contains("fn alpha") && c.contains("fn beta"))
+        );
     }
 }
@@ -0,0 +1,215 @@
+use anyhow::Result;
+use ck_core::CkError;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::diff::{git_toplevel, run_git};
+
+/// The tree of one commit, extracted to its own directory so it can be indexed
+/// and searched like any other checkout
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Full SHA of the commit the snapshot holds
+    pub commit: String,
+    /// Directory holding the commit's files (and, once searched, its `.ck` index)
+    pub root: PathBuf,
+    /// Top level of the repository the snapshot was taken from
+    pub repo_root: PathBuf,
+    /// Whether the snapshot was already on disk
+    pub reused: bool,
+}
+
+impl Snapshot {
+    /// Where `path` (a path in the working tree) lives inside the snapshot
+    pub fn path_for(&self, path: &Path) -> Result<PathBuf> {
+        let absolute = path
+            .canonicalize()
+            .or_else(|_| std::path::absolute(path))
+            .map_err(|e| CkError::Search(format!("Invalid path {}: {}", path.display(), e)))?;
+        let relative = absolute.strip_prefix(&self.repo_root).map_err(|_| {
+            CkError::Search(format!(
+                "{} is outside the repository at {}",
+                path.display(),
+                self.repo_root.display()
+            ))
+        })?;
+        Ok(self.root.join(relative))
+    }
+}
+
+/// Where snapshots are kept: `ck/snapshots` under the user cache directory, next
+/// to the downloaded models
+pub fn default_snapshot_dir() -> PathBuf {
+    let cache_dir = if let Some(cache_home) = std::env::var_os("XDG_CACHE_HOME") {
+        PathBuf::from(cache_home).join("ck")
+    } else if let Some(home) = std::env::var_os("HOME") {
+        PathBuf::from(home).join(".cache").join("ck")
+    } else if let Some(appdata) = std::env::var_os("LOCALAPPDATA") {
+        PathBuf::from(appdata).join("ck").join("cache")
+    } else {
+        PathBuf::from(".ck_cache")
+    };
+    cache_dir.join("snapshots")
+}
+
+/// Extract the tree of `rev` (any commit-ish) from the repository containing
+/// `path` into `store/<sha>`, reusing an earlier extraction of the same commit.
+/// The snapshot gets its own `.ck` directory so it is indexed on its own rather
+/// than as part of any enclosing index.
+pub fn materialize_snapshot(path: &Path, rev: &str, store: &Path) -> Result<Snapshot> {
+    // A revision is never an option
+    if rev.is_empty() || rev.starts_with('-') {
+        return Err(CkError::Search(format!("Invalid git revision: '{}'", rev)).into());
+    }
+
+    let dir = if path.is_file() {
+        path.parent().unwrap_or(path)
+    } else {
+        path
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let repo_root = git_toplevel(dir)?;
+    let commit = run_git(
+        &repo_root,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ],
+    )
+    .map_err(|_| CkError::Search(format!("Unknown git revision: '{}'", rev)))?
+    .trim()
+    .to_string();
+
+    let root = store.join(&commit);
+    if root.join(".ck").is_dir() {
+        return Ok(Snapshot {
+            commit,
+            root,
+            repo_root,
+            reused: true,
+        });
+    }
+
+    // Extract next to the final location and rename, so an interrupted run never
+    // leaves a partial snapshot that looks complete
+    let staging = store.join(format!("{}.partial-{}", commit, std::process::id()));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    std::fs::create_dir_all(&staging)?;
+    if let Err(err) = extract_tree(&repo_root, &commit, &staging) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(err);
+    }
+    std::fs::create_dir_all(staging.join(".ck"))?;
+
+    if root.exists() {
+        std::fs::remove_dir_all(&root)?;
+    }
+    std::fs::rename(&staging, &root)?;
+
+    Ok(Snapshot {
+        commit,
+        root,
+        repo_root,
+        reused: false,
+    })
+}
+
+/// Write the files of `commit` into `target` by piping `git archive` into `tar`
+fn extract_tree(repo_root: &Path, commit: &str, target: &Path) -> Result<()> {
+    let mut archive = Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(["archive", "--format=tar", commit])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| CkError::Search(format!("Failed to run git: {}", e)))?;
+    let tar_input = archive
+        .stdout
+        .take()
+        .ok_or_else(|| CkError::Search("git archive produced no output".to_string()))?;
+
+    let unpack = Command::new("tar")
+        .arg("-x")
+        .arg("-C")
+        .arg(target)
+        .stdin(Stdio::from(tar_input))
+        .output()
+        .map_err(|e| CkError::Search(format!("Failed to run tar: {}", e)))?;
+    let archived = archive.wait_with_output()?;
+
+    if !archived.status.success() {
+        return Err(CkError::Search(format!(
+            "git archive failed: {}",
+            String::from_utf8_lossy(&archived.stderr).trim()
+        ))
+        .into());
+    }
+    if !unpack.status.success() {
+        return Err(CkError::Search(format!(
+            "tar failed: {}",
+            String::from_utf8_lossy(&unpack.stderr).trim()
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_materialize_snapshot_extracts_past_commit() {
+        let repo_dir = tempfile::TempDir::new().unwrap();
+        let store = tempfile::TempDir::new().unwrap();
+        let root = repo_dir.path();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(root)
+                .args(["-c", "user.name=ck", "-c", "user.email=ck@example.com"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+
+        std::fs::create_dir(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "fn old_name() {}\n").unwrap();
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "first"]);
+        std::fs::write(root.join("src/lib.rs"), "fn new_name() {}\n").unwrap();
+        git(&["commit", "-q", "-am", "second"]);
+
+        let snapshot = materialize_snapshot(root, "HEAD~1", store.path()).unwrap();
+        assert!(!snapshot.reused);
+        assert_eq!(snapshot.commit.len(), 40);
+        assert!(snapshot.root.join(".ck").is_dir());
+        assert_eq!(
+            std::fs::read_to_string(snapshot.root.join("src/lib.rs")).unwrap(),
+            "fn old_name() {}\n"
+        );
+        assert_eq!(
+            snapshot.path_for(&root.join("src")).unwrap(),
+            snapshot.root.join("src")
+        );
+
+        let again = materialize_snapshot(&root.join("src/lib.rs"), "HEAD~1", store.path()).unwrap();
+        assert!(again.reused);
+        assert_eq!(again.root, snapshot.root);
+
+        assert!(materialize_snapshot(root, "no-such-ref", store.path()).is_err());
+        assert!(materialize_snapshot(root, "--output=x", store.path()).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use ck_core::{ResultSort, SearchMode, SearchOptions, SearchResult};
+use regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::{extract_code_sections, query_regex};
+
+/// Reorder `results` as `options.sort` asks; `None` leaves them alone. Every
+/// order breaks its ties by score, then path and line, so runs are repeatable.
+pub fn sort_results(results: &mut [SearchResult], options: &SearchOptions) {
+    let Some(sort) = options.sort else {
+        return;
+    };
+
+    let by_score = ck_core::compare_by_score;
+
+    match sort {
+        ResultSort::Score => results.sort_by(by_score),
+        ResultSort::Path => results.sort_by(ck_core::compare_by_location),
+        ResultSort::Lines => results.sort_by(|a, b| {
+            span_lines(b)
+                .cmp(&span_lines(a))
+                .then_with(|| by_score(a, b))
+        }),
+        ResultSort::Matches => {
+            let counts = count_matches(results, &match_regex(options));
+            let mut ranked: Vec<(usize, SearchResult)> =
+                counts.into_iter().zip(results.iter().cloned()).collect();
+            ranked.sort_by(|(count_a, a), (count_b, b)| {
+                count_b.cmp(count_a).then_with(|| by_score(a, b))
+            });
+            for (slot, (_, result)) in results.iter_mut().zip(ranked) {
+                *slot = result;
+            }
+        }
+    }
+}
+
+fn span_lines(result: &SearchResult) -> usize {
+    result.span.line_end.saturating_sub(result.span.line_start) + 1
+}
+
+/// What counts as a matching line: the search's own regex for regex and hybrid
+/// searches, and any query word (ignoring case) for lexical and semantic ones
+/// or when the query isn't a valid regex
+fn match_regex(options: &SearchOptions) -> Regex {
+    if matches!(options.mode, SearchMode::Regex | SearchMode::Hybrid)
+        && let Ok(regex) = query_regex(options)
+    {
+        return regex;
+    }
+
+    let words: Vec<String> = options
+        .query
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .map(regex::escape)
+        .collect();
+    let pattern = if words.is_empty() {
+        // Matches nothing
+        r"[^\s\S]".to_string()
+    } else {
+        format!(r"\b(?:{})\b", words.join("|"))
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .expect("escaped words form a valid regex")
+}
+
+/// A file's lines and its function, class and method sections (0-based, inclusive)
+struct FileLines {
+    lines: Vec<String>,
+    sections: Vec<(usize, usize)>,
+}
+
+/// Matching lines within each result's chunk, reading and parsing each file
+/// once. Single-line results (regex matches) count across the innermost
+/// function, class or method around them, as `--full-section` would show it.
+/// Files that can't be read as text (PDFs, deleted files) are counted from the
+/// result's preview instead.
+fn count_matches(results: &[SearchResult], regex: &Regex) -> Vec<usize> {
+    let mut files: HashMap<PathBuf, Option<FileLines>> = HashMap::new();
+
+    results
+        .iter()
+        .map(|result| {
+            let file = files.entry(result.file.clone()).or_insert_with(|| {
+                let content = ck_core::archive::read_to_string(&result.file).ok()?;
+                let sections = extract_code_sections(&result.file, &content)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(start, end, _)| (start, end))
+                    .collect();
+                Some(FileLines {
+                    lines: content.lines().map(str::to_string).collect(),
+                    sections,
+                })
+            });
+            let Some(file) = file else {
+                return count_lines(result.preview.lines(), regex);
+            };
+
+            let mut start = result.span.line_start.saturating_sub(1);
+            let mut end = result.span.line_end.saturating_sub(1);
+            if start == end
+                && let Some(&(section_start, section_end)) = file
+                    .sections
+                    .iter()
+                    .filter(|(section_start, section_end)| {
+                        *section_start <= start && start <= *section_end
+                    })
+                    .min_by_key(|(section_start, section_end)| section_end - section_start)
+            {
+                (start, end) = (section_start, section_end);
+            }
+            let end = (end + 1).min(file.lines.len());
+            let start = start.min(end);
+            count_lines(file.lines[start..end].iter().map(String::as_str), regex)
+        })
+        .collect()
+}
+
+fn count_lines<'a>(lines: impl Iterator<Item = &'a str>, regex: &Regex) -> usize {
+    lines.filter(|line| regex.is_match(line)).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ck_core::Span;
+    use std::path::Path;
+
+    fn result(file: &Path, lines: (usize, usize), score: f32) -> SearchResult {
+        SearchResult {
+            file: file.to_path_buf(),
+            span: Span::new_unchecked(0, 0, lines.0, lines.1),
+            score,
+            ..Default::default()
+        }
+    }
+
+    fn order(results: &[SearchResult]) -> Vec<(usize, usize)> {
+        results
+            .iter()
+            .map(|r| (r.span.line_start, r.span.line_end))
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_results_by_matches_lines_and_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("lib.rs");
+        std::fs::write(
+            &file,
+            "fn a() {\n    retry();\n}\nfn b() {\n    retry();\n    retry();\n    done();\n}\n",
+        )
+        .unwrap();
+
+        let unsorted = vec![
+            result(&file, (1, 3), 0.9),
+            result(&file, (4, 8), 0.4),
+            result(&file, (6, 7), 0.9),
+        ];
+        let mut options = SearchOptions {
+            mode: SearchMode::Regex,
+            query: "retry".to_string(),
+            sort: Some(ResultSort::Matches),
+            ..SearchOptions::default()
+        };
+
+        let mut results = unsorted.clone();
+        sort_results(&mut results, &options);
+        // Two matches beat one; equal counts fall back to score, then line
+        assert_eq!(order(&results), vec![(4, 8), (1, 3), (6, 7)]);
+
+        // Line matches count across the function around them
+        let mut lines = vec![
+            result(&file, (2, 2), 1.0),
+            result(&file, (5, 5), 1.0),
+            result(&file, (6, 6), 1.0),
+        ];
+        sort_results(&mut lines, &options);
+        assert_eq!(order(&lines), vec![(5, 5), (6, 6), (2, 2)]);
+
+        // Semantic queries count lines containing any query word
+        options.mode = SearchMode::Semantic;
+        options.query = "DONE".to_string();
+        let mut results = unsorted.clone();
+        sort_results(&mut results, &options);
+        assert_eq!(order(&results), vec![(6, 7), (4, 8), (1, 3)]);
+
+        options.sort = Some(ResultSort::Lines);
+        let mut results = unsorted.clone();
+        sort_results(&mut results, &options);
+        assert_eq!(order(&results), vec![(4, 8), (1, 3), (6, 7)]);
+
+        options.sort = Some(ResultSort::Path);
+        let mut results = unsorted.clone();
+        sort_results(&mut results, &options);
+        assert_eq!(order(&results), vec![(1, 3), (4, 8), (6, 7)]);
+
+        options.sort = None;
+        let mut results = unsorted.clone();
+        sort_results(&mut results, &options);
+        assert_eq!(order(&results), order(&unsorted));
+    }
+}
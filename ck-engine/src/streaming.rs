@@ -0,0 +1,223 @@
+use anyhow::Result;
+use ck_core::{SearchOptions, SearchResult};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::sync::mpsc;
+
+/// Called with the best results found so far while a semantic search is still
+/// scoring, best first
+pub type PartialResultsCallback = Box<dyn Fn(Vec<SearchResult>) + Send + Sync>;
+
+/// Chunks scored between partial result updates
+pub(crate) const SCORE_BATCH: usize = 4096;
+
+/// Keeps the `capacity` highest-scoring items pushed into it. Among equal
+/// scores the item pushed first wins, matching a stable sort of everything.
+pub struct TopK<T> {
+    capacity: usize,
+    heap: BinaryHeap<Reverse<Ranked<T>>>,
+    pushed: usize,
+}
+
+struct Ranked<T> {
+    score: f32,
+    seq: usize,
+    item: T,
+}
+
+impl<T> Ranked<T> {
+    fn rank(&self, other: &Self) -> Ordering {
+        self.score
+            .partial_cmp(&other.score)
+            .unwrap_or(Ordering::Equal)
+            // Pushed earlier ranks higher
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl<T> PartialEq for Ranked<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Ranked<T> {}
+
+impl<T> PartialOrd for Ranked<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Ranked<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank(other)
+    }
+}
+
+impl<T> TopK<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            heap: BinaryHeap::with_capacity(capacity.min(SCORE_BATCH)),
+            pushed: 0,
+        }
+    }
+
+    /// Offer an item; returns whether it made it into the top `capacity`
+    pub fn push(&mut self, score: f32, item: T) -> bool {
+        let ranked = Ranked {
+            score,
+            seq: self.pushed,
+            item,
+        };
+        self.pushed += 1;
+
+        if self.heap.len() < self.capacity {
+            self.heap.push(Reverse(ranked));
+            return true;
+        }
+        match self.heap.peek() {
+            Some(Reverse(worst)) if ranked > *worst => {
+                self.heap.pop();
+                self.heap.push(Reverse(ranked));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// The kept items, best first, without consuming the heap
+    pub fn sorted(&self) -> Vec<(f32, &T)> {
+        let mut ranked: Vec<&Ranked<T>> = self.heap.iter().map(|Reverse(r)| r).collect();
+        ranked.sort_by(|a, b| b.cmp(a));
+        ranked.into_iter().map(|r| (r.score, &r.item)).collect()
+    }
+
+    /// The kept items, best first
+    pub fn into_sorted_vec(self) -> Vec<(f32, T)> {
+        // Ascending order of `Reverse` is best first
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(r)| (r.score, r.item))
+            .collect()
+    }
+}
+
+/// One step of a [`SearchStream`]
+#[derive(Debug, Clone)]
+// Only one Complete is sent per search, so its size isn't worth a box
+#[allow(clippy::large_enum_variant)]
+pub enum SearchUpdate {
+    /// Best results so far, best first; later updates replace earlier ones
+    Partial(Vec<SearchResult>),
+    /// The finished search, the same as [`crate::search_enhanced`] returns
+    Complete(ck_core::SearchResults),
+}
+
+/// Iterator over a search running on a background thread. Semantic searches
+/// yield [`SearchUpdate::Partial`] as better candidates turn up; every search
+/// ends with one [`SearchUpdate::Complete`] or an error.
+pub struct SearchStream {
+    receiver: mpsc::Receiver<Result<SearchUpdate>>,
+}
+
+impl Iterator for SearchStream {
+    type Item = Result<SearchUpdate>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// Start `options`' search in the background and return its updates as they come
+pub fn search_stream(options: SearchOptions) -> SearchStream {
+    let (sender, receiver) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                let _ = sender.send(Err(e.into()));
+                return;
+            }
+        };
+
+        let partial_sender = sender.clone();
+        let on_partial: PartialResultsCallback = Box::new(move |results| {
+            let _ = partial_sender.send(Ok(SearchUpdate::Partial(results)));
+        });
+        let result = runtime.block_on(crate::search_enhanced_streaming(
+            &options,
+            None,
+            None,
+            None,
+            Some(on_partial),
+        ));
+        let _ = sender.send(result.map(SearchUpdate::Complete));
+    });
+
+    SearchStream { receiver }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_k_keeps_best_in_order() {
+        let mut top = TopK::new(3);
+        for (score, name) in [(0.2, "a"), (0.9, "b"), (0.5, "c"), (0.1, "d"), (0.7, "e")] {
+            top.push(score, name);
+        }
+        assert_eq!(top.len(), 3);
+        let best: Vec<&str> = top.sorted().into_iter().map(|(_, name)| *name).collect();
+        assert_eq!(best, vec!["b", "e", "c"]);
+
+        // Rejected once full and not better than the worst kept
+        assert!(!top.push(0.5, "f"));
+        assert!(top.push(0.6, "g"));
+        let scores: Vec<f32> = top.into_sorted_vec().into_iter().map(|(s, _)| s).collect();
+        assert_eq!(scores, vec![0.9, 0.7, 0.6]);
+    }
+
+    #[test]
+    fn test_top_k_ties_keep_first_pushed() {
+        let mut top = TopK::new(2);
+        for name in ["first", "second", "third"] {
+            top.push(0.5, name);
+        }
+        let kept: Vec<&str> = top.into_sorted_vec().into_iter().map(|(_, n)| n).collect();
+        assert_eq!(kept, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_search_stream_ends_with_the_error() {
+        let options = SearchOptions {
+            path: std::path::PathBuf::from("/definitely/not/a/real/path"),
+            ..SearchOptions::default()
+        };
+        let updates: Vec<_> = search_stream(options).collect();
+        assert_eq!(updates.len(), 1);
+        assert!(updates[0].is_err());
+    }
+
+    #[test]
+    fn test_top_k_with_zero_capacity_keeps_nothing() {
+        let mut top = TopK::new(0);
+        assert!(!top.push(1.0, ()));
+        assert!(top.is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::Result;
+use ck_core::{SearchOptions, SearchResult};
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+
+use super::diff::{chunk_breadcrumb, chunk_passes_filters, is_test_chunk};
+use super::{PathGlobs, filter_files_by_include};
+
+/// Name matches scoring below this are too far from the query to report
+const MIN_STRUCTURAL_SCORE: f32 = 0.3;
+
+/// Match the query against the names and breadcrumbs of structural chunks,
+/// chunking files as they are now rather than reading the index, so nothing
+/// is embedded and no model is loaded. Scores run from 1.0 (the name, ignoring
+/// case) through prefixes and subsequences of the name or its full path down
+/// to names within a few edits of the query. An empty query lists every named
+/// chunk passing the chunk filters, all scored 1.0, in path and line order.
+/// With `options.references` the query is a symbol to find the uses of
+/// instead, in the same files.
+pub(crate) fn structural_search(options: &SearchOptions) -> Result<Vec<SearchResult>> {
+    let query = options.query.trim().to_lowercase();
+    let globs = PathGlobs::new(options)?;
+    let file_options = ck_core::FileCollectionOptions::from(options);
+    let files: Vec<PathBuf> = filter_files_by_include(
+        ck_index::collect_files(&options.path, &file_options)?,
+        &options.include_patterns,
+    )
+    .into_iter()
+    .filter(|file| {
+        globs.matches(file)
+            && ck_core::Language::from_path(file).is_some_and(ck_chunk::has_structural_support)
+    })
+    .collect();
+    if options.references {
+        return Ok(super::references::reference_search(&files, &globs, options));
+    }
+
+    let threshold = if query.is_empty() {
+        0.0
+    } else {
+        options.threshold.unwrap_or(MIN_STRUCTURAL_SCORE)
+    };
+    let mut results: Vec<SearchResult> = files
+        .par_iter()
+        .flat_map_iter(|file| {
+            search_file_symbols(file, &query, &globs, options)
+                .unwrap_or_else(|e| {
+                    tracing::debug!("Skipping {:?}: {}", file, e);
+                    Vec::new()
+                })
+                .into_iter()
+                .filter(|result| result.score >= threshold)
+        })
+        .collect();
+
+    results.sort_by(ck_core::compare_by_score);
+    if let Some(top_k) = options.top_k {
+        results.truncate(top_k);
+    }
+    Ok(results)
+}
+
+fn search_file_symbols(
+    file: &Path,
+    query: &str,
+    globs: &PathGlobs,
+    options: &SearchOptions,
+) -> Result<Vec<SearchResult>> {
+    let content = std::fs::read_to_string(file)?;
+    if ck_chunk::has_ignore_file_marker(&content) {
+        return Ok(Vec::new());
+    }
+    let lang = ck_core::Language::from_path(file);
+    let chunks = ck_chunk::without_ignored(ck_chunk::chunk_text(&content, lang)?);
+
+    Ok(chunks
+        .iter()
+        .filter(|chunk| {
+            chunk_passes_filters(chunk, options) && globs.keeps_chunk(file, is_test_chunk(chunk))
+        })
+        .filter_map(|chunk| {
+            let name = chunk
+                .metadata
+                .name
+                .as_deref()
+                .filter(|name| !name.is_empty())?;
+            let path = match chunk_breadcrumb(chunk) {
+                Some(enclosing) => format!("{}::{}", enclosing, name),
+                None => name.to_string(),
+            };
+            let score = if query.is_empty() {
+                1.0
+            } else {
+                symbol_score(query, name, &path)?
+            };
+            let preview = if options.full_section {
+                chunk.text.clone()
+            } else {
+                chunk.text.lines().take(3).collect::<Vec<_>>().join("\n")
+            };
+            Some(SearchResult {
+                file: file.to_path_buf(),
+                span: chunk.span.clone(),
+                score,
+                preview,
+                lang,
+                symbol: Some(name.to_string()),
+                breadcrumb: Some(path),
+                chunk_hash: None,
+                index_epoch: None,
+                blame: None,
+                match_ranges: Vec::new(),
+            })
+        })
+        .collect())
+}
+
+/// How well `query` (lowercase) names the chunk called `name` at `path`,
+/// `None` when it doesn't resemble it at all
+fn symbol_score(query: &str, name: &str, path: &str) -> Option<f32> {
+    let name = name.to_lowercase();
+    let path = path.to_lowercase();
+    let coverage = |matched: usize, total: usize| matched as f32 / total.max(1) as f32;
+
+    if name == query || path == query {
+        return Some(1.0);
+    }
+    if name.starts_with(query) {
+        return Some(0.85 + 0.1 * coverage(query.len(), name.len()));
+    }
+    if let Some(span) = subsequence_span(query, &name) {
+        // Tightness matters most; among equally tight matches, shorter names win
+        return Some(
+            0.6 + 0.15 * coverage(query.len(), span) + 0.05 * coverage(query.len(), name.len()),
+        );
+    }
+    if let Some(span) = subsequence_span(query, &path) {
+        return Some(0.45 + 0.15 * coverage(query.len(), span));
+    }
+
+    let longest = query.chars().count().max(name.chars().count());
+    let similarity = 1.0 - coverage(edit_distance(query, &name), longest);
+    (similarity > 0.0).then_some(0.45 * similarity)
+}
+
+/// Bytes of `text` covered by the shortest run, starting at the query's
+/// first character, in which the characters of `query` appear in order
+fn subsequence_span(query: &str, text: &str) -> Option<usize> {
+    let first = query.chars().next()?;
+    text.char_indices()
+        .filter(|&(_, c)| c == first)
+        .filter_map(|(start, _)| {
+            let mut wanted = query.chars().peekable();
+            for (offset, c) in text[start..].char_indices() {
+                if wanted.next_if_eq(&c).is_some() && wanted.peek().is_none() {
+                    return Some(offset + c.len_utf8());
+                }
+            }
+            None
+        })
+        .min()
+}
+
+/// Levenshtein distance between `a` and `b`, in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbol_scores_rank_closer_names_higher() {
+        let score = |query: &str, name: &str, path: &str| symbol_score(query, name, path).unwrap();
+
+        let exact = score("parse_config", "parse_config", "Loader::parse_config");
+        let prefix = score("parse", "parse_config", "parse_config");
+        let subsequence = score("pcfg", "parse_config", "parse_config");
+        let in_path = score("loadparse", "parse", "Loader::parse");
+        let typo = score("prase_config", "parse_config", "parse_config");
+        assert_eq!(exact, 1.0);
+        assert!(exact > prefix && prefix > subsequence);
+        assert!(subsequence > in_path && in_path > typo);
+        assert!(typo > MIN_STRUCTURAL_SCORE);
+
+        // A tight subsequence beats a scattered one
+        assert!(
+            score("cfg", "cfg_reader", "cfg_reader") > score("crd", "cfg_reader", "cfg_reader")
+        );
+        assert!(
+            symbol_score("zzzz", "parse_config", "parse_config")
+                .is_none_or(|score| score < MIN_STRUCTURAL_SCORE)
+        );
+    }
+
+    #[test]
+    fn empty_query_lists_filtered_chunks_in_file_order() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("b.rs"),
+            "struct S;\n\nimpl S {\n    fn z() {}\n}\n\nfn a() {}\n",
+        )
+        .unwrap();
+        std::fs::write(temp_dir.path().join("a.rs"), "fn y() {}\n").unwrap();
+
+        let options = SearchOptions {
+            mode: ck_core::SearchMode::Structural,
+            path: temp_dir.path().to_path_buf(),
+            chunk_types: vec!["function".to_string(), "method".to_string()],
+            exclude_patterns: vec![".ck".to_string()],
+            ..Default::default()
+        };
+        let listed: Vec<(String, f32)> = structural_search(&options)
+            .unwrap()
+            .into_iter()
+            .map(|result| (result.breadcrumb.unwrap(), result.score))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("y".to_string(), 1.0),
+                ("S::z".to_string(), 1.0),
+                ("a".to_string(), 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn edit_distance_counts_character_edits() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+}
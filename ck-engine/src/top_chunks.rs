@@ -0,0 +1,205 @@
+use anyhow::Result;
+use ck_core::{CkError, SearchOptions, Span};
+use serde::Serialize;
+use std::path::PathBuf;
+
+use super::semantic_v3::load_indexed_chunks;
+use super::{ensure_index_updated_with_progress, find_nearest_index_root};
+
+/// Number of chunks reported when no limit is given
+pub const DEFAULT_TOP_CHUNKS_LIMIT: usize = 20;
+
+/// What makes a chunk big for [`largest_chunks`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkSizeMeasure {
+    /// Estimated tokens, which is what embedding and context budgets pay for
+    Tokens,
+    /// Lines spanned
+    Lines,
+    /// Bytes of chunk text
+    Bytes,
+}
+
+/// `--by` names, in the same order as [`ChunkSizeMeasure`]
+pub const CHUNK_SIZE_MEASURE_NAMES: &[&str] = &["tokens", "lines", "bytes"];
+
+impl ChunkSizeMeasure {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tokens" => Some(ChunkSizeMeasure::Tokens),
+            "lines" => Some(ChunkSizeMeasure::Lines),
+            "bytes" => Some(ChunkSizeMeasure::Bytes),
+            _ => None,
+        }
+    }
+}
+
+/// An indexed chunk with its sizes
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkSize {
+    pub file: PathBuf,
+    pub span: Span,
+    /// The chunk's enclosing chunks, then its own name
+    pub breadcrumb: Option<String>,
+    pub chunk_type: Option<String>,
+    /// `None` for sidecars written before sizes were kept
+    pub estimated_tokens: Option<usize>,
+    pub byte_length: Option<usize>,
+    pub lines: usize,
+}
+
+impl ChunkSize {
+    /// The chunk's size by `measure`, `None` when the index doesn't know it
+    pub fn size(&self, measure: ChunkSizeMeasure) -> Option<usize> {
+        match measure {
+            ChunkSizeMeasure::Tokens => self.estimated_tokens,
+            ChunkSizeMeasure::Lines => Some(self.lines),
+            ChunkSizeMeasure::Bytes => self.byte_length,
+        }
+    }
+}
+
+/// The `options.top_k` biggest chunks indexed under `options.path` by
+/// `measure`, biggest first. Updates the index first, without computing
+/// embeddings, so sizing a repo costs nothing to embed.
+pub async fn largest_chunks(
+    options: &SearchOptions,
+    measure: ChunkSizeMeasure,
+) -> Result<Vec<ChunkSize>> {
+    if !options.path.exists() {
+        return Err(
+            CkError::Search(format!("Path does not exist: {}", options.path.display())).into(),
+        );
+    }
+
+    let file_options = ck_core::FileCollectionOptions::from(options);
+    ensure_index_updated_with_progress(
+        &options.path,
+        options.reindex,
+        false,
+        None,
+        None,
+        &file_options,
+        options.embedding_model.as_deref(),
+    )
+    .await?;
+
+    let index_root = find_nearest_index_root(&options.path).unwrap_or_else(|| {
+        if options.path.is_file() {
+            options.path.parent().unwrap_or(&options.path).to_path_buf()
+        } else {
+            options.path.clone()
+        }
+    });
+    let target = options
+        .path
+        .canonicalize()
+        .unwrap_or_else(|_| options.path.clone());
+    let globs = super::PathGlobs::new(options)?;
+    let chunks: Vec<ChunkSize> = load_indexed_chunks(
+        &index_root.join(".ck"),
+        &index_root,
+        &options.include_patterns,
+        |_| true,
+    )?
+    .into_iter()
+    .filter(|(file, chunk)| {
+        globs.matches(file)
+            && globs.keeps_chunk(file, super::semantic_v3::is_test_chunk(chunk))
+            && file
+                .canonicalize()
+                .unwrap_or_else(|_| file.clone())
+                .starts_with(&target)
+    })
+    .map(|(file, chunk)| {
+        let breadcrumb = match (chunk.breadcrumb.filter(|c| !c.is_empty()), chunk.name) {
+            (Some(enclosing), Some(name)) => Some(format!("{}::{}", enclosing, name)),
+            (enclosing, name) => name.or(enclosing),
+        };
+        ChunkSize {
+            file,
+            lines: (chunk.span.line_end + 1).saturating_sub(chunk.span.line_start),
+            span: chunk.span,
+            breadcrumb,
+            chunk_type: chunk.chunk_type,
+            estimated_tokens: chunk.estimated_tokens,
+            byte_length: chunk.byte_length,
+        }
+    })
+    .collect();
+
+    Ok(rank_by_size(
+        chunks,
+        measure,
+        options.top_k.unwrap_or(DEFAULT_TOP_CHUNKS_LIMIT),
+    ))
+}
+
+/// Biggest first by `measure`, unknown sizes last, ties in path and line
+/// order; capped at `limit`
+fn rank_by_size(
+    mut chunks: Vec<ChunkSize>,
+    measure: ChunkSizeMeasure,
+    limit: usize,
+) -> Vec<ChunkSize> {
+    chunks.sort_by(|a, b| {
+        b.size(measure)
+            .cmp(&a.size(measure))
+            .then_with(|| a.file.cmp(&b.file))
+            .then_with(|| a.span.line_start.cmp(&b.span.line_start))
+    });
+    chunks.truncate(limit);
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(file: &str, lines: (usize, usize), tokens: Option<usize>, bytes: usize) -> ChunkSize {
+        ChunkSize {
+            file: PathBuf::from(file),
+            span: Span::new_unchecked(0, bytes, lines.0, lines.1),
+            breadcrumb: None,
+            chunk_type: None,
+            estimated_tokens: tokens,
+            byte_length: Some(bytes),
+            lines: lines.1 + 1 - lines.0,
+        }
+    }
+
+    #[test]
+    fn test_rank_by_size_orders_biggest_first() {
+        let chunks = vec![
+            chunk("a.rs", (1, 10), Some(50), 900),
+            chunk("b.rs", (1, 40), Some(300), 400),
+            chunk("c.rs", (1, 5), None, 100),
+            chunk("a.rs", (20, 30), Some(300), 500),
+        ];
+        let order = |measure, limit| -> Vec<(String, usize)> {
+            rank_by_size(chunks.clone(), measure, limit)
+                .into_iter()
+                .map(|c| (c.file.display().to_string(), c.span.line_start))
+                .collect()
+        };
+
+        // Ties fall back to path order, unknown token counts go last
+        assert_eq!(
+            order(ChunkSizeMeasure::Tokens, 10),
+            vec![
+                ("a.rs".to_string(), 20),
+                ("b.rs".to_string(), 1),
+                ("a.rs".to_string(), 1),
+                ("c.rs".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            order(ChunkSizeMeasure::Lines, 1),
+            vec![("b.rs".to_string(), 1)]
+        );
+        assert_eq!(
+            order(ChunkSizeMeasure::Bytes, 2),
+            vec![("a.rs".to_string(), 1), ("a.rs".to_string(), 20)]
+        );
+    }
+}
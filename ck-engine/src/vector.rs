@@ -0,0 +1,147 @@
+use anyhow::Result;
+use ck_core::{ChunkId, CkError, Span};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use super::{find_nearest_index_root, resolve_model_from_root};
+
+/// The stored embedding of one indexed chunk, with the model that produced it
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkVector {
+    pub id: ChunkId,
+    pub file: PathBuf,
+    pub span: Span,
+    pub breadcrumb: Option<String>,
+    pub model: String,
+    pub dimensions: usize,
+    pub embedding: Vec<f32>,
+}
+
+/// The embedding stored for the innermost chunk of `file` containing `line`
+/// (1-based). Reads the existing index without updating it, so the file must
+/// already be indexed with embeddings.
+pub fn chunk_vector(file: &Path, line: usize) -> Result<ChunkVector> {
+    let file = file
+        .canonicalize()
+        .map_err(|e| CkError::Index(format!("Cannot read {}: {}", file.display(), e)))?;
+    let index_root = find_nearest_index_root(&file).ok_or_else(|| {
+        CkError::Index(format!(
+            "No index found for {}. Run 'ck --index' first.",
+            file.display()
+        ))
+    })?;
+
+    let sidecar = ck_core::get_sidecar_path(&index_root, &file);
+    let entry = ck_index::load_index_entry(&sidecar).map_err(|_| {
+        CkError::Index(format!(
+            "{} is not indexed. Run 'ck --index' first.",
+            file.display()
+        ))
+    })?;
+    let chunk = innermost_embedded_chunk(&entry.chunks, line).ok_or_else(|| {
+        CkError::Index(format!(
+            "No embedded chunk covers {}:{}",
+            file.display(),
+            line
+        ))
+    })?;
+    let embedding = chunk.embedding.clone().unwrap_or_default();
+
+    let model = resolve_model_from_root(&index_root, None)?;
+    let relative = file.strip_prefix(&index_root).unwrap_or(&file);
+    let id = chunk
+        .chunk_hash
+        .as_deref()
+        .map(|hash| ck_core::compute_chunk_id(relative, chunk.breadcrumb.as_deref(), hash))
+        .unwrap_or_default();
+
+    Ok(ChunkVector {
+        id,
+        file: relative.to_path_buf(),
+        span: chunk.span.clone(),
+        breadcrumb: chunk.breadcrumb.clone(),
+        model: model.canonical_name,
+        dimensions: embedding.len(),
+        embedding,
+    })
+}
+
+/// A query as semantic search embeds it, with the model that embedded it
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryVector {
+    pub text: String,
+    pub model: String,
+    pub dimensions: usize,
+    pub embedding: Vec<f32>,
+}
+
+/// Embed `text` with the model a search under `path` would use: the index's
+/// when there is one, else `model` or the default. No index is read beyond its
+/// manifest, and none is created.
+pub fn query_vector(path: &Path, model: Option<&str>, text: &str) -> Result<QueryVector> {
+    let index_root = find_nearest_index_root(path).unwrap_or_else(|| path.to_path_buf());
+    let model = resolve_model_from_root(&index_root, model)?;
+    let mut embedder = model.embedder()?;
+    let embedding = embedder
+        .embed(&[text.to_string()])?
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+
+    Ok(QueryVector {
+        text: text.to_string(),
+        model: model.canonical_name,
+        dimensions: embedding.len(),
+        embedding,
+    })
+}
+
+/// The smallest chunk with an embedding whose lines include `line`
+pub(crate) fn innermost_embedded_chunk(
+    chunks: &[ck_index::ChunkEntry],
+    line: usize,
+) -> Option<&ck_index::ChunkEntry> {
+    chunks
+        .iter()
+        .filter(|chunk| chunk.embedding.as_ref().is_some_and(|e| !e.is_empty()))
+        .filter(|chunk| chunk.span.line_start <= line && line <= chunk.span.line_end)
+        .min_by_key(|chunk| chunk.span.line_end - chunk.span.line_start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(lines: (usize, usize), embedding: Option<Vec<f32>>) -> ck_index::ChunkEntry {
+        ck_index::ChunkEntry {
+            span: Span::new_unchecked(0, 0, lines.0, lines.1),
+            embedding,
+            chunk_type: Some("function".to_string()),
+            breadcrumb: None,
+            ancestry: None,
+            breadcrumb_segments: None,
+            name: None,
+            byte_length: None,
+            estimated_tokens: None,
+            leading_trivia: None,
+            trailing_trivia: None,
+            chunk_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_innermost_embedded_chunk_prefers_smallest_span() {
+        let chunks = vec![
+            chunk((1, 20), Some(vec![1.0, 0.0])),
+            chunk((5, 10), Some(vec![0.0, 1.0])),
+            // Innermost, but never embedded
+            chunk((6, 7), None),
+        ];
+
+        let found = innermost_embedded_chunk(&chunks, 6).unwrap();
+        assert_eq!((found.span.line_start, found.span.line_end), (5, 10));
+        let found = innermost_embedded_chunk(&chunks, 15).unwrap();
+        assert_eq!((found.span.line_start, found.span.line_end), (1, 20));
+        assert!(innermost_embedded_chunk(&chunks, 21).is_none());
+    }
+}
@@ -9,12 +9,20 @@ use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Once;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once, mpsc};
+use std::thread;
 use std::time::SystemTime;
 use tempfile::NamedTempFile;
 use walkdir::WalkDir;
 
+mod storage;
+
+pub use storage::{
+    IndexStorage, LocalStorage, ObjectStorage, ObjectStorageConfig, PullStats, PushStats,
+    open_storage, pull_index, push_index,
+};
+
 pub type ProgressCallback = Box<dyn Fn(&str) + Send + Sync>;
 
 /// Detailed progress information for embedding operations
@@ -75,6 +83,9 @@ static HANDLER_INIT: Once = Once::new();
 
 pub const INDEX_INTERRUPTED_MSG: &str = "Indexing interrupted by user";
 
+/// How many files are read and chunked in parallel ahead of the embedder
+const PREPARE_WINDOW: usize = 64;
+
 pub fn request_interrupt() {
     INTERRUPTED.store(true, Ordering::SeqCst);
 }
@@ -112,6 +123,12 @@ pub struct ChunkEntry {
     pub breadcrumb: Option<String>,
     #[serde(default)]
     pub ancestry: Option<Vec<String>>,
+    /// `ancestry` with the span of each enclosing chunk
+    #[serde(default)]
+    pub breadcrumb_segments: Option<Vec<ck_chunk::BreadcrumbSegment>>,
+    /// The chunk's own name; sidecars written before names were kept have none
+    #[serde(default)]
+    pub name: Option<String>,
     #[serde(default)]
     pub byte_length: Option<usize>,
     #[serde(default)]
@@ -140,6 +157,29 @@ pub struct IndexManifest {
     /// - v2 = blake3 of chunk text + leading_trivia + trailing_trivia
     #[serde(default)]
     pub chunk_hash_version: Option<u32>,
+    /// Whether each chunk's embedding input starts with a comment naming its
+    /// file and breadcrumb (the stored chunk text never does)
+    #[serde(default)]
+    pub context_prefix: bool,
+    /// Whether big string literals and heredocs get chunks of their own (see
+    /// [`ck_chunk::ChunkConfig::string_chunks`])
+    #[serde(default)]
+    pub string_chunks: bool,
+    /// The line windows files without structural chunking are cut into
+    #[serde(default)]
+    pub text_window: ck_chunk::TextWindow,
+    /// Whether the embeddings came from [`import_embeddings`] rather than ck,
+    /// in which case incremental updates leave the index alone
+    #[serde(default)]
+    pub imported: bool,
+    /// The metric the model's embeddings are meant to be compared with;
+    /// `None` means cosine
+    #[serde(default)]
+    pub similarity_metric: Option<ck_core::SimilarityMetric>,
+    /// The leading dimensions every embedding, and every query, is truncated
+    /// to for a matryoshka model; `None` keeps all of the model's
+    #[serde(default)]
+    pub truncated_dims: Option<usize>,
 }
 
 impl Default for IndexManifest {
@@ -157,6 +197,12 @@ impl Default for IndexManifest {
             embedding_model: None, // Default to None for backward compatibility
             embedding_dimensions: None,
             chunk_hash_version: Some(2), // v2 = blake3 of chunk text + trivia
+            context_prefix: false,
+            string_chunks: false,
+            text_window: ck_chunk::TextWindow::default(),
+            imported: false,
+            similarity_metric: None,
+            truncated_dims: None,
         }
     }
 }
@@ -169,19 +215,95 @@ fn should_include_file(entry: &ignore::DirEntry, index_dir: &Path) -> bool {
         && !path.starts_with(index_dir)
 }
 
-/// Apply common filtering to a WalkBuilder iterator
-fn filter_and_collect_files(walker: ignore::Walk, index_dir: &Path) -> Vec<PathBuf> {
-    walker
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| should_include_file(entry, index_dir))
-        .map(|entry| entry.path().to_path_buf())
-        .collect()
+/// Files found by walking a directory tree
+#[derive(Debug, Clone, Default)]
+pub struct CollectedFiles {
+    pub files: Vec<PathBuf>,
+    /// Symlinked directories left out because symlinks were not followed
+    pub symlinked_dirs_skipped: usize,
+}
+
+/// Walk with the common filtering, applying the symlink policy. When following
+/// links, each real directory is entered once, so links back to an ancestor (or
+/// a shared directory linked from several places) neither loop nor index the same
+/// files twice.
+fn filter_and_collect_files(
+    mut walker_builder: WalkBuilder,
+    index_dir: &Path,
+    root: &Path,
+    follow_symlinks: bool,
+    archives: bool,
+) -> CollectedFiles {
+    if follow_symlinks {
+        let mut visited = HashSet::new();
+        if let Ok(real_root) = root.canonicalize() {
+            visited.insert(real_root);
+        }
+        let visited = Arc::new(Mutex::new(visited));
+        walker_builder
+            .follow_links(true)
+            .filter_entry(move |entry| {
+                if !entry.file_type().is_some_and(|ft| ft.is_dir()) {
+                    return true;
+                }
+                match entry.path().canonicalize() {
+                    Ok(real) => visited
+                        .lock()
+                        .map(|mut visited| visited.insert(real))
+                        .unwrap_or(true),
+                    Err(_) => false,
+                }
+            });
+    }
+
+    let mut collected = CollectedFiles::default();
+    let mut seen_files = HashSet::new();
+    for entry in walker_builder.build().filter_map(|entry| entry.ok()) {
+        if !follow_symlinks && entry.path_is_symlink() && entry.path().is_dir() {
+            collected.symlinked_dirs_skipped += 1;
+            continue;
+        }
+        if archives
+            && entry.file_type().is_some_and(|ft| ft.is_file())
+            && ck_core::archive::is_archive_file(entry.path())
+        {
+            match ck_core::archive::list_text_entries(entry.path()) {
+                Ok(names) => collected.files.extend(
+                    names
+                        .iter()
+                        .map(|name| ck_core::archive::virtual_path(entry.path(), name)),
+                ),
+                Err(e) => tracing::warn!("Skipping archive {:?}: {}", entry.path(), e),
+            }
+            continue;
+        }
+        if !should_include_file(&entry, index_dir) {
+            continue;
+        }
+        // A file linked from several places is indexed under the first path found
+        if follow_symlinks
+            && let Ok(real) = entry.path().canonicalize()
+            && !seen_files.insert(real)
+        {
+            continue;
+        }
+        collected.files.push(entry.path().to_path_buf());
+    }
+    collected
 }
 
 pub fn collect_files(
     path: &Path,
     options: &ck_core::FileCollectionOptions,
 ) -> Result<Vec<PathBuf>> {
+    Ok(collect_files_with_stats(path, options)?.files)
+}
+
+/// Like [`collect_files`], also reporting what the walk skipped
+pub fn collect_files_with_stats(
+    path: &Path,
+    options: &ck_core::FileCollectionOptions,
+) -> Result<CollectedFiles> {
     let index_dir = path.join(".ck");
 
     if options.respect_gitignore {
@@ -199,9 +321,14 @@ pub fn collect_files(
         }
 
         walker_builder.overrides(overrides);
-        let walker = walker_builder.build();
 
-        Ok(filter_and_collect_files(walker, &index_dir))
+        Ok(filter_and_collect_files(
+            walker_builder,
+            &index_dir,
+            path,
+            options.follow_symlinks,
+            options.archives,
+        ))
     } else {
         // Use WalkBuilder without gitignore support, but still apply overrides
         use ck_core::get_default_exclude_patterns;
@@ -221,9 +348,14 @@ pub fn collect_files(
         }
 
         walker_builder.overrides(combined_overrides);
-        let walker = walker_builder.build();
 
-        Ok(filter_and_collect_files(walker, &index_dir))
+        Ok(filter_and_collect_files(
+            walker_builder,
+            &index_dir,
+            path,
+            options.follow_symlinks,
+            options.archives,
+        ))
     }
 }
 
@@ -257,11 +389,11 @@ pub async fn index_directory(
         let model_registry = ck_models::ModelRegistry::default();
         let selected_model = if let Some(model_name) = model {
             // User specified a model
-            if let Some(model_config) = model_registry.get_model(model_name) {
-                model_config.name.clone()
+            if let Some((_, model_config)) = model_registry.resolve(model_name) {
+                model_config.name
             } else {
                 return Err(anyhow::anyhow!(
-                    "Unknown model '{}'. Available models: bge-small, nomic-v1.5, jina-code",
+                    "Unknown model '{}'. Available models: bge-small, nomic-v1.5, jina-code, or openai:<model> for a remote endpoint",
                     model_name
                 ));
             }
@@ -290,7 +422,7 @@ pub async fn index_directory(
         // Set the model info in the manifest
         manifest.embedding_model = Some(selected_model.clone());
         if let Some(model_name) = model {
-            if let Some(model_config) = model_registry.get_model(model_name) {
+            if let Some((_, model_config)) = model_registry.resolve(model_name) {
                 manifest.embedding_dimensions = Some(model_config.dimensions);
             }
         } else if let Some(default_config) = model_registry.get_default_model() {
@@ -305,13 +437,28 @@ pub async fn index_directory(
     let files = collect_files(path, options)?;
 
     if compute_embeddings {
-        // Sequential processing with small-batch embeddings for streaming performance
+        // Files are read and chunked in parallel; embedding stays on this thread
+        // (it may be GPU-bound and isn't thread-safe) with small per-file batches
         tracing::info!("Creating embedder for {} files", files.len());
-        let mut embedder = ck_embed::create_embedder(resolved_model.as_deref())?;
+        let mut embedder = ck_embed::with_dims(
+            ck_embed::cached_embedder(resolved_model.as_deref())?,
+            manifest.truncated_dims,
+        );
+        // Remote models may only learn their dimensions from the endpoint
+        manifest.embedding_dimensions = Some(embedder.dim());
+        let (prepared_files, worker) = prepare_files_in_order(
+            files,
+            path.to_path_buf(),
+            Some(embedder.model_name().to_string()),
+            ChunkSettings::of(&manifest),
+        );
 
-        for file_path in files.iter() {
-            match index_single_file(file_path, path, Some(&mut embedder)) {
-                Ok(entry) => {
+        for (file_path, prepared) in prepared_files.iter() {
+            let file_path = &file_path;
+            match prepared.and_then(|prepared| {
+                embed_prepared_file(file_path, prepared, Some(&mut embedder), None, 0, 1)
+            }) {
+                Ok((entry, _chunks_reused, _chunks_embedded)) => {
                     // Write sidecar immediately
                     let sidecar_path = get_sidecar_path(path, file_path);
                     save_index_entry(&sidecar_path, &entry)?;
@@ -338,19 +485,21 @@ pub async fn index_directory(
                 }
             }
         }
+
+        worker
+            .join()
+            .map_err(|_| anyhow::anyhow!("Worker thread panicked"))?;
     } else {
         // Parallel processing with streaming using producer-consumer pattern
-        use std::sync::mpsc;
-        use std::thread;
-
         let (tx, rx) = mpsc::channel();
         let files_clone = files.clone();
         let path_clone = path.to_path_buf();
+        let settings = ChunkSettings::of(&manifest);
 
         // Spawn worker thread for parallel processing
         let worker_handle = thread::spawn(move || {
             files_clone.par_iter().for_each(|file_path| {
-                match index_single_file(file_path, &path_clone, None) {
+                match index_single_file(file_path, &path_clone, None, settings) {
                     Ok(entry) => {
                         if tx.send((file_path.clone(), entry)).is_err() {
                             // Receiver dropped, stop processing
@@ -414,14 +563,28 @@ pub async fn index_file(file_path: &Path, compute_embeddings: bool) -> Result<()
 
     let manifest_path = index_dir.join("manifest.json");
     let mut manifest = load_or_create_manifest(&manifest_path)?;
+    if manifest.imported {
+        anyhow::bail!(
+            "The index at {} holds imported embeddings, which ck can't add to. Import them again, or run 'ck --reindex' to replace them with ck's own",
+            repo_root.display()
+        );
+    }
 
     let entry = if compute_embeddings {
         // Use the model from the existing index, or default if none specified
         let model_name = manifest.embedding_model.as_deref();
-        let mut embedder = ck_embed::create_embedder(model_name)?;
-        index_single_file(file_path, &repo_root, Some(&mut embedder))?
+        let mut embedder = ck_embed::with_dims(
+            ck_embed::cached_embedder(model_name)?,
+            manifest.truncated_dims,
+        );
+        index_single_file(
+            file_path,
+            &repo_root,
+            Some(&mut embedder),
+            ChunkSettings::of(&manifest),
+        )?
     } else {
-        index_single_file(file_path, &repo_root, None)?
+        index_single_file(file_path, &repo_root, None, ChunkSettings::of(&manifest))?
     };
     let sidecar_path = get_sidecar_path(&repo_root, file_path);
 
@@ -460,45 +623,62 @@ pub async fn update_index(
     let files = collect_files(path, options)?;
 
     let updates: Vec<(PathBuf, IndexEntry)> = if compute_embeddings {
-        // Sequential processing when computing embeddings (for memory efficiency)
+        // Changed files are chunked in parallel; embedding stays sequential
         let model_name = manifest.embedding_model.as_deref();
-        let mut embedder = ck_embed::create_embedder(model_name)?;
-        files
-            .iter()
-            .filter_map(|file_path| {
+        let mut embedder = ck_embed::with_dims(
+            ck_embed::cached_embedder(model_name)?,
+            manifest.truncated_dims,
+        );
+        let changed: Vec<PathBuf> = files
+            .par_iter()
+            .filter(|file_path| {
                 let manifest_key =
                     path_utils::to_manifest_path(&path_utils::to_standard_path(file_path, path));
 
-                let needs_update = match manifest.files.get(&manifest_key) {
+                match manifest.files.get(&manifest_key) {
                     Some(metadata) => match compute_file_hash(file_path) {
                         Ok(hash) => hash != metadata.hash,
                         Err(_) => false,
                     },
                     None => true,
-                };
-                if needs_update {
-                    match index_single_file(file_path, path, Some(&mut embedder)) {
-                        Ok(entry) => Some((file_path.clone(), entry)),
-                        Err(e) => {
-                            // Suppress warnings for binary files and UTF-8 errors in .git directories
-                            let error_msg = e.to_string();
-                            let is_binary_skip = error_msg.contains("Binary file, skipping");
-                            let is_utf8_error =
-                                error_msg.contains("stream did not contain valid UTF-8");
-                            let is_git_file =
-                                file_path.components().any(|c| c.as_os_str() == ".git");
+                }
+            })
+            .cloned()
+            .collect();
+        let (prepared_files, worker) = prepare_files_in_order(
+            changed,
+            path.to_path_buf(),
+            Some(embedder.model_name().to_string()),
+            ChunkSettings::of(&manifest),
+        );
 
-                            if !(is_binary_skip || is_utf8_error && is_git_file) {
-                                tracing::warn!("Failed to index {:?}: {}", file_path, e);
-                            }
-                            None
+        let updates = prepared_files
+            .iter()
+            .filter_map(|(file_path, prepared)| {
+                match prepared.and_then(|prepared| {
+                    embed_prepared_file(&file_path, prepared, Some(&mut embedder), None, 0, 1)
+                }) {
+                    Ok((entry, _chunks_reused, _chunks_embedded)) => Some((file_path, entry)),
+                    Err(e) => {
+                        // Suppress warnings for binary files and UTF-8 errors in .git directories
+                        let error_msg = e.to_string();
+                        let is_binary_skip = error_msg.contains("Binary file, skipping");
+                        let is_utf8_error =
+                            error_msg.contains("stream did not contain valid UTF-8");
+                        let is_git_file = file_path.components().any(|c| c.as_os_str() == ".git");
+
+                        if !(is_binary_skip || is_utf8_error && is_git_file) {
+                            tracing::warn!("Failed to index {:?}: {}", file_path, e);
                         }
+                        None
                     }
-                } else {
-                    None
                 }
             })
-            .collect()
+            .collect();
+        worker
+            .join()
+            .map_err(|_| anyhow::anyhow!("Worker thread panicked"))?;
+        updates
     } else {
         // Parallel processing when not computing embeddings
         files
@@ -516,7 +696,7 @@ pub async fn update_index(
                 };
 
                 if needs_update {
-                    match index_single_file(file_path, path, None) {
+                    match index_single_file(file_path, path, None, ChunkSettings::of(&manifest)) {
                         Ok(entry) => Some((file_path.clone(), entry)),
                         Err(e) => {
                             // Suppress warnings for binary files and UTF-8 errors in .git directories
@@ -558,6 +738,252 @@ pub async fn update_index(
     Ok(())
 }
 
+/// Turn context prefixes on or off for the index at `path`, creating its
+/// manifest if needed. Existing embeddings were computed from different input,
+/// so changing the setting on a non-empty index removes it (keeping its model)
+/// for the next index run to rebuild; returns whether that happened.
+pub fn set_context_prefix(path: &Path, enabled: bool) -> Result<bool> {
+    change_chunk_setting(path, enabled, |manifest| &mut manifest.context_prefix)
+}
+
+/// Truncate the embeddings of the index at `path` to their first `dims`
+/// dimensions, or keep them whole with `None`, rebuilding like
+/// [`set_context_prefix`] when that changes. Whether the model supports it is
+/// for the caller to check against `ck_models::ModelConfig::matryoshka_dims`.
+pub fn set_truncated_dims(path: &Path, dims: Option<usize>) -> Result<bool> {
+    change_chunk_setting(path, dims, |manifest| &mut manifest.truncated_dims)
+}
+
+/// Turn string literal chunks on or off for the index at `path`, rebuilding
+/// like [`set_context_prefix`] since every file's chunks change
+pub fn set_string_chunks(path: &Path, enabled: bool) -> Result<bool> {
+    change_chunk_setting(path, enabled, |manifest| &mut manifest.string_chunks)
+}
+
+/// Set the line windows unparsed files are cut into for the index at `path`,
+/// rebuilding like [`set_context_prefix`] when they change
+pub fn set_text_window(path: &Path, window: ck_chunk::TextWindow) -> Result<bool> {
+    change_chunk_setting(path, window, |manifest| &mut manifest.text_window)
+}
+
+fn change_chunk_setting<T: PartialEq>(
+    path: &Path,
+    value: T,
+    setting: fn(&mut IndexManifest) -> &mut T,
+) -> Result<bool> {
+    let manifest_path = path.join(".ck").join("manifest.json");
+    let mut manifest = load_or_create_manifest(&manifest_path)?;
+    if *setting(&mut manifest) == value {
+        return Ok(false);
+    }
+
+    let rebuild = !manifest.files.is_empty();
+    if rebuild {
+        clean_index(path)?;
+        manifest = IndexManifest {
+            embedding_model: manifest.embedding_model,
+            embedding_dimensions: manifest.embedding_dimensions,
+            similarity_metric: manifest.similarity_metric,
+            context_prefix: manifest.context_prefix,
+            string_chunks: manifest.string_chunks,
+            text_window: manifest.text_window,
+            truncated_dims: manifest.truncated_dims,
+            ..IndexManifest::default()
+        };
+    }
+    *setting(&mut manifest) = value;
+    save_manifest(&manifest_path, &manifest)?;
+    Ok(rebuild)
+}
+
+/// One chunk of an embeddings file for [`import_embeddings`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportedChunk {
+    /// The chunk's file, relative to the index root or absolute inside it
+    pub path: PathBuf,
+    pub line_start: usize,
+    pub line_end: usize,
+    #[serde(default)]
+    pub chunk_type: Option<String>,
+    /// The chunk's `::`-separated path, ending with its own name
+    #[serde(default)]
+    pub breadcrumb: Option<String>,
+    pub vector: Vec<f32>,
+}
+
+/// Replace the index at `path` with externally computed embeddings, one JSON
+/// [`ImportedChunk`] per line of `records`, without embedding anything. `model`
+/// is recorded so queries are embedded the same way; every vector must have
+/// the same dimensions, matching the model's when the registry knows them.
+pub fn import_embeddings(
+    path: &Path,
+    records: impl std::io::BufRead,
+    model: &str,
+) -> Result<ImportStats> {
+    let (_, model_config) = ck_models::ModelRegistry::default()
+        .resolve(model)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown model '{}'. Queries need the model that produced the embeddings: bge-small, nomic-v1.5, jina-code, or openai:<model> for a remote endpoint",
+                model
+            )
+        })?;
+    let root = path.canonicalize()?;
+
+    let mut dimensions = (model_config.dimensions > 0).then_some(model_config.dimensions);
+    let mut by_file: std::collections::BTreeMap<PathBuf, Vec<ImportedChunk>> =
+        std::collections::BTreeMap::new();
+    for (index, line) in records.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: ImportedChunk = serde_json::from_str(&line)
+            .map_err(|e| anyhow::anyhow!("line {}: {}", line_number, e))?;
+        if record.line_start == 0 || record.line_end < record.line_start {
+            anyhow::bail!(
+                "line {}: invalid line range {}-{}",
+                line_number,
+                record.line_start,
+                record.line_end
+            );
+        }
+        let expected = *dimensions.get_or_insert(record.vector.len());
+        if record.vector.is_empty() || record.vector.len() != expected {
+            anyhow::bail!(
+                "line {}: vector has {} dimensions, expected {} for model '{}'",
+                line_number,
+                record.vector.len(),
+                expected,
+                model_config.name
+            );
+        }
+        let file = root.join(&record.path).canonicalize().map_err(|e| {
+            anyhow::anyhow!("line {}: {}: {}", line_number, record.path.display(), e)
+        })?;
+        if !file.starts_with(&root) {
+            anyhow::bail!(
+                "line {}: {} is outside {}",
+                line_number,
+                record.path.display(),
+                root.display()
+            );
+        }
+        by_file.entry(file).or_default().push(record);
+    }
+    let Some(dimensions) = dimensions.filter(|_| !by_file.is_empty()) else {
+        anyhow::bail!("No embeddings to import were found");
+    };
+
+    clean_index(&root)?;
+    let index_dir = root.join(".ck");
+    fs::create_dir_all(&index_dir)?;
+    let mut manifest = IndexManifest {
+        embedding_model: Some(model_config.name),
+        embedding_dimensions: Some(dimensions),
+        imported: true,
+        ..IndexManifest::default()
+    };
+
+    let mut stats = ImportStats {
+        dimensions,
+        ..ImportStats::default()
+    };
+    for (file, records) in by_file {
+        let content = fs::read_to_string(&file)?;
+        let mut line_bounds = Vec::new();
+        let mut start = 0;
+        for line in content.split_inclusive('\n') {
+            let text = line.trim_end_matches('\n').trim_end_matches('\r');
+            line_bounds.push((start, start + text.len()));
+            start += line.len();
+        }
+
+        let mut chunks = Vec::with_capacity(records.len());
+        for record in records {
+            if record.line_end > line_bounds.len() {
+                anyhow::bail!(
+                    "{}:{}-{} is past the end of the file ({} lines)",
+                    record.path.display(),
+                    record.line_start,
+                    record.line_end,
+                    line_bounds.len()
+                );
+            }
+            let byte_start = line_bounds[record.line_start - 1].0;
+            let byte_end = line_bounds[record.line_end - 1].1;
+            let text = &content[byte_start..byte_end];
+            let mut ancestry: Vec<String> = record
+                .breadcrumb
+                .as_deref()
+                .unwrap_or_default()
+                .split("::")
+                .filter(|segment| !segment.is_empty())
+                .map(str::to_string)
+                .collect();
+            let name = ancestry.pop();
+            chunks.push(ChunkEntry {
+                span: Span {
+                    byte_start,
+                    byte_end,
+                    line_start: record.line_start,
+                    line_end: record.line_end,
+                },
+                embedding: Some(record.vector),
+                chunk_type: record.chunk_type,
+                breadcrumb: (!ancestry.is_empty()).then(|| ancestry.join("::")),
+                ancestry: Some(ancestry),
+                breadcrumb_segments: None,
+                name,
+                byte_length: Some(text.len()),
+                estimated_tokens: None,
+                leading_trivia: None,
+                trailing_trivia: None,
+                chunk_hash: Some(compute_chunk_hash(text, &[], &[])),
+            });
+        }
+
+        let metadata = ck_core::archive::source_metadata(&file)?;
+        let manifest_path =
+            path_utils::to_manifest_path(&path_utils::to_standard_path(&file, &root));
+        let entry = IndexEntry {
+            metadata: FileMetadata {
+                path: manifest_path.clone(),
+                hash: compute_file_hash(&file)?,
+                last_modified: metadata
+                    .modified()?
+                    .duration_since(SystemTime::UNIX_EPOCH)?
+                    .as_secs(),
+                size: metadata.len(),
+            },
+            chunks,
+        };
+        stats.files += 1;
+        stats.chunks += entry.chunks.len();
+        save_index_entry(&get_sidecar_path(&root, &file), &entry)?;
+        manifest.files.insert(manifest_path, entry.metadata);
+    }
+
+    save_manifest(&index_dir.join("manifest.json"), &manifest)?;
+    Ok(stats)
+}
+
+/// Record the similarity metric for the index at `path`, creating its manifest
+/// if needed. Stored embeddings stay valid; searches score with it by default.
+pub fn set_similarity_metric(path: &Path, metric: ck_core::SimilarityMetric) -> Result<()> {
+    let manifest_path = path.join(".ck").join("manifest.json");
+    let mut manifest = load_or_create_manifest(&manifest_path)?;
+    manifest.similarity_metric = Some(metric);
+    save_manifest(&manifest_path, &manifest)
+}
+
+/// Whether the index at `path` was built by [`import_embeddings`]
+pub fn is_imported_index(path: &Path) -> bool {
+    load_or_create_manifest(&path.join(".ck").join("manifest.json"))
+        .is_ok_and(|manifest| manifest.imported)
+}
+
 pub fn clean_index(path: &Path) -> Result<()> {
     let index_dir = path.join(".ck");
     if index_dir.exists() {
@@ -600,6 +1026,345 @@ pub fn cleanup_index(
     Ok(stats)
 }
 
+/// Drop the index entries of source files that no longer exist, with their
+/// sidecars and content caches, so searches stop returning deleted files.
+/// Unlike [`cleanup_index`] the repository isn't walked and exclusions don't
+/// matter: only the paths the index references are checked. The lexical index,
+/// built once from the files of the day, is removed when anything is pruned
+/// and rebuilt by the next lexical search. With `dry_run` nothing changes and
+/// the stats say what would have been removed.
+pub fn prune_index(path: &Path, dry_run: bool) -> Result<PruneStats> {
+    let index_dir = path.join(".ck");
+    if !index_dir.is_dir() {
+        anyhow::bail!("No index found at {}", path.display());
+    }
+    let manifest_path = index_dir.join("manifest.json");
+    let mut manifest = load_or_create_manifest(&manifest_path)?;
+    normalize_manifest_paths(&mut manifest, path);
+
+    let missing = |standard_path: &Path| {
+        ck_core::archive::source_metadata(&path.join(standard_path))
+            .is_err_and(|err| err.kind() == std::io::ErrorKind::NotFound)
+    };
+    let mut stats = PruneStats::default();
+    let mut cleanup = CleanupStats::default();
+
+    let manifest_entries: Vec<PathBuf> = manifest.files.keys().cloned().collect();
+    for manifest_path in manifest_entries {
+        let standard_path = path_utils::from_manifest_path(&manifest_path);
+        if !missing(&standard_path) {
+            continue;
+        }
+        let sidecar_path =
+            path_utils::get_sidecar_path_for_standard_path(&index_dir, &standard_path);
+        stats.chunks_removed +=
+            load_index_entry(&sidecar_path).map_or(0, |entry| entry.chunks.len());
+        if dry_run {
+            stats.sidecars_removed += usize::from(sidecar_path.exists());
+        } else {
+            cleanup_validation::remove_manifest_entry(
+                &mut manifest,
+                &manifest_path,
+                path,
+                &index_dir,
+                &mut cleanup,
+            )?;
+        }
+        stats.pruned_files.push(standard_path);
+    }
+
+    // Sidecars the manifest lost track of are still read by semantic search
+    for entry in WalkDir::new(&index_dir).into_iter().flatten() {
+        let sidecar_path = entry.path();
+        if !entry.file_type().is_file()
+            || sidecar_path.extension().and_then(|ext| ext.to_str()) != Some("ck")
+        {
+            continue;
+        }
+        let Some(standard_path) = path_utils::sidecar_to_standard_path(sidecar_path, &index_dir)
+        else {
+            continue;
+        };
+        if stats.pruned_files.contains(&standard_path)
+            || manifest
+                .files
+                .contains_key(&path_utils::to_manifest_path(&standard_path))
+            || !missing(&standard_path)
+        {
+            continue;
+        }
+        stats.chunks_removed +=
+            load_index_entry(sidecar_path).map_or(0, |entry| entry.chunks.len());
+        if dry_run {
+            stats.sidecars_removed += 1;
+        } else {
+            fs::remove_file(sidecar_path)?;
+            cleanup.orphaned_sidecars_removed += 1;
+        }
+        stats.pruned_files.push(standard_path);
+    }
+    stats.pruned_files.sort();
+
+    if dry_run || stats.pruned_files.is_empty() {
+        return Ok(stats);
+    }
+    stats.sidecars_removed = cleanup.orphaned_sidecars_removed;
+    let lexical_index = index_dir.join("tantivy_index");
+    if lexical_index.is_dir() {
+        fs::remove_dir_all(&lexical_index)?;
+        stats.lexical_index_removed = true;
+    }
+    remove_empty_dirs(&index_dir)?;
+    manifest.updated = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    save_manifest(&manifest_path, &manifest)?;
+    Ok(stats)
+}
+
+/// Validate the index under `path`: the manifest parses, every manifest entry
+/// has a readable sidecar and every sidecar an entry, every file's chunks are
+/// all embedded, and every embedding has the dimensions of the index's model.
+///
+/// Without `fix` nothing on disk changes. With `fix`, sidecars that can't be
+/// trusted are dropped along with their manifest entries (the next `--index`
+/// re-embeds those files), a lost manifest is rebuilt from the sidecars, and
+/// derived files (an incomplete lexical index, temp files left by an
+/// interrupted write) are removed so they get rebuilt.
+pub fn check_index(path: &Path, fix: bool) -> Result<IndexCheck> {
+    let index_dir = path.join(".ck");
+    if !index_dir.is_dir() {
+        anyhow::bail!("No index found at {}", path.display());
+    }
+    let manifest_path = index_dir.join("manifest.json");
+    let mut check = IndexCheck::default();
+
+    let loaded = if manifest_path.exists() {
+        fs::read(&manifest_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Ok(serde_json::from_slice::<IndexManifest>(&data)?))
+            .map_err(|err| format!("manifest.json is unreadable: {}", err))
+    } else {
+        Err("manifest.json is missing".to_string())
+    };
+    let manifest_lost = loaded.is_err();
+    let mut manifest = match loaded {
+        Ok(mut manifest) => {
+            normalize_manifest_paths(&mut manifest, path);
+            manifest
+        }
+        Err(message) => {
+            check.report(None, message, fix);
+            IndexManifest::default()
+        }
+    };
+
+    // Every sidecar, readable or not
+    let mut sidecars = std::collections::BTreeMap::new();
+    for entry in WalkDir::new(&index_dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+    {
+        let sidecar = entry.path();
+        if !entry.file_type().is_file()
+            || sidecar.extension().and_then(|s| s.to_str()) != Some("ck")
+        {
+            continue;
+        }
+        if let Some(standard_path) = path_utils::sidecar_to_standard_path(sidecar, &index_dir) {
+            sidecars.insert(
+                standard_path,
+                (sidecar.to_path_buf(), load_index_entry(sidecar)),
+            );
+        }
+    }
+
+    let model = manifest
+        .embedding_model
+        .as_deref()
+        .map(|name| (name, ck_models::ModelRegistry::default().resolve(name)));
+    if let Some((name, None)) = model
+        && !manifest.imported
+    {
+        check.report(
+            None,
+            format!(
+                "model '{}' is not a known embedding model; searches can't embed queries",
+                name
+            ),
+            false,
+        );
+    }
+    let model_dims = model
+        .and_then(|(_, resolved)| resolved)
+        .map(|(_, config)| manifest.truncated_dims.unwrap_or(config.dimensions))
+        .filter(|&dims| dims > 0);
+    // The model's dimensions, else the recorded ones, else the most common
+    let expected_dims = model_dims.or(manifest.embedding_dimensions).or_else(|| {
+        let mut counts: HashMap<usize, usize> = HashMap::new();
+        for (_, entry) in sidecars.values() {
+            for embedding in entry
+                .iter()
+                .flat_map(|entry| &entry.chunks)
+                .flat_map(|c| &c.embedding)
+            {
+                *counts.entry(embedding.len()).or_default() += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .max_by_key(|&(dims, count)| (count, dims))
+            .map(|(dims, _)| dims)
+    });
+    let mut changed = manifest_lost;
+    if let (Some(recorded), Some(expected)) = (manifest.embedding_dimensions, expected_dims)
+        && recorded != expected
+    {
+        check.report(
+            None,
+            format!(
+                "manifest records {} dimensions but the model produces {}",
+                recorded, expected
+            ),
+            fix,
+        );
+        manifest.embedding_dimensions = Some(expected);
+        changed = true;
+    }
+    let index_has_embeddings = sidecars.values().any(|(_, entry)| {
+        entry
+            .as_ref()
+            .is_ok_and(|entry| entry.chunks.iter().any(|chunk| chunk.embedding.is_some()))
+    });
+
+    for (standard_path, (sidecar, entry)) in &sidecars {
+        let manifest_key = path_utils::to_manifest_path(standard_path);
+        let file = Some(standard_path.as_path());
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                check.report(file, format!("sidecar is unreadable ({})", err), fix);
+                if fix {
+                    fs::remove_file(sidecar)?;
+                    manifest.files.remove(&manifest_key);
+                    changed = true;
+                }
+                continue;
+            }
+        };
+        check.files_checked += 1;
+        check.chunks_checked += entry.chunks.len();
+
+        let mut problems = Vec::new();
+        let embedded = entry
+            .chunks
+            .iter()
+            .filter(|chunk| chunk.embedding.is_some())
+            .count();
+        if index_has_embeddings && embedded < entry.chunks.len() {
+            problems.push(format!(
+                "{} of {} chunks have no embedding",
+                entry.chunks.len() - embedded,
+                entry.chunks.len()
+            ));
+        }
+        if let Some(expected) = expected_dims {
+            let wrong: Vec<usize> = entry
+                .chunks
+                .iter()
+                .flat_map(|chunk| &chunk.embedding)
+                .map(Vec::len)
+                .filter(|&dims| dims != expected)
+                .collect();
+            if let Some(dims) = wrong.first() {
+                problems.push(format!(
+                    "{} embeddings have {} dimensions, the index expects {}",
+                    wrong.len(),
+                    dims,
+                    expected
+                ));
+            }
+        }
+        if !problems.is_empty() {
+            for problem in problems {
+                check.report(file, problem, fix);
+            }
+            if fix {
+                fs::remove_file(sidecar)?;
+                manifest.files.remove(&manifest_key);
+                changed = true;
+            }
+        } else if manifest_lost {
+            manifest.files.insert(manifest_key, entry.metadata.clone());
+        } else if !manifest.files.contains_key(&manifest_key) {
+            check.report(file, "sidecar has no manifest entry".to_string(), fix);
+            if fix {
+                fs::remove_file(sidecar)?;
+                changed = true;
+            }
+        }
+    }
+
+    let listed: Vec<PathBuf> = manifest.files.keys().cloned().collect();
+    for manifest_key in listed {
+        let standard_path = path_utils::from_manifest_path(&manifest_key);
+        if !sidecars.contains_key(&standard_path) {
+            check.report(
+                Some(&standard_path),
+                "manifest entry has no sidecar".to_string(),
+                fix,
+            );
+            if fix {
+                manifest.files.remove(&manifest_key);
+                changed = true;
+            }
+        }
+    }
+
+    // Derived files, rebuilt on demand
+    let lexical_index = index_dir.join("tantivy_index");
+    if lexical_index.is_dir() && !lexical_index.join("meta.json").exists() {
+        check.report(None, "lexical index is incomplete".to_string(), fix);
+        if fix {
+            fs::remove_dir_all(&lexical_index)?;
+        }
+    }
+    let temp_files: Vec<PathBuf> = WalkDir::new(&index_dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.file_type().is_file() && entry.file_name().to_string_lossy().starts_with(".tmp")
+        })
+        .map(|entry| entry.into_path())
+        .collect();
+    for temp_file in temp_files {
+        let name = temp_file.strip_prefix(&index_dir).unwrap_or(&temp_file);
+        check.report(
+            None,
+            format!("{} was left by an interrupted write", name.display()),
+            fix,
+        );
+        if fix {
+            fs::remove_file(&temp_file)?;
+        }
+    }
+
+    if fix && changed {
+        if manifest.embedding_dimensions.is_none() && index_has_embeddings {
+            manifest.embedding_dimensions = expected_dims;
+        }
+        manifest.updated = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        save_manifest(&manifest_path, &manifest)?;
+        remove_empty_dirs(&index_dir)?;
+    }
+
+    Ok(check)
+}
+
 pub fn get_index_stats(path: &Path) -> Result<IndexStats> {
     let index_dir = path.join(".ck");
     if !index_dir.exists() {
@@ -720,6 +1485,8 @@ pub async fn smart_update_index_with_detailed_progress(
         index_directory(path, compute_embeddings, options, model).await?;
         let index_stats = get_index_stats(path)?;
         stats.files_indexed = index_stats.total_files;
+        stats.symlinked_dirs_skipped =
+            collect_files_with_stats(path, options)?.symlinked_dirs_skipped;
         return Ok(stats);
     }
 
@@ -734,6 +1501,11 @@ pub async fn smart_update_index_with_detailed_progress(
     fs::create_dir_all(&index_dir)?;
     let manifest_path = index_dir.join("manifest.json");
     let mut manifest = load_or_create_manifest(&manifest_path)?;
+    if manifest.imported {
+        // ck didn't produce these embeddings, so it can't bring them up to date
+        stats.files_up_to_date = manifest.files.len();
+        return Ok(stats);
+    }
     normalize_manifest_paths(&mut manifest, &repo_root);
 
     // Handle model configuration for embeddings
@@ -742,11 +1514,11 @@ pub async fn smart_update_index_with_detailed_progress(
         let model_registry = ck_models::ModelRegistry::default();
         let (selected_model, model_dims) = if let Some(model_name) = model {
             // User specified a model
-            if let Some(model_config) = model_registry.get_model(model_name) {
-                (model_config.name.clone(), model_config.dimensions)
+            if let Some((_, model_config)) = model_registry.resolve(model_name) {
+                (model_config.name, model_config.dimensions)
             } else {
                 return Err(anyhow::anyhow!(
-                    "Unknown model '{}'. Available models: bge-small, nomic-v1.5, jina-code",
+                    "Unknown model '{}'. Available models: bge-small, nomic-v1.5, jina-code, or openai:<model> for a remote endpoint",
                     model_name
                 ));
             }
@@ -796,7 +1568,9 @@ pub async fn smart_update_index_with_detailed_progress(
 
     // For incremental updates, only process files in the search scope
     // The cleanup phase already handled removing orphaned files from the entire repo
-    let current_files = collect_files(path, options)?;
+    let collected = collect_files_with_stats(path, options)?;
+    stats.symlinked_dirs_skipped = collected.symlinked_dirs_skipped;
+    let current_files = collected.files;
 
     // First pass: determine which files need updating and collect stats
     let mut files_to_update = Vec::new();
@@ -813,7 +1587,7 @@ pub async fn smart_update_index_with_detailed_progress(
             path_utils::to_manifest_path(&path_utils::to_standard_path(&file_path, &repo_root));
 
         if let Some(metadata) = manifest.files.get(&manifest_key) {
-            let fs_meta = match fs::metadata(&file_path) {
+            let fs_meta = match ck_core::archive::source_metadata(&file_path) {
                 Ok(m) => m,
                 Err(_) => {
                     stats.files_errored += 1;
@@ -871,11 +1645,25 @@ pub async fn smart_update_index_with_detailed_progress(
 
     // Second pass: index the files that need updating
     if compute_embeddings {
-        // Sequential processing with streaming - write each file immediately
-        let mut embedder = ck_embed::create_embedder(resolved_model.as_deref())?;
+        // Files are read and chunked in parallel ahead of the embedder, which
+        // runs here one file at a time; each file is written as soon as it's done
+        let mut embedder = ck_embed::with_dims(
+            ck_embed::cached_embedder(resolved_model.as_deref())?,
+            manifest.truncated_dims,
+        );
+        // Remote models may only learn their dimensions from the endpoint
+        manifest.embedding_dimensions = Some(embedder.dim());
         let mut _processed_count = 0;
+        let total_files = files_to_update.len();
+        let (prepared_files, worker) = prepare_files_in_order(
+            files_to_update,
+            path.to_path_buf(),
+            Some(embedder.model_name().to_string()),
+            ChunkSettings::of(&manifest),
+        );
 
-        for file_path in files_to_update.iter() {
+        for (file_path, prepared) in prepared_files.iter() {
+            let file_path = &file_path;
             // Check for interrupt
             if INTERRUPTED.load(Ordering::SeqCst) {
                 eprintln!(
@@ -892,18 +1680,20 @@ pub async fn smart_update_index_with_detailed_progress(
             }
 
             // Call detailed progress version if callback is provided, otherwise use regular version
-            let result = if let Some(ref detailed_callback) = detailed_progress_callback {
-                index_single_file_with_progress(
-                    file_path,
-                    path,
-                    Some(&mut embedder),
-                    Some(detailed_callback),
-                    _processed_count,
-                    files_to_update.len(),
-                )
-            } else {
-                index_single_file_with_progress(file_path, path, Some(&mut embedder), None, 0, 1)
-            };
+            let result = prepared.and_then(|prepared| {
+                if let Some(ref detailed_callback) = detailed_progress_callback {
+                    embed_prepared_file(
+                        file_path,
+                        prepared,
+                        Some(&mut embedder),
+                        Some(detailed_callback),
+                        _processed_count,
+                        total_files,
+                    )
+                } else {
+                    embed_prepared_file(file_path, prepared, Some(&mut embedder), None, 0, 1)
+                }
+            });
 
             match result {
                 Ok((entry, file_chunks_reused, file_chunks_embedded)) => {
@@ -941,14 +1731,19 @@ pub async fn smart_update_index_with_detailed_progress(
         }
 
         stats.files_indexed = _processed_count;
+
+        // An interrupted run stops reading results; dropping the receiver lets
+        // the worker notice and finish its current window
+        drop(prepared_files);
+        worker
+            .join()
+            .map_err(|_| anyhow::anyhow!("Worker thread panicked"))?;
     } else {
         // Parallel processing with streaming using producer-consumer pattern
-        use std::sync::mpsc;
-        use std::thread;
-
         let (tx, rx) = mpsc::channel();
         let files_clone = files_to_update.clone();
         let path_clone = path.to_path_buf();
+        let settings = ChunkSettings::of(&manifest);
 
         // Spawn worker thread for parallel processing
         let worker_handle = thread::spawn(move || {
@@ -961,7 +1756,7 @@ pub async fn smart_update_index_with_detailed_progress(
                     return Err("interrupted");
                 }
 
-                match index_single_file(file_path, &path_clone, None) {
+                match index_single_file(file_path, &path_clone, None, settings) {
                     Ok(entry) => {
                         if tx.send((file_path.clone(), entry)).is_err() {
                             // Receiver dropped, stop processing
@@ -1051,9 +1846,10 @@ fn index_single_file(
     file_path: &Path,
     repo_root: &Path,
     embedder: Option<&mut Box<dyn ck_embed::Embedder>>,
+    settings: ChunkSettings,
 ) -> Result<IndexEntry> {
     let (entry, _chunks_reused, _chunks_embedded) =
-        index_single_file_with_progress(file_path, repo_root, embedder, None, 0, 1)?;
+        index_single_file_with_progress(file_path, repo_root, embedder, settings, None, 0, 1)?;
     Ok(entry)
 }
 
@@ -1061,17 +1857,78 @@ fn index_single_file_with_progress(
     file_path: &Path,
     repo_root: &Path,
     embedder: Option<&mut Box<dyn ck_embed::Embedder>>,
+    settings: ChunkSettings,
     detailed_progress: Option<&DetailedProgressCallback>,
     file_index: usize,
     total_files: usize,
 ) -> Result<(IndexEntry, usize, usize)> {
+    let model_name = embedder.as_ref().map(|e| e.model_name().to_string());
+    let prepared = prepare_file(
+        file_path,
+        repo_root,
+        model_name.as_deref(),
+        embedder.is_some(),
+        settings,
+    )?;
+    embed_prepared_file(
+        file_path,
+        prepared,
+        embedder,
+        detailed_progress,
+        file_index,
+        total_files,
+    )
+}
+
+/// The index settings that shape each file's chunks or embedding input
+#[derive(Debug, Clone, Copy, Default)]
+struct ChunkSettings {
+    context_prefix: bool,
+    string_chunks: bool,
+    text_window: ck_chunk::TextWindow,
+}
+
+impl ChunkSettings {
+    fn of(manifest: &IndexManifest) -> Self {
+        Self {
+            context_prefix: manifest.context_prefix,
+            string_chunks: manifest.string_chunks,
+            text_window: manifest.text_window,
+        }
+    }
+}
+
+/// A file that has been read and chunked and is waiting for its embeddings
+struct PreparedFile {
+    metadata: FileMetadata,
+    chunks: Vec<ck_chunk::Chunk>,
+    /// Embeddings from the file's previous sidecar, keyed by [`embedding_cache_key`]
+    chunk_cache: HashMap<String, Vec<f32>>,
+    /// The file's path as named in context prefixes, when the index embeds them
+    context_path: Option<String>,
+}
+
+/// The embedder-free half of indexing a file: read it, hash it and chunk it for
+/// `model_name`. Independent per file, so it is safe to run on the rayon pool.
+fn prepare_file(
+    file_path: &Path,
+    repo_root: &Path,
+    model_name: Option<&str>,
+    reuse_embeddings: bool,
+    settings: ChunkSettings,
+) -> Result<PreparedFile> {
     // Skip binary files to avoid UTF-8 warnings
     if !is_text_file(file_path) {
         return Err(anyhow::anyhow!("Binary file, skipping"));
     }
 
+    let standard_path = path_utils::to_standard_path(file_path, repo_root);
+    let context_path = settings
+        .context_prefix
+        .then(|| standard_path.to_string_lossy().replace('\\', "/"));
+
     // Build chunk cache from old sidecar if it exists (for chunk reuse)
-    let chunk_cache: HashMap<String, Vec<f32>> = if embedder.is_some() {
+    let chunk_cache: HashMap<String, Vec<f32>> = if reuse_embeddings {
         let sidecar_path = get_sidecar_path(repo_root, file_path);
         if sidecar_path.exists() {
             match load_index_entry(&sidecar_path) {
@@ -1079,11 +1936,13 @@ fn index_single_file_with_progress(
                     .chunks
                     .into_iter()
                     .filter_map(|chunk| {
-                        if let (Some(hash), Some(embedding)) = (chunk.chunk_hash, chunk.embedding) {
-                            Some((hash, embedding))
-                        } else {
-                            None
-                        }
+                        let key = embedding_cache_key(
+                            chunk.chunk_hash.as_deref()?,
+                            context_path.as_deref(),
+                            chunk.breadcrumb.as_deref(),
+                            chunk.name.as_deref(),
+                        );
+                        Some((key, chunk.embedding?))
                     })
                     .collect(),
                 Err(_) => HashMap::new(),
@@ -1101,9 +1960,8 @@ fn index_single_file_with_progress(
 
     // Always use the ORIGINAL file for hash and metadata
     let hash = compute_file_hash(file_path)?;
-    let metadata = fs::metadata(file_path)?;
+    let metadata = ck_core::archive::source_metadata(file_path)?;
 
-    let standard_path = path_utils::to_standard_path(file_path, repo_root);
     let manifest_path = path_utils::to_manifest_path(&standard_path);
 
     let file_metadata = FileMetadata {
@@ -1123,8 +1981,128 @@ fn index_single_file_with_progress(
         ck_core::Language::from_path(file_path)
     };
 
-    let model_name = embedder.as_ref().map(|e| e.model_name());
-    let chunks = ck_chunk::chunk_text_with_model(&content, lang, model_name)?;
+    // `ck:ignore` comments keep a file, or chunks of it, out of the index
+    let chunks = if ck_chunk::has_ignore_file_marker(&content) {
+        Vec::new()
+    } else {
+        let config = ck_chunk::ChunkConfig {
+            string_chunks: settings.string_chunks,
+            text_window: settings.text_window,
+            ..ck_chunk::ChunkConfig::for_model(model_name)
+        };
+        ck_chunk::without_ignored(ck_chunk::chunk_text_with_config_and_model(
+            &content, lang, &config, model_name,
+        )?)
+    };
+
+    Ok(PreparedFile {
+        metadata: file_metadata,
+        chunks,
+        chunk_cache,
+        context_path,
+    })
+}
+
+/// Read and chunk `files` on the rayon pool, a window at a time, while the
+/// caller embeds on its own thread. Results arrive in the order of `files`, so
+/// the index is written exactly as a sequential run would write it.
+fn prepare_files_in_order(
+    files: Vec<PathBuf>,
+    repo_root: PathBuf,
+    model_name: Option<String>,
+    settings: ChunkSettings,
+) -> (
+    mpsc::Receiver<(PathBuf, Result<PreparedFile>)>,
+    thread::JoinHandle<()>,
+) {
+    // Bounded so chunked files never pile up faster than they can be embedded
+    let (tx, rx) = mpsc::sync_channel(PREPARE_WINDOW);
+    let worker = thread::spawn(move || {
+        for window in files.chunks(PREPARE_WINDOW) {
+            if INTERRUPTED.load(Ordering::SeqCst) {
+                return;
+            }
+            let prepared: Vec<Result<PreparedFile>> = window
+                .par_iter()
+                .map(|file_path| {
+                    prepare_file(file_path, &repo_root, model_name.as_deref(), true, settings)
+                })
+                .collect();
+            for (file_path, result) in window.iter().zip(prepared) {
+                if tx.send((file_path.clone(), result)).is_err() {
+                    // Receiver dropped, stop processing
+                    return;
+                }
+            }
+        }
+    });
+    (rx, worker)
+}
+
+/// The comment line prepended to a chunk's embedding input when the index uses
+/// context prefixes, e.g. `// net/http/client.rs — Client::new`
+fn context_line(path: &str, breadcrumb: Option<&str>, name: Option<&str>) -> String {
+    let marker = ck_core::anchor::line_comment_marker(Language::from_path(Path::new(path)));
+    let symbol: Vec<&str> = breadcrumb
+        .into_iter()
+        .chain(name)
+        .filter(|part| !part.is_empty())
+        .collect();
+    if symbol.is_empty() {
+        format!("{} {}", marker, path)
+    } else {
+        format!("{} {} — {}", marker, path, symbol.join("::"))
+    }
+}
+
+/// The text embedded for `chunk`: its own text, after a context line naming
+/// `context_path` and the chunk's breadcrumb when the index uses context prefixes
+fn embedding_input(chunk: &ck_chunk::Chunk, context_path: Option<&str>) -> String {
+    match context_path {
+        Some(path) => format!(
+            "{}\n{}",
+            context_line(
+                path,
+                chunk.metadata.breadcrumb.as_deref(),
+                chunk.metadata.name.as_deref()
+            ),
+            chunk.text
+        ),
+        None => chunk.text.clone(),
+    }
+}
+
+/// Cached embeddings are reused by chunk hash, which only covers the chunk's
+/// own text. With context prefixes the breadcrumb is embedded too, so a chunk
+/// whose enclosing class was renamed must not reuse its old embedding.
+fn embedding_cache_key(
+    chunk_hash: &str,
+    context_path: Option<&str>,
+    breadcrumb: Option<&str>,
+    name: Option<&str>,
+) -> String {
+    match context_path {
+        Some(path) => format!("{}\n{}", chunk_hash, context_line(path, breadcrumb, name)),
+        None => chunk_hash.to_string(),
+    }
+}
+
+/// Embed a prepared file's chunks (reusing cached embeddings where the chunk is
+/// unchanged) and build its index entry
+fn embed_prepared_file(
+    file_path: &Path,
+    prepared: PreparedFile,
+    embedder: Option<&mut Box<dyn ck_embed::Embedder>>,
+    detailed_progress: Option<&DetailedProgressCallback>,
+    file_index: usize,
+    total_files: usize,
+) -> Result<(IndexEntry, usize, usize)> {
+    let PreparedFile {
+        metadata: file_metadata,
+        chunks,
+        chunk_cache,
+        context_path,
+    } = prepared;
 
     // Track chunk reuse statistics
     let mut chunks_reused = 0;
@@ -1171,7 +2149,14 @@ fn index_single_file_with_progress(
 
                 // Check cache first, but validate dimension matches current embedder
                 let expected_dim = embedder.dim();
-                let embedding = if let Some(cached_embedding) = chunk_cache.get(&chunk_hash) {
+                let cache_key = embedding_cache_key(
+                    &chunk_hash,
+                    context_path.as_deref(),
+                    chunk.metadata.breadcrumb.as_deref(),
+                    chunk.metadata.name.as_deref(),
+                );
+                let input = embedding_input(&chunk, context_path.as_deref());
+                let embedding = if let Some(cached_embedding) = chunk_cache.get(&cache_key) {
                     if cached_embedding.len() == expected_dim {
                         // Dimension matches, safe to reuse
                         chunks_reused += 1;
@@ -1185,7 +2170,7 @@ fn index_single_file_with_progress(
                             cached_embedding.len(),
                             expected_dim
                         );
-                        let embeddings = embedder.embed(std::slice::from_ref(&chunk.text))?;
+                        let embeddings = embedder.embed(std::slice::from_ref(&input))?;
                         embeddings.into_iter().next().ok_or_else(|| {
                             anyhow::anyhow!(
                                 "Embedder returned empty results for chunk {} in file {:?}. This may indicate an issue with the embedding model or chunk content.",
@@ -1197,7 +2182,7 @@ fn index_single_file_with_progress(
                 } else {
                     // No cache hit, compute embedding
                     chunks_embedded += 1;
-                    let embeddings = embedder.embed(std::slice::from_ref(&chunk.text))?;
+                    let embeddings = embedder.embed(std::slice::from_ref(&input))?;
                     embeddings.into_iter().next().ok_or_else(|| {
                         anyhow::anyhow!(
                             "Embedder returned empty results for chunk {} in file {:?}. This may indicate an issue with the embedding model or chunk content.",
@@ -1207,13 +2192,7 @@ fn index_single_file_with_progress(
                     })?
                 };
 
-                let chunk_type_str = match chunk.chunk_type {
-                    ck_chunk::ChunkType::Function => Some("function".to_string()),
-                    ck_chunk::ChunkType::Class => Some("class".to_string()),
-                    ck_chunk::ChunkType::Method => Some("method".to_string()),
-                    ck_chunk::ChunkType::Module => Some("module".to_string()),
-                    ck_chunk::ChunkType::Text => None,
-                };
+                let chunk_type_str = chunk.chunk_type.index_name().map(str::to_string);
 
                 let breadcrumb = chunk.metadata.breadcrumb.clone();
                 let ancestry = if chunk.metadata.ancestry.is_empty() {
@@ -1238,6 +2217,9 @@ fn index_single_file_with_progress(
                     chunk_type: chunk_type_str,
                     breadcrumb,
                     ancestry,
+                    breadcrumb_segments: (!chunk.metadata.breadcrumb_segments.is_empty())
+                        .then(|| chunk.metadata.breadcrumb_segments.clone()),
+                    name: chunk.metadata.name.clone(),
                     byte_length: Some(chunk.metadata.byte_length),
                     estimated_tokens: Some(chunk.metadata.estimated_tokens),
                     leading_trivia,
@@ -1260,7 +2242,13 @@ fn index_single_file_with_progress(
                     &chunk.metadata.leading_trivia,
                     &chunk.metadata.trailing_trivia,
                 );
-                if let Some(cached_embedding) = chunk_cache.get(&chunk_hash) {
+                let cache_key = embedding_cache_key(
+                    &chunk_hash,
+                    context_path.as_deref(),
+                    chunk.metadata.breadcrumb.as_deref(),
+                    chunk.metadata.name.as_deref(),
+                );
+                if let Some(cached_embedding) = chunk_cache.get(&cache_key) {
                     if cached_embedding.len() == expected_dim {
                         // Dimension matches, safe to reuse
                         chunks_reused += 1;
@@ -1273,12 +2261,18 @@ fn index_single_file_with_progress(
                             cached_embedding.len(),
                             expected_dim
                         );
-                        chunks_to_embed.push((chunk.text.clone(), chunk_results.len()));
+                        chunks_to_embed.push((
+                            embedding_input(&chunk, context_path.as_deref()),
+                            chunk_results.len(),
+                        ));
                         chunk_results.push((chunk, chunk_hash, None));
                     }
                 } else {
                     // No cache hit, need to embed
-                    chunks_to_embed.push((chunk.text.clone(), chunk_results.len()));
+                    chunks_to_embed.push((
+                        embedding_input(&chunk, context_path.as_deref()),
+                        chunk_results.len(),
+                    ));
                     chunk_results.push((chunk, chunk_hash, None));
                 }
             }
@@ -1319,13 +2313,7 @@ fn index_single_file_with_progress(
                 .into_iter()
                 .map(|(chunk, chunk_hash, embedding)| {
                     let embedding = embedding.expect("All chunks should have embeddings by now");
-                    let chunk_type_str = match chunk.chunk_type {
-                        ck_chunk::ChunkType::Function => Some("function".to_string()),
-                        ck_chunk::ChunkType::Class => Some("class".to_string()),
-                        ck_chunk::ChunkType::Method => Some("method".to_string()),
-                        ck_chunk::ChunkType::Module => Some("module".to_string()),
-                        ck_chunk::ChunkType::Text => None,
-                    };
+                    let chunk_type_str = chunk.chunk_type.index_name().map(str::to_string);
                     let breadcrumb = chunk.metadata.breadcrumb.clone();
                     let ancestry = if chunk.metadata.ancestry.is_empty() {
                         None
@@ -1348,6 +2336,9 @@ fn index_single_file_with_progress(
                         chunk_type: chunk_type_str,
                         breadcrumb,
                         ancestry,
+                        breadcrumb_segments: (!chunk.metadata.breadcrumb_segments.is_empty())
+                            .then(|| chunk.metadata.breadcrumb_segments.clone()),
+                        name: chunk.metadata.name.clone(),
                         byte_length: Some(chunk.metadata.byte_length),
                         estimated_tokens: Some(chunk.metadata.estimated_tokens),
                         leading_trivia,
@@ -1362,13 +2353,7 @@ fn index_single_file_with_progress(
         chunks
             .into_iter()
             .map(|chunk| {
-                let chunk_type_str = match chunk.chunk_type {
-                    ck_chunk::ChunkType::Function => Some("function".to_string()),
-                    ck_chunk::ChunkType::Class => Some("class".to_string()),
-                    ck_chunk::ChunkType::Method => Some("method".to_string()),
-                    ck_chunk::ChunkType::Module => Some("module".to_string()),
-                    ck_chunk::ChunkType::Text => None,
-                };
+                let chunk_type_str = chunk.chunk_type.index_name().map(str::to_string);
                 let breadcrumb = chunk.metadata.breadcrumb.clone();
                 let ancestry = if chunk.metadata.ancestry.is_empty() {
                     None
@@ -1391,6 +2376,9 @@ fn index_single_file_with_progress(
                     chunk_type: chunk_type_str,
                     breadcrumb,
                     ancestry,
+                    breadcrumb_segments: (!chunk.metadata.breadcrumb_segments.is_empty())
+                        .then(|| chunk.metadata.breadcrumb_segments.clone()),
+                    name: chunk.metadata.name.clone(),
                     byte_length: Some(chunk.metadata.byte_length),
                     estimated_tokens: Some(chunk.metadata.estimated_tokens),
                     leading_trivia: leading_trivia.clone(),
@@ -1528,8 +2516,11 @@ fn extract_pdf_text(path: &Path) -> Result<String> {
 /// Preprocess a file if needed, returning path to readable content
 /// For regular files: returns the original path (no preprocessing)
 /// For PDFs: extracts text to cache, returns cache path
+/// For archive entries: extracts the entry to cache, returns cache path
 fn preprocess_file(file_path: &Path, repo_root: &Path) -> Result<PathBuf> {
-    if ck_core::pdf::is_pdf_file(file_path) {
+    if ck_core::archive::is_virtual_path(file_path) {
+        Ok(ck_core::archive::cache_entry(repo_root, file_path)?)
+    } else if ck_core::pdf::is_pdf_file(file_path) {
         let cache_path = ck_core::pdf::get_content_cache_path(repo_root, file_path);
 
         // Check if re-extraction needed
@@ -1562,6 +2553,11 @@ fn is_text_file(path: &Path) -> bool {
         return true;
     }
 
+    // Archive entries were sniffed for NUL bytes when the archive was listed
+    if ck_core::archive::is_virtual_path(path) {
+        return true;
+    }
+
     // Use NUL byte heuristic like ripgrep - read first 8KB and check for NUL bytes
     const BUFFER_SIZE: usize = 8192;
 
@@ -1633,6 +2629,45 @@ pub struct CleanupStats {
     pub orphaned_sidecars_removed: usize,
 }
 
+/// Something wrong with an index, found by [`check_index`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexProblem {
+    /// The source file it concerns, relative to the index root; `None` for the
+    /// index as a whole
+    pub file: Option<PathBuf>,
+    pub message: String,
+    /// Whether `check_index` repaired it
+    pub fixed: bool,
+}
+
+/// What [`prune_index`] removed, or would remove in a dry run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PruneStats {
+    /// The deleted source files whose entries went, relative to the index root
+    pub pruned_files: Vec<PathBuf>,
+    pub sidecars_removed: usize,
+    pub chunks_removed: usize,
+    /// Whether the lexical index was removed, to be rebuilt without them
+    pub lexical_index_removed: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexCheck {
+    pub files_checked: usize,
+    pub chunks_checked: usize,
+    pub problems: Vec<IndexProblem>,
+}
+
+impl IndexCheck {
+    fn report(&mut self, file: Option<&Path>, message: String, fixed: bool) {
+        self.problems.push(IndexProblem {
+            file: file.map(Path::to_path_buf),
+            message,
+            fixed,
+        });
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct IndexStats {
     pub total_files: usize,
@@ -1644,6 +2679,13 @@ pub struct IndexStats {
     pub index_updated: u64,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportStats {
+    pub files: usize,
+    pub chunks: usize,
+    pub dimensions: usize,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UpdateStats {
     pub files_indexed: usize,
@@ -1654,6 +2696,8 @@ pub struct UpdateStats {
     pub orphaned_files_removed: usize,
     pub chunks_reused: usize,
     pub chunks_embedded: usize,
+    #[serde(default)]
+    pub symlinked_dirs_skipped: usize,
 }
 
 #[cfg(test)]
@@ -1723,7 +2767,12 @@ mod tests {
         let mut empty_embedder: Box<dyn ck_embed::Embedder> = Box::new(EmptyResultsEmbedder);
 
         // This should return an error, not panic
-        let result = index_single_file(&test_file, test_path, Some(&mut empty_embedder));
+        let result = index_single_file(
+            &test_file,
+            test_path,
+            Some(&mut empty_embedder),
+            ChunkSettings::default(),
+        );
 
         assert!(result.is_err());
         let error_msg = result.unwrap_err().to_string();
@@ -1751,6 +2800,7 @@ mod tests {
             &test_file,
             test_path,
             Some(&mut empty_embedder),
+            ChunkSettings::default(),
             Some(&dummy_callback),
             0,
             1,
@@ -1782,7 +2832,12 @@ mod tests {
             Box::new(MismatchedCountEmbedder);
 
         // This should return an error, not silently mismatch
-        let result = index_single_file(&test_file, test_path, Some(&mut mismatched_embedder));
+        let result = index_single_file(
+            &test_file,
+            test_path,
+            Some(&mut mismatched_embedder),
+            ChunkSettings::default(),
+        );
 
         assert!(result.is_err());
         let error_msg = result.unwrap_err().to_string();
@@ -1792,6 +2847,366 @@ mod tests {
         assert!(error_msg.contains("Expected equal counts"));
     }
 
+    /// Test embedder that remembers every text it was asked to embed
+    struct RecordingEmbedder(Arc<Mutex<Vec<String>>>);
+
+    impl ck_embed::Embedder for RecordingEmbedder {
+        fn id(&self) -> &'static str {
+            "recording-test"
+        }
+
+        fn dim(&self) -> usize {
+            4
+        }
+
+        fn model_name(&self) -> &str {
+            "test-recording"
+        }
+
+        fn embed(&mut self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            self.0.lock().unwrap().extend_from_slice(texts);
+            Ok(vec![vec![0.5; self.dim()]; texts.len()])
+        }
+    }
+
+    #[test]
+    fn test_context_prefix_only_changes_embedding_input() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_path = temp_dir.path();
+        fs::create_dir_all(test_path.join("net/http")).unwrap();
+        let test_file = test_path.join("net/http/client.rs");
+        fs::write(
+            &test_file,
+            "struct Client;\n\nimpl Client {\n    fn new() -> Self {\n        Client\n    }\n}\n",
+        )
+        .unwrap();
+
+        let embed = |context_prefix: bool| {
+            let texts = Arc::new(Mutex::new(Vec::new()));
+            let mut embedder: Box<dyn ck_embed::Embedder> =
+                Box::new(RecordingEmbedder(texts.clone()));
+            let entry = index_single_file(
+                &test_file,
+                test_path,
+                Some(&mut embedder),
+                ChunkSettings {
+                    context_prefix,
+                    ..ChunkSettings::default()
+                },
+            )
+            .unwrap();
+            let texts = texts.lock().unwrap().clone();
+            (entry, texts)
+        };
+
+        let (plain_entry, plain) = embed(false);
+        let (prefixed_entry, prefixed) = embed(true);
+        assert!(
+            plain
+                .iter()
+                .all(|text| !text.contains("net/http/client.rs"))
+        );
+        assert!(
+            prefixed
+                .iter()
+                .any(|text| text.starts_with("// net/http/client.rs — Client::new\nfn new()")),
+            "{:?}",
+            prefixed
+        );
+        // What gets stored and shown is the same either way
+        let hashes = |entry: &IndexEntry| -> Vec<Option<String>> {
+            entry.chunks.iter().map(|c| c.chunk_hash.clone()).collect()
+        };
+        assert_eq!(hashes(&plain_entry), hashes(&prefixed_entry));
+    }
+
+    #[test]
+    fn test_string_chunks_setting_indexes_big_literals() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_path = temp_dir.path();
+        let test_file = test_path.join("queries.rs");
+        fs::write(
+            &test_file,
+            "fn report() -> &'static str {\n    \"\n    SELECT day, count(*)\n    FROM events\n    GROUP BY day\n    \"\n}\n",
+        )
+        .unwrap();
+
+        let literal_chunks = |string_chunks: bool| {
+            let settings = ChunkSettings {
+                string_chunks,
+                ..ChunkSettings::default()
+            };
+            index_single_file(&test_file, test_path, None, settings)
+                .unwrap()
+                .chunks
+                .into_iter()
+                .filter(|chunk| chunk.name.as_deref() == Some("string"))
+                .count()
+        };
+        assert_eq!(literal_chunks(false), 0);
+        assert_eq!(literal_chunks(true), 1);
+    }
+
+    #[test]
+    fn test_text_window_setting_sizes_unparsed_file_chunks() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_path = temp_dir.path();
+        let test_file = test_path.join("settings.conf");
+        let text: String = (1..=100).map(|i| format!("option_{i} = on\n")).collect();
+        fs::write(&test_file, text).unwrap();
+
+        let spans = |text_window: ck_chunk::TextWindow| -> Vec<(usize, usize)> {
+            let settings = ChunkSettings {
+                text_window,
+                ..ChunkSettings::default()
+            };
+            index_single_file(&test_file, test_path, None, settings)
+                .unwrap()
+                .chunks
+                .iter()
+                .map(|chunk| (chunk.span.line_start, chunk.span.line_end))
+                .collect()
+        };
+        assert_eq!(
+            spans(ck_chunk::TextWindow::default()),
+            vec![(1, 40), (33, 72), (65, 100)]
+        );
+        assert_eq!(spans("50:0".parse().unwrap()), vec![(1, 50), (51, 100)]);
+    }
+
+    #[test]
+    fn test_set_context_prefix_rebuilds_only_when_it_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_path = temp_dir.path();
+        let manifest_path = test_path.join(".ck").join("manifest.json");
+
+        // A fresh index just records the setting
+        assert!(!set_context_prefix(test_path, true).unwrap());
+        assert!(
+            load_or_create_manifest(&manifest_path)
+                .unwrap()
+                .context_prefix
+        );
+        assert!(!set_context_prefix(test_path, true).unwrap());
+
+        let mut manifest = load_or_create_manifest(&manifest_path).unwrap();
+        manifest.embedding_model = Some("test-model".to_string());
+        manifest.files.insert(
+            PathBuf::from("./a.rs"),
+            FileMetadata {
+                path: PathBuf::from("./a.rs"),
+                hash: "abc".to_string(),
+                last_modified: 0,
+                size: 0,
+            },
+        );
+        save_manifest(&manifest_path, &manifest).unwrap();
+
+        assert!(set_context_prefix(test_path, false).unwrap());
+        let manifest = load_or_create_manifest(&manifest_path).unwrap();
+        assert!(!manifest.context_prefix);
+        assert!(manifest.files.is_empty());
+        assert_eq!(manifest.embedding_model.as_deref(), Some("test-model"));
+    }
+
+    #[test]
+    fn test_truncated_dims_survive_other_setting_rebuilds() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_path = temp_dir.path();
+        let manifest_path = test_path.join(".ck").join("manifest.json");
+
+        assert!(!set_truncated_dims(test_path, Some(256)).unwrap());
+        let mut manifest = load_or_create_manifest(&manifest_path).unwrap();
+        manifest.files.insert(
+            PathBuf::from("./a.rs"),
+            FileMetadata {
+                path: PathBuf::from("./a.rs"),
+                hash: "abc".to_string(),
+                last_modified: 0,
+                size: 0,
+            },
+        );
+        save_manifest(&manifest_path, &manifest).unwrap();
+
+        // Rebuilding for another setting keeps the truncation
+        assert!(set_string_chunks(test_path, true).unwrap());
+        let manifest = load_or_create_manifest(&manifest_path).unwrap();
+        assert_eq!(manifest.truncated_dims, Some(256));
+
+        assert!(!set_truncated_dims(test_path, None).unwrap());
+        assert_eq!(
+            load_or_create_manifest(&manifest_path)
+                .unwrap()
+                .truncated_dims,
+            None
+        );
+    }
+
+    #[test]
+    fn test_prune_index_drops_deleted_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_path = temp_dir.path();
+        let mut records = String::new();
+        for name in ["kept.rs", "gone.rs", "src/old.rs"] {
+            let file = test_path.join(name);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(&file, "fn f() {}\n").unwrap();
+            records.push_str(&format!(
+                "{{\"path\": \"{}\", \"line_start\": 1, \"line_end\": 1, \"vector\": {}}}\n",
+                name,
+                serde_json::to_string(&vec![0.5; 384]).unwrap()
+            ));
+        }
+        import_embeddings(test_path, records.as_bytes(), "bge-small").unwrap();
+        let index_dir = test_path.join(".ck");
+        fs::create_dir_all(index_dir.join("tantivy_index")).unwrap();
+        fs::remove_file(test_path.join("gone.rs")).unwrap();
+        fs::remove_file(test_path.join("src/old.rs")).unwrap();
+
+        let preview = prune_index(test_path, true).unwrap();
+        let expected = vec![PathBuf::from("gone.rs"), PathBuf::from("src/old.rs")];
+        assert_eq!(preview.pruned_files, expected);
+        assert_eq!((preview.sidecars_removed, preview.chunks_removed), (2, 2));
+        assert!(index_dir.join("gone.rs.ck").exists());
+
+        let pruned = prune_index(test_path, false).unwrap();
+        assert_eq!(pruned.pruned_files, expected);
+        assert_eq!(pruned.sidecars_removed, 2);
+        assert!(pruned.lexical_index_removed);
+        assert!(!index_dir.join("gone.rs.ck").exists());
+        assert!(!index_dir.join("tantivy_index").exists());
+        assert!(index_dir.join("kept.rs.ck").exists());
+        let manifest = load_or_create_manifest(&index_dir.join("manifest.json")).unwrap();
+        assert_eq!(manifest.files.len(), 1);
+
+        assert!(
+            prune_index(test_path, false)
+                .unwrap()
+                .pruned_files
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_check_index_reports_then_repairs() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_path = temp_dir.path();
+        let mut records = String::new();
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            fs::write(test_path.join(name), "fn f() {}\n").unwrap();
+            records.push_str(&format!(
+                "{{\"path\": \"{}\", \"line_start\": 1, \"line_end\": 1, \"vector\": {}}}\n",
+                name,
+                serde_json::to_string(&vec![0.5; 384]).unwrap()
+            ));
+        }
+        import_embeddings(test_path, records.as_bytes(), "bge-small").unwrap();
+        let healthy = check_index(test_path, false).unwrap();
+        assert_eq!(healthy.files_checked, 3);
+        assert!(healthy.problems.is_empty(), "{:?}", healthy.problems);
+
+        // A truncated sidecar, a short embedding and a stray temp file
+        let index_dir = test_path.join(".ck");
+        fs::write(index_dir.join("b.rs.ck"), b"\x01\x02").unwrap();
+        let mut entry = load_index_entry(&index_dir.join("c.rs.ck")).unwrap();
+        entry.chunks[0].embedding = Some(vec![0.5; 10]);
+        save_index_entry(&index_dir.join("c.rs.ck"), &entry).unwrap();
+        fs::write(index_dir.join(".tmpAbC123"), b"partial").unwrap();
+
+        let report = check_index(test_path, false).unwrap();
+        assert_eq!(report.problems.len(), 3, "{:?}", report.problems);
+        assert!(report.problems.iter().all(|problem| !problem.fixed));
+        assert!(index_dir.join("b.rs.ck").exists());
+        assert!(index_dir.join(".tmpAbC123").exists());
+
+        let repaired = check_index(test_path, true).unwrap();
+        assert!(repaired.problems.iter().all(|problem| problem.fixed));
+        assert!(check_index(test_path, false).unwrap().problems.is_empty());
+        let manifest_path = index_dir.join("manifest.json");
+        let manifest = load_or_create_manifest(&manifest_path).unwrap();
+        assert_eq!(manifest.files.len(), 1);
+
+        // A lost manifest is rebuilt from the sidecars left
+        fs::remove_file(&manifest_path).unwrap();
+        let report = check_index(test_path, false).unwrap();
+        assert_eq!(report.problems.len(), 1);
+        assert!(!manifest_path.exists());
+        check_index(test_path, true).unwrap();
+        let rebuilt = load_or_create_manifest(&manifest_path).unwrap();
+        assert_eq!(rebuilt.files.len(), 1);
+        assert_eq!(rebuilt.embedding_dimensions, Some(384));
+        assert!(check_index(test_path, false).unwrap().problems.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_import_embeddings_builds_index_without_embedding() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_path = temp_dir.path();
+        fs::create_dir(test_path.join("src")).unwrap();
+        let source = "// client\r\nimpl Client {\r\n    fn new() -> Self {\r\n        Client\r\n    }\r\n}\r\n";
+        fs::write(test_path.join("src/client.rs"), source).unwrap();
+
+        let vector = |x: f32| serde_json::to_string(&vec![x; 384]).unwrap();
+        let records = format!(
+            "{{\"path\": \"src/client.rs\", \"line_start\": 3, \"line_end\": 5, \"chunk_type\": \"method\", \"breadcrumb\": \"Client::new\", \"vector\": {}}}\n\n{{\"path\": \"src/client.rs\", \"line_start\": 1, \"line_end\": 1, \"vector\": {}}}\n",
+            vector(0.5),
+            vector(0.25)
+        );
+        let stats = import_embeddings(test_path, records.as_bytes(), "bge-small").unwrap();
+        assert_eq!((stats.files, stats.chunks, stats.dimensions), (1, 2, 384));
+
+        let root = test_path.canonicalize().unwrap();
+        let entry =
+            load_index_entry(&get_sidecar_path(&root, &root.join("src/client.rs"))).unwrap();
+        let method = &entry.chunks[0];
+        assert_eq!(
+            &source[method.span.byte_start..method.span.byte_end],
+            "    fn new() -> Self {\r\n        Client\r\n    }"
+        );
+        assert_eq!(method.breadcrumb.as_deref(), Some("Client"));
+        assert_eq!(method.name.as_deref(), Some("new"));
+        assert_eq!(method.embedding.as_ref().unwrap().len(), 384);
+        assert_eq!(entry.chunks[1].name, None);
+
+        let manifest = load_or_create_manifest(&root.join(".ck").join("manifest.json")).unwrap();
+        assert!(manifest.imported);
+        assert_eq!(
+            manifest.embedding_model.as_deref(),
+            Some("BAAI/bge-small-en-v1.5")
+        );
+        assert_eq!(manifest.embedding_dimensions, Some(384));
+
+        // Updates leave the imported chunks alone
+        fs::write(test_path.join("other.rs"), "fn other() {}").unwrap();
+        let file_options = ck_core::FileCollectionOptions {
+            respect_gitignore: true,
+            use_ckignore: true,
+            follow_symlinks: false,
+            archives: false,
+            exclude_patterns: vec![],
+        };
+        let stats = smart_update_index(test_path, false, &file_options)
+            .await
+            .unwrap();
+        assert_eq!((stats.files_indexed, stats.files_up_to_date), (0, 1));
+        assert!(
+            index_file(&test_path.join("other.rs"), false)
+                .await
+                .is_err()
+        );
+
+        let wrong_dims = "{\"path\": \"src/client.rs\", \"line_start\": 1, \"line_end\": 1, \"vector\": [1.0, 2.0]}";
+        let err = import_embeddings(test_path, wrong_dims.as_bytes(), "bge-small").unwrap_err();
+        assert!(err.to_string().contains("line 1: vector has 2 dimensions"));
+        let past_end = format!(
+            "{{\"path\": \"src/client.rs\", \"line_start\": 6, \"line_end\": 9, \"vector\": {}}}",
+            vector(1.0)
+        );
+        assert!(import_embeddings(test_path, past_end.as_bytes(), "bge-small").is_err());
+        assert!(import_embeddings(test_path, "".as_bytes(), "bge-small").is_err());
+        assert!(import_embeddings(test_path, records.as_bytes(), "no-such-model").is_err());
+    }
+
     #[test]
     fn test_index_single_file_with_valid_embedder_still_works() {
         let temp_dir = TempDir::new().unwrap();
@@ -1806,7 +3221,12 @@ mod tests {
         let mut boxed_embedder: Box<dyn ck_embed::Embedder> = Box::new(dummy_embedder);
 
         // This should work fine
-        let result = index_single_file(&test_file, test_path, Some(&mut boxed_embedder));
+        let result = index_single_file(
+            &test_file,
+            test_path,
+            Some(&mut boxed_embedder),
+            ChunkSettings::default(),
+        );
 
         assert!(result.is_ok());
         let entry = result.unwrap();
@@ -1818,6 +3238,73 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_prepare_files_in_order_keeps_input_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_path = temp_dir.path();
+
+        // More files than one window, each different enough to chunk at its own pace
+        let files: Vec<PathBuf> = (0..PREPARE_WINDOW * 2 + 5)
+            .map(|i| {
+                let file = test_path.join(format!("file_{i}.rs"));
+                let body = "    let x = 1;\n".repeat((i * 7) % 40 + 1);
+                fs::write(&file, format!("fn f{i}() {{\n{body}}}\n")).unwrap();
+                file
+            })
+            .collect();
+        let binary = test_path.join("blob.bin");
+        fs::write(&binary, [0u8, 159, 146, 150]).unwrap();
+        let mut inputs = files.clone();
+        inputs.insert(3, binary.clone());
+
+        let (prepared_files, worker) = prepare_files_in_order(
+            inputs.clone(),
+            test_path.to_path_buf(),
+            None,
+            ChunkSettings::default(),
+        );
+        let received: Vec<(PathBuf, Result<PreparedFile>)> = prepared_files.iter().collect();
+        worker.join().unwrap();
+
+        let order: Vec<PathBuf> = received.iter().map(|(file, _)| file.clone()).collect();
+        assert_eq!(order, inputs);
+        for (file, prepared) in &received {
+            if file == &binary {
+                assert!(prepared.is_err());
+            } else {
+                let prepared = prepared.as_ref().unwrap();
+                assert!(!prepared.chunks.is_empty());
+                assert!(prepared.chunk_cache.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_ignore_comments_keep_chunks_out_of_the_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_path = temp_dir.path();
+        let marked = test_path.join("marked.rs");
+        fs::write(
+            &marked,
+            "// ck:ignore\nfn fixture() {\n    1\n}\n\nfn kept() {\n    2\n}\n",
+        )
+        .unwrap();
+        let generated = test_path.join("generated.rs");
+        fs::write(&generated, "// ck:ignore-file\nfn generated() {}\n").unwrap();
+
+        let prepare = |file: &Path| {
+            prepare_file(file, test_path, None, false, ChunkSettings::default()).unwrap()
+        };
+        let names: Vec<Option<String>> = prepare(&marked)
+            .chunks
+            .into_iter()
+            .map(|chunk| chunk.metadata.name)
+            .collect();
+        assert!(names.contains(&Some("kept".to_string())));
+        assert!(!names.contains(&Some("fixture".to_string())));
+        assert!(prepare(&generated).chunks.is_empty());
+    }
+
     #[tokio::test]
     async fn test_smart_update_index() {
         let temp_dir = TempDir::new().unwrap();
@@ -1829,6 +3316,8 @@ mod tests {
         let file_options = ck_core::FileCollectionOptions {
             respect_gitignore: true,
             use_ckignore: true,
+            follow_symlinks: false,
+            archives: false,
             exclude_patterns: vec![],
         };
 
@@ -1891,6 +3380,8 @@ mod tests {
         let file_options = ck_core::FileCollectionOptions {
             respect_gitignore: true,
             use_ckignore: true,
+            follow_symlinks: false,
+            archives: false,
             exclude_patterns: vec![],
         };
         let stats = cleanup_index(test_path, &file_options).unwrap();
@@ -1997,6 +3488,48 @@ mod tests {
         assert!(!is_text_file(&nonexistent));
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_files_symlink_policy() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let shared = root.join("shared");
+        fs::create_dir_all(&shared).unwrap();
+        fs::write(shared.join("lib.txt"), "shared code").unwrap();
+        for service in ["api", "web"] {
+            fs::create_dir_all(root.join(service)).unwrap();
+            fs::write(root.join(service).join("main.txt"), service).unwrap();
+            symlink(&shared, root.join(service).join("shared")).unwrap();
+        }
+        // A link back to the root would recurse forever without cycle detection
+        symlink(root, shared.join("loop")).unwrap();
+
+        let mut options = ck_core::FileCollectionOptions {
+            respect_gitignore: true,
+            use_ckignore: true,
+            exclude_patterns: vec![],
+            follow_symlinks: false,
+            archives: false,
+        };
+
+        let skipped = collect_files_with_stats(root, &options).unwrap();
+        assert_eq!(skipped.files.len(), 3);
+        assert_eq!(skipped.symlinked_dirs_skipped, 3);
+
+        options.follow_symlinks = true;
+        let followed = collect_files_with_stats(root, &options).unwrap();
+        assert_eq!(followed.symlinked_dirs_skipped, 0);
+        let mut names: Vec<_> = followed
+            .files
+            .iter()
+            .map(|file| file.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["lib.txt", "main.txt", "main.txt"]);
+    }
+
     #[test]
     fn test_remove_empty_dirs() {
         let temp_dir = TempDir::new().unwrap();
@@ -2069,7 +3602,7 @@ mod cleanup_validation {
     }
 
     /// Remove a manifest entry and its associated files
-    fn remove_manifest_entry(
+    pub(super) fn remove_manifest_entry(
         manifest: &mut IndexManifest,
         manifest_path: &Path,
         repo_root: &Path,
@@ -2097,6 +3630,18 @@ mod cleanup_validation {
             }
         }
 
+        // Remove content cache for archive entries
+        if ck_core::archive::is_virtual_path(&standard_path) {
+            let cache_path = ck_core::archive::get_content_cache_path(
+                repo_root,
+                &repo_root.join(&standard_path),
+            );
+            if cache_path.exists() {
+                fs::remove_file(&cache_path)?;
+                tracing::debug!("Removed orphaned content cache: {:?}", cache_path);
+            }
+        }
+
         stats.orphaned_entries_removed += 1;
         tracing::warn!("Removed manifest entry: {:?}", manifest_path);
         Ok(())
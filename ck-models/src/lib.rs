@@ -10,6 +10,48 @@ pub struct ModelConfig {
     pub dimensions: usize,
     pub max_tokens: usize,
     pub description: String,
+    /// Smaller dimensionalities the model was trained to be truncated to
+    /// (matryoshka representation learning); empty when it wasn't
+    #[serde(default)]
+    pub matryoshka_dims: Vec<usize>,
+}
+
+/// Model names starting with this are served by an OpenAI-compatible HTTP
+/// endpoint instead of a local ONNX model, e.g. `openai:text-embedding-3-small`
+pub const REMOTE_MODEL_PREFIX: &str = "openai:";
+
+/// Provider name recorded for remote models
+pub const REMOTE_PROVIDER: &str = "openai";
+
+/// The endpoint's own name for a remote model (`openai:nomic-embed-text` ->
+/// `nomic-embed-text`), or `None` for local models
+pub fn remote_model_id(name: &str) -> Option<&str> {
+    name.strip_prefix(REMOTE_MODEL_PREFIX)
+        .filter(|model| !model.is_empty())
+}
+
+/// Config for a remote model. Dimensions are known up front only for OpenAI's
+/// own models; for anything else they are 0 until the endpoint reports them.
+pub fn remote_model_config(name: &str) -> Option<ModelConfig> {
+    let model = remote_model_id(name)?;
+    let (dimensions, max_tokens) = match model {
+        "text-embedding-3-small" | "text-embedding-ada-002" => (1536, 8191),
+        "text-embedding-3-large" => (3072, 8191),
+        _ => (0, 8192),
+    };
+    let matryoshka_dims = match model {
+        "text-embedding-3-small" => vec![1024, 512, 256],
+        "text-embedding-3-large" => vec![2048, 1536, 1024, 512, 256],
+        _ => Vec::new(),
+    };
+    Some(ModelConfig {
+        name: name.to_string(),
+        provider: REMOTE_PROVIDER.to_string(),
+        dimensions,
+        max_tokens,
+        description: format!("{} via an OpenAI-compatible embeddings endpoint", model),
+        matryoshka_dims,
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +72,7 @@ impl Default for ModelRegistry {
                 dimensions: 384,
                 max_tokens: 512,
                 description: "Small, fast English embedding model".to_string(),
+                matryoshka_dims: Vec::new(),
             },
         );
 
@@ -41,6 +84,7 @@ impl Default for ModelRegistry {
                 dimensions: 384,
                 max_tokens: 256,
                 description: "Lightweight English embedding model".to_string(),
+                matryoshka_dims: Vec::new(),
             },
         );
 
@@ -54,6 +98,7 @@ impl Default for ModelRegistry {
                 max_tokens: 8192,
                 description: "High-quality English embedding model with large context window"
                     .to_string(),
+                matryoshka_dims: vec![512, 256, 128, 64],
             },
         );
 
@@ -66,6 +111,7 @@ impl Default for ModelRegistry {
                 max_tokens: 8192,
                 description: "Code-specific embedding model optimized for programming tasks"
                     .to_string(),
+                matryoshka_dims: Vec::new(),
             },
         );
 
@@ -96,6 +142,18 @@ impl ModelRegistry {
         self.models.get(name)
     }
 
+    /// Look a model up by alias or canonical name, accepting any remote
+    /// `openai:<model>` name as well. Returns the alias with the config.
+    pub fn resolve(&self, name: &str) -> Option<(String, ModelConfig)> {
+        if let Some(config) = self.get_model(name) {
+            return Some((name.to_string(), config.clone()));
+        }
+        if let Some((alias, config)) = self.models.iter().find(|(_, config)| config.name == name) {
+            return Some((alias.clone(), config.clone()));
+        }
+        remote_model_config(name).map(|config| (config.name.clone(), config))
+    }
+
     pub fn get_default_model(&self) -> Option<&ModelConfig> {
         self.models.get(&self.default_model)
     }
@@ -0,0 +1,128 @@
+//! Chunking throughput benchmarks.
+//!
+//! Covers `ck_chunk::chunk_text_with_model` on the example fixtures (every file
+//! goes through it on each index) and `calculate_chunk_depths` on synthetic
+//! nesting (it runs on every preview render).
+//!
+//! ```text
+//! cargo bench -p ck-tui --bench chunking                              # report
+//! cargo bench -p ck-tui --bench chunking -- --save-baseline main      # record
+//! cargo bench -p ck-tui --bench chunking -- --baseline main           # compare
+//! cargo bench -p ck-tui --bench chunking -- depths                    # filter by name
+//! ```
+//!
+//! Criterion keeps baselines under `target/criterion`, so numbers only compare
+//! on the same machine; see `ck-tui/benches/BASELINE.md`.
+
+use ck_core::{Language, Span};
+use ck_tui::chunks::{IndexedChunkMeta, calculate_chunk_depths};
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+
+const MODEL: &str = "BAAI/bge-small-en-v1.5";
+
+struct Fixture {
+    name: &'static str,
+    language: Language,
+    source: &'static str,
+}
+
+const FIXTURES: &[Fixture] = &[
+    Fixture {
+        name: "rust/web_server.rs",
+        language: Language::Rust,
+        source: include_str!("../../examples/code/web_server.rs"),
+    },
+    Fixture {
+        name: "python/large_function.py",
+        language: Language::Python,
+        source: include_str!("../../examples/code/large_function.py"),
+    },
+    Fixture {
+        name: "python/full_section_demo.py",
+        language: Language::Python,
+        source: include_str!("../../examples/code/full_section_demo.py"),
+    },
+    Fixture {
+        name: "go/user_service.go",
+        language: Language::Go,
+        source: include_str!("../../examples/code/user_service.go"),
+    },
+];
+
+/// `depth` chunks each nested in the previous one, like a long chain of nested
+/// modules, impls and closures
+fn nested_chunks(depth: usize) -> Vec<IndexedChunkMeta> {
+    let lines = depth * 2 + 1;
+    (0..depth)
+        .map(|level| chunk_meta(level + 1, lines - level))
+        .collect()
+}
+
+/// `groups` top-level chunks, each holding a chain `depth` deep
+fn nested_groups(groups: usize, depth: usize) -> Vec<IndexedChunkMeta> {
+    let height = depth * 2 + 1;
+    (0..groups)
+        .flat_map(|group| {
+            let base = group * height;
+            (0..depth).map(move |level| chunk_meta(base + level + 1, base + height - level))
+        })
+        .collect()
+}
+
+fn chunk_meta(line_start: usize, line_end: usize) -> IndexedChunkMeta {
+    IndexedChunkMeta {
+        span: Span {
+            byte_start: line_start * 40,
+            byte_end: line_end * 40,
+            line_start,
+            line_end,
+        },
+        chunk_type: Some("function".to_string()),
+        breadcrumb: None,
+        ancestry: Vec::new(),
+        name: None,
+        breadcrumb_segments: Vec::new(),
+        estimated_tokens: None,
+        byte_length: None,
+        leading_trivia: None,
+        trailing_trivia: None,
+        chunk_id: None,
+    }
+}
+
+fn chunking(c: &mut Criterion) {
+    let mut group = c.benchmark_group("chunk_text_with_model");
+    for fixture in FIXTURES {
+        group.throughput(Throughput::Bytes(fixture.source.len() as u64));
+        group.bench_function(fixture.name, |b| {
+            b.iter(|| {
+                ck_chunk::chunk_text_with_model(
+                    black_box(fixture.source),
+                    Some(fixture.language),
+                    Some(MODEL),
+                )
+                .expect("fixture chunks")
+            })
+        });
+    }
+    group.finish();
+}
+
+fn chunk_depths(c: &mut Criterion) {
+    let mut group = c.benchmark_group("calculate_chunk_depths");
+    let inputs = [
+        ("nested_64", nested_chunks(64)),
+        ("nested_512", nested_chunks(512)),
+        ("groups_200x8", nested_groups(200, 8)),
+    ];
+    for (name, chunks) in &inputs {
+        group.bench_function(*name, |b| {
+            b.iter(|| calculate_chunk_depths(black_box(chunks)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, chunking, chunk_depths);
+criterion_main!(benches);
@@ -1,27 +1,47 @@
-use crate::colors::DEBOUNCE_MS;
-use crate::commands::{execute_command, show_chunks};
-use crate::config::{PreviewMode, TuiConfig};
+use crate::chunks::{
+    ColumnWindow, IndexedChunkMeta, canonical_breadcrumb, chunk_columns_for_width,
+    definition_symbol, enclosing_segment, format_chunk_as_prompt, format_chunk_for_clipboard,
+    max_column_scroll, relevant_line_range,
+};
+use crate::clipboard::copy_to_clipboard;
+use crate::colors::{CONTEXT_STEP, DEBOUNCE_MS, RELATED_CHUNKS};
+use crate::commands::{execute_command, show_chunks, show_legend};
+use crate::config::{ContextOverride, PreviewMode, TuiConfig};
 use crate::events::UiEvent;
+use crate::keymap::{Action, KeyLookup, KeyMap, KeyPress};
+use crate::minimap::{MinimapCell, adjacent_cell, chunk_minimap};
+use crate::palette::CommandPalette;
 use crate::preview::{
-    load_preview_lines, render_chunks_preview, render_heatmap_preview, render_syntax_preview,
+    elision_line, load_chunk_spans, load_preview_lines, render_chunks_preview,
+    render_heatmap_preview, render_raw_chunk, render_syntax_preview,
+};
+use crate::rendering::{
+    draw_command_palette, draw_minimap, draw_preview, draw_query_input, draw_related_chunks,
+    draw_results_list, draw_status_bar,
 };
-use crate::rendering::{draw_preview, draw_query_input, draw_results_list, draw_status_bar};
-use crate::state::{PreviewCache, TuiState};
+use crate::session::{SessionState, SessionStore, session_key};
+use crate::state::{PreviewCache, RelatedChunks, RelatedKind, TuiState, result_key};
+use crate::utils::find_repo_root;
 use anyhow::Result;
-use ck_core::{SearchMode, SearchOptions};
+use ck_core::{ResultSort, SearchMode, SearchOptions};
+use ck_engine::Feedback;
 use ck_index::get_index_stats;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{
     Frame, Terminal,
     backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     widgets::ListState,
 };
 use shlex::split;
+use std::collections::BTreeMap;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
@@ -38,6 +58,11 @@ pub struct TuiApp {
     progress_rx: UnboundedReceiver<UiEvent>,
     current_generation: u64,
     active_search: Option<JoinHandle<()>>,
+    pending_restore: Option<SessionState>,
+    keymap: KeyMap,
+    keybindings: BTreeMap<String, Vec<String>>,
+    pending_keys: Vec<KeyPress>,
+    terminal_size: Option<(u16, u16)>,
 }
 
 impl TuiApp {
@@ -45,6 +70,12 @@ impl TuiApp {
         let query = initial_query.unwrap_or_default();
         let config = TuiConfig::load();
         let (progress_tx, progress_rx) = unbounded_channel();
+        let (keymap, keymap_warnings) = KeyMap::from_config(&config.keybindings);
+        let status_message = if keymap_warnings.is_empty() {
+            "Ready. Type to search...".to_string()
+        } else {
+            format!("Keybindings: {}", keymap_warnings.join("; "))
+        };
 
         let mut app = Self {
             state: TuiState {
@@ -57,9 +88,11 @@ impl TuiApp {
                 preview_mode: config.preview_mode.clone(),
                 full_file_mode: config.full_file_mode,
                 scroll_offset: 0,
-                status_message: "Ready. Type to search...".to_string(),
+                status_message,
                 search_path,
                 selected_files: Default::default(),
+                feedback: Default::default(),
+                feedback_weights: config.feedback_weights,
                 search_history: if !query.is_empty() {
                     vec![query]
                 } else {
@@ -77,6 +110,29 @@ impl TuiApp {
                 indexing_started_at: None,
                 last_indexing_update: None,
                 search_in_progress: false,
+                copy_leading_trivia: config.copy_leading_trivia,
+                copy_whitespace: config.copy_whitespace,
+                prompt_token_budget: config.prompt_token_budget,
+                max_chunk_columns: config.max_chunk_columns,
+                chunk_column_offset: 0,
+                max_line_chars: config.max_line_chars,
+                preview_size: None,
+                context_lines: config.context_lines.clone(),
+                relevant_snippets: config.relevant_snippets,
+                relevant_snippet_lines: config.relevant_snippet_lines,
+                sibling_outline: config.sibling_outline,
+                collapse_text_chunks: config.collapse_text_chunks,
+                minimap: config.minimap,
+                chunk_syntax_highlighting: config.chunk_syntax_highlighting,
+                raw_chunk_view: false,
+                raw_chunk_scroll: 0,
+                preview_range: None,
+                extra_context_lines: 0,
+                context_override: ContextOverride::default(),
+                clipboard_fallback: Vec::new(),
+                related: None,
+                sort: None,
+                palette: None,
             },
             list_state: ListState::default(),
             last_search_time: Instant::now(),
@@ -85,11 +141,45 @@ impl TuiApp {
             progress_rx,
             current_generation: 0,
             active_search: None,
+            pending_restore: None,
+            keymap,
+            keybindings: config.keybindings,
+            pending_keys: Vec::new(),
+            terminal_size: None,
         };
         app.list_state.select(Some(0));
         app
     }
 
+    /// Restore the cursor and scroll position saved for this index when the
+    /// TUI was last closed with the same query
+    pub fn restore_session(&mut self) {
+        if self.state.query.is_empty() {
+            return;
+        }
+        let store = SessionStore::load();
+        self.pending_restore = store
+            .get(&session_key(&self.state.search_path))
+            .filter(|session| session.query == self.state.query)
+            .cloned();
+    }
+
+    fn save_session(&self) {
+        let selected = self.state.results.get(self.state.selected_idx);
+        let session = SessionState {
+            query: self.state.query.clone(),
+            selected_idx: self.state.selected_idx,
+            selected_file: selected.map(|result| result.file.clone()),
+            selected_line: selected.map(|result| result.span.line_start),
+            scroll_offset: self.state.scroll_offset,
+            chunk_column_offset: self.state.chunk_column_offset,
+            updated: 0,
+        };
+        let mut store = SessionStore::load();
+        store.insert(session_key(&self.state.search_path), session);
+        let _ = store.save(); // Silently ignore errors
+    }
+
     pub async fn run(mut self) -> Result<()> {
         // Setup terminal
         enable_raw_mode()?;
@@ -98,6 +188,8 @@ impl TuiApp {
         let backend = CrosstermBackend::new(stdout);
         let mut terminal = Terminal::new(backend)?;
 
+        self.start_warmup();
+
         // Run initial search if query provided
         if !self.state.query.is_empty() {
             self.start_search(&mut terminal)?;
@@ -106,6 +198,7 @@ impl TuiApp {
 
         // Main event loop
         let result = self.event_loop(&mut terminal).await;
+        self.save_session();
 
         // Restore terminal
         disable_raw_mode()?;
@@ -116,10 +209,18 @@ impl TuiApp {
         )?;
         terminal.show_cursor()?;
 
+        // Snippets that couldn't reach a clipboard are printed once the screen is restored
+        for snippet in &self.state.clipboard_fallback {
+            println!("{}", snippet);
+        }
+
         result
     }
 
     async fn event_loop<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        let size = terminal.size()?;
+        self.handle_resize(size.width, size.height);
+
         loop {
             self.pump_progress_events();
             terminal.draw(|f| self.draw(f))?;
@@ -135,123 +236,551 @@ impl TuiApp {
             }
 
             // Poll for events with timeout to support debouncing
-            if event::poll(Duration::from_millis(50))?
-                && let Event::Key(key) = event::read()?
+            if event::poll(Duration::from_millis(50))? {
+                match event::read()? {
+                    // Only process key press events, not release
+                    Event::Key(key) if key.kind == KeyEventKind::Press => {
+                        if self.handle_key(key)? {
+                            return Ok(());
+                        }
+                        self.pump_progress_events();
+                    }
+                    // Redrawn on the next iteration, without waiting for a key
+                    Event::Resize(width, height) => self.handle_resize(width, height),
+                    Event::Mouse(mouse) => self.handle_mouse(mouse),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Route a key press through the keymap. Returns true when the TUI should exit.
+    fn handle_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let press = KeyPress::from(key);
+
+        if self.state.palette.is_some() {
+            self.pending_keys.clear();
+            return self.handle_palette_key(key);
+        }
+
+        // Commands are typed and submitted as-is, whatever the keymap binds
+        if self.state.command_mode && (press.is_plain_char() || key.code == KeyCode::Enter) {
+            self.pending_keys.clear();
+            if key.code == KeyCode::Enter {
+                execute_command(&mut self.state)?;
+            } else {
+                self.type_key(key);
+            }
+            return Ok(false);
+        }
+
+        self.pending_keys.push(press);
+        let mut lookup = self.keymap.lookup(&self.pending_keys);
+        if lookup == KeyLookup::Unbound && self.pending_keys.len() > 1 {
+            // An abandoned sequence: start over from this key
+            self.pending_keys = vec![press];
+            lookup = self.keymap.lookup(&self.pending_keys);
+        }
+
+        match lookup {
+            // `?` and `:` are part of many regexes, so they only open the legend and
+            // the palette before typing starts
+            KeyLookup::Action(Action::ShowLegend | Action::CommandPalette)
+                if press.is_plain_char() && !self.state.query.is_empty() =>
             {
-                // Only process key press events, not release
-                if key.kind != KeyEventKind::Press {
-                    continue;
+                self.pending_keys.clear();
+                self.type_key(key);
+                Ok(false)
+            }
+            KeyLookup::Action(action) => {
+                self.pending_keys.clear();
+                self.perform(action)
+            }
+            KeyLookup::Pending => Ok(false),
+            KeyLookup::Unbound => {
+                self.pending_keys.clear();
+                self.type_key(key);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Keys typed while the command palette is open filter it; Enter runs the
+    /// selected action and Esc closes the palette
+    fn handle_palette_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let Some(palette) = self.state.palette.as_mut() else {
+            return Ok(false);
+        };
+        match key.code {
+            KeyCode::Esc => self.state.palette = None,
+            KeyCode::Enter => {
+                let action = palette.selected_action();
+                self.state.palette = None;
+                if let Some(action) = action {
+                    return self.perform(action);
                 }
+            }
+            KeyCode::Up => palette.step(-1),
+            KeyCode::Down => palette.step(1),
+            KeyCode::Backspace => palette.pop(),
+            KeyCode::Char(c) if KeyPress::from(key).is_plain_char() => palette.push(c),
+            _ => {}
+        }
+        Ok(false)
+    }
 
-                match key.code {
-                    KeyCode::Esc | KeyCode::Char('q') => {
-                        return Ok(());
-                    }
-                    KeyCode::Char('c') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                        return Ok(());
-                    }
-                    KeyCode::Char('v') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                        // Ctrl+V: Cycle preview mode
-                        self.cycle_preview_mode();
-                    }
-                    KeyCode::Char('f') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                        // Ctrl+F: Toggle snippet/full file
-                        self.toggle_full_file_mode();
-                    }
-                    KeyCode::Char('d') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                        // Ctrl+D: Show chunk metadata
-                        show_chunks(&mut self.state);
-                    }
-                    KeyCode::Char(' ') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                        // Ctrl+Space: Toggle multi-select
-                        self.toggle_select();
-                    }
-                    KeyCode::Tab => {
-                        self.cycle_mode();
-                        self.trigger_search();
-                    }
-                    KeyCode::Up if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                        // Ctrl+Up: Navigate search history
-                        self.history_previous();
-                    }
-                    KeyCode::Down if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                        // Ctrl+Down: Navigate search history
-                        self.history_next();
-                    }
-                    KeyCode::Up => {
-                        self.previous_result();
-                    }
-                    KeyCode::Down => {
-                        self.next_result();
-                    }
-                    KeyCode::PageUp => {
-                        self.scroll_up();
-                    }
-                    KeyCode::PageDown => {
-                        self.scroll_down();
-                    }
-                    KeyCode::Enter => {
-                        // In command mode, execute command; otherwise open selected file
-                        if self.state.command_mode {
-                            execute_command(&mut self.state)?;
-                        } else {
-                            self.open_selected()?;
-                        }
-                    }
-                    KeyCode::Backspace => {
-                        self.state.query.pop();
-                        // Exit command mode if we backspace the /
-                        if !self.state.query.starts_with('/') {
-                            self.state.command_mode = false;
-                        }
-                        self.trigger_search();
-                    }
-                    KeyCode::Char(c) => {
-                        // All plain characters go to search (including space, s, x, etc.)
-                        self.state.query.push(c);
+    fn perform(&mut self, action: Action) -> Result<bool> {
+        if self.state.related.is_some() && self.perform_in_related(action) {
+            return Ok(false);
+        }
 
-                        // Enter command mode if / is the first character
-                        if self.state.query == "/" {
-                            self.state.command_mode = true;
-                        }
+        match action {
+            Action::Quit => return Ok(true),
+            Action::NextMatch => self.next_result(),
+            Action::PrevMatch => self.previous_result(),
+            Action::FirstMatch => self.select_result(0),
+            Action::LastMatch => self.select_result(self.state.results.len().saturating_sub(1)),
+            Action::ScrollUp => self.scroll_up(),
+            Action::ScrollDown => self.scroll_down(),
+            Action::Open => {
+                if self.state.command_mode {
+                    execute_command(&mut self.state)?;
+                } else {
+                    self.open_selected()?;
+                }
+            }
+            Action::CopyChunk => self.copy_selected_chunk(),
+            Action::CopyPrompt => self.copy_selected_prompt(),
+            Action::CyclePreviewMode => self.cycle_preview_mode(),
+            Action::ToggleFullFile => self.toggle_full_file_mode(),
+            Action::ToggleRelevantSnippets => self.toggle_relevant_snippets(),
+            Action::ToggleSiblingOutline => self.toggle_sibling_outline(),
+            Action::ToggleCollapsedText => self.toggle_collapsed_text(),
+            Action::MinimapUp => self.step_minimap(false),
+            Action::MinimapDown => self.step_minimap(true),
+            Action::ShowChunks => show_chunks(&mut self.state),
+            Action::ToggleRawChunk => self.toggle_raw_chunk(),
+            Action::ToggleSelect => self.toggle_select(),
+            Action::CycleSearchMode => {
+                self.cycle_mode();
+                self.trigger_search();
+            }
+            Action::HistoryPrev => self.history_previous(),
+            Action::HistoryNext => self.history_next(),
+            Action::ColumnsOutward => self.scroll_chunk_columns_outward(),
+            Action::ColumnsInward => self.scroll_chunk_columns_inward(),
+            Action::ExpandContext => self.adjust_context(true),
+            Action::ShrinkContext => self.adjust_context(false),
+            Action::ToggleRelatedChunks => self.toggle_related_chunks(),
+            Action::GotoDefinition => self.goto_definition(),
+            Action::JumpToParent => self.jump_to_parent(),
+            Action::ShowLegend => show_legend(&mut self.state),
+            Action::CycleSort => {
+                self.cycle_sort();
+                self.trigger_search();
+            }
+            Action::MarkRelevant => self.mark_selected(Feedback::Relevant),
+            Action::MarkIrrelevant => self.mark_selected(Feedback::Irrelevant),
+            Action::RerankWithFeedback => self.rerank_with_feedback(),
+            Action::CommandPalette => {
+                self.state.palette = Some(CommandPalette::new(&self.keymap));
+            }
+        }
+        Ok(false)
+    }
 
-                        self.trigger_search();
-                    }
-                    _ => {}
+    /// While the related chunks panel is open, navigation moves through it, Enter
+    /// jumps to the neighbour and quit closes the panel. Returns false for actions
+    /// the panel leaves alone.
+    fn perform_in_related(&mut self, action: Action) -> bool {
+        let Some(related) = self.state.related.as_mut() else {
+            return false;
+        };
+        let last = related.neighbors.len().saturating_sub(1);
+        match action {
+            Action::NextMatch => related.selected = (related.selected + 1).min(last),
+            Action::PrevMatch => related.selected = related.selected.saturating_sub(1),
+            Action::FirstMatch => related.selected = 0,
+            Action::LastMatch => related.selected = last,
+            Action::Open => self.jump_to_related(),
+            Action::Quit => self.close_related(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Edit the query with a key no binding claimed
+    fn type_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Backspace => {
+                self.state.query.pop();
+                // Exit command mode if we backspace the /
+                if !self.state.query.starts_with('/') {
+                    self.state.command_mode = false;
                 }
-                self.pump_progress_events();
+                self.trigger_search();
+            }
+            KeyCode::Char(c) if KeyPress::from(key).is_plain_char() => {
+                // Unbound plain characters go to search (including space, s, x, etc.)
+                self.state.query.push(c);
+
+                // Enter command mode if / is the first character
+                if self.state.query == "/" {
+                    self.state.command_mode = true;
+                }
+
+                self.trigger_search();
             }
+            _ => {}
         }
     }
 
     fn draw(&mut self, f: &mut Frame) {
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(3), // Query input
-                Constraint::Min(10),   // Results + Preview
-                Constraint::Length(3), // Status bar
-            ])
-            .split(f.size());
+        let [query, results, preview, related, status] =
+            screen_areas(f.size(), self.related_rows());
 
         // Query input box
-        draw_query_input(f, chunks[0], &self.state);
-
-        // Split results and preview
-        let main_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
-            .split(chunks[1]);
+        draw_query_input(f, query, &self.state);
 
         // Results list
-        draw_results_list(f, main_chunks[0], &self.state, &mut self.list_state);
+        draw_results_list(f, results, &self.state, &mut self.list_state);
 
         // Preview pane
-        draw_preview(f, main_chunks[1], &self.state);
+        draw_preview(f, preview, &self.state);
+        if self.state.minimap {
+            draw_minimap(f, preview, &self.minimap_cells());
+        }
+        if let Some(panel) = self.state.related.as_ref() {
+            draw_related_chunks(f, related, panel);
+        }
 
         // Status bar
         self.refresh_index_stats(false);
-        draw_status_bar(f, chunks[2], &self.state);
+        draw_status_bar(f, status, &self.state);
+
+        if let Some(palette) = self.state.palette.as_ref() {
+            draw_command_palette(f, f.size(), palette);
+        }
+    }
+
+    /// Rebuild the preview for a new terminal size: the full-file window follows the
+    /// pane height and the nesting columns reflow to the pane width
+    fn handle_resize(&mut self, width: u16, height: u16) {
+        self.terminal_size = Some((width, height));
+        self.refresh_layout();
+    }
+
+    /// Recompute the preview pane size after the terminal or panel layout changed
+    fn refresh_layout(&mut self) {
+        let Some((width, height)) = self.terminal_size else {
+            return;
+        };
+        let [_, _, preview, _, _] =
+            screen_areas(Rect::new(0, 0, width, height), self.related_rows());
+        // Inside the pane's borders
+        let size = (
+            preview.width.saturating_sub(2) as usize,
+            preview.height.saturating_sub(2) as usize,
+        );
+        if self.state.preview_size == Some(size) {
+            return;
+        }
+        self.state.preview_size = Some(size);
+
+        let max_scroll = self
+            .state
+            .preview_cache
+            .as_ref()
+            .map(|cache| max_column_scroll(&cache.chunks, self.chunk_columns()))
+            .unwrap_or(0);
+        self.state.chunk_column_offset = self.state.chunk_column_offset.min(max_scroll);
+        self.update_preview();
+    }
+
+    /// Rows taken from the preview pane by the related chunks panel
+    fn related_rows(&self) -> u16 {
+        self.state
+            .related
+            .as_ref()
+            .map_or(0, |related| related.neighbors.len().max(1) as u16 + 2)
+    }
+
+    /// The innermost indexed chunk around the selected result, read from its sidecar
+    /// so its id matches the one stored in the index
+    fn selected_indexed_chunk(&self) -> Option<(PathBuf, IndexedChunkMeta)> {
+        let result = self.state.results.get(self.state.selected_idx)?;
+        let root = find_repo_root(&result.file)?;
+        let line = result.span.line_start;
+        let meta = load_chunk_spans(&root, &result.file)
+            .ok()?
+            .into_iter()
+            .filter(|meta| {
+                meta.chunk_id.is_some()
+                    && meta.span.line_start <= line
+                    && line <= meta.span.line_end
+            })
+            .min_by_key(|meta| meta.span.line_end.saturating_sub(meta.span.line_start))?;
+        Some((root, meta))
+    }
+
+    fn toggle_related_chunks(&mut self) {
+        if self
+            .state
+            .related
+            .as_ref()
+            .is_some_and(|panel| panel.kind == RelatedKind::Neighbors)
+        {
+            self.close_related();
+            return;
+        }
+
+        let Some((root, meta)) = self.selected_indexed_chunk() else {
+            self.state.status_message =
+                "Related chunks need an indexed chunk at the selected result".to_string();
+            return;
+        };
+        let Some(chunk_id) = meta.chunk_id.as_deref() else {
+            return;
+        };
+        let source = Some(canonical_breadcrumb(&meta))
+            .filter(|crumb| !crumb.is_empty())
+            .unwrap_or_else(|| {
+                format!(
+                    "{}:{}",
+                    self.state.results[self.state.selected_idx].file.display(),
+                    meta.span.line_start
+                )
+            });
+
+        match ck_engine::nearest_chunk_neighbors(&root, chunk_id, RELATED_CHUNKS) {
+            Ok(neighbors) => {
+                self.state.status_message =
+                    format!("{} chunks related to {}", neighbors.len(), source);
+                self.state.related = Some(RelatedChunks {
+                    kind: RelatedKind::Neighbors,
+                    source,
+                    neighbors,
+                    selected: 0,
+                });
+                self.refresh_layout();
+            }
+            Err(err) => {
+                self.state.status_message = format!("Related chunks failed: {}", err);
+            }
+        }
+    }
+
+    /// Look up where the identifier under the "cursor" at the selected result is
+    /// defined (see [`definition_symbol`]). One definition is jumped to directly;
+    /// several open a picker in the related chunks panel, exact names first.
+    fn goto_definition(&mut self) {
+        if self
+            .state
+            .related
+            .as_ref()
+            .is_some_and(|panel| panel.kind == RelatedKind::Definitions)
+        {
+            self.close_related();
+            return;
+        }
+
+        let Some(result) = self.state.results.get(self.state.selected_idx) else {
+            self.state.status_message = "No result selected".to_string();
+            return;
+        };
+        let Some(root) = find_repo_root(&result.file) else {
+            self.state.status_message = "Go to definition needs an indexed repository".to_string();
+            return;
+        };
+        let (lines, chunks) = match self.file_lines_and_chunks(&result.file) {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                self.state.status_message = err;
+                return;
+            }
+        };
+
+        let line = result.span.line_start;
+        let meta = chunks
+            .iter()
+            .filter(|meta| line >= meta.span.line_start && line <= meta.span.line_end)
+            .min_by_key(|meta| meta.span.line_end.saturating_sub(meta.span.line_start));
+        let end = meta
+            .map_or(result.span.line_end, |meta| meta.span.line_end)
+            .min(lines.len());
+        let start = line.saturating_sub(1).min(end);
+        let own_name = meta.and_then(|meta| meta.name.clone());
+
+        let Some(symbol) =
+            definition_symbol(&lines[start..end], &self.state.query, own_name.as_deref())
+        else {
+            self.state.status_message = "No identifier at the selected result".to_string();
+            return;
+        };
+
+        match ck_engine::find_definitions(&root, &symbol) {
+            Ok(definitions) if definitions.is_empty() => {
+                self.state.status_message = format!("No indexed chunk defines `{}`", symbol);
+            }
+            Ok(definitions) => {
+                let neighbors: Vec<ck_engine::ChunkNeighbor> = definitions
+                    .into_iter()
+                    .take(RELATED_CHUNKS)
+                    .map(|definition| {
+                        let relative = definition
+                            .file
+                            .strip_prefix(&root)
+                            .unwrap_or(&definition.file);
+                        ck_engine::ChunkNeighbor {
+                            id: ck_core::compute_chunk_id(
+                                relative,
+                                Some(&definition.breadcrumb),
+                                definition.chunk_hash.as_deref().unwrap_or_default(),
+                            ),
+                            score: if definition.exact { 1.0 } else { 0.5 },
+                            file: definition.file,
+                            span: definition.span,
+                            breadcrumb: Some(definition.breadcrumb),
+                            chunk_hash: definition.chunk_hash,
+                        }
+                    })
+                    .collect();
+                let single = neighbors.len() == 1;
+                self.state.status_message =
+                    format!("{} definitions of `{}`", neighbors.len(), symbol);
+                self.state.related = Some(RelatedChunks {
+                    kind: RelatedKind::Definitions,
+                    source: symbol,
+                    neighbors,
+                    selected: 0,
+                });
+                if single {
+                    self.jump_to_related();
+                } else {
+                    self.refresh_layout();
+                }
+            }
+            Err(err) => {
+                self.state.status_message = format!("Go to definition failed: {}", err);
+            }
+        }
+    }
+
+    fn close_related(&mut self) {
+        self.state.related = None;
+        self.refresh_layout();
+    }
+
+    /// Replace the results with the panel's neighbours, selecting the highlighted
+    /// one, so the usual preview, copy and open keys apply and the panel can be
+    /// reopened from there
+    /// Lines and chunks of `file`, from the preview cache when it holds them
+    fn file_lines_and_chunks(
+        &self,
+        file: &Path,
+    ) -> std::result::Result<(Vec<String>, Vec<IndexedChunkMeta>), String> {
+        match self.state.preview_cache.as_ref() {
+            Some(cache) if cache.file == file => Ok((cache.lines.clone(), cache.chunks.clone())),
+            _ => load_preview_lines(file).map(|(lines, _, chunks)| (lines, chunks)),
+        }
+    }
+
+    /// Point the selected result at the chunk enclosing it, one breadcrumb
+    /// segment up; pressing again keeps climbing the trail
+    fn jump_to_parent(&mut self) {
+        let Some(result) = self.state.results.get(self.state.selected_idx) else {
+            self.state.status_message = "No result selected".to_string();
+            return;
+        };
+        let chunks = match self.file_lines_and_chunks(&result.file) {
+            Ok((_, chunks)) => chunks,
+            Err(err) => {
+                self.state.status_message = err;
+                return;
+            }
+        };
+        let Some((parent, breadcrumb)) = enclosing_segment(&chunks, &result.span) else {
+            self.state.status_message = "Already at a top-level chunk".to_string();
+            return;
+        };
+
+        let path = match &breadcrumb {
+            Some(outer) => format!("{}::{}", outer, parent.name),
+            None => parent.name.clone(),
+        };
+        self.state.status_message = format!(
+            "Up to `{}` • L{}-{}",
+            path, parent.span.line_start, parent.span.line_end
+        );
+        let span = parent.span.clone();
+        let selected = self.state.selected_idx;
+        if let Some(result) = self.state.results.get_mut(selected) {
+            result.span = span;
+            result.breadcrumb = breadcrumb;
+            result.symbol = None;
+            result.chunk_hash = None;
+            result.preview.clear();
+        }
+        self.select_result(selected);
+    }
+
+    fn jump_to_related(&mut self) {
+        let Some(related) = self.state.related.take() else {
+            return;
+        };
+        if related.neighbors.is_empty() {
+            self.refresh_layout();
+            return;
+        }
+
+        self.state.results = related
+            .neighbors
+            .into_iter()
+            .map(|neighbor| ck_core::SearchResult {
+                lang: ck_core::Language::from_path(&neighbor.file),
+                file: neighbor.file,
+                span: neighbor.span,
+                score: neighbor.score,
+                preview: String::new(),
+                symbol: None,
+                breadcrumb: neighbor.breadcrumb,
+                chunk_hash: neighbor.chunk_hash,
+                index_epoch: None,
+                blame: None,
+                match_ranges: Vec::new(),
+            })
+            .collect();
+        self.state.selected_files.clear();
+        self.state.feedback.clear();
+        self.state.scroll_offset = 0;
+        self.state.status_message = match related.kind {
+            RelatedKind::Neighbors => format!(
+                "{} chunks related to {}",
+                self.state.results.len(),
+                related.source
+            ),
+            RelatedKind::Definitions if self.state.results.len() == 1 => {
+                format!("Definition of `{}`", related.source)
+            }
+            RelatedKind::Definitions => format!(
+                "{} definitions of `{}`",
+                self.state.results.len(),
+                related.source
+            ),
+        };
+        self.refresh_layout();
+        self.select_result(related.selected);
+    }
+
+    /// Nesting columns the chunk view shows at once: the configured cap, reduced
+    /// when the preview pane is too narrow to fit them beside the code
+    fn chunk_columns(&self) -> usize {
+        match (self.state.preview_size, self.state.preview_cache.as_ref()) {
+            (Some((width, _)), Some(cache)) => {
+                chunk_columns_for_width(self.state.max_chunk_columns, width, cache.lines.len())
+            }
+            _ => self.state.max_chunk_columns,
+        }
     }
 
     fn save_config(&self) {
@@ -259,6 +788,20 @@ impl TuiApp {
             search_mode: self.state.mode.clone(),
             preview_mode: self.state.preview_mode.clone(),
             full_file_mode: self.state.full_file_mode,
+            copy_leading_trivia: self.state.copy_leading_trivia,
+            copy_whitespace: self.state.copy_whitespace,
+            prompt_token_budget: self.state.prompt_token_budget,
+            max_chunk_columns: self.state.max_chunk_columns,
+            max_line_chars: self.state.max_line_chars,
+            context_lines: self.state.context_lines.clone(),
+            relevant_snippets: self.state.relevant_snippets,
+            relevant_snippet_lines: self.state.relevant_snippet_lines,
+            sibling_outline: self.state.sibling_outline,
+            collapse_text_chunks: self.state.collapse_text_chunks,
+            minimap: self.state.minimap,
+            chunk_syntax_highlighting: self.state.chunk_syntax_highlighting,
+            feedback_weights: self.state.feedback_weights,
+            keybindings: self.keybindings.clone(),
         };
         let _ = config.save(); // Silently ignore errors
     }
@@ -269,11 +812,27 @@ impl TuiApp {
             SearchMode::Regex => SearchMode::Hybrid,
             SearchMode::Hybrid => SearchMode::Semantic,
             SearchMode::Lexical => SearchMode::Semantic, // Skip lexical for now
+            SearchMode::Structural => SearchMode::Semantic,
         };
         self.state.status_message = format!("Switched to {:?} mode", self.state.mode);
         self.save_config();
     }
 
+    /// Step through relevance order and each `--sort` order
+    fn cycle_sort(&mut self) {
+        self.state.sort = match self.state.sort {
+            None => Some(ResultSort::Matches),
+            Some(ResultSort::Matches) => Some(ResultSort::Score),
+            Some(ResultSort::Score) => Some(ResultSort::Path),
+            Some(ResultSort::Path) => Some(ResultSort::Lines),
+            Some(ResultSort::Lines) => None,
+        };
+        self.state.status_message = match self.state.sort {
+            Some(sort) => format!("Sorted by {}", ck_core::RESULT_SORT_NAMES[sort as usize]),
+            None => "Sorted by relevance".to_string(),
+        };
+    }
+
     fn cycle_preview_mode(&mut self) {
         self.state.preview_mode = match self.state.preview_mode {
             PreviewMode::Heatmap => PreviewMode::Syntax,
@@ -298,20 +857,264 @@ impl TuiApp {
         self.save_config();
     }
 
+    fn toggle_relevant_snippets(&mut self) {
+        self.state.relevant_snippets = !self.state.relevant_snippets;
+        self.update_preview();
+        let mode_text = if self.state.relevant_snippets {
+            "Most relevant lines"
+        } else {
+            "Fixed window"
+        };
+        self.state.status_message = format!("Snippet: {}", mode_text);
+        self.save_config();
+    }
+
+    fn toggle_sibling_outline(&mut self) {
+        self.state.sibling_outline = !self.state.sibling_outline;
+        self.update_preview();
+        let mode_text = if self.state.sibling_outline {
+            "Collapsed siblings"
+        } else {
+            "Context lines"
+        };
+        self.state.status_message = format!("Chunk view: {}", mode_text);
+        self.save_config();
+    }
+
+    fn toggle_collapsed_text(&mut self) {
+        self.state.collapse_text_chunks = !self.state.collapse_text_chunks;
+        self.update_preview();
+        let mode_text = if self.state.collapse_text_chunks {
+            "Text chunks collapsed"
+        } else {
+            "Text chunks expanded"
+        };
+        self.state.status_message = format!("Chunk view: {}", mode_text);
+        self.save_config();
+    }
+
+    fn toggle_raw_chunk(&mut self) {
+        self.state.raw_chunk_view = !self.state.raw_chunk_view;
+        self.state.raw_chunk_scroll = 0;
+        self.update_preview();
+        self.state.status_message = if self.state.raw_chunk_view {
+            "Raw chunk view: exact bytes, · space → tab ␍ CR ↵ newline".to_string()
+        } else {
+            "Raw chunk view off".to_string()
+        };
+    }
+
+    /// The chunk map for the previewed file, one cell per row of the preview pane
+    fn minimap_cells(&self) -> Vec<MinimapCell> {
+        let (Some(cache), Some((_, rows))) =
+            (self.state.preview_cache.as_ref(), self.state.preview_size)
+        else {
+            return Vec::new();
+        };
+        // A file that failed to load leaves the previous one cached
+        let selected = self.state.results.get(self.state.selected_idx);
+        if selected.is_none_or(|result| result.file != cache.file) {
+            return Vec::new();
+        }
+        let match_lines: Vec<usize> = self
+            .state
+            .results
+            .iter()
+            .filter(|result| result.file == cache.file)
+            .map(|result| result.span.line_start)
+            .collect();
+        chunk_minimap(
+            cache.lines.len(),
+            &cache.chunks,
+            &match_lines,
+            self.state.preview_range,
+            rows,
+        )
+    }
+
+    /// Show the whole file from `line` (1-based), as a chunk map jump does
+    fn jump_to_line(&mut self, line: usize) {
+        self.state.full_file_mode = true;
+        self.state.scroll_offset = line.saturating_sub(1);
+        self.update_preview();
+        self.state.status_message = format!("Jumped to line {}", line);
+    }
+
+    fn step_minimap(&mut self, forward: bool) {
+        if let Some(cell) = adjacent_cell(&self.minimap_cells(), forward) {
+            self.jump_to_line(cell.line_start);
+        }
+    }
+
+    /// A left click on the chunk map jumps the preview to that band
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let (MouseEventKind::Down(MouseButton::Left), true, Some((width, height))) =
+            (mouse.kind, self.state.minimap, self.terminal_size)
+        else {
+            return;
+        };
+        let [_, _, preview, _, _] =
+            screen_areas(Rect::new(0, 0, width, height), self.related_rows());
+        let on_map = mouse.column + 1 == preview.x + preview.width
+            && mouse.row > preview.y
+            && mouse.row + 1 < preview.y + preview.height;
+        if !on_map {
+            return;
+        }
+        let row = (mouse.row - preview.y - 1) as usize;
+        if let Some(cell) = self.minimap_cells().get(row) {
+            self.jump_to_line(cell.line_start);
+        }
+    }
+
+    fn adjust_context(&mut self, expand: bool) {
+        self.state.extra_context_lines = if expand {
+            self.state.extra_context_lines + CONTEXT_STEP
+        } else {
+            self.state.extra_context_lines.saturating_sub(CONTEXT_STEP)
+        };
+        self.update_preview();
+        self.state.status_message = format!("Context: +{} lines", self.state.extra_context_lines);
+    }
+
     fn scroll_up(&mut self) {
-        if self.state.full_file_mode && self.state.scroll_offset > 0 {
+        if self.state.raw_chunk_view {
+            self.state.raw_chunk_scroll = self.state.raw_chunk_scroll.saturating_sub(10);
+            self.update_preview();
+        } else if self.state.full_file_mode && self.state.scroll_offset > 0 {
             self.state.scroll_offset = self.state.scroll_offset.saturating_sub(10);
             self.update_preview();
         }
     }
 
     fn scroll_down(&mut self) {
-        if self.state.full_file_mode {
+        if self.state.raw_chunk_view {
+            self.state.raw_chunk_scroll += 10;
+            self.update_preview();
+        } else if self.state.full_file_mode {
             self.state.scroll_offset += 10;
             self.update_preview();
         }
     }
 
+    /// Load the selected result's file lines, its chunks, and the smallest chunk
+    /// containing the match (or the match span itself when no chunk covers it)
+    fn selected_chunk_context(
+        &self,
+    ) -> Result<
+        (
+            PathBuf,
+            Vec<String>,
+            Vec<IndexedChunkMeta>,
+            IndexedChunkMeta,
+        ),
+        String,
+    > {
+        let result = self
+            .state
+            .results
+            .get(self.state.selected_idx)
+            .ok_or_else(|| "No result selected".to_string())?;
+
+        let (lines, chunks) = match self.state.preview_cache.as_ref() {
+            Some(cache) if cache.file == result.file => (cache.lines.clone(), cache.chunks.clone()),
+            _ => load_preview_lines(&result.file).map(|(lines, _, chunks)| (lines, chunks))?,
+        };
+
+        let line = result.span.line_start;
+        let meta = chunks
+            .iter()
+            .filter(|meta| line >= meta.span.line_start && line <= meta.span.line_end)
+            .min_by_key(|meta| meta.span.line_end.saturating_sub(meta.span.line_start))
+            .cloned()
+            .unwrap_or_else(|| IndexedChunkMeta {
+                span: result.span.clone(),
+                chunk_type: None,
+                breadcrumb: result.symbol.clone(),
+                ancestry: Vec::new(),
+                name: None,
+                breadcrumb_segments: Vec::new(),
+                estimated_tokens: None,
+                byte_length: None,
+                leading_trivia: None,
+                trailing_trivia: None,
+                chunk_id: None,
+            });
+
+        Ok((result.file.clone(), lines, chunks, meta))
+    }
+
+    /// Send text to the clipboard, queueing it for stdout on exit if that fails
+    fn deliver_to_clipboard(&mut self, text: String, what: &str) {
+        self.state.status_message = match copy_to_clipboard(&text) {
+            Ok(tool) => format!("Copied {} to clipboard ({})", what, tool),
+            Err(_) => {
+                self.state.clipboard_fallback.push(text);
+                format!("No clipboard available; {} will be printed on exit", what)
+            }
+        };
+    }
+
+    fn copy_selected_chunk(&mut self) {
+        let (file, lines, _, meta) = match self.selected_chunk_context() {
+            Ok(context) => context,
+            Err(err) => {
+                self.state.status_message = format!("Copy failed: {}", err);
+                return;
+            }
+        };
+
+        let lines = self.state.copy_whitespace.apply_lines(&lines);
+        let snippet =
+            format_chunk_for_clipboard(&lines, &meta, &file, self.state.copy_leading_trivia);
+        let line_count = meta.span.line_end.saturating_sub(meta.span.line_start) + 1;
+        self.deliver_to_clipboard(snippet, &format!("{} lines", line_count));
+    }
+
+    fn copy_selected_prompt(&mut self) {
+        let (file, lines, chunks, meta) = match self.selected_chunk_context() {
+            Ok(context) => context,
+            Err(err) => {
+                self.state.status_message = format!("Copy failed: {}", err);
+                return;
+            }
+        };
+
+        let lines = self.state.copy_whitespace.apply_lines(&lines);
+        let prompt = format_chunk_as_prompt(
+            &lines,
+            &meta,
+            &chunks,
+            &file,
+            self.state.prompt_token_budget,
+        );
+        let tokens = ck_embed::TokenEstimator::estimate_tokens(&prompt);
+        self.deliver_to_clipboard(prompt, &format!("prompt (~{} tokens)", tokens));
+    }
+
+    fn scroll_chunk_columns_outward(&mut self) {
+        if self.state.preview_mode != PreviewMode::Chunks {
+            return;
+        }
+        let max_scroll = self
+            .state
+            .preview_cache
+            .as_ref()
+            .map(|cache| max_column_scroll(&cache.chunks, self.chunk_columns()))
+            .unwrap_or(0);
+        if self.state.chunk_column_offset < max_scroll {
+            self.state.chunk_column_offset += 1;
+            self.update_preview();
+        }
+    }
+
+    fn scroll_chunk_columns_inward(&mut self) {
+        if self.state.preview_mode == PreviewMode::Chunks && self.state.chunk_column_offset > 0 {
+            self.state.chunk_column_offset -= 1;
+            self.update_preview();
+        }
+    }
+
     fn toggle_select(&mut self) {
         if let Some(result) = self.state.results.get(self.state.selected_idx) {
             let file = result.file.clone();
@@ -329,6 +1132,67 @@ impl TuiApp {
         }
     }
 
+    /// Mark the selected result, or clear the mark when it already has this one
+    fn mark_selected(&mut self, mark: Feedback) {
+        let Some(result) = self.state.results.get(self.state.selected_idx) else {
+            return;
+        };
+        let key = result_key(result);
+        let label = match mark {
+            Feedback::Relevant => "relevant",
+            Feedback::Irrelevant => "irrelevant",
+        };
+        if self.state.feedback.get(&key) == Some(&mark) {
+            self.state.feedback.remove(&key);
+            self.state.status_message = format!("Cleared mark on {}:{}", key.0.display(), key.1);
+        } else {
+            self.state.feedback.insert(key.clone(), mark);
+            self.state.status_message = format!("Marked {}:{} {}", key.0.display(), key.1, label);
+        }
+    }
+
+    /// Move the query embedding toward the results marked relevant and away
+    /// from those marked irrelevant, then re-sort the results by it
+    fn rerank_with_feedback(&mut self) {
+        if self.state.feedback.is_empty() {
+            self.state.status_message = format!(
+                "Mark results relevant ({}) or irrelevant ({}) first",
+                self.keymap.keys_for(Action::MarkRelevant).join("/"),
+                self.keymap.keys_for(Action::MarkIrrelevant).join("/")
+            );
+            return;
+        }
+        let feedback = &self.state.feedback;
+        let reranked = ck_engine::rerank_with_feedback(
+            &self.state.search_path,
+            &self.state.query,
+            &mut self.state.results,
+            |result| feedback.get(&result_key(result)).copied(),
+            self.state.feedback_weights,
+        );
+        self.state.status_message = match reranked {
+            Ok(0) => {
+                "No stored embeddings for these results; index the files for semantic search first"
+                    .to_string()
+            }
+            Ok(rescored) => {
+                let relevant = feedback
+                    .values()
+                    .filter(|mark| **mark == Feedback::Relevant)
+                    .count();
+                format!(
+                    "Re-ranked {} results by feedback ({} relevant, {} irrelevant)",
+                    rescored,
+                    relevant,
+                    feedback.len() - relevant
+                )
+            }
+            Err(e) => format!("Re-ranking failed: {}", e),
+        };
+        self.state.preview_cache = None;
+        self.select_result(0);
+    }
+
     fn history_previous(&mut self) {
         if self.state.search_history.is_empty() {
             return;
@@ -357,6 +1221,28 @@ impl TuiApp {
         self.last_search_time = Instant::now();
     }
 
+    /// Load the index's model in the background so the first semantic search
+    /// doesn't wait for it; the result shows in the status bar
+    fn start_warmup(&self) {
+        if !ck_engine::index_has_embeddings(&self.state.search_path) {
+            return;
+        }
+        let search_path = self.state.search_path.clone();
+        let sender = self.progress_tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let result = ck_engine::warm_up_embedder(&search_path, None)
+                .map(|warmup| {
+                    format!(
+                        "Model {} ready ({:.1}s)",
+                        warmup.model_name,
+                        (warmup.load_time + warmup.inference_time).as_secs_f64()
+                    )
+                })
+                .map_err(|err| format!("Model warm-up failed: {}", err));
+            let _ = sender.send(UiEvent::WarmupDone { result });
+        });
+    }
+
     fn pump_progress_events(&mut self) {
         while let Ok(event) = self.progress_rx.try_recv() {
             self.handle_progress_event(event);
@@ -408,6 +1294,25 @@ impl TuiApp {
                 }
                 self.state.status_message = message;
             }
+            UiEvent::SearchPartial {
+                generation,
+                results,
+            } => {
+                if generation != current_generation || !self.state.search_in_progress {
+                    return;
+                }
+                // Keep the cursor where it is so the list doesn't jump while filling in
+                let selected = self.state.selected_idx.min(results.len().saturating_sub(1));
+                self.state.results = results;
+                self.state.selected_idx = selected;
+                if self.state.results.is_empty() {
+                    self.list_state.select(None);
+                } else {
+                    self.list_state.select(Some(selected));
+                }
+                self.refresh_layout();
+                self.update_preview();
+            }
             UiEvent::SearchCompleted {
                 generation,
                 results,
@@ -425,6 +1330,7 @@ impl TuiApp {
                 self.state.indexing_started_at = None;
                 self.state.last_indexing_update = None;
                 self.state.selected_files.clear();
+                self.state.feedback.clear();
                 self.state.results = results;
                 self.state.selected_idx = 0;
                 self.state.scroll_offset = 0;
@@ -433,8 +1339,22 @@ impl TuiApp {
                 } else {
                     self.list_state.select(Some(0));
                 }
+                self.state.related = None;
+                self.refresh_layout();
                 self.state.preview_cache = None;
                 self.update_preview();
+                if let Some(session) = self.pending_restore.take()
+                    && session.query == query
+                    && let Some(idx) = session.cursor_in(&self.state.results)
+                {
+                    self.state.selected_idx = idx;
+                    self.list_state.select(Some(idx));
+                    self.state.scroll_offset = session.scroll_offset;
+                    self.update_preview();
+                    // Set after the first preview, which resets it when loading a new file
+                    self.state.chunk_column_offset = session.chunk_column_offset;
+                    self.update_preview();
+                }
                 self.state.status_message = summary;
 
                 if self.state.search_history.last() != Some(&query) {
@@ -447,6 +1367,12 @@ impl TuiApp {
                     self.state.history_index = self.state.search_history.len() - 1;
                 }
             }
+            UiEvent::WarmupDone { result } => {
+                // A search's own progress messages take precedence
+                if !self.state.search_in_progress {
+                    self.state.status_message = result.unwrap_or_else(|err| err);
+                }
+            }
             UiEvent::SearchFailed { generation, error } => {
                 if generation != current_generation {
                     return;
@@ -541,6 +1467,7 @@ impl TuiApp {
             SearchMode::Hybrid => None,
             SearchMode::Regex => None,
             SearchMode::Lexical => None,
+            SearchMode::Structural => None,
         };
 
         // Use the centralized pattern builder from ck-core
@@ -556,9 +1483,11 @@ impl TuiApp {
             path: self.state.search_path.clone(),
             top_k: Some(50),
             threshold,
-            case_insensitive: false,
+            // Smart case, as on the command line
+            case_insensitive: !ck_core::pattern_has_uppercase(&self.state.query, false),
             whole_word: false,
             fixed_string: false,
+            multiline: false,
             line_numbers: true,
             context_lines: 0,
             before_context_lines: 0,
@@ -566,6 +1495,9 @@ impl TuiApp {
             recursive: true,
             json_output: false,
             jsonl_output: false,
+            vimgrep_output: false,
+            compact_output: false,
+            markdown_rows: None,
             no_snippet: false,
             reindex: false,
             show_scores: true,
@@ -574,12 +1506,33 @@ impl TuiApp {
             files_without_matches: false,
             exclude_patterns,
             include_patterns: Vec::new(),
+            globs: Vec::new(),
             respect_gitignore: true,
             use_ckignore: true,
+            follow_symlinks: false,
+            archives: false,
             full_section: false,
             rerank: false,
             rerank_model: None,
             embedding_model: None,
+            search_by_example: false,
+            example_language: None,
+            example_file: None,
+            scopes: Vec::new(),
+            chunk_filter: ck_core::ChunkFilter::All,
+            chunk_types: Vec::new(),
+            diff_range: None,
+            sort: self.state.sort,
+            metric: None,
+            highlight_terms: false,
+            exact_tokens: false,
+            merge_adjacent: None,
+            blame: false,
+            show_enclosing: false,
+            recent: None,
+            references: false,
+            tests: ck_core::TestFilter::All,
+            test_patterns: Vec::new(),
         };
 
         let progress_tx = self.progress_tx.clone();
@@ -590,6 +1543,15 @@ impl TuiApp {
             let search_progress_sender = progress_tx.clone();
             let detailed_sender = progress_tx.clone();
             let completion_sender = progress_tx.clone();
+            let partial_sender = progress_tx.clone();
+
+            let partial_results_callback: ck_engine::PartialResultsCallback =
+                Box::new(move |results| {
+                    let _ = partial_sender.send(UiEvent::SearchPartial {
+                        generation,
+                        results,
+                    });
+                });
 
             let search_progress_callback: ck_engine::SearchProgressCallback =
                 Box::new(move |message: &str| {
@@ -638,11 +1600,12 @@ impl TuiApp {
                     }
                 });
 
-            let result = ck_engine::search_enhanced_with_indexing_progress(
+            let result = ck_engine::search_enhanced_streaming(
                 &options,
                 Some(search_progress_callback),
                 None, // Skip basic callback - only use detailed callback to avoid flashing
                 Some(detailed_indexing_progress_callback),
+                Some(partial_results_callback),
             )
             .await;
 
@@ -721,8 +1684,25 @@ impl TuiApp {
         self.update_preview();
     }
 
+    fn select_result(&mut self, idx: usize) {
+        if self.state.results.is_empty() {
+            return;
+        }
+        self.state.selected_idx = idx.min(self.state.results.len() - 1);
+        self.list_state.select(Some(self.state.selected_idx));
+
+        if self.state.full_file_mode
+            && let Some(result) = self.state.results.get(self.state.selected_idx)
+        {
+            self.state.scroll_offset = result.span.line_start.saturating_sub(6);
+        }
+
+        self.update_preview();
+    }
+
     fn update_preview(&mut self) {
         // Guard against empty results or invalid index
+        self.state.preview_range = None;
         if self.state.results.is_empty() {
             self.state.preview_content.clear();
             self.state.preview_lines.clear();
@@ -739,6 +1719,8 @@ impl TuiApp {
                 .unwrap_or(true);
 
             if cache_miss {
+                // Column scroll depends on the file's nesting, so start each file at the innermost levels
+                self.state.chunk_column_offset = 0;
                 match load_preview_lines(&result.file) {
                     Ok((lines, is_pdf, chunks)) => {
                         self.state.preview_cache = Some(PreviewCache {
@@ -787,25 +1769,6 @@ impl TuiApp {
                 return;
             }
 
-            // Calculate context range based on mode
-            let start_line = result
-                .span
-                .line_start
-                .saturating_sub(1)
-                .min(lines_ref.len().saturating_sub(1)); // 0-indexed
-            let mut context_start = if self.state.full_file_mode {
-                self.state
-                    .scroll_offset
-                    .min(lines_ref.len().saturating_sub(1))
-            } else {
-                start_line.saturating_sub(5)
-            };
-            let mut context_end = if self.state.full_file_mode {
-                (context_start + 40).min(lines_ref.len())
-            } else {
-                (start_line + 10).min(lines_ref.len())
-            };
-
             let chunk_meta = chunk_spans
                 .iter()
                 .filter(|meta| {
@@ -816,21 +1779,98 @@ impl TuiApp {
                 .min_by_key(|meta| meta.span.line_end.saturating_sub(meta.span.line_start))
                 .cloned();
 
-            // In Chunks mode + snippet mode, show the full chunk instead of ±5 lines
-            if self.state.preview_mode == PreviewMode::Chunks
-                && !self.state.full_file_mode
+            // The debug view shows the chunk itself, read fresh so every byte is as chunked
+            if self.state.raw_chunk_view
+                && !is_pdf
                 && let Some(meta) = chunk_meta.as_ref()
+                && let Ok(content) = std::fs::read_to_string(&result.file)
             {
-                context_start = meta
-                    .span
-                    .line_start
-                    .saturating_sub(1)
-                    .min(lines_ref.len().saturating_sub(1));
-                context_end = meta.span.line_end.min(lines_ref.len());
+                let mut lines = render_raw_chunk(&result.file, &content, meta);
+                // Page Up/Down scroll the chunk's rows under the two header lines
+                let scrollable = lines.len().saturating_sub(3);
+                self.state.raw_chunk_scroll = self.state.raw_chunk_scroll.min(scrollable);
+                lines.drain(2.min(lines.len())..(2 + self.state.raw_chunk_scroll).min(lines.len()));
+                self.state.preview_lines = lines;
+                self.state.preview_range = Some((meta.span.line_start, meta.span.line_end));
+                self.state.preview_content.clear();
+                return;
             }
 
-            if context_end <= context_start {
-                context_end = (context_start + 1).min(lines_ref.len());
+            // Context depends on what kind of chunk matched (imports need little, functions more)
+            let context_lines = self.state.context_lines.for_chunk_type(
+                chunk_meta
+                    .as_ref()
+                    .and_then(|meta| meta.chunk_type.as_deref()),
+            ) + self.state.extra_context_lines;
+
+            // In Chunks mode + snippet mode, show the full chunk plus its context lines
+            // (or just the chunk when its siblings are shown collapsed instead)
+            let sibling_outline = self.state.sibling_outline
+                && self.state.preview_mode == PreviewMode::Chunks
+                && !self.state.full_file_mode
+                && chunk_meta.is_some();
+            let (mut context_start, mut context_end) = if self.state.full_file_mode {
+                let context_start = self
+                    .state
+                    .scroll_offset
+                    .min(lines_ref.len().saturating_sub(1));
+                // One row of the pane goes to the header
+                let window = self
+                    .state
+                    .preview_size
+                    .map_or(40, |(_, height)| height.saturating_sub(1).max(1));
+                let context_end = (context_start + window).min(lines_ref.len());
+                (
+                    context_start,
+                    context_end.max((context_start + 1).min(lines_ref.len())),
+                )
+            } else if let Some(meta) = chunk_meta
+                .as_ref()
+                .filter(|_| self.state.preview_mode == PreviewMode::Chunks)
+            {
+                let (context, extra_context_lines) = if sibling_outline {
+                    let chunk_only = ContextOverride {
+                        before: Some(0),
+                        after: Some(0),
+                    };
+                    (chunk_only, 0)
+                } else {
+                    (self.state.context_override, self.state.extra_context_lines)
+                };
+                preview_window(
+                    (meta.span.line_start, meta.span.line_end),
+                    context,
+                    context_lines,
+                    extra_context_lines,
+                    true,
+                    lines_ref.len(),
+                )
+            } else {
+                preview_window(
+                    (result.span.line_start, result.span.line_end),
+                    self.state.context_override,
+                    context_lines,
+                    self.state.extra_context_lines,
+                    false,
+                    lines_ref.len(),
+                )
+            };
+
+            // Optionally narrow a large window to the lines that best match the query
+            let mut elided = (0, 0);
+            if self.state.relevant_snippets
+                && !self.state.full_file_mode
+                && let Some((start, end)) = relevant_line_range(
+                    lines_ref,
+                    context_start,
+                    context_end,
+                    &self.state.query,
+                    self.state.relevant_snippet_lines,
+                )
+            {
+                elided = (start - context_start, context_end - end);
+                context_start = start;
+                context_end = end;
             }
 
             // Validate range
@@ -844,6 +1884,8 @@ impl TuiApp {
                 return;
             }
 
+            self.state.preview_range = Some((context_start + 1, context_end));
+
             // Render based on preview mode (clone data to avoid borrow issues)
             let file_path = result.file.clone();
             let score = result.score;
@@ -859,6 +1901,7 @@ impl TuiApp {
                     score,
                     match_line,
                     &query,
+                    self.state.max_line_chars,
                 ),
                 PreviewMode::Syntax => render_syntax_preview(
                     lines_ref,
@@ -867,6 +1910,7 @@ impl TuiApp {
                     &file_path,
                     score,
                     match_line,
+                    self.state.max_line_chars,
                 ),
                 PreviewMode::Chunks => render_chunks_preview(
                     lines_ref,
@@ -880,8 +1924,27 @@ impl TuiApp {
                     &chunk_spans,
                     self.state.full_file_mode,
                     self.state.preview_mode == PreviewMode::Chunks,
+                    Some(ColumnWindow {
+                        max_columns: self.chunk_columns(),
+                        outer_offset: self.state.chunk_column_offset,
+                    }),
+                    sibling_outline,
+                    self.state.collapse_text_chunks,
+                    self.state.max_line_chars,
+                    self.state
+                        .chunk_syntax_highlighting
+                        .then(|| ck_core::Language::from_path(&file_path))
+                        .flatten(),
                 ),
             };
+            if elided.0 > 0 {
+                // Every renderer starts with a single header line
+                let at = self.state.preview_lines.len().min(1);
+                self.state.preview_lines.insert(at, elision_line(elided.0));
+            }
+            if elided.1 > 0 {
+                self.state.preview_lines.push(elision_line(elided.1));
+            }
             self.state.preview_content.clear();
         } else {
             self.state.preview_content.clear();
@@ -917,6 +1980,18 @@ impl TuiApp {
             return Ok(());
         }
 
+        // Editors can't open `lib.jar!/path` entries, so hand them a temp copy
+        let files_to_open = files_to_open
+            .into_iter()
+            .map(|(file, line)| {
+                if ck_core::archive::is_virtual_path(&file) {
+                    Ok((ck_core::archive::extract_to_temp(&file)?, line))
+                } else {
+                    Ok((file, line))
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
         let editor = std::env::var("EDITOR")
             .or_else(|_| std::env::var("VISUAL"))
             .unwrap_or_else(|_| "vim".to_string());
@@ -988,3 +2063,104 @@ impl TuiApp {
         std::process::exit(0);
     }
 }
+
+/// Query input, results list, preview pane, related chunks panel (`related_rows`
+/// tall, under the preview) and status bar for a terminal of `area`
+fn screen_areas(area: Rect, related_rows: u16) -> [Rect; 5] {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Query input
+            Constraint::Min(10),   // Results + Preview
+            Constraint::Length(3), // Status bar
+        ])
+        .split(area);
+
+    // Split results and preview
+    let main = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(rows[1]);
+
+    let preview = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(related_rows)])
+        .split(main[1]);
+
+    [rows[0], main[0], preview[0], preview[1], rows[2]]
+}
+
+/// The `[start, end)` lines, 0-indexed, that a snippet preview shows around
+/// lines `line_start..=line_end`, 1-indexed, of a file `total_lines` long.
+/// `-B`/`-A`/`-C` in `context` set the lines on their side, widened by
+/// `extra_context_lines`; a side without one gets `context_lines`, which
+/// already includes the widening. A match (not a `whole_chunk`) without `-A`
+/// shows `2 * context_lines` from its first line. Clamped to the file and
+/// never empty unless the file is.
+fn preview_window(
+    (line_start, line_end): (usize, usize),
+    context: ContextOverride,
+    context_lines: usize,
+    extra_context_lines: usize,
+    whole_chunk: bool,
+    total_lines: usize,
+) -> (usize, usize) {
+    let before = context
+        .before
+        .map_or(context_lines, |lines| lines + extra_context_lines);
+    let after = context.after.map(|lines| lines + extra_context_lines);
+    let first_line = line_start
+        .saturating_sub(1)
+        .min(total_lines.saturating_sub(1));
+    let start = first_line.saturating_sub(before);
+    let end = match after {
+        Some(after) => line_end.max(line_start) + after,
+        None if whole_chunk => line_end.max(line_start) + context_lines,
+        None => first_line + 2 * context_lines,
+    };
+    (
+        start,
+        end.min(total_lines).max((start + 1).min(total_lines)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_window_clamps_overrides_to_the_file() {
+        let unset = ContextOverride::default();
+        // Without -B/-A/-C a match gets the configured context, as before
+        assert_eq!(preview_window((10, 12), unset, 3, 0, false, 100), (6, 15));
+        assert_eq!(preview_window((10, 12), unset, 3, 0, true, 100), (6, 15));
+
+        let context = ContextOverride {
+            before: Some(5),
+            after: Some(2),
+        };
+        assert_eq!(preview_window((10, 12), context, 3, 0, false, 100), (4, 14));
+        // Expand/shrink still widen an override
+        assert_eq!(preview_window((10, 12), context, 3, 1, false, 100), (3, 15));
+
+        // At the start and end of the file the window stops at its bounds
+        let wide = ContextOverride {
+            before: Some(50),
+            after: Some(50),
+        };
+        assert_eq!(preview_window((2, 3), wide, 3, 0, false, 20), (0, 20));
+        assert_eq!(preview_window((19, 20), wide, 3, 0, true, 20), (0, 20));
+        let after_only = ContextOverride {
+            before: None,
+            after: Some(10),
+        };
+        assert_eq!(
+            preview_window((18, 20), after_only, 3, 0, false, 20),
+            (14, 20)
+        );
+
+        // A match past the end of a shrunken file still shows its last line
+        assert_eq!(preview_window((30, 30), unset, 0, 0, false, 20), (19, 20));
+        assert_eq!(preview_window((1, 1), unset, 3, 0, false, 0), (0, 0));
+    }
+}
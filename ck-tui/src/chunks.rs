@@ -1,6 +1,7 @@
+use crate::utils::{find_repo_root, truncate_line, truncation_marker};
 use ck_core::{Language, Span};
 use std::cmp::Reverse;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 
 #[derive(Clone)]
@@ -10,10 +11,29 @@ pub struct IndexedChunkMeta {
     pub chunk_type: Option<String>,
     pub breadcrumb: Option<String>,
     pub ancestry: Vec<String>,
+    /// The chunk's own name, which `ancestry` leaves out
+    pub name: Option<String>,
+    /// The enclosing chunks named by `ancestry`, outermost first, with their spans
+    pub breadcrumb_segments: Vec<ck_chunk::BreadcrumbSegment>,
     pub estimated_tokens: Option<usize>,
     pub byte_length: Option<usize>,
     pub leading_trivia: Option<Vec<String>>,
     pub trailing_trivia: Option<Vec<String>>,
+    /// Stable id from (path, breadcrumb, content hash); unchanged when only line numbers move
+    pub chunk_id: Option<String>,
+}
+
+/// The chunk's breadcrumb, derived from its ancestry so every label agrees
+///
+/// `ancestry` is what the chunker recorded and is authoritative; the stored
+/// `breadcrumb` is only used for sidecars written before ancestry was kept.
+/// Top-level chunks have an empty breadcrumb.
+pub fn canonical_breadcrumb(meta: &IndexedChunkMeta) -> String {
+    if meta.ancestry.is_empty() {
+        meta.breadcrumb.clone().unwrap_or_default()
+    } else {
+        meta.ancestry.join("::")
+    }
 }
 
 #[derive(Clone)]
@@ -34,10 +54,117 @@ pub enum ChunkDisplayLine {
         is_match_line: bool,
         in_matched_chunk: bool,
         has_any_chunk: bool,
+        /// Characters cut off the end of `text` by `truncate_long_lines`
+        truncated_chars: usize,
+        /// Syntax highlighting of `text` from `highlight_syntax`, empty until then
+        highlights: Vec<ck_chunk::HighlightSpan>,
+    },
+    /// One line of a before/after comparison produced by `collect_chunk_diff_lines`
+    Diff {
+        change: DiffChange,
+        old_line: Option<usize>,
+        new_line: Option<usize>,
+        text: String,
     },
     Message(String),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffChange {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+impl DiffChange {
+    pub fn marker(self) -> char {
+        match self {
+            DiffChange::Unchanged => ' ',
+            DiffChange::Added => '+',
+            DiffChange::Removed => '-',
+        }
+    }
+}
+
+/// First line of a multi-line chunk, one column per nesting level
+pub const GLYPH_START: char = '┌';
+/// Line inside a chunk
+pub const GLYPH_BODY: char = '│';
+/// Last line of a multi-line chunk
+pub const GLYPH_END: char = '└';
+/// A structural chunk that fits on one line
+pub const GLYPH_SINGLE_LINE: char = '─';
+/// A one-line text chunk (comment, import) outside any structural chunk
+pub const GLYPH_TEXT_LINE: char = '·';
+/// Definition line guessed from indentation when a file has no chunk metadata
+pub const GLYPH_FALLBACK_BOUNDARY: [char; 2] = ['┣', '━'];
+/// A sibling chunk collapsed to its signature by the sibling outline
+pub const GLYPH_COLLAPSED_SIBLING: char = '▸';
+/// A multi-line text chunk collapsed to one row by `collapse_text_chunks`
+pub const GLYPH_COLLAPSED_TEXT: char = '┆';
+/// Outer nesting levels scrolled out of view
+pub const GLYPH_CLIPPED: char = '‹';
+
+/// What each chunk view glyph means, as (glyph, meaning) rows, followed by how
+/// matches are highlighted. Built from the `GLYPH_*` constants the renderer uses.
+pub fn chunk_glyph_legend() -> Vec<(String, &'static str)> {
+    vec![
+        (
+            GLYPH_START.to_string(),
+            "chunk start (function, class, method, module; one column per nesting level)",
+        ),
+        (GLYPH_BODY.to_string(), "chunk body"),
+        (GLYPH_END.to_string(), "chunk end"),
+        (GLYPH_SINGLE_LINE.to_string(), "single-line chunk"),
+        (
+            GLYPH_TEXT_LINE.to_string(),
+            "single-line text chunk (comment, import); longer ones use the start/body/end brackets",
+        ),
+        (
+            GLYPH_FALLBACK_BOUNDARY.iter().collect(),
+            "definition guessed from indentation (no chunk metadata for the file)",
+        ),
+        (
+            GLYPH_COLLAPSED_SIBLING.to_string(),
+            "sibling chunk collapsed to its signature and line range",
+        ),
+        (
+            GLYPH_COLLAPSED_TEXT.to_string(),
+            "multi-line text chunk (imports, comments) collapsed to its line count",
+        ),
+        (
+            GLYPH_CLIPPED.to_string(),
+            "outer nesting levels scrolled out of view",
+        ),
+        (
+            "match".to_string(),
+            "in the TUI the matched chunk's brackets are orange, its text bright and the match line yellow",
+        ),
+    ]
+}
+
+/// Number of columns a tab expands to when estimating indentation in the no-chunk fallback
+const FALLBACK_TAB_WIDTH: usize = 4;
+
+/// Heuristic used when no chunk metadata exists: does this line start a definition?
+fn is_definition_line(line: &str) -> bool {
+    const KEYWORDS: [&str; 7] = [
+        "fn ", "func ", "def ", "class ", "impl ", "struct ", "enum ",
+    ];
+    let trimmed = line.trim_start();
+    KEYWORDS.iter().any(|keyword| trimmed.starts_with(keyword))
+}
+
+/// Leading whitespace of `line` expressed in units of `tab_width`
+fn indent_level(line: &str, tab_width: usize) -> usize {
+    let width: usize = line
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { tab_width } else { 1 })
+        .sum();
+    width / tab_width.max(1)
+}
+
 /// Calculate the global depth for each chunk across the entire file
 pub fn calculate_chunk_depths(all_chunks: &[IndexedChunkMeta]) -> HashMap<(usize, usize), usize> {
     let mut depth_map: HashMap<(usize, usize), usize> = HashMap::new();
@@ -84,6 +211,12 @@ pub fn collect_chunk_display_lines(
 ) -> Vec<ChunkDisplayLine> {
     let mut rows = Vec::new();
 
+    // Degenerate windows (empty, inverted or past the end of the file) render nothing
+    let context_end = context_end.min(lines.len());
+    if context_start >= context_end {
+        return rows;
+    }
+
     let first_line = context_start + 1;
     let last_line = context_end;
 
@@ -154,6 +287,19 @@ pub fn collect_chunk_display_lines(
         starts.sort_by_key(|meta| Reverse(meta.span.line_end.saturating_sub(meta.span.line_start)));
     }
 
+    // Without chunk metadata, definition markers are indented by nesting level;
+    // every line gets the same column width so the text stays aligned.
+    let fallback_width = if all_chunks.is_empty() {
+        lines[context_start..context_end]
+            .iter()
+            .filter(|line| is_definition_line(line))
+            .map(|line| indent_level(line, FALLBACK_TAB_WIDTH) + 2)
+            .max()
+            .unwrap_or(0)
+    } else {
+        0
+    };
+
     for (idx, line_text) in lines[context_start..context_end].iter().enumerate() {
         let line_num = context_start + idx + 1;
         let is_match_line = line_num == match_line;
@@ -183,18 +329,12 @@ pub fn collect_chunk_display_lines(
             && line_num == meta.span.line_start
         {
             let chunk_kind = meta.chunk_type.as_deref().unwrap_or("chunk");
-            let breadcrumb_text = meta
-                .breadcrumb
-                .as_deref()
-                .filter(|crumb| !crumb.is_empty())
-                .map(|crumb| format!(" ({})", crumb))
-                .unwrap_or_else(|| {
-                    if !meta.ancestry.is_empty() {
-                        format!(" ({})", meta.ancestry.join("::"))
-                    } else {
-                        String::new()
-                    }
-                });
+            let breadcrumb = canonical_breadcrumb(meta);
+            let breadcrumb_text = if breadcrumb.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", breadcrumb)
+            };
             let token_hint = meta
                 .estimated_tokens
                 .map(|tokens| format!(" • {} tokens", tokens))
@@ -210,28 +350,20 @@ pub fn collect_chunk_display_lines(
 
         // Handle files with no chunks
         if all_chunks.is_empty() {
-            let is_boundary = line_text.trim_start().starts_with("fn ")
-                || line_text.trim_start().starts_with("func ")
-                || line_text.trim_start().starts_with("def ")
-                || line_text.trim_start().starts_with("class ")
-                || line_text.trim_start().starts_with("impl ")
-                || line_text.trim_start().starts_with("struct ")
-                || line_text.trim_start().starts_with("enum ");
-
-            let columns_chars = if is_boundary {
-                vec![
-                    ChunkColumnChar {
-                        ch: '┣',
-                        is_match: false,
-                    },
-                    ChunkColumnChar {
-                        ch: '━',
-                        is_match: false,
-                    },
-                ]
-            } else {
-                Vec::new()
-            };
+            let is_boundary = is_definition_line(line_text);
+
+            let mut columns_chars = vec![
+                ChunkColumnChar {
+                    ch: ' ',
+                    is_match: false,
+                };
+                fallback_width
+            ];
+            if is_boundary {
+                let level = indent_level(line_text, FALLBACK_TAB_WIDTH);
+                columns_chars[level].ch = GLYPH_FALLBACK_BOUNDARY[0];
+                columns_chars[level + 1].ch = GLYPH_FALLBACK_BOUNDARY[1];
+            }
 
             rows.push(ChunkDisplayLine::Content {
                 columns: columns_chars,
@@ -240,6 +372,8 @@ pub fn collect_chunk_display_lines(
                 is_match_line,
                 in_matched_chunk: false,
                 has_any_chunk: is_boundary,
+                truncated_chars: 0,
+                highlights: Vec::new(),
             });
 
             continue;
@@ -256,61 +390,8 @@ pub fn collect_chunk_display_lines(
             .map(|meta| line_num >= meta.span.line_start && line_num <= meta.span.line_end)
             .unwrap_or(false);
 
-        // Build column characters for all depth levels (fixed width)
-        let mut column_chars: Vec<ChunkColumnChar> = depth_slots
-            .iter()
-            .map(|slot| {
-                if let Some(meta) = slot {
-                    let span = &meta.span;
-                    let ch = if span.line_start == span.line_end {
-                        '─'
-                    } else if line_num == span.line_start {
-                        '┌'
-                    } else if line_num == span.line_end {
-                        '└'
-                    } else {
-                        '│'
-                    };
-                    let is_match = chunk_meta
-                        .map(|m| {
-                            m.span.line_start == span.line_start && m.span.line_end == span.line_end
-                        })
-                        .unwrap_or(false);
-                    ChunkColumnChar { ch, is_match }
-                } else {
-                    ChunkColumnChar {
-                        ch: ' ',
-                        is_match: false,
-                    }
-                }
-            })
-            .collect();
-
-        // If line is ONLY in text chunk (no structural chunks), show with bracket indicator
-        if !has_any_structural && let Some(text_meta) = text_chunk_here {
-            let ch = if text_meta.span.line_start == text_meta.span.line_end {
-                // Single-line text chunk
-                '·'
-            } else if line_num == text_meta.span.line_start {
-                // Start of multi-line text chunk
-                '┌'
-            } else if line_num == text_meta.span.line_end {
-                // End of multi-line text chunk
-                '└'
-            } else {
-                // Middle of multi-line text chunk
-                '│'
-            };
-
-            if column_chars.is_empty() {
-                column_chars.push(ChunkColumnChar {
-                    ch,
-                    is_match: false,
-                });
-            } else {
-                column_chars[0].ch = ch;
-            }
-        }
+        let column_chars =
+            line_columns(&depth_slots, text_chunk_here.copied(), line_num, chunk_meta);
 
         rows.push(ChunkDisplayLine::Content {
             columns: column_chars,
@@ -319,6 +400,8 @@ pub fn collect_chunk_display_lines(
             is_match_line,
             in_matched_chunk,
             has_any_chunk,
+            truncated_chars: 0,
+            highlights: Vec::new(),
         });
 
         // Remove chunks that end at this line
@@ -341,8 +424,639 @@ pub fn collect_chunk_display_lines(
     rows
 }
 
+/// Bracket column characters for `line_num`, given the structural chunk holding
+/// each depth slot there and the text chunk covering the line, if any
+fn line_columns(
+    depth_slots: &[Option<&IndexedChunkMeta>],
+    text_chunk_here: Option<&IndexedChunkMeta>,
+    line_num: usize,
+    chunk_meta: Option<&IndexedChunkMeta>,
+) -> Vec<ChunkColumnChar> {
+    // Build column characters for all depth levels (fixed width)
+    let mut column_chars: Vec<ChunkColumnChar> = depth_slots
+        .iter()
+        .map(|slot| {
+            if let Some(meta) = slot {
+                let span = &meta.span;
+                let ch = if span.line_start == span.line_end {
+                    GLYPH_SINGLE_LINE
+                } else if line_num == span.line_start {
+                    GLYPH_START
+                } else if line_num == span.line_end {
+                    GLYPH_END
+                } else {
+                    GLYPH_BODY
+                };
+                let is_match = chunk_meta
+                    .map(|m| {
+                        m.span.line_start == span.line_start && m.span.line_end == span.line_end
+                    })
+                    .unwrap_or(false);
+                ChunkColumnChar { ch, is_match }
+            } else {
+                ChunkColumnChar {
+                    ch: ' ',
+                    is_match: false,
+                }
+            }
+        })
+        .collect();
+
+    // If line is ONLY in text chunk (no structural chunks), show with bracket indicator
+    let has_any_structural = depth_slots.iter().any(|slot| slot.is_some());
+    if !has_any_structural && let Some(text_meta) = text_chunk_here {
+        let ch = if text_meta.span.line_start == text_meta.span.line_end {
+            // Single-line text chunk
+            GLYPH_TEXT_LINE
+        } else if line_num == text_meta.span.line_start {
+            // Start of multi-line text chunk
+            GLYPH_START
+        } else if line_num == text_meta.span.line_end {
+            // End of multi-line text chunk
+            GLYPH_END
+        } else {
+            // Middle of multi-line text chunk
+            GLYPH_BODY
+        };
+
+        if column_chars.is_empty() {
+            column_chars.push(ChunkColumnChar {
+                ch,
+                is_match: false,
+            });
+        } else {
+            column_chars[0].ch = ch;
+        }
+    }
+
+    column_chars
+}
+
+/// The bracket columns the full-file chunk view shows for `line` (1-based),
+/// without building the other rows, e.g. for an editor gutter. `max_depth` is
+/// the column count, which the chunk view takes from [`calculate_max_depth`]
+/// over the non-text chunks in `metas`; deeper chunks are left out as they are
+/// there. No column is marked as the match. Files without chunk metadata give
+/// no columns, since the chunk view's indentation fallback needs the text.
+pub fn bracket_columns_at(
+    metas: &[IndexedChunkMeta],
+    line: usize,
+    max_depth: usize,
+) -> Vec<ChunkColumnChar> {
+    if metas.is_empty() {
+        return Vec::new();
+    }
+
+    let (text_chunks, structural_chunks): (Vec<IndexedChunkMeta>, Vec<IndexedChunkMeta>) = metas
+        .iter()
+        .cloned()
+        .partition(|meta| meta.chunk_type.as_deref() == Some("text"));
+    let depth_map = calculate_chunk_depths(&structural_chunks);
+
+    // Replay slot assignment in the order the chunk view meets chunk starts:
+    // by start line, longest first. A slot's holder is cleared once it has ended.
+    let mut starting: Vec<&IndexedChunkMeta> = structural_chunks
+        .iter()
+        .filter(|meta| meta.span.line_start <= line)
+        .collect();
+    starting.sort_by_key(|meta| {
+        (
+            meta.span.line_start,
+            Reverse(meta.span.line_end.saturating_sub(meta.span.line_start)),
+        )
+    });
+
+    let mut depth_slots: Vec<Option<&IndexedChunkMeta>> = vec![None; max_depth];
+    let clear_ended = |slots: &mut [Option<&IndexedChunkMeta>], before: usize| {
+        for slot in slots.iter_mut() {
+            if slot.is_some_and(|meta| meta.span.line_end < before) {
+                *slot = None;
+            }
+        }
+    };
+    for meta in starting {
+        clear_ended(&mut depth_slots, meta.span.line_start);
+        if let Some(&depth) = depth_map.get(&(meta.span.line_start, meta.span.line_end))
+            && depth < max_depth
+        {
+            depth_slots[depth] = Some(meta);
+        }
+    }
+    clear_ended(&mut depth_slots, line);
+
+    let text_chunk_here = text_chunks
+        .iter()
+        .find(|meta| line >= meta.span.line_start && line <= meta.span.line_end);
+    line_columns(&depth_slots, text_chunk_here, line, None)
+}
+
+/// Chunks at the same depth as `meta` under the same parent, split into those
+/// before and after it
+fn sibling_chunks<'a>(
+    meta: &IndexedChunkMeta,
+    structural_chunks: &'a [IndexedChunkMeta],
+    depth_map: &HashMap<(usize, usize), usize>,
+) -> (Vec<&'a IndexedChunkMeta>, Vec<&'a IndexedChunkMeta>) {
+    let key = (meta.span.line_start, meta.span.line_end);
+    let Some(&depth) = depth_map.get(&key) else {
+        return (Vec::new(), Vec::new());
+    };
+    let parent = enclosing_chunks(meta, structural_chunks).pop();
+
+    let mut siblings: Vec<&IndexedChunkMeta> = structural_chunks
+        .iter()
+        .filter(|candidate| {
+            let span = &candidate.span;
+            (span.line_start, span.line_end) != key
+                && depth_map.get(&(span.line_start, span.line_end)) == Some(&depth)
+                && parent.is_none_or(|parent| {
+                    parent.span.line_start <= span.line_start
+                        && span.line_end <= parent.span.line_end
+                })
+        })
+        .collect();
+    siblings.sort_by_key(|sibling| sibling.span.line_start);
+    siblings.dedup_by_key(|sibling| (sibling.span.line_start, sibling.span.line_end));
+
+    let (before, after): (Vec<_>, Vec<_>) = siblings
+        .into_iter()
+        .partition(|sibling| sibling.span.line_start < meta.span.line_start);
+    (before, after)
+}
+
+/// Surround the rows of the matched chunk with one collapsed label per sibling
+/// chunk, showing just its signature line, so the chunk's place in its parent is
+/// visible without scrolling. `rows` should cover only the matched chunk.
+pub fn add_sibling_outline(
+    rows: &mut Vec<ChunkDisplayLine>,
+    lines: &[String],
+    meta: &IndexedChunkMeta,
+    all_chunks: &[IndexedChunkMeta],
+) {
+    let structural_chunks: Vec<IndexedChunkMeta> = all_chunks
+        .iter()
+        .filter(|chunk| chunk.chunk_type.as_deref() != Some("text"))
+        .cloned()
+        .collect();
+    let depth_map = calculate_chunk_depths(&structural_chunks);
+    let prefix = calculate_max_depth(&structural_chunks);
+    let (before, after) = sibling_chunks(meta, &structural_chunks, &depth_map);
+
+    let collapsed = |sibling: &IndexedChunkMeta| ChunkDisplayLine::Label {
+        prefix,
+        text: format!(
+            "{} {} · L{}-{}",
+            GLYPH_COLLAPSED_SIBLING,
+            signature_line(lines, sibling)
+                .map(|line| line.trim())
+                .unwrap_or_else(|| sibling.chunk_type.as_deref().unwrap_or("chunk")),
+            sibling.span.line_start,
+            sibling.span.line_end
+        ),
+    };
+
+    let above: Vec<ChunkDisplayLine> = before.into_iter().map(collapsed).collect();
+    rows.splice(0..0, above);
+    rows.extend(after.into_iter().map(collapsed));
+}
+
+/// Line starts that mark an import or include
+const IMPORT_PREFIXES: &[&str] = &[
+    "use ",
+    "pub use ",
+    "import ",
+    "from ",
+    "#include",
+    "require",
+    "extern crate ",
+    "using ",
+    "package ",
+    "@import",
+];
+
+/// Line starts that mark a comment
+const COMMENT_PREFIXES: &[&str] = &["//", "/*", "*", "#", "--", ";", "<!--", "\"\"\""];
+
+/// What a collapsed text chunk holds: `imports` when its first line of code is
+/// an import, `comments` when it's all comments, else `text`
+fn text_chunk_label(lines: &[String]) -> &'static str {
+    let mut code = lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty());
+    let first_code = code.find(|line| {
+        IMPORT_PREFIXES
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+            || !COMMENT_PREFIXES
+                .iter()
+                .any(|prefix| line.starts_with(prefix))
+    });
+    match first_code {
+        Some(line)
+            if IMPORT_PREFIXES
+                .iter()
+                .any(|prefix| line.starts_with(prefix)) =>
+        {
+            "imports"
+        }
+        Some(_) => "text",
+        None if lines.iter().any(|line| !line.trim().is_empty()) => "comments",
+        None => "text",
+    }
+}
+
+/// Replace the rows of each multi-line text chunk outside any structural chunk
+/// (an import block, a license header) with one `┆ imports (12 lines)` row.
+/// Text chunks holding the match stay open; structural chunks are untouched.
+pub fn collapse_text_chunks(
+    rows: &mut Vec<ChunkDisplayLine>,
+    lines: &[String],
+    all_chunks: &[IndexedChunkMeta],
+) {
+    let (text_chunks, structural_chunks): (Vec<IndexedChunkMeta>, Vec<IndexedChunkMeta>) =
+        all_chunks
+            .iter()
+            .cloned()
+            .partition(|chunk| chunk.chunk_type.as_deref() == Some("text"));
+    let prefix = calculate_max_depth(&structural_chunks);
+    let collapsible: Vec<&IndexedChunkMeta> = text_chunks
+        .iter()
+        .filter(|text| {
+            text.span.line_end > text.span.line_start
+                && !structural_chunks.iter().any(|chunk| {
+                    chunk.span.line_start <= text.span.line_start
+                        && text.span.line_end <= chunk.span.line_end
+                })
+        })
+        .collect();
+    let chunk_at = |row: &ChunkDisplayLine| match row {
+        ChunkDisplayLine::Content { line_num, .. } => collapsible
+            .iter()
+            .position(|text| text.span.line_start <= *line_num && *line_num <= text.span.line_end),
+        _ => None,
+    };
+
+    let mut collapsed = Vec::with_capacity(rows.len());
+    let mut pending = std::mem::take(rows).into_iter().peekable();
+    while let Some(row) = pending.next() {
+        let Some(index) = chunk_at(&row) else {
+            collapsed.push(row);
+            continue;
+        };
+        let mut group = vec![row];
+        while let Some(next) = pending.next_if(|next| chunk_at(next) == Some(index)) {
+            group.push(next);
+        }
+
+        let holds_match = group.iter().any(|row| {
+            matches!(
+                row,
+                ChunkDisplayLine::Content {
+                    is_match_line: true,
+                    ..
+                } | ChunkDisplayLine::Content {
+                    in_matched_chunk: true,
+                    ..
+                }
+            )
+        });
+        if holds_match {
+            collapsed.extend(group);
+            continue;
+        }
+        let span = &collapsible[index].span;
+        let end = span.line_end.min(lines.len());
+        let start = span.line_start.saturating_sub(1).min(end);
+        collapsed.push(ChunkDisplayLine::Label {
+            prefix,
+            text: format!(
+                "{} {} ({} lines)",
+                GLYPH_COLLAPSED_TEXT,
+                text_chunk_label(&lines[start..end]),
+                span.line_end - span.line_start + 1
+            ),
+        });
+    }
+    *rows = collapsed;
+}
+
+fn relevance_tokens(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| token.chars().count() >= 2)
+        .map(str::to_lowercase)
+}
+
+/// Pick the `max_lines`-long run of lines in `lines[start..end]` that shares the
+/// most query tokens, scoring each line by how many distinct query tokens it
+/// contains. Returns the 0-based half-open range, or `None` when the window
+/// already fits or no line mentions the query.
+pub fn relevant_line_range(
+    lines: &[String],
+    start: usize,
+    end: usize,
+    query: &str,
+    max_lines: usize,
+) -> Option<(usize, usize)> {
+    let end = end.min(lines.len());
+    if max_lines == 0 || start >= end || end - start <= max_lines {
+        return None;
+    }
+
+    let query_tokens: HashSet<String> = relevance_tokens(query).collect();
+    if query_tokens.is_empty() {
+        return None;
+    }
+
+    let scores: Vec<usize> = lines[start..end]
+        .iter()
+        .map(|line| {
+            relevance_tokens(line)
+                .filter(|token| query_tokens.contains(token))
+                .collect::<HashSet<_>>()
+                .len()
+        })
+        .collect();
+
+    let mut window: usize = scores[..max_lines].iter().sum();
+    let (mut best, mut best_offset) = (window, 0);
+    for offset in 1..=scores.len() - max_lines {
+        window = window + scores[offset + max_lines - 1] - scores[offset - 1];
+        if window > best {
+            best = window;
+            best_offset = offset;
+        }
+    }
+
+    (best > 0).then(|| (start + best_offset, start + best_offset + max_lines))
+}
+
+/// Words that can sit before `(` without naming something that's defined
+const NOT_A_CALL: &[&str] = &[
+    "if", "for", "while", "match", "return", "fn", "def", "function", "func", "switch", "catch",
+    "elif", "and", "or", "not", "in", "new", "Some", "Ok", "Err", "None", "print", "println",
+];
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The identifier touching byte `column` of `line`, if any
+pub fn identifier_at(line: &str, column: usize) -> Option<&str> {
+    let column = column.min(line.len());
+    if !line.is_char_boundary(column) {
+        return None;
+    }
+    let start = line[..column]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_identifier_char(*c))
+        .last()
+        .map_or(column, |(idx, _)| idx);
+    let end = line[column..]
+        .find(|c: char| !is_identifier_char(c))
+        .map_or(line.len(), |offset| column + offset);
+    let word = &line[start..end];
+    // Numbers aren't names
+    (!word.is_empty() && !word.starts_with(|c: char| c.is_ascii_digit())).then_some(word)
+}
+
+/// The chunk one breadcrumb segment up from the chunk at `span`: the last
+/// enclosing segment of the chunk with exactly that span (else the innermost
+/// chunk around its first line), with the breadcrumb of that enclosing chunk
+/// itself. `None` for top-level chunks.
+pub fn enclosing_segment<'a>(
+    chunks: &'a [IndexedChunkMeta],
+    span: &Span,
+) -> Option<(&'a ck_chunk::BreadcrumbSegment, Option<String>)> {
+    let meta = chunks
+        .iter()
+        .find(|meta| meta.span.line_start == span.line_start && meta.span.line_end == span.line_end)
+        .or_else(|| {
+            chunks
+                .iter()
+                .filter(|meta| {
+                    meta.span.line_start <= span.line_start && span.line_start <= meta.span.line_end
+                })
+                .min_by_key(|meta| meta.span.line_end - meta.span.line_start)
+        })?;
+    let (parent, outer) = meta.breadcrumb_segments.split_last()?;
+    let breadcrumb = (!outer.is_empty()).then(|| {
+        outer
+            .iter()
+            .map(|segment| segment.name.as_str())
+            .collect::<Vec<_>>()
+            .join("::")
+    });
+    Some((parent, breadcrumb))
+}
+
+/// The symbol to look up when asking for the definition of what's "under the
+/// cursor" in `lines`, the selected chunk starting at its match line. The TUI
+/// has no text cursor, so this is the first identifier matching a query word,
+/// else the first called name that isn't a keyword or `own_name` (the chunk's
+/// own name, which would only find the chunk itself).
+pub fn definition_symbol(lines: &[String], query: &str, own_name: Option<&str>) -> Option<String> {
+    let query_tokens: HashSet<String> = relevance_tokens(query).collect();
+    // Each identifier in order with the text after it
+    let identifiers = || {
+        lines.iter().flat_map(|line| {
+            let mut words = Vec::new();
+            let mut offset = 0;
+            for part in line.split(|c: char| !is_identifier_char(c)) {
+                let end = offset + part.len();
+                if let Some(word) = identifier_at(line, offset).filter(|_| !part.is_empty()) {
+                    words.push((word, line[end..].trim_start()));
+                }
+                // Skip the separating character
+                offset = end + line[end..].chars().next().map_or(0, char::len_utf8);
+            }
+            words
+        })
+    };
+
+    if let Some((word, _)) =
+        identifiers().find(|(word, _)| query_tokens.contains(&word.to_lowercase()))
+    {
+        return Some(word.to_string());
+    }
+    identifiers()
+        .find(|(word, rest)| {
+            rest.starts_with('(') && !NOT_A_CALL.contains(word) && Some(*word) != own_name
+        })
+        .map(|(word, _)| word.to_string())
+}
+
+/// Horizontal viewport over the chunk column region
+#[derive(Clone, Copy, Debug)]
+pub struct ColumnWindow {
+    /// Maximum number of nesting columns shown at once
+    pub max_columns: usize,
+    /// Levels scrolled outward from the innermost (nearest the code) columns
+    pub outer_offset: usize,
+}
+
+/// Number of nesting columns the structural chunks of a file need
+fn structural_column_count(all_chunks: &[IndexedChunkMeta]) -> usize {
+    let structural: Vec<IndexedChunkMeta> = all_chunks
+        .iter()
+        .filter(|meta| meta.chunk_type.as_deref() != Some("text"))
+        .cloned()
+        .collect();
+    if structural.is_empty() {
+        0
+    } else {
+        calculate_max_depth(&structural)
+    }
+}
+
+/// Largest useful `outer_offset` for a file under a given column cap
+pub fn max_column_scroll(all_chunks: &[IndexedChunkMeta], max_columns: usize) -> usize {
+    structural_column_count(all_chunks).saturating_sub(max_columns)
+}
+
+/// Code kept visible beside the nesting columns when a narrow pane forces them to reflow
+const MIN_CHUNK_TEXT_WIDTH: usize = 20;
+
+/// Nesting columns that fit in a preview pane `pane_width` cells wide beside the
+/// line-number gutter of a `line_count`-line file, capped at `max_columns`
+pub fn chunk_columns_for_width(max_columns: usize, pane_width: usize, line_count: usize) -> usize {
+    // Scroll indicator, separating space, then "{line_num:width$} | "
+    let gutter = 2 + line_count.to_string().len() + 1 + 3;
+    pane_width
+        .saturating_sub(gutter + MIN_CHUNK_TEXT_WIDTH)
+        .clamp(1, max_columns.max(1))
+}
+
+/// Restrict every row's columns to `window`, keeping the innermost levels by
+/// default and prepending an indicator column that shows `‹` when outer
+/// levels are scrolled off. Rows are left untouched if they already fit.
+pub fn clip_chunk_columns(rows: &mut [ChunkDisplayLine], window: ColumnWindow) {
+    let total = rows
+        .iter()
+        .map(|row| match row {
+            ChunkDisplayLine::Content { columns, .. } => columns.len(),
+            _ => 0,
+        })
+        .max()
+        .unwrap_or(0);
+    let max_columns = window.max_columns.max(1);
+    if total <= max_columns {
+        return;
+    }
+
+    let hidden = total - max_columns;
+    let start = hidden - window.outer_offset.min(hidden);
+    let visible_width = max_columns + 1;
+
+    for row in rows.iter_mut() {
+        match row {
+            ChunkDisplayLine::Content { columns, .. } => {
+                columns.resize(
+                    total,
+                    ChunkColumnChar {
+                        ch: ' ',
+                        is_match: false,
+                    },
+                );
+                let outer_match = columns[..start].iter().any(|col| col.is_match);
+                let mut clipped = Vec::with_capacity(visible_width);
+                clipped.push(ChunkColumnChar {
+                    ch: if start > 0 { GLYPH_CLIPPED } else { ' ' },
+                    is_match: outer_match,
+                });
+                clipped.extend_from_slice(&columns[start..start + max_columns]);
+                *columns = clipped;
+            }
+            ChunkDisplayLine::Label { prefix, .. } if *prefix == total => {
+                *prefix = visible_width;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Fill in the `highlights` of every content row from a tree-sitter parse of
+/// the whole file, so strings and comments spanning lines are colored right.
+/// Rows are left plain when `language` has no grammar.
+pub fn highlight_syntax(rows: &mut [ChunkDisplayLine], lines: &[String], language: Language) {
+    let Some(highlighted) = ck_chunk::highlight_lines(&lines.join("\n"), language) else {
+        return;
+    };
+    for row in rows.iter_mut() {
+        if let ChunkDisplayLine::Content {
+            line_num,
+            highlights,
+            ..
+        } = row
+            && let Some(spans) = highlighted.get(line_num.wrapping_sub(1))
+        {
+            *highlights = spans.clone();
+        }
+    }
+}
+
+/// Cut every content row's text to `max_chars` characters (see
+/// [`truncate_line`]), so a minified line of 200k characters doesn't stall
+/// rendering. Columns and gutter are untouched.
+pub fn truncate_long_lines(rows: &mut [ChunkDisplayLine], max_chars: usize) {
+    for row in rows.iter_mut() {
+        if let ChunkDisplayLine::Content {
+            text,
+            truncated_chars,
+            ..
+        } = row
+        {
+            let (visible, hidden) = truncate_line(text, max_chars);
+            if hidden > 0 {
+                let visible_len = visible.len();
+                text.truncate(visible_len);
+                *truncated_chars += hidden;
+            }
+        }
+    }
+}
+
+/// What the gutter of a rendered content line shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GutterMode {
+    /// 1-based line numbers
+    #[default]
+    Lines,
+    /// The byte range of each line instead of its number
+    Bytes,
+    /// Line numbers followed by byte ranges
+    Both,
+}
+
+/// Byte range `[start, end)` of each line in the file the lines came from,
+/// excluding its newline. Every line is taken to end in a single `\n`, so
+/// files with `\r\n` endings are off by one byte per preceding line.
+pub fn line_byte_ranges(lines: &[String]) -> Vec<(usize, usize)> {
+    let mut offset = 0;
+    lines
+        .iter()
+        .map(|line| {
+            let start = offset;
+            offset += line.len() + 1;
+            (start, start + line.len())
+        })
+        .collect()
+}
+
 /// Convert ChunkDisplayLine to plain text string
 pub fn chunk_display_line_to_string(line: &ChunkDisplayLine) -> String {
+    chunk_display_line_to_string_with_gutter(line, GutterMode::Lines, &[])
+}
+
+/// Convert ChunkDisplayLine to plain text, with the gutter chosen by `gutter`.
+/// `byte_ranges` comes from [`line_byte_ranges`]; byte columns are padded to
+/// the widest offset so they line up.
+pub fn chunk_display_line_to_string_with_gutter(
+    line: &ChunkDisplayLine,
+    gutter: GutterMode,
+    byte_ranges: &[(usize, usize)],
+) -> String {
     match line {
         ChunkDisplayLine::Label { prefix, text } => {
             format!("{}{}", " ".repeat(*prefix), text)
@@ -351,6 +1065,7 @@ pub fn chunk_display_line_to_string(line: &ChunkDisplayLine) -> String {
             columns,
             line_num,
             text,
+            truncated_chars,
             ..
         } => {
             let mut output = String::new();
@@ -364,32 +1079,514 @@ pub fn chunk_display_line_to_string(line: &ChunkDisplayLine) -> String {
             output.push(' ');
 
             // Add line number with fixed width (at least 4 chars)
-            output.push_str(&format!("{:4} | ", line_num));
+            if gutter != GutterMode::Bytes {
+                output.push_str(&format!("{:4} ", line_num));
+            }
+            if gutter != GutterMode::Lines {
+                let width = byte_ranges
+                    .last()
+                    .map_or(1, |(_, end)| end.to_string().len());
+                match line_num.checked_sub(1).and_then(|i| byte_ranges.get(i)) {
+                    Some((start, end)) => {
+                        output.push_str(&format!("{:>width$}..{:<width$} ", start, end))
+                    }
+                    None => output.push_str(&" ".repeat(width * 2 + 3)),
+                }
+            }
+            output.push_str("| ");
 
             // Add line text
             output.push_str(text);
+            if *truncated_chars > 0 {
+                output.push_str(&truncation_marker(*truncated_chars));
+            }
 
             output
         }
+        ChunkDisplayLine::Diff {
+            change,
+            old_line,
+            new_line,
+            text,
+        } => format!(
+            "{} {} {} | {}",
+            change.marker(),
+            format_optional_line(*old_line),
+            format_optional_line(*new_line),
+            text
+        ),
         ChunkDisplayLine::Message(msg) => msg.clone(),
     }
 }
 
+/// A display row split into independently stylable regions
+#[derive(Clone)]
+pub struct RenderedCells {
+    /// Column glyphs to the left of the gutter, with their match flags
+    pub prefix: Vec<ChunkColumnChar>,
+    /// Line number for the gutter; `None` for labels and messages
+    pub line_num: Option<usize>,
+    /// Source text (or label/message text)
+    pub text: String,
+}
+
+/// Split a display row into prefix cells, gutter line number and text so
+/// embedding UIs can style each region without parsing the string form.
+///
+/// Labels get blank prefix cells matching their indent; diff rows carry their
+/// `+`/`-` marker as the single prefix cell and number the gutter with the
+/// new-side line, falling back to the old side for removed lines.
+pub fn chunk_display_line_to_cells(line: &ChunkDisplayLine) -> RenderedCells {
+    match line {
+        ChunkDisplayLine::Label { prefix, text } => RenderedCells {
+            prefix: vec![
+                ChunkColumnChar {
+                    ch: ' ',
+                    is_match: false,
+                };
+                *prefix
+            ],
+            line_num: None,
+            text: text.clone(),
+        },
+        ChunkDisplayLine::Content {
+            columns,
+            line_num,
+            text,
+            truncated_chars,
+            ..
+        } => RenderedCells {
+            prefix: columns.clone(),
+            line_num: Some(*line_num),
+            text: if *truncated_chars > 0 {
+                format!("{}{}", text, truncation_marker(*truncated_chars))
+            } else {
+                text.clone()
+            },
+        },
+        ChunkDisplayLine::Diff {
+            change,
+            old_line,
+            new_line,
+            text,
+        } => RenderedCells {
+            prefix: vec![ChunkColumnChar {
+                ch: change.marker(),
+                is_match: *change != DiffChange::Unchanged,
+            }],
+            line_num: new_line.or(*old_line),
+            text: text.clone(),
+        },
+        ChunkDisplayLine::Message(msg) => RenderedCells {
+            prefix: Vec::new(),
+            line_num: None,
+            text: msg.clone(),
+        },
+    }
+}
+
+fn format_optional_line(line: Option<usize>) -> String {
+    line.map(|n| format!("{:4}", n))
+        .unwrap_or_else(|| " ".repeat(4))
+}
+
+fn chunk_lines<'a>(lines: &'a [String], meta: &IndexedChunkMeta) -> &'a [String] {
+    let start = meta.span.line_start.saturating_sub(1).min(lines.len());
+    let end = meta.span.line_end.min(lines.len()).max(start);
+    &lines[start..end]
+}
+
+/// Longest common subsequence table; `table[i][j]` is the LCS length of `old[i..]` and `new[j..]`
+fn lcs_table(old: &[String], new: &[String]) -> Vec<Vec<u32>> {
+    let (n, m) = (old.len(), new.len());
+    let mut table = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if old[i] == new[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+    table
+}
+
+/// Append a header plus interleaved rows for one pair of chunk versions
+fn push_chunk_diff(
+    rows: &mut Vec<ChunkDisplayLine>,
+    breadcrumb: &str,
+    old: Option<(&IndexedChunkMeta, &[String])>,
+    new: Option<(&IndexedChunkMeta, &[String])>,
+) {
+    let describe = |side: Option<(&IndexedChunkMeta, &[String])>| {
+        side.map(|(meta, _)| format!("L{}-{}", meta.span.line_start, meta.span.line_end))
+            .unwrap_or_else(|| "absent".to_string())
+    };
+    let chunk_kind = old
+        .or(new)
+        .and_then(|(meta, _)| meta.chunk_type.as_deref())
+        .unwrap_or("chunk");
+    let crumb = if breadcrumb.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", breadcrumb)
+    };
+    rows.push(ChunkDisplayLine::Label {
+        prefix: 0,
+        text: format!(
+            "{}{} • before {} → after {}",
+            chunk_kind,
+            crumb,
+            describe(old),
+            describe(new)
+        ),
+    });
+
+    let old_lines = old.map(|(_, lines)| lines).unwrap_or(&[]);
+    let new_lines = new.map(|(_, lines)| lines).unwrap_or(&[]);
+    let old_offset = old.map(|(meta, _)| meta.span.line_start).unwrap_or(1);
+    let new_offset = new.map(|(meta, _)| meta.span.line_start).unwrap_or(1);
+    let lcs = lcs_table(old_lines, new_lines);
+
+    let (n, m) = (old_lines.len(), new_lines.len());
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old_lines[i] == new_lines[j] {
+            rows.push(ChunkDisplayLine::Diff {
+                change: DiffChange::Unchanged,
+                old_line: Some(old_offset + i),
+                new_line: Some(new_offset + j),
+                text: new_lines[j].clone(),
+            });
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            rows.push(ChunkDisplayLine::Diff {
+                change: DiffChange::Removed,
+                old_line: Some(old_offset + i),
+                new_line: None,
+                text: old_lines[i].clone(),
+            });
+            i += 1;
+        } else {
+            rows.push(ChunkDisplayLine::Diff {
+                change: DiffChange::Added,
+                old_line: None,
+                new_line: Some(new_offset + j),
+                text: new_lines[j].clone(),
+            });
+            j += 1;
+        }
+    }
+}
+
+/// Structural chunks whose breadcrumb equals `breadcrumb`, in file order
+fn diff_candidates<'a>(
+    (lines, metas): (&'a [String], &'a [IndexedChunkMeta]),
+    breadcrumb: &str,
+) -> Vec<(&'a IndexedChunkMeta, &'a [String])> {
+    let mut found: Vec<(&IndexedChunkMeta, &[String])> = metas
+        .iter()
+        .filter(|meta| meta.chunk_type.as_deref() != Some("text"))
+        .filter(|meta| meta.breadcrumb.as_deref().unwrap_or("") == breadcrumb)
+        .map(|meta| (meta, chunk_lines(lines, meta)))
+        .collect();
+    found.sort_by_key(|(meta, _)| meta.span.line_start);
+    found
+}
+
+/// Compare the chunks carrying `breadcrumb` across two snapshots of a file,
+/// producing interleaved unchanged/removed/added rows per chunk.
+///
+/// Breadcrumbs name a chunk's enclosing scope (an empty string selects
+/// top-level chunks), so several siblings can share one. Versions are paired
+/// by content similarity rather than position, which keeps a symbol that moved
+/// within the file aligned with its previous body. Unpaired chunks show up as
+/// entirely added or removed.
+pub fn collect_chunk_diff_lines(
+    before: (&[String], &[IndexedChunkMeta]),
+    after: (&[String], &[IndexedChunkMeta]),
+    breadcrumb: &str,
+) -> Vec<ChunkDisplayLine> {
+    let old_chunks = diff_candidates(before, breadcrumb);
+    let new_chunks = diff_candidates(after, breadcrumb);
+
+    if old_chunks.is_empty() && new_chunks.is_empty() {
+        let label = if breadcrumb.is_empty() {
+            "top level"
+        } else {
+            breadcrumb
+        };
+        return vec![ChunkDisplayLine::Message(format!(
+            "No chunks under {} in either version.",
+            label
+        ))];
+    }
+
+    // Greedily pair the most similar versions of the same chunk type
+    let mut scored = Vec::new();
+    for (oi, (old_meta, old_lines)) in old_chunks.iter().enumerate() {
+        for (ni, (new_meta, new_lines)) in new_chunks.iter().enumerate() {
+            if old_meta.chunk_type != new_meta.chunk_type {
+                continue;
+            }
+            let common = lcs_table(old_lines, new_lines)[0][0] as usize;
+            let longest = old_lines.len().max(new_lines.len()).max(1);
+            if common > 0 {
+                scored.push((common as f32 / longest as f32, oi, ni));
+            }
+        }
+    }
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut old_pair: Vec<Option<usize>> = vec![None; old_chunks.len()];
+    let mut new_taken = vec![false; new_chunks.len()];
+    for (_, oi, ni) in scored {
+        if old_pair[oi].is_none() && !new_taken[ni] {
+            old_pair[oi] = Some(ni);
+            new_taken[ni] = true;
+        }
+    }
+
+    // Emit in the new file's order, slotting removed chunks before their successor
+    let mut rows = Vec::new();
+    let mut emitted_old = vec![false; old_chunks.len()];
+    for (ni, new_chunk) in new_chunks.iter().enumerate() {
+        let paired_old = old_pair.iter().position(|pair| *pair == Some(ni));
+        if let Some(oi) = paired_old {
+            for (earlier, old_chunk) in old_chunks.iter().enumerate().take(oi) {
+                if old_pair[earlier].is_none() && !emitted_old[earlier] {
+                    push_chunk_diff(&mut rows, breadcrumb, Some(*old_chunk), None);
+                    emitted_old[earlier] = true;
+                }
+            }
+            push_chunk_diff(
+                &mut rows,
+                breadcrumb,
+                Some(old_chunks[oi]),
+                Some(*new_chunk),
+            );
+            emitted_old[oi] = true;
+        } else {
+            push_chunk_diff(&mut rows, breadcrumb, None, Some(*new_chunk));
+        }
+    }
+    for (oi, old_chunk) in old_chunks.iter().enumerate() {
+        if !emitted_old[oi] {
+            push_chunk_diff(&mut rows, breadcrumb, Some(*old_chunk), None);
+        }
+    }
+
+    rows
+}
+
+/// Format a chunk's source text for pasting elsewhere, prefixed with a comment
+/// line naming the file and breadcrumb.
+///
+/// The chunk span already covers attached comments/attributes; when
+/// `include_leading_trivia` is false those lines are skipped.
+pub fn format_chunk_for_clipboard(
+    lines: &[String],
+    meta: &IndexedChunkMeta,
+    file_path: &Path,
+    include_leading_trivia: bool,
+) -> String {
+    let start = meta.span.line_start.saturating_sub(1).min(lines.len());
+    let end = meta.span.line_end.min(lines.len()).max(start);
+    let mut body = &lines[start..end];
+
+    if !include_leading_trivia && let Some(trivia) = meta.leading_trivia.as_ref() {
+        let trivia_lines: usize = trivia.iter().map(|t| t.lines().count()).sum();
+        let mut skip = trivia_lines.min(body.len());
+        while skip < body.len() && body[skip].trim().is_empty() {
+            skip += 1;
+        }
+        body = &body[skip..];
+    }
+
+    let breadcrumb = canonical_breadcrumb(meta);
+    let location = if breadcrumb.is_empty() {
+        file_path.display().to_string()
+    } else {
+        format!("{} ({})", file_path.display(), breadcrumb)
+    };
+
+    let mut output = format!(
+        "{} {}:{}-{}\n",
+        ck_core::anchor::line_comment_marker(Language::from_path(file_path)),
+        location,
+        meta.span.line_start,
+        meta.span.line_end
+    );
+    for line in body {
+        output.push_str(line);
+        output.push('\n');
+    }
+    output
+}
+
+/// Structural chunks that strictly contain `meta`, outermost first
+fn enclosing_chunks<'a>(
+    meta: &IndexedChunkMeta,
+    all_chunks: &'a [IndexedChunkMeta],
+) -> Vec<&'a IndexedChunkMeta> {
+    let mut ancestors: Vec<&IndexedChunkMeta> = all_chunks
+        .iter()
+        .filter(|candidate| candidate.chunk_type.as_deref() != Some("text"))
+        .filter(|candidate| {
+            let outer = &candidate.span;
+            outer.line_start <= meta.span.line_start
+                && outer.line_end >= meta.span.line_end
+                && (outer.line_start, outer.line_end) != (meta.span.line_start, meta.span.line_end)
+        })
+        .collect();
+    ancestors.sort_by_key(|outer| {
+        (
+            outer.span.line_start,
+            Reverse(outer.span.line_end.saturating_sub(outer.span.line_start)),
+        )
+    });
+    ancestors
+}
+
+/// First line of a chunk that isn't attached trivia (comments, attributes, decorators)
+fn signature_line<'a>(lines: &'a [String], meta: &IndexedChunkMeta) -> Option<&'a String> {
+    let trivia_lines: usize = meta
+        .leading_trivia
+        .iter()
+        .flatten()
+        .map(|t| t.lines().count())
+        .sum();
+    let start = meta.span.line_start.saturating_sub(1) + trivia_lines;
+    let end = meta.span.line_end.min(lines.len());
+    lines
+        .get(start..end)?
+        .iter()
+        .find(|line| !line.trim().is_empty())
+}
+
+/// Assemble a chunk and the signatures of its enclosing chunks into a fenced
+/// prompt block, trimmed to fit `token_budget` estimated tokens.
+///
+/// Ancestor signatures are dropped outermost-first when over budget; if the
+/// chunk alone is still too large its tail is truncated.
+pub fn format_chunk_as_prompt(
+    lines: &[String],
+    meta: &IndexedChunkMeta,
+    all_chunks: &[IndexedChunkMeta],
+    file_path: &Path,
+    token_budget: usize,
+) -> String {
+    let fence_lang = Language::from_path(file_path)
+        .map(|lang| lang.to_string())
+        .unwrap_or_default();
+    let breadcrumb = canonical_breadcrumb(meta);
+    let header = if breadcrumb.is_empty() {
+        format!("File: {}\n```{}\n", file_path.display(), fence_lang)
+    } else {
+        format!(
+            "File: {} ({})\n```{}\n",
+            file_path.display(),
+            breadcrumb,
+            fence_lang
+        )
+    };
+    let footer = "```\n";
+
+    let mut signatures: Vec<String> = enclosing_chunks(meta, all_chunks)
+        .into_iter()
+        .filter_map(|outer| signature_line(lines, outer))
+        .map(|line| {
+            let indent: String = line.chars().take_while(|c| c.is_whitespace()).collect();
+            format!("{}\n{}    ...\n", line, indent)
+        })
+        .collect();
+
+    let start = meta.span.line_start.saturating_sub(1).min(lines.len());
+    let end = meta.span.line_end.min(lines.len()).max(start);
+    let body: Vec<&String> = lines[start..end].iter().collect();
+
+    let assemble = |signatures: &[String], body: &[&String], truncated: bool| {
+        let mut prompt = header.clone();
+        for signature in signatures {
+            prompt.push_str(signature);
+        }
+        for line in body {
+            prompt.push_str(line);
+            prompt.push('\n');
+        }
+        if truncated {
+            prompt.push_str("... (truncated)\n");
+        }
+        prompt.push_str(footer);
+        prompt
+    };
+    let fits = |prompt: &str| ck_embed::TokenEstimator::estimate_tokens(prompt) <= token_budget;
+
+    let mut prompt = assemble(&signatures, &body, false);
+    while !fits(&prompt) && !signatures.is_empty() {
+        signatures.remove(0);
+        prompt = assemble(&signatures, &body, false);
+    }
+    if fits(&prompt) {
+        return prompt;
+    }
+
+    // Binary search for the longest body prefix that fits (always keep one line)
+    let (mut low, mut high) = (1, body.len());
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if fits(&assemble(&signatures, &body[..mid], true)) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    assemble(&signatures, &body[..low.min(body.len())], true)
+}
+
 /// Convert ck_chunk::Chunk to IndexedChunkMeta format
-pub fn convert_chunks_to_meta(chunks: Vec<ck_chunk::Chunk>) -> Vec<IndexedChunkMeta> {
+///
+/// `file_path` feeds the chunk id and should be repo-relative so ids match
+/// those derived from the index. Spans are clamped to the file's `line_count`
+/// lines, since some grammars end a node past the last line, and chunks
+/// starting beyond it are dropped.
+pub fn convert_chunks_to_meta(
+    chunks: Vec<ck_chunk::Chunk>,
+    file_path: &Path,
+    line_count: usize,
+) -> Vec<IndexedChunkMeta> {
     chunks
         .iter()
-        .map(|chunk| IndexedChunkMeta {
-            span: chunk.span.clone(),
-            chunk_type: Some(match chunk.chunk_type {
-                ck_chunk::ChunkType::Function => "function".to_string(),
-                ck_chunk::ChunkType::Class => "class".to_string(),
-                ck_chunk::ChunkType::Method => "method".to_string(),
-                ck_chunk::ChunkType::Module => "module".to_string(),
-                ck_chunk::ChunkType::Text => "text".to_string(),
-            }),
+        .filter_map(|chunk| {
+            let mut span = chunk.span.clone();
+            if span.line_start > line_count {
+                return None;
+            }
+            span.line_end = span.line_end.clamp(span.line_start, line_count);
+            Some((chunk, span))
+        })
+        .map(|(chunk, span)| IndexedChunkMeta {
+            chunk_id: Some(ck_core::compute_chunk_id(
+                file_path,
+                chunk.metadata.breadcrumb.as_deref(),
+                &ck_core::compute_chunk_hash(
+                    &chunk.text,
+                    &chunk.metadata.leading_trivia,
+                    &chunk.metadata.trailing_trivia,
+                ),
+            )),
+            span,
+            chunk_type: Some(
+                ck_core::SymbolKind::from(&chunk.chunk_type)
+                    .name()
+                    .to_string(),
+            ),
             breadcrumb: chunk.metadata.breadcrumb.clone(),
             ancestry: chunk.metadata.ancestry.clone(),
+            name: chunk.metadata.name.clone(),
+            breadcrumb_segments: chunk.metadata.breadcrumb_segments.clone(),
             byte_length: Some(chunk.metadata.byte_length),
             estimated_tokens: Some(chunk.metadata.estimated_tokens),
             leading_trivia: Some(chunk.metadata.leading_trivia.clone()),
@@ -416,8 +1613,794 @@ pub fn chunk_file_live(file_path: &Path) -> Result<(Vec<String>, Vec<IndexedChun
     let chunks = ck_chunk::chunk_text_with_model(&content, detected_lang, Some(default_model))
         .map_err(|err| format!("Failed to chunk file: {}", err))?;
 
-    // Convert chunks to IndexedChunkMeta format
-    let chunk_metas = convert_chunks_to_meta(chunks);
+    // Convert chunks to IndexedChunkMeta format, keying ids on the repo-relative path
+    let id_path = find_repo_root(file_path)
+        .and_then(|root| file_path.strip_prefix(&root).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| file_path.to_path_buf());
+    let chunk_metas = convert_chunks_to_meta(chunks, &id_path, lines.len());
 
     Ok((lines, chunk_metas))
 }
+
+/// `text` split into lines with its whitespace made visible, for seeing exactly
+/// where a chunk starts and ends: `·` for spaces, `→` for tabs, `␍` for
+/// carriage returns and `↵` closing every line that ends in a newline. Each
+/// line is a list of runs, flagged when the run is markers rather than text.
+pub fn visible_whitespace_lines(text: &str) -> Vec<Vec<(String, bool)>> {
+    text.split_inclusive('\n')
+        .map(|line| {
+            let mut runs: Vec<(String, bool)> = Vec::new();
+            for c in line.chars() {
+                let (shown, marker) = match c {
+                    ' ' => ('·', true),
+                    '\t' => ('→', true),
+                    '\r' => ('␍', true),
+                    '\n' => ('↵', true),
+                    other => (other, false),
+                };
+                match runs.last_mut() {
+                    Some((run, run_marker)) if *run_marker == marker => run.push(shown),
+                    _ => runs.push((shown.to_string(), marker)),
+                }
+            }
+            runs
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn fixture_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn visible_whitespace_marks_spaces_tabs_and_newlines() {
+        let lines = visible_whitespace_lines("fn a() {\r\n\t  x\n}");
+        let shown: Vec<String> = lines
+            .iter()
+            .map(|runs| runs.iter().map(|(run, _)| run.as_str()).collect())
+            .collect();
+        assert_eq!(shown, vec!["fn·a()·{␍↵", "→··x↵", "}"]);
+        assert_eq!(
+            lines[1],
+            vec![
+                ("→··".to_string(), true),
+                ("x".to_string(), false),
+                ("↵".to_string(), true)
+            ]
+        );
+        assert!(visible_whitespace_lines("").is_empty());
+    }
+
+    /// Render every line of `lines` through the chunk pipeline in full-file mode.
+    fn render_chunk_view(lines: &[String], chunks: &[IndexedChunkMeta]) -> String {
+        let rows = collect_chunk_display_lines(lines, 0, lines.len(), 0, None, chunks, true);
+        let mut rendered = rows
+            .iter()
+            .map(chunk_display_line_to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        rendered.push('\n');
+        rendered
+    }
+
+    /// Compare `actual` against `tests/snapshots/<name>.snap`.
+    ///
+    /// Run with `UPDATE_SNAPSHOTS=1` to (re)write the golden file after an
+    /// intentional rendering change or for a new snapshot; a missing golden
+    /// file fails the test.
+    fn assert_snapshot(name: &str, actual: &str) {
+        let snap_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("snapshots")
+            .join(format!("{name}.snap"));
+
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            fs::create_dir_all(snap_path.parent().unwrap()).unwrap();
+            fs::write(&snap_path, actual).unwrap();
+            return;
+        }
+
+        let expected = fs::read_to_string(&snap_path).unwrap_or_else(|err| {
+            panic!(
+                "snapshot {} is missing ({}); run with UPDATE_SNAPSHOTS=1 to create it",
+                snap_path.display(),
+                err
+            )
+        });
+        assert_eq!(
+            expected,
+            actual,
+            "snapshot {} differs; rerun with UPDATE_SNAPSHOTS=1 if the change is intended",
+            snap_path.display()
+        );
+    }
+
+    fn snapshot_live_fixture(name: &str) -> String {
+        let (lines, chunks) = chunk_file_live(&fixture_path(name)).unwrap();
+        render_chunk_view(&lines, &chunks)
+    }
+
+    #[test]
+    fn snapshot_nested_rust() {
+        assert_snapshot("nested_rust", &snapshot_live_fixture("nested.rs"));
+    }
+
+    #[test]
+    fn snapshot_no_chunks() {
+        let content = fs::read_to_string(fixture_path("no_chunks.ml")).unwrap();
+        let lines: Vec<String> = content.lines().map(String::from).collect();
+        assert_snapshot("no_chunks", &render_chunk_view(&lines, &[]));
+    }
+
+    #[test]
+    fn snapshot_no_chunks_indented() {
+        let content = fs::read_to_string(fixture_path("no_chunks_indented.py")).unwrap();
+        let lines: Vec<String> = content.lines().map(String::from).collect();
+        assert_snapshot("no_chunks_indented", &render_chunk_view(&lines, &[]));
+    }
+
+    #[test]
+    fn snapshot_text_only() {
+        let (lines, chunks) = chunk_file_live(&fixture_path("text_only.txt")).unwrap();
+        assert!(
+            chunks
+                .iter()
+                .all(|meta| meta.chunk_type.as_deref() == Some("text"))
+        );
+        assert_snapshot("text_only", &render_chunk_view(&lines, &chunks));
+    }
+
+    #[test]
+    fn clipboard_text_has_header_and_optional_trivia() {
+        let lines: Vec<String> = ["#[derive(Debug)]", "", "struct Point {", "    x: i32,", "}"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        let meta = IndexedChunkMeta {
+            span: Span::new_unchecked(0, 0, 1, 5),
+            chunk_type: Some("class".to_string()),
+            breadcrumb: Some("Point".to_string()),
+            ancestry: vec!["Point".to_string()],
+            name: None,
+            breadcrumb_segments: Vec::new(),
+            estimated_tokens: None,
+            byte_length: None,
+            leading_trivia: Some(vec!["#[derive(Debug)]".to_string()]),
+            trailing_trivia: None,
+            chunk_id: None,
+        };
+        let path = Path::new("src/point.rs");
+
+        let with_trivia = format_chunk_for_clipboard(&lines, &meta, path, true);
+        assert_eq!(
+            with_trivia,
+            "// src/point.rs (Point):1-5\n#[derive(Debug)]\n\nstruct Point {\n    x: i32,\n}\n"
+        );
+
+        let without_trivia = format_chunk_for_clipboard(&lines, &meta, path, false);
+        assert_eq!(
+            without_trivia,
+            "// src/point.rs (Point):1-5\nstruct Point {\n    x: i32,\n}\n"
+        );
+    }
+
+    #[test]
+    fn prompt_includes_ancestor_signatures_within_budget() {
+        let (lines, chunks) = chunk_file_live(&fixture_path("nested.rs")).unwrap();
+        let method = chunks
+            .iter()
+            .find(|meta| meta.span.line_start == 14)
+            .expect("manhattan method chunk");
+        let path = Path::new("src/nested.rs");
+
+        let prompt = format_chunk_as_prompt(&lines, method, &chunks, path, 4000);
+        assert!(prompt.starts_with("File: src/nested.rs"));
+        assert!(prompt.contains("```rust\nimpl Point {\n    ...\n    pub fn manhattan"));
+        assert!(prompt.ends_with("    }\n```\n"));
+
+        // A tiny budget drops the ancestor signature and truncates the body
+        let tight = format_chunk_as_prompt(&lines, method, &chunks, path, 15);
+        assert!(!tight.contains("impl Point"));
+        assert!(tight.contains("pub fn manhattan"));
+        assert!(tight.contains("... (truncated)"));
+    }
+
+    #[test]
+    fn diff_aligns_moved_chunk_by_breadcrumb() {
+        let to_lines = |src: &str| src.lines().map(String::from).collect::<Vec<_>>();
+        let before_src = "impl S {\n    fn a() {\n        1\n    }\n\n    fn b() {\n        let x = 1;\n        x\n    }\n}\n";
+        let after_src = "impl S {\n    fn b() {\n        let x = 2;\n        x\n    }\n\n    fn a() {\n        1\n    }\n\n    fn c() {}\n}\n";
+        let chunk = |src: &str| {
+            convert_chunks_to_meta(
+                ck_chunk::chunk_text(src, Some(Language::Rust)).expect("chunking succeeds"),
+                Path::new("src/s.rs"),
+                src.lines().count(),
+            )
+        };
+        let (before_lines, after_lines) = (to_lines(before_src), to_lines(after_src));
+        let (before_chunks, after_chunks) = (chunk(before_src), chunk(after_src));
+
+        let rendered: Vec<String> = collect_chunk_diff_lines(
+            (&before_lines, &before_chunks),
+            (&after_lines, &after_chunks),
+            "S",
+        )
+        .iter()
+        .map(chunk_display_line_to_string)
+        .collect();
+
+        assert_eq!(
+            rendered,
+            vec![
+                "method (S) • before L6-9 → after L2-5",
+                "     6    2 |     fn b() {",
+                "-    7      |         let x = 1;",
+                "+         3 |         let x = 2;",
+                "     8    4 |         x",
+                "     9    5 |     }",
+                "method (S) • before L2-4 → after L7-9",
+                "     2    7 |     fn a() {",
+                "     3    8 |         1",
+                "     4    9 |     }",
+                "method (S) • before absent → after L11-11",
+                "+        11 |     fn c() {}",
+            ]
+        );
+    }
+
+    #[test]
+    fn chunk_id_survives_moves_but_not_edits() {
+        let path = Path::new("src/lib.rs");
+        let ids = |src: &str| {
+            let chunks = ck_chunk::chunk_text(src, Some(Language::Rust)).unwrap();
+            convert_chunks_to_meta(chunks, path, src.lines().count())
+                .into_iter()
+                .map(|meta| (meta.span.line_start, meta.chunk_id.unwrap()))
+                .collect::<Vec<_>>()
+        };
+
+        let original = ids("fn a() {\n    1\n}\n\nfn b() {\n    2\n}\n");
+        let moved = ids("\n\nfn a() {\n    1\n}\n\nfn b() {\n    2\n}\n");
+        let edited = ids("fn a() {\n    1\n}\n\nfn b() {\n    3\n}\n");
+
+        assert_eq!(original[0].1, moved[0].1);
+        assert_ne!(original[0].0, moved[0].0);
+        assert_eq!(original[0].1, edited[0].1);
+        assert_ne!(original[1].1, edited[1].1);
+    }
+
+    #[test]
+    fn chunks_past_end_of_file_are_clamped() {
+        let src = "fn a() {\n    1\n}";
+        let lines: Vec<String> = src.lines().map(String::from).collect();
+        let mut chunks = ck_chunk::chunk_text(src, Some(Language::Rust)).unwrap();
+        chunks.retain(|chunk| chunk.chunk_type == ck_chunk::ChunkType::Function);
+        let mut past_eof = chunks[0].clone();
+        past_eof.span = Span::new_unchecked(0, 0, 5, 6);
+        chunks[0].span.line_end = 4;
+        chunks.push(past_eof);
+
+        let metas = convert_chunks_to_meta(chunks, Path::new("src/a.rs"), lines.len());
+        assert_eq!(metas.len(), 1);
+        assert_eq!((metas[0].span.line_start, metas[0].span.line_end), (1, 3));
+
+        // The column closes on the last line instead of staying open
+        assert!(
+            render_chunk_view(&lines, &metas)
+                .starts_with("┌    1 | fn a() {\n│    2 |     1\n└    3 | }\n")
+        );
+    }
+
+    #[test]
+    fn live_chunks_start_on_line_one() {
+        let (_, chunks) = chunk_file_live(&fixture_path("nested.rs")).unwrap();
+        let first = chunks.iter().map(|meta| meta.span.line_start).min();
+        assert_eq!(first, Some(1));
+    }
+
+    #[test]
+    fn live_chunks_keep_the_lines_they_start_on() {
+        let path = fixture_path("nested.rs");
+        let content = std::fs::read_to_string(&path).unwrap();
+        let (_, chunks) = chunk_file_live(&path).unwrap();
+        assert!(chunks.len() > 1);
+        for meta in &chunks {
+            let expected = content[..meta.span.byte_start].matches('\n').count() + 1;
+            assert_eq!(meta.span.line_start, expected, "{:?}", meta.span);
+        }
+    }
+
+    #[test]
+    fn clip_columns_keeps_innermost_levels() {
+        // Five nested chunks: depth 0 spans lines 1-9, depth 4 spans line 5
+        let chunks: Vec<IndexedChunkMeta> = (0..5)
+            .map(|depth| IndexedChunkMeta {
+                span: Span::new_unchecked(0, 0, 1 + depth, 9 - depth),
+                chunk_type: Some("function".to_string()),
+                breadcrumb: None,
+                ancestry: Vec::new(),
+                name: None,
+                breadcrumb_segments: Vec::new(),
+                estimated_tokens: None,
+                byte_length: None,
+                leading_trivia: None,
+                trailing_trivia: None,
+                chunk_id: None,
+            })
+            .collect();
+        let lines: Vec<String> = (1..=9).map(|i| format!("line {}", i)).collect();
+        let render = |window: ColumnWindow| {
+            let mut rows = collect_chunk_display_lines(&lines, 0, 9, 0, None, &chunks, true);
+            clip_chunk_columns(&mut rows, window);
+            rows.iter()
+                .map(chunk_display_line_to_string)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(max_column_scroll(&chunks, 2), 3);
+
+        let innermost = render(ColumnWindow {
+            max_columns: 2,
+            outer_offset: 0,
+        });
+        assert_eq!(innermost[0], "‹      1 | line 1");
+        assert_eq!(innermost[4], "‹│─    5 | line 5");
+
+        let outermost = render(ColumnWindow {
+            max_columns: 2,
+            outer_offset: 10,
+        });
+        assert_eq!(outermost[0], " ┌     1 | line 1");
+        assert_eq!(outermost[4], " ││    5 | line 5");
+
+        // A cap wider than the forest leaves rows unchanged
+        let unclipped = render(ColumnWindow {
+            max_columns: 8,
+            outer_offset: 0,
+        });
+        assert_eq!(unclipped[4], "││││─    5 | line 5");
+    }
+
+    #[test]
+    fn canonical_breadcrumb_prefers_ancestry() {
+        let meta = |breadcrumb: Option<&str>, ancestry: &[&str]| IndexedChunkMeta {
+            span: Span::new_unchecked(0, 0, 1, 1),
+            chunk_type: Some("method".to_string()),
+            breadcrumb: breadcrumb.map(str::to_string),
+            ancestry: ancestry.iter().map(|part| part.to_string()).collect(),
+            name: None,
+            breadcrumb_segments: Vec::new(),
+            estimated_tokens: None,
+            byte_length: None,
+            leading_trivia: None,
+            trailing_trivia: None,
+            chunk_id: None,
+        };
+
+        assert_eq!(
+            canonical_breadcrumb(&meta(Some(""), &["net", "Client"])),
+            "net::Client"
+        );
+        assert_eq!(
+            canonical_breadcrumb(&meta(None, &["net", "Client"])),
+            "net::Client"
+        );
+        // A stale breadcrumb left by a merge or split loses to the ancestry
+        assert_eq!(
+            canonical_breadcrumb(&meta(Some("net::OldClient"), &["net", "Client"])),
+            "net::Client"
+        );
+        // Sidecars without ancestry keep their stored breadcrumb
+        assert_eq!(
+            canonical_breadcrumb(&meta(Some("net::Client"), &[])),
+            "net::Client"
+        );
+        assert_eq!(canonical_breadcrumb(&meta(None, &[])), "");
+    }
+
+    #[test]
+    fn chunk_columns_reflow_to_pane_width() {
+        // 999 lines: 2 + 4 + 3 gutter cells plus 20 cells of code
+        assert_eq!(chunk_columns_for_width(8, 80, 999), 8);
+        assert_eq!(chunk_columns_for_width(8, 33, 999), 4);
+        assert_eq!(chunk_columns_for_width(8, 10, 999), 1);
+        assert_eq!(chunk_columns_for_width(0, 80, 999), 1);
+    }
+
+    #[test]
+    fn collapse_text_chunks_folds_multi_line_text() {
+        let lines: Vec<String> = [
+            "use std::fmt;",
+            "use std::io;",
+            "",
+            "// Licensed under MIT",
+            "// Copyright the authors",
+            "fn main() {",
+            "    // inside a function",
+            "    // stays expanded",
+            "}",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        let meta = |chunk_type: &str, start: usize, end: usize| IndexedChunkMeta {
+            span: Span::new_unchecked(0, 0, start, end),
+            chunk_type: Some(chunk_type.to_string()),
+            breadcrumb: None,
+            ancestry: Vec::new(),
+            name: None,
+            breadcrumb_segments: Vec::new(),
+            estimated_tokens: None,
+            byte_length: None,
+            leading_trivia: None,
+            trailing_trivia: None,
+            chunk_id: None,
+        };
+        let chunks = vec![
+            meta("text", 1, 3),
+            meta("text", 4, 5),
+            meta("function", 6, 9),
+            meta("text", 7, 8),
+        ];
+        let render = |match_line: usize| {
+            let mut rows =
+                collect_chunk_display_lines(&lines, 0, 9, match_line, None, &chunks, true);
+            collapse_text_chunks(&mut rows, &lines, &chunks);
+            rows.iter()
+                .map(chunk_display_line_to_string)
+                .collect::<Vec<_>>()
+        };
+
+        let rendered = render(0);
+        assert_eq!(rendered.len(), 6);
+        assert!(rendered[0].ends_with("┆ imports (3 lines)"));
+        assert!(rendered[1].ends_with("┆ comments (2 lines)"));
+        assert!(rendered[2].contains("fn main() {"));
+        assert!(rendered[3].contains("// inside a function"));
+
+        // The text chunk holding the match stays open
+        let rendered = render(2);
+        assert_eq!(rendered.len(), 8);
+        assert!(rendered[1].contains("use std::io;"));
+        assert!(rendered[3].ends_with("┆ comments (2 lines)"));
+    }
+
+    #[test]
+    fn highlight_syntax_fills_content_rows() {
+        let lines: Vec<String> = ["/* a", "b */", "fn f() -> u32 { 1 }"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        let mut rows = collect_chunk_display_lines(&lines, 0, 3, 0, None, &[], true);
+        highlight_syntax(&mut rows, &lines, Language::Rust);
+        let classes: Vec<Vec<ck_chunk::HighlightClass>> = rows
+            .iter()
+            .filter_map(|row| match row {
+                ChunkDisplayLine::Content { highlights, .. } => {
+                    Some(highlights.iter().map(|span| span.class).collect())
+                }
+                _ => None,
+            })
+            .collect();
+        // The comment's second line is only a comment when the file is parsed whole
+        use ck_chunk::HighlightClass::{Comment, Function, Keyword, Number, Type};
+        assert_eq!(
+            classes,
+            vec![
+                vec![Comment],
+                vec![Comment],
+                vec![Keyword, Function, Type, Number]
+            ]
+        );
+
+        // Languages without a grammar stay plain
+        let mut rows = collect_chunk_display_lines(&lines, 0, 3, 0, None, &[], true);
+        highlight_syntax(&mut rows, &lines, Language::Toml);
+        assert!(rows.iter().all(|row| match row {
+            ChunkDisplayLine::Content { highlights, .. } => highlights.is_empty(),
+            _ => true,
+        }));
+    }
+
+    #[test]
+    fn sibling_outline_collapses_same_parent_chunks() {
+        let (lines, chunks) = chunk_file_live(&fixture_path("nested.rs")).unwrap();
+        let matched = chunks
+            .iter()
+            .find(|meta| meta.span.line_start == 14)
+            .unwrap();
+        let mut rows =
+            collect_chunk_display_lines(&lines, 13, 16, 15, Some(matched), &chunks, false);
+        let expanded = rows.len();
+        add_sibling_outline(&mut rows, &lines, matched, &chunks);
+        let rendered: Vec<String> = rows.iter().map(chunk_display_line_to_string).collect();
+
+        // Only `new` shares the impl; methods of other parents are left out
+        assert_eq!(rows.len(), expanded + 1);
+        assert!(rendered[0].ends_with("▸ pub fn new(x: i32, y: i32) -> Self { · L10-12"));
+        assert!(rendered[1].contains("method"));
+        assert!(rendered.last().unwrap().contains("16 |"));
+
+        // Top-level chunks are siblings of each other
+        let outer = chunks
+            .iter()
+            .find(|meta| meta.span.line_start == 19)
+            .unwrap();
+        let mut rows = collect_chunk_display_lines(&lines, 18, 23, 20, Some(outer), &chunks, false);
+        add_sibling_outline(&mut rows, &lines, outer, &chunks);
+        let rendered: Vec<String> = rows.iter().map(chunk_display_line_to_string).collect();
+        assert!(rendered.iter().any(|row| row.contains("▸ impl Point {")));
+        assert!(
+            rendered
+                .last()
+                .unwrap()
+                .contains("▸ impl fmt::Display for Point {")
+        );
+        assert!(!rendered.iter().any(|row| row.contains("▸ pub fn new")));
+    }
+
+    #[test]
+    fn cells_split_prefix_gutter_and_text() {
+        let (lines, chunks) = chunk_file_live(&fixture_path("nested.rs")).unwrap();
+        let matched = chunks.iter().find(|meta| meta.span.line_start == 10);
+        let rows = collect_chunk_display_lines(&lines, 8, 12, 11, matched, &chunks, false);
+
+        let cells: Vec<RenderedCells> = rows.iter().map(chunk_display_line_to_cells).collect();
+
+        // Label row precedes the matched chunk's first line
+        assert_eq!(cells[1].line_num, None);
+        assert!(cells[1].text.starts_with("method"));
+        assert!(cells[1].prefix.iter().all(|cell| cell.ch == ' '));
+
+        let body = &cells[3];
+        assert_eq!(body.line_num, Some(11));
+        assert_eq!(body.text, "        Self { x, y }");
+        let glyphs: String = body.prefix.iter().map(|cell| cell.ch).collect();
+        assert_eq!(glyphs, "││");
+        assert_eq!(
+            body.prefix
+                .iter()
+                .map(|cell| cell.is_match)
+                .collect::<Vec<_>>(),
+            vec![false, true]
+        );
+
+        // Content rows reassemble into the string form
+        let rebuilt = format!("{} {:4} | {}", glyphs, body.line_num.unwrap(), body.text);
+        assert_eq!(rebuilt, chunk_display_line_to_string(&rows[3]));
+    }
+
+    #[test]
+    fn snapshot_crlf_rust() {
+        let rendered = snapshot_live_fixture("crlf.rs");
+        assert!(!rendered.contains('\r'));
+        assert_snapshot("crlf_rust", &rendered);
+    }
+
+    #[test]
+    fn enclosing_segment_climbs_one_level() {
+        let (_, chunks) = chunk_file_live(&fixture_path("nested.rs")).unwrap();
+        let span_of = |line_start, line_end| Span::new_unchecked(0, 0, line_start, line_end);
+
+        // `origin` sits in `mod geometry`, which is top level
+        let (parent, breadcrumb) = enclosing_segment(&chunks, &span_of(20, 22)).unwrap();
+        assert_eq!(parent.name, "geometry");
+        assert_eq!((parent.span.line_start, parent.span.line_end), (19, 23));
+        assert_eq!(breadcrumb, None);
+        assert!(enclosing_segment(&chunks, &parent.span).is_none());
+
+        // A regex match inside `manhattan` climbs from the method to `impl Point`
+        let (parent, _) = enclosing_segment(&chunks, &span_of(15, 15)).unwrap();
+        assert_eq!(parent.name, "Point");
+        assert_eq!(parent.span.line_start, 9);
+    }
+
+    #[test]
+    fn bracket_columns_at_matches_full_render() {
+        for fixture in ["nested.rs", "crlf.rs", "text_only.txt"] {
+            let (lines, chunks) = chunk_file_live(&fixture_path(fixture)).unwrap();
+            let structural: Vec<IndexedChunkMeta> = chunks
+                .iter()
+                .filter(|meta| meta.chunk_type.as_deref() != Some("text"))
+                .cloned()
+                .collect();
+            let max_depth = calculate_max_depth(&structural);
+
+            let rows = collect_chunk_display_lines(&lines, 0, lines.len(), 0, None, &chunks, true);
+            for row in rows {
+                if let ChunkDisplayLine::Content {
+                    columns, line_num, ..
+                } = row
+                {
+                    let expected: String = columns.iter().map(|c| c.ch).collect();
+                    let actual: String = bracket_columns_at(&chunks, line_num, max_depth)
+                        .iter()
+                        .map(|c| c.ch)
+                        .collect();
+                    assert_eq!(actual, expected, "{} line {}", fixture, line_num);
+                }
+            }
+        }
+        assert!(bracket_columns_at(&[], 1, 3).is_empty());
+    }
+
+    #[test]
+    fn degenerate_windows_render_nothing() {
+        let (lines, chunks) = chunk_file_live(&fixture_path("nested.rs")).unwrap();
+
+        let full = collect_chunk_display_lines(&lines, 0, lines.len(), 0, None, &chunks, true);
+        let overshoot =
+            collect_chunk_display_lines(&lines, 0, lines.len() + 50, 0, None, &chunks, true);
+        assert_eq!(
+            full.iter()
+                .map(chunk_display_line_to_string)
+                .collect::<Vec<_>>(),
+            overshoot
+                .iter()
+                .map(chunk_display_line_to_string)
+                .collect::<Vec<_>>()
+        );
+
+        assert!(collect_chunk_display_lines(&lines, 3, 3, 0, None, &chunks, true).is_empty());
+        assert!(collect_chunk_display_lines(&lines, 5, 2, 0, None, &chunks, true).is_empty());
+        assert!(
+            collect_chunk_display_lines(
+                &lines,
+                lines.len() + 1,
+                lines.len() + 4,
+                0,
+                None,
+                &chunks,
+                true
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn long_lines_are_truncated_after_the_gutter() {
+        let (mut lines, chunks) = chunk_file_live(&fixture_path("nested.rs")).unwrap();
+        lines[1] = format!("{}{}", lines[1], "é".repeat(5000));
+        let full_width = lines[1].chars().count();
+
+        let mut rows = collect_chunk_display_lines(&lines, 0, lines.len(), 0, None, &chunks, true);
+        let before: Vec<RenderedCells> = rows.iter().map(chunk_display_line_to_cells).collect();
+        truncate_long_lines(&mut rows, 100);
+
+        for (row, before) in rows.iter().zip(&before) {
+            let after = chunk_display_line_to_cells(row);
+            let prefix =
+                |cells: &RenderedCells| -> String { cells.prefix.iter().map(|c| c.ch).collect() };
+            assert_eq!(prefix(&after), prefix(before));
+            assert_eq!(after.line_num, before.line_num);
+            if after.line_num == Some(2) {
+                assert_eq!(
+                    after.text,
+                    format!(
+                        "{}…(truncated {} chars)",
+                        lines[1].chars().take(100).collect::<String>(),
+                        full_width - 100
+                    )
+                );
+            } else {
+                assert_eq!(after.text, before.text);
+            }
+        }
+
+        assert_eq!(truncate_line("short", 0), ("short", 0));
+        assert_eq!(truncate_line("short", 5), ("short", 0));
+        assert_eq!(truncate_line("ééé", 1), ("é", 2));
+    }
+
+    #[test]
+    fn byte_gutter_reports_each_line_range() {
+        let lines: Vec<String> = ["fn a() {}", "", "// é"].map(String::from).to_vec();
+        let ranges = line_byte_ranges(&lines);
+        assert_eq!(ranges, vec![(0, 9), (10, 10), (11, 16)]);
+        let source = lines.join("\n");
+        for (line, (start, end)) in lines.iter().zip(&ranges) {
+            assert_eq!(&source[*start..*end], line);
+        }
+
+        let rows = collect_chunk_display_lines(&lines, 0, lines.len(), 0, None, &[], true);
+        let render = |gutter| {
+            rows.iter()
+                .map(|row| chunk_display_line_to_string_with_gutter(row, gutter, &ranges))
+                .collect::<Vec<_>>()
+        };
+        let lines_only = render(GutterMode::Lines);
+        assert_eq!(
+            lines_only,
+            rows.iter()
+                .map(chunk_display_line_to_string)
+                .collect::<Vec<_>>()
+        );
+        assert!(lines_only[2].ends_with("   3 | // é"));
+        assert!(render(GutterMode::Bytes)[2].ends_with(" 11..16 | // é"));
+        assert!(render(GutterMode::Both)[1].ends_with("   2 10..10 | "));
+        assert!(render(GutterMode::Both)[0].ends_with("   1  0..9  | fn a() {}"));
+    }
+
+    #[test]
+    fn empty_file_renders_nothing() {
+        assert!(collect_chunk_display_lines(&[], 0, 0, 1, None, &[], true).is_empty());
+        assert!(collect_chunk_display_lines(&[], 0, 10, 1, None, &[], false).is_empty());
+    }
+
+    #[test]
+    fn relevant_range_picks_densest_window() {
+        let lines: Vec<String> = [
+            "fn handle(request: Request) -> Response {",
+            "    let user = load_user(&request);",
+            "    let config = Config::default();",
+            "    log::debug!(\"starting\");",
+            "    let mut attempts = 0;",
+            "    loop {",
+            "        match send_with_retry(&request) {",
+            "            Err(_) if attempts < MAX_RETRY => backoff(attempts),",
+            "            other => return other,",
+            "        }",
+            "    }",
+            "}",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+
+        assert_eq!(
+            relevant_line_range(&lines, 0, lines.len(), "retry backoff", 3),
+            Some((5, 8))
+        );
+        // Fits already, or nothing relevant: keep the caller's window
+        assert_eq!(relevant_line_range(&lines, 0, 4, "retry", 4), None);
+        assert_eq!(
+            relevant_line_range(&lines, 0, lines.len(), "serialization", 3),
+            None
+        );
+        assert_eq!(relevant_line_range(&lines, 0, lines.len(), "", 3), None);
+    }
+
+    #[test]
+    fn test_identifier_at_and_definition_symbol() {
+        assert_eq!(
+            identifier_at("let x = parse_all(input);", 10),
+            Some("parse_all")
+        );
+        assert_eq!(
+            identifier_at("let x = parse_all(input);", 17),
+            Some("parse_all")
+        );
+        assert_eq!(identifier_at("a + 42", 4), None);
+        assert_eq!(identifier_at("a + b", 2), None);
+
+        let lines: Vec<String> = [
+            "fn load(path: &Path) -> Result<Config> {",
+            "    if path.exists() {",
+            "        return parse_config(&read(path)?);",
+            "    }",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        // A query word names the symbol
+        assert_eq!(
+            definition_symbol(&lines, "config file", Some("load")),
+            Some("Config".to_string())
+        );
+        // Otherwise the first call that isn't the chunk itself or a keyword
+        assert_eq!(
+            definition_symbol(&lines, "", Some("load")),
+            Some("exists".to_string())
+        );
+        assert_eq!(definition_symbol(&lines[3..], "", None), None);
+    }
+}
@@ -0,0 +1,42 @@
+use anyhow::{Result, anyhow};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Clipboard helpers tried in order; the first one that runs successfully wins
+const CLIPBOARD_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+
+/// Copy `text` to the system clipboard via the first available helper command.
+/// Returns the name of the command that accepted the text.
+pub fn copy_to_clipboard(text: &str) -> Result<&'static str> {
+    for (program, args) in CLIPBOARD_COMMANDS {
+        let child = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+
+        let Ok(mut child) = child else {
+            continue;
+        };
+
+        if let Some(mut stdin) = child.stdin.take()
+            && stdin.write_all(text.as_bytes()).is_err()
+        {
+            let _ = child.wait();
+            continue;
+        }
+
+        if child.wait().map(|status| status.success()).unwrap_or(false) {
+            return Ok(program);
+        }
+    }
+
+    Err(anyhow!("no clipboard helper available"))
+}
@@ -8,6 +8,7 @@ pub const COLOR_DARK_GRAY: Color = Color::Rgb(100, 100, 100); // Dark gray - sub
 pub const COLOR_GRAY: Color = Color::Rgb(150, 150, 150); // Gray - secondary text
 pub const COLOR_GREEN: Color = Color::Rgb(80, 200, 120); // Green - success, chunk boundaries
 pub const COLOR_MAGENTA: Color = Color::Rgb(200, 80, 200); // Magenta - special markers
+pub const COLOR_RED: Color = Color::Rgb(230, 90, 90); // Red - removed lines in diffs
 pub const COLOR_BLACK: Color = Color::Rgb(0, 0, 0); // Black - backgrounds
 
 // Enhanced chunk colors for better visualization
@@ -16,5 +17,15 @@ pub const COLOR_CHUNK_BOUNDARY: Color = Color::Rgb(0, 255, 127); // Spring green
 pub const COLOR_CHUNK_TEXT: Color = Color::Rgb(255, 255, 255); // Bright white - highlighted chunk text
 pub const COLOR_CHUNK_LINE_NUM: Color = Color::Rgb(255, 215, 0); // Gold - highlighted chunk line numbers
 
+// Token colors for syntax highlighting in the Chunks view
+pub const COLOR_SYNTAX_KEYWORD: Color = Color::Rgb(198, 120, 221); // Purple - keywords
+pub const COLOR_SYNTAX_TYPE: Color = Color::Rgb(229, 192, 123); // Sand - types
+pub const COLOR_SYNTAX_FUNCTION: Color = Color::Rgb(97, 175, 239); // Blue - function names
+pub const COLOR_SYNTAX_STRING: Color = Color::Rgb(152, 195, 121); // Green - strings
+pub const COLOR_SYNTAX_NUMBER: Color = Color::Rgb(209, 154, 102); // Orange - numbers
+pub const COLOR_SYNTAX_COMMENT: Color = Color::Rgb(110, 120, 135); // Slate - comments
+
 pub const SPINNER_FRAMES: [char; 4] = ['|', '/', '-', '\\'];
 pub const DEBOUNCE_MS: u64 = 300;
+pub const CONTEXT_STEP: usize = 3; // Lines added or removed per expand/shrink context
+pub const RELATED_CHUNKS: usize = 10; // Neighbours listed in the related chunks panel
@@ -1,12 +1,11 @@
-use crate::chunks::IndexedChunkMeta;
+use crate::chunks::chunk_glyph_legend;
 use crate::colors::*;
+use crate::preview::load_chunk_spans;
 use crate::state::TuiState;
 use crate::utils::find_repo_root;
 use anyhow::Result;
-use ck_index::load_index_entry;
 use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
-use std::path::Path;
 
 pub fn execute_command(state: &mut TuiState) -> Result<()> {
     let cmd = state.query.trim();
@@ -29,6 +28,9 @@ pub fn execute_command(state: &mut TuiState) -> Result<()> {
         "/stats" => {
             show_stats(state);
         }
+        "/legend" => {
+            show_legend(state);
+        }
         _ => {
             state.status_message = format!(
                 "Unknown command: {}. Type /help for available commands",
@@ -49,17 +51,38 @@ fn show_help(state: &mut TuiState) {
         "  /clear, /c       - Clear results and search".to_string(),
         "  /history         - Show search history".to_string(),
         "  /stats           - Show index statistics".to_string(),
+        "  /legend          - Explain the chunk view glyphs (also ? with an empty query)"
+            .to_string(),
         "".to_string(),
-        "━━━ KEYBINDINGS ━━━".to_string(),
+        "━━━ KEYBINDINGS (defaults, remap in tui.json) ━━━".to_string(),
         "".to_string(),
+        "  :                - Command palette, every action with its keys (empty query)"
+            .to_string(),
         "  Tab              - Cycle search modes (SEM/REG/HYB)".to_string(),
         "  Ctrl+V           - Cycle preview modes (Heatmap/Syntax/Chunks)".to_string(),
         "  Ctrl+F           - Toggle snippet/full file view".to_string(),
+        "  Ctrl+R           - Toggle relevance-trimmed snippets".to_string(),
+        "  Ctrl+O           - Collapse sibling chunks around the match (Chunks view)".to_string(),
+        "  Ctrl+T           - Collapse multi-line text chunks to one row (Chunks view)".to_string(),
+        "  Alt+Up/Down      - Step the preview through the chunk map (click a cell to jump)"
+            .to_string(),
         "  Ctrl+D           - Show chunk metadata (debug)".to_string(),
+        "  Alt+D            - Toggle the selected chunk's raw bytes, whitespace shown (debug)"
+            .to_string(),
+        "  Ctrl+N           - Related chunks panel (↑↓ move, Enter jump, Esc close)".to_string(),
+        "  Ctrl+G           - Go to the definition of the identifier at the result".to_string(),
+        "  Ctrl+U           - Jump up the breadcrumb to the enclosing chunk".to_string(),
+        "  Left/Right       - Scroll nested chunk columns (Chunks view)".to_string(),
+        "  Ctrl+Y           - Copy matched chunk to clipboard".to_string(),
+        "  Ctrl+P           - Copy chunk + enclosing signatures as LLM prompt".to_string(),
+        "  Alt+Y/Alt+N      - Mark result relevant/irrelevant".to_string(),
+        "  Alt+R            - Re-rank results by the marks (Rocchio feedback)".to_string(),
         "  Ctrl+Space       - Multi-select files".to_string(),
         "  Ctrl+Up/Down     - Navigate search history".to_string(),
         "  Up/Down          - Navigate results".to_string(),
+        "  Home/End         - Jump to first/last result".to_string(),
         "  PgUp/PgDn        - Scroll preview".to_string(),
+        "  Ctrl+E/Ctrl+W    - Expand/shrink snippet context".to_string(),
         "  Enter            - Open in $EDITOR".to_string(),
         "  Esc, q, Ctrl+C   - Quit".to_string(),
         "".to_string(),
@@ -88,6 +111,7 @@ fn show_help(state: &mut TuiState) {
                     Style::default().fg(COLOR_CYAN).add_modifier(Modifier::BOLD),
                 ))
             } else if line.starts_with("  /")
+                || line.starts_with("  :")
                 || line.starts_with("  Ctrl")
                 || line.starts_with("  Tab")
                 || line.starts_with("  Up")
@@ -134,6 +158,40 @@ fn show_help(state: &mut TuiState) {
     state.status_message = "Help - Press Esc to return to search".to_string();
 }
 
+/// Show what the chunk view glyphs and highlight colours mean in the preview pane
+pub fn show_legend(state: &mut TuiState) {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "━━━ CHUNK VIEW LEGEND ━━━",
+            Style::default().fg(COLOR_CYAN).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    lines.extend(chunk_glyph_legend().into_iter().map(|(glyph, meaning)| {
+        Line::from(vec![
+            Span::styled(
+                format!("  {:<6} ", glyph),
+                Style::default()
+                    .fg(COLOR_CHUNK_BOUNDARY)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(meaning, Style::default().fg(COLOR_WHITE)),
+        ])
+    }));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Move to another result or keep typing to return to the preview",
+        Style::default()
+            .fg(COLOR_DARK_GRAY)
+            .add_modifier(Modifier::ITALIC),
+    )));
+
+    state.preview_lines = lines;
+    state.query.clear();
+    state.command_mode = false;
+    state.status_message = "Chunk view legend".to_string();
+}
+
 pub fn show_chunks(state: &mut TuiState) {
     // Get currently selected file
     if state.results.is_empty() {
@@ -264,45 +322,6 @@ pub fn show_chunks(state: &mut TuiState) {
     );
 }
 
-fn load_chunk_spans(repo_root: &Path, file_path: &Path) -> Result<Vec<IndexedChunkMeta>, String> {
-    let standard_path = file_path
-        .strip_prefix(repo_root)
-        .unwrap_or(file_path)
-        .to_path_buf();
-    let index_dir = repo_root.join(".ck");
-    let sidecar_path = index_dir.join(format!("{}.ck", standard_path.display()));
-
-    if !sidecar_path.exists() {
-        return Ok(Vec::new());
-    }
-
-    let entry = load_index_entry(&sidecar_path)
-        .map_err(|err| format!("Failed to load chunk metadata: {}", err))?;
-    let mut metas: Vec<IndexedChunkMeta> = entry
-        .chunks
-        .iter()
-        .map(|chunk| IndexedChunkMeta {
-            span: chunk.span.clone(),
-            chunk_type: chunk.chunk_type.clone(),
-            breadcrumb: chunk.breadcrumb.clone(),
-            ancestry: chunk.ancestry.clone().unwrap_or_default(),
-            estimated_tokens: chunk.estimated_tokens,
-            byte_length: chunk.byte_length,
-            leading_trivia: chunk.leading_trivia.clone(),
-            trailing_trivia: chunk.trailing_trivia.clone(),
-        })
-        .collect();
-
-    let has_non_module = metas
-        .iter()
-        .any(|meta| meta.chunk_type.as_deref() != Some("module"));
-    if has_non_module {
-        metas.retain(|meta| meta.chunk_type.as_deref() != Some("module"));
-    }
-
-    Ok(metas)
-}
-
 fn show_history(state: &mut TuiState) {
     if state.search_history.is_empty() {
         state.status_message = "No search history".to_string();
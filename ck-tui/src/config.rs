@@ -1,6 +1,7 @@
 use anyhow::Result;
-use ck_core::SearchMode;
+use ck_core::{SearchMode, WhitespaceMode};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -16,6 +17,125 @@ pub struct TuiConfig {
     pub search_mode: SearchMode,
     pub preview_mode: PreviewMode,
     pub full_file_mode: bool,
+    /// Include doc comments/attributes when copying a chunk (Ctrl+Y)
+    #[serde(default = "default_true")]
+    pub copy_leading_trivia: bool,
+    /// Whitespace in copied chunks and prompts; the preview always shows the source as is
+    #[serde(default = "default_copy_whitespace")]
+    pub copy_whitespace: WhitespaceMode,
+    /// Estimated token cap for prompts copied with Ctrl+P
+    #[serde(default = "default_prompt_token_budget")]
+    pub prompt_token_budget: usize,
+    /// Nesting columns shown in the chunk view before it scrolls horizontally
+    #[serde(default = "default_max_chunk_columns")]
+    pub max_chunk_columns: usize,
+    /// Characters of a source line the preview shows before cutting it off
+    /// (minified files can have lines of hundreds of thousands); 0 shows every line whole
+    #[serde(default = "default_max_line_chars")]
+    pub max_line_chars: usize,
+    /// Context lines shown around a match, per chunk type
+    #[serde(default)]
+    pub context_lines: ContextLinesConfig,
+    /// Trim snippet previews to the lines that best match the query (Ctrl+R)
+    #[serde(default)]
+    pub relevant_snippets: bool,
+    /// Lines kept when trimming a preview to its most relevant region
+    #[serde(default = "default_relevant_snippet_lines")]
+    pub relevant_snippet_lines: usize,
+    /// In the Chunks snippet view, collapse sibling chunks to their signatures
+    /// around the matched one (Ctrl+O)
+    #[serde(default)]
+    pub sibling_outline: bool,
+    /// In the Chunks view, show each multi-line text chunk (imports, comments)
+    /// as one collapsed row (Ctrl+T)
+    #[serde(default)]
+    pub collapse_text_chunks: bool,
+    /// Draw the chunk map (chunks and matches across the whole file) down the
+    /// preview's right edge
+    #[serde(default = "default_true")]
+    pub minimap: bool,
+    /// Color the code in the Chunks view by token (keywords, strings, comments...)
+    /// for languages with a tree-sitter grammar
+    #[serde(default = "default_true")]
+    pub chunk_syntax_highlighting: bool,
+    /// Rocchio weights for re-ranking by relevance feedback (Alt+R)
+    #[serde(default)]
+    pub feedback_weights: ck_engine::FeedbackWeights,
+    /// Action name to key sequences, replacing that action's default keys
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keybindings: BTreeMap<String, Vec<String>>,
+}
+
+/// Preview context around a matched chunk. Keys of `by_type` use the chunk type
+/// names from the index (`text`, `function`, `class`, `method`, `module`); any
+/// type not listed falls back to `default`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ContextLinesConfig {
+    #[serde(default = "default_context_lines")]
+    pub default: usize,
+    #[serde(default = "default_context_lines_by_type")]
+    pub by_type: HashMap<String, usize>,
+}
+
+impl ContextLinesConfig {
+    pub fn for_chunk_type(&self, chunk_type: Option<&str>) -> usize {
+        chunk_type
+            .and_then(|kind| self.by_type.get(kind))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+impl Default for ContextLinesConfig {
+    fn default() -> Self {
+        Self {
+            default: default_context_lines(),
+            by_type: default_context_lines_by_type(),
+        }
+    }
+}
+
+/// Preview context from `-B`/`-A`/`-C` for one run, replacing the configured
+/// context on either side of the match. `None` keeps `context_lines`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ContextOverride {
+    pub before: Option<usize>,
+    pub after: Option<usize>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_copy_whitespace() -> WhitespaceMode {
+    WhitespaceMode::Normalized
+}
+
+fn default_prompt_token_budget() -> usize {
+    4000
+}
+
+fn default_max_chunk_columns() -> usize {
+    6
+}
+
+fn default_max_line_chars() -> usize {
+    2000
+}
+
+fn default_relevant_snippet_lines() -> usize {
+    12
+}
+
+fn default_context_lines() -> usize {
+    5
+}
+
+fn default_context_lines_by_type() -> HashMap<String, usize> {
+    [("text", 2), ("function", 5), ("class", 3)]
+        .into_iter()
+        .map(|(kind, lines)| (kind.to_string(), lines))
+        .collect()
 }
 
 mod search_mode_serde {
@@ -30,6 +150,7 @@ mod search_mode_serde {
             SearchMode::Regex => "regex",
             SearchMode::Hybrid => "hybrid",
             SearchMode::Lexical => "lexical",
+            SearchMode::Structural => "structural",
         };
         serializer.serialize_str(s)
     }
@@ -44,6 +165,7 @@ mod search_mode_serde {
             "regex" => SearchMode::Regex,
             "hybrid" => SearchMode::Hybrid,
             "lexical" => SearchMode::Lexical,
+            "structural" => SearchMode::Structural,
             _ => SearchMode::Semantic, // Default fallback
         })
     }
@@ -55,6 +177,20 @@ impl Default for TuiConfig {
             search_mode: SearchMode::Semantic,
             preview_mode: PreviewMode::Heatmap,
             full_file_mode: true,
+            copy_leading_trivia: true,
+            copy_whitespace: default_copy_whitespace(),
+            prompt_token_budget: default_prompt_token_budget(),
+            max_chunk_columns: default_max_chunk_columns(),
+            max_line_chars: default_max_line_chars(),
+            context_lines: ContextLinesConfig::default(),
+            relevant_snippets: false,
+            relevant_snippet_lines: default_relevant_snippet_lines(),
+            sibling_outline: false,
+            collapse_text_chunks: false,
+            minimap: true,
+            chunk_syntax_highlighting: true,
+            feedback_weights: ck_engine::FeedbackWeights::default(),
+            keybindings: BTreeMap::new(),
         }
     }
 }
@@ -87,3 +223,25 @@ impl TuiConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_lines_fall_back_to_default() {
+        let config: TuiConfig = serde_json::from_str(
+            r#"{"search_mode":"semantic","preview_mode":"Chunks","full_file_mode":false,
+                "context_lines":{"default":4,"by_type":{"method":1}}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.context_lines.for_chunk_type(Some("method")), 1);
+        assert_eq!(config.context_lines.for_chunk_type(Some("function")), 4);
+        assert_eq!(config.context_lines.for_chunk_type(None), 4);
+
+        let defaults = ContextLinesConfig::default();
+        assert_eq!(defaults.for_chunk_type(Some("text")), 2);
+        assert_eq!(defaults.for_chunk_type(Some("class")), 3);
+        assert_eq!(defaults.for_chunk_type(Some("module")), 5);
+    }
+}
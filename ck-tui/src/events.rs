@@ -14,6 +14,11 @@ pub enum UiEvent {
         generation: u64,
         message: String,
     },
+    /// Best results so far from a search that is still scoring
+    SearchPartial {
+        generation: u64,
+        results: Vec<SearchResult>,
+    },
     SearchCompleted {
         generation: u64,
         results: Vec<SearchResult>,
@@ -24,4 +29,8 @@ pub enum UiEvent {
         generation: u64,
         error: String,
     },
+    /// The background model warm-up started with the TUI finished
+    WarmupDone {
+        result: Result<String, String>,
+    },
 }
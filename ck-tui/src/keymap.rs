@@ -0,0 +1,504 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Something the user can bind a key to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    NextMatch,
+    PrevMatch,
+    FirstMatch,
+    LastMatch,
+    ScrollUp,
+    ScrollDown,
+    Open,
+    CopyChunk,
+    CopyPrompt,
+    CyclePreviewMode,
+    ToggleFullFile,
+    ToggleRelevantSnippets,
+    ToggleSiblingOutline,
+    ToggleCollapsedText,
+    MinimapUp,
+    MinimapDown,
+    ShowChunks,
+    ToggleRawChunk,
+    ToggleSelect,
+    CycleSearchMode,
+    HistoryPrev,
+    HistoryNext,
+    ColumnsOutward,
+    ColumnsInward,
+    ExpandContext,
+    ShrinkContext,
+    ToggleRelatedChunks,
+    GotoDefinition,
+    JumpToParent,
+    ShowLegend,
+    CycleSort,
+    MarkRelevant,
+    MarkIrrelevant,
+    RerankWithFeedback,
+    CommandPalette,
+}
+
+impl Action {
+    pub const ALL: [Action; 36] = [
+        Action::Quit,
+        Action::NextMatch,
+        Action::PrevMatch,
+        Action::FirstMatch,
+        Action::LastMatch,
+        Action::ScrollUp,
+        Action::ScrollDown,
+        Action::Open,
+        Action::CopyChunk,
+        Action::CopyPrompt,
+        Action::CyclePreviewMode,
+        Action::ToggleFullFile,
+        Action::ToggleRelevantSnippets,
+        Action::ToggleSiblingOutline,
+        Action::ToggleCollapsedText,
+        Action::MinimapUp,
+        Action::MinimapDown,
+        Action::ShowChunks,
+        Action::ToggleRawChunk,
+        Action::ToggleSelect,
+        Action::CycleSearchMode,
+        Action::HistoryPrev,
+        Action::HistoryNext,
+        Action::ColumnsOutward,
+        Action::ColumnsInward,
+        Action::ExpandContext,
+        Action::ShrinkContext,
+        Action::ToggleRelatedChunks,
+        Action::GotoDefinition,
+        Action::JumpToParent,
+        Action::ShowLegend,
+        Action::CycleSort,
+        Action::MarkRelevant,
+        Action::MarkIrrelevant,
+        Action::RerankWithFeedback,
+        Action::CommandPalette,
+    ];
+
+    /// Name used for the action in the `keybindings` config section
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::NextMatch => "next_match",
+            Action::PrevMatch => "prev_match",
+            Action::FirstMatch => "first_match",
+            Action::LastMatch => "last_match",
+            Action::ScrollUp => "scroll_up",
+            Action::ScrollDown => "scroll_down",
+            Action::Open => "open",
+            Action::CopyChunk => "copy_chunk",
+            Action::CopyPrompt => "copy_prompt",
+            Action::CyclePreviewMode => "cycle_preview_mode",
+            Action::ToggleFullFile => "toggle_full_file",
+            Action::ToggleRelevantSnippets => "toggle_relevant_snippets",
+            Action::ToggleSiblingOutline => "toggle_sibling_outline",
+            Action::ToggleCollapsedText => "toggle_collapsed_text",
+            Action::MinimapUp => "minimap_up",
+            Action::MinimapDown => "minimap_down",
+            Action::ShowChunks => "show_chunks",
+            Action::ToggleRawChunk => "toggle_raw_chunk",
+            Action::ToggleSelect => "toggle_select",
+            Action::CycleSearchMode => "cycle_search_mode",
+            Action::HistoryPrev => "history_prev",
+            Action::HistoryNext => "history_next",
+            Action::ColumnsOutward => "columns_outward",
+            Action::ColumnsInward => "columns_inward",
+            Action::ExpandContext => "expand_context",
+            Action::ShrinkContext => "shrink_context",
+            Action::ToggleRelatedChunks => "toggle_related_chunks",
+            Action::GotoDefinition => "goto_definition",
+            Action::JumpToParent => "jump_to_parent",
+            Action::ShowLegend => "show_legend",
+            Action::CycleSort => "cycle_sort",
+            Action::MarkRelevant => "mark_relevant",
+            Action::MarkIrrelevant => "mark_irrelevant",
+            Action::RerankWithFeedback => "rerank_with_feedback",
+            Action::CommandPalette => "command_palette",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    /// The bindings used when the config does not mention the action
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["esc", "q", "ctrl+c"],
+            Action::NextMatch => &["down"],
+            Action::PrevMatch => &["up"],
+            Action::FirstMatch => &["home"],
+            Action::LastMatch => &["end"],
+            Action::ScrollUp => &["pageup"],
+            Action::ScrollDown => &["pagedown"],
+            Action::Open => &["enter"],
+            Action::CopyChunk => &["ctrl+y"],
+            Action::CopyPrompt => &["ctrl+p"],
+            Action::CyclePreviewMode => &["ctrl+v"],
+            Action::ToggleFullFile => &["ctrl+f"],
+            Action::ToggleRelevantSnippets => &["ctrl+r"],
+            Action::ToggleSiblingOutline => &["ctrl+o"],
+            Action::ToggleCollapsedText => &["ctrl+t"],
+            Action::MinimapUp => &["alt+up"],
+            Action::MinimapDown => &["alt+down"],
+            Action::ShowChunks => &["ctrl+d"],
+            Action::ToggleRawChunk => &["alt+d"],
+            Action::ToggleSelect => &["ctrl+space"],
+            Action::CycleSearchMode => &["tab"],
+            Action::HistoryPrev => &["ctrl+up"],
+            Action::HistoryNext => &["ctrl+down"],
+            Action::ColumnsOutward => &["left"],
+            Action::ColumnsInward => &["right"],
+            Action::ExpandContext => &["ctrl+e"],
+            Action::ShrinkContext => &["ctrl+w"],
+            Action::ToggleRelatedChunks => &["ctrl+n"],
+            Action::GotoDefinition => &["ctrl+g"],
+            Action::JumpToParent => &["ctrl+u"],
+            Action::ShowLegend => &["?"],
+            Action::CycleSort => &[],
+            Action::MarkRelevant => &["alt+y"],
+            Action::MarkIrrelevant => &["alt+n"],
+            Action::RerankWithFeedback => &["alt+r"],
+            Action::CommandPalette => &[":"],
+        }
+    }
+}
+
+/// A single key press, normalised so `G` and `shift+g` compare equal
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyPress {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyPress {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let relevant = KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT;
+        let mut modifiers = modifiers & relevant;
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            other => other,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parse a key such as `j`, `G`, `ctrl+y`, `alt+enter` or `pagedown`
+    pub fn parse(spec: &str) -> Option<Self> {
+        let (modifier_part, key) = match spec.strip_suffix('+') {
+            // A trailing `+` is the plus key itself (`+` or `ctrl++`)
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => {
+                (rest.strip_suffix('+').unwrap_or(rest), "+")
+            }
+            _ => match spec.rsplit_once('+') {
+                Some((modifiers, key)) => (modifiers, key),
+                None => ("", spec),
+            },
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in modifier_part.split('+').filter(|part| !part.is_empty()) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return None,
+                },
+            },
+        };
+
+        Some(Self::new(code, modifiers))
+    }
+
+    /// Whether this press should be typed into the query when nothing is bound to it
+    pub fn is_plain_char(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && !self
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+}
+
+/// The key in the form [`KeyPress::parse`] reads, e.g. `ctrl+y` or `pagedown`
+impl fmt::Display for KeyPress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, label) in [
+            (KeyModifiers::CONTROL, "ctrl+"),
+            (KeyModifiers::ALT, "alt+"),
+            (KeyModifiers::SHIFT, "shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(label)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::Esc => f.write_str("esc"),
+            KeyCode::Enter => f.write_str("enter"),
+            KeyCode::Tab => f.write_str("tab"),
+            KeyCode::BackTab => f.write_str("backtab"),
+            KeyCode::Backspace => f.write_str("backspace"),
+            KeyCode::Delete => f.write_str("delete"),
+            KeyCode::Insert => f.write_str("insert"),
+            KeyCode::Up => f.write_str("up"),
+            KeyCode::Down => f.write_str("down"),
+            KeyCode::Left => f.write_str("left"),
+            KeyCode::Right => f.write_str("right"),
+            KeyCode::Home => f.write_str("home"),
+            KeyCode::End => f.write_str("end"),
+            KeyCode::PageUp => f.write_str("pageup"),
+            KeyCode::PageDown => f.write_str("pagedown"),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+impl From<KeyEvent> for KeyPress {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+/// Result of feeding the keys pressed so far to the keymap
+#[derive(Debug, PartialEq, Eq)]
+pub enum KeyLookup {
+    Action(Action),
+    /// The keys start a longer binding (e.g. the first `g` of `g g`)
+    Pending,
+    Unbound,
+}
+
+/// Key sequences bound to actions
+#[derive(Clone, Debug)]
+pub struct KeyMap {
+    bindings: Vec<(Vec<KeyPress>, Action)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::from_config(&BTreeMap::new()).0
+    }
+}
+
+impl KeyMap {
+    /// Build the keymap from the `keybindings` config section, which maps action
+    /// names to key sequences (`"g g"` is `g` pressed twice). Listed actions replace
+    /// their defaults, and a key claimed by the config is removed from any default
+    /// binding. Unknown actions or keys are skipped and described in the returned
+    /// warnings.
+    pub fn from_config(overrides: &BTreeMap<String, Vec<String>>) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut configured = Vec::new();
+        let mut custom: Vec<(Vec<KeyPress>, Action)> = Vec::new();
+
+        for (name, specs) in overrides {
+            let Some(action) = Action::from_name(name) else {
+                warnings.push(format!("unknown action '{}'", name));
+                continue;
+            };
+            configured.push(action);
+            for spec in specs {
+                match parse_sequence(spec) {
+                    Some(keys) => custom.push((keys, action)),
+                    None => warnings.push(format!("unknown key '{}' for {}", spec, name)),
+                }
+            }
+        }
+
+        let mut bindings: Vec<(Vec<KeyPress>, Action)> = Action::ALL
+            .into_iter()
+            .filter(|action| !configured.contains(action))
+            .flat_map(|action| {
+                action
+                    .default_keys()
+                    .iter()
+                    .filter_map(move |spec| parse_sequence(spec).map(|keys| (keys, action)))
+            })
+            .filter(|(keys, _)| !custom.iter().any(|(claimed, _)| claimed == keys))
+            .collect();
+        bindings.extend(custom);
+
+        (Self { bindings }, warnings)
+    }
+
+    /// Look up the keys pressed so far. An exact binding wins over a longer one
+    /// sharing its prefix.
+    pub fn lookup(&self, keys: &[KeyPress]) -> KeyLookup {
+        if let Some((_, action)) = self.bindings.iter().find(|(bound, _)| bound == keys) {
+            return KeyLookup::Action(*action);
+        }
+        if self
+            .bindings
+            .iter()
+            .any(|(bound, _)| bound.len() > keys.len() && bound.starts_with(keys))
+        {
+            return KeyLookup::Pending;
+        }
+        KeyLookup::Unbound
+    }
+
+    /// The key sequences bound to `action`, as they'd be written in the config
+    pub fn keys_for(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(keys, _)| {
+                keys.iter()
+                    .map(KeyPress::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+}
+
+fn parse_sequence(spec: &str) -> Option<Vec<KeyPress>> {
+    let keys: Option<Vec<KeyPress>> = spec.split_whitespace().map(KeyPress::parse).collect();
+    keys.filter(|keys| !keys.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(spec: &str) -> KeyPress {
+        KeyPress::parse(spec).unwrap()
+    }
+
+    #[test]
+    fn parses_named_and_modified_keys() {
+        assert_eq!(
+            press("ctrl+y"),
+            KeyPress::new(KeyCode::Char('y'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(press("G"), press("shift+g"));
+        assert_eq!(
+            press("G"),
+            KeyPress::from(KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT))
+        );
+        assert_eq!(press("PageDown").code, KeyCode::PageDown);
+        assert_eq!(press("ctrl+space").code, KeyCode::Char(' '));
+        assert_eq!(press("+").code, KeyCode::Char('+'));
+        assert_eq!(press("ctrl++").modifiers, KeyModifiers::CONTROL);
+        assert_eq!(press("f5").code, KeyCode::F(5));
+        assert!(KeyPress::parse("hyper+x").is_none());
+        assert!(KeyPress::parse("pgdown").is_none());
+        assert!(press("j").is_plain_char());
+        assert!(!press("ctrl+j").is_plain_char());
+        for spec in ["ctrl+y", "G", "alt+up", "space", "f5", "?", "pagedown"] {
+            assert_eq!(press(spec).to_string(), spec);
+        }
+    }
+
+    #[test]
+    fn defaults_match_builtin_keys() {
+        let keymap = KeyMap::default();
+        assert_eq!(
+            keymap.lookup(&[press("ctrl+y")]),
+            KeyLookup::Action(Action::CopyChunk)
+        );
+        assert_eq!(
+            keymap.lookup(&[press("q")]),
+            KeyLookup::Action(Action::Quit)
+        );
+        assert_eq!(
+            keymap.lookup(&[press("ctrl+up")]),
+            KeyLookup::Action(Action::HistoryPrev)
+        );
+        // Shift is folded into the character, so a shifted `?` still matches
+        assert_eq!(
+            keymap.lookup(&[KeyPress::new(KeyCode::Char('?'), KeyModifiers::SHIFT)]),
+            KeyLookup::Action(Action::ShowLegend)
+        );
+        assert_eq!(keymap.lookup(&[press("j")]), KeyLookup::Unbound);
+        assert_eq!(keymap.keys_for(Action::Quit), vec!["esc", "q", "ctrl+c"]);
+        assert!(keymap.keys_for(Action::CycleSort).is_empty());
+    }
+
+    #[test]
+    fn config_overrides_defaults_and_warns() {
+        let overrides: BTreeMap<String, Vec<String>> = [
+            ("next_match", vec!["j", "down"]),
+            ("first_match", vec!["g g"]),
+            ("last_match", vec!["G"]),
+            ("open", vec!["ctrl+y"]),
+            ("teleport", vec!["t"]),
+            ("prev_match", vec!["k", "pgdown"]),
+        ]
+        .into_iter()
+        .map(|(name, keys)| {
+            (
+                name.to_string(),
+                keys.into_iter().map(str::to_string).collect(),
+            )
+        })
+        .collect();
+
+        let (keymap, warnings) = KeyMap::from_config(&overrides);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings.iter().any(|w| w.contains("teleport")));
+        assert!(warnings.iter().any(|w| w.contains("pgdown")));
+
+        assert_eq!(
+            keymap.lookup(&[press("j")]),
+            KeyLookup::Action(Action::NextMatch)
+        );
+        assert_eq!(keymap.lookup(&[press("g")]), KeyLookup::Pending);
+        assert_eq!(
+            keymap.lookup(&[press("g"), press("g")]),
+            KeyLookup::Action(Action::FirstMatch)
+        );
+        assert_eq!(keymap.keys_for(Action::FirstMatch), vec!["g g"]);
+        assert_eq!(
+            keymap.lookup(&[press("G")]),
+            KeyLookup::Action(Action::LastMatch)
+        );
+        // The default Home binding is replaced, and ctrl+y moved from copy to open
+        assert_eq!(keymap.lookup(&[press("home")]), KeyLookup::Unbound);
+        assert_eq!(
+            keymap.lookup(&[press("ctrl+y")]),
+            KeyLookup::Action(Action::Open)
+        );
+        assert_eq!(keymap.lookup(&[press("enter")]), KeyLookup::Unbound);
+        // The up arrow stays unbound once prev_match is remapped
+        assert_eq!(keymap.lookup(&[press("up")]), KeyLookup::Unbound);
+    }
+}
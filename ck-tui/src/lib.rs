@@ -1,11 +1,16 @@
 pub mod app;
 pub mod chunks;
+pub mod clipboard;
 pub mod colors;
 pub mod commands;
 pub mod config;
 pub mod events;
+pub mod keymap;
+pub mod minimap;
+pub mod palette;
 pub mod preview;
 pub mod rendering;
+pub mod session;
 pub mod state;
 pub mod utils;
 
@@ -15,15 +20,32 @@ use std::path::PathBuf;
 // Re-export main types for public API
 pub use app::TuiApp;
 pub use chunks::{
-    ChunkColumnChar, ChunkDisplayLine, IndexedChunkMeta, chunk_display_line_to_string,
-    chunk_file_live,
+    ChunkColumnChar, ChunkDisplayLine, ColumnWindow, DiffChange, GutterMode, IndexedChunkMeta,
+    RenderedCells, add_sibling_outline, bracket_columns_at, canonical_breadcrumb,
+    chunk_display_line_to_cells, chunk_display_line_to_string,
+    chunk_display_line_to_string_with_gutter, chunk_file_live, chunk_glyph_legend,
+    collapse_text_chunks, collect_chunk_diff_lines, format_chunk_as_prompt,
+    format_chunk_for_clipboard, highlight_syntax, line_byte_ranges, relevant_line_range,
+    visible_whitespace_lines,
 };
-pub use config::{PreviewMode, TuiConfig};
+pub use config::{ContextLinesConfig, ContextOverride, PreviewMode, TuiConfig};
+pub use keymap::{Action, KeyMap, KeyPress};
 pub use preview::dump_chunk_view_internal;
 
 /// Main entry point to run the TUI application
-pub async fn run_tui(search_path: PathBuf, initial_query: Option<String>) -> Result<()> {
-    let app = TuiApp::new(search_path, initial_query);
+pub async fn run_tui(
+    search_path: PathBuf,
+    initial_query: Option<String>,
+    sort: Option<ck_core::ResultSort>,
+    context: ContextOverride,
+    restore_session: bool,
+) -> Result<()> {
+    let mut app = TuiApp::new(search_path, initial_query);
+    app.state.sort = sort;
+    app.state.context_override = context;
+    if restore_session {
+        app.restore_session();
+    }
     app.run().await
 }
 
@@ -0,0 +1,138 @@
+//! The chunk map drawn down the preview's right edge: one cell per band of
+//! file lines, shaded by structural chunks and match density, with the lines
+//! on screen marked so a large file's layout is visible at a glance.
+
+use crate::chunks::IndexedChunkMeta;
+
+/// One band of lines in the chunk map
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MinimapCell {
+    /// 1-based first and last line of the band
+    pub line_start: usize,
+    pub line_end: usize,
+    /// Whether a structural chunk (function, class, method, module) overlaps the band
+    pub has_chunk: bool,
+    /// Matches starting on lines in the band
+    pub matches: usize,
+    /// Whether the preview shows any of the band's lines
+    pub in_viewport: bool,
+}
+
+impl MinimapCell {
+    /// Darker shades for more matches, the lightest for chunk-only bands
+    pub fn glyph(&self) -> char {
+        match (self.matches, self.has_chunk) {
+            (0, false) => ' ',
+            (0, true) => '░',
+            (1, _) => '▒',
+            (2..=3, _) => '▓',
+            _ => '█',
+        }
+    }
+}
+
+/// Split `total_lines` into at most `rows` equal bands and describe each.
+/// `viewport` is the 1-based first and last line on screen, if any.
+pub fn chunk_minimap(
+    total_lines: usize,
+    chunks: &[IndexedChunkMeta],
+    match_lines: &[usize],
+    viewport: Option<(usize, usize)>,
+    rows: usize,
+) -> Vec<MinimapCell> {
+    if total_lines == 0 || rows == 0 {
+        return Vec::new();
+    }
+    let band = total_lines.div_ceil(rows);
+    let overlaps = |start: usize, end: usize, from: usize, to: usize| start <= to && from <= end;
+
+    (1..=total_lines)
+        .step_by(band)
+        .map(|line_start| {
+            let line_end = (line_start + band - 1).min(total_lines);
+            MinimapCell {
+                line_start,
+                line_end,
+                has_chunk: chunks.iter().any(|chunk| {
+                    chunk.chunk_type.as_deref() != Some("text")
+                        && overlaps(
+                            chunk.span.line_start,
+                            chunk.span.line_end,
+                            line_start,
+                            line_end,
+                        )
+                }),
+                matches: match_lines
+                    .iter()
+                    .filter(|&&line| (line_start..=line_end).contains(&line))
+                    .count(),
+                in_viewport: viewport
+                    .is_some_and(|(first, last)| overlaps(first, last, line_start, line_end)),
+            }
+        })
+        .collect()
+}
+
+/// The band after (or before) the first one on screen, for stepping the viewport
+/// through the map from the keyboard
+pub fn adjacent_cell(cells: &[MinimapCell], forward: bool) -> Option<&MinimapCell> {
+    let current = cells.iter().position(|cell| cell.in_viewport)?;
+    if forward {
+        cells.get(current + 1)
+    } else {
+        current.checked_sub(1).and_then(|before| cells.get(before))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ck_core::Span;
+
+    fn chunk(chunk_type: &str, start: usize, end: usize) -> IndexedChunkMeta {
+        IndexedChunkMeta {
+            span: Span::new_unchecked(0, 0, start, end),
+            chunk_type: Some(chunk_type.to_string()),
+            breadcrumb: None,
+            ancestry: Vec::new(),
+            name: None,
+            breadcrumb_segments: Vec::new(),
+            estimated_tokens: None,
+            byte_length: None,
+            leading_trivia: None,
+            trailing_trivia: None,
+            chunk_id: None,
+        }
+    }
+
+    #[test]
+    fn minimap_bands_lines_and_shades_matches() {
+        let chunks = vec![
+            chunk("text", 1, 10),
+            chunk("function", 25, 40),
+            chunk("function", 90, 100),
+        ];
+        let cells = chunk_minimap(100, &chunks, &[29, 30, 95], Some((21, 40)), 10);
+
+        assert_eq!(cells.len(), 10);
+        assert_eq!((cells[0].line_start, cells[0].line_end), (1, 10));
+        assert_eq!((cells[9].line_start, cells[9].line_end), (91, 100));
+        // Text chunks don't count as structure
+        let glyphs: String = cells.iter().map(MinimapCell::glyph).collect();
+        assert_eq!(glyphs, "  ▓░    ░▒");
+        let on_screen: Vec<usize> = cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.in_viewport)
+            .map(|(at, _)| at)
+            .collect();
+        assert_eq!(on_screen, vec![2, 3]);
+
+        assert_eq!(adjacent_cell(&cells, true).unwrap().line_start, 31);
+        assert_eq!(adjacent_cell(&cells, false).unwrap().line_start, 11);
+
+        // Short files get one cell per line
+        assert_eq!(chunk_minimap(3, &chunks, &[], None, 10).len(), 3);
+        assert!(chunk_minimap(0, &chunks, &[], None, 10).is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+use crate::keymap::{Action, KeyMap};
+
+/// One action offered by the palette, with the keys currently bound to it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub action: Action,
+    pub title: &'static str,
+    pub keys: Vec<String>,
+}
+
+/// The open command palette: every action, narrowed by a fuzzy filter
+#[derive(Clone, Debug)]
+pub struct CommandPalette {
+    pub filter: String,
+    pub selected: usize,
+    entries: Vec<PaletteEntry>,
+}
+
+impl CommandPalette {
+    /// Open the palette listing every action but itself, with its keys from `keymap`
+    pub fn new(keymap: &KeyMap) -> Self {
+        let entries = Action::ALL
+            .into_iter()
+            .filter(|&action| action != Action::CommandPalette)
+            .map(|action| PaletteEntry {
+                action,
+                title: title(action),
+                keys: keymap.keys_for(action),
+            })
+            .collect();
+        Self {
+            filter: String::new(),
+            selected: 0,
+            entries,
+        }
+    }
+
+    /// Entries matching the filter, best match first. Every character of the
+    /// filter has to appear in order in the title or config name; tighter and
+    /// earlier matches rank higher, ties keep the action order.
+    pub fn matches(&self) -> Vec<&PaletteEntry> {
+        let filter = self.filter.to_lowercase();
+        let mut ranked: Vec<((usize, usize), &PaletteEntry)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                [
+                    entry.title.to_lowercase(),
+                    entry.action.name().replace('_', " "),
+                ]
+                .iter()
+                .filter_map(|text| fuzzy_match(&filter, text))
+                .min()
+                .map(|rank| (rank, entry))
+            })
+            .collect();
+        ranked.sort_by_key(|(rank, _)| *rank);
+        ranked.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    /// The action under the cursor, if anything matches
+    pub fn selected_action(&self) -> Option<Action> {
+        self.matches().get(self.selected).map(|entry| entry.action)
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.filter.push(c);
+        self.selected = 0;
+    }
+
+    pub fn pop(&mut self) {
+        self.filter.pop();
+        self.selected = 0;
+    }
+
+    /// Move the cursor by `delta` rows, staying within the matches
+    pub fn step(&mut self, delta: isize) {
+        let last = self.matches().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+}
+
+/// What the action does, as listed in the palette
+pub fn title(action: Action) -> &'static str {
+    match action {
+        Action::Quit => "Quit",
+        Action::NextMatch => "Next result",
+        Action::PrevMatch => "Previous result",
+        Action::FirstMatch => "First result",
+        Action::LastMatch => "Last result",
+        Action::ScrollUp => "Scroll preview up",
+        Action::ScrollDown => "Scroll preview down",
+        Action::Open => "Open in editor",
+        Action::CopyChunk => "Copy chunk",
+        Action::CopyPrompt => "Copy chunk as LLM prompt",
+        Action::CyclePreviewMode => "Switch preview mode (heatmap/syntax/chunks)",
+        Action::ToggleFullFile => "Toggle full file view",
+        Action::ToggleRelevantSnippets => "Toggle relevance-trimmed snippets",
+        Action::ToggleSiblingOutline => "Toggle sibling outline",
+        Action::ToggleCollapsedText => "Toggle collapsed text chunks",
+        Action::MinimapUp => "Previous chunk in minimap",
+        Action::MinimapDown => "Next chunk in minimap",
+        Action::ShowChunks => "Show chunk metadata",
+        Action::ToggleRawChunk => "Toggle raw chunk bytes (debug)",
+        Action::ToggleSelect => "Toggle file selection",
+        Action::CycleSearchMode => "Switch search mode",
+        Action::HistoryPrev => "Previous search in history",
+        Action::HistoryNext => "Next search in history",
+        Action::ColumnsOutward => "Scroll chunk columns outward",
+        Action::ColumnsInward => "Scroll chunk columns inward",
+        Action::ExpandContext => "Expand context",
+        Action::ShrinkContext => "Shrink context",
+        Action::ToggleRelatedChunks => "Toggle related chunks",
+        Action::GotoDefinition => "Go to definition",
+        Action::JumpToParent => "Jump to enclosing chunk",
+        Action::ShowLegend => "Show glyph legend",
+        Action::CycleSort => "Switch sort (relevance/matches/score/path/lines)",
+        Action::MarkRelevant => "Mark result relevant",
+        Action::MarkIrrelevant => "Mark result irrelevant",
+        Action::RerankWithFeedback => "Re-rank results by feedback",
+        Action::CommandPalette => "Command palette",
+    }
+}
+
+/// Rank of the tightest in-order match of `filter` in `text` as (characters
+/// spanned, start); `None` when the characters don't all appear in order
+fn fuzzy_match(filter: &str, text: &str) -> Option<(usize, usize)> {
+    let mut wanted = filter.chars().filter(|c| !c.is_whitespace()).peekable();
+    let Some(&first) = wanted.peek() else {
+        return Some((0, 0));
+    };
+    let text: Vec<char> = text.chars().collect();
+    let wanted: Vec<char> = wanted.collect();
+    (0..text.len())
+        .filter(|&start| text[start] == first)
+        .filter_map(|start| {
+            let mut remaining = wanted.iter().peekable();
+            for (offset, c) in text[start..].iter().enumerate() {
+                if remaining.next_if_eq(&c).is_some() && remaining.peek().is_none() {
+                    return Some((offset + 1, start));
+                }
+            }
+            None
+        })
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(palette: &CommandPalette) -> Vec<&'static str> {
+        palette.matches().iter().map(|entry| entry.title).collect()
+    }
+
+    #[test]
+    fn lists_every_action_with_its_keys() {
+        let palette = CommandPalette::new(&KeyMap::default());
+        let matches = palette.matches();
+        assert_eq!(matches.len(), Action::ALL.len() - 1);
+        assert_eq!(matches[0].action, Action::Quit);
+        assert_eq!(matches[0].keys, vec!["esc", "q", "ctrl+c"]);
+        assert!(
+            matches
+                .iter()
+                .all(|entry| entry.action != Action::CommandPalette)
+        );
+    }
+
+    #[test]
+    fn filter_ranks_tight_matches_first() {
+        let mut palette = CommandPalette::new(&KeyMap::default());
+        for c in "copy".chars() {
+            palette.push(c);
+        }
+        assert_eq!(
+            titles(&palette)[..2],
+            ["Copy chunk", "Copy chunk as LLM prompt"]
+        );
+
+        palette.filter = "opn".to_string();
+        assert_eq!(palette.selected_action(), Some(Action::Open));
+
+        // Config names match too, with underscores read as spaces
+        palette.filter = "goto def".to_string();
+        assert_eq!(palette.selected_action(), Some(Action::GotoDefinition));
+
+        palette.filter = "zzz".to_string();
+        assert!(palette.matches().is_empty());
+        assert_eq!(palette.selected_action(), None);
+    }
+
+    #[test]
+    fn cursor_stays_within_matches() {
+        let mut palette = CommandPalette::new(&KeyMap::default());
+        palette.filter = "context".to_string();
+        palette.step(5);
+        assert_eq!(palette.selected, 1);
+        palette.step(-3);
+        assert_eq!(palette.selected, 0);
+        palette.push('x');
+        assert!(palette.matches().is_empty());
+        palette.step(1);
+        assert_eq!(palette.selected, 0);
+    }
+}
@@ -1,12 +1,15 @@
 use crate::chunks::{
-    ChunkDisplayLine, IndexedChunkMeta, chunk_file_live, collect_chunk_display_lines,
+    ChunkDisplayLine, ColumnWindow, DiffChange, IndexedChunkMeta, add_sibling_outline,
+    canonical_breadcrumb, chunk_display_line_to_string, chunk_file_live, clip_chunk_columns,
+    collapse_text_chunks, collect_chunk_display_lines, highlight_syntax, truncate_long_lines,
+    visible_whitespace_lines,
 };
 use crate::colors::*;
 use crate::utils::{
     apply_heatmap_color_to_token, calculate_token_similarity, find_repo_root, split_into_tokens,
-    syntax_set, theme_set,
+    syntax_set, theme_set, truncate_line, truncation_marker,
 };
-use ck_core::pdf;
+use ck_core::{Language, pdf};
 use ck_index::load_index_entry;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
@@ -36,7 +39,8 @@ pub fn load_preview_lines(
         let lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
         (content, lines)
     } else {
-        let content = fs::read_to_string(&resolved_path)
+        // Archive entries are read straight from their archive
+        let content = ck_core::archive::read_to_string(&resolved_path)
             .map_err(|err| format!("Could not read {}: {}", resolved_path.display(), err))?;
         let lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
         (content, lines)
@@ -68,7 +72,10 @@ pub fn load_preview_lines(
     Ok((lines, is_pdf, chunk_spans))
 }
 
-fn load_chunk_spans(repo_root: &Path, file_path: &Path) -> Result<Vec<IndexedChunkMeta>, String> {
+pub(crate) fn load_chunk_spans(
+    repo_root: &Path,
+    file_path: &Path,
+) -> Result<Vec<IndexedChunkMeta>, String> {
     let standard_path = file_path
         .strip_prefix(repo_root)
         .unwrap_or(file_path)
@@ -90,10 +97,15 @@ fn load_chunk_spans(repo_root: &Path, file_path: &Path) -> Result<Vec<IndexedChu
             chunk_type: chunk.chunk_type.clone(),
             breadcrumb: chunk.breadcrumb.clone(),
             ancestry: chunk.ancestry.clone().unwrap_or_default(),
+            name: chunk.name.clone(),
+            breadcrumb_segments: chunk.breadcrumb_segments.clone().unwrap_or_default(),
             estimated_tokens: chunk.estimated_tokens,
             byte_length: chunk.byte_length,
             leading_trivia: chunk.leading_trivia.clone(),
             trailing_trivia: chunk.trailing_trivia.clone(),
+            chunk_id: chunk.chunk_hash.as_deref().map(|hash| {
+                ck_core::compute_chunk_id(&standard_path, chunk.breadcrumb.as_deref(), hash)
+            }),
         })
         .collect();
 
@@ -107,6 +119,64 @@ fn load_chunk_spans(repo_root: &Path, file_path: &Path) -> Result<Vec<IndexedChu
     Ok(metas)
 }
 
+/// Dim `⋯` marker standing in for preview lines trimmed away
+pub fn elision_line(hidden: usize) -> Line<'static> {
+    let noun = if hidden == 1 { "line" } else { "lines" };
+    Line::from(vec![Span::styled(
+        format!("  ⋯ {} {} hidden", hidden, noun),
+        Style::default().fg(COLOR_DARK_GRAY),
+    )])
+}
+
+/// The span marking where a long line was cut off
+fn truncation_span(hidden: usize) -> Span<'static> {
+    Span::styled(
+        truncation_marker(hidden),
+        Style::default()
+            .fg(COLOR_DARK_GRAY)
+            .add_modifier(Modifier::ITALIC),
+    )
+}
+
+/// `text` as spans in `base` style, with the tokens `highlights` covers
+/// recolored by class. Spans past the end of a truncated line are dropped.
+fn highlighted_text_spans(
+    text: String,
+    highlights: &[ck_chunk::HighlightSpan],
+    base: Style,
+) -> Vec<Span<'static>> {
+    if highlights.is_empty() {
+        return vec![Span::styled(text, base)];
+    }
+    let mut spans = Vec::new();
+    let mut at = 0;
+    for highlight in highlights {
+        let end = highlight.end.min(text.len());
+        if highlight.start < at || highlight.start >= end {
+            continue;
+        }
+        if highlight.start > at {
+            spans.push(Span::styled(text[at..highlight.start].to_string(), base));
+        }
+        let style = match highlight.class {
+            ck_chunk::HighlightClass::Keyword => base.fg(COLOR_SYNTAX_KEYWORD),
+            ck_chunk::HighlightClass::Type => base.fg(COLOR_SYNTAX_TYPE),
+            ck_chunk::HighlightClass::Function => base.fg(COLOR_SYNTAX_FUNCTION),
+            ck_chunk::HighlightClass::String => base.fg(COLOR_SYNTAX_STRING),
+            ck_chunk::HighlightClass::Number => base.fg(COLOR_SYNTAX_NUMBER),
+            ck_chunk::HighlightClass::Comment => {
+                base.fg(COLOR_SYNTAX_COMMENT).add_modifier(Modifier::ITALIC)
+            }
+        };
+        spans.push(Span::styled(text[highlight.start..end].to_string(), style));
+        at = end;
+    }
+    if at < text.len() {
+        spans.push(Span::styled(text[at..].to_string(), base));
+    }
+    spans
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn render_heatmap_preview(
     lines: &[String],
@@ -116,6 +186,7 @@ pub fn render_heatmap_preview(
     score: f32,
     match_line: usize,
     query: &str,
+    max_line_chars: usize,
 ) -> Vec<Line<'static>> {
     let mut colored_lines = Vec::new();
 
@@ -145,6 +216,7 @@ pub fn render_heatmap_preview(
         )];
 
         // Apply heatmap coloring
+        let (line, hidden) = truncate_line(line, max_line_chars);
         let tokens = split_into_tokens(line);
         for token in tokens {
             let similarity = calculate_token_similarity(&token, query);
@@ -158,6 +230,9 @@ pub fn render_heatmap_preview(
 
             line_spans.push(Span::styled(token.to_string(), style));
         }
+        if hidden > 0 {
+            line_spans.push(truncation_span(hidden));
+        }
 
         colored_lines.push(Line::from(line_spans));
     }
@@ -173,6 +248,7 @@ pub fn render_syntax_preview(
     file_path: &PathBuf,
     score: f32,
     match_line: usize,
+    max_line_chars: usize,
 ) -> Vec<Line<'static>> {
     let mut colored_lines = Vec::new();
 
@@ -206,8 +282,9 @@ pub fn render_syntax_preview(
                 let is_match_line = line_num == match_line;
                 let in_chunk_range =
                     line_num >= match_line.saturating_sub(5) && line_num <= match_line + 5;
+                let (line, hidden) = truncate_line(line, max_line_chars);
 
-                let line_spans = vec![
+                let mut line_spans = vec![
                     Span::styled(
                         format!("{:4} | ", line_num),
                         if is_match_line {
@@ -222,6 +299,9 @@ pub fn render_syntax_preview(
                     ),
                     Span::styled(line.to_string(), Style::default().fg(COLOR_WHITE)),
                 ];
+                if hidden > 0 {
+                    line_spans.push(truncation_span(hidden));
+                }
 
                 colored_lines.push(Line::from(line_spans));
             }
@@ -250,6 +330,7 @@ pub fn render_syntax_preview(
         )];
 
         // Highlight the line
+        let (line, hidden) = truncate_line(line, max_line_chars);
         if let Ok(ranges) = highlighter.highlight_line(line, ps) {
             for (style, text) in ranges {
                 let fg = style.foreground;
@@ -259,6 +340,9 @@ pub fn render_syntax_preview(
         } else {
             line_spans.push(Span::raw(line.to_string()));
         }
+        if hidden > 0 {
+            line_spans.push(truncation_span(hidden));
+        }
 
         colored_lines.push(Line::from(line_spans));
     }
@@ -266,6 +350,96 @@ pub fn render_syntax_preview(
     colored_lines
 }
 
+/// The selected chunk exactly as the chunker cut it, for debugging chunk
+/// boundaries: a header with its span and token estimate, then the bytes of
+/// its span (leading trivia included) with whitespace made visible, then the
+/// trailing trivia recorded after it, which the chunk's text leaves out
+pub fn render_raw_chunk(
+    file_path: &Path,
+    content: &str,
+    meta: &IndexedChunkMeta,
+) -> Vec<Line<'static>> {
+    let span = &meta.span;
+    let label = [
+        meta.chunk_type.clone(),
+        meta.name.clone(),
+        Some(canonical_breadcrumb(meta)).filter(|crumb| !crumb.is_empty()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" ");
+    let tokens = meta.estimated_tokens.map_or_else(
+        || "tokens unknown".to_string(),
+        |t| format!("~{} tokens", t),
+    );
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("File: {} | Raw chunk: {}", file_path.display(), label),
+            Style::default().fg(COLOR_CYAN),
+        )),
+        Line::from(Span::styled(
+            format!(
+                "Lines {}-{} · bytes {}-{} ({} bytes) · {}",
+                span.line_start,
+                span.line_end,
+                span.byte_start,
+                span.byte_end,
+                span.byte_end.saturating_sub(span.byte_start),
+                tokens
+            ),
+            Style::default().fg(COLOR_YELLOW),
+        )),
+    ];
+
+    let marker_style = Style::default().fg(COLOR_DARK_GRAY);
+    let visible_line = |line_num: Option<usize>, runs: Vec<(String, bool)>| {
+        let gutter = line_num.map_or_else(|| "     | ".to_string(), |n| format!("{:4} | ", n));
+        let mut spans = vec![Span::styled(gutter, marker_style)];
+        spans.extend(runs.into_iter().map(|(run, marker)| {
+            if marker {
+                Span::styled(run, marker_style)
+            } else {
+                Span::styled(run, Style::default().fg(COLOR_WHITE))
+            }
+        }));
+        Line::from(spans)
+    };
+
+    let Some(bytes) = content.get(span.byte_start..span.byte_end) else {
+        lines.push(Line::from(Span::styled(
+            "(The span is outside the file or splits a character; the index may be stale)",
+            Style::default().fg(COLOR_RED),
+        )));
+        return lines;
+    };
+    let leading = meta.leading_trivia.as_ref().map_or(0, Vec::len);
+    if leading > 0 {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "── {} leading trivia line(s), part of the chunk ──",
+                leading
+            ),
+            Style::default().fg(COLOR_MAGENTA),
+        )));
+    }
+    for (offset, runs) in visible_whitespace_lines(bytes).into_iter().enumerate() {
+        lines.push(visible_line(Some(span.line_start + offset), runs));
+    }
+    if let Some(trailing) = meta.trailing_trivia.as_ref().filter(|t| !t.is_empty()) {
+        lines.push(Line::from(Span::styled(
+            "── trailing trivia, outside the chunk text ──",
+            Style::default().fg(COLOR_MAGENTA),
+        )));
+        for trivia in trailing {
+            for runs in visible_whitespace_lines(trivia) {
+                lines.push(visible_line(None, runs));
+            }
+        }
+    }
+    lines
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn render_chunks_preview(
     lines: &[String],
@@ -279,24 +453,23 @@ pub fn render_chunks_preview(
     all_chunks: &[IndexedChunkMeta],
     full_file_mode: bool,
     disable_match_highlighting: bool,
+    column_window: Option<ColumnWindow>,
+    sibling_outline: bool,
+    collapse_text: bool,
+    max_line_chars: usize,
+    syntax: Option<Language>,
 ) -> Vec<Line<'static>> {
     let mut colored_lines = Vec::new();
 
     let header = if let Some(meta) = chunk_meta {
         let span = &meta.span;
         let chunk_kind = meta.chunk_type.as_deref().unwrap_or("chunk");
-        let breadcrumb_display = meta
-            .breadcrumb
-            .as_deref()
-            .filter(|crumb| !crumb.is_empty())
-            .map(|crumb| format!(" • {}", crumb))
-            .unwrap_or_else(|| {
-                if !meta.ancestry.is_empty() {
-                    format!(" • {}", meta.ancestry.join("::"))
-                } else {
-                    String::new()
-                }
-            });
+        let breadcrumb = canonical_breadcrumb(meta);
+        let breadcrumb_display = if breadcrumb.is_empty() {
+            String::new()
+        } else {
+            format!(" • {}", breadcrumb)
+        };
         let token_display = meta
             .estimated_tokens
             .map(|tokens| format!(" • ~{} tokens", tokens))
@@ -343,6 +516,11 @@ PDF chunk (approximate)
         all_chunks,
         full_file_mode,
         disable_match_highlighting,
+        column_window,
+        sibling_outline,
+        collapse_text,
+        max_line_chars,
+        syntax,
     ));
 
     colored_lines
@@ -358,12 +536,17 @@ pub fn build_chunk_lines(
     all_chunks: &[IndexedChunkMeta],
     full_file_mode: bool,
     disable_match_highlighting: bool,
+    column_window: Option<ColumnWindow>,
+    sibling_outline: bool,
+    collapse_text: bool,
+    max_line_chars: usize,
+    syntax: Option<Language>,
 ) -> Vec<Line<'static>> {
     // Calculate the width needed for line numbers
     let max_line_num = lines.len();
     let line_num_width = max_line_num.to_string().len() + 1; // +1 for spacing
 
-    collect_chunk_display_lines(
+    let mut rows = collect_chunk_display_lines(
         lines,
         context_start,
         context_end,
@@ -375,97 +558,111 @@ pub fn build_chunk_lines(
         chunk_meta,
         all_chunks,
         full_file_mode,
-    )
-    .into_iter()
-    .map(|row| match row {
-        ChunkDisplayLine::Label { prefix, text } => {
-            let mut spans = Vec::new();
-
-            // Add indentation
-            spans.push(Span::styled(
-                " ".repeat(prefix),
-                Style::default().fg(COLOR_DARK_GRAY),
-            ));
-
-            // Create a bar-like header with borders
-            let bar_start = "┌─ ";
-            let bar_end = " ─┐";
+    );
+    if sibling_outline && let Some(meta) = chunk_meta {
+        add_sibling_outline(&mut rows, lines, meta, all_chunks);
+    }
+    if collapse_text {
+        collapse_text_chunks(&mut rows, lines, all_chunks);
+    }
+    if let Some(window) = column_window {
+        clip_chunk_columns(&mut rows, window);
+    }
+    if let Some(language) = syntax {
+        highlight_syntax(&mut rows, lines, language);
+    }
+    truncate_long_lines(&mut rows, max_line_chars);
+
+    rows.into_iter()
+        .map(|row| match row {
+            ChunkDisplayLine::Label { prefix, text } => {
+                let mut spans = Vec::new();
+
+                // Add indentation
+                spans.push(Span::styled(
+                    " ".repeat(prefix),
+                    Style::default().fg(COLOR_DARK_GRAY),
+                ));
+
+                // Create a bar-like header with borders
+                let bar_start = "┌─ ";
+                let bar_end = " ─┐";
+
+                // Left border
+                spans.push(Span::styled(
+                    bar_start,
+                    Style::default()
+                        .fg(COLOR_CHUNK_BOUNDARY)
+                        .add_modifier(Modifier::BOLD),
+                ));
 
-            // Left border
-            spans.push(Span::styled(
-                bar_start,
-                Style::default()
-                    .fg(COLOR_CHUNK_BOUNDARY)
-                    .add_modifier(Modifier::BOLD),
-            ));
+                // Content with background-like effect
+                spans.push(Span::styled(
+                    text,
+                    Style::default()
+                        .fg(COLOR_CHUNK_TEXT)
+                        .bg(COLOR_CHUNK_BOUNDARY)
+                        .add_modifier(Modifier::BOLD),
+                ));
+
+                // Right border
+                spans.push(Span::styled(
+                    bar_end,
+                    Style::default()
+                        .fg(COLOR_CHUNK_BOUNDARY)
+                        .add_modifier(Modifier::BOLD),
+                ));
 
-            // Content with background-like effect
-            spans.push(Span::styled(
+                Line::from(spans)
+            }
+            ChunkDisplayLine::Content {
+                columns,
+                line_num,
                 text,
-                Style::default()
-                    .fg(COLOR_CHUNK_TEXT)
-                    .bg(COLOR_CHUNK_BOUNDARY)
-                    .add_modifier(Modifier::BOLD),
-            ));
-
-            // Right border
-            spans.push(Span::styled(
-                bar_end,
-                Style::default()
-                    .fg(COLOR_CHUNK_BOUNDARY)
-                    .add_modifier(Modifier::BOLD),
-            ));
-
-            Line::from(spans)
-        }
-        ChunkDisplayLine::Content {
-            columns,
-            line_num,
-            text,
-            is_match_line,
-            in_matched_chunk,
-            has_any_chunk,
-        } => {
-            let mut spans = Vec::new();
-
-            // Always render chunk columns with fixed width
-            if columns.is_empty() {
-                spans.push(Span::styled(" ", Style::default().fg(COLOR_DARK_GRAY)));
-            } else {
-                for column in columns {
-                    let mut style = Style::default().fg(if column.is_match {
-                        COLOR_CHUNK_HIGHLIGHT // Orange for highlighted chunk boundaries
-                    } else {
-                        COLOR_CHUNK_BOUNDARY // Spring green for regular chunk boundaries
-                    });
-                    if column.is_match {
-                        style = style.add_modifier(Modifier::BOLD);
+                is_match_line,
+                in_matched_chunk,
+                has_any_chunk,
+                truncated_chars,
+                highlights,
+            } => {
+                let mut spans = Vec::new();
+
+                // Always render chunk columns with fixed width
+                if columns.is_empty() {
+                    spans.push(Span::styled(" ", Style::default().fg(COLOR_DARK_GRAY)));
+                } else {
+                    for column in columns {
+                        let mut style = Style::default().fg(if column.is_match {
+                            COLOR_CHUNK_HIGHLIGHT // Orange for highlighted chunk boundaries
+                        } else {
+                            COLOR_CHUNK_BOUNDARY // Spring green for regular chunk boundaries
+                        });
+                        if column.is_match {
+                            style = style.add_modifier(Modifier::BOLD);
+                        }
+                        spans.push(Span::styled(column.ch.to_string(), style));
                     }
-                    spans.push(Span::styled(column.ch.to_string(), style));
                 }
-            }
 
-            spans.push(Span::styled(" ", Style::default().fg(COLOR_DARK_GRAY)));
+                spans.push(Span::styled(" ", Style::default().fg(COLOR_DARK_GRAY)));
 
-            // Use fixed-width line number formatting
-            spans.push(Span::styled(
-                format!("{:width$} | ", line_num, width = line_num_width),
-                if is_match_line {
-                    Style::default()
-                        .fg(COLOR_YELLOW)
-                        .add_modifier(Modifier::BOLD)
-                } else if in_matched_chunk {
-                    Style::default()
-                        .fg(COLOR_CHUNK_LINE_NUM) // Gold for highlighted chunk line numbers
-                        .add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default().fg(COLOR_GRAY)
-                },
-            ));
+                // Use fixed-width line number formatting
+                spans.push(Span::styled(
+                    format!("{:width$} | ", line_num, width = line_num_width),
+                    if is_match_line {
+                        Style::default()
+                            .fg(COLOR_YELLOW)
+                            .add_modifier(Modifier::BOLD)
+                    } else if in_matched_chunk {
+                        Style::default()
+                            .fg(COLOR_CHUNK_LINE_NUM) // Gold for highlighted chunk line numbers
+                            .add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default().fg(COLOR_GRAY)
+                    },
+                ));
 
-            spans.push(Span::styled(
-                text,
-                if in_matched_chunk {
+                let text_style = if in_matched_chunk {
                     Style::default()
                         .fg(COLOR_CHUNK_TEXT) // Bright white for highlighted chunk text
                         .add_modifier(Modifier::BOLD)
@@ -473,19 +670,49 @@ pub fn build_chunk_lines(
                     Style::default().fg(COLOR_WHITE) // Regular white for chunk text
                 } else {
                     Style::default().fg(COLOR_DARK_GRAY) // Dim for non-chunk text
-                },
-            ));
+                };
+                spans.extend(highlighted_text_spans(text, &highlights, text_style));
+                if truncated_chars > 0 {
+                    spans.push(truncation_span(truncated_chars));
+                }
 
-            Line::from(spans)
-        }
-        ChunkDisplayLine::Message(message) => Line::from(vec![Span::styled(
-            message,
-            Style::default()
-                .fg(COLOR_CHUNK_BOUNDARY)
-                .add_modifier(Modifier::ITALIC),
-        )]),
-    })
-    .collect()
+                Line::from(spans)
+            }
+            ChunkDisplayLine::Diff {
+                change,
+                old_line,
+                new_line,
+                text,
+            } => {
+                let color = match change {
+                    DiffChange::Unchanged => COLOR_WHITE,
+                    DiffChange::Added => COLOR_GREEN,
+                    DiffChange::Removed => COLOR_RED,
+                };
+                let gutter = |line: Option<usize>| {
+                    line.map(|n| format!("{:width$}", n, width = line_num_width))
+                        .unwrap_or_else(|| " ".repeat(line_num_width))
+                };
+                Line::from(vec![
+                    Span::styled(
+                        format!("{} ", change.marker()),
+                        Style::default().fg(color).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!("{} {} | ", gutter(old_line), gutter(new_line)),
+                        Style::default().fg(COLOR_GRAY),
+                    ),
+                    Span::styled(text, Style::default().fg(color)),
+                ])
+            }
+            ChunkDisplayLine::Message(message) => Line::from(vec![Span::styled(
+                message,
+                Style::default()
+                    .fg(COLOR_CHUNK_BOUNDARY)
+                    .add_modifier(Modifier::ITALIC),
+            )]),
+        })
+        .collect()
 }
 
 #[allow(dead_code)]
@@ -543,6 +770,7 @@ pub fn build_chunk_strings(
             }
             line_buf
         }
+        row @ ChunkDisplayLine::Diff { .. } => chunk_display_line_to_string(&row),
         ChunkDisplayLine::Message(message) => message,
     })
     .collect()
@@ -609,18 +837,12 @@ pub fn dump_chunk_view_internal(
     let header_lines: Vec<String> = if let Some(meta) = chunk_meta {
         let span = &meta.span;
         let chunk_kind = meta.chunk_type.as_deref().unwrap_or("chunk");
-        let breadcrumb_display = meta
-            .breadcrumb
-            .as_deref()
-            .filter(|crumb| !crumb.is_empty())
-            .map(|crumb| format!(" • {}", crumb))
-            .unwrap_or_else(|| {
-                if !meta.ancestry.is_empty() {
-                    format!(" • {}", meta.ancestry.join("::"))
-                } else {
-                    String::new()
-                }
-            });
+        let breadcrumb = canonical_breadcrumb(meta);
+        let breadcrumb_display = if breadcrumb.is_empty() {
+            String::new()
+        } else {
+            format!(" • {}", breadcrumb)
+        };
         let token_display = meta
             .estimated_tokens
             .map(|tokens| format!(" • ~{} tokens", tokens))
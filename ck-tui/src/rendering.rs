@@ -1,12 +1,15 @@
 use crate::colors::*;
-use crate::state::TuiState;
+use crate::minimap::MinimapCell;
+use crate::palette::CommandPalette;
+use crate::state::{RelatedChunks, RelatedKind, TuiState, result_key};
 use crate::utils::score_to_color;
 use ck_core::SearchMode;
+use ck_engine::Feedback;
 use ratatui::Frame;
-use ratatui::layout::Rect;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
 
 pub fn draw_query_input(f: &mut Frame, area: Rect, state: &TuiState) {
     let (title, style) = if state.command_mode {
@@ -22,6 +25,7 @@ pub fn draw_query_input(f: &mut Frame, area: Rect, state: &TuiState) {
             SearchMode::Regex => "[REG]",
             SearchMode::Hybrid => "[HYB]",
             SearchMode::Lexical => "[LEX]",
+            SearchMode::Structural => "[SYM]",
         };
         (
             format!(
@@ -46,7 +50,12 @@ pub fn draw_results_list(f: &mut Frame, area: Rect, state: &TuiState, list_state
         .map(|(idx, result)| {
             let score_color = score_to_color(result.score);
             let is_selected = state.selected_files.contains(&result.file);
-            let prefix = if is_selected { "✓ " } else { "  " };
+            let mark = match state.feedback.get(&result_key(result)) {
+                Some(Feedback::Relevant) => '+',
+                Some(Feedback::Irrelevant) => '-',
+                None => ' ',
+            };
+            let prefix = format!("{}{}", if is_selected { '✓' } else { ' ' }, mark);
             let content = format!(
                 "{}[{:.3}] {}:{}",
                 prefix,
@@ -108,8 +117,165 @@ pub fn draw_preview(f: &mut Frame, area: Rect, state: &TuiState) {
     f.render_widget(preview, area);
 }
 
+/// Draw the chunk map over the preview's right border, one cell per row inside
+/// the borders; bands on screen get a highlighted background
+pub fn draw_minimap(f: &mut Frame, area: Rect, cells: &[MinimapCell]) {
+    if area.width < 2 || area.height < 3 {
+        return;
+    }
+    let x = area.x + area.width - 1;
+    let rows = (area.height - 2) as usize;
+    for (row, cell) in cells.iter().take(rows).enumerate() {
+        let glyph = cell.glyph();
+        let color = if cell.matches > 0 {
+            COLOR_YELLOW
+        } else {
+            COLOR_GREEN
+        };
+        let mut style = Style::default().fg(color);
+        if cell.in_viewport {
+            style = style.bg(COLOR_DARK_GRAY);
+        }
+        let target = f.buffer_mut().get_mut(x, area.y + 1 + row as u16);
+        // Empty bands keep the border line
+        if glyph != ' ' {
+            target.set_char(glyph);
+        }
+        target.set_style(style);
+    }
+}
+
+pub fn draw_related_chunks(f: &mut Frame, area: Rect, related: &RelatedChunks) {
+    let items: Vec<ListItem> = if related.neighbors.is_empty() {
+        vec![
+            ListItem::new("  No other embedded chunks in the index")
+                .style(Style::default().fg(COLOR_GRAY)),
+        ]
+    } else {
+        related
+            .neighbors
+            .iter()
+            .enumerate()
+            .map(|(idx, neighbor)| {
+                let score_color = score_to_color(neighbor.score);
+                let location = format!("{}:{}", neighbor.file.display(), neighbor.span.line_start);
+                let crumb = neighbor
+                    .breadcrumb
+                    .as_deref()
+                    .filter(|crumb| !crumb.is_empty());
+                let content = match (related.kind, crumb) {
+                    (RelatedKind::Neighbors, Some(crumb)) => {
+                        format!("[{:.3}] {}  {}", neighbor.score, crumb, location)
+                    }
+                    (RelatedKind::Neighbors, None) => {
+                        format!("[{:.3}] {}", neighbor.score, location)
+                    }
+                    // Names matching only when ignoring case are marked as approximate
+                    (RelatedKind::Definitions, crumb) => format!(
+                        "{} {}  {}",
+                        if neighbor.score >= 1.0 { " " } else { "≈" },
+                        crumb.unwrap_or_default(),
+                        location
+                    ),
+                };
+                let style = if idx == related.selected {
+                    Style::default()
+                        .fg(COLOR_BLACK)
+                        .bg(score_color)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(score_color)
+                };
+                ListItem::new(content).style(style)
+            })
+            .collect()
+    };
+
+    let title = match related.kind {
+        RelatedKind::Neighbors => format!(
+            "Related to {} (↑↓: move | Enter: jump | ^N/Esc: close)",
+            related.source
+        ),
+        RelatedKind::Definitions => format!(
+            "Definitions of `{}` (↑↓: move | Enter: jump | ^G/Esc: close)",
+            related.source
+        ),
+    };
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(list, area);
+}
+
+/// The command palette as a popup centred over `area`: the filter on top, then
+/// the matching actions with their keys right-aligned
+pub fn draw_command_palette(f: &mut Frame, area: Rect, palette: &CommandPalette) {
+    let width = area.width.saturating_sub(4).min(72);
+    let height = area.height.saturating_sub(4).min(20);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    f.render_widget(Clear, popup);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(popup);
+    let (filter_area, list_area) = (rows[0], rows[1]);
+    let filter = Paragraph::new(format!("> {}", palette.filter))
+        .style(Style::default().fg(COLOR_YELLOW))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Command Palette (↑↓: move | Enter: run | Esc: close)"),
+        );
+    f.render_widget(filter, filter_area);
+
+    let inner_width = list_area.width.saturating_sub(2) as usize;
+    let matches = palette.matches();
+    let visible = list_area.height.saturating_sub(2) as usize;
+    let first = (palette.selected + 1).saturating_sub(visible);
+    let items: Vec<ListItem> = if matches.is_empty() {
+        vec![ListItem::new("  No matching actions").style(Style::default().fg(COLOR_GRAY))]
+    } else {
+        matches
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(visible)
+            .map(|(idx, entry)| {
+                let keys = entry.keys.join(", ");
+                let padding = inner_width
+                    .saturating_sub(entry.title.chars().count() + keys.chars().count() + 3);
+                let selected = idx == palette.selected;
+                let (title_style, key_style) = if selected {
+                    let style = Style::default()
+                        .fg(COLOR_BLACK)
+                        .bg(COLOR_CYAN)
+                        .add_modifier(Modifier::BOLD);
+                    (style, style)
+                } else {
+                    (
+                        Style::default().fg(COLOR_WHITE),
+                        Style::default().fg(COLOR_DARK_GRAY),
+                    )
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!(" {}", entry.title), title_style),
+                    Span::styled(format!("{} {} ", " ".repeat(padding), keys), key_style),
+                ]))
+            })
+            .collect()
+    };
+    f.render_widget(
+        List::new(items).block(Block::default().borders(Borders::ALL)),
+        list_area,
+    );
+}
+
 pub fn draw_status_bar(f: &mut Frame, area: Rect, state: &TuiState) {
-    let help_text = " ↑↓: Nav | Tab: Mode | ^V: View | ^Space: Select | Enter: Open | ^↑↓: History | Esc/q: Quit ";
+    let help_text = " ↑↓: Nav | Tab: Mode | ^V: View | ^Y: Copy | ^Space: Select | Enter: Open | ^↑↓: History | Esc/q: Quit ";
 
     let mut status_spans = vec![Span::styled(
         state.status_message.clone(),
@@ -0,0 +1,185 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::utils::find_repo_root;
+
+/// Sessions kept before the least recently used one is dropped
+const MAX_SESSIONS: usize = 50;
+
+/// Where the user left off in one index, restored when the TUI is reopened
+/// with the same query
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionState {
+    pub query: String,
+    pub selected_idx: usize,
+    /// File and line of the selected result, so the cursor follows it if ranks shift
+    pub selected_file: Option<PathBuf>,
+    pub selected_line: Option<usize>,
+    pub scroll_offset: usize,
+    pub chunk_column_offset: usize,
+    #[serde(default)]
+    pub updated: u64,
+}
+
+impl SessionState {
+    /// Index of the remembered result in `results`, falling back to the saved
+    /// position when it is no longer present
+    pub fn cursor_in(&self, results: &[ck_core::SearchResult]) -> Option<usize> {
+        if results.is_empty() {
+            return None;
+        }
+
+        let by_identity = self.selected_file.as_ref().and_then(|file| {
+            results.iter().position(|result| {
+                &result.file == file && Some(result.span.line_start) == self.selected_line
+            })
+        });
+
+        Some(by_identity.unwrap_or(self.selected_idx.min(results.len() - 1)))
+    }
+}
+
+/// Session snapshots keyed by index root
+#[derive(Default, Serialize, Deserialize)]
+pub struct SessionStore {
+    sessions: HashMap<String, SessionState>,
+}
+
+impl SessionStore {
+    pub fn load() -> Self {
+        Self::load_from(&Self::store_path())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::store_path())
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&SessionState> {
+        self.sessions.get(key)
+    }
+
+    /// Record `state` for `key`, evicting the stalest sessions beyond the cap
+    pub fn insert(&mut self, key: String, mut state: SessionState) {
+        state.updated = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        self.sessions.insert(key.clone(), state);
+
+        while self.sessions.len() > MAX_SESSIONS {
+            let Some(stalest) = self
+                .sessions
+                .iter()
+                .filter(|(existing, _)| **existing != key)
+                .min_by_key(|(_, session)| session.updated)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.sessions.remove(&stalest);
+        }
+    }
+
+    fn store_path() -> PathBuf {
+        if let Some(config_dir) = dirs::config_dir() {
+            config_dir.join("ck").join("tui_sessions.json")
+        } else {
+            PathBuf::from(".ck_tui_sessions.json")
+        }
+    }
+}
+
+/// Sessions are keyed by the index root covering `search_path` (or the path itself
+/// when there is no index yet)
+pub fn session_key(search_path: &Path) -> String {
+    let root = find_repo_root(search_path).unwrap_or_else(|| search_path.to_path_buf());
+    root.canonicalize()
+        .unwrap_or(root)
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ck_core::{SearchResult, Span};
+
+    fn result(file: &str, line: usize) -> SearchResult {
+        SearchResult {
+            file: PathBuf::from(file),
+            span: Span::new_unchecked(0, 0, line, line),
+            score: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn cursor_follows_result_identity() {
+        let session = SessionState {
+            query: "auth".to_string(),
+            selected_idx: 1,
+            selected_file: Some(PathBuf::from("b.rs")),
+            selected_line: Some(7),
+            ..Default::default()
+        };
+
+        let reordered = [result("b.rs", 7), result("a.rs", 1), result("c.rs", 2)];
+        assert_eq!(session.cursor_in(&reordered), Some(0));
+
+        let missing = [result("a.rs", 1), result("c.rs", 2), result("d.rs", 3)];
+        assert_eq!(session.cursor_in(&missing), Some(1));
+        assert_eq!(session.cursor_in(&[result("a.rs", 1)]), Some(0));
+        assert_eq!(session.cursor_in(&[]), None);
+    }
+
+    #[test]
+    fn store_round_trips_and_caps_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.json");
+
+        let mut store = SessionStore::default();
+        for i in 0..MAX_SESSIONS + 5 {
+            store.insert(
+                format!("/repo/{i}"),
+                SessionState {
+                    query: format!("q{i}"),
+                    ..Default::default()
+                },
+            );
+        }
+        assert_eq!(store.sessions.len(), MAX_SESSIONS);
+        store.save_to(&path).unwrap();
+
+        let loaded = SessionStore::load_from(&path);
+        assert_eq!(loaded.sessions.len(), MAX_SESSIONS);
+        let last = format!("/repo/{}", MAX_SESSIONS + 4);
+        assert_eq!(
+            loaded.get(&last).map(|s| s.query.as_str()),
+            Some(format!("q{}", MAX_SESSIONS + 4).as_str())
+        );
+
+        assert!(
+            SessionStore::load_from(&dir.path().join("missing.json"))
+                .get(&last)
+                .is_none()
+        );
+    }
+}
@@ -1,13 +1,26 @@
 use crate::chunks::IndexedChunkMeta;
-use crate::config::PreviewMode;
+use crate::config::{ContextLinesConfig, ContextOverride, PreviewMode};
+use crate::palette::CommandPalette;
 use ck_core::SearchMode;
 use ck_core::SearchResult;
+use ck_engine::{Feedback, FeedbackWeights};
 use ck_index::IndexStats;
 use ratatui::text::Line;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::time::Instant;
 
+/// A result's file and line span, identifying it across re-rankings
+pub type ResultKey = (PathBuf, usize, usize);
+
+pub fn result_key(result: &SearchResult) -> ResultKey {
+    (
+        result.file.clone(),
+        result.span.line_start,
+        result.span.line_end,
+    )
+}
+
 pub struct TuiState {
     pub query: String,
     pub mode: SearchMode,
@@ -21,9 +34,11 @@ pub struct TuiState {
     pub status_message: String,
     pub search_path: PathBuf,
     pub selected_files: HashSet<PathBuf>, // For multi-select
-    pub search_history: Vec<String>,      // Search history
-    pub history_index: usize,             // Current position in history
-    pub command_mode: bool,               // true when query starts with /
+    pub feedback: HashMap<ResultKey, Feedback>, // Relevance marks for re-ranking the results
+    pub feedback_weights: FeedbackWeights,
+    pub search_history: Vec<String>, // Search history
+    pub history_index: usize,        // Current position in history
+    pub command_mode: bool,          // true when query starts with /
     pub index_stats: Option<IndexStats>,
     pub last_index_stats_refresh: Option<Instant>,
     pub index_stats_error: Option<String>,
@@ -34,6 +49,46 @@ pub struct TuiState {
     pub indexing_started_at: Option<Instant>,
     pub last_indexing_update: Option<Instant>,
     pub search_in_progress: bool,
+    pub copy_leading_trivia: bool,
+    pub copy_whitespace: ck_core::WhitespaceMode,
+    pub prompt_token_budget: usize,
+    pub max_chunk_columns: usize, // Nesting columns shown before the chunk view scrolls
+    pub chunk_column_offset: usize, // Levels scrolled outward from the innermost columns
+    pub max_line_chars: usize,    // Preview cap on a source line's length, 0 for none
+    pub preview_size: Option<(usize, usize)>, // Inner width and height of the preview pane
+    pub context_lines: ContextLinesConfig, // Preview context around a match, per chunk type
+    pub relevant_snippets: bool,  // Trim snippet previews to the region matching the query
+    pub relevant_snippet_lines: usize,
+    pub sibling_outline: bool, // Collapsed sibling signatures around the matched chunk
+    pub collapse_text_chunks: bool, // Multi-line text chunks shown as one collapsed row
+    pub minimap: bool,         // Chunk map drawn over the preview's right border
+    pub chunk_syntax_highlighting: bool, // Token colors for code in the Chunks view
+    pub raw_chunk_view: bool,  // Preview the selected chunk's exact bytes with whitespace shown
+    pub raw_chunk_scroll: usize, // Rows of the raw chunk view scrolled past
+    pub preview_range: Option<(usize, usize)>, // 1-based first and last file line in the preview
+    pub extra_context_lines: usize, // Added to the per-type context by expand/shrink
+    pub context_override: ContextOverride, // Context lines from -B/-A/-C for this run
+    pub clipboard_fallback: Vec<String>, // Copied snippets to print on exit when no clipboard exists
+    pub related: Option<RelatedChunks>,  // Open related chunks or definitions panel
+    pub sort: Option<ck_core::ResultSort>, // Result order from --sort
+    pub palette: Option<CommandPalette>, // Open command palette
+}
+
+/// What a [`RelatedChunks`] panel lists
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelatedKind {
+    /// Chunks whose embeddings are nearest to the chunk the panel was opened on
+    Neighbors,
+    /// Chunks defining the symbol `source`, scored 1.0 for exact name matches
+    Definitions,
+}
+
+/// Chunks listed under the preview for the selected result
+pub struct RelatedChunks {
+    pub kind: RelatedKind,
+    pub source: String,
+    pub neighbors: Vec<ck_engine::ChunkNeighbor>,
+    pub selected: usize,
 }
 
 pub struct PreviewCache {
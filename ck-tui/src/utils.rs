@@ -17,6 +17,23 @@ pub fn theme_set() -> &'static ThemeSet {
 
 pub use heatmap::{calculate_token_similarity, split_into_tokens};
 
+/// `line` cut to its first `max_chars` characters, and how many characters
+/// were cut off; `max_chars == 0` keeps every line whole
+pub fn truncate_line(line: &str, max_chars: usize) -> (&str, usize) {
+    if max_chars == 0 {
+        return (line, 0);
+    }
+    match line.char_indices().nth(max_chars) {
+        Some((cut, _)) => (&line[..cut], line[cut..].chars().count()),
+        None => (line, 0),
+    }
+}
+
+/// Shown in place of the characters [`truncate_line`] cut off
+pub fn truncation_marker(hidden: usize) -> String {
+    format!("…(truncated {} chars)", hidden)
+}
+
 pub fn score_to_color(score: f32) -> Color {
     match HeatmapBucket::from_score(score) {
         HeatmapBucket::Step8 => Color::Rgb(0, 255, 100),
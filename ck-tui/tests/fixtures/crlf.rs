@@ -0,0 +1,7 @@
+fn first() -> u32 {
+    1
+}
+
+fn second() -> u32 {
+    first() + 1
+}
@@ -0,0 +1,29 @@
+use std::fmt;
+
+/// A point in 2D space
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn manhattan(&self) -> i32 {
+        self.x.abs() + self.y.abs()
+    }
+}
+
+mod geometry {
+    pub fn origin() -> super::Point {
+        super::Point::new(0, 0)
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
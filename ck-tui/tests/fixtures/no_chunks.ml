@@ -0,0 +1,7 @@
+let square x = x * x
+
+fn looks_like_rust () = ()
+
+class counter = object
+  val mutable n = 0
+end
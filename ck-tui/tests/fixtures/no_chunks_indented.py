@@ -0,0 +1,8 @@
+class Outer:
+    def method(self):
+        def helper():
+			return 1
+        return helper()
+
+def top_level():
+    pass
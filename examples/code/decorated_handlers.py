@@ -0,0 +1,29 @@
+"""HTTP handlers whose routing lives in decorators."""
+
+from functools import wraps
+
+from flask import Flask, jsonify
+
+app = Flask(__name__)
+
+
+def login_required(handler):
+    @wraps(handler)
+    def wrapper(*args, **kwargs):
+        return handler(*args, **kwargs)
+
+    return wrapper
+
+
+# List every registered user
+@app.route("/users")
+@login_required
+def list_users():
+    return jsonify([])
+
+
+class UserAdmin:
+    @staticmethod
+    @app.route("/users/<int:user_id>", methods=["DELETE"])
+    def delete_user(user_id):
+        return jsonify({"deleted": user_id})